@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS dogs (
+    id INTEGER PRIMARY KEY,
+    url TEXT NOT NULL UNIQUE
+);
+
+-- Databases created before urls were unique may hold duplicates; keep the oldest copy of each
+DELETE FROM dogs WHERE id NOT IN (SELECT MIN(id) FROM dogs GROUP BY url);
+
+CREATE UNIQUE INDEX IF NOT EXISTS dogs_url_unique ON dogs (url);
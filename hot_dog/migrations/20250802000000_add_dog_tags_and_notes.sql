@@ -0,0 +1,3 @@
+-- Tags are stored as a comma separated list of normalized (trimmed, lowercase) tags
+ALTER TABLE dogs ADD COLUMN tags TEXT NOT NULL DEFAULT '';
+ALTER TABLE dogs ADD COLUMN notes TEXT NOT NULL DEFAULT '';
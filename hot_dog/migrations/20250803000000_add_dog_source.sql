@@ -0,0 +1,2 @@
+-- Which image provider a saved image came from, see `ImageSource::as_str`
+ALTER TABLE dogs ADD COLUMN source TEXT NOT NULL DEFAULT 'dog';
@@ -0,0 +1,20 @@
+-- One row per tag of a saved dog, so that dogs can be filtered by tag and the tags listed in SQL.
+-- The `tags` column stays the source of each dog's tags in the order they were entered.
+CREATE TABLE IF NOT EXISTS dog_tags (
+    dog_id INTEGER NOT NULL REFERENCES dogs (id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (dog_id, tag)
+);
+
+CREATE INDEX IF NOT EXISTS dog_tags_tag ON dog_tags (tag);
+
+-- Split the tags already saved; they are normalized, so splitting on commas is enough
+WITH RECURSIVE split (dog_id, tag, rest) AS (
+    SELECT id, '', tags || ',' FROM dogs
+    UNION ALL
+    SELECT dog_id, substr(rest, 1, instr(rest, ',') - 1), substr(rest, instr(rest, ',') + 1)
+    FROM split
+    WHERE rest <> ''
+)
+INSERT OR IGNORE INTO dog_tags (dog_id, tag)
+SELECT dog_id, tag FROM split WHERE tag <> '';
@@ -1,41 +1,327 @@
+use crate::providers::ImageSource;
 use dioxus::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// A dog image saved to the favorites, along with where it came from and the metadata attached to
+/// it.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedDog {
+    pub id: usize,
+    pub url: String,
+    pub source: ImageSource,
+    pub tags: Vec<String>,
+    pub notes: String,
+}
+
+/// Result of saving a dog image.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum SaveOutcome {
+    /// The image was new and got stored under this id.
+    Saved(usize),
+    /// The image had been saved before under this id, so nothing was inserted.
+    AlreadySaved(usize),
+}
+
+impl SaveOutcome {
+    /// The id of the row holding the image, whether or not it was just inserted.
+    pub fn id(&self) -> usize {
+        match self {
+            SaveOutcome::Saved(id) | SaveOutcome::AlreadySaved(id) => *id,
+        }
+    }
+}
+
+/// Portable JSON document holding every saved dog, used to move favorites between machines.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FavoritesExport {
+    pub version: u32,
+    pub dogs: Vec<ExportedDog>,
+}
+
+/// A saved dog as it appears in a [`FavoritesExport`]. Ids are local to each database, so only the
+/// url identifies a dog across machines.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ExportedDog {
+    pub url: String,
+    #[serde(default)]
+    pub source: ImageSource,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub notes: String,
+}
+
+impl FavoritesExport {
+    /// The only export format understood so far.
+    pub const VERSION: u32 = 1;
+
+    /// Checks that the document can be imported as a whole, so that a bad file never gets
+    /// half-applied.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.version != Self::VERSION {
+            return Err(format!(
+                "unsupported export version {} (expected {})",
+                self.version,
+                Self::VERSION
+            ));
+        }
+        if let Some(dog) = self
+            .dogs
+            .iter()
+            .find(|dog| !(dog.url.starts_with("https://") || dog.url.starts_with("http://")))
+        {
+            return Err(format!("'{}' is not an http(s) url", dog.url));
+        }
+        Ok(())
+    }
+}
+
+/// How many dogs an import added and how many were merged into dogs that were already saved.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ImportSummary {
+    pub added: usize,
+    pub merged: usize,
+}
+
+/// Splits comma separated tag input into trimmed, lowercase tags, dropping blanks and repeats.
+pub fn parse_tags(input: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in input.split(',').map(|tag| tag.trim().to_lowercase()) {
+        if !tag.is_empty() && !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Saves an image from `source` with its tags unless its url is already stored.
+#[server]
+pub async fn save_dog(
+    image: String,
+    source: ImageSource,
+    tags: Vec<String>,
+) -> Result<SaveOutcome, ServerFnError> {
+    let mut tx = db().await?.begin().await?;
+    let inserted =
+        sqlx::query("INSERT OR IGNORE INTO dogs (url, source, tags) VALUES (?1, ?2, ?3)")
+            .bind(&image)
+            .bind(source.as_str())
+            .bind(tags_column(&tags))
+            .execute(&mut *tx)
+            .await?;
+    if inserted.rows_affected() == 0 {
+        let id: i64 = sqlx::query_scalar("SELECT id FROM dogs WHERE url = ?1")
+            .bind(&image)
+            .fetch_one(&mut *tx)
+            .await?;
+        return Ok(SaveOutcome::AlreadySaved(id as usize));
+    }
+    let id = inserted.last_insert_rowid();
+    set_dog_tags(&mut tx, id, &tags).await?;
+    tx.commit().await?;
+    Ok(SaveOutcome::Saved(id as usize))
+}
+
+/// Replaces the tags and notes of a saved dog.
+#[server]
+pub async fn update_dog_metadata(
+    id: usize,
+    tags: Vec<String>,
+    notes: String,
+) -> Result<(), ServerFnError> {
+    let mut tx = db().await?.begin().await?;
+    sqlx::query("UPDATE dogs SET tags = ?1, notes = ?2 WHERE id = ?3")
+        .bind(tags_column(&tags))
+        .bind(notes.trim())
+        .bind(id as i64)
+        .execute(&mut *tx)
+        .await?;
+    set_dog_tags(&mut tx, id as i64, &tags).await?;
+    tx.commit().await?;
+    Ok(())
+}
 
 #[server]
-pub async fn save_dog(image: String) -> Result<(), ServerFnError> {
-    DB.with(|f| f.execute("INSERT INTO dogs (url) VALUES (?1)", &[&image]))?;
+pub async fn delete_dog(id: usize) -> Result<(), ServerFnError> {
+    sqlx::query("DELETE FROM dogs WHERE id = ?1")
+        .bind(id as i64)
+        .execute(db().await?)
+        .await?;
     Ok(())
 }
 
 #[server]
-pub async fn list_dogs() -> Result<Vec<(usize, String)>, ServerFnError> {
-    let dogs = DB.with(|f| {
-        f.prepare("SELECT id, url FROM dogs ORDER BY id DESC LIMIT 10")
-            .unwrap()
-            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
-            .unwrap()
-            .map(|r| r.unwrap())
-            .collect()
-    });
+pub async fn list_dogs() -> Result<Vec<SavedDog>, ServerFnError> {
+    let dogs: Vec<DogRow> =
+        sqlx::query_as("SELECT id, url, source, tags, notes FROM dogs ORDER BY id DESC")
+            .fetch_all(db().await?)
+            .await?;
+
+    Ok(dogs.into_iter().map(saved_dog).collect())
+}
+
+/// Lists at most `limit` saved dogs, newest first, skipping the `offset` newest ones. With a
+/// `tag`, only the dogs tagged with it are listed and paged through.
+#[server]
+pub async fn list_dogs_page(
+    offset: usize,
+    limit: usize,
+    tag: Option<String>,
+) -> Result<Vec<SavedDog>, ServerFnError> {
+    let dogs: Vec<DogRow> = sqlx::query_as(
+        "SELECT id, url, source, tags, notes FROM dogs
+         WHERE ?3 IS NULL OR id IN (SELECT dog_id FROM dog_tags WHERE tag = ?3)
+         ORDER BY id DESC LIMIT ?1 OFFSET ?2",
+    )
+    .bind(limit as i64)
+    .bind(offset as i64)
+    .bind(tag)
+    .fetch_all(db().await?)
+    .await?;
 
-    Ok(dogs)
+    Ok(dogs.into_iter().map(saved_dog).collect())
+}
+
+/// Lists every tag used by a saved dog, in alphabetical order.
+#[server]
+pub async fn list_tags() -> Result<Vec<String>, ServerFnError> {
+    let tags = sqlx::query_scalar("SELECT DISTINCT tag FROM dog_tags ORDER BY tag")
+        .fetch_all(db().await?)
+        .await?;
+    Ok(tags)
+}
+
+/// Serializes every saved dog into a [`FavoritesExport`] JSON document.
+#[server]
+pub async fn export_dogs() -> Result<String, ServerFnError> {
+    let export = FavoritesExport {
+        version: FavoritesExport::VERSION,
+        dogs: list_dogs()
+            .await?
+            .into_iter()
+            .rev()
+            .map(|dog| ExportedDog {
+                url: dog.url,
+                source: dog.source,
+                tags: dog.tags,
+                notes: dog.notes,
+            })
+            .collect(),
+    };
+    Ok(serde_json::to_string_pretty(&export)?)
+}
+
+/// Imports a [`FavoritesExport`] JSON document, merging dogs whose url is already saved: their
+/// tags are combined and existing notes win over imported ones.
+#[server]
+pub async fn import_dogs(json: String) -> Result<ImportSummary, ServerFnError> {
+    let export: FavoritesExport = serde_json::from_str(&json)
+        .map_err(|e| ServerFnError::new(format!("invalid favorites file: {e}")))?;
+    export.validate().map_err(ServerFnError::new)?;
+
+    let mut summary = ImportSummary {
+        added: 0,
+        merged: 0,
+    };
+    let mut tx = db().await?.begin().await?;
+    for dog in export.dogs {
+        let existing: Option<(i64, String, String)> =
+            sqlx::query_as("SELECT id, tags, notes FROM dogs WHERE url = ?1")
+                .bind(&dog.url)
+                .fetch_optional(&mut *tx)
+                .await?;
+        match existing {
+            Some((id, tags, notes)) => {
+                let mut merged_tags = parse_tags(&tags);
+                merged_tags.extend(dog.tags);
+                let notes = if notes.is_empty() { dog.notes } else { notes };
+                sqlx::query("UPDATE dogs SET tags = ?1, notes = ?2 WHERE id = ?3")
+                    .bind(tags_column(&merged_tags))
+                    .bind(notes.trim())
+                    .bind(id)
+                    .execute(&mut *tx)
+                    .await?;
+                set_dog_tags(&mut tx, id, &merged_tags).await?;
+                summary.merged += 1;
+            }
+            None => {
+                let inserted = sqlx::query(
+                    "INSERT INTO dogs (url, source, tags, notes) VALUES (?1, ?2, ?3, ?4)",
+                )
+                .bind(&dog.url)
+                .bind(dog.source.as_str())
+                .bind(tags_column(&dog.tags))
+                .bind(dog.notes.trim())
+                .execute(&mut *tx)
+                .await?;
+                set_dog_tags(&mut tx, inserted.last_insert_rowid(), &dog.tags).await?;
+                summary.added += 1;
+            }
+        }
+    }
+    tx.commit().await?;
+    Ok(summary)
+}
+
+/// The `id, url, source, tags, notes` columns of a row in the `dogs` table.
+#[cfg(feature = "server")]
+type DogRow = (i64, String, String, String, String);
+
+#[cfg(feature = "server")]
+fn saved_dog((id, url, source, tags, notes): DogRow) -> SavedDog {
+    SavedDog {
+        id: id as usize,
+        url,
+        source: ImageSource::parse(&source).unwrap_or_default(),
+        tags: parse_tags(&tags),
+        notes,
+    }
+}
+
+/// Normalizes tags into the comma separated form stored in the `tags` column.
+#[cfg(feature = "server")]
+fn tags_column(tags: &[String]) -> String {
+    parse_tags(&tags.join(",")).join(",")
+}
+
+/// Replaces the `dog_tags` rows of the dog with `id`, keeping them in step with its `tags` column.
+#[cfg(feature = "server")]
+async fn set_dog_tags(
+    conn: &mut sqlx::SqliteConnection,
+    id: i64,
+    tags: &[String],
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM dog_tags WHERE dog_id = ?1")
+        .bind(id)
+        .execute(&mut *conn)
+        .await?;
+    for tag in parse_tags(&tags.join(",")) {
+        sqlx::query("INSERT INTO dog_tags (dog_id, tag) VALUES (?1, ?2)")
+            .bind(id)
+            .bind(tag)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
 }
 
 // The database is only available to server code
 #[cfg(feature = "server")]
-thread_local! {
-    pub static DB: rusqlite::Connection = {
-        // Open the database from the persisted "hotdog.db" file
-        let conn = rusqlite::Connection::open("hotdog.db").expect("Failed to open database");
-
-        // Create the "dogs" table if it doesn't already exist
-        conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS dogs (
-                id INTEGER PRIMARY KEY,
-                url TEXT NOT NULL
-            );",
-        ).unwrap();
-
-        // Return the connection
-        conn
-    };
+static DB: tokio::sync::OnceCell<sqlx::SqlitePool> = tokio::sync::OnceCell::const_new();
+
+/// Returns the shared connection pool, opening the persisted "hotdog.db" file and running the
+/// migrations on first use.
+#[cfg(feature = "server")]
+async fn db() -> Result<&'static sqlx::SqlitePool, ServerFnError> {
+    DB.get_or_try_init(|| async {
+        let options = sqlx::sqlite::SqliteConnectOptions::new()
+            .filename("hotdog.db")
+            .create_if_missing(true);
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .connect_with(options)
+            .await?;
+        sqlx::migrate!().run(&pool).await?;
+        Ok::<_, ServerFnError>(pool)
+    })
+    .await
 }
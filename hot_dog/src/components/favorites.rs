@@ -1,21 +1,309 @@
+use crate::backend::{self, SavedDog};
+use crate::components::TransferPanel;
 use dioxus::prelude::*;
 
+/// How many favorites are fetched at a time as the gallery is scrolled.
+const PAGE_SIZE: usize = 24;
+
 #[component]
 pub fn Favorites() -> Element {
-    // Create a pending resource that resolves to the list of dogs from the backend
+    // Create a pending resource that resolves to the first page of dogs from the backend
     // Wait for the favorites list to resolve with `.suspend()`
-    let favorites = use_resource(crate::backend::list_dogs).suspend()?;
+    let favorites = use_resource(|| backend::list_dogs_page(0, PAGE_SIZE, None)).suspend()?;
 
     rsx! {
         div { id: "favorites",
+            match favorites() {
+                Ok(dogs) => rsx! {
+                    DogGrid { dogs }
+                },
+                Err(err) => rsx! {
+                    p { class: "favorites-message", "Couldn't load your favorites: {err}" }
+                },
+            }
+        }
+    }
+}
+
+/// Lays out saved dogs as a grid of cards, newest first, optionally narrowed down to one tag.
+/// Further pages are loaded as the end of the grid scrolls into view.
+///
+/// Removing a dog deletes it right away and offers a short-lived undo toast that saves it again.
+#[component]
+fn DogGrid(dogs: Vec<SavedDog>) -> Element {
+    let mut has_more = use_signal(|| dogs.len() == PAGE_SIZE);
+    let mut dogs = use_signal(|| dogs);
+    let mut loading = use_signal(|| false);
+    let mut load_error = use_signal(|| None::<String>);
+    let mut selected_tag = use_signal(|| None::<String>);
+    let mut recently_removed = use_signal(|| None::<SavedDog>);
+    // Every tag in use, including the ones of dogs that aren't loaded yet
+    let mut tags = use_resource(backend::list_tags);
+
+    // Everything loaded so far is still on screen (removals included), so the number of loaded
+    // dogs is exactly where the next page starts
+    let mut load_more = move || {
+        if *loading.peek() || !*has_more.peek() {
+            return;
+        }
+        loading.set(true);
+        spawn(async move {
+            let tag = selected_tag.peek().clone();
+            let page = backend::list_dogs_page(dogs.peek().len(), PAGE_SIZE, tag.clone()).await;
+            // A page of the previously selected tag doesn't belong to the reloaded dogs
+            if *selected_tag.peek() != tag {
+                loading.set(false);
+                return;
+            }
+            match page {
+                Ok(page) => {
+                    load_error.set(None);
+                    has_more.set(page.len() == PAGE_SIZE);
+                    let mut dogs = dogs.write();
+                    // Dogs saved elsewhere in the meantime shift the pages, so skip repeats
+                    let new: Vec<SavedDog> = page
+                        .into_iter()
+                        .filter(|dog| !dogs.iter().any(|loaded| loaded.id == dog.id))
+                        .collect();
+                    dogs.extend(new);
+                }
+                Err(err) => load_error.set(Some(format!("Couldn't load more dogs: {err}"))),
+            }
+            loading.set(false);
+        });
+    };
+
+    // Starts over from the first page of dogs with the selected tag, along with the tag list
+    let mut reload = move || {
+        tags.restart();
+        spawn(async move {
+            let tag = selected_tag.peek().clone();
+            let page = backend::list_dogs_page(0, PAGE_SIZE, tag.clone()).await;
+            // Another tag may have been picked while this one was loading
+            if *selected_tag.peek() != tag {
+                return;
+            }
+            match page {
+                Ok(page) => {
+                    load_error.set(None);
+                    has_more.set(page.len() == PAGE_SIZE);
+                    dogs.set(page);
+                }
+                Err(err) => load_error.set(Some(format!("Couldn't load your favorites: {err}"))),
+            }
+        });
+    };
+    let mut select_tag = move |tag: Option<String>| {
+        if *selected_tag.peek() != tag {
+            selected_tag.set(tag);
+            reload();
+        }
+    };
+
+    let all_tags: Vec<String> = tags
+        .read()
+        .as_ref()
+        .and_then(|tags| tags.as_ref().ok())
+        .cloned()
+        .unwrap_or_default();
+
+    // A tag whose last dog was removed no longer filters anything
+    use_effect(move || {
+        let gone = match &*tags.read() {
+            Some(Ok(all_tags)) => selected_tag
+                .peek()
+                .as_ref()
+                .is_some_and(|tag| !all_tags.contains(tag)),
+            _ => false,
+        };
+        if gone {
+            select_tag(None);
+        }
+    });
+
+    let active_tag = selected_tag();
+
+    rsx! {
+        TransferPanel {
+            onimported: move |_| reload(),
+        }
+        if !all_tags.is_empty() {
+            div { id: "tag-filter",
+                button {
+                    class: if active_tag.is_none() { "tag selected" } else { "tag" },
+                    onclick: move |_| select_tag(None),
+                    "all"
+                }
+                for tag in all_tags {
+                    button {
+                        key: "{tag}",
+                        class: if active_tag.as_ref() == Some(&tag) { "tag selected" } else { "tag" },
+                        onclick: {
+                            let tag = tag.clone();
+                            move |_| select_tag(Some(tag.clone()))
+                        },
+                        "{tag}"
+                    }
+                }
+            }
+        }
+        if dogs.read().is_empty() && !has_more() {
+            if let Some(tag) = &active_tag {
+                p { class: "favorites-message", "No saved dogs are tagged {tag}." }
+            } else {
+                p { class: "favorites-message", "No saved dogs yet. Go save some!" }
+            }
+        } else {
             div { id: "favorites-container",
-                for (id , url) in favorites().unwrap() {
-                    // Render a div for each photo using the dog's ID as the list key
-                    div { key: id, class: "favorite-dog",
-                        img { src: "{url}" }
+                for dog in dogs() {
+                    // Render a card for each photo using the dog's ID as the list key
+                    DogCard {
+                        key: "{dog.id}",
+                        dog: dog.clone(),
+                        onupdate: move |updated: SavedDog| {
+                            // A dog that lost the selected tag leaves the list, like it did on the
+                            // server, so that the next page still starts in the right place
+                            match selected_tag.peek().as_ref() {
+                                Some(tag) if !updated.tags.contains(tag) => {
+                                    dogs.write().retain(|dog| dog.id != updated.id);
+                                }
+                                _ => {
+                                    if let Some(dog) = dogs.write().iter_mut().find(|dog| dog.id == updated.id) {
+                                        *dog = updated;
+                                    }
+                                }
+                            }
+                            tags.restart();
+                        },
+                        onremove: move |_| {
+                            let dog = dog.clone();
+                            async move {
+                                if backend::delete_dog(dog.id).await.is_ok() {
+                                    dogs.write().retain(|saved| saved.id != dog.id);
+                                    recently_removed.set(Some(dog));
+                                    tags.restart();
+                                }
+                            }
+                        },
                     }
                 }
+                if has_more() {
+                    div {
+                        id: "load-more",
+                        onvisible: move |evt| {
+                            if evt.is_intersecting().unwrap_or(false) {
+                                load_more();
+                            }
+                        },
+                        // Only fires when the sentinel comes into view, so keep a manual fallback
+                        // for pages that don't fill the screen
+                        button { disabled: loading(), onclick: move |_| load_more(), "load more" }
+                    }
+                }
+            }
+        }
+        if let Some(err) = load_error() {
+            p { class: "favorites-message", "{err}" }
+        }
+        if recently_removed.read().is_some() {
+            UndoToast {
+                ondismiss: move |_| recently_removed.set(None),
+                onundo: move |_| async move {
+                    let Some(mut dog) = recently_removed.take() else {
+                        return;
+                    };
+                    // Re-inserting hands out a new id, which is also the newest, so it goes first
+                    let saved = backend::save_dog(dog.url.clone(), dog.source, dog.tags.clone()).await;
+                    let Ok(outcome) = saved else {
+                        return;
+                    };
+                    dog.id = outcome.id();
+                    if !dog.notes.is_empty() {
+                        _ = backend::update_dog_metadata(dog.id, dog.tags.clone(), dog.notes.clone())
+                            .await;
+                    }
+                    dogs.write().insert(0, dog);
+                    tags.restart();
+                },
+            }
+        }
+    }
+}
+
+/// A saved dog with its tags and notes, which can be edited in place.
+#[component]
+fn DogCard(
+    dog: SavedDog,
+    onupdate: EventHandler<SavedDog>,
+    onremove: EventHandler<MouseEvent>,
+) -> Element {
+    let mut editing = use_signal(|| false);
+    let mut tags_input = use_signal(|| dog.tags.join(", "));
+    let mut notes_input = use_signal(|| dog.notes.clone());
+
+    let save_metadata = {
+        let dog = dog.clone();
+        move |_: MouseEvent| {
+            let mut dog = dog.clone();
+            async move {
+                dog.tags = backend::parse_tags(&tags_input());
+                dog.notes = notes_input().trim().to_string();
+                if backend::update_dog_metadata(dog.id, dog.tags.clone(), dog.notes.clone())
+                    .await
+                    .is_ok()
+                {
+                    editing.set(false);
+                    onupdate.call(dog);
+                }
+            }
+        }
+    };
+
+    rsx! {
+        div { class: "favorite-dog",
+            img { src: "{dog.url}" }
+            button { onclick: move |evt| onremove.call(evt), "remove" }
+            if editing() {
+                div { class: "dog-metadata",
+                    input {
+                        placeholder: "tags, comma separated",
+                        value: "{tags_input}",
+                        oninput: move |evt| tags_input.set(evt.value()),
+                    }
+                    textarea {
+                        placeholder: "notes",
+                        value: "{notes_input}",
+                        oninput: move |evt| notes_input.set(evt.value()),
+                    }
+                    button { class: "edit", onclick: save_metadata, "done" }
+                }
+            } else {
+                div { class: "dog-metadata",
+                    div { class: "dog-tags",
+                        for tag in dog.tags.iter() {
+                            span { key: "{tag}", class: "tag", "{tag}" }
+                        }
+                    }
+                    if !dog.notes.is_empty() {
+                        p { class: "dog-notes", "{dog.notes}" }
+                    }
+                    button { class: "edit", onclick: move |_| editing.set(true), "edit" }
+                }
             }
         }
     }
 }
+
+/// Toast shown after a dog is removed. It fades out on its own and dismisses itself once the
+/// animation finishes.
+#[component]
+fn UndoToast(ondismiss: EventHandler<()>, onundo: EventHandler<MouseEvent>) -> Element {
+    rsx! {
+        div {
+            id: "undo-toast",
+            onanimationend: move |_| ondismiss.call(()),
+            span { "Dog removed" }
+            button { onclick: move |evt| onundo.call(evt), "undo" }
+        }
+    }
+}
@@ -1,7 +1,11 @@
 mod favorites;
 mod nav;
+mod settings;
+mod transfer;
 mod view;
 
 pub use favorites::*;
 pub use nav::*;
+pub use settings::*;
+pub use transfer::*;
 pub use view::*;
@@ -0,0 +1,31 @@
+use crate::providers::ImageSource;
+use dioxus::prelude::*;
+
+/// Lets the user pick which provider the random images come from.
+#[component]
+pub fn SettingsPanel(source: ImageSource, onchange: EventHandler<ImageSource>) -> Element {
+    rsx! {
+        details { id: "settings",
+            summary { "Settings" }
+            label {
+                "Image source "
+                select {
+                    value: source.as_str(),
+                    onchange: move |evt| {
+                        if let Some(source) = ImageSource::parse(&evt.value()) {
+                            onchange.call(source);
+                        }
+                    },
+                    for option in ImageSource::ALL {
+                        option {
+                            key: "{option.as_str()}",
+                            value: option.as_str(),
+                            selected: option == source,
+                            "{option.label()}"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use crate::backend;
+use dioxus::prelude::*;
+
+/// Lets favorites move between machines: export fills the text box with a JSON document to copy,
+/// import reads one pasted into it.
+#[component]
+pub fn TransferPanel(onimported: EventHandler<()>) -> Element {
+    let mut document = use_signal(String::new);
+    let mut status = use_signal(|| None::<String>);
+
+    rsx! {
+        details { id: "transfer",
+            summary { "Export / import" }
+            textarea {
+                placeholder: "Paste an exported favorites file here to import it",
+                value: "{document}",
+                oninput: move |evt| document.set(evt.value()),
+            }
+            div { id: "transfer-buttons",
+                button {
+                    onclick: move |_| async move {
+                        match backend::export_dogs().await {
+                            Ok(json) => {
+                                document.set(json);
+                                status.set(Some("Copy the text above to keep your favorites".to_string()));
+                            }
+                            Err(err) => status.set(Some(format!("Export failed: {err}"))),
+                        }
+                    },
+                    "export"
+                }
+                button {
+                    onclick: move |_| async move {
+                        match backend::import_dogs(document()).await {
+                            Ok(summary) => {
+                                status.set(Some(format!(
+                                    "Imported {} new dogs, merged {} already saved",
+                                    summary.added,
+                                    summary.merged,
+                                )));
+                                onimported.call(());
+                            }
+                            Err(err) => status.set(Some(format!("Import failed: {err}"))),
+                        }
+                    },
+                    "import"
+                }
+            }
+            if let Some(status) = status() {
+                p { class: "favorites-message", "{status}" }
+            }
+        }
+    }
+}
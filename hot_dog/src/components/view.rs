@@ -1,38 +1,131 @@
 use crate::backend;
+use crate::components::SettingsPanel;
+use crate::providers::{FetchError, ImageProvider, ImageSource};
 use dioxus::prelude::*;
+use std::collections::VecDeque;
+
+/// How many upcoming images are kept loaded ahead of the one on screen.
+const PREFETCH_COUNT: usize = 3;
 
 #[component]
 pub fn DogView() -> Element {
-    let mut img_src = use_resource(|| async move {
-        reqwest::get("https://dog.ceo/api/breeds/image/random")
-            .await
-            .unwrap()
-            .json::<DogApi>()
-            .await
-            .unwrap()
-            .message
-    });
+    let mut source = use_context::<Signal<ImageSource>>();
+    let mut current = use_signal(|| None::<String>);
+    let mut queue = use_signal(VecDeque::<String>::new);
+    let mut refilling = use_signal(|| false);
+    let mut fetch_error = use_signal(|| None::<FetchError>);
+    let mut save_status = use_signal(|| None::<&'static str>);
+    let mut tags_input = use_signal(String::new);
+
+    // Tops the queue back up in the background so that skipping never waits on the network. A
+    // failed fetch (e.g. rate limiting) is simply retried on the next skip or save.
+    let mut refill = move || {
+        if *refilling.peek() {
+            return;
+        }
+        refilling.set(true);
+        spawn(async move {
+            loop {
+                let provider = *source.peek();
+                let missing = PREFETCH_COUNT.saturating_sub(queue.peek().len())
+                    + usize::from(current.peek().is_none());
+                if missing == 0 {
+                    break;
+                }
+                match provider.fetch_random(missing).await {
+                    // The source was switched while this batch was in flight, so fetch again
+                    Ok(_) if provider != *source.peek() => {}
+                    Ok(urls) if urls.is_empty() => break,
+                    Ok(urls) => {
+                        fetch_error.set(None);
+                        queue.write().extend(urls);
+                        if current.peek().is_none() {
+                            let next = queue.write().pop_front();
+                            current.set(next);
+                        }
+                    }
+                    Err(err) => {
+                        fetch_error.set(Some(err));
+                        break;
+                    }
+                }
+            }
+            refilling.set(false);
+        });
+    };
+    use_hook(move || refill());
+
+    let mut advance = move || {
+        let next = queue.write().pop_front();
+        current.set(next);
+        refill();
+    };
 
     rsx! {
+        SettingsPanel {
+            source: source(),
+            onchange: move |selected| {
+                source.set(selected);
+                queue.write().clear();
+                current.set(None);
+                fetch_error.set(None);
+                refill();
+            },
+        }
         div { id: "dogview",
-            img { src: img_src.cloned().unwrap_or_default() }
+            match (current(), fetch_error()) {
+                (Some(url), _) => rsx! {
+                    img { src: "{url}" }
+                },
+                (None, Some(err)) => rsx! {
+                    p { class: "favorites-message", "{err}" }
+                },
+                (None, None) => rsx! {},
+            }
+        }
+        // Hidden copies of the queued images so the browser has them cached before they are shown
+        div { id: "prefetch",
+            for url in queue() {
+                img { key: "{url}", src: "{url}" }
+            }
+        }
+        input {
+            id: "tags-input",
+            placeholder: "tags, comma separated",
+            value: "{tags_input}",
+            oninput: move |evt| tags_input.set(evt.value()),
+        }
+        div { id: "save-status",
+            if let Some(status) = save_status() {
+                "{status}"
+            }
         }
         div { id: "buttons",
-            button { onclick: move |_| img_src.restart(), id: "skip", "skip" }
+            button {
+                onclick: move |_| {
+                    save_status.set(None);
+                    advance();
+                },
+                id: "skip",
+                "skip"
+            }
             button {
                 id: "save",
                 onclick: move |_| async move {
-                    let image = img_src.cloned().unwrap();
-                    img_src.restart();
-                    _ = backend::save_dog(image).await;
+                    let Some(image) = current() else {
+                        return;
+                    };
+                    let tags = backend::parse_tags(&tags_input.take());
+                    advance();
+                    let status = match backend::save_dog(image, source(), tags).await {
+                        Ok(backend::SaveOutcome::Saved(_)) => "Saved!",
+                        Ok(backend::SaveOutcome::AlreadySaved(_)) => "Already saved",
+                        Err(_) => "Couldn't save that one",
+                    };
+                    save_status.set(Some(status));
                 },
                 "save!"
             }
         }
     }
 }
-
-#[derive(serde::Deserialize)]
-struct DogApi {
-    message: String,
-}
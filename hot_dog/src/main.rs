@@ -1,7 +1,9 @@
 mod backend;
 mod components;
+mod providers;
 
 use crate::components::*;
+use crate::providers::ImageSource;
 
 use dioxus::prelude::*;
 
@@ -13,6 +15,9 @@ static CSS: Asset = asset!("assets/main.css");
 
 #[component]
 fn App() -> Element {
+    // The chosen image source lives above the router so it survives moving between pages
+    use_context_provider(|| Signal::new(ImageSource::default()));
+
     rsx! {
         document::Stylesheet { href: CSS }
         Router::<Route> {}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+
+/// Somewhere random animal pictures can be fetched from.
+pub trait ImageProvider {
+    /// Fetches the urls of `count` random images.
+    async fn fetch_random(&self, count: usize) -> Result<Vec<String>, FetchError>;
+}
+
+/// The image providers the app knows about. This is also what gets stored with each saved image so
+/// the gallery remembers where it came from.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageSource {
+    #[default]
+    Dog,
+    Cat,
+    Fox,
+}
+
+impl ImageSource {
+    pub const ALL: [ImageSource; 3] = [ImageSource::Dog, ImageSource::Cat, ImageSource::Fox];
+
+    /// Identifier used in the database and in exports.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImageSource::Dog => "dog",
+            ImageSource::Cat => "cat",
+            ImageSource::Fox => "fox",
+        }
+    }
+
+    /// Parses an identifier produced by [`ImageSource::as_str`].
+    pub fn parse(value: &str) -> Option<ImageSource> {
+        ImageSource::ALL
+            .into_iter()
+            .find(|source| source.as_str() == value)
+    }
+
+    /// Human readable name shown in the settings panel.
+    pub fn label(&self) -> &'static str {
+        match self {
+            ImageSource::Dog => "Dogs (dog.ceo)",
+            ImageSource::Cat => "Cats (thecatapi.com)",
+            ImageSource::Fox => "Foxes (randomfox.ca)",
+        }
+    }
+}
+
+impl ImageProvider for ImageSource {
+    async fn fetch_random(&self, count: usize) -> Result<Vec<String>, FetchError> {
+        match self {
+            ImageSource::Dog => DogCeo.fetch_random(count).await,
+            ImageSource::Cat => TheCatApi.fetch_random(count).await,
+            ImageSource::Fox => RandomFox.fetch_random(count).await,
+        }
+    }
+}
+
+/// Why a batch of random images couldn't be fetched.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FetchError {
+    RateLimited,
+    Unavailable,
+}
+
+impl std::fmt::Display for FetchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FetchError::RateLimited => {
+                write!(f, "Too many pictures too fast! Try again in a moment.")
+            }
+            FetchError::Unavailable => write!(f, "Couldn't reach the image API. Try again later."),
+        }
+    }
+}
+
+/// <https://dog.ceo>, which hands out several images per request.
+pub struct DogCeo;
+
+impl ImageProvider for DogCeo {
+    async fn fetch_random(&self, count: usize) -> Result<Vec<String>, FetchError> {
+        #[derive(Deserialize)]
+        struct DogListApi {
+            message: Vec<String>,
+        }
+
+        let dogs: DogListApi =
+            get_json(&format!("https://dog.ceo/api/breeds/image/random/{count}")).await?;
+        Ok(dogs.message)
+    }
+}
+
+/// <https://thecatapi.com>, which also returns a batch per request.
+pub struct TheCatApi;
+
+impl ImageProvider for TheCatApi {
+    async fn fetch_random(&self, count: usize) -> Result<Vec<String>, FetchError> {
+        #[derive(Deserialize)]
+        struct CatApi {
+            url: String,
+        }
+
+        let cats: Vec<CatApi> = get_json(&format!(
+            "https://api.thecatapi.com/v1/images/search?limit={count}"
+        ))
+        .await?;
+        // Without an API key the limit is only a hint, so trim whatever came back
+        Ok(cats.into_iter().take(count).map(|cat| cat.url).collect())
+    }
+}
+
+/// <https://randomfox.ca>, which only serves one image per request.
+pub struct RandomFox;
+
+impl ImageProvider for RandomFox {
+    async fn fetch_random(&self, count: usize) -> Result<Vec<String>, FetchError> {
+        #[derive(Deserialize)]
+        struct FoxApi {
+            image: String,
+        }
+
+        let mut foxes = Vec::with_capacity(count);
+        for _ in 0..count {
+            let fox: FoxApi = get_json("https://randomfox.ca/floof/").await?;
+            foxes.push(fox.image);
+        }
+        Ok(foxes)
+    }
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(url: &str) -> Result<T, FetchError> {
+    let response = reqwest::get(url)
+        .await
+        .map_err(|_| FetchError::Unavailable)?;
+    if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Err(FetchError::RateLimited);
+    }
+    response
+        .error_for_status()
+        .map_err(|_| FetchError::Unavailable)?
+        .json()
+        .await
+        .map_err(|_| FetchError::Unavailable)
+}
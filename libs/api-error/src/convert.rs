@@ -0,0 +1,112 @@
+//! Conversions from the errors handlers commonly run into, so they can be returned with `?`.
+
+use crate::ApiError;
+use axum::extract::rejection::{JsonRejection, PathRejection, QueryRejection};
+
+impl From<JsonRejection> for ApiError {
+    fn from(rejection: JsonRejection) -> Self {
+        ApiError::new(rejection.status()).with_detail(rejection.body_text())
+    }
+}
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        ApiError::new(rejection.status()).with_detail(rejection.body_text())
+    }
+}
+
+impl From<PathRejection> for ApiError {
+    fn from(rejection: PathRejection) -> Self {
+        ApiError::new(rejection.status()).with_detail(rejection.body_text())
+    }
+}
+
+/// A missing record is the client's problem. Anything else is a server error whose details stay
+/// in the logs.
+#[cfg(feature = "sea-orm")]
+impl From<sea_orm::DbErr> for ApiError {
+    fn from(err: sea_orm::DbErr) -> Self {
+        match err {
+            sea_orm::DbErr::RecordNotFound(detail) => ApiError::not_found(detail),
+            err => ApiError::internal("A database error occurred").with_source(err),
+        }
+    }
+}
+
+#[cfg(feature = "askama")]
+impl From<askama::Error> for ApiError {
+    fn from(err: askama::Error) -> Self {
+        ApiError::internal("Failed to render the response").with_source(err)
+    }
+}
+
+#[cfg(feature = "config-core")]
+impl From<config_core::ValidationError> for crate::FieldError {
+    fn from(err: config_core::ValidationError) -> Self {
+        crate::FieldError::new(err.field, err.message)
+    }
+}
+
+#[cfg(feature = "config-core")]
+impl From<Vec<config_core::ValidationError>> for ApiError {
+    fn from(errors: Vec<config_core::ValidationError>) -> Self {
+        ApiError::validation(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::Query, http::StatusCode};
+
+    #[derive(Debug, serde::Deserialize)]
+    #[allow(dead_code)]
+    struct Params {
+        page: u64,
+    }
+
+    #[test]
+    fn rejections_keep_their_status_and_message() {
+        let rejection =
+            Query::<Params>::try_from_uri(&"/names?page=first".parse().unwrap()).unwrap_err();
+        let error = ApiError::from(rejection);
+
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+        assert!(error.problem().detail.unwrap().contains("page"));
+    }
+
+    #[cfg(feature = "sea-orm")]
+    #[test]
+    fn missing_records_are_not_found() {
+        let error = ApiError::from(sea_orm::DbErr::RecordNotFound("name 3".to_string()));
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+
+        let error = ApiError::from(sea_orm::DbErr::Custom("pool timed out".to_string()));
+        assert_eq!(error.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            error.problem().detail.as_deref(),
+            Some("A database error occurred")
+        );
+    }
+
+    #[cfg(feature = "askama")]
+    #[test]
+    fn template_errors_are_server_errors() {
+        let error = ApiError::from(askama::Error::Custom("boom".into()));
+        assert_eq!(error.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[cfg(feature = "config-core")]
+    #[test]
+    fn validation_errors_list_each_field() {
+        let error = ApiError::from(vec![config_core::ValidationError::new(
+            "name",
+            "must not be empty",
+        )]);
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            error.problem().errors,
+            vec![crate::FieldError::new("name", "must not be empty")]
+        );
+    }
+}
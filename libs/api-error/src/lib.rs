@@ -0,0 +1,282 @@
+//! Machine-readable error responses for the JSON APIs.
+//!
+//! [`ApiError`] renders as an RFC 7807 "problem details" document served as
+//! `application/problem+json`, so every API reports failures in the same shape no matter which
+//! handler or extractor produced them. Server errors keep their cause for the logs but never
+//! expose it to the client.
+
+mod convert;
+
+use axum::{
+    Json,
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use utoipa::ToSchema;
+
+/// Media type of a problem details document.
+pub const PROBLEM_JSON: &str = "application/problem+json";
+
+/// Problem type used when the status code says everything there is to say.
+pub const ABOUT_BLANK: &str = "about:blank";
+
+/// RFC 7807 problem details, as sent to API clients.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ProblemDetails {
+    /// URI reference identifying the kind of problem
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// Short summary of the kind of problem
+    pub title: String,
+    /// HTTP status code of the response
+    pub status: u16,
+    /// Explanation specific to this occurrence of the problem
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    /// URI reference identifying this occurrence of the problem
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Invalid fields, for validation failures
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+}
+
+/// A single invalid field in a request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct FieldError {
+    /// Name of the invalid field
+    pub field: String,
+    /// What is wrong with its value
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// An error returned from an API handler, rendered as [`ProblemDetails`].
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    problem_type: String,
+    title: Option<String>,
+    detail: Option<String>,
+    instance: Option<String>,
+    errors: Vec<FieldError>,
+    source: Option<Box<dyn std::error::Error + Send + Sync>>,
+}
+
+impl ApiError {
+    /// An error with the given status, titled after its canonical reason phrase.
+    pub fn new(status: StatusCode) -> Self {
+        Self {
+            status,
+            problem_type: ABOUT_BLANK.to_string(),
+            title: None,
+            detail: None,
+            instance: None,
+            errors: Vec::new(),
+            source: None,
+        }
+    }
+
+    pub fn bad_request(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST).with_detail(detail)
+    }
+
+    pub fn unauthorized(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNAUTHORIZED).with_detail(detail)
+    }
+
+    pub fn forbidden(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::FORBIDDEN).with_detail(detail)
+    }
+
+    pub fn not_found(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND).with_detail(detail)
+    }
+
+    pub fn conflict(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT).with_detail(detail)
+    }
+
+    pub fn internal(detail: impl Into<String>) -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR).with_detail(detail)
+    }
+
+    /// A `422 Unprocessable Entity` listing every invalid field.
+    pub fn validation<E: Into<FieldError>>(errors: impl IntoIterator<Item = E>) -> Self {
+        let mut error = Self::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .with_detail("The request contains invalid fields");
+        error.errors = errors.into_iter().map(Into::into).collect();
+        error
+    }
+
+    /// Identifies the kind of problem more precisely than the status code does.
+    pub fn with_type(mut self, problem_type: impl Into<String>) -> Self {
+        self.problem_type = problem_type.into();
+        self
+    }
+
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    pub fn with_instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Keeps the underlying error so that it gets logged. It is never sent to the client.
+    pub fn with_source(
+        mut self,
+        source: impl Into<Box<dyn std::error::Error + Send + Sync>>,
+    ) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// The document sent to the client for this error.
+    pub fn problem(&self) -> ProblemDetails {
+        ProblemDetails {
+            problem_type: self.problem_type.clone(),
+            title: self.title.clone().unwrap_or_else(|| {
+                self.status
+                    .canonical_reason()
+                    .unwrap_or("Unknown Error")
+                    .to_string()
+            }),
+            status: self.status.as_u16(),
+            detail: self.detail.clone(),
+            instance: self.instance.clone(),
+            errors: self.errors.clone(),
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.detail {
+            Some(detail) => write!(f, "{}: {}", self.status, detail),
+            None => write!(f, "{}", self.status),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_deref()
+            .map(|source| source as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        if self.status.is_server_error() {
+            match &self.source {
+                Some(source) => tracing::error!(status = %self.status, error = %source, "{self}"),
+                None => tracing::error!(status = %self.status, "{self}"),
+            }
+        }
+        (
+            self.status,
+            [(header::CONTENT_TYPE, PROBLEM_JSON)],
+            Json(self.problem()),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn body(error: ApiError) -> (StatusCode, String, serde_json::Value) {
+        let response = error.into_response();
+        let status = response.status();
+        let content_type = response.headers()[header::CONTENT_TYPE]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, content_type, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[tokio::test]
+    async fn renders_problem_json() {
+        let (status, content_type, json) =
+            body(ApiError::not_found("Name entry with ID 7 not found").with_instance("/names/7"))
+                .await;
+
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(content_type, PROBLEM_JSON);
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "type": "about:blank",
+                "title": "Not Found",
+                "status": 404,
+                "detail": "Name entry with ID 7 not found",
+                "instance": "/names/7",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn lists_invalid_fields() {
+        let (status, _, json) = body(ApiError::validation([
+            FieldError::new("name", "must not be empty"),
+            FieldError::new("discord_id", "must be a snowflake"),
+        ]))
+        .await;
+
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(json["errors"][0]["field"], "name");
+        assert_eq!(json["errors"][1]["message"], "must be a snowflake");
+    }
+
+    #[tokio::test]
+    async fn hides_the_source_of_server_errors() {
+        let error = ApiError::internal("Failed to retrieve names").with_source("connection reset");
+        assert_eq!(
+            std::error::Error::source(&error).unwrap().to_string(),
+            "connection reset"
+        );
+
+        let (_, _, json) = body(error).await;
+        assert_eq!(json["detail"], "Failed to retrieve names");
+        assert!(!json.to_string().contains("connection reset"));
+    }
+
+    #[test]
+    fn custom_type_and_title_replace_the_defaults() {
+        let problem = ApiError::unauthorized("Invalid username or password")
+            .with_type("/problems/invalid-credentials")
+            .with_title("Invalid credentials")
+            .problem();
+
+        assert_eq!(problem.problem_type, "/problems/invalid-credentials");
+        assert_eq!(problem.title, "Invalid credentials");
+        assert_eq!(problem.status, 401);
+    }
+}
@@ -0,0 +1,286 @@
+//! Layered configuration loading shared by the services.
+//!
+//! Values are resolved from environment variables first, then an optional config file, then
+//! defaults registered on the [`ConfigLoader`]. The loaded config is validated before it is handed
+//! out, and values wrapped in [`Secret`] never show up in `Debug` output.
+
+mod secret;
+
+use std::fmt;
+use std::path::PathBuf;
+
+use serde::de::DeserializeOwned;
+
+pub use secret::Secret;
+
+/// A config that can check its own values after it has been loaded.
+pub trait Validate {
+    /// Returns every problem with the loaded values, not just the first one.
+    fn validate(&self) -> Vec<ValidationError> {
+        Vec::new()
+    }
+}
+
+/// A single invalid config value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new(field: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            field,
+            message: message.into(),
+        }
+    }
+
+    /// Reports `field` as missing if `value` is empty.
+    pub fn require_non_empty(field: &'static str, value: &str) -> Option<Self> {
+        value
+            .trim()
+            .is_empty()
+            .then(|| Self::new(field, "must not be empty"))
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Custom error type for configuration loading.
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    /// A source couldn't be read, or its values don't fit the config type.
+    #[error("Failed to load configuration: {0}")]
+    Load(#[from] config::ConfigError),
+    /// The values were loaded but some of them are invalid.
+    #[error("Invalid configuration: {}", join(.0))]
+    Invalid(Vec<ValidationError>),
+}
+
+fn join(errors: &[ValidationError]) -> String {
+    errors
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Builds a config out of, from highest to lowest priority, environment variables, an optional
+/// file and defaults.
+#[derive(Debug, Default)]
+pub struct ConfigLoader {
+    env_prefix: Option<String>,
+    file: Option<PathBuf>,
+    defaults: Vec<(String, config::Value)>,
+}
+
+impl ConfigLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only reads environment variables starting with `PREFIX_`. Without a prefix every variable
+    /// is considered.
+    pub fn env_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.env_prefix = Some(prefix.into());
+        self
+    }
+
+    /// Reads values from this file if it exists. The format is picked from the extension.
+    pub fn file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.file = Some(path.into());
+        self
+    }
+
+    /// Uses `value` for `key` when neither the environment nor the file sets it.
+    pub fn default_value(mut self, key: &str, value: impl Into<config::Value>) -> Self {
+        self.defaults.push((key.to_string(), value.into()));
+        self
+    }
+
+    /// Loads and validates the config.
+    pub fn load<T: DeserializeOwned + Validate>(&self) -> Result<T, ConfigError> {
+        self.load_with(self.environment())
+    }
+
+    fn environment(&self) -> config::Environment {
+        match &self.env_prefix {
+            Some(prefix) => config::Environment::with_prefix(prefix),
+            None => config::Environment::default(),
+        }
+    }
+
+    fn load_with<T: DeserializeOwned + Validate>(
+        &self,
+        environment: config::Environment,
+    ) -> Result<T, ConfigError> {
+        let mut builder = config::Config::builder();
+        for (key, value) in &self.defaults {
+            builder = builder.set_default(key.as_str(), value.clone())?;
+        }
+        if let Some(file) = &self.file {
+            builder = builder.add_source(config::File::from(file.as_path()).required(false));
+        }
+        let settings = builder.add_source(environment).build()?;
+
+        let config: T = settings.try_deserialize()?;
+        let errors = config.validate();
+        if !errors.is_empty() {
+            return Err(ConfigError::Invalid(errors));
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Loads with `vars` standing in for the process environment, which tests can't safely modify.
+    fn load<T: DeserializeOwned + Validate>(
+        loader: ConfigLoader,
+        vars: &[(&str, &str)],
+    ) -> Result<T, ConfigError> {
+        let vars = vars
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        loader.load_with(loader.environment().source(Some(vars)))
+    }
+
+    #[derive(serde::Deserialize, Debug)]
+    struct TestConfig {
+        name: String,
+        port: u16,
+        token: Secret,
+    }
+
+    impl Validate for TestConfig {
+        fn validate(&self) -> Vec<ValidationError> {
+            let mut errors = Vec::new();
+            errors.extend(ValidationError::require_non_empty("name", &self.name));
+            if self.port == 0 {
+                errors.push(ValidationError::new("port", "must not be 0"));
+            }
+            errors
+        }
+    }
+
+    fn config_file(contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "config-core-{}-{:?}.toml",
+            std::process::id(),
+            std::thread::current().id()
+        ));
+        std::fs::File::create(&path)
+            .unwrap()
+            .write_all(contents.as_bytes())
+            .unwrap();
+        path
+    }
+
+    #[test]
+    fn can_fall_back_to_defaults() {
+        let loader = ConfigLoader::new().default_value("port", 8080);
+
+        let config: TestConfig = load(loader, &[("NAME", "nicknamer"), ("TOKEN", "abc")]).unwrap();
+
+        assert_eq!(config.name, "nicknamer");
+        assert_eq!(config.port, 8080);
+        assert_eq!(config.token.expose(), "abc");
+    }
+
+    #[test]
+    fn env_overrides_file_and_file_overrides_defaults() {
+        let file = config_file("name = \"from-file\"\nport = 9000\ntoken = \"file\"\n");
+        let loader = ConfigLoader::new()
+            .file(&file)
+            .default_value("port", 8080)
+            .default_value("name", "default");
+
+        let config: TestConfig = load(loader, &[("TOKEN", "env")]).unwrap();
+        std::fs::remove_file(file).unwrap();
+
+        assert_eq!(config.name, "from-file");
+        assert_eq!(config.port, 9000);
+        assert_eq!(config.token.expose(), "env");
+    }
+
+    #[test]
+    fn ignores_a_missing_file() {
+        let loader = ConfigLoader::new().file("/does/not/exist.toml");
+
+        let config: Result<TestConfig, _> = load(
+            loader,
+            &[("NAME", "nicknamer"), ("PORT", "1"), ("TOKEN", "abc")],
+        );
+
+        assert!(config.is_ok());
+    }
+
+    #[test]
+    fn only_reads_prefixed_variables_when_a_prefix_is_set() {
+        let loader = ConfigLoader::new().env_prefix("APP");
+
+        let config: TestConfig = load(
+            loader,
+            &[
+                ("NAME", "unprefixed"),
+                ("APP_NAME", "prefixed"),
+                ("APP_PORT", "1"),
+                ("APP_TOKEN", "abc"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(config.name, "prefixed");
+    }
+
+    #[test]
+    fn reports_every_invalid_value() {
+        let error = load::<TestConfig>(
+            ConfigLoader::new(),
+            &[("NAME", " "), ("PORT", "0"), ("TOKEN", "abc")],
+        )
+        .unwrap_err();
+
+        let ConfigError::Invalid(errors) = &error else {
+            panic!("expected validation errors, got {error:?}");
+        };
+        assert_eq!(
+            errors,
+            &vec![
+                ValidationError::new("name", "must not be empty"),
+                ValidationError::new("port", "must not be 0"),
+            ]
+        );
+        assert_eq!(
+            error.to_string(),
+            "Invalid configuration: name: must not be empty, port: must not be 0"
+        );
+    }
+
+    #[test]
+    fn reports_missing_values() {
+        let error = load::<TestConfig>(ConfigLoader::new(), &[("NAME", "nicknamer")]).unwrap_err();
+
+        assert!(matches!(error, ConfigError::Load(_)));
+    }
+
+    #[test]
+    fn does_not_leak_secrets_in_debug_output() {
+        let config: TestConfig = load(
+            ConfigLoader::new(),
+            &[("NAME", "nicknamer"), ("PORT", "1"), ("TOKEN", "hunter2")],
+        )
+        .unwrap();
+
+        assert!(!format!("{config:?}").contains("hunter2"));
+    }
+}
@@ -0,0 +1,44 @@
+//! Helpers shared by integration tests that need a real Postgres database: booting a throwaway
+//! container and running a service's migrations against it.
+
+use sea_orm::{Database, DatabaseConnection};
+use sea_orm_migration::MigratorTrait;
+use testcontainers_modules::postgres::Postgres;
+use testcontainers_modules::testcontainers::ContainerAsync;
+use testcontainers_modules::testcontainers::runners::AsyncRunner;
+
+/// A migrated database running in its own Postgres container.
+///
+/// The container is stopped when this is dropped, so keep it alive for as long as `db` is used.
+pub struct TestDb {
+    pub db: DatabaseConnection,
+    #[allow(dead_code)] // container is kept to ensure it's not dropped
+    container: ContainerAsync<Postgres>,
+}
+
+impl TestDb {
+    /// Boots a fresh Postgres container and applies every migration of `M` to it.
+    pub async fn start<M: MigratorTrait>() -> anyhow::Result<Self> {
+        let container = setup_container().await?;
+        let db = setup_db::<M>(&container).await?;
+        Ok(Self { db, container })
+    }
+}
+
+/// Boots a fresh Postgres container.
+pub async fn setup_container() -> anyhow::Result<ContainerAsync<Postgres>> {
+    let container = Postgres::default().start().await?;
+    Ok(container)
+}
+
+/// Connects to the database in `container` and applies every migration of `M`.
+pub async fn setup_db<M: MigratorTrait>(
+    container: &ContainerAsync<Postgres>,
+) -> anyhow::Result<DatabaseConnection> {
+    let host = container.get_host().await?;
+    let port = container.get_host_port_ipv4(5432).await?;
+    let db_url = format!("postgres://postgres:postgres@{}:{}/postgres", host, port);
+    let db = Database::connect(&db_url).await?;
+    M::up(&db, None).await?;
+    Ok(db)
+}
@@ -0,0 +1,13 @@
+/// Represents a file attached to the command being handled.
+///
+/// Only describes the file, its contents are fetched with
+/// [`DiscordConnector::download_attachment`](crate::DiscordConnector::download_attachment).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Attachment {
+    /// Discord's unique identifier of the attachment
+    pub id: u64,
+    /// Name of the file, as uploaded
+    pub filename: String,
+    /// Size of the file in bytes
+    pub size: u64,
+}
@@ -0,0 +1,327 @@
+//! Dry runs of commands, which show what they would change without changing anything.
+
+use crate::{Attachment, DiscordConnector, Error, Role, ServerMember};
+use async_trait::async_trait;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// What replies sent during a dry run start with, so they aren't mistaken for real ones.
+const DRY_RUN_PREFIX: &str = "[dry run] ";
+
+/// A change to a guild or its members that a dry run left out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mutation {
+    NickName { member_id: u64, nick_name: String },
+    AddRole { member_id: u64, role_id: u64 },
+    RemoveRole { member_id: u64, role_id: u64 },
+    DirectMessage { user_id: u64, message: String },
+}
+
+impl fmt::Display for Mutation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mutation::NickName {
+                member_id,
+                nick_name,
+            } => write!(
+                f,
+                "change the nickname of <@{}> to '{}'",
+                member_id, nick_name
+            ),
+            Mutation::AddRole { member_id, role_id } => {
+                write!(f, "give <@{}> the <@&{}> role", member_id, role_id)
+            }
+            Mutation::RemoveRole { member_id, role_id } => {
+                write!(f, "take the <@&{}> role from <@{}>", role_id, member_id)
+            }
+            Mutation::DirectMessage { user_id, message } => {
+                write!(f, "send <@{}> a direct message: {}", user_id, message)
+            }
+        }
+    }
+}
+
+/// Connector that wraps another one, and in a dry run records the changes a command makes
+/// instead of making them.
+///
+/// Everything that only reads passes through, and so do replies, marked as coming from a dry
+/// run. Confirmations are taken as given without waiting, so that the dry run shows all that
+/// would change. Outside of a dry run, every call passes through untouched.
+pub struct DryRunConnector<C> {
+    inner: C,
+    dry_run: bool,
+    mutations: Mutex<Vec<Mutation>>,
+}
+
+impl<C> DryRunConnector<C> {
+    /// Wraps `inner`, only recording changes if `dry_run` is set.
+    pub fn new(inner: C, dry_run: bool) -> Self {
+        Self {
+            inner,
+            dry_run,
+            mutations: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn is_dry_run(&self) -> bool {
+        self.dry_run
+    }
+
+    /// The changes left out so far, in the order they were asked for.
+    pub fn mutations(&self) -> Vec<Mutation> {
+        self.mutations
+            .lock()
+            .expect("mutations lock poisoned")
+            .clone()
+    }
+
+    /// Records `mutation` in a dry run.
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - Whether it was recorded, rather than left to the inner connector
+    fn record(&self, mutation: impl FnOnce() -> Mutation) -> bool {
+        if self.dry_run {
+            self.mutations
+                .lock()
+                .expect("mutations lock poisoned")
+                .push(mutation());
+        }
+        self.dry_run
+    }
+
+    fn marked(&self, message: &str) -> String {
+        if self.dry_run {
+            format!("{}{}", DRY_RUN_PREFIX, message)
+        } else {
+            message.to_string()
+        }
+    }
+
+    /// Lists the changes a dry run left out, or `None` outside of one.
+    pub fn summary(&self) -> Option<String> {
+        if !self.dry_run {
+            return None;
+        }
+        let mutations = self.mutations();
+        if mutations.is_empty() {
+            return Some("Nothing would have changed".to_string());
+        }
+        let lines: Vec<String> = mutations.iter().map(ToString::to_string).collect();
+        Some(format!(
+            "Nothing was changed, but I would have:\n\t{}",
+            lines.join("\n\t")
+        ))
+    }
+}
+
+impl<C: DiscordConnector + Send + Sync> DryRunConnector<C> {
+    /// Replies with what a dry run would have changed, or does nothing outside of one.
+    pub async fn send_summary(&self) -> Result<(), Error> {
+        match self.summary() {
+            Some(summary) => self.send_reply(&summary).await,
+            None => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl<C: DiscordConnector + Send + Sync> DiscordConnector for DryRunConnector<C> {
+    async fn get_members_of_current_channel(&self) -> Result<Vec<ServerMember>, Error> {
+        self.inner.get_members_of_current_channel().await
+    }
+
+    async fn send_reply(&self, message: &str) -> Result<(), Error> {
+        self.inner.send_reply(&self.marked(message)).await
+    }
+
+    async fn send_message(&self, message: &str) -> Result<u64, Error> {
+        self.inner.send_message(&self.marked(message)).await
+    }
+
+    async fn send_direct_message(&self, user_id: u64, message: &str) -> Result<u64, Error> {
+        let recorded = self.record(|| Mutation::DirectMessage {
+            user_id,
+            message: message.to_string(),
+        });
+        if recorded {
+            // There's no message to point to
+            return Ok(0);
+        }
+        self.inner.send_direct_message(user_id, message).await
+    }
+
+    async fn edit_message(&self, message_id: u64, new_content: &str) -> Result<(), Error> {
+        self.inner
+            .edit_message(message_id, &self.marked(new_content))
+            .await
+    }
+
+    async fn send_embed(&self, title: &str, description: &str) -> Result<u64, Error> {
+        self.inner
+            .send_embed(&self.marked(title), description)
+            .await
+    }
+
+    async fn download_attachment(&self, attachment: &Attachment) -> Result<Vec<u8>, Error> {
+        self.inner.download_attachment(attachment).await
+    }
+
+    async fn add_reaction(&self, message_id: u64, emoji: &str) -> Result<(), Error> {
+        if self.dry_run {
+            return Ok(());
+        }
+        self.inner.add_reaction(message_id, emoji).await
+    }
+
+    async fn await_author_reaction(
+        &self,
+        message_id: u64,
+        emoji: &str,
+        timeout: Duration,
+    ) -> Result<bool, Error> {
+        if self.dry_run {
+            return Ok(true);
+        }
+        self.inner
+            .await_author_reaction(message_id, emoji, timeout)
+            .await
+    }
+
+    async fn get_role_by_name(&self, name: &str) -> Result<Box<dyn Role>, Error> {
+        self.inner.get_role_by_name(name).await
+    }
+
+    async fn add_role_to_member(&self, member_id: u64, role_id: u64) -> Result<(), Error> {
+        if self.record(|| Mutation::AddRole { member_id, role_id }) {
+            return Ok(());
+        }
+        self.inner.add_role_to_member(member_id, role_id).await
+    }
+
+    async fn remove_role_from_member(&self, member_id: u64, role_id: u64) -> Result<(), Error> {
+        if self.record(|| Mutation::RemoveRole { member_id, role_id }) {
+            return Ok(());
+        }
+        self.inner.remove_role_from_member(member_id, role_id).await
+    }
+
+    async fn author_has_role(&self, role_id: u64) -> Result<bool, Error> {
+        self.inner.author_has_role(role_id).await
+    }
+
+    async fn change_member_nick_name(
+        &self,
+        member_id: u64,
+        new_nick_name: &str,
+    ) -> Result<(), Error> {
+        let recorded = self.record(|| Mutation::NickName {
+            member_id,
+            nick_name: new_nick_name.to_string(),
+        });
+        if recorded {
+            return Ok(());
+        }
+        self.inner
+            .change_member_nick_name(member_id, new_nick_name)
+            .await
+    }
+
+    async fn get_guild_owner_id(&self) -> Result<u64, Error> {
+        self.inner.get_guild_owner_id().await
+    }
+
+    async fn get_guild_id(&self) -> Result<u64, Error> {
+        self.inner.get_guild_id().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MockDiscordConnector;
+    use mockall::predicate::eq;
+
+    #[tokio::test]
+    async fn dry_runs_record_changes_instead_of_making_them() {
+        // Arrange
+        let mut inner = MockDiscordConnector::new();
+        inner.expect_change_member_nick_name().never();
+        inner.expect_add_role_to_member().never();
+        inner
+            .expect_send_reply()
+            .with(eq("[dry run] 'bob' is now 'Bob'"))
+            .times(1)
+            .returning(|_| Ok(()));
+        let sut = DryRunConnector::new(inner, true);
+
+        // Act
+        sut.change_member_nick_name(2, "Bob").await.unwrap();
+        sut.add_role_to_member(2, 9).await.unwrap();
+        sut.send_reply("'bob' is now 'Bob'").await.unwrap();
+
+        // Assert
+        assert_eq!(
+            sut.mutations(),
+            vec![
+                Mutation::NickName {
+                    member_id: 2,
+                    nick_name: "Bob".to_string()
+                },
+                Mutation::AddRole {
+                    member_id: 2,
+                    role_id: 9
+                },
+            ]
+        );
+        assert_eq!(
+            sut.summary().unwrap(),
+            "Nothing was changed, but I would have:\n\t\
+             change the nickname of <@2> to 'Bob'\n\tgive <@2> the <@&9> role"
+        );
+    }
+
+    #[tokio::test]
+    async fn dry_runs_take_confirmations_as_given() {
+        let mut inner = MockDiscordConnector::new();
+        inner.expect_add_reaction().never();
+        inner.expect_await_author_reaction().never();
+        let sut = DryRunConnector::new(inner, true);
+
+        sut.add_reaction(1, "✅").await.unwrap();
+        let confirmed = sut
+            .await_author_reaction(1, "✅", Duration::from_secs(60))
+            .await
+            .unwrap();
+
+        assert!(confirmed);
+        assert_eq!(sut.summary().unwrap(), "Nothing would have changed");
+    }
+
+    #[tokio::test]
+    async fn everything_passes_through_outside_of_dry_runs() {
+        // Arrange
+        let mut inner = MockDiscordConnector::new();
+        inner
+            .expect_change_member_nick_name()
+            .with(eq(2), eq("Bob"))
+            .times(1)
+            .returning(|_, _| Ok(()));
+        inner
+            .expect_send_reply()
+            .with(eq("'bob' is now 'Bob'"))
+            .times(1)
+            .returning(|_| Ok(()));
+        let sut = DryRunConnector::new(inner, false);
+
+        // Act
+        sut.change_member_nick_name(2, "Bob").await.unwrap();
+        sut.send_reply("'bob' is now 'Bob'").await.unwrap();
+        sut.send_summary().await.unwrap();
+
+        // Assert
+        assert!(sut.mutations().is_empty());
+        assert!(sut.summary().is_none());
+    }
+}
@@ -0,0 +1,283 @@
+//! Discord connectivity shared by the bots.
+//!
+//! This crate provides abstractions for interacting with Discord, including:
+//! - Error types for Discord connectivity issues
+//! - Traits defining Discord server interaction capabilities
+//! - Data structures for representing Discord server members, roles and attachments
+//!
+//! The crate is designed to be implementation-agnostic, allowing for different
+//! Discord client libraries to be used by implementing the `DiscordConnector` trait.
+//! A concrete implementation using the Serenity library is provided in the `serenity` module
+//! (behind the default `serenity` feature), and the `mock` feature exposes
+//! `MockDiscordConnector` and `ServerMemberBuilder` for testing code built on top of it.
+//! Calls that fail for reasons that may go away by themselves are retried according to a
+//! [`RetryPolicy`], and a [`DryRunConnector`] wraps another connector to show what a command
+//! would change without changing anything.
+
+use async_trait::async_trait;
+pub use attachment::Attachment;
+pub use dry_run::{DryRunConnector, Mutation};
+pub use retry::RetryPolicy;
+pub use server_member::ServerMember;
+#[cfg(any(test, feature = "mock"))]
+pub use server_member::ServerMemberBuilder;
+use std::time::Duration;
+use thiserror::Error;
+
+mod attachment;
+mod dry_run;
+mod retry;
+#[cfg(feature = "serenity")]
+pub mod serenity;
+mod server_member;
+
+/// Errors that can occur during Discord connectivity operations.
+///
+/// These errors represent various failure modes when interacting with Discord,
+/// such as being unable to find channels, members, or send messages.
+/// Only [`Error::RateLimited`] and [`Error::Unavailable`] are worth retrying, see
+/// [`Error::is_retryable`].
+#[derive(Error, Debug, Clone)]
+pub enum Error {
+    /// The command was not executed in a server channel
+    #[error("Not in a server channel")]
+    NotInServerChannel,
+    /// Unable to find the specified Discord channel
+    #[error("Cannot find channel")]
+    CannotFindChannel,
+    /// Unable to retrieve the members of a channel
+    #[error("Cannot find members of channel")]
+    CannotFindMembersOfChannel,
+    /// Failed to send a reply message
+    #[error("Cannot send reply")]
+    CannotSendReply,
+    /// Failed to send a message to a channel or a direct message to a user
+    #[error("Cannot send message")]
+    CannotSendMessage,
+    /// Failed to edit a previously sent message
+    #[error("Cannot edit message")]
+    CannotEditMessage,
+    /// Failed to react to a message
+    #[error("Cannot react to message")]
+    CannotReact,
+    /// Failed to retrieve the guild (server) information
+    #[error("Cannot get guild")]
+    CannotGetGuild,
+    /// Unable to find the specified role
+    #[error("Cannot find role")]
+    CannotFindRole,
+    #[error("Not enough permissions")]
+    NotEnoughPermissions,
+    /// Failed to download a file attached to the command
+    #[error("Cannot download attachment")]
+    CannotDownloadAttachment,
+    /// Discord kept rate limiting the bot
+    #[error("Discord is rate limiting the bot")]
+    RateLimited,
+    /// Discord had trouble handling the call, or couldn't be reached at all
+    #[error("Discord is unavailable")]
+    Unavailable,
+}
+
+impl Error {
+    /// Whether the call that failed may succeed when made again later.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Error::RateLimited | Error::Unavailable)
+    }
+
+    /// A name for the kind of failure that stays the same when the message changes, e.g. to
+    /// group failures by in metrics and error reports.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::NotInServerChannel => "NotInServerChannel",
+            Error::CannotFindChannel => "CannotFindChannel",
+            Error::CannotFindMembersOfChannel => "CannotFindMembersOfChannel",
+            Error::CannotSendReply => "CannotSendReply",
+            Error::CannotSendMessage => "CannotSendMessage",
+            Error::CannotEditMessage => "CannotEditMessage",
+            Error::CannotReact => "CannotReact",
+            Error::CannotGetGuild => "CannotGetGuild",
+            Error::CannotFindRole => "CannotFindRole",
+            Error::NotEnoughPermissions => "NotEnoughPermissions",
+            Error::CannotDownloadAttachment => "CannotDownloadAttachment",
+            Error::RateLimited => "RateLimited",
+            Error::Unavailable => "Unavailable",
+        }
+    }
+}
+
+/// Trait for abstracting Discord server interactions.
+///
+/// This trait defines the required functionality for connecting to
+/// and retrieving information from Discord servers. Every call is made on behalf of the
+/// command being handled, so "current" refers to its channel and guild.
+#[cfg_attr(any(test, feature = "mock"), mockall::automock)]
+#[async_trait]
+pub trait DiscordConnector {
+    /// Retrieves all members present in the current Discord channel.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<ServerMember>, Error>` - List of server members on success, or Discord error
+    async fn get_members_of_current_channel(&self) -> Result<Vec<ServerMember>, Error>;
+    /// Sends a reply to the person that invoked the prefix command
+    async fn send_reply(&self, message: &str) -> Result<(), Error>;
+    /// Sends a message to the current channel without replying to anyone.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<u64, Error>` - The ID of the sent message, which can be passed to `edit_message`
+    async fn send_message(&self, message: &str) -> Result<u64, Error>;
+    /// Sends a direct message to a user, e.g. to play a game privately.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<u64, Error>` - The ID of the sent message
+    async fn send_direct_message(&self, user_id: u64, message: &str) -> Result<u64, Error>;
+    /// Replaces the content of a message the bot sent to the current channel.
+    async fn edit_message(&self, message_id: u64, new_content: &str) -> Result<(), Error>;
+    /// Replies to the person that invoked the command with an embed.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<u64, Error>` - The ID of the sent message, e.g. to collect reactions to it
+    async fn send_embed(&self, title: &str, description: &str) -> Result<u64, Error>;
+    /// Downloads a file attached to the command being handled.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<u8>, Error>` - The contents of the file
+    async fn download_attachment(&self, attachment: &Attachment) -> Result<Vec<u8>, Error>;
+    /// Reacts to a message in the current channel with a unicode emoji.
+    async fn add_reaction(&self, message_id: u64, emoji: &str) -> Result<(), Error>;
+    /// Waits for the person that invoked the command to react to a message with a unicode
+    /// emoji, ignoring everyone else's reactions.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool, Error>` - Whether they reacted before `timeout` ran out
+    async fn await_author_reaction(
+        &self,
+        message_id: u64,
+        emoji: &str,
+        timeout: Duration,
+    ) -> Result<bool, Error>;
+    /// Looks up a role in the current guild by its name.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the role to find
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Box<dyn Role>, Error>` - The role if found, or an error otherwise
+    async fn get_role_by_name(&self, name: &str) -> Result<Box<dyn Role>, Error>;
+    /// Gives a member of the current guild a role, as found by `get_role_by_name`.
+    async fn add_role_to_member(&self, member_id: u64, role_id: u64) -> Result<(), Error>;
+    /// Takes a role away from a member of the current guild.
+    async fn remove_role_from_member(&self, member_id: u64, role_id: u64) -> Result<(), Error>;
+    /// Whether the member who invoked the command has a role, as found by `get_role_by_name`.
+    async fn author_has_role(&self, role_id: u64) -> Result<bool, Error>;
+
+    async fn change_member_nick_name<'connector, 'name>(
+        &'connector self,
+        member_id: u64,
+        new_nick_name: &'name str,
+    ) -> Result<(), Error>;
+
+    async fn get_guild_owner_id(&self) -> Result<u64, Error>;
+    /// The ID of the current guild, or `NotInServerChannel` outside of one.
+    async fn get_guild_id(&self) -> Result<u64, Error>;
+}
+
+/// Represents an entity that can be mentioned in Discord messages.
+///
+/// This trait is implemented by types that can be referenced in Discord
+/// messages using mentions (like @user or @role).
+pub trait Mentionable: Send + Sync + 'static {
+    /// Returns the string representation of a mention for this entity.
+    ///
+    /// # Returns
+    ///
+    /// The formatted mention string that can be included in Discord messages
+    fn mention(&self) -> String;
+}
+
+/// Represents a Discord role.
+///
+/// This trait extends the `Mentionable` trait to specifically identify
+/// Discord role entities. Implementations should represent Discord roles
+/// with their associated permissions and properties.
+pub trait Role: Mentionable {
+    /// Returns the role's unique identifier.
+    fn id(&self) -> u64;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mockall::predicate::eq;
+
+    /// Stand-in for game code that only knows about the trait.
+    async fn announce_winner(
+        discord: &impl DiscordConnector,
+        winner: &ServerMember,
+    ) -> Result<(), Error> {
+        let message_id = discord.send_message("And the winner is...").await?;
+        discord
+            .edit_message(
+                message_id,
+                &format!("And the winner is {}!", winner.mention),
+            )
+            .await?;
+        discord
+            .send_direct_message(winner.id, "Congratulations!")
+            .await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn mock_records_send_edit_and_direct_messages() {
+        let winner = ServerMemberBuilder::new()
+            .id(42)
+            .user_name("winner")
+            .build();
+        let mut discord = MockDiscordConnector::new();
+        discord
+            .expect_send_message()
+            .with(eq("And the winner is..."))
+            .times(1)
+            .returning(|_| Ok(7));
+        discord
+            .expect_edit_message()
+            .with(eq(7), eq("And the winner is <@42>!"))
+            .times(1)
+            .returning(|_, _| Ok(()));
+        discord
+            .expect_send_direct_message()
+            .with(eq(42), eq("Congratulations!"))
+            .times(1)
+            .returning(|_, _| Ok(8));
+
+        announce_winner(&discord, &winner).await.unwrap();
+    }
+
+    #[test]
+    fn error_kinds_match_the_variant_names() {
+        assert_eq!(Error::CannotSendMessage.kind(), "CannotSendMessage");
+        assert_eq!(Error::RateLimited.kind(), "RateLimited");
+        assert_eq!(Error::NotEnoughPermissions.kind(), "NotEnoughPermissions");
+    }
+
+    #[tokio::test]
+    async fn errors_from_the_connector_are_passed_on() {
+        let winner = ServerMemberBuilder::new().id(42).build();
+        let mut discord = MockDiscordConnector::new();
+        discord
+            .expect_send_message()
+            .returning(|_| Err(Error::CannotSendMessage));
+
+        let result = announce_winner(&discord, &winner).await;
+        assert!(matches!(result, Err(Error::CannotSendMessage)));
+    }
+}
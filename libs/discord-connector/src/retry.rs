@@ -0,0 +1,196 @@
+//! Retrying Discord API calls that failed for reasons that may go away by themselves.
+
+use crate::Error;
+use std::time::Duration;
+use tracing::warn;
+
+/// How often and how patiently failed Discord API calls are retried.
+///
+/// Only errors for which [`Error::is_retryable`] holds are retried, with exponential backoff.
+/// Serenity already waits out the rate limits Discord announces in its response headers, so
+/// calls that get rate limited anyway wait for the longest backoff before trying again.
+///
+/// With the `serde` feature, policies can be read from config files, where the backoffs are
+/// given in milliseconds and anything left out is the default.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(default)
+)]
+pub struct RetryPolicy {
+    /// How many times a call is made at most, the first one included
+    pub max_attempts: u32,
+    /// How long to wait before the first retry, doubled for every retry after it
+    #[cfg_attr(
+        feature = "serde",
+        serde(rename = "initial_backoff_ms", with = "millis")
+    )]
+    pub initial_backoff: Duration,
+    /// The longest to wait between two attempts
+    #[cfg_attr(feature = "serde", serde(rename = "max_backoff_ms", with = "millis"))]
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(8),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Makes every call just once, for when failures should be reported straight away.
+    pub const NEVER: Self = Self {
+        max_attempts: 1,
+        initial_backoff: Duration::ZERO,
+        max_backoff: Duration::ZERO,
+    };
+
+    /// How long to wait after the `attempt`th failed attempt, counting from 1.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let doublings = attempt.saturating_sub(1).min(31);
+        self.initial_backoff
+            .saturating_mul(1 << doublings)
+            .min(self.max_backoff)
+    }
+
+    /// Calls `call` until it succeeds, fails in a way retrying can't fix, or runs out of
+    /// attempts, passing on the last error.
+    pub async fn run<T, F, Fut>(&self, operation: &str, mut call: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match call().await {
+                Err(err) if err.is_retryable() && attempt < self.max_attempts => {
+                    let wait = match err {
+                        Error::RateLimited => self.max_backoff,
+                        _ => self.backoff(attempt),
+                    };
+                    warn!(
+                        "Discord API call {} failed ({}), retrying in {:?}",
+                        operation, err, wait
+                    );
+                    tokio::time::sleep(wait).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Durations as whole milliseconds.
+#[cfg(feature = "serde")]
+mod millis {
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::time::Duration;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u64(duration.as_millis() as u64)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        Ok(Duration::from_millis(u64::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use tokio::time::Instant;
+
+    /// Runs a call that fails with each of `errors` in turn and then succeeds, returning its
+    /// result and how many attempts were made.
+    async fn run_failing(policy: RetryPolicy, errors: Vec<Error>) -> (Result<(), Error>, u32) {
+        let attempts = Cell::new(0);
+        let mut errors = errors.into_iter();
+        let result = policy
+            .run("test", || {
+                attempts.set(attempts.get() + 1);
+                std::future::ready(errors.next().map_or(Ok(()), Err))
+            })
+            .await;
+        (result, attempts.get())
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_the_maximum() {
+        let policy = RetryPolicy {
+            max_attempts: 10,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(5),
+        };
+
+        let backoffs: Vec<u64> = (1..=5)
+            .map(|attempt| policy.backoff(attempt).as_secs())
+            .collect();
+
+        assert_eq!(backoffs, [1, 2, 4, 5, 5]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_transient_failures_until_they_succeed() {
+        // Arrange
+        let errors = vec![Error::Unavailable, Error::Unavailable];
+        let started = Instant::now();
+
+        // Act
+        let (result, attempts) = run_failing(RetryPolicy::default(), errors).await;
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(attempts, 3);
+        assert_eq!(started.elapsed(), Duration::from_millis(500 + 1000));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gives_up_after_the_last_attempt() {
+        let errors = vec![Error::Unavailable; 5];
+
+        let (result, attempts) = run_failing(RetryPolicy::default(), errors).await;
+
+        assert!(matches!(result, Err(Error::Unavailable)));
+        assert_eq!(attempts, 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn does_not_retry_permanent_failures() {
+        let errors = vec![Error::NotEnoughPermissions];
+
+        let (result, attempts) = run_failing(RetryPolicy::default(), errors).await;
+
+        assert!(matches!(result, Err(Error::NotEnoughPermissions)));
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waits_longest_when_rate_limited() {
+        // Arrange
+        let policy = RetryPolicy::default();
+        let started = Instant::now();
+
+        // Act
+        let (result, attempts) = run_failing(policy, vec![Error::RateLimited]).await;
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(attempts, 2);
+        assert_eq!(started.elapsed(), policy.max_backoff);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn never_retries_anything() {
+        let (result, attempts) = run_failing(RetryPolicy::NEVER, vec![Error::Unavailable]).await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+}
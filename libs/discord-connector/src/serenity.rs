@@ -0,0 +1,347 @@
+//! Serenity-based implementation of Discord connectivity.
+//!
+//! This module provides the concrete implementation of the Discord connector
+//! trait using the Serenity Discord library, driven by a Poise command context.
+//! Discord API calls that fail for reasons that may go away by themselves are retried
+//! according to a [`RetryPolicy`], and calls that fail for good are counted in the
+//! `discord_api_errors_total` metric.
+
+use crate::Error::{
+    CannotDownloadAttachment, CannotEditMessage, CannotFindChannel, CannotFindMembersOfChannel,
+    CannotFindRole, CannotGetGuild, CannotReact, CannotSendMessage, CannotSendReply,
+    NotEnoughPermissions, NotInServerChannel, RateLimited, Unavailable,
+};
+use crate::{Attachment, DiscordConnector, Error, Mentionable, RetryPolicy, Role, ServerMember};
+use async_trait::async_trait;
+use poise::serenity_prelude as serenity;
+use poise::serenity_prelude::Mentionable as poise_Mentionable;
+use poise::serenity_prelude::{
+    AttachmentId, CreateEmbed, CreateMessage, EditMember, EditMessage, MessageId,
+    ReactionCollector, ReactionType, RoleId, UserId,
+};
+use std::time::Duration;
+use tracing::info;
+
+/// Discord connector implementation using Serenity library.
+///
+/// Provides functionality to interact with Discord servers using
+/// the context of the Poise command being handled. `U` and `E` are the
+/// bot's own framework data and error types.
+pub struct SerenityDiscordConnector<'a, U, E> {
+    context: poise::Context<'a, U, E>,
+    retry_policy: RetryPolicy,
+}
+
+impl<'a, U, E> SerenityDiscordConnector<'a, U, E> {
+    /// Creates a new SerenityDiscordConnector instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - Poise command context for Discord interactions
+    pub fn new(context: poise::Context<'a, U, E>) -> Self {
+        Self {
+            context,
+            retry_policy: RetryPolicy::default(),
+        }
+    }
+
+    /// Retries failed Discord API calls according to `retry_policy` rather than the default one.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Makes a Discord API call, retrying it according to the retry policy. Failures that
+    /// retrying can't fix become `permanent`, and the final failure is counted in the metrics.
+    async fn call<T, F, Fut>(
+        &self,
+        operation: &'static str,
+        permanent: Error,
+        mut request: F,
+    ) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, serenity::Error>>,
+    {
+        self.retry_policy
+            .run(operation, || {
+                let response = request();
+                let permanent = permanent.clone();
+                async move { response.await.map_err(|err| classify(&err, permanent)) }
+            })
+            .await
+            .map_err(|err| count_api_error(operation, err))
+    }
+
+    /// Finds a file attached to the command, either as an option of the slash command or to
+    /// the message of the prefix command.
+    fn command_attachment(&self, id: u64) -> Option<&'a serenity::Attachment> {
+        match self.context {
+            poise::Context::Application(ctx) => ctx
+                .interaction
+                .data
+                .resolved
+                .attachments
+                .get(&AttachmentId::new(id)),
+            poise::Context::Prefix(ctx) => ctx
+                .msg
+                .attachments
+                .iter()
+                .find(|attachment| attachment.id.get() == id),
+        }
+    }
+}
+
+#[async_trait]
+impl<U, E> DiscordConnector for SerenityDiscordConnector<'_, U, E>
+where
+    U: Send + Sync + 'static,
+    E: Send + Sync + 'static,
+{
+    async fn get_members_of_current_channel(&self) -> Result<Vec<ServerMember>, Error> {
+        let ctx = &self.context;
+        let channel = self
+            .call("get_members_of_current_channel", CannotFindChannel, || {
+                ctx.channel_id().to_channel(ctx)
+            })
+            .await?;
+        let Some(channel) = channel.guild() else {
+            return Err(NotInServerChannel);
+        };
+        let Ok(members) = channel.members(ctx) else {
+            return Err(CannotFindMembersOfChannel);
+        };
+        let members: Vec<ServerMember> =
+            members.iter().map(|member| member.clone().into()).collect();
+        info!("Found {} members in current channel", members.len());
+        Ok(members)
+    }
+
+    async fn send_reply(&self, message: &str) -> Result<(), Error> {
+        let ctx = &self.context;
+        self.call("send_reply", CannotSendReply, || ctx.reply(message))
+            .await?;
+        Ok(())
+    }
+
+    async fn send_message(&self, message: &str) -> Result<u64, Error> {
+        let ctx = &self.context;
+        let sent = self
+            .call("send_message", CannotSendMessage, || {
+                ctx.channel_id().say(ctx, message)
+            })
+            .await?;
+        Ok(sent.id.get())
+    }
+
+    async fn send_direct_message(&self, user_id: u64, message: &str) -> Result<u64, Error> {
+        let ctx = &self.context;
+        let builder = CreateMessage::new().content(message);
+        let sent = self
+            .call("send_direct_message", CannotSendMessage, || {
+                UserId::new(user_id).direct_message(ctx, builder.clone())
+            })
+            .await?;
+        Ok(sent.id.get())
+    }
+
+    async fn edit_message(&self, message_id: u64, new_content: &str) -> Result<(), Error> {
+        let ctx = &self.context;
+        let builder = EditMessage::new().content(new_content);
+        self.call("edit_message", CannotEditMessage, || {
+            ctx.channel_id()
+                .edit_message(ctx, MessageId::new(message_id), builder.clone())
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn send_embed(&self, title: &str, description: &str) -> Result<u64, Error> {
+        let ctx = &self.context;
+        let embed = CreateEmbed::new().title(title).description(description);
+        let reply = poise::CreateReply::default().embed(embed);
+        let reply = &reply;
+        self.call("send_embed", CannotSendReply, move || async move {
+            let handle = ctx.send(reply.clone()).await?;
+            let sent = handle.message().await?;
+            Ok::<_, serenity::Error>(sent.id.get())
+        })
+        .await
+    }
+
+    async fn download_attachment(&self, attachment: &Attachment) -> Result<Vec<u8>, Error> {
+        let Some(attachment) = self.command_attachment(attachment.id) else {
+            return Err(CannotDownloadAttachment);
+        };
+        self.call("download_attachment", CannotDownloadAttachment, || {
+            attachment.download()
+        })
+        .await
+    }
+
+    async fn add_reaction(&self, message_id: u64, emoji: &str) -> Result<(), Error> {
+        let ctx = &self.context;
+        let reaction = ReactionType::Unicode(emoji.to_string());
+        self.call("add_reaction", CannotReact, || {
+            ctx.http()
+                .create_reaction(ctx.channel_id(), MessageId::new(message_id), &reaction)
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn await_author_reaction(
+        &self,
+        message_id: u64,
+        emoji: &str,
+        timeout: Duration,
+    ) -> Result<bool, Error> {
+        let ctx = &self.context;
+        let emoji = emoji.to_string();
+        let reaction = ReactionCollector::new(ctx.serenity_context())
+            .message_id(MessageId::new(message_id))
+            .author_id(ctx.author().id)
+            .filter(move |reaction| reaction.emoji.unicode_eq(&emoji))
+            .timeout(timeout)
+            .await;
+        Ok(reaction.is_some())
+    }
+
+    async fn get_role_by_name(&self, name: &str) -> Result<Box<dyn Role>, Error> {
+        let Some(guild) = self.context.guild() else {
+            return Err(CannotGetGuild);
+        };
+        let Some(role) = guild.role_by_name(name) else {
+            return Err(CannotFindRole);
+        };
+        Ok(Box::new(role.clone()))
+    }
+
+    async fn add_role_to_member(&self, member_id: u64, role_id: u64) -> Result<(), Error> {
+        let Some(guild) = self.context.guild_id() else {
+            return Err(CannotGetGuild);
+        };
+        let http = self.context.http();
+        self.call("add_role_to_member", NotEnoughPermissions, || {
+            http.add_member_role(guild, UserId::new(member_id), RoleId::new(role_id), None)
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn remove_role_from_member(&self, member_id: u64, role_id: u64) -> Result<(), Error> {
+        let Some(guild) = self.context.guild_id() else {
+            return Err(CannotGetGuild);
+        };
+        let http = self.context.http();
+        self.call("remove_role_from_member", NotEnoughPermissions, || {
+            http.remove_member_role(guild, UserId::new(member_id), RoleId::new(role_id), None)
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn author_has_role(&self, role_id: u64) -> Result<bool, Error> {
+        let Some(member) = self.context.author_member().await else {
+            return Err(NotInServerChannel);
+        };
+        Ok(member.roles.contains(&RoleId::new(role_id)))
+    }
+
+    async fn change_member_nick_name(
+        &self,
+        member_id: u64,
+        new_nick_name: &str,
+    ) -> Result<(), Error> {
+        let Some(guild) = self.context.guild_id() else {
+            return Err(CannotGetGuild);
+        };
+        let builder = EditMember::new().nickname(new_nick_name);
+        self.call("change_member_nick_name", NotEnoughPermissions, || {
+            guild.edit_member(&self.context, member_id, builder.clone())
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn get_guild_owner_id(&self) -> Result<u64, Error> {
+        let Some(guild) = self.context.guild() else {
+            return Err(CannotGetGuild);
+        };
+        Ok(guild.owner_id.get())
+    }
+
+    async fn get_guild_id(&self) -> Result<u64, Error> {
+        let Some(guild) = self.context.guild_id() else {
+            return Err(NotInServerChannel);
+        };
+        Ok(guild.get())
+    }
+}
+
+/// Counts a failed Discord API call in the `discord_api_errors_total` metric, by operation and
+/// error, and passes the error on.
+pub fn count_api_error(operation: &'static str, error: Error) -> Error {
+    metrics::counter!(
+        "discord_api_errors_total",
+        "operation" => operation,
+        "error" => error.kind(),
+    )
+    .increment(1);
+    error
+}
+
+/// Tells failures that may go away by themselves, because Discord is rate limiting the bot,
+/// having trouble or can't be reached, apart from `permanent` ones.
+fn classify(err: &serenity::Error, permanent: Error) -> Error {
+    let serenity::Error::Http(http_error) = err else {
+        return permanent;
+    };
+    match http_error {
+        serenity::HttpError::UnsuccessfulRequest(response) => {
+            if response.status_code.as_u16() == 429 {
+                RateLimited
+            } else if response.status_code.is_server_error() {
+                Unavailable
+            } else {
+                permanent
+            }
+        }
+        serenity::HttpError::Request(_) => Unavailable,
+        _ => permanent,
+    }
+}
+
+impl Mentionable for serenity::Role {
+    fn mention(&self) -> String {
+        <Self as serenity::Mentionable>::mention(self).to_string()
+    }
+}
+
+impl Role for serenity::Role {
+    fn id(&self) -> u64 {
+        self.id.get()
+    }
+}
+
+impl From<serenity::Attachment> for Attachment {
+    fn from(attachment: serenity::Attachment) -> Self {
+        Attachment {
+            id: attachment.id.get(),
+            filename: attachment.filename,
+            size: u64::from(attachment.size),
+        }
+    }
+}
+
+impl From<serenity::Member> for ServerMember {
+    fn from(member: serenity::Member) -> Self {
+        ServerMember {
+            id: member.user.id.get(),
+            nick_name: member.nick.clone(),
+            user_name: member.user.name.clone(),
+            is_bot: member.user.bot,
+            mention: member.mention().to_string(),
+        }
+    }
+}
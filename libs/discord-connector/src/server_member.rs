@@ -5,14 +5,15 @@
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub struct ServerMember {
     /// Discord user's unique identifier
-    pub(crate) id: u64,
+    pub id: u64,
     /// Optional nickname set for the user in the server
-    pub(crate) nick_name: Option<String>,
+    pub nick_name: Option<String>,
     /// Discord username of the member
-    pub(crate) user_name: String,
+    pub user_name: String,
     /// Whether the member is a bot
-    pub(crate) is_bot: bool,
-    pub(crate) mention: String,
+    pub is_bot: bool,
+    /// Text that mentions the member in a message, e.g. `<@123>`
+    pub mention: String,
 }
 
 /// Builder for ServerMember instances.
@@ -20,7 +21,7 @@ pub struct ServerMember {
 /// This provides a fluent interface for constructing ServerMember objects,
 /// making test code more readable and flexible.
 #[derive(Debug, Default)]
-#[cfg(test)]
+#[cfg(any(test, feature = "mock"))]
 pub struct ServerMemberBuilder {
     id: u64,
     nick_name: Option<String>,
@@ -29,7 +30,7 @@ pub struct ServerMemberBuilder {
     mention: String,
 }
 
-#[cfg(test)]
+#[cfg(any(test, feature = "mock"))]
 impl ServerMemberBuilder {
     /// Creates a new builder with default values.
     pub fn new() -> Self {
@@ -0,0 +1,253 @@
+use crate::{FlagState, FlagStore, StoreError};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+/// Prefix of the environment variables that set a flag, e.g. `FEATURE_LIVE_TABLES=25%` for
+/// `live-tables`.
+pub const ENV_PREFIX: &str = "FEATURE_";
+
+/// Error for a flag that can't be toggled.
+#[derive(Debug, thiserror::Error)]
+pub enum FlagError {
+    /// Flag names are lowercase ASCII letters, digits and dashes.
+    #[error("Invalid flag name '{0}', use lowercase letters, digits and dashes")]
+    InvalidName(String),
+    #[error(transparent)]
+    Store(#[from] StoreError),
+}
+
+/// Collects the flags of a service and where their states come from.
+///
+/// A flag's state is resolved from, in increasing priority: the default it was registered with,
+/// its `FEATURE_*` environment variable, and the store, which holds the flags toggled at runtime.
+#[derive(Default)]
+pub struct FeatureFlagsBuilder {
+    defaults: BTreeMap<String, FlagState>,
+    env: Vec<(String, String)>,
+    store: Option<Arc<dyn FlagStore>>,
+}
+
+impl FeatureFlagsBuilder {
+    /// Registers a flag with its default state.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name isn't lowercase letters, digits and dashes.
+    pub fn flag(mut self, name: &str, default: FlagState) -> Self {
+        assert!(is_valid_name(name), "invalid feature flag name '{name}'");
+        self.defaults.insert(name.to_string(), default);
+        self
+    }
+
+    /// Reads overrides for the registered flags from the process environment.
+    pub fn from_env(self) -> Self {
+        self.env_vars(std::env::vars())
+    }
+
+    /// Reads overrides for the registered flags from `vars`, see [`ENV_PREFIX`].
+    pub fn env_vars(mut self, vars: impl IntoIterator<Item = (String, String)>) -> Self {
+        self.env.extend(vars);
+        self
+    }
+
+    /// Persists toggled flags in `store`. Without a store, toggles only last until the process
+    /// exits.
+    pub fn store(mut self, store: impl FlagStore + 'static) -> Self {
+        self.store = Some(Arc::new(store));
+        self
+    }
+
+    /// Resolves the defaults and environment overrides. Call [`FeatureFlags::reload`] to apply
+    /// the stored states too.
+    pub fn build(self) -> FeatureFlags {
+        let mut base = self.defaults;
+        for (var, value) in self.env {
+            let Some(name) = var.strip_prefix(ENV_PREFIX) else {
+                continue;
+            };
+            let name = name.to_ascii_lowercase().replace('_', "-");
+            let Some(state) = base.get_mut(&name) else {
+                continue;
+            };
+            match value.parse() {
+                Ok(parsed) => *state = parsed,
+                Err(err) => tracing::warn!(%var, "Ignoring feature flag override: {err}"),
+            }
+        }
+        FeatureFlags {
+            inner: Arc::new(Inner {
+                current: RwLock::new(base.clone()),
+                base,
+                store: self.store,
+            }),
+        }
+    }
+}
+
+struct Inner {
+    base: BTreeMap<String, FlagState>,
+    current: RwLock<BTreeMap<String, FlagState>>,
+    store: Option<Arc<dyn FlagStore>>,
+}
+
+/// The feature flags of a service, cheap to clone and shared by every clone.
+///
+/// Flags that were never registered or toggled are off.
+#[derive(Clone)]
+pub struct FeatureFlags {
+    inner: Arc<Inner>,
+}
+
+impl FeatureFlags {
+    pub fn builder() -> FeatureFlagsBuilder {
+        FeatureFlagsBuilder::default()
+    }
+
+    /// Whether `name` is on for everyone.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.state(name).is_some_and(|state| state.is_on())
+    }
+
+    /// Whether `name` is on for `key`, e.g. a user or server id, taking rollouts into account.
+    pub fn is_enabled_for(&self, name: &str, key: &str) -> bool {
+        self.state(name)
+            .is_some_and(|state| state.is_on_for(name, key))
+    }
+
+    pub fn state(&self, name: &str) -> Option<FlagState> {
+        self.read().get(name).copied()
+    }
+
+    /// Every known flag and its state.
+    pub fn all(&self) -> BTreeMap<String, FlagState> {
+        self.read().clone()
+    }
+
+    /// Toggles a flag, storing the new state first so that a failed write changes nothing.
+    pub async fn set(&self, name: &str, state: FlagState) -> Result<(), FlagError> {
+        if !is_valid_name(name) {
+            return Err(FlagError::InvalidName(name.to_string()));
+        }
+        if let Some(store) = &self.inner.store {
+            store.save(name, state).await?;
+        }
+        tracing::info!(flag = %name, %state, "Feature flag changed");
+        self.write().insert(name.to_string(), state);
+        Ok(())
+    }
+
+    /// Reapplies the stored states on top of the defaults and environment overrides, picking up
+    /// flags toggled by other instances.
+    pub async fn reload(&self) -> Result<(), StoreError> {
+        let Some(store) = &self.inner.store else {
+            return Ok(());
+        };
+        let stored = store.load().await?;
+        let mut flags = self.inner.base.clone();
+        flags.extend(stored);
+        *self.write() = flags;
+        Ok(())
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, BTreeMap<String, FlagState>> {
+        self.inner
+            .current
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, BTreeMap<String, FlagState>> {
+        self.inner
+            .current
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| matches!(b, b'a'..=b'z' | b'0'..=b'9' | b'-'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::sync::Mutex;
+
+    #[derive(Default, Clone)]
+    struct MemoryStore(Arc<Mutex<Vec<(String, FlagState)>>>);
+
+    #[async_trait]
+    impl FlagStore for MemoryStore {
+        async fn load(&self) -> Result<Vec<(String, FlagState)>, StoreError> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        async fn save(&self, name: &str, state: FlagState) -> Result<(), StoreError> {
+            self.0.lock().unwrap().push((name.to_string(), state));
+            Ok(())
+        }
+    }
+
+    fn env(vars: &[(&str, &str)]) -> Vec<(String, String)> {
+        vars.iter()
+            .map(|(var, value)| (var.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn environment_overrides_registered_defaults() {
+        let flags = FeatureFlags::builder()
+            .flag("live-tables", FlagState::Off)
+            .flag("new-mode", FlagState::On)
+            .flag("beta", FlagState::Off)
+            .env_vars(env(&[
+                ("FEATURE_LIVE_TABLES", "on"),
+                ("FEATURE_NEW_MODE", "nonsense"),
+                ("FEATURE_UNKNOWN", "on"),
+                ("PATH", "/usr/bin"),
+            ]))
+            .build();
+
+        assert!(flags.is_enabled("live-tables"));
+        assert!(flags.is_enabled("new-mode"));
+        assert!(!flags.is_enabled("beta"));
+        assert_eq!(flags.state("unknown"), None);
+        assert!(!flags.is_enabled("unknown"));
+    }
+
+    #[tokio::test]
+    async fn toggles_are_stored_and_win_over_defaults() {
+        let store = MemoryStore::default();
+        let flags = FeatureFlags::builder()
+            .flag("live-tables", FlagState::Off)
+            .env_vars(env(&[("FEATURE_LIVE_TABLES", "50%")]))
+            .store(store.clone())
+            .build();
+
+        flags.set("live-tables", FlagState::On).await.unwrap();
+        assert!(flags.is_enabled("live-tables"));
+
+        // Another instance picks the toggle up from the store
+        let other = FeatureFlags::builder()
+            .flag("live-tables", FlagState::Off)
+            .store(store)
+            .build();
+        assert!(!other.is_enabled("live-tables"));
+        other.reload().await.unwrap();
+        assert!(other.is_enabled("live-tables"));
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_names() {
+        let flags = FeatureFlags::builder().build();
+
+        let result = flags.set("Live Tables", FlagState::On).await;
+
+        assert!(matches!(result, Err(FlagError::InvalidName(_))));
+        assert!(flags.all().is_empty());
+    }
+}
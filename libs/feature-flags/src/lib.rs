@@ -0,0 +1,21 @@
+//! Feature flags shared by the web services, so that risky features can be rolled out gradually
+//! and switched off without a redeploy.
+//!
+//! Flags are registered with a default state, can be overridden with `FEATURE_*` environment
+//! variables, and toggled at runtime through an admin router. Toggles are persisted in a
+//! [`FlagStore`]; with the `sea-orm` feature, [`SeaOrmFlagStore`] keeps them in the service's
+//! database.
+
+mod flags;
+mod router;
+#[cfg(feature = "sea-orm")]
+mod sea_orm;
+mod state;
+mod store;
+
+pub use flags::{ENV_PREFIX, FeatureFlags, FeatureFlagsBuilder, FlagError};
+pub use router::{FlagJson, SetFlagRequest};
+#[cfg(feature = "sea-orm")]
+pub use sea_orm::{SeaOrmFlagStore, entity};
+pub use state::{FlagState, InvalidFlagState};
+pub use store::{FlagStore, StoreError};
@@ -0,0 +1,127 @@
+use crate::{FeatureFlags, FlagError, FlagState};
+use api_error::ApiError;
+use axum::extract::rejection::JsonRejection;
+use axum::extract::{Path, State};
+use axum::routing::{get, put};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+
+/// A flag as shown by the admin endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FlagJson {
+    pub name: String,
+    pub state: FlagState,
+}
+
+/// Body of a request toggling a flag.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SetFlagRequest {
+    pub state: FlagState,
+}
+
+impl From<FlagError> for ApiError {
+    fn from(err: FlagError) -> Self {
+        match err {
+            FlagError::InvalidName(_) => ApiError::bad_request(err.to_string()),
+            FlagError::Store(_) => {
+                ApiError::internal("Failed to store the feature flag").with_source(err)
+            }
+        }
+    }
+}
+
+impl FeatureFlags {
+    /// Routes for viewing and toggling flags at runtime: `GET /` lists every flag and
+    /// `PUT /{name}` with `{"state": "on" | "off" | "25%"}` sets one.
+    ///
+    /// These change the behavior of the whole service, so only mount them behind admin
+    /// authentication.
+    pub fn admin_router(&self) -> Router {
+        Router::new()
+            .route("/", get(list_flags))
+            .route("/{name}", put(set_flag))
+            .with_state(self.clone())
+    }
+}
+
+async fn list_flags(State(flags): State<FeatureFlags>) -> Json<Vec<FlagJson>> {
+    Json(
+        flags
+            .all()
+            .into_iter()
+            .map(|(name, state)| FlagJson { name, state })
+            .collect(),
+    )
+}
+
+async fn set_flag(
+    State(flags): State<FeatureFlags>,
+    Path(name): Path<String>,
+    request: Result<Json<SetFlagRequest>, JsonRejection>,
+) -> Result<Json<FlagJson>, ApiError> {
+    let Json(request) = request?;
+    flags.set(&name, request.state).await?;
+    Ok(Json(FlagJson {
+        name,
+        state: request.state,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::http::{Request, StatusCode};
+    use tower::ServiceExt;
+
+    async fn send(flags: &FeatureFlags, request: Request<Body>) -> (StatusCode, serde_json::Value) {
+        let response = flags.admin_router().oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    fn put_state(name: &str, body: &str) -> Request<Body> {
+        Request::put(format!("/{name}"))
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn lists_and_toggles_flags() {
+        let flags = FeatureFlags::builder()
+            .flag("live-tables", FlagState::Off)
+            .build();
+
+        let (status, body) = send(&flags, put_state("live-tables", r#"{"state":"25%"}"#)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            serde_json::json!({"name": "live-tables", "state": "25%"})
+        );
+
+        let (status, body) = send(&flags, Request::get("/").body(Body::empty()).unwrap()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            serde_json::json!([{"name": "live-tables", "state": "25%"}])
+        );
+        assert_eq!(flags.state("live-tables"), Some(FlagState::Rollout(25)));
+    }
+
+    #[tokio::test]
+    async fn rejects_invalid_toggles() {
+        let flags = FeatureFlags::builder().build();
+
+        let (status, _) = send(&flags, put_state("live-tables", r#"{"state":"maybe"}"#)).await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, body) = send(&flags, put_state("Live_Tables", r#"{"state":"on"}"#)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["status"], 400);
+        assert!(flags.all().is_empty());
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{FlagState, FlagStore, StoreError};
+use async_trait::async_trait;
+use sea_orm::sea_query::OnConflict;
+use sea_orm::{DatabaseConnection, EntityTrait, Set};
+
+/// The `feature_flags` table: one row per toggled flag, with its state as text.
+pub mod entity {
+    use sea_orm::entity::prelude::*;
+
+    #[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+    #[sea_orm(table_name = "feature_flags")]
+    pub struct Model {
+        #[sea_orm(primary_key, auto_increment = false)]
+        pub name: String,
+        pub state: String,
+    }
+
+    #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+    pub enum Relation {}
+
+    impl ActiveModelBehavior for ActiveModel {}
+}
+
+/// Stores flags in the `feature_flags` table. The service's migrations must create it.
+#[derive(Debug, Clone)]
+pub struct SeaOrmFlagStore {
+    db: DatabaseConnection,
+}
+
+impl SeaOrmFlagStore {
+    pub fn new(db: DatabaseConnection) -> Self {
+        Self { db }
+    }
+}
+
+#[async_trait]
+impl FlagStore for SeaOrmFlagStore {
+    async fn load(&self) -> Result<Vec<(String, FlagState)>, StoreError> {
+        let rows = entity::Entity::find()
+            .all(&self.db)
+            .await
+            .map_err(StoreError::new)?;
+        let mut flags = Vec::with_capacity(rows.len());
+        for row in rows {
+            match row.state.parse() {
+                Ok(state) => flags.push((row.name, state)),
+                Err(err) => tracing::warn!(flag = %row.name, "Ignoring stored flag: {err}"),
+            }
+        }
+        Ok(flags)
+    }
+
+    async fn save(&self, name: &str, state: FlagState) -> Result<(), StoreError> {
+        let row = entity::ActiveModel {
+            name: Set(name.to_string()),
+            state: Set(state.to_string()),
+        };
+        entity::Entity::insert(row)
+            .on_conflict(
+                OnConflict::column(entity::Column::Name)
+                    .update_column(entity::Column::State)
+                    .to_owned(),
+            )
+            .exec(&self.db)
+            .await
+            .map_err(StoreError::new)?;
+        Ok(())
+    }
+}
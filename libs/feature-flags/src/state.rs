@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Whether a flag is on, written as `on`, `off` or a rollout percentage like `25%`.
+///
+/// The same text is used in environment variables, the database and the admin endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum FlagState {
+    Off,
+    On,
+    /// On for roughly this percentage of keys, e.g. users or servers. A key always gets the same
+    /// answer for a flag, and raising the percentage only ever adds keys.
+    Rollout(u8),
+}
+
+impl FlagState {
+    /// Whether the flag is on for everyone.
+    pub fn is_on(&self) -> bool {
+        match self {
+            FlagState::Off => false,
+            FlagState::On => true,
+            FlagState::Rollout(percentage) => *percentage >= 100,
+        }
+    }
+
+    /// Whether the flag named `flag` is on for `key`.
+    pub fn is_on_for(&self, flag: &str, key: &str) -> bool {
+        match self {
+            FlagState::Off => false,
+            FlagState::On => true,
+            FlagState::Rollout(percentage) => bucket(flag, key) < u64::from(*percentage),
+        }
+    }
+}
+
+/// Places `key` in one of 100 buckets. The flag name is part of the hash so that a key isn't in
+/// the first few percent of every rollout.
+fn bucket(flag: &str, key: &str) -> u64 {
+    // FNV-1a, which unlike `DefaultHasher` is stable across releases and processes
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in flag.bytes().chain([0]).chain(key.bytes()) {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash % 100
+}
+
+impl fmt::Display for FlagState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlagState::Off => f.write_str("off"),
+            FlagState::On => f.write_str("on"),
+            FlagState::Rollout(percentage) => write!(f, "{percentage}%"),
+        }
+    }
+}
+
+/// Error for flag state text that isn't `on`, `off` or a percentage.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid flag state '{0}', expected 'on', 'off' or a percentage like '25%'")]
+pub struct InvalidFlagState(String);
+
+impl FromStr for FlagState {
+    type Err = InvalidFlagState;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || InvalidFlagState(value.to_string());
+        match value.trim().to_ascii_lowercase().as_str() {
+            "on" | "true" | "1" => Ok(FlagState::On),
+            "off" | "false" | "0" => Ok(FlagState::Off),
+            other => {
+                let percentage: u8 = other
+                    .strip_suffix('%')
+                    .ok_or_else(invalid)?
+                    .trim()
+                    .parse()
+                    .map_err(|_| invalid())?;
+                match percentage {
+                    0 => Ok(FlagState::Off),
+                    100 => Ok(FlagState::On),
+                    1..=99 => Ok(FlagState::Rollout(percentage)),
+                    _ => Err(invalid()),
+                }
+            }
+        }
+    }
+}
+
+impl TryFrom<String> for FlagState {
+    type Error = InvalidFlagState;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<FlagState> for String {
+    fn from(state: FlagState) -> Self {
+        state.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_states() {
+        assert_eq!("on".parse(), Ok(FlagState::On));
+        assert_eq!("TRUE".parse(), Ok(FlagState::On));
+        assert_eq!("off".parse(), Ok(FlagState::Off));
+        assert_eq!("25%".parse(), Ok(FlagState::Rollout(25)));
+        assert_eq!("0%".parse(), Ok(FlagState::Off));
+        assert_eq!("100%".parse(), Ok(FlagState::On));
+        for invalid in ["", "maybe", "25", "101%", "-5%"] {
+            assert!(invalid.parse::<FlagState>().is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn round_trips_through_text() {
+        for state in [FlagState::On, FlagState::Off, FlagState::Rollout(42)] {
+            assert_eq!(state.to_string().parse(), Ok(state));
+        }
+    }
+
+    #[test]
+    fn rolls_out_to_a_stable_share_of_keys() {
+        let rollout = FlagState::Rollout(30);
+        let keys: Vec<String> = (0..1000).map(|key| key.to_string()).collect();
+        let enabled = |state: FlagState| {
+            keys.iter()
+                .filter(|key| state.is_on_for("new-mode", key))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        let at_30 = enabled(rollout);
+        assert!((250..350).contains(&at_30.len()), "{}", at_30.len());
+        assert_eq!(enabled(rollout), at_30);
+
+        let at_60 = enabled(FlagState::Rollout(60));
+        assert!(at_30.iter().all(|key| at_60.contains(key)));
+    }
+}
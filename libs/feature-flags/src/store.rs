@@ -0,0 +1,24 @@
+use crate::FlagState;
+use async_trait::async_trait;
+
+/// Error for a flag store that can't be read or written.
+#[derive(Debug, thiserror::Error)]
+#[error("Feature flag store failed: {0}")]
+pub struct StoreError(#[source] pub Box<dyn std::error::Error + Send + Sync>);
+
+impl StoreError {
+    pub fn new(err: impl std::error::Error + Send + Sync + 'static) -> Self {
+        Self(Box::new(err))
+    }
+}
+
+/// Where toggled flags are persisted, so that they survive restarts and are shared by every
+/// instance of a service.
+#[async_trait]
+pub trait FlagStore: Send + Sync {
+    /// Every stored flag and its state.
+    async fn load(&self) -> Result<Vec<(String, FlagState)>, StoreError>;
+
+    /// Stores the state of a flag, replacing the previous one.
+    async fn save(&self, name: &str, state: FlagState) -> Result<(), StoreError>;
+}
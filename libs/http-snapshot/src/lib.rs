@@ -0,0 +1,11 @@
+//! Snapshot testing for axum handlers: builders for requests sent straight to a router, and
+//! response snapshots with the headers that change between runs filtered out.
+
+mod request;
+mod snapshot;
+
+pub use request::{TestRequest, TestResponse};
+pub use snapshot::{
+    HttpResponseSnapshot, JsonApiResponseSnapshot, VARIABLE_HEADERS, filter_variable_headers,
+    normalize_html_for_snapshot,
+};
@@ -0,0 +1,210 @@
+use crate::{HttpResponseSnapshot, JsonApiResponseSnapshot};
+use axum::Router;
+use axum::body::Body;
+use axum::http::{HeaderMap, HeaderName, HeaderValue, Method, Request, StatusCode, header};
+use axum::response::Response;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use tower::ServiceExt;
+
+/// Builder for a request sent straight to a router, without a running server.
+///
+/// ```ignore
+/// let response = TestRequest::post("/login")
+///     .form(&[("username", "admin"), ("password", "password")])
+///     .send(app)
+///     .await;
+/// assert_yaml_snapshot!(response.html_snapshot("login"));
+/// ```
+#[derive(Debug)]
+pub struct TestRequest {
+    method: Method,
+    uri: String,
+    headers: HeaderMap,
+    body: Body,
+}
+
+impl TestRequest {
+    pub fn new(method: Method, uri: impl Into<String>) -> Self {
+        Self {
+            method,
+            uri: uri.into(),
+            headers: HeaderMap::new(),
+            body: Body::empty(),
+        }
+    }
+
+    pub fn get(uri: impl Into<String>) -> Self {
+        Self::new(Method::GET, uri)
+    }
+
+    pub fn post(uri: impl Into<String>) -> Self {
+        Self::new(Method::POST, uri)
+    }
+
+    pub fn put(uri: impl Into<String>) -> Self {
+        Self::new(Method::PUT, uri)
+    }
+
+    pub fn delete(uri: impl Into<String>) -> Self {
+        Self::new(Method::DELETE, uri)
+    }
+
+    /// Sets a header, replacing any previous value.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the name or value isn't a valid header.
+    pub fn header(mut self, name: &str, value: impl AsRef<str>) -> Self {
+        let name = HeaderName::try_from(name).expect("invalid header name");
+        let value = HeaderValue::try_from(value.as_ref()).expect("invalid header value");
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Adds a cookie, keeping the ones added before it.
+    pub fn cookie(self, name: &str, value: &str) -> Self {
+        let cookie = match self.headers.get(header::COOKIE) {
+            Some(existing) => format!("{}; {name}={value}", existing.to_str().unwrap_or_default()),
+            None => format!("{name}={value}"),
+        };
+        self.header(header::COOKIE.as_str(), cookie)
+    }
+
+    /// Sets an `Authorization: Bearer` header.
+    pub fn bearer_token(self, token: &str) -> Self {
+        self.header(header::AUTHORIZATION.as_str(), format!("Bearer {token}"))
+    }
+
+    /// Sends `body` url-encoded, like a submitted HTML form.
+    pub fn form(mut self, body: &impl Serialize) -> Self {
+        let body = serde_urlencoded::to_string(body).expect("form body can't be url-encoded");
+        self.body = Body::from(body);
+        self.header(
+            header::CONTENT_TYPE.as_str(),
+            "application/x-www-form-urlencoded",
+        )
+    }
+
+    /// Sends `body` as JSON.
+    pub fn json(mut self, body: &impl Serialize) -> Self {
+        let body = serde_json::to_vec(body).expect("JSON body can't be serialized");
+        self.body = Body::from(body);
+        self.header(header::CONTENT_TYPE.as_str(), "application/json")
+    }
+
+    /// Sends a raw body, e.g. to test malformed input. Set the content type with
+    /// [`header`](Self::header).
+    pub fn body(mut self, body: impl Into<Body>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    pub fn build(self) -> Request<Body> {
+        let mut request = Request::builder()
+            .method(self.method)
+            .uri(self.uri)
+            .body(self.body)
+            .expect("invalid request");
+        *request.headers_mut() = self.headers;
+        request
+    }
+
+    /// Sends the request to `app` and reads the whole response.
+    pub async fn send(self, app: Router) -> TestResponse {
+        let response = app.oneshot(self.build()).await.expect("routers never fail");
+        TestResponse::read(response).await
+    }
+}
+
+/// A response whose body has been read, ready to be asserted on or snapshotted.
+#[derive(Debug)]
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+impl TestResponse {
+    /// Reads the whole body of `response`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the body can't be read or isn't UTF-8.
+    pub async fn read(response: Response) -> Self {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .expect("failed to read response body");
+        let body = String::from_utf8(body.to_vec()).expect("response body isn't UTF-8");
+        Self {
+            status,
+            headers,
+            body,
+        }
+    }
+
+    /// Parses the body as JSON.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the body isn't JSON of type `T`.
+    pub fn json<T: DeserializeOwned>(&self) -> T {
+        serde_json::from_str(&self.body).expect("response body isn't the expected JSON")
+    }
+
+    pub fn html_snapshot(&self, test_context: &str) -> HttpResponseSnapshot {
+        HttpResponseSnapshot::new(&self.body, self.status, &self.headers, test_context)
+    }
+
+    pub fn json_snapshot(&self, test_name: &str) -> JsonApiResponseSnapshot {
+        JsonApiResponseSnapshot::new(&self.body, self.status, &self.headers, test_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::Json;
+    use axum::routing::post;
+
+    #[test]
+    fn builds_requests() {
+        let request = TestRequest::post("/login")
+            .cookie("a", "1")
+            .cookie("b", "2")
+            .bearer_token("token")
+            .form(&[("username", "admin"), ("password", "p&ss")])
+            .build();
+
+        assert_eq!(request.method(), Method::POST);
+        assert_eq!(request.uri(), "/login");
+        assert_eq!(request.headers()[header::COOKIE], "a=1; b=2");
+        assert_eq!(request.headers()[header::AUTHORIZATION], "Bearer token");
+        assert_eq!(
+            request.headers()[header::CONTENT_TYPE],
+            "application/x-www-form-urlencoded"
+        );
+    }
+
+    #[tokio::test]
+    async fn sends_requests_and_reads_responses() {
+        let app = Router::new().route(
+            "/echo",
+            post(|Json(body): Json<serde_json::Value>| async move { Json(body) }),
+        );
+
+        let response = TestRequest::post("/echo")
+            .json(&serde_json::json!({ "name": "Alice" }))
+            .send(app)
+            .await;
+
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.body, r#"{"name":"Alice"}"#);
+        assert_eq!(
+            response.json::<serde_json::Value>()["name"],
+            serde_json::json!("Alice")
+        );
+    }
+}
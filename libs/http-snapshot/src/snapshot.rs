@@ -0,0 +1,119 @@
+use axum::http::{HeaderMap, StatusCode};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Headers that vary between test runs and should be filtered out for stable snapshots.
+pub const VARIABLE_HEADERS: &[&str] = &[
+    "date",
+    "expires",
+    "last-modified",
+    "etag",
+    "server",
+    "x-request-id",
+    "x-trace-id",
+    "set-cookie",
+    "content-length",
+];
+
+/// HTTP response snapshot for testing endpoints.
+#[derive(Debug, Serialize)]
+pub struct HttpResponseSnapshot {
+    pub test_context: String,
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    pub html_body: Vec<String>,
+}
+
+impl HttpResponseSnapshot {
+    /// Create a new HTTP response snapshot.
+    pub fn new(
+        body_text: &str,
+        status: StatusCode,
+        headers: &HeaderMap,
+        test_context: &str,
+    ) -> Self {
+        Self {
+            test_context: test_context.to_string(),
+            status: status.as_u16(),
+            headers: filter_variable_headers(headers),
+            html_body: normalize_html_for_snapshot(body_text),
+        }
+    }
+}
+
+/// Snapshot structure for JSON API responses
+#[derive(Debug, Serialize)]
+pub struct JsonApiResponseSnapshot {
+    status: u16,
+    headers: BTreeMap<String, String>,
+    body: serde_json::Value,
+    test_name: String,
+}
+
+impl JsonApiResponseSnapshot {
+    pub fn new(body_text: &str, status: StatusCode, headers: &HeaderMap, test_name: &str) -> Self {
+        let body = serde_json::from_str(body_text)
+            .unwrap_or_else(|_| serde_json::Value::String(body_text.to_string()));
+
+        Self {
+            status: status.as_u16(),
+            headers: filter_variable_headers(headers),
+            body,
+            test_name: test_name.to_string(),
+        }
+    }
+}
+
+/// Normalize HTML content for consistent snapshots by removing dynamic values.
+pub fn normalize_html_for_snapshot(html: &str) -> Vec<String> {
+    // Split HTML by newlines and convert to Vec<String>
+    // In the future, we could add more sophisticated normalization
+    html.lines().map(|line| line.to_string()).collect()
+}
+
+/// Filter out variable headers from response headers for snapshot testing.
+pub fn filter_variable_headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            let name_str = name.as_str().to_lowercase();
+            if VARIABLE_HEADERS.contains(&name_str.as_str()) {
+                None
+            } else {
+                value.to_str().ok().map(|v| (name_str, v.to_string()))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn filters_out_variable_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-type", HeaderValue::from_static("text/html"));
+        headers.insert(
+            "date",
+            HeaderValue::from_static("Thu, 01 Jan 1970 00:00:00 GMT"),
+        );
+        headers.insert("set-cookie", HeaderValue::from_static("auth_token=abc"));
+
+        let filtered = filter_variable_headers(&headers);
+
+        assert_eq!(
+            filtered,
+            BTreeMap::from([("content-type".to_string(), "text/html".to_string())])
+        );
+    }
+
+    #[test]
+    fn keeps_non_json_bodies_as_strings() {
+        let snapshot =
+            JsonApiResponseSnapshot::new("not json", StatusCode::OK, &HeaderMap::new(), "test");
+
+        assert_eq!(snapshot.body, serde_json::Value::String("not json".into()));
+    }
+}
@@ -0,0 +1,11 @@
+//! Background jobs for the services: periodic jobs on an interval or cron schedule, one-off
+//! queued jobs, retry policies for failed runs, and a graceful shutdown that lets running jobs
+//! finish before the process exits.
+
+mod retry;
+mod schedule;
+mod scheduler;
+
+pub use retry::RetryPolicy;
+pub use schedule::{CronExpr, Schedule, ScheduleError};
+pub use scheduler::{JobQueue, RunningScheduler, Scheduler};
@@ -0,0 +1,99 @@
+use std::time::Duration;
+
+/// How often a failing job run is retried, and how long to wait in between.
+///
+/// A run that still fails after the last attempt is logged and given up on. A scheduled job is
+/// tried again at its next scheduled time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Runs once and never retries.
+    pub fn none() -> Self {
+        Self::fixed(1, Duration::ZERO)
+    }
+
+    /// Tries up to `max_attempts` times, waiting `delay` after each failure.
+    pub fn fixed(max_attempts: u32, delay: Duration) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            initial_backoff: delay,
+            max_backoff: delay,
+        }
+    }
+
+    /// Tries up to `max_attempts` times, doubling the wait after each failure from `initial` up
+    /// to `max`.
+    pub fn exponential(max_attempts: u32, initial: Duration, max: Duration) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            initial_backoff: initial,
+            max_backoff: max.max(initial),
+        }
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// How long to wait after the `attempt`th attempt (starting at 1) failed, or `None` if that
+    /// was the last one.
+    pub fn backoff(&self, attempt: u32) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        Some(
+            self.initial_backoff
+                .saturating_mul(factor)
+                .min(self.max_backoff),
+        )
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Three attempts, one second apart and then two.
+    fn default() -> Self {
+        Self::exponential(3, Duration::from_secs(1), Duration::from_secs(60))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exponential_backoff_doubles_up_to_the_maximum() {
+        let policy = RetryPolicy::exponential(5, Duration::from_secs(1), Duration::from_secs(5));
+        let backoffs: Vec<_> = (1..=5).map(|attempt| policy.backoff(attempt)).collect();
+        assert_eq!(
+            backoffs,
+            vec![
+                Some(Duration::from_secs(1)),
+                Some(Duration::from_secs(2)),
+                Some(Duration::from_secs(4)),
+                Some(Duration::from_secs(5)),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn no_retries_means_a_single_attempt() {
+        let policy = RetryPolicy::none();
+        assert_eq!(policy.max_attempts(), 1);
+        assert_eq!(policy.backoff(1), None);
+    }
+
+    #[test]
+    fn fixed_backoff_stays_the_same() {
+        let policy = RetryPolicy::fixed(3, Duration::from_millis(250));
+        assert_eq!(policy.backoff(1), Some(Duration::from_millis(250)));
+        assert_eq!(policy.backoff(2), Some(Duration::from_millis(250)));
+        assert_eq!(policy.backoff(3), None);
+    }
+}
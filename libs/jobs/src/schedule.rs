@@ -0,0 +1,308 @@
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, TimeZone, Timelike, Utc};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// When a job runs: at a fixed interval, or whenever a cron expression matches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Schedule {
+    /// Runs every `Duration`, starting one interval after the scheduler starts.
+    Every(Duration),
+    /// Runs at the minutes matched by a cron expression, evaluated in UTC.
+    Cron(CronExpr),
+}
+
+impl Schedule {
+    pub fn every(interval: Duration) -> Self {
+        Schedule::Every(interval)
+    }
+
+    /// Parses a five field cron expression, see [`CronExpr`].
+    pub fn cron(expr: &str) -> Result<Self, ScheduleError> {
+        expr.parse().map(Schedule::Cron)
+    }
+
+    /// How long to wait from `now` until the next run, or `None` if the schedule never fires
+    /// again.
+    pub fn delay_from(&self, now: DateTime<Utc>) -> Option<Duration> {
+        match self {
+            Schedule::Every(interval) => Some(*interval),
+            Schedule::Cron(expr) => {
+                let next = expr.next_after(now)?;
+                (next - now).to_std().ok()
+            }
+        }
+    }
+}
+
+/// Error for a cron expression that can't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid cron expression '{expr}': {reason}")]
+pub struct ScheduleError {
+    expr: String,
+    reason: String,
+}
+
+impl ScheduleError {
+    fn new(expr: &str, reason: impl Into<String>) -> Self {
+        Self {
+            expr: expr.to_string(),
+            reason: reason.into(),
+        }
+    }
+}
+
+/// A standard five field cron expression: `minute hour day-of-month month day-of-week`.
+///
+/// Each field accepts `*`, single values, ranges (`1-5`), steps (`*/15`, `0-30/10`) and comma
+/// separated lists of those. Day of week runs from 0 (Sunday) to 6, with 7 also meaning Sunday.
+/// As in classic cron, when both day fields are restricted a day matching either one fires.
+/// The `@hourly`, `@daily`, `@weekly`, `@monthly` and `@yearly` shorthands are understood too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronExpr {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    restricted_days_of_month: bool,
+    restricted_days_of_week: bool,
+}
+
+/// How far ahead to look for a match before deciding an expression never fires, e.g. `0 0 31 2 *`.
+const SEARCH_YEARS: i32 = 5;
+
+impl CronExpr {
+    /// The first minute strictly after `after` that the expression matches.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + ChronoDuration::minutes(1);
+        let limit = after.year() + SEARCH_YEARS;
+
+        while time.year() <= limit {
+            if !contains(self.months, time.month()) {
+                let (year, month) = if time.month() == 12 {
+                    (time.year() + 1, 1)
+                } else {
+                    (time.year(), time.month() + 1)
+                };
+                time = Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()?;
+                continue;
+            }
+            if !self.matches_day(time) {
+                time = (time + ChronoDuration::days(1))
+                    .with_hour(0)?
+                    .with_minute(0)?;
+                continue;
+            }
+            if !contains(self.hours, time.hour()) {
+                time = (time + ChronoDuration::hours(1)).with_minute(0)?;
+                continue;
+            }
+            if !contains(self.minutes, time.minute()) {
+                time += ChronoDuration::minutes(1);
+                continue;
+            }
+            return Some(time);
+        }
+        None
+    }
+
+    fn matches_day(&self, time: DateTime<Utc>) -> bool {
+        let day_of_month = contains(self.days_of_month, time.day());
+        let day_of_week = contains(self.days_of_week, time.weekday().num_days_from_sunday());
+        match (self.restricted_days_of_month, self.restricted_days_of_week) {
+            (true, true) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+}
+
+impl FromStr for CronExpr {
+    type Err = ScheduleError;
+
+    fn from_str(expr: &str) -> Result<Self, Self::Err> {
+        let expanded = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(ScheduleError::new(
+                expr,
+                format!("expected 5 fields, found {}", fields.len()),
+            ));
+        };
+
+        let parse = |field: &str, name: &str, min: u32, max: u32| {
+            parse_field(field, min, max)
+                .map_err(|reason| ScheduleError::new(expr, format!("{name}: {reason}")))
+        };
+        let mut days_of_week = parse(day_of_week, "day of week", 0, 7)?;
+        // 7 is an alias for Sunday
+        if contains(days_of_week, 7) {
+            days_of_week |= 1;
+        }
+
+        Ok(Self {
+            minutes: parse(minute, "minute", 0, 59)?,
+            hours: parse(hour, "hour", 0, 23)?,
+            days_of_month: parse(day_of_month, "day of month", 1, 31)?,
+            months: parse(month, "month", 1, 12)?,
+            days_of_week,
+            restricted_days_of_month: day_of_month != "*",
+            restricted_days_of_week: day_of_week != "*",
+        })
+    }
+}
+
+fn contains(set: u64, value: u32) -> bool {
+    set & (1 << value) != 0
+}
+
+/// Parses one cron field into a bit set of the values it matches.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut set = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("invalid step '{step}'"))?;
+                if step == 0 {
+                    return Err("step must be at least 1".to_string());
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (parse_value(start, min, max)?, parse_value(end, min, max)?),
+                // A stepped single value, like `5/15`, runs from that value to the end
+                None if step > 1 => (parse_value(range, min, max)?, max),
+                None => {
+                    let value = parse_value(range, min, max)?;
+                    (value, value)
+                }
+            },
+        };
+        if start > end {
+            return Err(format!("range '{range}' is backwards"));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+fn parse_value(value: &str, min: u32, max: u32) -> Result<u32, String> {
+    let parsed: u32 = value
+        .parse()
+        .map_err(|_| format!("invalid value '{value}'"))?;
+    if !(min..=max).contains(&parsed) {
+        return Err(format!("{parsed} is not between {min} and {max}"));
+    }
+    Ok(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    fn next(expr: &str, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        expr.parse::<CronExpr>().unwrap().next_after(after)
+    }
+
+    #[test]
+    fn steps_through_minutes() {
+        let after = at(2025, 3, 10, 12, 7);
+        assert_eq!(next("*/15 * * * *", after), Some(at(2025, 3, 10, 12, 15)));
+        assert_eq!(
+            next("*/15 * * * *", at(2025, 3, 10, 12, 15)),
+            Some(at(2025, 3, 10, 12, 30))
+        );
+    }
+
+    #[test]
+    fn rolls_over_into_the_next_day_month_and_year() {
+        assert_eq!(
+            next("30 2 * * *", at(2025, 3, 10, 3, 0)),
+            Some(at(2025, 3, 11, 2, 30))
+        );
+        assert_eq!(
+            next("0 0 1 * *", at(2025, 1, 31, 12, 0)),
+            Some(at(2025, 2, 1, 0, 0))
+        );
+        assert_eq!(
+            next("@yearly", at(2025, 6, 1, 0, 0)),
+            Some(at(2026, 1, 1, 0, 0))
+        );
+    }
+
+    #[test]
+    fn handles_days_of_week() {
+        // 2025-03-10 is a Monday
+        assert_eq!(
+            next("0 9 * * 5", at(2025, 3, 10, 0, 0)),
+            Some(at(2025, 3, 14, 9, 0))
+        );
+        assert_eq!(
+            next("0 9 * * 7", at(2025, 3, 10, 0, 0)),
+            Some(at(2025, 3, 16, 9, 0))
+        );
+        assert_eq!(
+            next("0 9 * * 1-5", at(2025, 3, 14, 10, 0)),
+            Some(at(2025, 3, 17, 9, 0))
+        );
+    }
+
+    #[test]
+    fn either_day_field_matches_when_both_are_restricted() {
+        // The 20th or any Sunday, whichever comes first
+        assert_eq!(
+            next("0 0 20 * 0", at(2025, 3, 10, 0, 0)),
+            Some(at(2025, 3, 16, 0, 0))
+        );
+    }
+
+    #[test]
+    fn never_fires_for_impossible_dates() {
+        assert_eq!(next("0 0 31 2 *", at(2025, 1, 1, 0, 0)), None);
+    }
+
+    #[test]
+    fn rejects_malformed_expressions() {
+        for expr in [
+            "* * * *",
+            "60 * * * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "a * * * *",
+        ] {
+            assert!(expr.parse::<CronExpr>().is_err(), "{expr}");
+        }
+    }
+
+    #[test]
+    fn interval_schedules_always_wait_the_interval() {
+        let schedule = Schedule::every(Duration::from_secs(90));
+        assert_eq!(
+            schedule.delay_from(at(2025, 3, 10, 0, 0)),
+            Some(Duration::from_secs(90))
+        );
+
+        let schedule = Schedule::cron("0 * * * *").unwrap();
+        assert_eq!(
+            schedule.delay_from(at(2025, 3, 10, 0, 59)),
+            Some(Duration::from_secs(60))
+        );
+    }
+}
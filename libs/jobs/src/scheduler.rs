@@ -0,0 +1,257 @@
+use crate::{RetryPolicy, Schedule};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+type JobFn = Arc<dyn Fn() -> BoxFuture<anyhow::Result<()>> + Send + Sync>;
+type ShutdownHook = Box<dyn FnOnce() -> BoxFuture<()> + Send>;
+
+struct ScheduledJob {
+    name: String,
+    schedule: Schedule,
+    retry: RetryPolicy,
+    run: JobFn,
+}
+
+/// Collects periodic jobs and shutdown hooks, then runs them on the tokio runtime.
+///
+/// Each job runs in its own task, so a slow job never delays another one, and a job never
+/// overlaps with itself: the next run is only scheduled once the current one is done.
+#[derive(Default)]
+pub struct Scheduler {
+    jobs: Vec<ScheduledJob>,
+    hooks: Vec<ShutdownHook>,
+}
+
+impl Scheduler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a job that runs on `schedule`, retrying failed runs according to `retry`.
+    pub fn job<F, Fut>(
+        mut self,
+        name: impl Into<String>,
+        schedule: Schedule,
+        retry: RetryPolicy,
+        job: F,
+    ) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        self.jobs.push(ScheduledJob {
+            name: name.into(),
+            schedule,
+            retry,
+            run: Arc::new(move || Box::pin(job())),
+        });
+        self
+    }
+
+    /// Adds a hook that runs during [`RunningScheduler::shutdown`], once every job has stopped.
+    /// Hooks run one after another in the order they were added.
+    pub fn on_shutdown<F, Fut>(mut self, hook: F) -> Self
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.hooks.push(Box::new(move || Box::pin(hook())));
+        self
+    }
+
+    /// Spawns every job. Must be called from within a tokio runtime.
+    pub fn start(self) -> RunningScheduler {
+        let token = CancellationToken::new();
+        let tracker = TaskTracker::new();
+        for job in self.jobs {
+            tracing::info!(job = %job.name, schedule = ?job.schedule, "Scheduling job");
+            tracker.spawn(run_on_schedule(job, token.clone()));
+        }
+        RunningScheduler {
+            queue: JobQueue { token, tracker },
+            hooks: self.hooks,
+        }
+    }
+}
+
+/// The jobs of a started [`Scheduler`].
+pub struct RunningScheduler {
+    queue: JobQueue,
+    hooks: Vec<ShutdownHook>,
+}
+
+impl RunningScheduler {
+    /// A handle for queueing one-off jobs that are also waited for on shutdown.
+    pub fn queue(&self) -> JobQueue {
+        self.queue.clone()
+    }
+
+    /// Stops scheduling new runs and retries, waits for the runs in progress to finish, then
+    /// runs the shutdown hooks.
+    pub async fn shutdown(self) {
+        tracing::info!("Shutting down job scheduler");
+        self.queue.token.cancel();
+        self.queue.tracker.close();
+        self.queue.tracker.wait().await;
+        for hook in self.hooks {
+            hook().await;
+        }
+    }
+}
+
+/// Runs one-off jobs in the background, e.g. work triggered by a request that shouldn't hold up
+/// the response.
+#[derive(Clone)]
+pub struct JobQueue {
+    token: CancellationToken,
+    tracker: TaskTracker,
+}
+
+impl JobQueue {
+    /// Starts `job` right away, retrying it according to `retry`. Returns `false` without running
+    /// it if the scheduler is already shutting down.
+    pub fn enqueue<F, Fut>(&self, name: impl Into<String>, retry: RetryPolicy, job: F) -> bool
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<()>> + Send + 'static,
+    {
+        if self.token.is_cancelled() {
+            return false;
+        }
+        let name = name.into();
+        let token = self.token.clone();
+        self.tracker
+            .spawn(async move { run_with_retry(&name, retry, &job, &token).await });
+        true
+    }
+}
+
+async fn run_on_schedule(job: ScheduledJob, token: CancellationToken) {
+    loop {
+        let Some(delay) = job.schedule.delay_from(chrono::Utc::now()) else {
+            tracing::info!(job = %job.name, "Job has no more scheduled runs");
+            return;
+        };
+        tokio::select! {
+            _ = token.cancelled() => return,
+            _ = tokio::time::sleep(delay) => {}
+        }
+        run_with_retry(&job.name, job.retry, &*job.run, &token).await;
+    }
+}
+
+async fn run_with_retry<F, Fut>(name: &str, retry: RetryPolicy, job: &F, token: &CancellationToken)
+where
+    F: Fn() -> Fut + ?Sized,
+    Fut: Future<Output = anyhow::Result<()>>,
+{
+    for attempt in 1.. {
+        let Err(err) = job().await else {
+            tracing::debug!(job = %name, attempt, "Job succeeded");
+            return;
+        };
+        let Some(backoff) = retry.backoff(attempt) else {
+            tracing::error!(job = %name, attempt, "Job failed: {err:#}");
+            return;
+        };
+        tracing::warn!(job = %name, attempt, ?backoff, "Job failed, retrying: {err:#}");
+        tokio::select! {
+            _ = token.cancelled() => return,
+            _ = tokio::time::sleep(backoff) => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    fn counter() -> (
+        Arc<AtomicU32>,
+        impl Fn() -> BoxFuture<anyhow::Result<()>> + Send + Sync,
+    ) {
+        let count = Arc::new(AtomicU32::new(0));
+        let job_count = count.clone();
+        let job = move || -> BoxFuture<anyhow::Result<()>> {
+            job_count.fetch_add(1, Ordering::SeqCst);
+            Box::pin(async { Ok(()) })
+        };
+        (count, job)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn runs_jobs_on_their_interval_until_shutdown() {
+        let (count, job) = counter();
+        let scheduler = Scheduler::new()
+            .job(
+                "tick",
+                Schedule::every(Duration::from_secs(10)),
+                RetryPolicy::none(),
+                job,
+            )
+            .start();
+
+        tokio::time::sleep(Duration::from_secs(35)).await;
+        scheduler.shutdown().await;
+        tokio::time::sleep(Duration::from_secs(60)).await;
+
+        assert_eq!(count.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn retries_failed_runs() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let job_attempts = attempts.clone();
+        let scheduler = Scheduler::new().start();
+
+        scheduler.queue().enqueue(
+            "flaky",
+            RetryPolicy::fixed(5, Duration::from_secs(1)),
+            move || {
+                let attempt = job_attempts.fetch_add(1, Ordering::SeqCst) + 1;
+                async move {
+                    anyhow::ensure!(attempt >= 3, "attempt {attempt} failed");
+                    Ok(())
+                }
+            },
+        );
+
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        scheduler.shutdown().await;
+        assert_eq!(attempts.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn shutdown_waits_for_running_jobs_then_runs_hooks() {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let hook_events = events.clone();
+        let scheduler = Scheduler::new()
+            .on_shutdown(move || async move { hook_events.lock().unwrap().push("hook") })
+            .start();
+
+        let job_events = events.clone();
+        scheduler
+            .queue()
+            .enqueue("slow", RetryPolicy::none(), move || {
+                let events = job_events.clone();
+                async move {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    events.lock().unwrap().push("job");
+                    Ok(())
+                }
+            });
+        let queue = scheduler.queue();
+
+        scheduler.shutdown().await;
+
+        assert_eq!(*events.lock().unwrap(), vec!["job", "hook"]);
+        assert!(!queue.enqueue("late", RetryPolicy::none(), || async { Ok(()) }));
+    }
+}
@@ -0,0 +1,71 @@
+use crate::{Names, NamesFormatError};
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Record {
+    discord_id: u64,
+    name: String,
+}
+
+impl Names {
+    /// Parses a CSV document with a `discord_id,name` header row.
+    pub fn from_csv(content: &str) -> Result<Self, NamesFormatError> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(content.as_bytes());
+        let mut entries = Vec::new();
+        for record in reader.deserialize() {
+            let record: Record = record?;
+            entries.push((record.discord_id, record.name));
+        }
+        Self::validated(entries)
+    }
+
+    /// Writes the names as a CSV document with a `discord_id,name` header row.
+    pub fn to_csv(&self) -> Result<String, NamesFormatError> {
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        writer.write_record(["discord_id", "name"])?;
+        for (discord_id, name) in self.sorted() {
+            writer.write_record([discord_id.to_string().as_str(), name])?;
+        }
+        let bytes = writer
+            .into_inner()
+            .map_err(|err| csv::Error::from(err.into_error()))?;
+        Ok(String::from_utf8(bytes).expect("CSV written from strings is valid UTF-8"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_documents_with_a_header() {
+        let names =
+            Names::from_csv("discord_id,name\n123456789, Alice\n987654321,\"Smith, Bob\"\n")
+                .unwrap();
+
+        assert_eq!(names.get(123456789), Some("Alice"));
+        assert_eq!(names.get(987654321), Some("Smith, Bob"));
+    }
+
+    #[test]
+    fn rejects_malformed_documents() {
+        for content in [
+            "discord_id,name\nnot-an-id,Alice\n",
+            "id,real_name\n123456789,Alice\n",
+            "discord_id,name\n123456789\n",
+        ] {
+            let error = Names::from_csv(content).unwrap_err();
+            assert!(
+                error.to_string().starts_with("Invalid CSV format"),
+                "{content}: {error}"
+            );
+        }
+    }
+
+    #[test]
+    fn writes_a_header_even_without_names() {
+        assert_eq!(Names::default().to_csv().unwrap(), "discord_id,name\n");
+    }
+}
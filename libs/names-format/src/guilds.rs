@@ -0,0 +1,198 @@
+use crate::{Names, NamesFormatError};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// The top level key of the per-guild mappings in a guild names document.
+const GUILDS_KEY: &str = "guilds";
+
+/// Real names of the members of several Discord guilds.
+///
+/// Names are kept per guild, as a member may go by different names in different guilds. Names
+/// documents from before guilds, with a single mapping, still parse: their names are shared by
+/// every guild, unless a guild has a name of its own for the member.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GuildNames {
+    /// Names that apply in every guild
+    pub shared: Names,
+    /// Names that only apply in one guild, indexed by guild id
+    pub guilds: HashMap<u64, Names>,
+}
+
+#[derive(Deserialize)]
+struct DocumentIn {
+    #[serde(default)]
+    names: Option<HashMap<u64, String>>,
+    #[serde(default)]
+    guilds: Option<HashMap<u64, Option<HashMap<u64, String>>>>,
+}
+
+#[derive(Serialize)]
+struct DocumentOut<'a> {
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    names: BTreeMap<u64, &'a str>,
+    guilds: BTreeMap<u64, BTreeMap<u64, &'a str>>,
+}
+
+impl GuildNames {
+    /// Parses a YAML document with per-guild mappings under a `guilds` key, and optionally
+    /// shared names under a `names` key. Documents without a `guilds` key are read as
+    /// [`Names::from_yaml`] does, with all of their names shared.
+    pub fn from_yaml(content: &str) -> Result<Self, NamesFormatError> {
+        let value: serde_yaml::Value = serde_yaml::from_str(content)?;
+        let has_guilds = value
+            .as_mapping()
+            .is_some_and(|mapping| mapping.contains_key(GUILDS_KEY));
+        if !has_guilds {
+            return Ok(Self {
+                shared: Names::from_yaml(content)?,
+                guilds: HashMap::new(),
+            });
+        }
+        let document: DocumentIn = serde_yaml::from_value(value)?;
+        let mut guilds = HashMap::new();
+        for (guild_id, names) in document.guilds.unwrap_or_default() {
+            guilds.insert(guild_id, Names::validated(names.unwrap_or_default())?);
+        }
+        Ok(Self {
+            shared: Names::validated(document.names.unwrap_or_default())?,
+            guilds,
+        })
+    }
+
+    /// Writes the names as a YAML document with per-guild mappings under a `guilds` key, and
+    /// the shared names, if there are any, under a `names` key.
+    pub fn to_yaml(&self) -> Result<String, NamesFormatError> {
+        let document = DocumentOut {
+            names: self.shared.sorted().into_iter().collect(),
+            guilds: self
+                .guilds
+                .iter()
+                .filter(|(_, names)| !names.is_empty())
+                .map(|(guild_id, names)| (*guild_id, names.sorted().into_iter().collect()))
+                .collect(),
+        };
+        Ok(serde_yaml::to_string(&document)?)
+    }
+
+    /// The names that apply in a guild: the shared names, overridden by the guild's own.
+    pub fn in_guild(&self, guild_id: u64) -> Names {
+        let mut names = self.shared.clone();
+        if let Some(guild_names) = self.guilds.get(&guild_id) {
+            names.names.extend(guild_names.names.clone());
+        }
+        names
+    }
+
+    /// Sets the real name of a member of a guild, replacing any previous one in that guild.
+    pub fn insert(&mut self, guild_id: u64, discord_id: u64, name: String) {
+        self.guilds
+            .entry(guild_id)
+            .or_default()
+            .names
+            .insert(discord_id, name);
+    }
+
+    /// Forgets the real name of a member of a guild, returning the name that applied there.
+    ///
+    /// A shared name is forgotten in every guild, as documents from before guilds don't say
+    /// which guild their names belong to.
+    pub fn remove(&mut self, guild_id: u64, discord_id: u64) -> Option<String> {
+        let guild_name = self
+            .guilds
+            .get_mut(&guild_id)
+            .and_then(|names| names.names.remove(&discord_id));
+        let shared_name = self.shared.names.remove(&discord_id);
+        guild_name.or(shared_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_names_per_guild() {
+        let names = GuildNames::from_yaml(
+            r#"
+names:
+  123456789: Alice
+guilds:
+  1:
+    123456789: Alicia
+    987654321: Bob
+  2:
+    555555555: Carol
+"#,
+        )
+        .unwrap();
+
+        let first = names.in_guild(1);
+        let second = names.in_guild(2);
+        let elsewhere = names.in_guild(3);
+        assert_eq!(first.get(123456789), Some("Alicia"));
+        assert_eq!(first.get(987654321), Some("Bob"));
+        assert_eq!(second.get(123456789), Some("Alice"));
+        assert_eq!(second.get(987654321), None);
+        assert_eq!(elsewhere.len(), 1);
+    }
+
+    #[test]
+    fn shares_names_of_documents_from_before_guilds() {
+        let names = GuildNames::from_yaml("names:\n  123456789: Alice\n").unwrap();
+
+        assert_eq!(names.guilds, HashMap::new());
+        assert_eq!(names.in_guild(1).get(123456789), Some("Alice"));
+        assert_eq!(names.in_guild(2).get(123456789), Some("Alice"));
+        assert!(GuildNames::from_yaml("").unwrap().in_guild(1).is_empty());
+    }
+
+    #[test]
+    fn rejects_invalid_guild_names() {
+        let error = GuildNames::from_yaml("guilds:\n  1:\n    123456789: ' '\n").unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Invalid names: 123456789: name must not be empty"
+        );
+    }
+
+    #[test]
+    fn keeps_shared_names_when_written() {
+        // Arrange
+        let mut names = GuildNames::from_yaml("names:\n  123456789: Alice\n").unwrap();
+
+        // Act
+        names.insert(2, 987654321, "Bob".to_string());
+        names.insert(1, 555555555, "Carol".to_string());
+        let yaml = names.to_yaml().unwrap();
+
+        // Assert
+        assert_eq!(
+            yaml,
+            "names:\n  123456789: Alice\nguilds:\n  1:\n    555555555: Carol\n  2:\n    987654321: Bob\n"
+        );
+        assert_eq!(GuildNames::from_yaml(&yaml).unwrap(), names);
+    }
+
+    #[test]
+    fn removes_the_name_that_applies_in_the_guild() {
+        // Arrange
+        let mut names = GuildNames::default();
+        names.shared.names.insert(123456789, "Alice".to_string());
+        names.insert(1, 123456789, "Alicia".to_string());
+        names.insert(1, 987654321, "Bob".to_string());
+        names.insert(2, 987654321, "Robert".to_string());
+
+        // Act
+        let alice = names.remove(1, 123456789);
+        let bob = names.remove(1, 987654321);
+        let nobody = names.remove(1, 555555555);
+
+        // Assert
+        assert_eq!(alice.as_deref(), Some("Alicia"));
+        assert_eq!(bob.as_deref(), Some("Bob"));
+        assert_eq!(nobody, None);
+        assert_eq!(names.in_guild(2).get(123456789), None);
+        assert_eq!(names.in_guild(2).get(987654321), Some("Robert"));
+    }
+}
@@ -0,0 +1,210 @@
+//! The real names file format shared by the nicknamer bot and server: a mapping of Discord user
+//! ids to real names, read from and written to YAML or CSV.
+//!
+//! YAML documents look like the bot's `real_names.yml`, with the mapping under a `names` key. A
+//! bare mapping without the key is accepted too, as pasted into the server's bulk import form.
+//! CSV documents have a `discord_id,name` header row.
+//!
+//! Names of several guilds are kept in [`GuildNames`] documents, with a mapping per guild id
+//! under a `guilds` key.
+//!
+//! Large documents can be written one entry at a time with [`NamesWriter`].
+//!
+//! Importing a document into the names already known only adds names, as worked out by
+//! [`NamesMerge`], so that the bot and the server's bulk import agree on what an import changes.
+
+mod csv;
+mod guilds;
+mod merge;
+mod writer;
+mod yaml;
+
+pub use guilds::GuildNames;
+pub use merge::{Conflict, NamesMerge};
+pub use writer::NamesWriter;
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// A collection of real names indexed by Discord user id.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Names {
+    /// Mapping of Discord user ids to real names
+    pub names: HashMap<u64, String>,
+}
+
+/// A file format names can be read from and written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Yaml,
+    Csv,
+}
+
+impl Names {
+    /// Parses and validates a document in `format`.
+    pub fn parse(content: &str, format: Format) -> Result<Self, NamesFormatError> {
+        match format {
+            Format::Yaml => Self::from_yaml(content),
+            Format::Csv => Self::from_csv(content),
+        }
+    }
+
+    /// Writes the names as a document in `format`, ordered by Discord user id.
+    pub fn serialize(&self, format: Format) -> Result<String, NamesFormatError> {
+        match format {
+            Format::Yaml => self.to_yaml(),
+            Format::Csv => self.to_csv(),
+        }
+    }
+
+    /// The real name of a Discord user, if there is one.
+    pub fn get(&self, discord_id: u64) -> Option<&str> {
+        self.names.get(&discord_id).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Builds the collection from parsed entries, trimming names and reporting every invalid
+    /// entry rather than just the first one.
+    fn validated(
+        entries: impl IntoIterator<Item = (u64, String)>,
+    ) -> Result<Self, NamesFormatError> {
+        let mut names = HashMap::new();
+        let mut problems = Vec::new();
+        for (discord_id, name) in entries {
+            let name = name.trim().to_string();
+            if discord_id == 0 {
+                problems.push(InvalidName::new(discord_id, "Discord ID must not be 0"));
+            } else if name.is_empty() {
+                problems.push(InvalidName::new(discord_id, "name must not be empty"));
+            } else if names.insert(discord_id, name).is_some() {
+                problems.push(InvalidName::new(
+                    discord_id,
+                    "Discord ID appears more than once",
+                ));
+            }
+        }
+        if problems.is_empty() {
+            Ok(Self { names })
+        } else {
+            Err(NamesFormatError::Invalid(problems))
+        }
+    }
+
+    /// The entries ordered by Discord user id, so that serialized documents are stable.
+    fn sorted(&self) -> Vec<(u64, &str)> {
+        let mut entries: Vec<_> = self
+            .names
+            .iter()
+            .map(|(discord_id, name)| (*discord_id, name.as_str()))
+            .collect();
+        entries.sort_unstable_by_key(|(discord_id, _)| *discord_id);
+        entries
+    }
+}
+
+impl FromIterator<(u64, String)> for Names {
+    fn from_iter<I: IntoIterator<Item = (u64, String)>>(iter: I) -> Self {
+        Self {
+            names: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// A single entry that parsed but isn't a valid real name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidName {
+    pub discord_id: u64,
+    pub message: String,
+}
+
+impl InvalidName {
+    fn new(discord_id: u64, message: impl Into<String>) -> Self {
+        Self {
+            discord_id,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for InvalidName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.discord_id, self.message)
+    }
+}
+
+/// Error for a names document that can't be read or written.
+#[derive(Debug, thiserror::Error)]
+pub enum NamesFormatError {
+    /// The document isn't a valid YAML names mapping.
+    #[error("Invalid YAML format: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+    /// The document isn't valid CSV with a `discord_id,name` header.
+    #[error("Invalid CSV format: {0}")]
+    Csv(#[from] ::csv::Error),
+    /// The document parsed, but some of its entries are invalid.
+    #[error("Invalid names: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    Invalid(Vec<InvalidName>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(entries: &[(u64, &str)]) -> Names {
+        entries
+            .iter()
+            .map(|(discord_id, name)| (*discord_id, name.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_between_formats() {
+        let expected = names(&[(987654321, "Bob"), (123456789, "Alice")]);
+
+        for format in [Format::Yaml, Format::Csv] {
+            let serialized = expected.serialize(format).unwrap();
+            assert_eq!(Names::parse(&serialized, format).unwrap(), expected);
+        }
+
+        let from_csv = Names::parse(&expected.to_csv().unwrap(), Format::Csv).unwrap();
+        assert_eq!(from_csv.to_yaml().unwrap(), expected.to_yaml().unwrap());
+    }
+
+    #[test]
+    fn reports_every_invalid_entry() {
+        let error = Names::validated([
+            (0, "Nobody".to_string()),
+            (123456789, "  ".to_string()),
+            (987654321, "Bob".to_string()),
+            (987654321, "Robert".to_string()),
+        ])
+        .unwrap_err();
+
+        let NamesFormatError::Invalid(problems) = &error else {
+            panic!("Expected invalid names, got {error:?}");
+        };
+        assert_eq!(
+            problems.iter().map(|p| p.discord_id).collect::<Vec<_>>(),
+            vec![0, 123456789, 987654321]
+        );
+        assert_eq!(
+            error.to_string(),
+            "Invalid names: 0: Discord ID must not be 0, 123456789: name must not be empty, \
+             987654321: Discord ID appears more than once"
+        );
+    }
+
+    #[test]
+    fn trims_names() {
+        let parsed = Names::validated([(123456789, " Alice ".to_string())]).unwrap();
+        assert_eq!(parsed.get(123456789), Some("Alice"));
+        assert_eq!(parsed.get(987654321), None);
+    }
+}
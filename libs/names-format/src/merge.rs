@@ -0,0 +1,100 @@
+use crate::Names;
+
+/// An imported name for a member who already has a different one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub discord_id: u64,
+    /// The name the member keeps
+    pub existing: String,
+    /// The name the import has for them
+    pub imported: String,
+}
+
+/// What importing a names document into the names already known would change.
+///
+/// Imports only add names: a member who already has a real name keeps it, even if the import
+/// has another, so that importing an old export can't undo names edited since. Entries are
+/// ordered by Discord user id.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NamesMerge {
+    /// Names of members who didn't have one yet
+    pub added: Vec<(u64, String)>,
+    /// Members whose imported name is the one they already have
+    pub unchanged: Vec<u64>,
+    /// Members whose imported name differs from the one they already have
+    pub conflicts: Vec<Conflict>,
+}
+
+impl NamesMerge {
+    /// Works out what importing `imported` into `existing` would change.
+    pub fn new(existing: &Names, imported: &Names) -> Self {
+        let mut merge = Self::default();
+        for (discord_id, name) in imported.sorted() {
+            match existing.get(discord_id) {
+                None => merge.added.push((discord_id, name.to_string())),
+                Some(existing) if existing == name => merge.unchanged.push(discord_id),
+                Some(existing) => merge.conflicts.push(Conflict {
+                    discord_id,
+                    existing: existing.to_string(),
+                    imported: name.to_string(),
+                }),
+            }
+        }
+        merge
+    }
+
+    /// How many imported names are left out, because their member already has a name.
+    pub fn skipped(&self) -> usize {
+        self.unchanged.len() + self.conflicts.len()
+    }
+
+    /// Whether importing would add no names at all.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(entries: &[(u64, &str)]) -> Names {
+        entries
+            .iter()
+            .map(|(discord_id, name)| (*discord_id, name.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn only_adds_names_of_members_without_one() {
+        let existing = names(&[(1, "Alice"), (2, "Bob")]);
+        let imported = names(&[(4, "Dave"), (2, "Robert"), (1, "Alice"), (3, "Charlie")]);
+
+        let merge = NamesMerge::new(&existing, &imported);
+
+        assert_eq!(
+            merge.added,
+            vec![(3, "Charlie".to_string()), (4, "Dave".to_string())]
+        );
+        assert_eq!(merge.unchanged, vec![1]);
+        assert_eq!(
+            merge.conflicts,
+            vec![Conflict {
+                discord_id: 2,
+                existing: "Bob".to_string(),
+                imported: "Robert".to_string(),
+            }]
+        );
+        assert_eq!(merge.skipped(), 2);
+    }
+
+    #[test]
+    fn importing_known_names_changes_nothing() {
+        let existing = names(&[(1, "Alice")]);
+
+        let merge = NamesMerge::new(&existing, &existing);
+
+        assert!(merge.is_empty());
+        assert_eq!(merge.skipped(), 1);
+    }
+}
@@ -0,0 +1,91 @@
+use crate::yaml::NAMES_KEY;
+use crate::{Format, NamesFormatError};
+use std::collections::BTreeMap;
+
+/// Writes a names document one entry at a time, for documents too large to hold in memory as
+/// [`Names`](crate::Names). Writing the header followed by every entry gives the same document
+/// as [`Names::serialize`](crate::Names::serialize), as long as the entries are ordered by
+/// Discord user id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NamesWriter {
+    format: Format,
+}
+
+impl NamesWriter {
+    pub fn new(format: Format) -> Self {
+        Self { format }
+    }
+
+    /// What the document starts with, before any entry.
+    pub fn header(&self) -> String {
+        match self.format {
+            Format::Yaml => format!("{}:\n", NAMES_KEY),
+            Format::Csv => "discord_id,name\n".to_string(),
+        }
+    }
+
+    /// One entry of the document, ending in a newline.
+    pub fn entry(&self, discord_id: u64, name: &str) -> Result<String, NamesFormatError> {
+        match self.format {
+            Format::Yaml => {
+                let entry = serde_yaml::to_string(&BTreeMap::from([(discord_id, name)]))?;
+                Ok(entry.lines().map(|line| format!("  {}\n", line)).collect())
+            }
+            Format::Csv => {
+                let mut writer = csv::WriterBuilder::new()
+                    .has_headers(false)
+                    .from_writer(Vec::new());
+                writer.write_record([discord_id.to_string().as_str(), name])?;
+                let bytes = writer
+                    .into_inner()
+                    .map_err(|err| csv::Error::from(err.into_error()))?;
+                Ok(String::from_utf8(bytes).expect("CSV written from strings is valid UTF-8"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Names;
+
+    fn write(format: Format, entries: &[(u64, &str)]) -> String {
+        let writer = NamesWriter::new(format);
+        let mut document = writer.header();
+        for (discord_id, name) in entries {
+            document.push_str(&writer.entry(*discord_id, name).unwrap());
+        }
+        document
+    }
+
+    #[test]
+    fn writes_the_same_documents_as_serialize() {
+        let entries = [(123456789, "Alice"), (987654321, "Smith, Bob: \"Bobby\"")];
+        let names: Names = entries
+            .iter()
+            .map(|(discord_id, name)| (*discord_id, name.to_string()))
+            .collect();
+
+        for format in [Format::Yaml, Format::Csv] {
+            let document = write(format, &entries);
+            assert_eq!(document, names.serialize(format).unwrap());
+            assert_eq!(Names::parse(&document, format).unwrap(), names);
+        }
+    }
+
+    #[test]
+    fn writes_documents_without_names_that_parse() {
+        for format in [Format::Yaml, Format::Csv] {
+            let document = write(format, &[]);
+            assert!(Names::parse(&document, format).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    fn writes_names_spanning_lines() {
+        let document = write(Format::Yaml, &[(123456789, "Alice\nLiddell")]);
+        let names = Names::parse(&document, Format::Yaml).unwrap();
+        assert_eq!(names.get(123456789), Some("Alice\nLiddell"));
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{Names, NamesFormatError};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// The top level key of a names document, as in `real_names.yml`.
+pub(crate) const NAMES_KEY: &str = "names";
+
+#[derive(Serialize)]
+struct Document<'a> {
+    names: BTreeMap<u64, &'a str>,
+}
+
+impl Names {
+    /// Parses a YAML document, with the mapping either under a `names` key or at the top level.
+    /// An empty document, or one with nothing under the `names` key, has no names.
+    pub fn from_yaml(content: &str) -> Result<Self, NamesFormatError> {
+        let mut value: serde_yaml::Value = serde_yaml::from_str(content)?;
+        if value.is_null() {
+            return Ok(Self::default());
+        }
+        if let Some(names) = value
+            .as_mapping_mut()
+            .filter(|mapping| mapping.len() == 1)
+            .and_then(|mapping| mapping.remove(NAMES_KEY))
+        {
+            value = names;
+        }
+        if value.is_null() {
+            return Ok(Self::default());
+        }
+        let names: HashMap<u64, String> = serde_yaml::from_value(value)?;
+        Self::validated(names)
+    }
+
+    /// Writes the names as a YAML document with the mapping under a `names` key.
+    pub fn to_yaml(&self) -> Result<String, NamesFormatError> {
+        let document = Document {
+            names: self.sorted().into_iter().collect(),
+        };
+        Ok(serde_yaml::to_string(&document)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_documents_with_and_without_the_names_key() {
+        let wrapped = Names::from_yaml(
+            r#"
+names:
+  123456789: Alice
+  987654321: Bob
+"#,
+        )
+        .unwrap();
+        let bare = Names::from_yaml(
+            r#"
+123456789: "Alice"
+987654321: "Bob"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(wrapped, bare);
+        assert_eq!(wrapped.get(123456789), Some("Alice"));
+        assert_eq!(wrapped.len(), 2);
+    }
+
+    #[test]
+    fn parses_empty_documents() {
+        assert!(Names::from_yaml("").unwrap().is_empty());
+        assert!(Names::from_yaml("{}").unwrap().is_empty());
+        assert!(Names::from_yaml("names: {}").unwrap().is_empty());
+        assert!(Names::from_yaml("names:\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_malformed_documents() {
+        for content in [
+            "invalid yaml content: not properly formatted",
+            "names: [Alice, Bob]",
+            "123456789: [Alice]",
+        ] {
+            let error = Names::from_yaml(content).unwrap_err();
+            assert!(
+                error.to_string().starts_with("Invalid YAML format"),
+                "{content}: {error}"
+            );
+        }
+    }
+
+    #[test]
+    fn writes_names_in_id_order() {
+        let names: Names = [
+            (987654321, "Bob".to_string()),
+            (123456789, "Alice".to_string()),
+        ]
+        .into_iter()
+        .collect();
+
+        assert_eq!(
+            names.to_yaml().unwrap(),
+            "names:\n  123456789: Alice\n  987654321: Bob\n"
+        );
+    }
+}
@@ -0,0 +1,13 @@
+//! Observability setup shared by the services: the tracing subscriber, request ids that tie log
+//! lines to the request that caused them, W3C trace context propagation between services, and a
+//! Prometheus `/metrics` endpoint.
+
+mod logging;
+mod registry;
+mod request_id;
+mod trace_context;
+
+pub use logging::{LogFormat, init_tracing};
+pub use registry::{Metrics, track_http_metrics};
+pub use request_id::{REQUEST_ID_HEADER, RequestIdLayer, request_span};
+pub use trace_context::{TRACEPARENT_HEADER, TraceContext, TraceContextLayer, TraceContextService};
@@ -0,0 +1,134 @@
+use std::time::Instant;
+
+use axum::Router;
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use axum::routing::get;
+use metrics_exporter_prometheus::{BuildError, PrometheusBuilder, PrometheusHandle};
+
+/// The Prometheus registry behind the `metrics` macros.
+#[derive(Clone)]
+pub struct Metrics {
+    handle: PrometheusHandle,
+}
+
+impl Metrics {
+    /// Installs the global metrics recorder. This can only be done once per process.
+    pub fn install() -> Result<Self, BuildError> {
+        let handle = PrometheusBuilder::new().install_recorder()?;
+        Ok(Self { handle })
+    }
+
+    /// Renders every recorded metric in the Prometheus text format.
+    pub fn render(&self) -> String {
+        self.handle.render()
+    }
+
+    /// Router serving the rendered metrics on `/metrics`.
+    pub fn router(&self) -> Router {
+        let metrics = self.clone();
+        Router::new().route(
+            "/metrics",
+            get(move || {
+                let metrics = metrics.clone();
+                async move { metrics.render() }
+            }),
+        )
+    }
+}
+
+/// Middleware counting requests (`http_requests_total`) and timing them
+/// (`http_request_duration_seconds`) by method, route and status.
+///
+/// Add it with `Router::route_layer` so the route template is known, otherwise requests are
+/// labelled with a path of `unmatched` to keep the number of series bounded.
+pub async fn track_http_metrics(request: Request, next: Next) -> Response {
+    let start = Instant::now();
+    let path = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let method = request.method().to_string();
+
+    let response = next.run(request).await;
+
+    let labels = [
+        ("method", method),
+        ("path", path),
+        ("status", response.status().as_u16().to_string()),
+    ];
+    metrics::counter!("http_requests_total", &labels).increment(1);
+    metrics::histogram!("http_request_duration_seconds", &labels)
+        .record(start.elapsed().as_secs_f64());
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::middleware::from_fn;
+    use tower::ServiceExt;
+
+    #[test]
+    fn can_count_requests_by_route() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let metrics = Metrics {
+            handle: recorder.handle(),
+        };
+        let app = Router::new()
+            .route("/names/{id}", get(|| async { "OK" }))
+            .route_layer(from_fn(track_http_metrics));
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        // The recorder is only installed for this thread, which is where the runtime polls
+        metrics::with_local_recorder(&recorder, || {
+            runtime
+                .block_on(app.oneshot(Request::get("/names/42").body(Body::empty()).unwrap()))
+                .unwrap()
+        });
+
+        let rendered = metrics.render();
+        assert!(
+            rendered
+                .contains(r#"http_requests_total{method="GET",path="/names/{id}",status="200"} 1"#),
+            "{rendered}"
+        );
+        assert!(rendered.contains("http_request_duration_seconds"));
+    }
+
+    #[test]
+    fn serves_rendered_metrics() {
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let metrics = Metrics {
+            handle: recorder.handle(),
+        };
+        metrics::with_local_recorder(&recorder, || {
+            metrics::counter!("names_created_total").increment(3)
+        });
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+
+        let body = runtime.block_on(async {
+            let response = metrics
+                .router()
+                .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap()
+        });
+
+        assert!(
+            String::from_utf8(body.to_vec())
+                .unwrap()
+                .contains("names_created_total 3")
+        );
+    }
+}
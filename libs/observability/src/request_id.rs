@@ -0,0 +1,102 @@
+use crate::TraceContext;
+use axum::http::{HeaderName, Request};
+use tower::Layer;
+use tower_http::request_id::{
+    MakeRequestUuid, PropagateRequestId, PropagateRequestIdLayer, SetRequestId, SetRequestIdLayer,
+};
+use tracing::Span;
+
+/// Header carrying the id of a request, on both the request and its response.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Gives every request an `x-request-id` (keeping one sent by the caller) and copies it onto the
+/// response.
+///
+/// Add it outside of `TraceLayer` and use [`request_span`] for the trace spans so that every log
+/// line of a request carries its id.
+#[derive(Debug, Clone, Default)]
+pub struct RequestIdLayer;
+
+impl<S> Layer<S> for RequestIdLayer {
+    type Service = SetRequestId<PropagateRequestId<S>, MakeRequestUuid>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        let header = HeaderName::from_static(REQUEST_ID_HEADER);
+        let inner = PropagateRequestIdLayer::new(header.clone()).layer(inner);
+        SetRequestIdLayer::new(header, MakeRequestUuid).layer(inner)
+    }
+}
+
+/// Span for `TraceLayer::make_span_with` that records the request id next to the method and uri,
+/// along with the trace ids set by [`TraceContextLayer`](crate::TraceContextLayer) if it is used.
+pub fn request_span<B>(request: &Request<B>) -> Span {
+    let request_id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|id| id.to_str().ok())
+        .unwrap_or_default();
+    let trace = request.extensions().get::<TraceContext>();
+    tracing::info_span!(
+        "request",
+        method = %request.method(),
+        uri = %request.uri(),
+        request_id,
+        trace_id = trace.map(TraceContext::trace_id),
+        span_id = trace.map(TraceContext::span_id),
+        parent_span_id = trace.and_then(TraceContext::parent_span_id),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    fn app() -> axum::Router {
+        axum::Router::new()
+            .route(
+                "/",
+                get(|request: Request<Body>| async move {
+                    request.headers()[REQUEST_ID_HEADER]
+                        .to_str()
+                        .unwrap()
+                        .to_string()
+                }),
+            )
+            .layer(RequestIdLayer)
+    }
+
+    #[test]
+    fn generates_a_request_id_and_returns_it() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let response = runtime
+            .block_on(app().oneshot(Request::get("/").body(Body::empty()).unwrap()))
+            .unwrap();
+
+        let id = response.headers()[REQUEST_ID_HEADER].clone();
+        assert_eq!(id.len(), 36);
+        let body = runtime
+            .block_on(axum::body::to_bytes(response.into_body(), usize::MAX))
+            .unwrap();
+        assert_eq!(body, id.as_bytes());
+    }
+
+    #[test]
+    fn keeps_the_callers_request_id() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let request = Request::get("/")
+            .header(REQUEST_ID_HEADER, "abc")
+            .body(Body::empty())
+            .unwrap();
+
+        let response = runtime.block_on(app().oneshot(request)).unwrap();
+
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "abc");
+    }
+}
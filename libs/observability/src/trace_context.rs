@@ -0,0 +1,273 @@
+use axum::http::{HeaderMap, HeaderValue, Request};
+use std::fmt;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
+
+/// Header carrying the W3C trace context of a request.
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Where a unit of work sits in a distributed trace, as described by the W3C Trace Context
+/// `traceparent` header (`00-<trace id>-<span id>-<flags>`).
+///
+/// Every service that handles part of a user action continues the caller's trace with a child
+/// context, so the trace id ties together the log lines of all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceContext {
+    trace_id: u128,
+    span_id: u64,
+    parent_span_id: Option<u64>,
+    sampled: bool,
+}
+
+impl TraceContext {
+    /// Starts a new trace, e.g. for a bot command or a request without a `traceparent`.
+    pub fn new_root() -> Self {
+        Self {
+            trace_id: random_id(),
+            span_id: random_id() as u64,
+            parent_span_id: None,
+            sampled: true,
+        }
+    }
+
+    /// Continues this trace in a new span, e.g. for an outgoing request.
+    pub fn child(&self) -> Self {
+        Self {
+            trace_id: self.trace_id,
+            span_id: random_id() as u64,
+            parent_span_id: Some(self.span_id),
+            sampled: self.sampled,
+        }
+    }
+
+    /// Parses a `traceparent` header value. Returns `None` for malformed values and the all-zero
+    /// ids the spec calls invalid, so that the receiver starts a fresh trace instead.
+    pub fn parse(traceparent: &str) -> Option<Self> {
+        let mut parts = traceparent.trim().split('-');
+        let version = parts.next()?;
+        let trace_id = parts.next()?;
+        let span_id = parts.next()?;
+        let flags = parts.next()?;
+        // Later versions may append fields, version 00 may not
+        if version.len() != 2 || version == "ff" || (version == "00" && parts.next().is_some()) {
+            return None;
+        }
+        let trace_id = parse_hex(trace_id, 32)?;
+        let span_id = parse_hex(span_id, 16)? as u64;
+        let flags = parse_hex(flags, 2)?;
+        if trace_id == 0 || span_id == 0 {
+            return None;
+        }
+        Some(Self {
+            trace_id,
+            span_id,
+            parent_span_id: None,
+            sampled: flags & 1 == 1,
+        })
+    }
+
+    /// Reads the `traceparent` header of an incoming request.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        Self::parse(headers.get(TRACEPARENT_HEADER)?.to_str().ok()?)
+    }
+
+    /// Sets the `traceparent` header of an outgoing request so the receiver continues this trace.
+    /// Pass a [`child`](Self::child) when the request is its own unit of work.
+    pub fn inject(&self, headers: &mut HeaderMap) {
+        let value = HeaderValue::from_str(&self.to_string())
+            .expect("a formatted traceparent is a valid header value");
+        headers.insert(TRACEPARENT_HEADER, value);
+    }
+
+    /// The id shared by every span of the trace, as 32 hex digits.
+    pub fn trace_id(&self) -> String {
+        format!("{:032x}", self.trace_id)
+    }
+
+    /// The id of this span, as 16 hex digits.
+    pub fn span_id(&self) -> String {
+        format!("{:016x}", self.span_id)
+    }
+
+    /// The id of the span this one continues, if it was received from a caller.
+    pub fn parent_span_id(&self) -> Option<String> {
+        self.parent_span_id.map(|id| format!("{id:016x}"))
+    }
+}
+
+impl fmt::Display for TraceContext {
+    /// Formats the context as a `traceparent` header value.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "00-{:032x}-{:016x}-{:02x}",
+            self.trace_id,
+            self.span_id,
+            u8::from(self.sampled)
+        )
+    }
+}
+
+fn parse_hex(value: &str, len: usize) -> Option<u128> {
+    // Uppercase hex is not allowed by the spec
+    if value.len() != len
+        || !value
+            .bytes()
+            .all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+    {
+        return None;
+    }
+    u128::from_str_radix(value, 16).ok()
+}
+
+/// A random, non-zero id.
+fn random_id() -> u128 {
+    loop {
+        let id = uuid::Uuid::new_v4().as_u128();
+        if id as u64 != 0 {
+            return id;
+        }
+    }
+}
+
+/// Continues the caller's trace for every request, or starts a new one if the request has no
+/// valid `traceparent`.
+///
+/// The request's [`TraceContext`] is stored in its extensions, where handlers can pick it up to
+/// propagate it to services they call, and where [`request_span`](crate::request_span) records
+/// its ids. Add it outside of `TraceLayer`, next to [`RequestIdLayer`](crate::RequestIdLayer).
+#[derive(Debug, Clone, Default)]
+pub struct TraceContextLayer;
+
+impl<S> Layer<S> for TraceContextLayer {
+    type Service = TraceContextService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        TraceContextService { inner }
+    }
+}
+
+/// Service created by [`TraceContextLayer`].
+#[derive(Debug, Clone)]
+pub struct TraceContextService<S> {
+    inner: S,
+}
+
+impl<S, B> Service<Request<B>> for TraceContextService<S>
+where
+    S: Service<Request<B>>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<B>) -> Self::Future {
+        let context = TraceContext::from_headers(request.headers())
+            .map(|parent| parent.child())
+            .unwrap_or_else(TraceContext::new_root);
+        request.extensions_mut().insert(context);
+        self.inner.call(request)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::routing::get;
+    use axum::{Extension, Router};
+    use tower::ServiceExt;
+
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn round_trips_a_traceparent() {
+        let context = TraceContext::parse(TRACEPARENT).unwrap();
+        assert_eq!(context.trace_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(context.span_id(), "00f067aa0ba902b7");
+        assert_eq!(context.to_string(), TRACEPARENT);
+    }
+
+    #[test]
+    fn rejects_malformed_traceparents() {
+        for traceparent in [
+            "",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01-extra",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4BF92F3577B34DA6A3CE929D0E0E4736-00f067aa0ba902b7-01",
+            "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+        ] {
+            assert_eq!(TraceContext::parse(traceparent), None, "{traceparent}");
+        }
+    }
+
+    #[test]
+    fn children_stay_in_the_same_trace() {
+        let root = TraceContext::new_root();
+        let child = root.child();
+        assert_eq!(child.trace_id(), root.trace_id());
+        assert_ne!(child.span_id(), root.span_id());
+        assert_eq!(child.parent_span_id(), Some(root.span_id()));
+
+        let mut headers = HeaderMap::new();
+        child.inject(&mut headers);
+        assert_eq!(
+            TraceContext::from_headers(&headers).unwrap().span_id(),
+            child.span_id()
+        );
+    }
+
+    async fn handled_context(request: Request<Body>) -> TraceContext {
+        let app =
+            Router::new()
+                .route(
+                    "/",
+                    get(|Extension(context): Extension<TraceContext>| async move {
+                        context.to_string()
+                    }),
+                )
+                .layer(TraceContextLayer);
+        let response = app.oneshot(request).await.unwrap();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        TraceContext::parse(std::str::from_utf8(&body).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn continues_the_callers_trace() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let request = Request::get("/")
+            .header(TRACEPARENT_HEADER, TRACEPARENT)
+            .body(Body::empty())
+            .unwrap();
+
+        let context = runtime.block_on(handled_context(request));
+
+        assert_eq!(context.trace_id(), "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_ne!(context.span_id(), "00f067aa0ba902b7");
+    }
+
+    #[test]
+    fn starts_a_new_trace_without_a_traceparent() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let request = Request::get("/")
+            .header(TRACEPARENT_HEADER, "garbage")
+            .body(Body::empty())
+            .unwrap();
+
+        let context = runtime.block_on(handled_context(request));
+
+        assert_ne!(context.trace_id(), "00000000000000000000000000000000");
+    }
+}
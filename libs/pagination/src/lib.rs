@@ -0,0 +1,13 @@
+//! Pagination for list endpoints: `?page=&per_page=` and `?sort=&order=` query extractors with
+//! validated bounds, and the [`Paginated`] envelope that list responses are wrapped in.
+
+mod page;
+mod rejection;
+mod sort;
+
+pub use page::{PageParams, Paginated};
+pub use rejection::PaginationRejection;
+pub use sort::{SortOrder, SortParams};
+
+#[cfg(feature = "sea-orm")]
+pub use page::paginate;
@@ -0,0 +1,230 @@
+use crate::PaginationRejection;
+use axum::{extract::FromRequestParts, extract::Query, http::request::Parts};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Which page of a list to return, taken from the `page` and `per_page` query parameters.
+///
+/// Pages are numbered from 1. Both parameters are optional, but values outside
+/// `1..=MAX_PER_PAGE` (or a page of 0) are rejected with `400 Bad Request` rather than clamped,
+/// so clients notice when they ask for something they will not get.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageParams {
+    /// Page number, starting at 1
+    #[serde(default = "default_page")]
+    #[param(minimum = 1, default = 1)]
+    pub page: u64,
+    /// Number of items per page, at most 100
+    #[serde(default = "default_per_page")]
+    #[param(minimum = 1, maximum = 100, default = 20)]
+    pub per_page: u64,
+}
+
+fn default_page() -> u64 {
+    1
+}
+
+fn default_per_page() -> u64 {
+    PageParams::DEFAULT_PER_PAGE
+}
+
+impl PageParams {
+    pub const DEFAULT_PER_PAGE: u64 = 20;
+    pub const MAX_PER_PAGE: u64 = 100;
+
+    /// Checks the bounds of a page request.
+    pub fn new(page: u64, per_page: u64) -> Result<Self, PaginationRejection> {
+        if page == 0 {
+            return Err(PaginationRejection::new("page must be at least 1"));
+        }
+        if !(1..=Self::MAX_PER_PAGE).contains(&per_page) {
+            return Err(PaginationRejection::new(format!(
+                "per_page must be between 1 and {}",
+                Self::MAX_PER_PAGE
+            )));
+        }
+        Ok(Self { page, per_page })
+    }
+
+    /// Number of items that come before this page.
+    pub fn offset(&self) -> u64 {
+        (self.page - 1) * self.per_page
+    }
+}
+
+impl Default for PageParams {
+    fn default() -> Self {
+        Self {
+            page: default_page(),
+            per_page: default_per_page(),
+        }
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for PageParams {
+    type Rejection = PaginationRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Query(params) = Query::<PageParams>::try_from_uri(&parts.uri)
+            .map_err(|err| PaginationRejection::new(err.body_text()))?;
+        Self::new(params.page, params.per_page)
+    }
+}
+
+/// One page of a list along with where it sits in the whole list.
+///
+/// Serializes as `{ "items", "page", "per_page", "total", "total_pages" }`. The navigation
+/// helpers are meant for templates, which cannot do the arithmetic themselves.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct Paginated<T> {
+    /// Items on this page
+    pub items: Vec<T>,
+    /// Page number, starting at 1
+    pub page: u64,
+    /// Maximum number of items per page
+    pub per_page: u64,
+    /// Number of items across all pages
+    pub total: u64,
+    /// Number of pages needed to show every item
+    pub total_pages: u64,
+}
+
+impl<T> Paginated<T> {
+    /// Wraps the items of the page requested by `params`, out of `total` items overall.
+    pub fn new(items: Vec<T>, params: PageParams, total: u64) -> Self {
+        Self {
+            items,
+            page: params.page,
+            per_page: params.per_page,
+            total,
+            total_pages: total.div_ceil(params.per_page),
+        }
+    }
+
+    /// Converts the items while keeping the page information, e.g. from models to response types.
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Paginated<U> {
+        Paginated {
+            items: self.items.into_iter().map(f).collect(),
+            page: self.page,
+            per_page: self.per_page,
+            total: self.total,
+            total_pages: self.total_pages,
+        }
+    }
+
+    pub fn has_previous(&self) -> bool {
+        self.page > 1
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.page < self.total_pages
+    }
+
+    pub fn previous_page(&self) -> Option<u64> {
+        self.has_previous().then(|| self.page - 1)
+    }
+
+    pub fn next_page(&self) -> Option<u64> {
+        self.has_next().then(|| self.page + 1)
+    }
+
+    /// 1-based position of the first item on this page, or 0 if the page is empty.
+    pub fn first_item(&self) -> u64 {
+        if self.items.is_empty() {
+            0
+        } else {
+            (self.page - 1) * self.per_page + 1
+        }
+    }
+
+    /// 1-based position of the last item on this page, or 0 if the page is empty.
+    pub fn last_item(&self) -> u64 {
+        if self.items.is_empty() {
+            0
+        } else {
+            self.first_item() + self.items.len() as u64 - 1
+        }
+    }
+}
+
+/// Fetches the page requested by `params` from a sea-orm query, counting the matching rows for
+/// the page information. The query should be ordered, or rows may shift between pages.
+#[cfg(feature = "sea-orm")]
+pub async fn paginate<'db, C, S>(
+    select: S,
+    params: PageParams,
+    db: &'db C,
+) -> Result<Paginated<<S::Selector as sea_orm::SelectorTrait>::Item>, sea_orm::DbErr>
+where
+    C: sea_orm::ConnectionTrait,
+    S: sea_orm::PaginatorTrait<'db, C>,
+{
+    let paginator = select.paginate(db, params.per_page);
+    let total = paginator.num_items().await?;
+    let items = paginator.fetch_page(params.page - 1).await?;
+    Ok(Paginated::new(items, params, total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+
+    async fn extract(uri: &str) -> Result<PageParams, PaginationRejection> {
+        let (mut parts, ()) = Request::get(uri).body(()).unwrap().into_parts();
+        PageParams::from_request_parts(&mut parts, &()).await
+    }
+
+    #[tokio::test]
+    async fn uses_defaults_when_parameters_are_missing() {
+        assert_eq!(extract("/names").await.unwrap(), PageParams::default());
+    }
+
+    #[tokio::test]
+    async fn reads_page_and_per_page() {
+        let params = extract("/names?page=3&per_page=10").await.unwrap();
+        assert_eq!(params, PageParams::new(3, 10).unwrap());
+        assert_eq!(params.offset(), 20);
+    }
+
+    #[tokio::test]
+    async fn rejects_out_of_bounds_values() {
+        assert!(extract("/names?page=0").await.is_err());
+        assert!(extract("/names?per_page=0").await.is_err());
+        assert!(extract("/names?per_page=101").await.is_err());
+        assert!(extract("/names?page=first").await.is_err());
+    }
+
+    #[test]
+    fn computes_page_information() {
+        let page = Paginated::new(vec![21, 22, 23], PageParams::new(3, 10).unwrap(), 23);
+        assert_eq!(page.total_pages, 3);
+        assert_eq!(page.previous_page(), Some(2));
+        assert_eq!(page.next_page(), None);
+        assert_eq!((page.first_item(), page.last_item()), (21, 23));
+    }
+
+    #[test]
+    fn empty_list_has_no_pages() {
+        let page = Paginated::<u32>::new(Vec::new(), PageParams::default(), 0);
+        assert_eq!(page.total_pages, 0);
+        assert!(!page.has_previous() && !page.has_next());
+        assert_eq!((page.first_item(), page.last_item()), (0, 0));
+    }
+
+    #[test]
+    fn serializes_as_envelope() {
+        let page = Paginated::new(vec!["a"], PageParams::default(), 1).map(str::to_uppercase);
+        assert_eq!(
+            serde_json::to_value(page).unwrap(),
+            serde_json::json!({
+                "items": ["A"],
+                "page": 1,
+                "per_page": 20,
+                "total": 1,
+                "total_pages": 1,
+            })
+        );
+    }
+}
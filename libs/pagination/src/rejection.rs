@@ -0,0 +1,44 @@
+use api_error::ApiError;
+use axum::response::{IntoResponse, Response};
+use std::fmt;
+
+/// Rejection for pagination or sorting query parameters that are malformed or out of bounds.
+///
+/// Responds with `400 Bad Request` and a problem details body, like the APIs' other errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaginationRejection {
+    message: String,
+}
+
+impl PaginationRejection {
+    pub(crate) fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+        }
+    }
+
+    /// What was wrong with the query parameters.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl fmt::Display for PaginationRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for PaginationRejection {}
+
+impl From<PaginationRejection> for ApiError {
+    fn from(rejection: PaginationRejection) -> Self {
+        ApiError::bad_request(rejection.message)
+    }
+}
+
+impl IntoResponse for PaginationRejection {
+    fn into_response(self) -> Response {
+        ApiError::from(self).into_response()
+    }
+}
@@ -0,0 +1,188 @@
+use axum::extract::ConnectInfo;
+use axum::http::{HeaderName, Request};
+use std::net::SocketAddr;
+
+/// Tells clients apart, so that each one gets its own quota.
+///
+/// Requests without a key aren't rate limited, e.g. unauthenticated requests with [`User`].
+pub trait KeyExtractor: Clone + Send + Sync + 'static {
+    fn extract<B>(&self, request: &Request<B>) -> Option<String>;
+}
+
+/// Keys requests by the IP address of the connection.
+///
+/// Needs the server to be started with
+/// `into_make_service_with_connect_info::<SocketAddr>()`. Behind a reverse proxy every request
+/// comes from the proxy, use [`ForwardedIp`] there.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PeerIp;
+
+impl KeyExtractor for PeerIp {
+    fn extract<B>(&self, request: &Request<B>) -> Option<String> {
+        request
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string())
+    }
+}
+
+/// Keys requests by the client IP address the trusted reverse proxies in front of the server
+/// report in `X-Forwarded-For`, or by [`PeerIp`] without trusted proxies.
+///
+/// Every proxy appends the address it got the request from, so the client's address is the one
+/// added by the outermost trusted proxy, as many entries from the end as there are trusted
+/// proxies. Anything before it was sent by the client. Only trust proxies that clients can't go
+/// around, since they could pick their own key otherwise.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ForwardedIp {
+    trusted_proxies: usize,
+}
+
+impl ForwardedIp {
+    /// Trusts the `X-Forwarded-For` entries of the `trusted_proxies` proxies in front of the
+    /// server. With 0, requests are keyed by [`PeerIp`] and the header is ignored.
+    pub fn new(trusted_proxies: usize) -> Self {
+        Self { trusted_proxies }
+    }
+}
+
+impl KeyExtractor for ForwardedIp {
+    fn extract<B>(&self, request: &Request<B>) -> Option<String> {
+        if self.trusted_proxies == 0 {
+            return PeerIp.extract(request);
+        }
+        let forwarded: Vec<&str> = request
+            .headers()
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .collect();
+        // Requests with fewer entries didn't come through the proxies
+        forwarded
+            .len()
+            .checked_sub(self.trusted_proxies)
+            .map(|client| forwarded[client])
+            .filter(|ip| !ip.is_empty())
+            .map(str::to_string)
+            .or_else(|| PeerIp.extract(request))
+    }
+}
+
+/// Keys requests by the value of an API key header.
+#[derive(Debug, Clone)]
+pub struct ApiKey {
+    header: HeaderName,
+}
+
+impl ApiKey {
+    pub fn header(header: HeaderName) -> Self {
+        Self { header }
+    }
+}
+
+impl Default for ApiKey {
+    /// Reads the `X-Api-Key` header.
+    fn default() -> Self {
+        Self::header(HeaderName::from_static("x-api-key"))
+    }
+}
+
+impl KeyExtractor for ApiKey {
+    fn extract<B>(&self, request: &Request<B>) -> Option<String> {
+        request
+            .headers()
+            .get(&self.header)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    }
+}
+
+/// Keys requests by the [`CurrentUser`](web_auth::CurrentUser) set by one of the `web-auth`
+/// middlewares, which have to run first.
+#[cfg(feature = "web-auth")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct User;
+
+#[cfg(feature = "web-auth")]
+impl KeyExtractor for User {
+    fn extract<B>(&self, request: &Request<B>) -> Option<String> {
+        request
+            .extensions()
+            .get::<web_auth::CurrentUser>()
+            .map(|user| user.username.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(headers: &[(&str, &str)]) -> Request<()> {
+        let mut request = Request::builder();
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        request.body(()).unwrap()
+    }
+
+    fn direct(headers: &[(&str, &str)]) -> Request<()> {
+        let mut request = request(headers);
+        request
+            .extensions_mut()
+            .insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 4000))));
+        request
+    }
+
+    #[test]
+    fn reads_the_address_added_by_the_outermost_trusted_proxy() {
+        let forwarded = request(&[("x-forwarded-for", "6.6.6.6, 10.0.0.1 ")]);
+        assert_eq!(
+            ForwardedIp::new(1).extract(&forwarded).as_deref(),
+            Some("10.0.0.1")
+        );
+        let forwarded = request(&[
+            ("x-forwarded-for", "6.6.6.6, 10.0.0.1"),
+            ("x-forwarded-for", "10.0.0.2"),
+        ]);
+        assert_eq!(
+            ForwardedIp::new(2).extract(&forwarded).as_deref(),
+            Some("10.0.0.1")
+        );
+
+        let around_the_proxies = direct(&[("x-forwarded-for", "6.6.6.6")]);
+        assert_eq!(
+            ForwardedIp::new(2).extract(&around_the_proxies).as_deref(),
+            Some("127.0.0.1")
+        );
+        assert_eq!(PeerIp.extract(&request(&[])), None);
+    }
+
+    #[test]
+    fn ignores_forwarded_addresses_without_trusted_proxies() {
+        let spoofed = direct(&[("x-forwarded-for", "6.6.6.6")]);
+        let other_spoofed = direct(&[("x-forwarded-for", "7.7.7.7")]);
+        assert_eq!(
+            ForwardedIp::default().extract(&spoofed).as_deref(),
+            Some("127.0.0.1")
+        );
+        assert_eq!(
+            ForwardedIp::default().extract(&other_spoofed),
+            ForwardedIp::default().extract(&spoofed)
+        );
+    }
+
+    #[test]
+    fn reads_api_keys() {
+        let request = request(&[("x-api-key", "secret")]);
+        assert_eq!(
+            ApiKey::default().extract(&request).as_deref(),
+            Some("secret")
+        );
+        assert_eq!(
+            ApiKey::header(HeaderName::from_static("x-other")).extract(&request),
+            None
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{KeyExtractor, Quota};
+use api_error::ApiError;
+use axum::http::{HeaderValue, Request, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use governor::clock::{Clock, DefaultClock};
+use governor::{DefaultKeyedRateLimiter, RateLimiter};
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tower::{Layer, Service};
+
+/// Number of checked requests after which clients that are back to a full quota get forgotten.
+const RETAIN_EVERY: u64 = 1024;
+
+/// Rejects the requests of clients that went over their [`Quota`] with
+/// `429 Too Many Requests` and a `Retry-After` header.
+///
+/// Every service created by the layer shares the same limiter, so apply the layer once per
+/// group of routes that should share a quota.
+#[derive(Debug, Clone)]
+pub struct RateLimitLayer<K> {
+    limiter: Limiter,
+    key: K,
+}
+
+impl<K: KeyExtractor> RateLimitLayer<K> {
+    pub fn new(quota: Quota, key: K) -> Self {
+        Self {
+            limiter: Limiter {
+                governor: Arc::new(RateLimiter::keyed(quota.to_governor())),
+                checks: Arc::default(),
+            },
+            key,
+        }
+    }
+}
+
+impl<S, K: Clone> Layer<S> for RateLimitLayer<K> {
+    type Service = RateLimit<S, K>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit {
+            inner,
+            limiter: self.limiter.clone(),
+            key: self.key.clone(),
+        }
+    }
+}
+
+/// Service created by [`RateLimitLayer`].
+#[derive(Debug, Clone)]
+pub struct RateLimit<S, K> {
+    inner: S,
+    limiter: Limiter,
+    key: K,
+}
+
+/// The keyed governor limiter shared by a layer and its services.
+#[derive(Debug, Clone)]
+struct Limiter {
+    governor: Arc<DefaultKeyedRateLimiter<String>>,
+    checks: Arc<AtomicU64>,
+}
+
+impl Limiter {
+    /// Counts a request of the client `key`, or tells how long it has to wait.
+    fn check(&self, key: String) -> Result<(), Duration> {
+        if self.checks.fetch_add(1, Ordering::Relaxed) % RETAIN_EVERY == RETAIN_EVERY - 1 {
+            self.governor.retain_recent();
+        }
+        self.governor
+            .check_key(&key)
+            .map_err(|not_until| not_until.wait_time_from(DefaultClock::default().now()))
+    }
+}
+
+/// `retry_after` in whole seconds, rounded up, as sent in `Retry-After`.
+fn retry_after_secs(retry_after: Duration) -> u64 {
+    let secs = retry_after.as_secs();
+    if retry_after.subsec_nanos() > 0 {
+        secs + 1
+    } else {
+        secs.max(1)
+    }
+}
+
+impl<S, K, B> Service<Request<B>> for RateLimit<S, K>
+where
+    S: Service<Request<B>, Response = Response, Error = Infallible>,
+    S::Future: Send + 'static,
+    K: KeyExtractor,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = Pin<Box<dyn Future<Output = Result<Response, Infallible>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<B>) -> Self::Future {
+        let limited = self.key.extract(&request).and_then(|key| {
+            let wait = self.limiter.check(key.clone()).err()?;
+            Some((key, wait))
+        });
+        if let Some((key, wait)) = limited {
+            let retry_after = retry_after_secs(wait);
+            tracing::warn!(%key, retry_after, "Rate limit exceeded");
+            let mut response = ApiError::new(StatusCode::TOO_MANY_REQUESTS)
+                .with_detail(format!(
+                    "Too many requests, try again in {retry_after} seconds"
+                ))
+                .into_response();
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+            return Box::pin(async move { Ok(response) });
+        }
+        Box::pin(self.inner.call(request))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ApiKey;
+    use axum::body::Body;
+    use axum::routing::get;
+    use tower::ServiceExt;
+
+    fn app() -> axum::Router {
+        axum::Router::new()
+            .route("/", get(|| async { "OK" }))
+            .layer(RateLimitLayer::new(Quota::per_minute(2), ApiKey::default()))
+    }
+
+    fn request(api_key: Option<&str>) -> Request<Body> {
+        let mut request = Request::get("/");
+        if let Some(api_key) = api_key {
+            request = request.header("x-api-key", api_key);
+        }
+        request.body(Body::empty()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn rejects_clients_over_their_quota() {
+        let app = app();
+
+        for _ in 0..2 {
+            let response = app.clone().oneshot(request(Some("a"))).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let response = app.clone().oneshot(request(Some("a"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+
+        let response = app.oneshot(request(Some("b"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn lets_requests_without_a_key_through() {
+        let app = app();
+
+        for _ in 0..3 {
+            let response = app.clone().oneshot(request(None)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+    }
+
+    #[test]
+    fn rounds_retry_after_up_to_whole_seconds() {
+        assert_eq!(retry_after_secs(Duration::from_millis(1)), 1);
+        assert_eq!(retry_after_secs(Duration::from_millis(2000)), 2);
+        assert_eq!(retry_after_secs(Duration::from_millis(2001)), 3);
+    }
+}
@@ -0,0 +1,17 @@
+//! Rate limiting for the web services, as a tower layer on top of a keyed
+//! [`governor`] rate limiter.
+//!
+//! [`RateLimitLayer`] gives every client a [`Quota`] of requests, telling clients apart with a
+//! [`KeyExtractor`]: their IP address, an API key header or, with the `web-auth` feature, the
+//! authenticated user. Requests over the quota are rejected with `429 Too Many Requests` and a
+//! `Retry-After` header saying when the next one will be accepted.
+
+mod key;
+mod layer;
+mod quota;
+
+#[cfg(feature = "web-auth")]
+pub use key::User;
+pub use key::{ApiKey, ForwardedIp, KeyExtractor, PeerIp};
+pub use layer::{RateLimit, RateLimitLayer};
+pub use quota::Quota;
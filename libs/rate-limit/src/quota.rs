@@ -0,0 +1,105 @@
+use std::num::NonZeroU32;
+use std::time::Duration;
+
+/// How many requests a client may make: one every [`Quota::interval`] on average, with up to
+/// [`Quota::burst`] requests at once after being idle for a while.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    interval: Duration,
+    burst: u32,
+}
+
+impl Quota {
+    /// `requests` per second, all of which may be made at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `requests` is 0.
+    pub fn per_second(requests: u32) -> Self {
+        Self::per_period(Duration::from_secs(1), requests)
+    }
+
+    /// `requests` per minute, all of which may be made at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `requests` is 0.
+    pub fn per_minute(requests: u32) -> Self {
+        Self::per_period(Duration::from_secs(60), requests)
+    }
+
+    /// `requests` per hour, all of which may be made at once.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `requests` is 0.
+    pub fn per_hour(requests: u32) -> Self {
+        Self::per_period(Duration::from_secs(60 * 60), requests)
+    }
+
+    /// One request every `interval`, without bursts.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `interval` is zero.
+    pub fn with_interval(interval: Duration) -> Self {
+        assert!(!interval.is_zero(), "rate limit interval must not be zero");
+        Self { interval, burst: 1 }
+    }
+
+    /// Allows `burst` requests at once, keeping the average rate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `burst` is 0.
+    pub fn allow_burst(mut self, burst: u32) -> Self {
+        assert!(burst > 0, "rate limit burst must not be 0");
+        self.burst = burst;
+        self
+    }
+
+    /// Time between two requests at the average rate.
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Requests that may be made at once.
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+
+    /// The same quota for [`governor`].
+    pub(crate) fn to_governor(self) -> governor::Quota {
+        let burst = NonZeroU32::new(self.burst).expect("rate limit burst is never 0");
+        governor::Quota::with_period(self.interval)
+            .expect("rate limit interval is never zero")
+            .allow_burst(burst)
+    }
+
+    fn per_period(period: Duration, requests: u32) -> Self {
+        assert!(requests > 0, "rate limit must allow at least one request");
+        Self::with_interval(period / requests).allow_burst(requests)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spreads_requests_over_the_period() {
+        let quota = Quota::per_minute(30);
+        assert_eq!(quota.interval(), Duration::from_secs(2));
+        assert_eq!(quota.burst(), 30);
+
+        let quota = Quota::per_second(10).allow_burst(2);
+        assert_eq!(quota.interval(), Duration::from_millis(100));
+        assert_eq!(quota.burst(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one request")]
+    fn rejects_empty_quotas() {
+        Quota::per_hour(0);
+    }
+}
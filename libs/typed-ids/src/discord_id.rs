@@ -0,0 +1,119 @@
+use crate::IdError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A Discord snowflake id, e.g. of a user or a server.
+///
+/// Snowflakes are unsigned 64-bit numbers, stored in signed `BIGINT` columns. Every snowflake
+/// Discord hands out fits in 63 bits, so larger values are rejected rather than wrapped into
+/// negative numbers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "u64", into = "u64")]
+pub struct DiscordId(u64);
+
+impl DiscordId {
+    const KIND: &'static str = "Discord ID";
+
+    pub fn new(id: u64) -> Result<Self, IdError> {
+        if i64::try_from(id).is_ok() {
+            Ok(Self(id))
+        } else {
+            Err(IdError::new(Self::KIND, id))
+        }
+    }
+
+    pub fn get(self) -> u64 {
+        self.0
+    }
+}
+
+impl TryFrom<u64> for DiscordId {
+    type Error = IdError;
+
+    fn try_from(id: u64) -> Result<Self, Self::Error> {
+        Self::new(id)
+    }
+}
+
+impl TryFrom<i64> for DiscordId {
+    type Error = IdError;
+
+    fn try_from(id: i64) -> Result<Self, Self::Error> {
+        u64::try_from(id)
+            .map(Self)
+            .map_err(|_| IdError::new(Self::KIND, id))
+    }
+}
+
+impl From<DiscordId> for u64 {
+    fn from(id: DiscordId) -> Self {
+        id.0
+    }
+}
+
+impl From<DiscordId> for i64 {
+    fn from(id: DiscordId) -> Self {
+        // Checked on construction
+        i64::try_from(id.0).expect("Discord IDs fit in an i64")
+    }
+}
+
+impl PartialEq<u64> for DiscordId {
+    fn eq(&self, other: &u64) -> bool {
+        self.0 == *other
+    }
+}
+
+impl fmt::Display for DiscordId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for DiscordId {
+    type Err = IdError;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        let parsed: u64 = id
+            .trim()
+            .parse()
+            .map_err(|_| IdError::new(Self::KIND, id))?;
+        Self::new(parsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_between_representations() {
+        let id = DiscordId::new(246711053898088448).unwrap();
+        assert_eq!(u64::from(id), 246711053898088448);
+        assert_eq!(i64::from(id), 246711053898088448);
+        assert_eq!(DiscordId::try_from(246711053898088448_i64), Ok(id));
+        assert_eq!("246711053898088448".parse(), Ok(id));
+        assert_eq!(id.to_string(), "246711053898088448");
+    }
+
+    #[test]
+    fn rejects_ids_that_dont_fit_the_database() {
+        assert!(DiscordId::new(u64::MAX).is_err());
+        assert!(DiscordId::new(1 << 63).is_err());
+        assert!(DiscordId::try_from(-1_i64).is_err());
+        assert!("abc".parse::<DiscordId>().is_err());
+        assert_eq!(
+            DiscordId::new(u64::MAX).unwrap_err().to_string(),
+            "'18446744073709551615' is not a valid Discord ID"
+        );
+    }
+
+    #[test]
+    fn serializes_as_a_number() {
+        let id = DiscordId::new(123456789).unwrap();
+        assert_eq!(serde_json::to_string(&id).unwrap(), "123456789");
+        assert_eq!(serde_json::from_str::<DiscordId>("123456789").unwrap(), id);
+        assert!(serde_json::from_str::<DiscordId>("18446744073709551615").is_err());
+    }
+}
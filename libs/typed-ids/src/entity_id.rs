@@ -0,0 +1,189 @@
+use crate::IdError;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::str::FromStr;
+
+/// The id of a row of the entity `T`, stored in a `SERIAL` column.
+///
+/// The type parameter keeps ids of different entities apart, e.g. a name id can't be passed
+/// where a user id is expected. Ids are non-negative `i32`s, exposed as `u32`s.
+pub struct EntityId<T> {
+    value: i32,
+    entity: PhantomData<fn() -> T>,
+}
+
+impl<T> EntityId<T> {
+    const KIND: &'static str = "entity ID";
+
+    pub fn new(id: u32) -> Result<Self, IdError> {
+        i32::try_from(id)
+            .map(Self::from_valid)
+            .map_err(|_| IdError::new(Self::KIND, id))
+    }
+
+    pub fn get(self) -> u32 {
+        // Checked on construction
+        u32::try_from(self.value).expect("entity IDs are non-negative")
+    }
+
+    fn from_valid(value: i32) -> Self {
+        Self {
+            value,
+            entity: PhantomData,
+        }
+    }
+}
+
+// Implemented by hand, deriving would require `T` to implement the traits too.
+
+impl<T> Clone for EntityId<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for EntityId<T> {}
+
+impl<T> fmt::Debug for EntityId<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("EntityId").field(&self.value).finish()
+    }
+}
+
+impl<T> PartialEq for EntityId<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<T> Eq for EntityId<T> {}
+
+impl<T> PartialOrd for EntityId<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for EntityId<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value.cmp(&other.value)
+    }
+}
+
+impl<T> Hash for EntityId<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.value.hash(state);
+    }
+}
+
+impl<T> TryFrom<u32> for EntityId<T> {
+    type Error = IdError;
+
+    fn try_from(id: u32) -> Result<Self, Self::Error> {
+        Self::new(id)
+    }
+}
+
+impl<T> TryFrom<i32> for EntityId<T> {
+    type Error = IdError;
+
+    fn try_from(id: i32) -> Result<Self, Self::Error> {
+        if id >= 0 {
+            Ok(Self::from_valid(id))
+        } else {
+            Err(IdError::new(Self::KIND, id))
+        }
+    }
+}
+
+impl<T> From<EntityId<T>> for u32 {
+    fn from(id: EntityId<T>) -> Self {
+        id.get()
+    }
+}
+
+impl<T> From<EntityId<T>> for i32 {
+    fn from(id: EntityId<T>) -> Self {
+        id.value
+    }
+}
+
+impl<T> PartialEq<u32> for EntityId<T> {
+    fn eq(&self, other: &u32) -> bool {
+        self.get() == *other
+    }
+}
+
+impl<T> fmt::Display for EntityId<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.value.fmt(f)
+    }
+}
+
+impl<T> FromStr for EntityId<T> {
+    type Err = IdError;
+
+    fn from_str(id: &str) -> Result<Self, Self::Err> {
+        let parsed: u32 = id
+            .trim()
+            .parse()
+            .map_err(|_| IdError::new(Self::KIND, id))?;
+        Self::new(parsed)
+    }
+}
+
+impl<T> Serialize for EntityId<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u32(self.get())
+    }
+}
+
+impl<'de, T> Deserialize<'de> for EntityId<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = u32::deserialize(deserializer)?;
+        Self::new(id).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Name;
+
+    type NameId = EntityId<Name>;
+
+    #[test]
+    fn converts_between_representations() {
+        let id = NameId::new(42).unwrap();
+        assert_eq!(u32::from(id), 42);
+        assert_eq!(i32::from(id), 42);
+        assert_eq!(NameId::try_from(42_i32), Ok(id));
+        assert_eq!(" 42 ".parse(), Ok(id));
+        assert_eq!(id.to_string(), "42");
+        assert_eq!(id, 42);
+    }
+
+    #[test]
+    fn rejects_ids_that_dont_fit_the_database() {
+        assert!(NameId::new(u32::MAX).is_err());
+        assert!(NameId::try_from(-1_i32).is_err());
+        assert!("-1".parse::<NameId>().is_err());
+        assert_eq!(
+            NameId::new(1 << 31).unwrap_err().to_string(),
+            "'2147483648' is not a valid entity ID"
+        );
+    }
+
+    #[test]
+    fn serializes_as_a_number() {
+        let id = NameId::new(7).unwrap();
+        assert_eq!(serde_json::to_string(&id).unwrap(), "7");
+        assert_eq!(serde_json::from_str::<NameId>("7").unwrap(), id);
+        assert!(serde_json::from_str::<NameId>("4294967295").is_err());
+        assert!(serde_json::from_str::<NameId>("-7").is_err());
+    }
+}
@@ -0,0 +1,30 @@
+//! Id newtypes that convert between their API and database representations without `as` casts.
+//!
+//! APIs expose ids as unsigned numbers while Postgres only has signed columns. Each type here
+//! only holds values that fit both, so the checks happen once, where an id enters the service,
+//! and every conversion after that is lossless.
+
+mod discord_id;
+mod entity_id;
+#[cfg(feature = "sea-orm")]
+mod sea_orm;
+
+pub use discord_id::DiscordId;
+pub use entity_id::EntityId;
+
+/// Error for a number that isn't a valid id of the given kind.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("'{value}' is not a valid {kind}")]
+pub struct IdError {
+    kind: &'static str,
+    value: String,
+}
+
+impl IdError {
+    fn new(kind: &'static str, value: impl ToString) -> Self {
+        Self {
+            kind,
+            value: value.to_string(),
+        }
+    }
+}
@@ -0,0 +1,124 @@
+//! Lets the ids be used as SeaORM entity fields, stored as `BIGINT` and `INTEGER` respectively.
+//!
+//! This is what `DeriveValueType` would generate, except that values read from the database
+//! are validated, and `TryFromU64` is implemented so the ids can be primary keys.
+
+use crate::{DiscordId, EntityId};
+use sea_orm::sea_query::{self, ArrayType, ColumnType, Nullable, ValueTypeErr};
+use sea_orm::{ColIdx, DbErr, QueryResult, TryFromU64, TryGetError, TryGetable, Value};
+
+impl From<DiscordId> for Value {
+    fn from(id: DiscordId) -> Self {
+        Value::BigInt(Some(id.into()))
+    }
+}
+
+impl TryGetable for DiscordId {
+    fn try_get_by<I: ColIdx>(res: &QueryResult, index: I) -> Result<Self, TryGetError> {
+        let id = i64::try_get_by(res, index)?;
+        DiscordId::try_from(id).map_err(|err| TryGetError::DbErr(DbErr::Type(err.to_string())))
+    }
+}
+
+impl sea_query::ValueType for DiscordId {
+    fn try_from(v: Value) -> Result<Self, ValueTypeErr> {
+        let id = <i64 as sea_query::ValueType>::try_from(v)?;
+        id.try_into().map_err(|_| ValueTypeErr)
+    }
+
+    fn type_name() -> String {
+        "DiscordId".to_string()
+    }
+
+    fn array_type() -> ArrayType {
+        ArrayType::BigInt
+    }
+
+    fn column_type() -> ColumnType {
+        ColumnType::BigInteger
+    }
+}
+
+impl Nullable for DiscordId {
+    fn null() -> Value {
+        Value::BigInt(None)
+    }
+}
+
+impl TryFromU64 for DiscordId {
+    fn try_from_u64(n: u64) -> Result<Self, DbErr> {
+        DiscordId::new(n).map_err(|err| DbErr::Type(err.to_string()))
+    }
+}
+
+impl<T> From<EntityId<T>> for Value {
+    fn from(id: EntityId<T>) -> Self {
+        Value::Int(Some(id.into()))
+    }
+}
+
+impl<T> TryGetable for EntityId<T> {
+    fn try_get_by<I: ColIdx>(res: &QueryResult, index: I) -> Result<Self, TryGetError> {
+        let id = i32::try_get_by(res, index)?;
+        EntityId::try_from(id).map_err(|err| TryGetError::DbErr(DbErr::Type(err.to_string())))
+    }
+}
+
+impl<T> sea_query::ValueType for EntityId<T> {
+    fn try_from(v: Value) -> Result<Self, ValueTypeErr> {
+        let id = <i32 as sea_query::ValueType>::try_from(v)?;
+        id.try_into().map_err(|_| ValueTypeErr)
+    }
+
+    fn type_name() -> String {
+        "EntityId".to_string()
+    }
+
+    fn array_type() -> ArrayType {
+        ArrayType::Int
+    }
+
+    fn column_type() -> ColumnType {
+        ColumnType::Integer
+    }
+}
+
+impl<T> Nullable for EntityId<T> {
+    fn null() -> Value {
+        Value::Int(None)
+    }
+}
+
+impl<T> TryFromU64 for EntityId<T> {
+    fn try_from_u64(n: u64) -> Result<Self, DbErr> {
+        u32::try_from(n)
+            .ok()
+            .and_then(|id| EntityId::new(id).ok())
+            .ok_or_else(|| DbErr::Type(format!("'{n}' is not a valid entity ID")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::sea_query::ValueType;
+
+    #[test]
+    fn round_trips_through_database_values() {
+        let discord_id = DiscordId::new(246711053898088448).unwrap();
+        let value = Value::from(discord_id);
+        assert_eq!(value, Value::BigInt(Some(246711053898088448)));
+        assert_eq!(
+            <DiscordId as ValueType>::try_from(value).ok(),
+            Some(discord_id)
+        );
+        assert!(<DiscordId as ValueType>::try_from(Value::BigInt(Some(-1))).is_err());
+
+        let id = EntityId::<()>::new(7).unwrap();
+        let value = Value::from(id);
+        assert_eq!(value, Value::Int(Some(7)));
+        assert_eq!(<EntityId<()> as ValueType>::try_from(value).ok(), Some(id));
+        assert!(<EntityId<()> as ValueType>::try_from(Value::Int(Some(-1))).is_err());
+        assert!(EntityId::<()>::try_from_u64(u64::MAX).is_err());
+    }
+}
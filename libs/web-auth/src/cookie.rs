@@ -0,0 +1,58 @@
+use axum::http::HeaderMap;
+use axum_extra::extract::cookie::{Cookie, SameSite};
+
+/// Name of the cookie holding the token of a browser session.
+pub const AUTH_COOKIE: &str = "auth_token";
+
+/// Builds the http-only session cookie holding `token`, expiring after `max_age`.
+pub fn auth_cookie(token: String, max_age: chrono::Duration) -> Cookie<'static> {
+    Cookie::build((AUTH_COOKIE, token))
+        .http_only(true)
+        .secure(false) // Set to true in production with HTTPS
+        .same_site(SameSite::Lax)
+        .max_age(time::Duration::seconds(max_age.num_seconds()))
+        .path("/")
+        .build()
+}
+
+/// Extracts the token from an `Authorization: Bearer <token>` header.
+pub fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get("authorization")?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    #[test]
+    fn can_extract_bearer_token() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_static("Bearer abc.def"));
+
+        assert_eq!(bearer_token(&headers), Some("abc.def"));
+    }
+
+    #[test]
+    fn ignores_other_authorization_schemes() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_static("Basic abc"));
+
+        assert_eq!(bearer_token(&headers), None);
+        assert_eq!(bearer_token(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn auth_cookie_is_http_only_and_site_wide() {
+        let cookie = auth_cookie("token".to_string(), chrono::Duration::hours(24));
+
+        assert_eq!(cookie.name(), AUTH_COOKIE);
+        assert_eq!(cookie.http_only(), Some(true));
+        assert_eq!(cookie.path(), Some("/"));
+        assert_eq!(cookie.max_age(), Some(time::Duration::hours(24)));
+    }
+}
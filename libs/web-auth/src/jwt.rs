@@ -0,0 +1,176 @@
+use std::marker::PhantomData;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+use crate::CurrentUser;
+
+pub use jsonwebtoken::errors::Error as JwtError;
+
+/// Claims carried by a token. Services that need more than the username implement this for their
+/// own claims type and use it with [`Jwt`].
+pub trait AuthClaims: Serialize + DeserializeOwned {
+    /// Builds the claims for a token issued to `username` at `iat`, valid until `exp` (both in
+    /// seconds since the epoch).
+    fn issue(username: &str, iat: usize, exp: usize) -> Self;
+
+    /// The user the token was issued to.
+    fn current_user(&self) -> CurrentUser;
+}
+
+/// The default claims: who the token belongs to and when it was issued and expires.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+pub struct Claims {
+    pub exp: usize,       // Expiry time of the token
+    pub iat: usize,       // Issued at time of the token
+    pub username: String, // Username of the authenticated user
+}
+
+impl AuthClaims for Claims {
+    fn issue(username: &str, iat: usize, exp: usize) -> Self {
+        Self {
+            exp,
+            iat,
+            username: username.to_string(),
+        }
+    }
+
+    fn current_user(&self) -> CurrentUser {
+        CurrentUser::new(self.username.clone())
+    }
+}
+
+/// Issues and verifies HS256 tokens signed with a shared secret.
+#[derive(Clone)]
+pub struct Jwt<C = Claims> {
+    secret: String,
+    ttl: chrono::Duration,
+    claims: PhantomData<fn() -> C>,
+}
+
+impl<C> std::fmt::Debug for Jwt<C> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Jwt")
+            .field("secret", &"[redacted]")
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
+
+impl<C: AuthClaims> Jwt<C> {
+    /// Tokens are valid for a day unless changed with [`Jwt::with_ttl`].
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self {
+            secret: secret.into(),
+            ttl: chrono::Duration::hours(24),
+            claims: PhantomData,
+        }
+    }
+
+    /// Sets how long issued tokens stay valid.
+    pub fn with_ttl(mut self, ttl: chrono::Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// How long issued tokens stay valid.
+    pub fn ttl(&self) -> chrono::Duration {
+        self.ttl
+    }
+
+    /// Issues a token for `username`.
+    pub fn encode(&self, username: &str) -> Result<String, JwtError> {
+        let now = chrono::Utc::now();
+        let exp = (now + self.ttl).timestamp() as usize;
+        let iat = now.timestamp() as usize;
+        jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &C::issue(username, iat, exp),
+            &jsonwebtoken::EncodingKey::from_secret(self.secret.as_bytes()),
+        )
+    }
+
+    /// Verifies a token's signature and expiry and returns its claims.
+    pub fn decode(&self, token: &str) -> Result<C, JwtError> {
+        let token_data = jsonwebtoken::decode(
+            token,
+            &jsonwebtoken::DecodingKey::from_secret(self.secret.as_bytes()),
+            &jsonwebtoken::Validation::default(),
+        )?;
+        Ok(token_data.claims)
+    }
+
+    /// The user a valid token belongs to, or `None` if the token doesn't verify.
+    pub fn current_user(&self, token: &str) -> Option<CurrentUser> {
+        self.decode(token).ok().map(|claims| claims.current_user())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn can_decode_an_encoded_token() {
+        let jwt: Jwt = Jwt::new("secret");
+
+        let token = jwt.encode("admin").unwrap();
+        let claims = jwt.decode(&token).unwrap();
+
+        assert_eq!(claims.username, "admin");
+        assert_eq!(claims.exp - claims.iat, 24 * 60 * 60);
+    }
+
+    #[test]
+    fn cannot_decode_a_token_signed_with_another_secret() {
+        let token = Jwt::<Claims>::new("secret").encode("admin").unwrap();
+
+        assert!(Jwt::<Claims>::new("other").current_user(&token).is_none());
+    }
+
+    #[test]
+    fn cannot_decode_an_expired_token() {
+        let jwt: Jwt = Jwt::new("secret").with_ttl(chrono::Duration::hours(-1));
+
+        let token = jwt.encode("admin").unwrap();
+
+        assert!(jwt.decode(&token).is_err());
+    }
+
+    #[test]
+    fn can_use_custom_claims() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        struct AdminClaims {
+            exp: usize,
+            iat: usize,
+            sub: String,
+            admin: bool,
+        }
+
+        impl AuthClaims for AdminClaims {
+            fn issue(username: &str, iat: usize, exp: usize) -> Self {
+                Self {
+                    exp,
+                    iat,
+                    sub: username.to_string(),
+                    admin: true,
+                }
+            }
+
+            fn current_user(&self) -> CurrentUser {
+                CurrentUser::new(self.sub.clone())
+            }
+        }
+
+        let jwt: Jwt<AdminClaims> = Jwt::new("secret");
+
+        let token = jwt.encode("root").unwrap();
+        let claims = jwt.decode(&token).unwrap();
+
+        assert!(claims.admin);
+        assert_eq!(
+            jwt.current_user(&token),
+            Some(CurrentUser::new("root".to_string()))
+        );
+    }
+}
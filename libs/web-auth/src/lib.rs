@@ -0,0 +1,25 @@
+//! JWT based authentication shared by the web services: issuing and verifying tokens, the auth
+//! cookie, and axum middlewares that expose the authenticated [`CurrentUser`] to handlers.
+
+pub mod cookie;
+pub mod jwt;
+pub mod middleware;
+
+pub use cookie::{AUTH_COOKIE, auth_cookie, bearer_token};
+pub use jwt::{AuthClaims, Claims, Jwt, JwtError};
+pub use middleware::{
+    AuthProvider, bearer_auth_middleware, cookie_auth_middleware, login_redirect_middleware,
+};
+
+/// Represents the currently authenticated user.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurrentUser {
+    pub username: String,
+}
+
+impl CurrentUser {
+    /// Creates a new CurrentUser instance.
+    pub fn new(username: String) -> Self {
+        Self { username }
+    }
+}
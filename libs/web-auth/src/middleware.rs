@@ -0,0 +1,174 @@
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::http::HeaderMap;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Redirect, Response};
+use axum_extra::extract::CookieJar;
+
+use crate::{AUTH_COOKIE, AuthClaims, CurrentUser, Jwt, bearer_token};
+
+/// State that knows how to verify tokens, which is all the authentication middlewares need.
+pub trait AuthProvider: Clone + Send + Sync + 'static {
+    type Claims: AuthClaims;
+
+    fn jwt(&self) -> &Jwt<Self::Claims>;
+}
+
+impl<T: AuthProvider> AuthProvider for Arc<T> {
+    type Claims = T::Claims;
+
+    fn jwt(&self) -> &Jwt<Self::Claims> {
+        (**self).jwt()
+    }
+}
+
+/// Authentication middleware that checks the auth cookie for a valid JWT token and sets the
+/// CurrentUser extension. It does not reject or redirect anything by itself.
+pub async fn cookie_auth_middleware<S: AuthProvider>(
+    State(state): State<S>,
+    jar: CookieJar,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if let Some(user) = jar
+        .get(AUTH_COOKIE)
+        .and_then(|cookie| state.jwt().current_user(cookie.value()))
+    {
+        request.extensions_mut().insert(user);
+    }
+
+    next.run(request).await
+}
+
+/// Authentication middleware that checks the Authorization Bearer header for a valid JWT token
+/// and sets the CurrentUser extension. It does not reject anything by itself.
+pub async fn bearer_auth_middleware<S: AuthProvider>(
+    State(state): State<S>,
+    headers: HeaderMap,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if let Some(user) = bearer_token(&headers).and_then(|token| state.jwt().current_user(token)) {
+        request.extensions_mut().insert(user);
+    }
+
+    next.run(request).await
+}
+
+/// Login redirect middleware that redirects unauthenticated users to the login page.
+/// This middleware should be applied after one of the authentication middlewares.
+pub async fn login_redirect_middleware(request: Request, next: Next) -> Response {
+    if request.extensions().get::<CurrentUser>().is_none() {
+        return Redirect::to("/login").into_response();
+    }
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Claims;
+    use axum::body::Body;
+    use axum::http::StatusCode;
+    use axum::middleware::{from_fn, from_fn_with_state};
+    use tower::ServiceExt;
+
+    #[derive(Clone)]
+    struct TestState {
+        jwt: Jwt,
+    }
+
+    impl AuthProvider for TestState {
+        type Claims = Claims;
+
+        fn jwt(&self) -> &Jwt {
+            &self.jwt
+        }
+    }
+
+    fn app() -> (axum::Router, Arc<TestState>) {
+        let state = Arc::new(TestState {
+            jwt: Jwt::new("test_secret"),
+        });
+        let app = axum::Router::new()
+            .route(
+                "/whoami",
+                axum::routing::get(
+                    |user: axum::Extension<CurrentUser>| async move { user.0.username },
+                ),
+            )
+            .layer(from_fn(login_redirect_middleware))
+            .layer(from_fn_with_state(
+                state.clone(),
+                cookie_auth_middleware::<Arc<TestState>>,
+            ))
+            .layer(from_fn_with_state(
+                state.clone(),
+                bearer_auth_middleware::<Arc<TestState>>,
+            ));
+        (app, state)
+    }
+
+    async fn send(app: axum::Router, request: Request<Body>) -> (StatusCode, String) {
+        let response = app.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, String::from_utf8(body.to_vec()).unwrap())
+    }
+
+    #[tokio::test]
+    async fn redirects_unauthenticated_requests_to_login() {
+        let (app, _) = app();
+
+        let response = app
+            .oneshot(Request::get("/whoami").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers().get("location").unwrap(), "/login");
+    }
+
+    #[tokio::test]
+    async fn can_authenticate_with_cookie() {
+        let (app, state) = app();
+        let token = state.jwt.encode("admin").unwrap();
+
+        let request = Request::get("/whoami")
+            .header("cookie", format!("{AUTH_COOKIE}={token}"))
+            .body(Body::empty())
+            .unwrap();
+
+        assert_eq!(send(app, request).await, (StatusCode::OK, "admin".into()));
+    }
+
+    #[tokio::test]
+    async fn can_authenticate_with_bearer_token() {
+        let (app, state) = app();
+        let token = state.jwt.encode("admin").unwrap();
+
+        let request = Request::get("/whoami")
+            .header("authorization", format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap();
+
+        assert_eq!(send(app, request).await, (StatusCode::OK, "admin".into()));
+    }
+
+    #[tokio::test]
+    async fn ignores_tokens_that_do_not_verify() {
+        let (app, _) = app();
+        let token = Jwt::<Claims>::new("other_secret").encode("admin").unwrap();
+
+        let request = Request::get("/whoami")
+            .header("authorization", format!("Bearer {token}"))
+            .body(Body::empty())
+            .unwrap();
+
+        assert_eq!(send(app, request).await.0, StatusCode::SEE_OTHER);
+    }
+}
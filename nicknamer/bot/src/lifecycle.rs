@@ -0,0 +1,173 @@
+//! Starting and stopping the bot's Discord client and web server together.
+//!
+//! When the process is asked to stop, `/health` starts failing straight away so that nothing new
+//! is routed to the bot, then the Discord shards are shut down, and the web server only stops
+//! once the bot has, so that it keeps answering health checks in the meantime.
+
+use axum::extract::State;
+use axum::http::StatusCode;
+use tokio::sync::watch;
+use tracing::{error, info};
+
+/// How far the bot is along in its life, in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    Running,
+    /// Asked to stop, and waiting for the Discord client to finish
+    ShuttingDown,
+    /// The Discord client has finished, so the web server can too
+    Stopped,
+}
+
+/// Shared view of the bot's [`Stage`], which the tasks wait on to know when to stop.
+#[derive(Clone)]
+pub struct Lifecycle {
+    stage: watch::Sender<Stage>,
+}
+
+impl Default for Lifecycle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Lifecycle {
+    pub fn new() -> Self {
+        Self {
+            stage: watch::Sender::new(Stage::Running),
+        }
+    }
+
+    pub fn stage(&self) -> Stage {
+        *self.stage.borrow()
+    }
+
+    /// Starts shutting down, unless that's already happening.
+    pub fn shut_down(&self) {
+        self.advance_to(Stage::ShuttingDown);
+    }
+
+    /// Marks the Discord client as finished.
+    pub fn stop(&self) {
+        self.advance_to(Stage::Stopped);
+    }
+
+    /// Resolves once the bot has reached `stage`, or gone past it.
+    ///
+    /// The future doesn't borrow the lifecycle, so that it can be handed to spawned tasks.
+    pub fn reached(&self, stage: Stage) -> impl Future<Output = ()> + Send + 'static {
+        let mut receiver = self.stage.subscribe();
+        async move {
+            // Only fails once every sender is dropped, and then the stage can't change anymore
+            let _ = receiver.wait_for(|current| *current >= stage).await;
+        }
+    }
+
+    fn advance_to(&self, stage: Stage) {
+        self.stage.send_if_modified(|current| {
+            let advanced = *current < stage;
+            if advanced {
+                info!("Bot lifecycle: {:?} -> {:?}", current, stage);
+                *current = stage;
+            }
+            advanced
+        });
+    }
+}
+
+/// Health check, which fails as soon as the bot starts shutting down.
+pub async fn health_check(State(lifecycle): State<Lifecycle>) -> (StatusCode, &'static str) {
+    match lifecycle.stage() {
+        Stage::Running => (StatusCode::OK, "OK"),
+        Stage::ShuttingDown | Stage::Stopped => (StatusCode::SERVICE_UNAVAILABLE, "Shutting down"),
+    }
+}
+
+/// Shuts the bot down once the process is asked to stop, with Ctrl+C or, on Unix, SIGTERM.
+pub async fn shut_down_on_signal(lifecycle: Lifecycle) {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl+C: {}", err);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(err) => {
+                error!("Failed to listen for SIGTERM: {}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+        // Nothing left to wait for if the bot stopped by itself
+        _ = lifecycle.reached(Stage::ShuttingDown) => return,
+    }
+    info!("Shutdown signal received");
+    lifecycle.shut_down();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn health_check_fails_once_shutting_down() {
+        // Arrange
+        let lifecycle = Lifecycle::new();
+
+        // Act
+        let (running, _) = health_check(State(lifecycle.clone())).await;
+        lifecycle.shut_down();
+        let (shutting_down, body) = health_check(State(lifecycle.clone())).await;
+
+        // Assert
+        assert_eq!(running, StatusCode::OK);
+        assert_eq!(shutting_down, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body, "Shutting down");
+    }
+
+    #[tokio::test]
+    async fn stages_only_move_forward() {
+        let lifecycle = Lifecycle::new();
+
+        lifecycle.stop();
+        lifecycle.shut_down();
+
+        assert_eq!(lifecycle.stage(), Stage::Stopped);
+    }
+
+    #[tokio::test]
+    async fn waiters_wake_when_their_stage_is_reached_or_passed() {
+        // Arrange
+        let lifecycle = Lifecycle::new();
+        let shutting_down = tokio::spawn({
+            let lifecycle = lifecycle.clone();
+            async move { lifecycle.reached(Stage::ShuttingDown).await }
+        });
+        let stopped = tokio::spawn({
+            let lifecycle = lifecycle.clone();
+            async move { lifecycle.reached(Stage::Stopped).await }
+        });
+        tokio::task::yield_now().await;
+
+        // Act
+        lifecycle.stop();
+
+        // Assert
+        let timeout = Duration::from_secs(1);
+        assert!(tokio::time::timeout(timeout, shutting_down).await.is_ok());
+        assert!(tokio::time::timeout(timeout, stopped).await.is_ok());
+    }
+}
@@ -1,31 +1,54 @@
+mod lifecycle;
 mod nicknamer;
 
-use self::nicknamer::config::Config;
+use self::lifecycle::{Lifecycle, Stage, health_check, shut_down_on_signal};
+use self::nicknamer::authorization::authorize;
+use self::nicknamer::config::{Config, ConfigFiles};
 use self::nicknamer::connectors::discord;
+use self::nicknamer::connectors::discord::DryRunConnector;
 use self::nicknamer::connectors::discord::serenity::{
-    Context as PoiseContext, SerenityDiscordConnector,
+    ChannelReporter, Context as PoiseContext, FrameworkError, SerenityDiscordConnector,
+    SerenityGuildConnector,
 };
-use self::nicknamer::names::EmbeddedNamesRepository;
+use self::nicknamer::cooldowns::{apply_cooldowns, cooldown_reply};
+use self::nicknamer::enforcement::{NicknameEnforcer, RecentNicknames};
+use self::nicknamer::history::{
+    ConfiguredHistoryRepository, MemoryHistoryRepository, record_nickname,
+};
+use self::nicknamer::metrics;
+use self::nicknamer::names::ConfiguredNamesRepository;
+use self::nicknamer::privacy::{ConfiguredOptOutRepository, answer_direct_message};
+use self::nicknamer::reload::{Settings, Snapshot, watch_config_files};
+use self::nicknamer::reporting::sentry::SentryReporter;
+use self::nicknamer::reporting::{ErrorReport, ErrorReporter, report_to_all};
 use crate::nicknamer::{Nicknamer, NicknamerImpl};
 use anyhow::Context as AnyhowContext;
 use axum::Router;
 use include_dir::{Dir, include_dir};
+use observability::{LogFormat, Metrics, RequestIdLayer, TraceContext};
 use poise::serenity_prelude as serenity;
-use poise::serenity_prelude::{FullEvent, Member, Message};
-use tracing::{debug, info};
+use poise::serenity_prelude::{FullEvent, GuildId, Member, Message};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, error, info};
 
 static CONFIG_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/config");
 
+const COMMAND_PREFIX: &str = "~";
+
+/// How often the config files are checked for changes, when they're read from a directory
+const CONFIG_WATCH_INTERVAL: Duration = Duration::from_secs(10);
+
 /// Show this menu
 #[tracing::instrument(skip(ctx))]
-#[poise::command(prefix_command)]
+#[poise::command(prefix_command, slash_command)]
 pub async fn help(
     ctx: PoiseContext<'_>,
     #[description = "Specific command to show help about"] command: Option<String>,
 ) -> anyhow::Result<()> {
     let config = poise::builtins::HelpConfiguration {
         extra_text_at_bottom: "\
-Type ~help command for more info on a command.",
+Type ~help command or /help command for more info on a command.",
         ..Default::default()
     };
     poise::builtins::help(ctx, command.as_deref(), config).await?;
@@ -36,42 +59,118 @@ Type ~help command for more info on a command.",
 ///
 /// Any instance of bot connected to the server will respond with "Pong!" and some runtime information.
 #[tracing::instrument(skip(ctx))]
-#[poise::command(prefix_command)]
+#[poise::command(prefix_command, slash_command)]
 async fn ping(ctx: PoiseContext<'_>) -> anyhow::Result<()> {
     ctx.reply("Pong!").await?;
     Ok(())
 }
 
 /// Changes the nickname for a member into a new
+///
+/// Add `preview` to see what would change without changing anything, e.g.
+/// `~nick @member Alice preview`.
 #[tracing::instrument(skip(ctx))]
-#[poise::command(prefix_command)]
+#[poise::command(prefix_command, slash_command)]
 async fn nick(
     ctx: PoiseContext<'_>,
-    #[description = "The specific member to reveal the name of"] member: Member,
+    #[description = "The member whose nickname to change"] member: Member,
     #[description = "The new nickname to set"] nickname: String,
+    #[description = "Only show what would change"]
+    #[flag]
+    preview: bool,
 ) -> anyhow::Result<()> {
-    let connector = SerenityDiscordConnector::new(ctx);
-    let nicknamer_config = &ctx.data().config.nicknamer;
-    let nicknamer = NicknamerImpl::new(&ctx.data().names_repository, &connector, nicknamer_config);
-    nicknamer.change_nickname(&member.into(), &nickname).await?;
+    let command = NicknamerCommand::with_preview(ctx, preview);
+    command
+        .nicknamer()
+        .change_nickname(&member.into(), &nickname)
+        .await?;
+    command.connector.send_summary().await?;
     Ok(())
 }
 
+/// Changes the nickname of everyone in this channel to their real name
+///
+/// I'll list the changes first, and only make them if you react with ✅ within a minute. Add
+/// `preview` to see how the changes go without making them, e.g. `~nick-all preview`.
+#[tracing::instrument(skip(ctx))]
+#[poise::command(prefix_command, slash_command, rename = "nick-all")]
+async fn nick_all(
+    ctx: PoiseContext<'_>,
+    #[description = "Only show what would change"]
+    #[flag]
+    preview: bool,
+) -> anyhow::Result<()> {
+    let command = NicknamerCommand::with_preview(ctx, preview);
+    command.nicknamer().nick_all().await?;
+    command.connector.send_summary().await?;
+    Ok(())
+}
+
+/// What commands run the nicknamer with, set up the same way for each of them.
+///
+/// Previews only pretend to change nicknames, so they record them in a history that's thrown
+/// away afterwards, and don't ask enforcement to leave them alone.
+struct NicknamerCommand<'a> {
+    data: &'a discord::serenity::Data<ConfiguredNamesRepository>,
+    /// The settings the command started with, even if they're reloaded while it runs
+    settings: Arc<Snapshot<ConfiguredNamesRepository>>,
+    connector: DryRunConnector<SerenityDiscordConnector<'a>>,
+    preview_history: ConfiguredHistoryRepository,
+}
+
+impl<'a> NicknamerCommand<'a> {
+    fn new(ctx: PoiseContext<'a>) -> Self {
+        Self::with_preview(ctx, false)
+    }
+
+    fn with_preview(ctx: PoiseContext<'a>, preview: bool) -> Self {
+        let data = ctx.data();
+        let settings = data.settings.current();
+        let connector = DryRunConnector::new(
+            SerenityDiscordConnector::new(ctx).with_retry_policy(settings.config.retry),
+            preview,
+        );
+        Self {
+            data,
+            settings,
+            connector,
+            preview_history: ConfiguredHistoryRepository::Memory(MemoryHistoryRepository::new()),
+        }
+    }
+
+    fn nicknamer(&self) -> impl Nicknamer + '_ {
+        let nicknamer = NicknamerImpl::new(
+            &self.settings.names_repository,
+            &self.connector,
+            &self.data.opt_outs,
+            if self.connector.is_dry_run() {
+                &self.preview_history
+            } else {
+                &self.data.history
+            },
+            &self.settings.config.nicknamer,
+        );
+        if self.connector.is_dry_run() {
+            nicknamer
+        } else {
+            nicknamer.with_recent_nicknames(&self.data.recent_nicknames)
+        }
+    }
+}
+
 /// Reveal members' true names, greatly diminishing their power level
 ///
 /// Specifically, I'll reveal the names of members that can access this channel
 ///
 /// You can also tag another member and I'll reveal the name of that person, regardless of whether they can access this channel or not
 #[tracing::instrument(skip(ctx))]
-#[poise::command(prefix_command)]
+#[poise::command(prefix_command, slash_command)]
 async fn reveal(
     ctx: PoiseContext<'_>,
     #[description = "The specific member to reveal the name of"] member: Option<Member>,
 ) -> anyhow::Result<()> {
-    // Use the names_repository from the Data struct via the wrapper
-    let connector = SerenityDiscordConnector::new(ctx);
-    let nicknamer_config = &ctx.data().config.nicknamer;
-    let nicknamer = NicknamerImpl::new(&ctx.data().names_repository, &connector, nicknamer_config);
+    let command = NicknamerCommand::new(ctx);
+    let nicknamer = command.nicknamer();
     match member {
         Some(member) => {
             nicknamer.reveal(&member.into()).await?;
@@ -84,55 +183,187 @@ async fn reveal(
     }
 }
 
-#[tokio::main]
-async fn main() -> anyhow::Result<()> {
-    configure_logging();
+/// Remembers the real name of a member
+///
+/// Only members with the name editor role can change real names.
+#[tracing::instrument(skip(ctx))]
+#[poise::command(prefix_command, slash_command, rename = "add-name")]
+async fn add_name(
+    ctx: PoiseContext<'_>,
+    #[description = "The member whose real name to remember"] member: Member,
+    #[description = "Their real name"]
+    #[rest]
+    name: String,
+) -> anyhow::Result<()> {
+    let command = NicknamerCommand::new(ctx);
+    let nicknamer = command.nicknamer();
+    nicknamer.add_name(&member.into(), &name).await?;
+    Ok(())
+}
 
-    tokio::spawn(start_web_server());
+/// Forgets the real name of a member
+///
+/// Only members with the name editor role can change real names.
+#[tracing::instrument(skip(ctx))]
+#[poise::command(prefix_command, slash_command, rename = "remove-name")]
+async fn remove_name(
+    ctx: PoiseContext<'_>,
+    #[description = "The member whose real name to forget"] member: Member,
+) -> anyhow::Result<()> {
+    let command = NicknamerCommand::new(ctx);
+    let nicknamer = command.nicknamer();
+    nicknamer.remove_name(&member.into()).await?;
+    Ok(())
+}
 
-    start_discord_bot()
-        .await
-        .context("Discord bot failed to start or encountered a critical error during operation")?;
+/// Imports real names from an attached YAML or CSV file
+///
+/// I'll list the names first, and only import them if you react with ✅ within a minute.
+/// Members who already have a real name keep it. Only members with the name editor role can
+/// import real names.
+#[tracing::instrument(skip(ctx))]
+#[poise::command(prefix_command, slash_command, rename = "import-names")]
+async fn import_names(
+    ctx: PoiseContext<'_>,
+    #[description = "A .yml or .csv file of Discord IDs and real names"] file: serenity::Attachment,
+) -> anyhow::Result<()> {
+    let command = NicknamerCommand::new(ctx);
+    let nicknamer = command.nicknamer();
+    nicknamer.import_names(&file.into()).await?;
+    Ok(())
+}
 
+/// Finds members by part of their real name, even misspelled
+///
+/// For example, `~whois Ali` finds Alice and Alicia, and so does `~whois Alcie`.
+#[tracing::instrument(skip(ctx))]
+#[poise::command(prefix_command, slash_command)]
+async fn whois(
+    ctx: PoiseContext<'_>,
+    #[description = "Part of the real name to look for"]
+    #[rest]
+    query: String,
+) -> anyhow::Result<()> {
+    let command = NicknamerCommand::new(ctx);
+    let nicknamer = command.nicknamer();
+    nicknamer.whois(&query).await?;
     Ok(())
 }
 
-fn configure_logging() {
-    // The log4rs configuration is removed as we are switching to tracing.
-    // If specific log4rs features like file output or complex filtering were used,
-    // equivalent tracing subscribers and layers would need to be configured here.
-    tracing_subscriber::fmt().init();
+/// Lists the nicknames a member took, newest first
+///
+/// I list ten at a time, ask for a later page to see older ones.
+#[tracing::instrument(skip(ctx))]
+#[poise::command(prefix_command, slash_command)]
+async fn history(
+    ctx: PoiseContext<'_>,
+    #[description = "The member whose nicknames to list"] member: Member,
+    #[description = "The page to list, starting at 1"] page: Option<u64>,
+) -> anyhow::Result<()> {
+    let command = NicknamerCommand::new(ctx);
+    let nicknamer = command.nicknamer();
+    nicknamer.history(&member.into(), page.unwrap_or(1)).await?;
+    Ok(())
 }
 
-#[tracing::instrument]
-async fn start_web_server() {
-    let app = Router::new().route("/health", axum::routing::get(health_check));
+/// Reloads the configuration and real names, for administrators
+///
+/// Changes to the config files are picked up within a few seconds anyway, this is for when
+/// you can't wait.
+#[tracing::instrument(skip(ctx))]
+#[poise::command(
+    prefix_command,
+    slash_command,
+    rename = "reload-config",
+    required_permissions = "ADMINISTRATOR",
+    default_member_permissions = "ADMINISTRATOR"
+)]
+async fn reload_config(ctx: PoiseContext<'_>) -> anyhow::Result<()> {
+    let data = ctx.data();
+    let reply = match data.settings.reload(&data.config_files) {
+        Err(err) => {
+            error!("Failed to reload the configuration: {:?}", err);
+            format!("Kept the old configuration, the new one is broken: {:#}", err)
+        }
+        Ok(()) if data.config_files == ConfigFiles::BuiltIn => {
+            "My configuration is built in, set NICKNAMER_CONFIG_DIR to change it without rebuilding me"
+                .to_string()
+        }
+        Ok(()) => "Reloaded the configuration and real names".to_string(),
+    };
+    ctx.reply(reply).await?;
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    observability::init_tracing(LogFormat::from_env(), &[]);
+    let metrics = Metrics::install().context("Failed to install the metrics recorder")?;
+    let lifecycle = Lifecycle::new();
+
+    let web_server = tokio::spawn(start_web_server(metrics, lifecycle.clone()));
+    tokio::spawn(shut_down_on_signal(lifecycle.clone()));
+
+    let bot_result = start_discord_bot(lifecycle.clone()).await;
+    lifecycle.stop();
+    match web_server.await {
+        Ok(Ok(())) => info!("Web server stopped"),
+        Ok(Err(err)) => error!("Web server failed: {:?}", err),
+        Err(err) => error!("Web server task failed: {}", err),
+    }
+
+    bot_result
+        .context("Discord bot failed to start or encountered a critical error during operation")
+}
+
+/// Serves the health check and metrics until the Discord bot has stopped.
+///
+/// The bot is shut down if the web server can't run, as it couldn't be monitored.
+#[tracing::instrument(skip_all)]
+async fn start_web_server(metrics: Metrics, lifecycle: Lifecycle) -> anyhow::Result<()> {
+    let app = Router::new()
+        .route("/health", axum::routing::get(health_check))
+        .with_state(lifecycle.clone())
+        .merge(metrics.router())
+        .layer(RequestIdLayer);
     let port = std::env::var("PORT")
         .ok()
         .and_then(|s| s.parse::<u16>().ok())
         .unwrap_or(3030);
     let addr = std::net::SocketAddr::from(([0, 0, 0, 0], port));
-    let listener = tokio::net::TcpListener::bind(addr)
-        .await
-        .expect("Failed to bind web server");
-    info!("Web server running on http://{}", addr);
-
-    axum::serve(listener, app.into_make_service())
-        .await
-        .expect("Web server encountered an error");
-}
+    let served = async {
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .context("Failed to bind web server")?;
+        info!("Web server running on http://{}", addr);
 
-async fn health_check() -> &'static str {
-    "OK"
+        axum::serve(listener, app.into_make_service())
+            .with_graceful_shutdown(lifecycle.reached(Stage::Stopped))
+            .await
+            .context("Web server encountered an error")
+    };
+    let result = served.await;
+    if result.is_err() {
+        lifecycle.shut_down();
+    }
+    result
 }
 
-#[tracing::instrument]
-async fn start_discord_bot() -> anyhow::Result<()> {
+/// Runs the Discord bot until it's shut down, or stops by itself.
+#[tracing::instrument(skip_all)]
+async fn start_discord_bot(lifecycle: Lifecycle) -> anyhow::Result<()> {
     info!("Initiating Discord bot startup sequence...");
     let mut client = configure_discord_bot()
         .await
         .context("Discord bot configuration failed")?;
 
+    let shard_manager = client.shard_manager.clone();
+    tokio::spawn(async move {
+        lifecycle.reached(Stage::ShuttingDown).await;
+        info!("Shutting down the Discord shards...");
+        shard_manager.shutdown_all().await;
+    });
+
     info!("Discord bot configured. Starting bot's main loop...");
     client
         .start()
@@ -150,24 +381,79 @@ async fn configure_discord_bot() -> anyhow::Result<serenity::Client> {
     let intents = serenity::GatewayIntents::non_privileged()
         | serenity::GatewayIntents::MESSAGE_CONTENT
         | serenity::GatewayIntents::GUILD_MESSAGES
-        | serenity::GatewayIntents::GUILD_PRESENCES;
+        | serenity::GatewayIntents::GUILD_PRESENCES
+        | serenity::GatewayIntents::GUILD_MEMBERS;
+    let config_files = ConfigFiles::from_env();
+    let config =
+        Config::load(&config_files).context("Failed to load configuration for Discord bot")?;
+    let mut commands = vec![
+        help(),
+        ping(),
+        reveal(),
+        nick(),
+        nick_all(),
+        add_name(),
+        remove_name(),
+        import_names(),
+        whois(),
+        history(),
+        reload_config(),
+    ];
+    apply_cooldowns(&mut commands, &config.nicknamer.cooldowns);
+    let sentry = SentryReporter::from_env().context("SENTRY_DSN is not a valid Sentry DSN")?;
 
     let framework = poise::Framework::<
-        discord::serenity::Data<EmbeddedNamesRepository>,
+        discord::serenity::Data<ConfiguredNamesRepository>,
         anyhow::Error,
     >::builder()
     .options(poise::FrameworkOptions {
-        commands: vec![help(), ping(), reveal(), nick()],
+        commands,
+        pre_command: |ctx| {
+            Box::pin(async move {
+                let trace = TraceContext::new_root();
+                info!(
+                    trace_id = %trace.trace_id(),
+                    "{} is running {}",
+                    ctx.author().name,
+                    ctx.command().qualified_name
+                );
+                ctx.set_invocation_data(trace).await;
+                metrics::command_executed(&ctx.command().qualified_name);
+            })
+        },
+        command_check: Some(|ctx| Box::pin(check_permissions(ctx))),
+        on_error: |error| Box::pin(on_error(error)),
         prefix_options: poise::PrefixFrameworkOptions {
-            prefix: Some("~".into()),
+            prefix: Some(COMMAND_PREFIX.into()),
             ..Default::default()
         },
-        event_handler: |ctx, event, _framework, _data| {
+        event_handler: |ctx, event, _framework, data| {
             Box::pin(async move {
                 match &event {
+                    // Commands sent in direct messages are left to Poise
+                    FullEvent::Message { new_message }
+                        if new_message.guild_id.is_none()
+                            && !new_message.author.bot
+                            && !new_message.content.starts_with(COMMAND_PREFIX) =>
+                    {
+                        on_direct_message(ctx, data, new_message).await;
+                    }
                     FullEvent::Message { new_message } => {
                         on_message_create(ctx, new_message).await;
                     }
+                    FullEvent::GuildMemberAddition { new_member } => {
+                        on_member_named(ctx, data, new_member).await;
+                    }
+                    FullEvent::GuildMemberUpdate {
+                        old_if_available,
+                        new: Some(new),
+                        ..
+                    } if old_if_available
+                        .as_ref()
+                        .is_none_or(|old| old.nick != new.nick) =>
+                    {
+                        on_member_named(ctx, data, new).await;
+                    }
                     _ => debug!("Unhandled event: {:?}", event),
                 }
                 Ok(())
@@ -175,15 +461,51 @@ async fn configure_discord_bot() -> anyhow::Result<serenity::Client> {
         },
         ..Default::default()
     })
-    .setup(|ctx, _ready, framework| {
+    .setup(move |ctx, _ready, framework| {
         Box::pin(async move {
-            poise::builtins::register_globally(ctx, &framework.options().commands)
-                .await
-                .context("Failed to register Discord commands globally")?;
+            let commands = &framework.options().commands;
+            match config.commands.register_in_guild {
+                Some(guild_id) => {
+                    poise::builtins::register_in_guild(ctx, commands, GuildId::new(guild_id))
+                        .await
+                        .context("Failed to register Discord commands in the guild")?;
+                    info!("Registered Discord commands in guild {}", guild_id);
+                }
+                None => {
+                    poise::builtins::register_globally(ctx, commands)
+                        .await
+                        .context("Failed to register Discord commands globally")?;
+                }
+            }
+            let opt_outs = ConfiguredOptOutRepository::from_config(&config.privacy);
+            let history = ConfiguredHistoryRepository::from_config(&config.history);
+            let names_repository =
+                ConfiguredNamesRepository::from_config(&config.names, &config_files)
+                    .context("Failed to set up the names repository for Discord bot")?;
+            let settings = Arc::new(Settings::new(Snapshot {
+                config,
+                names_repository,
+            }));
+            tokio::spawn(watch_config_files(
+                config_files.clone(),
+                CONFIG_WATCH_INTERVAL,
+                {
+                    let settings = settings.clone();
+                    let config_files = config_files.clone();
+                    move || {
+                        if let Err(err) = settings.reload(&config_files) {
+                            error!("Failed to reload the changed configuration: {:?}", err);
+                        }
+                    }
+                },
+            ));
             Ok(discord::serenity::Data {
-                names_repository: EmbeddedNamesRepository::new()
-                    .context("Failed to load embedded names repository for Discord bot")?,
-                config: Config::new().context("Failed to load configuration for Discord bot")?,
+                settings,
+                config_files,
+                opt_outs,
+                history,
+                recent_nicknames: RecentNicknames::new(),
+                sentry,
             })
         })
     })
@@ -195,6 +517,178 @@ async fn configure_discord_bot() -> anyhow::Result<serenity::Client> {
         .context("Failed to create Discord client")
 }
 
+/// Only lets members run the commands their roles allow, as configured in `[nicknamer.permissions]`
+async fn check_permissions(ctx: PoiseContext<'_>) -> anyhow::Result<bool> {
+    let settings = ctx.data().settings.current();
+    let connector = SerenityDiscordConnector::new(ctx).with_retry_policy(settings.config.retry);
+    authorize(
+        &connector,
+        &settings.config.nicknamer.permissions,
+        &ctx.command().qualified_name,
+    )
+    .await?;
+    Ok(true)
+}
+
+/// Tells whoever ran a command what went wrong with it
+///
+/// Replies to slash commands are only shown to the person who ran the command. Commands that
+/// failed are also reported to the ops channel and Sentry. Errors that don't come from running
+/// or parsing a command, a cooldown or a permission check, are left to Poise.
+async fn on_error(error: FrameworkError<'_>) {
+    let (ctx, reply) = match error {
+        poise::FrameworkError::Command { error, ctx, .. } => {
+            let trace = command_trace(ctx).await;
+            error!(
+                trace_id = %trace.trace_id(),
+                "Command {} failed: {:?}",
+                ctx.command().qualified_name,
+                error
+            );
+            report_failure(ctx, trace, &error).await;
+            (ctx, format!("Something went wrong: {}", error))
+        }
+        poise::FrameworkError::CooldownHit {
+            remaining_cooldown,
+            ctx,
+            ..
+        } => {
+            let settings = ctx.data().settings.current();
+            let insult = &settings.config.nicknamer.reveal.insult;
+            (ctx, cooldown_reply(remaining_cooldown, insult))
+        }
+        poise::FrameworkError::CommandCheckFailed {
+            error: Some(error),
+            ctx,
+            ..
+        } => {
+            info!(
+                "{} can't use {}: {}",
+                ctx.author().name,
+                ctx.command().qualified_name,
+                error
+            );
+            (ctx, error.to_string())
+        }
+        poise::FrameworkError::ArgumentParse {
+            error, input, ctx, ..
+        } => {
+            let reply = match input {
+                Some(input) => format!("I can't make sense of `{}`: {}", input, error),
+                None => format!("I can't make sense of that: {}", error),
+            };
+            (ctx, reply)
+        }
+        error => {
+            if let Err(e) = poise::builtins::on_error(error).await {
+                error!("Failed to handle error: {}", e);
+            }
+            return;
+        }
+    };
+    let reply = poise::CreateReply::default().content(reply).ephemeral(true);
+    if let Err(e) = ctx.send(reply).await {
+        error!("Failed to send error reply: {}", e);
+    }
+}
+
+/// The trace started for a command before it ran, or a new one if it didn't get that far
+async fn command_trace(ctx: PoiseContext<'_>) -> TraceContext {
+    match ctx.invocation_data::<TraceContext>().await {
+        Some(trace) => *trace,
+        None => TraceContext::new_root(),
+    }
+}
+
+/// Reports a failed command to the ops channel and Sentry, whichever are set up
+async fn report_failure(ctx: PoiseContext<'_>, trace: TraceContext, error: &anyhow::Error) {
+    let report = ErrorReport::new(
+        &ctx.command().qualified_name,
+        ctx.author().id.get(),
+        ctx.guild_id().map(|guild_id| guild_id.get()),
+        ctx.channel_id().get(),
+        trace,
+        error,
+    );
+    let settings = ctx.data().settings.current();
+    let ops_channel = settings
+        .config
+        .reporting
+        .ops_channel
+        .map(|channel_id| ChannelReporter::new(&ctx.serenity_context().http, channel_id));
+    let mut reporters: Vec<&dyn ErrorReporter> = Vec::new();
+    if let Some(ops_channel) = &ops_channel {
+        reporters.push(ops_channel);
+    }
+    if let Some(sentry) = &ctx.data().sentry {
+        reporters.push(sentry);
+    }
+    report_to_all(&reporters, &report).await;
+}
+
+/// Records the nickname of a member who joined or changed nickname in their history, and holds
+/// them to the nickname enforcement policy
+#[tracing::instrument(skip_all)]
+async fn on_member_named(
+    ctx: &serenity::Context,
+    data: &discord::serenity::Data<ConfiguredNamesRepository>,
+    member: &Member,
+) {
+    if let Err(err) = record_nickname(
+        &data.history,
+        member.guild_id.get(),
+        member.user.id.get(),
+        member.nick.as_deref(),
+    )
+    .await
+    {
+        error!(
+            "Failed to record the nickname of {}: {:?}",
+            member.user.name, err
+        );
+    }
+    let guild_connector = SerenityGuildConnector::new(ctx, member.guild_id);
+    let settings = data.settings.current();
+    let enforcer = NicknameEnforcer::new(
+        &settings.names_repository,
+        &guild_connector,
+        &data.opt_outs,
+        &data.recent_nicknames,
+        &settings.config.nicknamer.enforcement,
+    );
+    if let Err(err) = enforcer.enforce(&member.clone().into()).await {
+        error!(
+            "Failed to enforce the nickname of {}: {:?}",
+            member.user.name, err
+        );
+    }
+}
+
+/// Opts the author of a direct message out of having their real name revealed, or back in
+#[tracing::instrument(skip_all)]
+async fn on_direct_message(
+    ctx: &serenity::Context,
+    data: &discord::serenity::Data<ConfiguredNamesRepository>,
+    message: &Message,
+) {
+    let reply = match answer_direct_message(
+        &data.opt_outs,
+        message.author.id.get(),
+        &message.content,
+    )
+    .await
+    {
+        Ok(reply) => reply,
+        Err(err) => {
+            error!("Failed to answer a direct message: {:?}", err);
+            "Something went wrong, try again later".to_string()
+        }
+    };
+    if let Err(err) = message.channel_id.say(ctx, reply).await {
+        error!("Failed to reply to a direct message: {}", err);
+    }
+}
+
 /// Logs message contents when a message is created
 #[tracing::instrument(skip_all)]
 async fn on_message_create(_ctx: &serenity::Context, new_message: &Message) {
@@ -0,0 +1,153 @@
+//! Deciding who may run which command.
+//!
+//! Each command can require a role in the config, by the name the command is invoked with.
+//! Commands that don't are open to everyone. The check runs before every command, so the
+//! commands themselves don't need to know about it.
+
+use crate::nicknamer::config::PermissionsConfig;
+use crate::nicknamer::connectors::discord;
+use crate::nicknamer::connectors::discord::DiscordConnector;
+use thiserror::Error;
+use tracing::info;
+
+/// Reasons a command can't be run.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// The member who invoked the command doesn't have the role it requires
+    #[error("Only members with the {role} role can use {command}")]
+    Denied { command: String, role: String },
+    #[error("Something went wrong with Discord")]
+    DiscordError(#[from] discord::Error),
+}
+
+/// Checks that the member who invoked `command` has the role it requires, if any.
+///
+/// A role that doesn't exist in the guild denies everyone, rather than letting everyone in.
+#[tracing::instrument(skip(discord, permissions))]
+pub async fn authorize(
+    discord: &impl DiscordConnector,
+    permissions: &PermissionsConfig,
+    command: &str,
+) -> Result<(), Error> {
+    let Some(role_name) = permissions.required_role(command) else {
+        return Ok(());
+    };
+    let denied = || Error::Denied {
+        command: command.to_string(),
+        role: role_name.to_string(),
+    };
+    let role = match discord.get_role_by_name(role_name).await {
+        Ok(role) => role,
+        Err(discord::Error::CannotFindRole) => {
+            info!(
+                "The {} role that {} requires doesn't exist",
+                role_name, command
+            );
+            return Err(denied());
+        }
+        Err(err) => return Err(err.into()),
+    };
+    if discord.author_has_role(role.id()).await? {
+        Ok(())
+    } else {
+        Err(denied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nicknamer::connectors::discord::{Mentionable, MockDiscordConnector, Role};
+    use mockall::predicate::*;
+
+    const MODERATORS_ROLE_ID: u64 = 7;
+
+    struct Moderators;
+
+    impl Mentionable for Moderators {
+        fn mention(&self) -> String {
+            "@Moderators".to_string()
+        }
+    }
+
+    impl Role for Moderators {
+        fn id(&self) -> u64 {
+            MODERATORS_ROLE_ID
+        }
+    }
+
+    fn permissions() -> PermissionsConfig {
+        toml::from_str(r#"nick = "Moderators""#).unwrap()
+    }
+
+    fn discord_connector(author_is_moderator: bool) -> MockDiscordConnector {
+        let mut mock_discord = MockDiscordConnector::new();
+        mock_discord
+            .expect_get_role_by_name()
+            .with(eq("Moderators"))
+            .returning(|_| Ok(Box::new(Moderators)));
+        mock_discord
+            .expect_author_has_role()
+            .with(eq(MODERATORS_ROLE_ID))
+            .returning(move |_| Ok(author_is_moderator));
+        mock_discord
+    }
+
+    #[tokio::test]
+    async fn members_with_the_role_may_run_the_command() {
+        let mock_discord = discord_connector(true);
+
+        let result = authorize(&mock_discord, &permissions(), "nick").await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn members_without_the_role_are_denied() {
+        let mock_discord = discord_connector(false);
+
+        let result = authorize(&mock_discord, &permissions(), "nick").await;
+
+        let err = result.unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Only members with the Moderators role can use nick"
+        );
+    }
+
+    #[tokio::test]
+    async fn commands_without_a_role_are_open_to_everyone() {
+        let mock_discord = MockDiscordConnector::new();
+
+        let result = authorize(&mock_discord, &permissions(), "reveal").await;
+
+        assert!(result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn missing_roles_deny_everyone() {
+        // Arrange
+        let mut mock_discord = MockDiscordConnector::new();
+        mock_discord
+            .expect_get_role_by_name()
+            .returning(|_| Err(discord::Error::CannotFindRole));
+
+        // Act
+        let result = authorize(&mock_discord, &permissions(), "nick").await;
+
+        // Assert
+        assert!(matches!(result, Err(Error::Denied { .. })));
+    }
+
+    #[tokio::test]
+    async fn passes_on_discord_errors() {
+        let mut mock_discord = MockDiscordConnector::new();
+        mock_discord
+            .expect_get_role_by_name()
+            .returning(|_| Err(discord::Error::CannotGetGuild));
+
+        let result = authorize(&mock_discord, &permissions(), "nick").await;
+
+        assert!(matches!(result, Err(Error::DiscordError(_))));
+    }
+}
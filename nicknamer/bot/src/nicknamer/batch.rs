@@ -0,0 +1,174 @@
+//! Changing many nicknames at once, to bring a channel in line with its members' real names.
+
+use crate::nicknamer::connectors::discord;
+use crate::nicknamer::names::Names;
+use std::collections::HashSet;
+
+/// The most changes listed in a summary, so that it fits in an embed.
+const MAX_CHANGES_LISTED: usize = 25;
+
+/// A nickname to change to a member's real name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NicknameChange {
+    pub member: discord::ServerMember,
+    pub real_name: String,
+}
+
+impl NicknameChange {
+    /// The name the member currently shows up as.
+    fn shown_name(&self) -> &str {
+        self.member
+            .nick_name
+            .as_deref()
+            .unwrap_or(&self.member.user_name)
+    }
+}
+
+/// Proposes changing the nickname of every member who doesn't already go by their real name,
+/// ordered by real name.
+///
+/// Bots and members without a known real name are left alone, as are members who opted out of
+/// having it revealed, and the guild owner, whose nickname can't be changed.
+pub fn propose_changes(
+    members: &[discord::ServerMember],
+    real_names: &Names,
+    opted_out: &HashSet<u64>,
+    guild_owner_id: u64,
+) -> Vec<NicknameChange> {
+    let mut changes: Vec<NicknameChange> = members
+        .iter()
+        .filter(|member| {
+            !member.is_bot && member.id != guild_owner_id && !opted_out.contains(&member.id)
+        })
+        .filter_map(|member| {
+            let real_name = real_names.get(member.id)?;
+            let change = NicknameChange {
+                member: member.clone(),
+                real_name: real_name.to_string(),
+            };
+            let goes_by_real_name = change.shown_name().trim().eq_ignore_ascii_case(real_name);
+            (!goes_by_real_name).then_some(change)
+        })
+        .collect();
+    changes.sort_by(|change, other| change.real_name.cmp(&other.real_name));
+    changes
+}
+
+/// Lists proposed changes, one per line, for the invoker to confirm.
+pub fn summarize(changes: &[NicknameChange]) -> String {
+    let mut lines: Vec<String> = changes
+        .iter()
+        .take(MAX_CHANGES_LISTED)
+        .map(|change| {
+            format!(
+                "{}: '{}' → '{}'",
+                change.member.mention,
+                change.shown_name(),
+                change.real_name
+            )
+        })
+        .collect();
+    if changes.len() > MAX_CHANGES_LISTED {
+        lines.push(format!("…and {} more", changes.len() - MAX_CHANGES_LISTED));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nicknamer::connectors::discord::ServerMemberBuilder;
+
+    const GUILD_OWNER_ID: u64 = 4;
+
+    fn members() -> Vec<discord::ServerMember> {
+        vec![
+            ServerMemberBuilder::new()
+                .id(1)
+                .user_name("alice99")
+                .nick_name("Wonderland")
+                .build(),
+            ServerMemberBuilder::new().id(2).user_name("bob").build(),
+            ServerMemberBuilder::new()
+                .id(3)
+                .user_name("carol")
+                .nick_name("Carol")
+                .build(),
+            ServerMemberBuilder::new()
+                .id(GUILD_OWNER_ID)
+                .user_name("dave")
+                .build(),
+            ServerMemberBuilder::new()
+                .id(5)
+                .user_name("robot")
+                .is_bot(true)
+                .build(),
+            ServerMemberBuilder::new()
+                .id(6)
+                .user_name("erin")
+                .nick_name("Lizzie")
+                .build(),
+            ServerMemberBuilder::new()
+                .id(7)
+                .user_name("stranger")
+                .build(),
+        ]
+    }
+
+    fn real_names() -> Names {
+        [
+            (1, "Alice"),
+            (2, "Bob Jones"),
+            (3, "carol"),
+            (GUILD_OWNER_ID, "Dave"),
+            (5, "Robert"),
+            (6, "Erin"),
+        ]
+        .into_iter()
+        .map(|(discord_id, name)| (discord_id, name.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn proposes_changes_for_members_hiding_their_real_name() {
+        let opted_out = HashSet::from([6]);
+
+        let changes = propose_changes(&members(), &real_names(), &opted_out, GUILD_OWNER_ID);
+
+        let proposed: Vec<(u64, &str)> = changes
+            .iter()
+            .map(|change| (change.member.id, change.real_name.as_str()))
+            .collect();
+        assert_eq!(proposed, [(1, "Alice"), (2, "Bob Jones")]);
+    }
+
+    #[test]
+    fn summarizes_changes_by_member() {
+        let opted_out = HashSet::from([6]);
+        let changes = propose_changes(&members(), &real_names(), &opted_out, GUILD_OWNER_ID);
+
+        let summary = summarize(&changes);
+
+        assert_eq!(
+            summary,
+            "<@1>: 'Wonderland' → 'Alice'\n<@2>: 'bob' → 'Bob Jones'"
+        );
+    }
+
+    #[test]
+    fn summaries_of_many_changes_are_cut_short() {
+        let change = NicknameChange {
+            member: ServerMemberBuilder::new()
+                .id(1)
+                .user_name("alice99")
+                .build(),
+            real_name: "Alice".to_string(),
+        };
+        let changes = vec![change; MAX_CHANGES_LISTED + 3];
+
+        let summary = summarize(&changes);
+
+        assert_eq!(summary.lines().count(), MAX_CHANGES_LISTED + 1);
+        assert!(summary.ends_with("…and 3 more"));
+    }
+}
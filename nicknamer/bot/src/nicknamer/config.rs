@@ -1,13 +1,93 @@
 use anyhow::Context;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::SystemTime;
 
 use crate::CONFIG_DIR;
+use crate::nicknamer::connectors::discord::RetryPolicy;
 
 /// Represents the overall configuration structure.
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Config {
     /// Configuration specific to the nicknamer application.
     pub nicknamer: NicknamerConfig,
+    /// How the bot's slash commands are registered with Discord.
+    #[serde(default)]
+    pub commands: CommandsConfig,
+    /// Where real names are loaded from.
+    #[serde(default)]
+    pub names: NamesConfig,
+    /// Where members' choices to keep their real names private are kept.
+    #[serde(default)]
+    pub privacy: PrivacyConfig,
+    /// Where the nicknames members took are kept.
+    #[serde(default)]
+    pub history: HistoryConfig,
+    /// How Discord API calls that fail because Discord is rate limiting the bot or having
+    /// trouble are retried.
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    /// Where failed commands are reported, besides the logs.
+    #[serde(default)]
+    pub reporting: ReportingConfig,
+}
+
+/// Where real names are loaded from, picked with the `source` key.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum NamesConfig {
+    /// The `real_names.yml` file next to `config.toml`, built into the bot unless
+    /// `NICKNAMER_CONFIG_DIR` points elsewhere.
+    #[default]
+    Embedded,
+    /// The nicknamer server's API, so that names edited in its web UI are used straight away.
+    ///
+    /// The bot logs in with the `NICKNAMER_API_USERNAME` and `NICKNAMER_API_PASSWORD`
+    /// environment variables, and uses the names whose server ID is the guild's ID.
+    Server {
+        /// Base URL of the server, e.g. `https://nicknamer.example.com`
+        url: String,
+    },
+    /// A YAML file like `real_names.yml`, which moderators can change from Discord with
+    /// `add-name` and `remove-name`.
+    File {
+        /// Where the file is kept, it's created when the first name is added
+        path: PathBuf,
+    },
+}
+
+/// Configuration for members opting out of having their real names revealed.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct PrivacyConfig {
+    /// A file listing the members who opted out, one Discord ID per line. Without it, opt-outs
+    /// are forgotten when the bot restarts.
+    pub opt_out_file: Option<PathBuf>,
+}
+
+/// Configuration for reporting failed commands to the people who run the bot.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct ReportingConfig {
+    /// The ID of a channel to post reports in, which only they should be able to see
+    pub ops_channel: Option<u64>,
+}
+
+/// Configuration for keeping members' nickname history.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct HistoryConfig {
+    /// A file the changes are appended to. Without it, history is forgotten when the bot
+    /// restarts.
+    pub file: Option<PathBuf>,
+}
+
+/// Configuration for registering slash commands.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CommandsConfig {
+    /// The ID of a guild to register the slash commands in, instead of globally.
+    ///
+    /// Guild commands show up straight away, whereas global ones can take up to an hour to
+    /// update, so this is handy while testing the bot on a server.
+    pub register_in_guild: Option<u64>,
 }
 
 /// Configuration for the reveal feature.
@@ -24,21 +104,157 @@ pub struct RevealConfig {
 /// Configuration for the nicknamer application.
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct NicknamerConfig {
+    /// The role members need to add and remove real names. Nobody can if it's not set.
+    #[serde(default)]
+    pub name_editor_role: Option<String>,
     /// Configuration for the reveal feature.
     pub reveal: RevealConfig,
+    /// What to do about members whose nickname isn't their real name.
+    #[serde(default)]
+    pub enforcement: EnforcementConfig,
+    /// How often the commands that post to the channel can be used.
+    #[serde(default)]
+    pub cooldowns: CooldownsConfig,
+    /// Who can use which commands.
+    #[serde(default)]
+    pub permissions: PermissionsConfig,
+}
+
+/// The role members need to use a command, by the name the command is invoked with, e.g.
+/// `nick-all = "Moderators"`. Everyone can use the commands that aren't listed.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct PermissionsConfig {
+    roles: BTreeMap<String, String>,
+}
+
+impl PermissionsConfig {
+    /// The role needed to use `command`, if it needs one.
+    pub fn required_role(&self, command: &str) -> Option<&str> {
+        self.roles.get(command).map(String::as_str)
+    }
+}
+
+/// Cooldowns of the commands that post to the channel, so that they can't be spammed.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct CooldownsConfig {
+    #[serde(default = "CooldownsConfig::default_reveal")]
+    pub reveal: CommandCooldown,
+    #[serde(default = "CooldownsConfig::default_nick")]
+    pub nick: CommandCooldown,
+}
+
+impl CooldownsConfig {
+    fn default_reveal() -> CommandCooldown {
+        CommandCooldown {
+            user_seconds: 30,
+            channel_seconds: 10,
+        }
+    }
+
+    fn default_nick() -> CommandCooldown {
+        CommandCooldown {
+            user_seconds: 5,
+            channel_seconds: 0,
+        }
+    }
+}
+
+impl Default for CooldownsConfig {
+    fn default() -> Self {
+        Self {
+            reveal: Self::default_reveal(),
+            nick: Self::default_nick(),
+        }
+    }
+}
+
+/// How long a command can't be used again for after it was used, 0 for no wait.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CommandCooldown {
+    /// Seconds the member who used the command has to wait
+    #[serde(default)]
+    pub user_seconds: u64,
+    /// Seconds everyone has to wait in the channel the command was used in
+    #[serde(default)]
+    pub channel_seconds: u64,
+}
+
+/// Configuration for keeping nicknames in line with real names.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct EnforcementConfig {
+    /// What happens when a member joins or changes nickname to something other than their
+    /// real name.
+    #[serde(default)]
+    pub policy: EnforcementPolicy,
+}
+
+/// What to do about a nickname that isn't the member's real name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnforcementPolicy {
+    /// Let members call themselves whatever they like.
+    #[default]
+    Off,
+    /// Change the nickname back to the real name.
+    Revert,
+    /// Send the member a direct message telling them to change it back.
+    Warn,
+}
+
+/// Where `config.toml` and `real_names.yml` are read from.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigFiles {
+    /// The files in `config/` built into the bot, which can't change while it runs.
+    BuiltIn,
+    /// A directory with the same files, set with the `NICKNAMER_CONFIG_DIR` environment
+    /// variable, so that they can be changed and reloaded without rebuilding the bot.
+    Directory(PathBuf),
+}
+
+impl ConfigFiles {
+    pub(crate) fn from_env() -> Self {
+        match std::env::var_os("NICKNAMER_CONFIG_DIR") {
+            Some(dir) => Self::Directory(PathBuf::from(dir)),
+            None => Self::BuiltIn,
+        }
+    }
+
+    /// Reads one of the config files.
+    pub(crate) fn read(&self, name: &str) -> anyhow::Result<String> {
+        match self {
+            Self::BuiltIn => {
+                let file = CONFIG_DIR
+                    .get_file(name)
+                    .with_context(|| format!("Failed to find {name} in the config directory"))?;
+                let contents = file
+                    .contents_utf8()
+                    .with_context(|| format!("Failed to read {name} as UTF-8"))?;
+                Ok(contents.to_string())
+            }
+            Self::Directory(dir) => {
+                let path = dir.join(name);
+                std::fs::read_to_string(&path)
+                    .with_context(|| format!("Failed to read {}", path.display()))
+            }
+        }
+    }
+
+    /// When one of the config files last changed, `None` if it's built in or missing.
+    pub(crate) fn modified(&self, name: &str) -> Option<SystemTime> {
+        match self {
+            Self::BuiltIn => None,
+            Self::Directory(dir) => std::fs::metadata(dir.join(name)).ok()?.modified().ok(),
+        }
+    }
 }
 
 impl Config {
-    pub(crate) fn new() -> anyhow::Result<Self> {
-        let config_file = CONFIG_DIR
-            .get_file("config.toml")
-            .context("Failed to find config.toml in the config directory")?;
-        let config_data = config_file
-            .contents_utf8()
-            .context("Failed to read config file as UTF-8")?;
+    pub(crate) fn load(files: &ConfigFiles) -> anyhow::Result<Self> {
+        let config_data = files.read("config.toml")?;
         let config = ::config::Config::builder()
             .add_source(::config::File::from_str(
-                config_data,
+                &config_data,
                 ::config::FileFormat::Toml,
             ))
             .build()?;
@@ -72,6 +288,143 @@ mod tests {
             assert_eq!(config.nicknamer.reveal.insult, "test insult");
             assert_eq!(config.nicknamer.reveal.role_to_mention, "test role");
             assert_eq!(config.nicknamer.reveal.he_who_shall_not_be_named, 1);
+            assert_eq!(config.commands.register_in_guild, None);
+            assert_eq!(config.names, NamesConfig::Embedded);
+            assert_eq!(config.nicknamer.name_editor_role, None);
+            assert_eq!(config.nicknamer.enforcement.policy, EnforcementPolicy::Off);
+            assert_eq!(config.nicknamer.cooldowns, CooldownsConfig::default());
+            assert_eq!(config.privacy, PrivacyConfig::default());
+            assert_eq!(config.history, HistoryConfig::default());
+            assert_eq!(config.nicknamer.permissions, PermissionsConfig::default());
+            assert_eq!(config.retry, RetryPolicy::default());
+            assert_eq!(config.reporting.ops_channel, None);
+        }
+
+        #[test]
+        fn test_config_deserialize_register_in_guild() {
+            // Arrange
+            let toml_str = r#"
+                [nicknamer]
+                [nicknamer.reveal]
+                insult = "test insult"
+                role_to_mention = "test role"
+                he_who_shall_not_be_named = 1
+
+                [commands]
+                register_in_guild = 42
+            "#;
+
+            // Act
+            let config: Config = toml::from_str(toml_str).unwrap();
+
+            // Assert
+            assert_eq!(config.commands.register_in_guild, Some(42));
+        }
+
+        #[test]
+        fn test_config_deserialize_partial_cooldowns() {
+            // Arrange
+            let toml_str = r#"
+                [nicknamer]
+                [nicknamer.reveal]
+                insult = "test insult"
+                role_to_mention = "test role"
+                he_who_shall_not_be_named = 1
+
+                [nicknamer.cooldowns.reveal]
+                channel_seconds = 60
+            "#;
+
+            // Act
+            let config: Config = toml::from_str(toml_str).unwrap();
+
+            // Assert
+            let cooldowns = config.nicknamer.cooldowns;
+            assert_eq!(
+                cooldowns.reveal,
+                CommandCooldown {
+                    user_seconds: 0,
+                    channel_seconds: 60,
+                }
+            );
+            assert_eq!(cooldowns.nick, CooldownsConfig::default().nick);
+        }
+
+        #[test]
+        fn test_config_deserialize_partial_retry() {
+            // Arrange
+            let toml_str = r#"
+                [nicknamer.reveal]
+                insult = "test insult"
+                role_to_mention = "test role"
+                he_who_shall_not_be_named = 1
+
+                [retry]
+                max_attempts = 5
+            "#;
+
+            // Act
+            let config: Config = toml::from_str(toml_str).unwrap();
+
+            // Assert
+            let policy = config.retry;
+            assert_eq!(policy.max_attempts, 5);
+            assert_eq!(
+                policy.initial_backoff,
+                RetryPolicy::default().initial_backoff
+            );
+            assert_eq!(policy.max_backoff, RetryPolicy::default().max_backoff);
+        }
+
+        #[test]
+        fn test_config_deserialize_permissions() {
+            // Arrange
+            let toml_str = r#"
+                [nicknamer.reveal]
+                insult = "test insult"
+                role_to_mention = "test role"
+                he_who_shall_not_be_named = 1
+
+                [nicknamer.permissions]
+                nick = "Moderators"
+                nick-all = "Admins"
+            "#;
+
+            // Act
+            let config: Config = toml::from_str(toml_str).unwrap();
+
+            // Assert
+            let permissions = config.nicknamer.permissions;
+            assert_eq!(permissions.required_role("nick"), Some("Moderators"));
+            assert_eq!(permissions.required_role("nick-all"), Some("Admins"));
+            assert_eq!(permissions.required_role("reveal"), None);
+        }
+
+        #[test]
+        fn test_config_deserialize_names_source() {
+            // Arrange
+            let toml_str = r#"
+                [nicknamer]
+                [nicknamer.reveal]
+                insult = "test insult"
+                role_to_mention = "test role"
+                he_who_shall_not_be_named = 1
+
+                [names]
+                source = "server"
+                url = "https://nicknamer.example.com"
+            "#;
+
+            // Act
+            let config: Config = toml::from_str(toml_str).unwrap();
+
+            // Assert
+            assert_eq!(
+                config.names,
+                NamesConfig::Server {
+                    url: "https://nicknamer.example.com".to_string(),
+                }
+            );
         }
 
         #[test]
@@ -123,12 +476,22 @@ mod tests {
         // Arrange
         let config = Config {
             nicknamer: NicknamerConfig {
+                name_editor_role: None,
                 reveal: RevealConfig {
                     insult: "test insult".to_string(),
                     role_to_mention: "test role".to_string(),
                     he_who_shall_not_be_named: 123456789,
                 },
+                enforcement: EnforcementConfig::default(),
+                cooldowns: CooldownsConfig::default(),
+                permissions: PermissionsConfig::default(),
             },
+            commands: CommandsConfig::default(),
+            names: NamesConfig::default(),
+            privacy: PrivacyConfig::default(),
+            history: HistoryConfig::default(),
+            retry: RetryPolicy::default(),
+            reporting: ReportingConfig::default(),
         };
 
         // Act
@@ -145,11 +508,41 @@ mod tests {
         // Arrange
         let original_config = Config {
             nicknamer: NicknamerConfig {
+                name_editor_role: Some("Name Keepers".to_string()),
                 reveal: RevealConfig {
                     insult: "roundtrip insult".to_string(),
                     role_to_mention: "roundtrip role".to_string(),
                     he_who_shall_not_be_named: 987654321,
                 },
+                enforcement: EnforcementConfig {
+                    policy: EnforcementPolicy::Warn,
+                },
+                cooldowns: CooldownsConfig {
+                    reveal: CommandCooldown {
+                        user_seconds: 60,
+                        channel_seconds: 20,
+                    },
+                    nick: CommandCooldown::default(),
+                },
+                permissions: PermissionsConfig {
+                    roles: BTreeMap::from([("nick".to_string(), "Moderators".to_string())]),
+                },
+            },
+            commands: CommandsConfig {
+                register_in_guild: Some(1234),
+            },
+            names: NamesConfig::Server {
+                url: "http://localhost:8080".to_string(),
+            },
+            privacy: PrivacyConfig {
+                opt_out_file: Some(PathBuf::from("/data/opt_outs.txt")),
+            },
+            history: HistoryConfig {
+                file: Some(PathBuf::from("/data/history.txt")),
+            },
+            retry: RetryPolicy::NEVER,
+            reporting: ReportingConfig {
+                ops_channel: Some(5678),
             },
         };
 
@@ -175,5 +568,63 @@ mod tests {
                 .he_who_shall_not_be_named,
             987654321
         );
+        assert_eq!(deserialized_config.commands.register_in_guild, Some(1234));
+        assert_eq!(deserialized_config.names, original_config.names);
+        assert_eq!(deserialized_config.privacy, original_config.privacy);
+        assert_eq!(deserialized_config.history, original_config.history);
+        assert_eq!(deserialized_config.retry, original_config.retry);
+        assert_eq!(deserialized_config.reporting, original_config.reporting);
+        assert_eq!(
+            deserialized_config.nicknamer.name_editor_role.as_deref(),
+            Some("Name Keepers")
+        );
+        assert_eq!(
+            deserialized_config.nicknamer.enforcement.policy,
+            EnforcementPolicy::Warn
+        );
+        assert_eq!(
+            deserialized_config.nicknamer.cooldowns,
+            original_config.nicknamer.cooldowns
+        );
+        assert_eq!(
+            deserialized_config.nicknamer.permissions,
+            original_config.nicknamer.permissions
+        );
+    }
+
+    #[test]
+    fn test_config_load_from_directory_or_built_in() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("config.toml"),
+            r#"
+                [nicknamer.reveal]
+                insult = "changed insult"
+                role_to_mention = "test role"
+                he_who_shall_not_be_named = 1
+            "#,
+        )
+        .unwrap();
+        let files = ConfigFiles::Directory(dir.path().to_path_buf());
+
+        // Act
+        let from_directory = Config::load(&files).unwrap();
+        let built_in = Config::load(&ConfigFiles::BuiltIn).unwrap();
+
+        // Assert
+        assert_eq!(from_directory.nicknamer.reveal.insult, "changed insult");
+        assert_eq!(built_in.nicknamer.reveal.insult, "ya dingus");
+        assert!(files.modified("config.toml").is_some());
+        assert_eq!(ConfigFiles::BuiltIn.modified("config.toml"), None);
+    }
+
+    #[test]
+    fn test_config_load_fails_without_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let result = Config::load(&ConfigFiles::Directory(dir.path().to_path_buf()));
+
+        assert!(result.is_err());
     }
 }
@@ -1,126 +1,148 @@
-//! Serenity-based implementation of Discord connectivity.
-//!
-//! This module provides the concrete implementation of the Discord connector
-//! trait using the Serenity Discord library.
-
-use crate::nicknamer::config::Config;
-use crate::nicknamer::connectors::discord::Error::{
-    CannotFindChannel, CannotFindMembersOfChannel, CannotFindRole, CannotGetGuild, CannotSendReply,
-    NotEnoughPermissions, NotInServerChannel,
-};
-use crate::nicknamer::connectors::discord::server_member::ServerMember;
-use crate::nicknamer::connectors::discord::{DiscordConnector, Error, Mentionable, Role};
-use crate::nicknamer::names::{EmbeddedNamesRepository, NamesRepository};
+//! Poise framework types for driving the Serenity-based Discord connector.
+
+use crate::nicknamer::config::ConfigFiles;
+use crate::nicknamer::connectors::discord::Error;
+use crate::nicknamer::enforcement::{GuildConnector, RecentNicknames};
+use crate::nicknamer::history::ConfiguredHistoryRepository;
+use crate::nicknamer::messages::truncate;
+use crate::nicknamer::names::{ConfiguredNamesRepository, NamesRepository};
+use crate::nicknamer::privacy::ConfiguredOptOutRepository;
+use crate::nicknamer::reload::Settings;
+use crate::nicknamer::reporting;
+use crate::nicknamer::reporting::sentry::SentryReporter;
+use crate::nicknamer::reporting::{ErrorReport, ErrorReporter};
 use async_trait::async_trait;
+use discord_connector::serenity::count_api_error;
 use poise::serenity_prelude as serenity;
-use poise::serenity_prelude::EditMember;
-use poise::serenity_prelude::Mentionable as poise_Mentionable;
-use tracing::info;
-
-/// Discord connector implementation using Serenity library.
-///
-/// Provides functionality to interact with Discord servers using
-/// the Serenity context.
-pub struct SerenityDiscordConnector<'a> {
-    context: Context<'a>,
-}
+use poise::serenity_prelude::{ChannelId, CreateEmbed, CreateMessage, EditMember, GuildId, UserId};
+use std::sync::Arc;
 
-impl<'a> SerenityDiscordConnector<'a> {
-    /// Creates a new SerenityDiscordConnector instance.
-    ///
-    /// # Arguments
-    ///
-    /// * `context` - Poise command context for Discord interactions
-    pub fn new(context: Context<'a>) -> Self {
-        Self { context }
-    }
+/// Discord refuses embeds with longer descriptions than this many characters.
+const MAX_EMBED_DESCRIPTION_LENGTH: usize = 4096;
+
+/// The red of the embeds reporting failed commands.
+const ERROR_REPORT_COLOUR: u32 = 0xE7_4C_3C;
+
+/// Discord connector bound to the bot's command context.
+pub type SerenityDiscordConnector<'a> = discord_connector::serenity::SerenityDiscordConnector<
+    'a,
+    Data<ConfiguredNamesRepository>,
+    anyhow::Error,
+>;
+
+/// Empty data structure for Poise framework configuration
+pub struct Data<NamesRepo: NamesRepository> {
+    /// The config and real names, which can be reloaded while the bot runs
+    pub(crate) settings: Arc<Settings<NamesRepo>>,
+    /// Where the settings are reloaded from
+    pub(crate) config_files: ConfigFiles,
+    pub(crate) opt_outs: ConfiguredOptOutRepository,
+    /// Every nickname members took, read from its file when needed rather than reloaded
+    pub(crate) history: ConfiguredHistoryRepository,
+    /// The nicknames the bot just gave members, which enforcement leaves alone
+    pub(crate) recent_nicknames: RecentNicknames,
+    /// Where failed commands are reported besides the ops channel, if `SENTRY_DSN` is set
+    pub(crate) sentry: Option<SentryReporter>,
 }
 
-#[async_trait]
-impl DiscordConnector for SerenityDiscordConnector<'_> {
-    async fn get_members_of_current_channel(&self) -> Result<Vec<ServerMember>, Error> {
-        let ctx = &self.context;
-        let Ok(channel) = ctx.channel_id().to_channel(ctx).await else {
-            return Err(CannotFindChannel);
-        };
-        let Some(channel) = channel.guild() else {
-            return Err(NotInServerChannel);
-        };
-        let Ok(members) = channel.members(ctx) else {
-            return Err(CannotFindMembersOfChannel);
-        };
-        let members: Vec<ServerMember> =
-            members.iter().map(|member| member.clone().into()).collect();
-        info!("Found {} members in current channel", members.len());
-        Ok(members)
-    }
+/// Type alias for Poise command context
+pub type Context<'a> = poise::Context<'a, Data<ConfiguredNamesRepository>, anyhow::Error>;
 
-    async fn send_reply(&self, message: &str) -> Result<(), Error> {
-        let ctx = &self.context;
-        let Ok(_) = ctx.reply(message).await else {
-            return Err(CannotSendReply);
-        };
-        Ok(())
-    }
+/// Type alias for errors Poise hands to the framework's error handler
+pub type FrameworkError<'a> =
+    poise::FrameworkError<'a, Data<ConfiguredNamesRepository>, anyhow::Error>;
 
-    async fn get_role_by_name(&self, name: &str) -> Result<Box<dyn Role>, Error> {
-        let Some(guild) = self.context.guild() else {
-            return Err(CannotGetGuild);
-        };
-        let Some(role) = guild.role_by_name(name) else {
-            return Err(CannotFindRole);
-        };
-        Ok(Box::new(role.clone()))
+/// Guild connector for events, bound to the guild an event happened in.
+pub struct SerenityGuildConnector<'a> {
+    context: &'a serenity::Context,
+    guild_id: GuildId,
+}
+
+impl<'a> SerenityGuildConnector<'a> {
+    pub fn new(context: &'a serenity::Context, guild_id: GuildId) -> Self {
+        Self { context, guild_id }
     }
+}
 
+#[async_trait]
+impl GuildConnector for SerenityGuildConnector<'_> {
     async fn change_member_nick_name(
         &self,
         member_id: u64,
         new_nick_name: &str,
     ) -> Result<(), Error> {
-        let Some(guild) = self.context.guild_id() else {
-            return Err(CannotGetGuild);
-        };
         let builder = EditMember::new().nickname(new_nick_name);
-        let Ok(_member) = guild.edit_member(self.context, member_id, builder).await else {
-            return Err(NotEnoughPermissions);
+        let Ok(_member) = self
+            .guild_id
+            .edit_member(self.context, UserId::new(member_id), builder)
+            .await
+        else {
+            return Err(count_api_error(
+                "change_member_nick_name",
+                Error::NotEnoughPermissions,
+            ));
+        };
+        Ok(())
+    }
+
+    async fn send_direct_message(&self, user_id: u64, message: &str) -> Result<(), Error> {
+        let builder = CreateMessage::new().content(message);
+        let Ok(_message) = UserId::new(user_id)
+            .direct_message(self.context, builder)
+            .await
+        else {
+            return Err(count_api_error(
+                "send_direct_message",
+                Error::CannotSendMessage,
+            ));
         };
         Ok(())
     }
 
     async fn get_guild_owner_id(&self) -> Result<u64, Error> {
-        let Some(guild) = self.context.guild() else {
-            return Err(CannotGetGuild);
+        let Some(guild) = self.guild_id.to_guild_cached(self.context) else {
+            return Err(Error::CannotGetGuild);
         };
         Ok(guild.owner_id.get())
     }
-}
 
-impl Mentionable for serenity::Role {
-    fn mention(&self) -> String {
-        <Self as serenity::Mentionable>::mention(self).to_string()
+    fn guild_id(&self) -> u64 {
+        self.guild_id.get()
     }
 }
 
-impl Role for serenity::Role {}
+/// Reporter that posts reports as embeds in a channel, like the ops channel.
+pub struct ChannelReporter<'a> {
+    http: &'a serenity::Http,
+    channel_id: ChannelId,
+}
 
-impl From<serenity::Member> for ServerMember {
-    fn from(member: serenity::Member) -> Self {
-        ServerMember {
-            id: member.user.id.get(),
-            nick_name: member.nick.clone(),
-            user_name: member.user.name.clone(),
-            is_bot: member.user.bot,
-            mention: member.mention().to_string(),
+impl<'a> ChannelReporter<'a> {
+    pub fn new(http: &'a serenity::Http, channel_id: u64) -> Self {
+        Self {
+            http,
+            channel_id: ChannelId::new(channel_id),
         }
     }
 }
 
-/// Empty data structure for Poise framework configuration
-pub struct Data<NamesRepo: NamesRepository> {
-    pub(crate) names_repository: NamesRepo,
-    pub config: Config,
+#[async_trait]
+impl ErrorReporter for ChannelReporter<'_> {
+    async fn report(&self, report: &ErrorReport) -> Result<(), reporting::Error> {
+        let fields = report
+            .fields()
+            .into_iter()
+            .map(|(name, value)| (name, value, true));
+        let embed = CreateEmbed::new()
+            .title(report.title())
+            .description(truncate(&report.error, MAX_EMBED_DESCRIPTION_LENGTH))
+            .fields(fields)
+            .colour(ERROR_REPORT_COLOUR);
+        let builder = CreateMessage::new().embed(embed);
+        let Ok(_message) = self.channel_id.send_message(self.http, builder).await else {
+            count_api_error("send_error_report", Error::CannotSendMessage);
+            return Err(reporting::Error::CannotSendReport);
+        };
+        Ok(())
+    }
 }
-
-/// Type alias for Poise command context
-pub type Context<'a> = poise::Context<'a, Data<EmbeddedNamesRepository>, anyhow::Error>;
@@ -0,0 +1,133 @@
+//! Cooldowns on the commands that post to the channel, so that they can't be spammed.
+//!
+//! Poise keeps track of when commands were last used, this sets how long it makes members wait
+//! from the config, and words the reply for those who don't.
+
+use crate::nicknamer::config::{CommandCooldown, CooldownsConfig};
+use std::time::Duration;
+
+impl CommandCooldown {
+    /// The Poise cooldowns for a command, leaving out the ones that are 0.
+    pub fn to_poise(self) -> poise::CooldownConfig {
+        let seconds = |seconds: u64| (seconds > 0).then(|| Duration::from_secs(seconds));
+        poise::CooldownConfig {
+            user: seconds(self.user_seconds),
+            channel: seconds(self.channel_seconds),
+            ..Default::default()
+        }
+    }
+}
+
+/// Sets the cooldowns of the `reveal` and `nick` commands.
+pub fn apply_cooldowns<U, E>(commands: &mut [poise::Command<U, E>], config: &CooldownsConfig) {
+    for command in commands {
+        let cooldown = match command.name.as_str() {
+            "reveal" => config.reveal,
+            "nick" => config.nick,
+            _ => continue,
+        };
+        *command
+            .cooldown_config
+            .get_mut()
+            .expect("cooldown config lock poisoned") = cooldown.to_poise();
+    }
+}
+
+/// The reply to someone using a command before its cooldown is over.
+pub fn cooldown_reply(remaining: Duration, insult: &str) -> String {
+    let seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+    let unit = if seconds == 1 { "second" } else { "seconds" };
+    format!("Patience, {}! Try again in {} {}.", insult, seconds, unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use poise::serenity_prelude::{ChannelId, UserId};
+    use poise::{CooldownContext, CooldownTracker};
+
+    fn invocation(user_id: u64, channel_id: u64) -> CooldownContext {
+        CooldownContext {
+            user_id: UserId::new(user_id),
+            guild_id: None,
+            channel_id: ChannelId::new(channel_id),
+        }
+    }
+
+    #[test]
+    fn zero_seconds_means_no_cooldown() {
+        let cooldown = CommandCooldown {
+            user_seconds: 5,
+            channel_seconds: 0,
+        };
+
+        let poise_cooldown = cooldown.to_poise();
+
+        assert_eq!(poise_cooldown.user, Some(Duration::from_secs(5)));
+        assert_eq!(poise_cooldown.channel, None);
+        assert_eq!(poise_cooldown.global, None);
+    }
+
+    #[test]
+    fn user_and_channel_cooldowns_are_tracked_separately() {
+        // Arrange
+        let cooldown = CommandCooldown {
+            user_seconds: 30,
+            channel_seconds: 10,
+        }
+        .to_poise();
+        let mut tracker = CooldownTracker::new();
+
+        // Act
+        tracker.start_cooldown(invocation(1, 100));
+
+        // Assert
+        let same_user_elsewhere = tracker.remaining_cooldown(invocation(1, 200), &cooldown);
+        let other_user_same_channel = tracker.remaining_cooldown(invocation(2, 100), &cooldown);
+        let other_user_elsewhere = tracker.remaining_cooldown(invocation(2, 200), &cooldown);
+        assert!(same_user_elsewhere.is_some_and(|left| left > Duration::from_secs(10)));
+        assert!(other_user_same_channel.is_some_and(|left| left <= Duration::from_secs(10)));
+        assert_eq!(other_user_elsewhere, None);
+    }
+
+    #[test]
+    fn apply_cooldowns_only_touches_reveal_and_nick() {
+        // Arrange
+        let mut commands: Vec<poise::Command<(), ()>> = ["reveal", "nick", "ping"]
+            .into_iter()
+            .map(|name| poise::Command {
+                name: name.to_string(),
+                ..Default::default()
+            })
+            .collect();
+
+        // Act
+        apply_cooldowns(&mut commands, &CooldownsConfig::default());
+
+        // Assert
+        let user_cooldowns: Vec<Option<Duration>> = commands
+            .iter()
+            .map(|command| command.cooldown_config.read().unwrap().user)
+            .collect();
+        assert_eq!(
+            user_cooldowns,
+            [
+                Some(Duration::from_secs(30)),
+                Some(Duration::from_secs(5)),
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn cooldown_reply_rounds_up_to_whole_seconds() {
+        assert_eq!(
+            cooldown_reply(Duration::from_millis(12_300), "ya dingus"),
+            "Patience, ya dingus! Try again in 13 seconds."
+        );
+        assert_eq!(
+            cooldown_reply(Duration::from_millis(400), "ya dingus"),
+            "Patience, ya dingus! Try again in 1 second."
+        );
+    }
+}
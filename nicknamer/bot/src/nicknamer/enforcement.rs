@@ -0,0 +1,506 @@
+//! Keeps members' nicknames in line with their real names.
+//!
+//! Enforcement runs on member events rather than commands, so it talks to Discord through a
+//! [`GuildConnector`] bound to the guild the event happened in, instead of a command context.
+//! Discord reports the nicknames the bot gives members as member updates too, so the bot
+//! remembers them in [`RecentNicknames`] and leaves them alone.
+
+use crate::nicknamer::config::{EnforcementConfig, EnforcementPolicy};
+use crate::nicknamer::connectors::discord;
+use crate::nicknamer::metrics;
+use crate::nicknamer::names::NamesRepository;
+use crate::nicknamer::privacy::OptOutRepository;
+use crate::nicknamer::user::Error;
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{debug, info};
+
+/// How long after the bot changes a nickname Discord's report of it is expected.
+const RECENT_NICKNAME_WINDOW: Duration = Duration::from_secs(60);
+
+/// Discord actions on a guild that don't need a command being handled.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait GuildConnector {
+    /// Changes the nickname of a member of the guild.
+    async fn change_member_nick_name(
+        &self,
+        member_id: u64,
+        new_nick_name: &str,
+    ) -> Result<(), discord::Error>;
+    /// Sends a direct message to a user.
+    async fn send_direct_message(&self, user_id: u64, message: &str) -> Result<(), discord::Error>;
+    /// The ID of the member who owns the guild.
+    async fn get_guild_owner_id(&self) -> Result<u64, discord::Error>;
+    /// The ID of the guild.
+    fn guild_id(&self) -> u64;
+}
+
+/// The nicknames the bot gave members lately, per guild and member.
+///
+/// A nickname is remembered before it's changed, as Discord may report the change before
+/// the bot hears back about making it.
+#[derive(Default)]
+pub struct RecentNicknames {
+    applied: Mutex<BTreeMap<(u64, u64), (String, Instant)>>,
+}
+
+impl RecentNicknames {
+    pub const fn new() -> Self {
+        Self {
+            applied: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Remembers that the bot is giving a member of a guild `nick_name`.
+    pub fn remember(&self, guild_id: u64, member_id: u64, nick_name: &str) {
+        let mut applied = self.applied.lock().expect("recent nicknames lock poisoned");
+        applied.retain(|_, (_, applied_at)| applied_at.elapsed() < RECENT_NICKNAME_WINDOW);
+        applied.insert(
+            (guild_id, member_id),
+            (nick_name.to_string(), Instant::now()),
+        );
+    }
+
+    /// Forgets the nickname the bot was giving a member, for when changing it failed.
+    pub fn forget(&self, guild_id: u64, member_id: u64) {
+        let mut applied = self.applied.lock().expect("recent nicknames lock poisoned");
+        applied.remove(&(guild_id, member_id));
+    }
+
+    /// Whether the bot just gave a member `nick_name`, so that Discord is reporting its own
+    /// change. Each change is only reported once, so it's forgotten after.
+    pub fn was_applied(&self, guild_id: u64, member_id: u64, nick_name: Option<&str>) -> bool {
+        let mut applied = self.applied.lock().expect("recent nicknames lock poisoned");
+        match applied.remove(&(guild_id, member_id)) {
+            Some((applied_nick_name, applied_at)) => {
+                nick_name == Some(applied_nick_name.as_str())
+                    && applied_at.elapsed() < RECENT_NICKNAME_WINDOW
+            }
+            None => false,
+        }
+    }
+}
+
+pub struct NicknameEnforcer<
+    'a,
+    REPO: NamesRepository,
+    GUILD: GuildConnector,
+    OPTOUTS: OptOutRepository,
+> {
+    names_repository: &'a REPO,
+    guild_connector: &'a GUILD,
+    opt_outs: &'a OPTOUTS,
+    recent_nicknames: &'a RecentNicknames,
+    config: &'a EnforcementConfig,
+}
+
+impl<
+    'a,
+    REPO: NamesRepository + Sync,
+    GUILD: GuildConnector + Sync,
+    OPTOUTS: OptOutRepository + Sync,
+> NicknameEnforcer<'a, REPO, GUILD, OPTOUTS>
+{
+    pub fn new(
+        names_repository: &'a REPO,
+        guild_connector: &'a GUILD,
+        opt_outs: &'a OPTOUTS,
+        recent_nicknames: &'a RecentNicknames,
+        config: &'a EnforcementConfig,
+    ) -> Self {
+        Self {
+            names_repository,
+            guild_connector,
+            opt_outs,
+            recent_nicknames,
+            config,
+        }
+    }
+
+    /// Checks a member who just joined or changed nickname against their real name, and
+    /// reverts the nickname or warns them as the policy says.
+    ///
+    /// Bots, members without a known real name, members who opted out of having it revealed
+    /// and nicknames the bot gave itself are left alone, and the guild owner can't be renamed,
+    /// so they're only ever warned.
+    #[tracing::instrument(skip(self))]
+    pub async fn enforce(&self, member: &discord::ServerMember) -> Result<(), Error> {
+        if self.config.policy == EnforcementPolicy::Off || member.is_bot {
+            return Ok(());
+        }
+        let guild_id = self.guild_connector.guild_id();
+        if self
+            .recent_nicknames
+            .was_applied(guild_id, member.id, member.nick_name.as_deref())
+        {
+            debug!(
+                "Leaving the nickname the bot gave {} alone",
+                member.user_name
+            );
+            return Ok(());
+        }
+        let names = self.names_repository.load_real_names(guild_id).await?;
+        let Some(real_name) = names.get(member.id) else {
+            return Ok(());
+        };
+        // Putting the real name in their nickname would reveal it
+        if self.opt_outs.load_opted_out().await?.contains(&member.id) {
+            return Ok(());
+        }
+        let shown_name = member.nick_name.as_deref().unwrap_or(&member.user_name);
+        if shown_name.trim().eq_ignore_ascii_case(real_name) {
+            return Ok(());
+        }
+
+        match self.config.policy {
+            EnforcementPolicy::Off => {}
+            EnforcementPolicy::Revert
+                if member.id == self.guild_connector.get_guild_owner_id().await? =>
+            {
+                info!(
+                    "Not reverting the nickname of {}, the guild owner",
+                    member.user_name
+                );
+            }
+            EnforcementPolicy::Revert => {
+                info!(
+                    "Reverting the nickname of {} to their real name",
+                    member.user_name
+                );
+                self.guild_connector
+                    .change_member_nick_name(member.id, real_name)
+                    .await?;
+                metrics::nickname_changed("enforcement");
+            }
+            EnforcementPolicy::Warn => {
+                info!("Warning {} about their nickname", member.user_name);
+                let warning = format!(
+                    "Your nickname '{}' hides your real name. Members are asked to go by their real name here, so please change it back to {}.",
+                    shown_name, real_name
+                );
+                self.guild_connector
+                    .send_direct_message(member.id, &warning)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nicknamer::connectors::discord::ServerMemberBuilder;
+    use crate::nicknamer::names::{MockNamesRepository, Names};
+    use crate::nicknamer::privacy::MemoryOptOutRepository;
+    use mockall::predicate::*;
+    use std::collections::HashMap;
+
+    const GUILD_ID: u64 = 42;
+    const GUILD_OWNER_ID: u64 = 987654321;
+
+    static NO_OPT_OUTS: MemoryOptOutRepository = MemoryOptOutRepository::new();
+    static NO_RECENT_NICKNAMES: RecentNicknames = RecentNicknames::new();
+
+    fn names_repository() -> MockNamesRepository {
+        let mut mock_repo = MockNamesRepository::new();
+        mock_repo
+            .expect_load_real_names()
+            .with(eq(GUILD_ID))
+            .returning(|_| {
+                Ok(Names {
+                    names: HashMap::from([(123456789, "Alice".to_string())]),
+                })
+            });
+        mock_repo
+    }
+
+    fn guild_connector() -> MockGuildConnector {
+        let mut mock_guild = MockGuildConnector::new();
+        mock_guild.expect_guild_id().return_const(GUILD_ID);
+        mock_guild
+    }
+
+    fn config(policy: EnforcementPolicy) -> EnforcementConfig {
+        EnforcementConfig { policy }
+    }
+
+    #[tokio::test]
+    async fn reverts_nicknames_that_hide_the_real_name() {
+        // Arrange
+        let mock_repo = names_repository();
+        let mut mock_guild = guild_connector();
+        let config = config(EnforcementPolicy::Revert);
+        let member = ServerMemberBuilder::new()
+            .id(123456789)
+            .user_name("alice99")
+            .nick_name("Definitely Bob")
+            .build();
+        mock_guild
+            .expect_get_guild_owner_id()
+            .returning(|| Ok(GUILD_OWNER_ID));
+        mock_guild
+            .expect_change_member_nick_name()
+            .with(eq(123456789), eq("Alice"))
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let sut = NicknameEnforcer::new(
+            &mock_repo,
+            &mock_guild,
+            &NO_OPT_OUTS,
+            &NO_RECENT_NICKNAMES,
+            &config,
+        );
+
+        // Act
+        let result = sut.enforce(&member).await;
+
+        // Assert
+        assert!(result.is_ok(), "enforce should succeed");
+    }
+
+    #[tokio::test]
+    async fn warns_members_joining_without_their_real_name() {
+        // Arrange
+        let mock_repo = names_repository();
+        let mut mock_guild = guild_connector();
+        let config = config(EnforcementPolicy::Warn);
+        let member = ServerMemberBuilder::new()
+            .id(123456789)
+            .user_name("alice99")
+            .build();
+        mock_guild
+            .expect_send_direct_message()
+            .with(
+                eq(123456789),
+                eq("Your nickname 'alice99' hides your real name. Members are asked to go by their real name here, so please change it back to Alice."),
+            )
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let sut = NicknameEnforcer::new(
+            &mock_repo,
+            &mock_guild,
+            &NO_OPT_OUTS,
+            &NO_RECENT_NICKNAMES,
+            &config,
+        );
+
+        // Act
+        let result = sut.enforce(&member).await;
+
+        // Assert
+        assert!(result.is_ok(), "enforce should succeed");
+    }
+
+    #[tokio::test]
+    async fn leaves_matching_nicknames_alone() {
+        // Arrange
+        let mock_repo = names_repository();
+        let mock_guild = guild_connector();
+        let config = config(EnforcementPolicy::Revert);
+        let member = ServerMemberBuilder::new()
+            .id(123456789)
+            .user_name("alice99")
+            .nick_name(" alice ")
+            .build();
+        let sut = NicknameEnforcer::new(
+            &mock_repo,
+            &mock_guild,
+            &NO_OPT_OUTS,
+            &NO_RECENT_NICKNAMES,
+            &config,
+        );
+
+        // Act
+        let result = sut.enforce(&member).await;
+
+        // Assert
+        assert!(result.is_ok(), "enforce should succeed without changes");
+    }
+
+    #[tokio::test]
+    async fn leaves_unknown_members_and_bots_alone() {
+        // Arrange
+        let mock_repo = names_repository();
+        let mock_guild = guild_connector();
+        let config = config(EnforcementPolicy::Revert);
+        let stranger = ServerMemberBuilder::new()
+            .id(42)
+            .nick_name("Stranger")
+            .build();
+        let bot = ServerMemberBuilder::new()
+            .id(123456789)
+            .nick_name("Robot")
+            .is_bot(true)
+            .build();
+        let sut = NicknameEnforcer::new(
+            &mock_repo,
+            &mock_guild,
+            &NO_OPT_OUTS,
+            &NO_RECENT_NICKNAMES,
+            &config,
+        );
+
+        // Act
+        let stranger_result = sut.enforce(&stranger).await;
+        let bot_result = sut.enforce(&bot).await;
+
+        // Assert
+        assert!(stranger_result.is_ok() && bot_result.is_ok());
+    }
+
+    #[tokio::test]
+    async fn leaves_opted_out_members_alone() {
+        // Arrange
+        let mock_repo = names_repository();
+        let mock_guild = guild_connector();
+        let opt_outs = MemoryOptOutRepository::new();
+        opt_outs.opt_out(123456789).await.unwrap();
+        let config = config(EnforcementPolicy::Revert);
+        let member = ServerMemberBuilder::new()
+            .id(123456789)
+            .nick_name("Definitely Bob")
+            .build();
+        let sut = NicknameEnforcer::new(
+            &mock_repo,
+            &mock_guild,
+            &opt_outs,
+            &NO_RECENT_NICKNAMES,
+            &config,
+        );
+
+        // Act
+        let result = sut.enforce(&member).await;
+
+        // Assert
+        assert!(result.is_ok(), "enforce should keep the real name private");
+    }
+
+    #[tokio::test]
+    async fn leaves_nicknames_the_bot_gave_alone() {
+        // Arrange
+        let mock_repo = names_repository();
+        let mut mock_guild = guild_connector();
+        let recent_nicknames = RecentNicknames::new();
+        recent_nicknames.remember(GUILD_ID, 123456789, "Definitely Bob");
+        let config = config(EnforcementPolicy::Revert);
+        let member = ServerMemberBuilder::new()
+            .id(123456789)
+            .nick_name("Definitely Bob")
+            .build();
+        mock_guild
+            .expect_get_guild_owner_id()
+            .returning(|| Ok(GUILD_OWNER_ID));
+        mock_guild
+            .expect_change_member_nick_name()
+            .times(1)
+            .returning(|_, _| Ok(()));
+        let sut = NicknameEnforcer::new(
+            &mock_repo,
+            &mock_guild,
+            &NO_OPT_OUTS,
+            &recent_nicknames,
+            &config,
+        );
+
+        // Act
+        let bots_change = sut.enforce(&member).await;
+        let members_change = sut.enforce(&member).await;
+
+        // Assert
+        assert!(bots_change.is_ok() && members_change.is_ok());
+    }
+
+    #[tokio::test]
+    async fn never_renames_the_guild_owner() {
+        // Arrange
+        let mut mock_repo = MockNamesRepository::new();
+        mock_repo.expect_load_real_names().returning(|_| {
+            Ok(Names {
+                names: HashMap::from([(GUILD_OWNER_ID, "Carol".to_string())]),
+            })
+        });
+        let mut mock_guild = guild_connector();
+        let config = config(EnforcementPolicy::Revert);
+        let owner = ServerMemberBuilder::new()
+            .id(GUILD_OWNER_ID)
+            .nick_name("General Secretary")
+            .build();
+        mock_guild
+            .expect_get_guild_owner_id()
+            .times(1)
+            .returning(|| Ok(GUILD_OWNER_ID));
+        let sut = NicknameEnforcer::new(
+            &mock_repo,
+            &mock_guild,
+            &NO_OPT_OUTS,
+            &NO_RECENT_NICKNAMES,
+            &config,
+        );
+
+        // Act
+        let result = sut.enforce(&owner).await;
+
+        // Assert
+        assert!(result.is_ok(), "enforce should leave the owner's nickname");
+    }
+
+    #[tokio::test]
+    async fn does_nothing_when_turned_off() {
+        // Arrange
+        let mock_repo = MockNamesRepository::new();
+        let mock_guild = guild_connector();
+        let config = config(EnforcementPolicy::Off);
+        let member = ServerMemberBuilder::new()
+            .id(123456789)
+            .nick_name("Definitely Bob")
+            .build();
+        let sut = NicknameEnforcer::new(
+            &mock_repo,
+            &mock_guild,
+            &NO_OPT_OUTS,
+            &NO_RECENT_NICKNAMES,
+            &config,
+        );
+
+        // Act
+        let result = sut.enforce(&member).await;
+
+        // Assert
+        assert!(result.is_ok(), "enforce should not even load names");
+    }
+
+    #[tokio::test]
+    async fn passes_on_discord_errors() {
+        // Arrange
+        let mock_repo = names_repository();
+        let mut mock_guild = guild_connector();
+        let config = config(EnforcementPolicy::Revert);
+        let member = ServerMemberBuilder::new()
+            .id(123456789)
+            .nick_name("Definitely Bob")
+            .build();
+        mock_guild
+            .expect_get_guild_owner_id()
+            .returning(|| Ok(GUILD_OWNER_ID));
+        mock_guild
+            .expect_change_member_nick_name()
+            .returning(|_, _| Err(discord::Error::NotEnoughPermissions));
+        let sut = NicknameEnforcer::new(
+            &mock_repo,
+            &mock_guild,
+            &NO_OPT_OUTS,
+            &NO_RECENT_NICKNAMES,
+            &config,
+        );
+
+        // Act
+        let result = sut.enforce(&member).await;
+
+        // Assert
+        assert!(matches!(result, Err(Error::DiscordError(_))));
+    }
+}
@@ -0,0 +1,163 @@
+//! A value kept in a file on disk, which the file repositories build on.
+//!
+//! The file is read on every load, so edits made to it by hand are picked up too. Changes are
+//! made under a lock, so that concurrent ones don't overwrite each other, and written through a
+//! temporary file, so that a crash can't leave half of them.
+
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use thiserror::Error;
+use tokio::sync::Mutex;
+
+/// Errors that can occur reading or writing the file.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Indicates the file can't be read or doesn't parse
+    #[error("Failed to load the file")]
+    CannotLoad,
+    /// Indicates the changed value can't be written back
+    #[error("Failed to save the file")]
+    CannotSave,
+}
+
+/// How a value is written down in its file.
+pub trait FileFormat {
+    /// The value the file holds, which is the default one until the file exists.
+    type Value: Clone + Default + PartialEq + Send;
+
+    /// Reads the value from the contents of the file.
+    fn parse(&self, contents: &str) -> anyhow::Result<Self::Value>;
+
+    /// Writes the value down as the contents of the file.
+    fn serialize(&self, value: &Self::Value) -> anyhow::Result<String>;
+}
+
+/// A value kept in the file at `path`, written down in `FORMAT`.
+pub struct FileStore<FORMAT: FileFormat> {
+    path: PathBuf,
+    format: FORMAT,
+    /// Held while changing the file
+    write_lock: Mutex<()>,
+}
+
+impl<FORMAT: FileFormat> FileStore<FORMAT> {
+    pub fn new(path: PathBuf, format: FORMAT) -> Self {
+        Self {
+            path,
+            format,
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    /// Reads the value from the file, or the default one if there's no file yet.
+    pub async fn load(&self) -> Result<FORMAT::Value, Error> {
+        let contents = match tokio::fs::read_to_string(&self.path).await {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(FORMAT::Value::default()),
+            Err(err) => {
+                tracing::error!("Failed to read {}: {err}", self.path.display());
+                return Err(Error::CannotLoad);
+            }
+        };
+        self.format.parse(&contents).map_err(|err| {
+            tracing::error!("Failed to parse {}: {err:#}", self.path.display());
+            Error::CannotLoad
+        })
+    }
+
+    /// Changes the value in the file, only writing it back if `change` changed it.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<T, Error>` - What `change` returned
+    pub async fn update<T>(
+        &self,
+        change: impl FnOnce(&mut FORMAT::Value) -> T,
+    ) -> Result<T, Error> {
+        let _guard = self.write_lock.lock().await;
+        let mut value = self.load().await?;
+        let before = value.clone();
+        let result = change(&mut value);
+        if value != before {
+            self.write(&value).await?;
+        }
+        Ok(result)
+    }
+
+    async fn write(&self, value: &FORMAT::Value) -> Result<(), Error> {
+        let contents = self.format.serialize(value).map_err(|err| {
+            tracing::error!("Failed to serialize {}: {err:#}", self.path.display());
+            Error::CannotSave
+        })?;
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+        let written = async {
+            tokio::fs::write(&temporary, contents).await?;
+            tokio::fs::rename(&temporary, &self.path).await
+        };
+        written.await.map_err(|err| {
+            tracing::error!("Failed to write {}: {err}", self.path.display());
+            Error::CannotSave
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Words, one per line.
+    struct Words;
+
+    impl FileFormat for Words {
+        type Value = Vec<String>;
+
+        fn parse(&self, contents: &str) -> anyhow::Result<Self::Value> {
+            Ok(contents.lines().map(str::to_string).collect())
+        }
+
+        fn serialize(&self, value: &Self::Value) -> anyhow::Result<String> {
+            Ok(value.iter().map(|word| format!("{word}\n")).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn only_writes_changes() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("words.txt");
+        let sut = FileStore::new(path.clone(), Words);
+
+        // Act
+        let before = sut.load().await.unwrap();
+        let unchanged = sut.update(|words| words.len()).await.unwrap();
+        let file_after_reading = path.exists();
+        sut.update(|words| words.push("hello".to_string()))
+            .await
+            .unwrap();
+
+        // Assert
+        assert!(before.is_empty());
+        assert_eq!(unchanged, 0);
+        assert!(!file_after_reading);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "hello\n");
+        assert!(!dir.path().join("words.txt.tmp").exists());
+    }
+
+    #[tokio::test]
+    async fn concurrent_changes_are_all_kept() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let sut = FileStore::new(dir.path().join("words.txt"), Words);
+
+        // Act
+        let (first, second) = tokio::join!(
+            sut.update(|words| words.push("first".to_string())),
+            sut.update(|words| words.push("second".to_string())),
+        );
+
+        // Assert
+        assert!(first.is_ok() && second.is_ok());
+        assert_eq!(sut.load().await.unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,166 @@
+//! Repository that keeps nickname history in a file on disk, so that it survives restarts.
+
+use crate::nicknamer::history::Error::{CannotLoadHistory, CannotSaveHistory};
+use crate::nicknamer::history::{Error, HistoryRepository, NicknameChange};
+use async_trait::async_trait;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::Mutex;
+
+/// Repository implementation that appends changes to a file, one per line.
+///
+/// Each line holds the guild ID, the member ID, when the change happened in seconds since the
+/// Unix epoch and the new nickname, separated by spaces. Lines without a nickname record that
+/// the member went back to their user name. The file doesn't need to exist until the first
+/// change is recorded.
+pub struct FileHistoryRepository {
+    path: PathBuf,
+    /// Held while appending, so that concurrent changes don't interleave
+    write_lock: Mutex<()>,
+}
+
+impl FileHistoryRepository {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    fn parse_line(&self, line: &str) -> Result<(u64, u64, NicknameChange), Error> {
+        let invalid = || {
+            tracing::error!("Invalid history line '{line}' in {}", self.path.display());
+            CannotLoadHistory
+        };
+        let mut fields = line.splitn(4, ' ');
+        let mut number = || -> Result<u64, Error> {
+            fields
+                .next()
+                .and_then(|field| field.parse().ok())
+                .ok_or_else(invalid)
+        };
+        let guild_id = number()?;
+        let member_id = number()?;
+        let changed_at = number()?;
+        let nick_name = fields.next().map(str::to_string);
+        Ok((
+            guild_id,
+            member_id,
+            NicknameChange {
+                nick_name,
+                changed_at,
+            },
+        ))
+    }
+}
+
+#[async_trait]
+impl HistoryRepository for FileHistoryRepository {
+    async fn record(
+        &self,
+        guild_id: u64,
+        member_id: u64,
+        change: NicknameChange,
+    ) -> Result<(), Error> {
+        let mut line = format!("{} {} {}", guild_id, member_id, change.changed_at);
+        if let Some(nick_name) = &change.nick_name {
+            // Nicknames can't hold line breaks, but a broken line would lose the whole file
+            line.push(' ');
+            line.push_str(&nick_name.replace(['\n', '\r'], " "));
+        }
+        line.push('\n');
+        let _guard = self.write_lock.lock().await;
+        let appended = async {
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .await?;
+            file.write_all(line.as_bytes()).await?;
+            // Tokio finishes writes in the background, so they could still be lost on drop
+            file.flush().await
+        };
+        appended.await.map_err(|err| {
+            tracing::error!("Failed to write {}: {err}", self.path.display());
+            CannotSaveHistory
+        })
+    }
+
+    async fn load_history(
+        &self,
+        guild_id: u64,
+        member_id: u64,
+    ) -> Result<Vec<NicknameChange>, Error> {
+        let contents = match tokio::fs::read_to_string(&self.path).await {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                tracing::error!("Failed to read {}: {err}", self.path.display());
+                return Err(CannotLoadHistory);
+            }
+        };
+        let mut changes = Vec::new();
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            let (line_guild_id, line_member_id, change) = self.parse_line(line)?;
+            if line_guild_id == guild_id && line_member_id == member_id {
+                changes.push(change);
+            }
+        }
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(nick_name: Option<&str>, changed_at: u64) -> NicknameChange {
+        NicknameChange {
+            nick_name: nick_name.map(str::to_string),
+            changed_at,
+        }
+    }
+
+    #[tokio::test]
+    async fn appends_changes_to_the_file() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.txt");
+        let sut = FileHistoryRepository::new(path.clone());
+
+        // Act
+        let before = sut.load_history(1, 2).await.unwrap();
+        sut.record(1, 2, change(Some("Lizzie Borden"), 100))
+            .await
+            .unwrap();
+        sut.record(1, 3, change(Some("Bob"), 200)).await.unwrap();
+        sut.record(1, 2, change(None, 300)).await.unwrap();
+
+        // Assert
+        assert!(before.is_empty());
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "1 2 100 Lizzie Borden\n1 3 200 Bob\n1 2 300\n"
+        );
+        let reread = FileHistoryRepository::new(path)
+            .load_history(1, 2)
+            .await
+            .unwrap();
+        assert_eq!(
+            reread,
+            [change(Some("Lizzie Borden"), 100), change(None, 300)]
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_to_load_broken_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("history.txt");
+        std::fs::write(&path, "1 2 100 Lizzie\nLizzie\n").unwrap();
+
+        let result = FileHistoryRepository::new(path).load_history(1, 2).await;
+
+        assert!(matches!(result, Err(CannotLoadHistory)));
+    }
+}
@@ -0,0 +1,303 @@
+//! Members' nickname history: every nickname the bot saw them take, and when.
+//!
+//! Changes are recorded as Discord reports members joining and changing nickname, and when the
+//! bot changes a nickname itself. The bot hears about its own changes from Discord too, so a
+//! change that repeats the last one recorded is skipped. History is kept per guild.
+//! This module includes:
+//! - A repository trait for recording and loading nickname changes
+//! - An implementation that only remembers them until the bot restarts
+//! - An implementation that keeps them in a file on disk
+//! - Recording a change, and listing a page of a member's history
+
+mod file;
+
+use crate::nicknamer::config::HistoryConfig;
+use async_trait::async_trait;
+pub use file::FileHistoryRepository;
+use pagination::{PageParams, Paginated};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+use tracing::warn;
+
+/// How many changes a page of history lists.
+pub const CHANGES_PER_PAGE: u64 = 10;
+
+/// Errors that can occur keeping nickname history.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Indicates a failure to read the history
+    #[error("Failed to load nickname history")]
+    CannotLoadHistory,
+    /// Indicates a failure to record a change
+    #[error("Failed to save nickname history")]
+    CannotSaveHistory,
+}
+
+/// A nickname a member took.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NicknameChange {
+    /// The new nickname, or `None` if they went back to their user name
+    pub nick_name: Option<String>,
+    /// Seconds since the Unix epoch
+    pub changed_at: u64,
+}
+
+impl NicknameChange {
+    /// A change to `nick_name` that happened just now.
+    pub fn now(nick_name: Option<&str>) -> Self {
+        let changed_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since_epoch| since_epoch.as_secs());
+        Self {
+            nick_name: nick_name.map(str::to_string),
+            changed_at,
+        }
+    }
+}
+
+/// Trait for keeping track of the nicknames members took.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait HistoryRepository {
+    /// Adds a change to the end of a member's history in a guild.
+    async fn record(
+        &self,
+        guild_id: u64,
+        member_id: u64,
+        change: NicknameChange,
+    ) -> Result<(), Error>;
+
+    /// Loads a member's history in a guild, oldest change first.
+    async fn load_history(
+        &self,
+        guild_id: u64,
+        member_id: u64,
+    ) -> Result<Vec<NicknameChange>, Error>;
+}
+
+/// Repository implementation that keeps history in memory, for when no file is configured.
+#[derive(Default)]
+pub struct MemoryHistoryRepository {
+    changes: Mutex<BTreeMap<(u64, u64), Vec<NicknameChange>>>,
+}
+
+impl MemoryHistoryRepository {
+    pub const fn new() -> Self {
+        Self {
+            changes: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl HistoryRepository for MemoryHistoryRepository {
+    async fn record(
+        &self,
+        guild_id: u64,
+        member_id: u64,
+        change: NicknameChange,
+    ) -> Result<(), Error> {
+        let mut changes = self.changes.lock().expect("history lock poisoned");
+        changes
+            .entry((guild_id, member_id))
+            .or_default()
+            .push(change);
+        Ok(())
+    }
+
+    async fn load_history(
+        &self,
+        guild_id: u64,
+        member_id: u64,
+    ) -> Result<Vec<NicknameChange>, Error> {
+        let changes = self.changes.lock().expect("history lock poisoned");
+        Ok(changes
+            .get(&(guild_id, member_id))
+            .cloned()
+            .unwrap_or_default())
+    }
+}
+
+/// The history repository picked in the config.
+pub enum ConfiguredHistoryRepository {
+    Memory(MemoryHistoryRepository),
+    File(FileHistoryRepository),
+}
+
+impl ConfiguredHistoryRepository {
+    pub fn from_config(config: &HistoryConfig) -> Self {
+        match &config.file {
+            Some(path) => Self::File(FileHistoryRepository::new(path.clone())),
+            None => {
+                warn!("No nickname history file configured, history is forgotten on restart");
+                Self::Memory(MemoryHistoryRepository::new())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl HistoryRepository for ConfiguredHistoryRepository {
+    async fn record(
+        &self,
+        guild_id: u64,
+        member_id: u64,
+        change: NicknameChange,
+    ) -> Result<(), Error> {
+        match self {
+            Self::Memory(repository) => repository.record(guild_id, member_id, change).await,
+            Self::File(repository) => repository.record(guild_id, member_id, change).await,
+        }
+    }
+
+    async fn load_history(
+        &self,
+        guild_id: u64,
+        member_id: u64,
+    ) -> Result<Vec<NicknameChange>, Error> {
+        match self {
+            Self::Memory(repository) => repository.load_history(guild_id, member_id).await,
+            Self::File(repository) => repository.load_history(guild_id, member_id).await,
+        }
+    }
+}
+
+/// Records that a member took `nick_name`, unless that's the nickname last recorded for them.
+///
+/// # Returns
+///
+/// * `Result<bool, Error>` - Whether the change was recorded
+pub async fn record_nickname(
+    history: &impl HistoryRepository,
+    guild_id: u64,
+    member_id: u64,
+    nick_name: Option<&str>,
+) -> Result<bool, Error> {
+    let changes = history.load_history(guild_id, member_id).await?;
+    if changes
+        .last()
+        .is_some_and(|last| last.nick_name.as_deref() == nick_name)
+    {
+        return Ok(false);
+    }
+    history
+        .record(guild_id, member_id, NicknameChange::now(nick_name))
+        .await?;
+    Ok(true)
+}
+
+/// Picks a page of a member's history, newest change first.
+///
+/// # Returns
+///
+/// * `Option<Paginated<NicknameChange>>` - The page, or `None` if the history doesn't have it.
+///   An empty history has an empty first page.
+pub fn history_page(
+    mut changes: Vec<NicknameChange>,
+    page: u64,
+) -> Option<Paginated<NicknameChange>> {
+    let params = PageParams::new(page, CHANGES_PER_PAGE).ok()?;
+    let total = changes.len() as u64;
+    if page > 1 && params.offset() >= total {
+        return None;
+    }
+    changes.reverse();
+    let items = changes
+        .into_iter()
+        .skip(params.offset() as usize)
+        .take(CHANGES_PER_PAGE as usize)
+        .collect();
+    Some(Paginated::new(items, params, total))
+}
+
+/// A change as a line of a history reply, with a timestamp Discord shows in the reader's time
+/// zone.
+pub fn format_change(change: &NicknameChange) -> String {
+    let nick_name = match &change.nick_name {
+        Some(nick_name) => format!("'{}'", nick_name),
+        None => "no nickname".to_string(),
+    };
+    format!("<t:{}:f> {}", change.changed_at, nick_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn changes(count: u64) -> Vec<NicknameChange> {
+        (1..=count)
+            .map(|n| NicknameChange {
+                nick_name: Some(format!("nick {n}")),
+                changed_at: n,
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn repeated_nicknames_are_recorded_once() {
+        // Arrange
+        let history = MemoryHistoryRepository::new();
+
+        // Act
+        let first = record_nickname(&history, 1, 2, Some("Lizzie"))
+            .await
+            .unwrap();
+        let repeated = record_nickname(&history, 1, 2, Some("Lizzie"))
+            .await
+            .unwrap();
+        let cleared = record_nickname(&history, 1, 2, None).await.unwrap();
+
+        // Assert
+        assert!(first);
+        assert!(!repeated);
+        assert!(cleared);
+        let recorded: Vec<Option<String>> = history
+            .load_history(1, 2)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|change| change.nick_name)
+            .collect();
+        assert_eq!(recorded, [Some("Lizzie".to_string()), None]);
+    }
+
+    #[tokio::test]
+    async fn history_is_kept_per_guild() {
+        let history = MemoryHistoryRepository::new();
+
+        record_nickname(&history, 1, 2, Some("Lizzie"))
+            .await
+            .unwrap();
+
+        assert!(history.load_history(3, 2).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn pages_list_the_newest_changes_first() {
+        let page = history_page(changes(25), 2).unwrap();
+
+        let listed: Vec<u64> = page.items.iter().map(|change| change.changed_at).collect();
+        assert_eq!(listed, (6..=15).rev().collect::<Vec<u64>>());
+        assert_eq!(page.total_pages, 3);
+        assert_eq!(page.next_page(), Some(3));
+    }
+
+    #[test]
+    fn pages_past_the_end_dont_exist() {
+        assert!(history_page(changes(10), 2).is_none());
+        assert!(history_page(changes(10), 0).is_none());
+        assert!(history_page(Vec::new(), 1).unwrap().items.is_empty());
+    }
+
+    #[test]
+    fn changes_show_when_they_happened() {
+        let change = NicknameChange {
+            nick_name: None,
+            changed_at: 1700000000,
+        };
+
+        assert_eq!(format_change(&change), "<t:1700000000:f> no nickname");
+    }
+}
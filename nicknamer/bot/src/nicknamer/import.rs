@@ -0,0 +1,173 @@
+//! Importing many real names at once from a file attached to a command.
+//!
+//! Files are YAML like `real_names.yml`, or CSV with a `discord_id,name` header, told apart by
+//! their extension. Importing only adds names of members who don't have one yet, the same way
+//! the server's bulk import does, so a preview lists the names that would be added and the ones
+//! that differ from what's already known and would be left out.
+
+use crate::nicknamer::connectors::discord;
+use crate::nicknamer::names::Names;
+use names_format::{Format, NamesFormatError, NamesMerge};
+use thiserror::Error;
+
+/// The largest file that's imported, far more than the names of any guild take.
+pub const MAX_IMPORT_BYTES: u64 = 1024 * 1024;
+
+/// The most names of each kind listed in a preview, so that it fits in an embed.
+const MAX_NAMES_LISTED: usize = 15;
+
+/// Reasons an attached file can't be imported, worded to be shown to whoever attached it.
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("I only import .yml, .yaml and .csv files")]
+    UnsupportedFile,
+    #[error("The file is bigger than {} KiB", MAX_IMPORT_BYTES / 1024)]
+    TooLarge,
+    #[error("The file isn't text")]
+    NotText,
+    #[error("{0}")]
+    InvalidNames(#[from] NamesFormatError),
+}
+
+/// Checks whether an attachment can be imported before it's downloaded.
+///
+/// # Returns
+///
+/// * `Result<Format, ImportError>` - The format to read the attachment in
+pub fn check_attachment(attachment: &discord::Attachment) -> Result<Format, ImportError> {
+    if attachment.size > MAX_IMPORT_BYTES {
+        return Err(ImportError::TooLarge);
+    }
+    let extension = attachment
+        .filename
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase());
+    match extension.as_deref() {
+        Some("yml" | "yaml") => Ok(Format::Yaml),
+        Some("csv") => Ok(Format::Csv),
+        _ => Err(ImportError::UnsupportedFile),
+    }
+}
+
+/// Parses and validates the contents of an attachment.
+pub fn parse(contents: &[u8], format: Format) -> Result<Names, ImportError> {
+    let contents = std::str::from_utf8(contents).map_err(|_| ImportError::NotText)?;
+    Ok(Names::parse(contents, format)?)
+}
+
+/// Lists what an import would change, for the invoker to confirm.
+pub fn summarize(merge: &NamesMerge) -> String {
+    let mut lines = vec![format!("Real names to add ({}):", merge.added.len())];
+    lines.extend(
+        merge
+            .added
+            .iter()
+            .take(MAX_NAMES_LISTED)
+            .map(|(discord_id, name)| format!("<@{}>: {}", discord_id, name)),
+    );
+    if merge.added.len() > MAX_NAMES_LISTED {
+        lines.push(format!(
+            "…and {} more",
+            merge.added.len() - MAX_NAMES_LISTED
+        ));
+    }
+    if !merge.conflicts.is_empty() {
+        lines.push(format!(
+            "\nReal names to keep, as the file has different ones ({}):",
+            merge.conflicts.len()
+        ));
+        lines.extend(
+            merge
+                .conflicts
+                .iter()
+                .take(MAX_NAMES_LISTED)
+                .map(|conflict| {
+                    format!(
+                        "<@{}>: {} (not {})",
+                        conflict.discord_id, conflict.existing, conflict.imported
+                    )
+                }),
+        );
+        if merge.conflicts.len() > MAX_NAMES_LISTED {
+            lines.push(format!(
+                "…and {} more",
+                merge.conflicts.len() - MAX_NAMES_LISTED
+            ));
+        }
+    }
+    if !merge.unchanged.is_empty() {
+        lines.push(format!("\nAlready known: {}", merge.unchanged.len()));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attachment(filename: &str, size: u64) -> discord::Attachment {
+        discord::Attachment {
+            id: 1,
+            filename: filename.to_string(),
+            size,
+        }
+    }
+
+    fn names(entries: &[(u64, &str)]) -> Names {
+        entries
+            .iter()
+            .map(|(discord_id, name)| (*discord_id, name.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn formats_come_from_the_extension() {
+        assert_eq!(
+            check_attachment(&attachment("names.YAML", 10)).unwrap(),
+            Format::Yaml
+        );
+        assert_eq!(
+            check_attachment(&attachment("names.csv", 10)).unwrap(),
+            Format::Csv
+        );
+        assert!(matches!(
+            check_attachment(&attachment("names.txt", 10)),
+            Err(ImportError::UnsupportedFile)
+        ));
+        assert!(matches!(
+            check_attachment(&attachment("names.yml", MAX_IMPORT_BYTES + 1)),
+            Err(ImportError::TooLarge)
+        ));
+    }
+
+    #[test]
+    fn parses_and_validates_files() {
+        let parsed = parse(b"discord_id,name\n123,Alice\n", Format::Csv).unwrap();
+        assert_eq!(parsed, names(&[(123, "Alice")]));
+
+        let invalid = parse(b"names:\n  123: ''\n", Format::Yaml).unwrap_err();
+        assert_eq!(
+            invalid.to_string(),
+            "Invalid names: 123: name must not be empty"
+        );
+        assert!(matches!(
+            parse(&[0xff, 0xfe], Format::Yaml),
+            Err(ImportError::NotText)
+        ));
+    }
+
+    #[test]
+    fn summaries_list_added_and_kept_names() {
+        let existing = names(&[(1, "Alice"), (2, "Bob")]);
+        let imported = names(&[(1, "Alice"), (2, "Robert"), (3, "Charlie")]);
+
+        let summary = summarize(&NamesMerge::new(&existing, &imported));
+
+        assert_eq!(
+            summary,
+            "Real names to add (1):\n<@3>: Charlie\n\n\
+             Real names to keep, as the file has different ones (1):\n<@2>: Bob (not Robert)\n\n\
+             Already known: 1"
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! Splitting long replies into messages Discord accepts.
+
+/// Discord refuses messages longer than this many characters.
+pub const MAX_MESSAGE_LENGTH: usize = 2000;
+
+/// Splits what would be the message `header + lines.join(separator) + footer` into as few
+/// messages of at most `limit` characters as it takes.
+///
+/// Messages only break between lines, and the messages after the first start with a line
+/// rather than the separator. A line too long for a message of its own is cut short.
+pub fn chunk_message(
+    header: &str,
+    lines: &[String],
+    separator: &str,
+    footer: &str,
+    limit: usize,
+) -> Vec<String> {
+    let mut messages = Vec::new();
+    let mut current = truncate(header, limit);
+    let mut current_length = current.chars().count();
+    let mut has_lines = false;
+    for line in lines {
+        let line = truncate(line, limit);
+        let line_length = line.chars().count();
+        let separator = if has_lines { separator } else { "" };
+        let separator_length = separator.chars().count();
+        if current_length + separator_length + line_length > limit {
+            messages.push(std::mem::take(&mut current));
+            current = line;
+            current_length = line_length;
+        } else {
+            current.push_str(separator);
+            current.push_str(&line);
+            current_length += separator_length + line_length;
+        }
+        has_lines = true;
+    }
+    if current_length + footer.chars().count() > limit {
+        messages.push(current);
+        current = truncate(footer.trim_start(), limit);
+    } else {
+        current.push_str(footer);
+    }
+    messages.push(current);
+    messages
+}
+
+/// `text` if it's at most `limit` characters, or as much of it as fits with an ellipsis.
+pub fn truncate(text: &str, limit: usize) -> String {
+    if text.chars().count() <= limit {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(limit.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn short_messages_stay_whole() {
+        let messages = chunk_message("Names:\n\t", &lines(&["a", "b"]), "\n\t", "\nBye", 2000);
+
+        assert_eq!(messages, ["Names:\n\ta\n\tb\nBye"]);
+    }
+
+    #[test]
+    fn long_messages_break_between_lines() {
+        // Arrange
+        let lines = lines(&["aaaa", "bbbb", "cccc", "dddd"]);
+
+        // Act
+        let messages = chunk_message("H:", &lines, "\n", "", 12);
+
+        // Assert
+        assert_eq!(messages, ["H:aaaa\nbbbb", "cccc\ndddd"]);
+        assert!(messages.iter().all(|message| message.chars().count() <= 12));
+    }
+
+    #[test]
+    fn footer_moves_to_its_own_message_when_it_does_not_fit() {
+        let messages = chunk_message("H:", &lines(&["aaaa"]), "\n", "\nfooter!", 10);
+
+        assert_eq!(messages, ["H:aaaa", "footer!"]);
+    }
+
+    #[test]
+    fn overlong_lines_are_cut_short() {
+        let messages = chunk_message("H:", &lines(&["abcdefghij"]), "\n", "", 5);
+
+        assert_eq!(messages, ["H:", "abcd…"]);
+    }
+
+    #[test]
+    fn limits_count_characters_rather_than_bytes() {
+        let messages = chunk_message("", &lines(&["😊😊", "🚀🚀"]), "", "", 4);
+
+        assert_eq!(messages, ["😊😊🚀🚀"]);
+    }
+
+    #[test]
+    fn no_lines_leaves_header_and_footer() {
+        let messages = chunk_message("Header", &[], "\n", " footer", 2000);
+
+        assert_eq!(messages, ["Header footer"]);
+    }
+}
@@ -0,0 +1,82 @@
+//! The bot's Prometheus metrics, served on `/metrics` by the web server:
+//!
+//! - `bot_commands_total`: commands run, by command
+//! - `bot_nickname_changes_total`: nicknames the bot changed, by what asked for the change
+//! - `bot_reveal_duration_seconds`: how long reveals take, of a member or the whole channel
+//! - `discord_api_errors_total`: failed Discord API calls, by operation and error, counted by
+//!   the Serenity connectors with `count_api_error`
+//!
+//! Nothing is kept until `observability::Metrics::install` sets up the recorder, so tests of
+//! instrumented code don't need one.
+
+use ::metrics::{counter, histogram};
+use std::time::Instant;
+
+/// Counts a command being run.
+pub fn command_executed(command: &str) {
+    counter!("bot_commands_total", "command" => command.to_string()).increment(1);
+}
+
+/// Counts a nickname the bot changed, for a `"command"` or for nickname `"enforcement"`.
+pub fn nickname_changed(source: &'static str) {
+    counter!("bot_nickname_changes_total", "source" => source).increment(1);
+}
+
+/// Times a reveal, recording how long it took when dropped, however the reveal ended.
+pub struct RevealTimer {
+    /// `"member"` or `"all"`
+    target: &'static str,
+    started: Instant,
+}
+
+impl RevealTimer {
+    pub fn start(target: &'static str) -> Self {
+        Self {
+            target,
+            started: Instant::now(),
+        }
+    }
+}
+
+impl Drop for RevealTimer {
+    fn drop(&mut self) {
+        histogram!("bot_reveal_duration_seconds", "target" => self.target)
+            .record(self.started.elapsed().as_secs_f64());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use metrics_exporter_prometheus::PrometheusBuilder;
+
+    #[test]
+    fn records_changes_and_reveal_durations() {
+        // Arrange
+        let recorder = PrometheusBuilder::new().build_recorder();
+        let handle = recorder.handle();
+
+        // Act
+        ::metrics::with_local_recorder(&recorder, || {
+            command_executed("nick");
+            nickname_changed("command");
+            nickname_changed("command");
+            drop(RevealTimer::start("all"));
+        });
+
+        // Assert
+        let rendered = handle.render();
+        assert!(
+            rendered.contains(r#"bot_commands_total{command="nick"} 1"#),
+            "{rendered}"
+        );
+        assert!(
+            rendered.contains(r#"bot_nickname_changes_total{source="command"} 2"#),
+            "{rendered}"
+        );
+        assert!(
+            rendered.contains(r#"bot_reveal_duration_seconds_count{target="all"} 1"#),
+            "{rendered}"
+        );
+    }
+}
@@ -1,15 +1,33 @@
 pub mod config;
 
+pub(crate) mod authorization;
+pub(crate) mod batch;
 pub(crate) mod connectors;
+pub(crate) mod cooldowns;
+pub(crate) mod enforcement;
+pub(crate) mod file_store;
+pub(crate) mod history;
+pub(crate) mod import;
+pub(crate) mod messages;
+pub(crate) mod metrics;
 pub(crate) mod names;
+pub(crate) mod privacy;
+pub(crate) mod reload;
+pub(crate) mod reporting;
 pub(crate) mod user;
 
 use crate::nicknamer::config::NicknamerConfig;
 use crate::nicknamer::connectors::discord;
 use async_trait::async_trait;
 use connectors::discord::DiscordConnector;
+use enforcement::RecentNicknames;
+use history::HistoryRepository;
+use messages::{MAX_MESSAGE_LENGTH, chunk_message};
 use names::NamesRepository;
-use tracing::info;
+use names_format::NamesMerge;
+use privacy::OptOutRepository;
+use std::time::Duration;
+use tracing::{info, warn};
 use user::Error;
 use user::User;
 
@@ -22,27 +40,138 @@ pub trait Nicknamer {
         member: &discord::ServerMember,
         new_nickname: &str,
     ) -> Result<(), Error>;
+    async fn add_name(&self, member: &discord::ServerMember, name: &str) -> Result<(), Error>;
+    async fn remove_name(&self, member: &discord::ServerMember) -> Result<(), Error>;
+    async fn whois(&self, query: &str) -> Result<(), Error>;
+    async fn nick_all(&self) -> Result<(), Error>;
+    async fn history(&self, member: &discord::ServerMember, page: u64) -> Result<(), Error>;
+    async fn import_names(&self, attachment: &discord::Attachment) -> Result<(), Error>;
 }
 
-pub struct NicknamerImpl<'a, REPO: NamesRepository, DISCORD: DiscordConnector> {
+const READ_ONLY_REPLY: &str =
+    "Real names can't be changed from Discord here, they have to be edited at the source";
+
+/// The most members `whois` lists, so that a vague search doesn't list everyone.
+const MAX_WHOIS_RESULTS: usize = 10;
+
+/// The reaction that confirms changing everyone's nickname.
+const CONFIRMATION_EMOJI: &str = "✅";
+
+/// How long the invoker of `nick_all` has to confirm the changes.
+const NICK_ALL_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long the invoker of `import_names` has to confirm the import.
+const IMPORT_CONFIRMATION_TIMEOUT: Duration = Duration::from_secs(60);
+
+pub struct NicknamerImpl<
+    'a,
+    REPO: NamesRepository,
+    DISCORD: DiscordConnector,
+    OPTOUTS: OptOutRepository,
+    HISTORY: HistoryRepository,
+> {
     names_repository: &'a REPO,
     discord_connector: &'a DISCORD,
+    opt_outs: &'a OPTOUTS,
+    history: &'a HISTORY,
     config: &'a NicknamerConfig,
+    /// Where the nicknames given are remembered, so that enforcement leaves them alone
+    recent_nicknames: Option<&'a RecentNicknames>,
 }
 
-impl<'a, REPO: NamesRepository, DISCORD: DiscordConnector> NicknamerImpl<'a, REPO, DISCORD> {
+impl<
+    'a,
+    REPO: NamesRepository,
+    DISCORD: DiscordConnector,
+    OPTOUTS: OptOutRepository,
+    HISTORY: HistoryRepository,
+> NicknamerImpl<'a, REPO, DISCORD, OPTOUTS, HISTORY>
+{
     pub fn new(
         names_repository: &'a REPO,
         discord_connector: &'a DISCORD,
+        opt_outs: &'a OPTOUTS,
+        history: &'a HISTORY,
         config: &'a NicknamerConfig,
     ) -> Self {
         Self {
             names_repository,
             discord_connector,
+            opt_outs,
+            history,
             config,
+            recent_nicknames: None,
+        }
+    }
+
+    /// Remembers the nicknames given in `recent_nicknames`.
+    pub fn with_recent_nicknames(mut self, recent_nicknames: &'a RecentNicknames) -> Self {
+        self.recent_nicknames = Some(recent_nicknames);
+        self
+    }
+
+    /// Gives a member a nickname, remembering it first if asked to, as Discord may report the
+    /// change before it's done.
+    async fn apply_nick_name(&self, member_id: u64, nick_name: &str) -> Result<(), discord::Error> {
+        let Some(recent_nicknames) = self.recent_nicknames else {
+            return self
+                .discord_connector
+                .change_member_nick_name(member_id, nick_name)
+                .await;
+        };
+        let guild_id = self.discord_connector.get_guild_id().await?;
+        recent_nicknames.remember(guild_id, member_id, nick_name);
+        let changed = self
+            .discord_connector
+            .change_member_nick_name(member_id, nick_name)
+            .await;
+        if changed.is_err() {
+            recent_nicknames.forget(guild_id, member_id);
+        }
+        changed
+    }
+
+    /// Records a nickname the bot gave a member, only warning if it can't, as the nickname
+    /// changed either way.
+    async fn record_change(&self, member_id: u64, nick_name: &str) {
+        let recorded = async {
+            let guild_id = self.discord_connector.get_guild_id().await?;
+            history::record_nickname(self.history, guild_id, member_id, Some(nick_name)).await?;
+            Ok::<(), Error>(())
+        };
+        if let Err(err) = recorded.await {
+            warn!(
+                "Failed to record the new nickname of {}: {}",
+                member_id, err
+            );
         }
     }
 
+    /// Lists changes in an embed and waits for the invoker to confirm them by reacting.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool, Error>` - Whether they confirmed before `timeout` ran out
+    async fn ask_for_confirmation(
+        &self,
+        title: &str,
+        description: &str,
+        timeout: Duration,
+    ) -> Result<bool, Error> {
+        let message_id = self
+            .discord_connector
+            .send_embed(title, description)
+            .await?;
+        self.discord_connector
+            .add_reaction(message_id, CONFIRMATION_EMOJI)
+            .await?;
+        let confirmed = self
+            .discord_connector
+            .await_author_reaction(message_id, CONFIRMATION_EMOJI, timeout)
+            .await?;
+        Ok(confirmed)
+    }
+
     async fn admonish_for_violating_party_guidelines(&self) -> Result<(), Error> {
         let reply = "You dare to rename our great General Secretary??? Away with your impudence!";
         self.discord_connector.send_reply(reply).await?;
@@ -54,21 +183,21 @@ impl<'a, REPO: NamesRepository, DISCORD: DiscordConnector> NicknamerImpl<'a, REP
         member: &discord::ServerMember,
         new_nick_name: &str,
     ) -> Result<(), Error> {
-        match self
-            .discord_connector
-            .change_member_nick_name(member.id, new_nick_name)
-            .await
-        {
-            Ok(()) => match &member.nick_name {
-                Some(nick_name) => {
-                    self.send_reply_for_member_with_nick_name(member, new_nick_name, nick_name)
-                        .await?
-                }
-                None => {
-                    self.send_reply_for_member_without_nick_name(member, new_nick_name)
-                        .await?
+        match self.apply_nick_name(member.id, new_nick_name).await {
+            Ok(()) => {
+                metrics::nickname_changed("command");
+                self.record_change(member.id, new_nick_name).await;
+                match &member.nick_name {
+                    Some(nick_name) => {
+                        self.send_reply_for_member_with_nick_name(member, new_nick_name, nick_name)
+                            .await?
+                    }
+                    None => {
+                        self.send_reply_for_member_without_nick_name(member, new_nick_name)
+                            .await?
+                    }
                 }
-            },
+            }
             Err(err) => {
                 let reply = match err {
                     discord::Error::NotEnoughPermissions => {
@@ -82,6 +211,9 @@ impl<'a, REPO: NamesRepository, DISCORD: DiscordConnector> NicknamerImpl<'a, REP
                             member.mention
                         )
                     }
+                    err if err.is_retryable() => {
+                        format!("Discord won't let me right now, try again later: {}", err)
+                    }
                     err => format!("You fool! You messed it up!: {}", err),
                 };
                 self.discord_connector.send_reply(&reply).await?;
@@ -90,6 +222,28 @@ impl<'a, REPO: NamesRepository, DISCORD: DiscordConnector> NicknamerImpl<'a, REP
         Ok(())
     }
 
+    /// Whether the member who invoked the command may change real names, telling them off if not.
+    async fn can_edit_names(&self) -> Result<bool, Error> {
+        let Some(role_name) = &self.config.name_editor_role else {
+            self.discord_connector
+                .send_reply(
+                    "Nobody is trusted with real names until a name editor role is configured",
+                )
+                .await?;
+            return Ok(false);
+        };
+        let role = self.discord_connector.get_role_by_name(role_name).await?;
+        if self.discord_connector.author_has_role(role.id()).await? {
+            return Ok(true);
+        }
+        let reply = format!(
+            "Only members with the {} role can change real names, {}!",
+            role_name, self.config.reveal.insult
+        );
+        self.discord_connector.send_reply(&reply).await?;
+        Ok(false)
+    }
+
     async fn send_reply_for_member_without_nick_name(
         &self,
         member: &discord::ServerMember,
@@ -119,26 +273,35 @@ impl<'a, REPO: NamesRepository, DISCORD: DiscordConnector> NicknamerImpl<'a, REP
 }
 
 #[async_trait]
-impl<REPO: NamesRepository + Send + Sync, DISCORD: DiscordConnector + Send + Sync> Nicknamer
-    for NicknamerImpl<'_, REPO, DISCORD>
+impl<
+    REPO: NamesRepository + Send + Sync,
+    DISCORD: DiscordConnector + Send + Sync,
+    OPTOUTS: OptOutRepository + Send + Sync,
+    HISTORY: HistoryRepository + Send + Sync,
+> Nicknamer for NicknamerImpl<'_, REPO, DISCORD, OPTOUTS, HISTORY>
 {
     #[tracing::instrument(skip(self))]
     async fn reveal_all(&self) -> Result<(), Error> {
+        let _timer = metrics::RevealTimer::start("all");
         info!("Revealing real names for current channel members ...");
         let members = self
             .discord_connector
             .get_members_of_current_channel()
             .await?;
 
+        // Members who opted out are left out altogether, rather than listed as unrecognized
+        let opted_out = self.opt_outs.load_opted_out().await?;
         let members: Vec<discord::ServerMember> = members
             .into_iter()
             .filter(|member| {
                 // Filter out bots and the "he who shall not be named" user
                 !member.is_bot && member.id != self.config.reveal.he_who_shall_not_be_named
             })
+            .filter(|member| !opted_out.contains(&member.id))
             .collect();
 
-        let real_names = self.names_repository.load_real_names().await?;
+        let guild_id = self.discord_connector.get_guild_id().await?;
+        let real_names = self.names_repository.load_real_names(guild_id).await?;
 
         // Reveal users with real names
         let users_with_real_names: Vec<User> = members
@@ -162,14 +325,15 @@ impl<REPO: NamesRepository + Send + Sync, DISCORD: DiscordConnector + Send + Syn
                 .map(|user| Self::format_user(user))
                 .collect::<Vec<String>>();
 
-            let formatted_reply = format!(
+            let header = format!(
                 "Here are people's real names, {}:
-\t{}",
-                self.config.reveal.insult,
-                reply.join("\n\t")
+\t",
+                self.config.reveal.insult
             );
 
-            self.discord_connector.send_reply(&formatted_reply).await?;
+            for message in chunk_message(&header, &reply, "\n\t", "", MAX_MESSAGE_LENGTH) {
+                self.discord_connector.send_reply(&message).await?;
+            }
         }
 
         // Reveal users without real names
@@ -202,16 +366,16 @@ impl<REPO: NamesRepository + Send + Sync, DISCORD: DiscordConnector + Send + Syn
                 .map(|user| Self::format_user(user))
                 .collect::<Vec<String>>();
 
-            let formatted_reply = format!(
+            let header = format!(
                 "Hey {}, these members are unrecognized:
-                \t{}
-                One of y'all should improve real name management and/or add them to the config",
-                role_to_mention.mention(),
-                reply.join("\n\t")
+                \t",
+                role_to_mention.mention()
             );
+            let footer = "
+                One of y'all should improve real name management and/or add them to the config";
 
-            if !formatted_reply.is_empty() {
-                self.discord_connector.send_reply(&formatted_reply).await?;
+            for message in chunk_message(&header, &reply, "\n\t", footer, MAX_MESSAGE_LENGTH) {
+                self.discord_connector.send_reply(&message).await?;
             }
         }
 
@@ -220,6 +384,7 @@ impl<REPO: NamesRepository + Send + Sync, DISCORD: DiscordConnector + Send + Syn
 
     #[tracing::instrument(skip(self))]
     async fn reveal(&self, member: &discord::ServerMember) -> Result<(), Error> {
+        let _timer = metrics::RevealTimer::start("member");
         info!("Revealing real name for {}", member.user_name);
         if member.is_bot {
             // Handle bot member
@@ -229,9 +394,14 @@ impl<REPO: NamesRepository + Send + Sync, DISCORD: DiscordConnector + Send + Syn
             };
             let reply = format!("{} is a bot, {}!", name_to_show, &self.config.reveal.insult);
             self.discord_connector.send_reply(&reply).await?;
+        } else if self.opt_outs.load_opted_out().await?.contains(&member.id) {
+            let name_to_show = member.nick_name.as_ref().unwrap_or(&member.user_name);
+            let reply = format!("'{}' keeps their real name private", name_to_show);
+            self.discord_connector.send_reply(&reply).await?;
         } else {
             // Handle human member
-            let names = self.names_repository.load_real_names().await?;
+            let guild_id = self.discord_connector.get_guild_id().await?;
+            let names = self.names_repository.load_real_names(guild_id).await?;
             let user_id = member.id;
             let mut user: User = member.into();
             let real_name = names.names.get(&user_id).cloned();
@@ -256,10 +426,317 @@ impl<REPO: NamesRepository + Send + Sync, DISCORD: DiscordConnector + Send + Syn
         }
         Ok(())
     }
+
+    #[tracing::instrument(skip(self))]
+    async fn add_name(&self, member: &discord::ServerMember, name: &str) -> Result<(), Error> {
+        if !self.can_edit_names().await? {
+            return Ok(());
+        }
+        let name = name.trim();
+        let reply = if member.is_bot {
+            format!("{} is a bot, it has no real name!", member.user_name)
+        } else if name.is_empty() {
+            "A real name can't be empty".to_string()
+        } else {
+            let guild_id = self.discord_connector.get_guild_id().await?;
+            match self
+                .names_repository
+                .save_name(guild_id, member.id, name)
+                .await
+            {
+                Ok(()) => {
+                    info!("Saved the real name of {}", member.user_name);
+                    format!("{} is now known to be {}", member.user_name, name)
+                }
+                Err(names::Error::ReadOnly) => READ_ONLY_REPLY.to_string(),
+                Err(err) => return Err(err.into()),
+            }
+        };
+        self.discord_connector.send_reply(&reply).await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn remove_name(&self, member: &discord::ServerMember) -> Result<(), Error> {
+        if !self.can_edit_names().await? {
+            return Ok(());
+        }
+        let guild_id = self.discord_connector.get_guild_id().await?;
+        let reply = match self.names_repository.delete_name(guild_id, member.id).await {
+            Ok(Some(name)) => {
+                info!("Removed the real name of {}", member.user_name);
+                format!("Forgot that {} is {}", member.user_name, name)
+            }
+            Ok(None) => format!("I don't know the real name of {}", member.user_name),
+            Err(names::Error::ReadOnly) => READ_ONLY_REPLY.to_string(),
+            Err(err) => return Err(err.into()),
+        };
+        self.discord_connector.send_reply(&reply).await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn whois(&self, query: &str) -> Result<(), Error> {
+        let query = query.trim();
+        if query.is_empty() {
+            self.discord_connector
+                .send_reply("Who is who? Tell me part of their real name")
+                .await?;
+            return Ok(());
+        }
+        let guild_id = self.discord_connector.get_guild_id().await?;
+        let mut real_names = self.names_repository.load_real_names(guild_id).await?;
+        let opted_out = self.opt_outs.load_opted_out().await?;
+        real_names
+            .names
+            .retain(|discord_id, _| !opted_out.contains(discord_id));
+        let found = names::search(&real_names, query, MAX_WHOIS_RESULTS);
+        info!("Found {} real names like '{}'", found.len(), query);
+        if found.is_empty() {
+            let reply = format!(
+                "Nobody goes by anything like '{}', {}",
+                query, self.config.reveal.insult
+            );
+            self.discord_connector.send_reply(&reply).await?;
+            return Ok(());
+        }
+
+        // Members who can't see the channel are listed without their nickname
+        let members = self
+            .discord_connector
+            .get_members_of_current_channel()
+            .await?;
+        let reply = found
+            .into_iter()
+            .map(
+                |found| match members.iter().find(|member| member.id == found.discord_id) {
+                    Some(member) => {
+                        let mut user: User = member.into();
+                        user.real_name = Some(found.name);
+                        Self::format_user(&user)
+                    }
+                    None => format!("{} isn't in this channel", found.name),
+                },
+            )
+            .collect::<Vec<String>>();
+
+        let header = format!("Here's who '{}' could be:\n\t", query);
+        for message in chunk_message(&header, &reply, "\n\t", "", MAX_MESSAGE_LENGTH) {
+            self.discord_connector.send_reply(&message).await?;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn nick_all(&self) -> Result<(), Error> {
+        let members = self
+            .discord_connector
+            .get_members_of_current_channel()
+            .await?;
+        let guild_id = self.discord_connector.get_guild_id().await?;
+        let real_names = self.names_repository.load_real_names(guild_id).await?;
+        let opted_out = self.opt_outs.load_opted_out().await?;
+        let owner_id = self.discord_connector.get_guild_owner_id().await?;
+        let changes = batch::propose_changes(&members, &real_names, &opted_out, owner_id);
+        info!("Proposing {} nickname changes", changes.len());
+        if changes.is_empty() {
+            self.discord_connector
+                .send_reply("Everyone here already goes by their real name")
+                .await?;
+            return Ok(());
+        }
+
+        let description = format!(
+            "{}\n\nReact with {} within {} seconds to make the changes",
+            batch::summarize(&changes),
+            CONFIRMATION_EMOJI,
+            NICK_ALL_CONFIRMATION_TIMEOUT.as_secs()
+        );
+        let confirmed = self
+            .ask_for_confirmation(
+                "Change these nicknames to real names?",
+                &description,
+                NICK_ALL_CONFIRMATION_TIMEOUT,
+            )
+            .await?;
+        if !confirmed {
+            info!("Nickname changes weren't confirmed in time");
+            self.discord_connector
+                .send_reply("Not confirmed in time, so nicknames stay as they are")
+                .await?;
+            return Ok(());
+        }
+
+        let mut failed = Vec::new();
+        for change in &changes {
+            match self
+                .apply_nick_name(change.member.id, &change.real_name)
+                .await
+            {
+                Ok(()) => {
+                    metrics::nickname_changed("command");
+                    self.record_change(change.member.id, &change.real_name)
+                        .await;
+                }
+                Err(err) => {
+                    warn!(
+                        "Failed to change the nickname of {}: {}",
+                        change.member.user_name, err
+                    );
+                    failed.push(format!("{}: {}", change.member.user_name, err));
+                }
+            }
+        }
+
+        let header = format!(
+            "Changed {} of {} nicknames to real names",
+            changes.len() - failed.len(),
+            changes.len()
+        );
+        if failed.is_empty() {
+            self.discord_connector.send_reply(&header).await?;
+            return Ok(());
+        }
+        let header = format!("{}, these couldn't be changed:\n\t", header);
+        for message in chunk_message(&header, &failed, "\n\t", "", MAX_MESSAGE_LENGTH) {
+            self.discord_connector.send_reply(&message).await?;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn history(&self, member: &discord::ServerMember, page: u64) -> Result<(), Error> {
+        let guild_id = self.discord_connector.get_guild_id().await?;
+        let changes = self.history.load_history(guild_id, member.id).await?;
+        let Some(changes) = history::history_page(changes, page) else {
+            let reply = format!("There's no page {} of {}'s nicknames", page, member.mention);
+            self.discord_connector.send_reply(&reply).await?;
+            return Ok(());
+        };
+        if changes.items.is_empty() {
+            let reply = format!("I haven't seen {} take a nickname yet", member.mention);
+            self.discord_connector.send_reply(&reply).await?;
+            return Ok(());
+        }
+        let lines: Vec<String> = changes.items.iter().map(history::format_change).collect();
+        let mut reply = format!(
+            "Nicknames {} took, newest first (page {} of {}):\n\t{}",
+            member.mention,
+            changes.page,
+            changes.total_pages,
+            lines.join("\n\t")
+        );
+        if let Some(next_page) = changes.next_page() {
+            reply.push_str(&format!("\nAsk for page {} for older ones", next_page));
+        }
+        self.discord_connector.send_reply(&reply).await?;
+        Ok(())
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn import_names(&self, attachment: &discord::Attachment) -> Result<(), Error> {
+        if !self.can_edit_names().await? {
+            return Ok(());
+        }
+        let imported = match import::check_attachment(attachment) {
+            Ok(format) => {
+                let contents = self
+                    .discord_connector
+                    .download_attachment(attachment)
+                    .await?;
+                import::parse(&contents, format)
+            }
+            Err(err) => Err(err),
+        };
+        let imported = match imported {
+            Ok(imported) => imported,
+            Err(err) => {
+                info!("Refused to import {}: {}", attachment.filename, err);
+                let reply = format!("I can't import {}: {}", attachment.filename, err);
+                self.discord_connector.send_reply(&reply).await?;
+                return Ok(());
+            }
+        };
+        let guild_id = self.discord_connector.get_guild_id().await?;
+        let real_names = self.names_repository.load_real_names(guild_id).await?;
+        let merge = NamesMerge::new(&real_names, &imported);
+        info!(
+            "Proposing to import {} of {} real names",
+            merge.added.len(),
+            imported.len()
+        );
+        if merge.is_empty() {
+            let reply = format!(
+                "Everyone in {} already has a real name, so there's nothing to import",
+                attachment.filename
+            );
+            self.discord_connector.send_reply(&reply).await?;
+            return Ok(());
+        }
+
+        let description = format!(
+            "{}\n\nReact with {} within {} seconds to import them",
+            import::summarize(&merge),
+            CONFIRMATION_EMOJI,
+            IMPORT_CONFIRMATION_TIMEOUT.as_secs()
+        );
+        let confirmed = self
+            .ask_for_confirmation(
+                "Import these real names?",
+                &description,
+                IMPORT_CONFIRMATION_TIMEOUT,
+            )
+            .await?;
+        if !confirmed {
+            info!("Name import wasn't confirmed in time");
+            self.discord_connector
+                .send_reply("Not confirmed in time, so no names were imported")
+                .await?;
+            return Ok(());
+        }
+
+        let mut failed = Vec::new();
+        for (discord_id, name) in &merge.added {
+            match self
+                .names_repository
+                .save_name(guild_id, *discord_id, name)
+                .await
+            {
+                Ok(()) => {}
+                Err(names::Error::ReadOnly) => {
+                    self.discord_connector.send_reply(READ_ONLY_REPLY).await?;
+                    return Ok(());
+                }
+                Err(err) => {
+                    warn!("Failed to import the real name of {}: {}", discord_id, err);
+                    failed.push(format!("<@{}>: {}", discord_id, err));
+                }
+            }
+        }
+
+        let header = format!(
+            "Imported {} of {} real names",
+            merge.added.len() - failed.len(),
+            merge.added.len()
+        );
+        if failed.is_empty() {
+            self.discord_connector.send_reply(&header).await?;
+            return Ok(());
+        }
+        let header = format!("{}, these couldn't be saved:\n\t", header);
+        for message in chunk_message(&header, &failed, "\n\t", "", MAX_MESSAGE_LENGTH) {
+            self.discord_connector.send_reply(&message).await?;
+        }
+        Ok(())
+    }
 }
 
-impl<REPO: NamesRepository + Send + Sync, DISCORD: DiscordConnector + Send + Sync>
-    NicknamerImpl<'_, REPO, DISCORD>
+impl<
+    REPO: NamesRepository + Send + Sync,
+    DISCORD: DiscordConnector + Send + Sync,
+    OPTOUTS: OptOutRepository + Send + Sync,
+    HISTORY: HistoryRepository + Send + Sync,
+> NicknamerImpl<'_, REPO, DISCORD, OPTOUTS, HISTORY>
 {
     fn format_user(user: &User) -> String {
         if let Some(real_name) = &user.real_name {
@@ -282,18 +759,25 @@ mod nicknamer_impl_tests {
     use crate::nicknamer::config;
     use crate::nicknamer::config::NicknamerConfig;
     use crate::nicknamer::connectors::discord::MockDiscordConnector;
+    use crate::nicknamer::history::MemoryHistoryRepository;
     use crate::nicknamer::names::MockNamesRepository;
+    use crate::nicknamer::privacy::MemoryOptOutRepository;
 
     static HE_WHO_SHALL_NOT_BE_NAMED: u64 = 899501665365929985; // Example ID for tests
+    static GUILD_ID: u64 = 42;
 
     // Helper function to create a test NicknamerConfig for tests
     fn create_test_config() -> config::NicknamerConfig {
         config::NicknamerConfig {
+            name_editor_role: Some("Name Keepers".to_string()),
             reveal: config::RevealConfig {
                 insult: "ya dingus".to_string(),
                 role_to_mention: "Code Monkeys".to_string(),
                 he_who_shall_not_be_named: HE_WHO_SHALL_NOT_BE_NAMED, // Ensure this ID is correct
             },
+            enforcement: config::EnforcementConfig::default(),
+            cooldowns: config::CooldownsConfig::default(),
+            permissions: config::PermissionsConfig::default(),
         }
     }
 
@@ -313,22 +797,39 @@ mod nicknamer_impl_tests {
         }
     }
 
-    impl crate::nicknamer::connectors::discord::Role for MockRole {}
+    impl crate::nicknamer::connectors::discord::Role for MockRole {
+        fn id(&self) -> u64 {
+            1
+        }
+    }
+
+    // Nobody has opted out unless a test says so
+    static NO_OPT_OUTS: MemoryOptOutRepository = MemoryOptOutRepository::new();
+
+    // Shared by the tests that don't look at the nickname history
+    static HISTORY: MemoryHistoryRepository = MemoryHistoryRepository::new();
 
     // Helper function to create a NicknamerImpl with mock objects
     fn create_nicknamer<'a>(
         repo: &'a MockNamesRepository,
         discord: &'a MockDiscordConnector,
         config: &'a NicknamerConfig,
-    ) -> NicknamerImpl<'a, MockNamesRepository, MockDiscordConnector> {
-        NicknamerImpl::new(repo, discord, config)
+    ) -> NicknamerImpl<
+        'a,
+        MockNamesRepository,
+        MockDiscordConnector,
+        MemoryOptOutRepository,
+        MemoryHistoryRepository,
+    > {
+        NicknamerImpl::new(repo, discord, &NO_OPT_OUTS, &HISTORY, config)
     }
 
     mod change_nickname_tests {
-        use super::{MockRole, create_nicknamer, create_test_config};
+        use super::{GUILD_ID, MockRole, create_nicknamer, create_test_config};
         use crate::nicknamer::Nicknamer;
         use crate::nicknamer::connectors::discord::MockDiscordConnector;
-        use crate::nicknamer::connectors::discord::server_member::ServerMemberBuilder;
+        use crate::nicknamer::connectors::discord::ServerMemberBuilder;
+        use crate::nicknamer::enforcement::RecentNicknames;
         use crate::nicknamer::names::MockNamesRepository;
         use crate::nicknamer::user::Error;
         use mockall::predicate::*;
@@ -356,6 +857,9 @@ mod nicknamer_impl_tests {
                 .expect_get_guild_owner_id()
                 .times(1)
                 .returning(|| Ok(GUILD_OWNER_ID));
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
 
             mock_discord
                 .expect_change_member_nick_name()
@@ -381,6 +885,38 @@ mod nicknamer_impl_tests {
             assert!(result.is_ok(), "change_nickname should succeed");
         }
 
+        #[tokio::test]
+        async fn change_nickname_remembers_the_nickname_for_enforcement() {
+            // Arrange
+            let mock_repo = MockNamesRepository::new();
+            let mut mock_discord = MockDiscordConnector::new();
+            let config = create_test_config();
+            let recent_nicknames = RecentNicknames::new();
+            let member = ServerMemberBuilder::new()
+                .id(123456789)
+                .user_name("TestUser")
+                .build();
+            mock_discord
+                .expect_get_guild_owner_id()
+                .returning(|| Ok(GUILD_OWNER_ID));
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
+            mock_discord
+                .expect_change_member_nick_name()
+                .returning(|_, _| Ok(()));
+            mock_discord.expect_send_reply().returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config)
+                .with_recent_nicknames(&recent_nicknames);
+
+            // Act
+            let result = sut.change_nickname(&member, "NewNickname").await;
+
+            // Assert
+            assert!(result.is_ok(), "change_nickname should succeed");
+            assert!(recent_nicknames.was_applied(GUILD_ID, 123456789, Some("NewNickname")));
+        }
+
         #[tokio::test]
         async fn change_nickname_prevents_renaming_server_owner() {
             // Arrange
@@ -512,6 +1048,35 @@ mod nicknamer_impl_tests {
             );
         }
 
+        #[tokio::test]
+        async fn change_nickname_asks_to_try_again_when_discord_is_unavailable() {
+            // Arrange
+            let mock_repo = MockNamesRepository::new();
+            let mut mock_discord = MockDiscordConnector::new();
+            let config = create_test_config();
+            let member = ServerMemberBuilder::new().id(123456789).build();
+            mock_discord
+                .expect_get_guild_owner_id()
+                .returning(|| Ok(GUILD_OWNER_ID));
+            mock_discord
+                .expect_change_member_nick_name()
+                .returning(|_, _| Err(crate::nicknamer::connectors::discord::Error::Unavailable));
+            mock_discord
+                .expect_send_reply()
+                .with(eq(
+                    "Discord won't let me right now, try again later: Discord is unavailable",
+                ))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.change_nickname(&member, "NewNickname").await;
+
+            // Assert
+            assert!(result.is_ok());
+        }
+
         #[tokio::test]
         async fn change_nickname_handles_permission_error() {
             // Arrange
@@ -589,6 +1154,9 @@ mod nicknamer_impl_tests {
                 .expect_get_guild_owner_id()
                 .times(1)
                 .returning(|| Ok(GUILD_OWNER_ID));
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
 
             // Discord connector accepts empty nickname
             mock_discord
@@ -635,6 +1203,9 @@ mod nicknamer_impl_tests {
                 .expect_get_guild_owner_id()
                 .times(1)
                 .returning(|| Ok(GUILD_OWNER_ID));
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
 
             // Discord connector changes nickname
             mock_discord
@@ -688,6 +1259,9 @@ mod nicknamer_impl_tests {
                 .expect_get_guild_owner_id()
                 .times(1)
                 .returning(|| Ok(GUILD_OWNER_ID));
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
 
             // Discord connector accepts long nickname
             mock_discord
@@ -735,6 +1309,9 @@ mod nicknamer_impl_tests {
                 .expect_get_guild_owner_id()
                 .times(1)
                 .returning(|| Ok(GUILD_OWNER_ID));
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
 
             // Discord connector changes nickname
             mock_discord
@@ -786,6 +1363,9 @@ mod nicknamer_impl_tests {
                 .expect_get_guild_owner_id()
                 .times(1)
                 .returning(|| Ok(GUILD_OWNER_ID));
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
 
             // Discord connector changes nickname
             mock_discord
@@ -818,10 +1398,10 @@ mod nicknamer_impl_tests {
     }
 
     mod reveal_tests {
-        use super::{MockRole, create_nicknamer, create_test_config};
+        use super::{GUILD_ID, MockRole, create_nicknamer, create_test_config};
         use crate::nicknamer::Nicknamer;
         use crate::nicknamer::connectors::discord::MockDiscordConnector;
-        use crate::nicknamer::connectors::discord::server_member::ServerMemberBuilder;
+        use crate::nicknamer::connectors::discord::ServerMemberBuilder;
         use crate::nicknamer::names::{MockNamesRepository, Names};
         use crate::nicknamer::user::Error;
         use mockall::predicate::*;
@@ -862,10 +1442,13 @@ mod nicknamer_impl_tests {
                 .times(1)
                 .returning(move || Ok(members.clone()));
 
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
             mock_repo
                 .expect_load_real_names()
                 .times(1)
-                .returning(move || Ok(names_db.clone()));
+                .returning(move |_| Ok(names_db.clone()));
 
             // Expect that the reply only contains "TheBoyWhoLived"
             let expected_reply = format!(
@@ -922,10 +1505,13 @@ mod nicknamer_impl_tests {
                 .times(1)
                 .returning(move || Ok(members.clone()));
 
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
             mock_repo
                 .expect_load_real_names()
                 .times(1)
-                .returning(move || Ok(names.clone()));
+                .returning(move |_| Ok(names.clone()));
 
             // Expect exact message content
             mock_discord
@@ -949,6 +1535,57 @@ mod nicknamer_impl_tests {
             assert!(result.is_ok(), "reveal_all should succeed");
         }
 
+        #[tokio::test]
+        async fn reveal_all_splits_big_channels_across_messages() {
+            // Arrange
+            let mut mock_repo = MockNamesRepository::new();
+            let mut mock_discord = MockDiscordConnector::new();
+            let config = create_test_config();
+            let members: Vec<_> = (1..=200)
+                .map(|id| {
+                    ServerMemberBuilder::new()
+                        .id(id)
+                        .nick_name(format!("Nickname{}", id))
+                        .build()
+                })
+                .collect();
+            let names = Names {
+                names: (1..=200).map(|id| (id, format!("Name{}", id))).collect(),
+            };
+            mock_discord
+                .expect_get_members_of_current_channel()
+                .returning(move || Ok(members.clone()));
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
+            mock_repo
+                .expect_load_real_names()
+                .returning(move |_| Ok(names.clone()));
+            let sent = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+            let sent_by_mock = sent.clone();
+            mock_discord.expect_send_reply().returning(move |message| {
+                sent_by_mock.lock().unwrap().push(message.to_string());
+                Ok(())
+            });
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.reveal_all().await;
+
+            // Assert
+            assert!(result.is_ok(), "reveal_all should succeed");
+            let sent = sent.lock().unwrap();
+            assert!(sent.len() > 1, "200 members shouldn't fit in one message");
+            assert!(sent.iter().all(|message| message.chars().count() <= 2000));
+            assert!(sent[0].starts_with("Here are people's real names, ya dingus:"));
+            let revealed = sent.iter().flat_map(|message| message.lines()).count();
+            assert_eq!(
+                revealed,
+                200 + 1,
+                "every member and the header should be sent"
+            );
+        }
+
         #[tokio::test]
         async fn handles_discord_error_when_revealing_all() {
             // Setup mock objects
@@ -1012,10 +1649,13 @@ mod nicknamer_impl_tests {
                 .times(1)
                 .returning(move || Ok(members.clone()));
 
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
             mock_repo
                 .expect_load_real_names()
                 .times(1)
-                .returning(move || Ok(names.clone()));
+                .returning(move |_| Ok(names.clone()));
 
             // Mock the role request
             mock_discord
@@ -1080,10 +1720,13 @@ mod nicknamer_impl_tests {
                 .times(1)
                 .returning(move || Ok(members.clone()));
 
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
             mock_repo
                 .expect_load_real_names()
                 .times(1)
-                .returning(move || Ok(names.clone()));
+                .returning(move |_| Ok(names.clone()));
 
             // No message should be sent because all users are bots
             // So we don't expect any call to send_reply
@@ -1229,10 +1872,13 @@ mod nicknamer_impl_tests {
             };
 
             // Set up expectations
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
             mock_repo
                 .expect_load_real_names()
                 .times(1)
-                .returning(move || Ok(names.clone()));
+                .returning(move |_| Ok(names.clone()));
 
             // The message should show username and nickname but no real name
             mock_discord
@@ -1275,10 +1921,13 @@ mod nicknamer_impl_tests {
             let names = Names { names: names_map };
 
             // Set up expectations
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
             mock_repo
                 .expect_load_real_names()
                 .times(1)
-                .returning(move || Ok(names.clone()));
+                .returning(move |_| Ok(names.clone()));
 
             // The message should include the real name
             mock_discord
@@ -1320,10 +1969,13 @@ mod nicknamer_impl_tests {
             let names = Names { names: names_map };
 
             // Set up expectations
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
             mock_repo
                 .expect_load_real_names()
                 .times(1)
-                .returning(move || Ok(names.clone()));
+                .returning(move |_| Ok(names.clone()));
 
             // The message should include the real name but use username instead of nickname
             mock_discord
@@ -1365,10 +2017,13 @@ mod nicknamer_impl_tests {
             };
 
             // Set up expectations
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
             mock_repo
                 .expect_load_real_names()
                 .times(1)
-                .returning(move || Ok(names.clone()));
+                .returning(move |_| Ok(names.clone()));
 
             // The message should indicate the user has neither a nickname nor a real name
             mock_discord
@@ -1396,7 +2051,7 @@ mod nicknamer_impl_tests {
         async fn reveal_member_should_handle_names_repository_error() {
             // Setup mock objects
             let mut mock_repo = MockNamesRepository::new();
-            let mock_discord = MockDiscordConnector::new();
+            let mut mock_discord = MockDiscordConnector::new();
             let config = create_test_config();
 
             // Define test data
@@ -1408,10 +2063,13 @@ mod nicknamer_impl_tests {
                 .build();
 
             // Set up expectations - repository returns an error
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
             mock_repo
                 .expect_load_real_names()
                 .times(1)
-                .returning(|| Err(crate::nicknamer::names::Error::CannotLoadNames));
+                .returning(|_| Err(crate::nicknamer::names::Error::CannotLoadNames));
 
             // Create nicknamer with mock objects
             let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
@@ -1427,4 +2085,929 @@ mod nicknamer_impl_tests {
             );
         }
     }
+
+    mod name_editing_tests {
+        use super::{GUILD_ID, MockRole, create_nicknamer, create_test_config};
+        use crate::nicknamer::connectors::discord::MockDiscordConnector;
+        use crate::nicknamer::connectors::discord::ServerMemberBuilder;
+        use crate::nicknamer::names::{Error as NamesError, MockNamesRepository};
+        use crate::nicknamer::user::Error;
+        use crate::nicknamer::{Nicknamer, READ_ONLY_REPLY};
+        use mockall::predicate::*;
+
+        /// Lets the invoking member through the name editor check, or not.
+        fn expect_name_editor(mock_discord: &mut MockDiscordConnector, allowed: bool) {
+            mock_discord
+                .expect_get_role_by_name()
+                .with(eq("Name Keepers"))
+                .times(1)
+                .returning(|_| Ok(Box::new(MockRole::new())));
+            mock_discord
+                .expect_author_has_role()
+                .with(eq(1))
+                .times(1)
+                .returning(move |_| Ok(allowed));
+        }
+
+        #[tokio::test]
+        async fn add_name_saves_the_trimmed_name() {
+            // Arrange
+            let mut mock_repo = MockNamesRepository::new();
+            let mut mock_discord = MockDiscordConnector::new();
+            let config = create_test_config();
+            let member = ServerMemberBuilder::new()
+                .id(123456789)
+                .user_name("TestUser")
+                .build();
+            expect_name_editor(&mut mock_discord, true);
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
+            mock_repo
+                .expect_save_name()
+                .with(eq(GUILD_ID), eq(123456789), eq("Alice"))
+                .times(1)
+                .returning(|_, _, _| Ok(()));
+            mock_discord
+                .expect_send_reply()
+                .with(eq("TestUser is now known to be Alice"))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.add_name(&member, "  Alice ").await;
+
+            // Assert
+            assert!(result.is_ok(), "add_name should succeed");
+        }
+
+        #[tokio::test]
+        async fn add_name_refuses_members_without_the_editor_role() {
+            // Arrange
+            let mock_repo = MockNamesRepository::new();
+            let mut mock_discord = MockDiscordConnector::new();
+            let config = create_test_config();
+            let member = ServerMemberBuilder::new().id(123456789).build();
+            expect_name_editor(&mut mock_discord, false);
+            mock_discord
+                .expect_send_reply()
+                .with(eq(
+                    "Only members with the Name Keepers role can change real names, ya dingus!",
+                ))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.add_name(&member, "Alice").await;
+
+            // Assert
+            assert!(result.is_ok(), "refusing should not be an error");
+        }
+
+        #[tokio::test]
+        async fn add_name_refuses_everyone_without_an_editor_role_configured() {
+            // Arrange
+            let mock_repo = MockNamesRepository::new();
+            let mut mock_discord = MockDiscordConnector::new();
+            let mut config = create_test_config();
+            config.name_editor_role = None;
+            let member = ServerMemberBuilder::new().id(123456789).build();
+            mock_discord
+                .expect_send_reply()
+                .with(eq(
+                    "Nobody is trusted with real names until a name editor role is configured",
+                ))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.add_name(&member, "Alice").await;
+
+            // Assert
+            assert!(result.is_ok(), "refusing should not be an error");
+        }
+
+        #[tokio::test]
+        async fn add_name_explains_read_only_repositories() {
+            // Arrange
+            let mut mock_repo = MockNamesRepository::new();
+            let mut mock_discord = MockDiscordConnector::new();
+            let config = create_test_config();
+            let member = ServerMemberBuilder::new().id(123456789).build();
+            expect_name_editor(&mut mock_discord, true);
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
+            mock_repo
+                .expect_save_name()
+                .times(1)
+                .returning(|_, _, _| Err(NamesError::ReadOnly));
+            mock_discord
+                .expect_send_reply()
+                .with(eq(READ_ONLY_REPLY))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.add_name(&member, "Alice").await;
+
+            // Assert
+            assert!(
+                result.is_ok(),
+                "read-only repositories should not be an error"
+            );
+        }
+
+        #[tokio::test]
+        async fn remove_name_reports_the_forgotten_name() {
+            // Arrange
+            let mut mock_repo = MockNamesRepository::new();
+            let mut mock_discord = MockDiscordConnector::new();
+            let config = create_test_config();
+            let member = ServerMemberBuilder::new()
+                .id(123456789)
+                .user_name("TestUser")
+                .build();
+            expect_name_editor(&mut mock_discord, true);
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
+            mock_repo
+                .expect_delete_name()
+                .with(eq(GUILD_ID), eq(123456789))
+                .times(1)
+                .returning(|_, _| Ok(Some("Alice".to_string())));
+            mock_discord
+                .expect_send_reply()
+                .with(eq("Forgot that TestUser is Alice"))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.remove_name(&member).await;
+
+            // Assert
+            assert!(result.is_ok(), "remove_name should succeed");
+        }
+
+        #[tokio::test]
+        async fn remove_name_handles_members_without_a_name() {
+            // Arrange
+            let mut mock_repo = MockNamesRepository::new();
+            let mut mock_discord = MockDiscordConnector::new();
+            let config = create_test_config();
+            let member = ServerMemberBuilder::new()
+                .id(123456789)
+                .user_name("TestUser")
+                .build();
+            expect_name_editor(&mut mock_discord, true);
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
+            mock_repo
+                .expect_delete_name()
+                .times(1)
+                .returning(|_, _| Ok(None));
+            mock_discord
+                .expect_send_reply()
+                .with(eq("I don't know the real name of TestUser"))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.remove_name(&member).await;
+
+            // Assert
+            assert!(result.is_ok(), "remove_name should succeed");
+        }
+
+        #[tokio::test]
+        async fn remove_name_passes_on_storage_errors() {
+            // Arrange
+            let mut mock_repo = MockNamesRepository::new();
+            let mut mock_discord = MockDiscordConnector::new();
+            let config = create_test_config();
+            let member = ServerMemberBuilder::new().id(123456789).build();
+            expect_name_editor(&mut mock_discord, true);
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
+            mock_repo
+                .expect_delete_name()
+                .times(1)
+                .returning(|_, _| Err(NamesError::CannotSaveNames));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.remove_name(&member).await;
+
+            // Assert
+            assert!(matches!(result, Err(Error::NamesAccessError(_))));
+        }
+    }
+
+    mod whois_tests {
+        use super::{GUILD_ID, create_nicknamer, create_test_config};
+        use crate::nicknamer::Nicknamer;
+        use crate::nicknamer::connectors::discord::MockDiscordConnector;
+        use crate::nicknamer::connectors::discord::ServerMemberBuilder;
+        use crate::nicknamer::names::{MockNamesRepository, Names};
+        use mockall::predicate::*;
+
+        fn names_repository() -> MockNamesRepository {
+            let mut mock_repo = MockNamesRepository::new();
+            mock_repo
+                .expect_load_real_names()
+                .with(eq(GUILD_ID))
+                .times(1)
+                .returning(|_| {
+                    Ok([(1, "Alice Smith"), (2, "Alicia Keys"), (3, "Bob")]
+                        .into_iter()
+                        .map(|(discord_id, name)| (discord_id, name.to_string()))
+                        .collect::<Names>())
+                });
+            mock_repo
+        }
+
+        #[tokio::test]
+        async fn whois_lists_members_with_names_like_the_query() {
+            // Arrange
+            let mock_repo = names_repository();
+            let mut mock_discord = MockDiscordConnector::new();
+            let config = create_test_config();
+            let members = vec![
+                ServerMemberBuilder::new()
+                    .id(1)
+                    .user_name("alice99")
+                    .nick_name("Wonderland")
+                    .build(),
+                ServerMemberBuilder::new().id(3).user_name("bobby").build(),
+            ];
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
+            mock_discord
+                .expect_get_members_of_current_channel()
+                .times(1)
+                .returning(move || Ok(members.clone()));
+            mock_discord
+                .expect_send_reply()
+                .with(eq(
+                    "Here's who 'Alcie' could be:\n\t'Wonderland' is Alice Smith",
+                ))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.whois(" Alcie ").await;
+
+            // Assert
+            assert!(result.is_ok(), "whois should succeed");
+        }
+
+        #[tokio::test]
+        async fn whois_lists_members_outside_the_channel_without_nicknames() {
+            // Arrange
+            let mock_repo = names_repository();
+            let mut mock_discord = MockDiscordConnector::new();
+            let config = create_test_config();
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
+            mock_discord
+                .expect_get_members_of_current_channel()
+                .returning(|| Ok(vec![]));
+            mock_discord
+                .expect_send_reply()
+                .with(eq(
+                    "Here's who 'ali' could be:\n\tAlice Smith isn't in this channel\n\tAlicia Keys isn't in this channel",
+                ))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.whois("ali").await;
+
+            // Assert
+            assert!(result.is_ok(), "whois should succeed");
+        }
+
+        #[tokio::test]
+        async fn whois_says_when_nobody_matches() {
+            // Arrange
+            let mock_repo = names_repository();
+            let mut mock_discord = MockDiscordConnector::new();
+            let config = create_test_config();
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
+            mock_discord
+                .expect_send_reply()
+                .with(eq("Nobody goes by anything like 'Zed', ya dingus"))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.whois("Zed").await;
+
+            // Assert
+            assert!(result.is_ok(), "whois should succeed");
+        }
+
+        #[tokio::test]
+        async fn whois_asks_for_a_name_when_given_none() {
+            // Arrange
+            let mock_repo = MockNamesRepository::new();
+            let mut mock_discord = MockDiscordConnector::new();
+            let config = create_test_config();
+            mock_discord
+                .expect_send_reply()
+                .with(eq("Who is who? Tell me part of their real name"))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.whois("  ").await;
+
+            // Assert
+            assert!(result.is_ok(), "whois should succeed");
+        }
+    }
+
+    mod opt_out_tests {
+        use super::{GUILD_ID, create_test_config};
+        use crate::nicknamer::connectors::discord::MockDiscordConnector;
+        use crate::nicknamer::connectors::discord::ServerMemberBuilder;
+        use crate::nicknamer::history::MemoryHistoryRepository;
+        use crate::nicknamer::names::{MockNamesRepository, Names};
+        use crate::nicknamer::privacy::{self, MockOptOutRepository};
+        use crate::nicknamer::user::Error;
+        use crate::nicknamer::{Nicknamer, NicknamerImpl};
+        use mockall::predicate::*;
+        use std::collections::HashSet;
+
+        const OPTED_OUT_ID: u64 = 2;
+
+        fn names_repository() -> MockNamesRepository {
+            let mut mock_repo = MockNamesRepository::new();
+            mock_repo
+                .expect_load_real_names()
+                .with(eq(GUILD_ID))
+                .returning(|_| {
+                    Ok([(1, "Alice Smith"), (OPTED_OUT_ID, "Alicia Keys")]
+                        .into_iter()
+                        .map(|(discord_id, name)| (discord_id, name.to_string()))
+                        .collect::<Names>())
+                });
+            mock_repo
+        }
+
+        fn opt_outs() -> MockOptOutRepository {
+            let mut mock_opt_outs = MockOptOutRepository::new();
+            mock_opt_outs
+                .expect_load_opted_out()
+                .returning(|| Ok(HashSet::from([OPTED_OUT_ID, 3])));
+            mock_opt_outs
+        }
+
+        fn discord_connector() -> MockDiscordConnector {
+            let members = vec![
+                ServerMemberBuilder::new()
+                    .id(1)
+                    .user_name("alice99")
+                    .nick_name("Wonderland")
+                    .build(),
+                ServerMemberBuilder::new()
+                    .id(OPTED_OUT_ID)
+                    .user_name("alicia")
+                    .nick_name("Fallin'")
+                    .build(),
+                // Opted out and unrecognized, so nobody gets asked to add their name
+                ServerMemberBuilder::new().id(3).user_name("carol").build(),
+            ];
+            let mut mock_discord = MockDiscordConnector::new();
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
+            mock_discord
+                .expect_get_members_of_current_channel()
+                .returning(move || Ok(members.clone()));
+            mock_discord
+        }
+
+        #[tokio::test]
+        async fn reveal_keeps_the_real_names_of_opted_out_members_private() {
+            // Arrange
+            let mock_repo = names_repository();
+            let mut mock_discord = discord_connector();
+            let mock_opt_outs = opt_outs();
+            let config = create_test_config();
+            let member = ServerMemberBuilder::new()
+                .id(OPTED_OUT_ID)
+                .user_name("alicia")
+                .nick_name("Fallin'")
+                .build();
+            mock_discord
+                .expect_send_reply()
+                .with(eq("'Fallin'' keeps their real name private"))
+                .times(1)
+                .returning(|_| Ok(()));
+            let history = MemoryHistoryRepository::new();
+            let sut =
+                NicknamerImpl::new(&mock_repo, &mock_discord, &mock_opt_outs, &history, &config);
+
+            // Act
+            let result = sut.reveal(&member).await;
+
+            // Assert
+            assert!(result.is_ok(), "reveal should succeed");
+        }
+
+        #[tokio::test]
+        async fn reveal_all_leaves_out_opted_out_members() {
+            // Arrange
+            let mock_repo = names_repository();
+            let mut mock_discord = discord_connector();
+            let mock_opt_outs = opt_outs();
+            let config = create_test_config();
+            mock_discord
+                .expect_send_reply()
+                .with(eq(
+                    "Here are people's real names, ya dingus:\n\t'Wonderland' is Alice Smith",
+                ))
+                .times(1)
+                .returning(|_| Ok(()));
+            let history = MemoryHistoryRepository::new();
+            let sut =
+                NicknamerImpl::new(&mock_repo, &mock_discord, &mock_opt_outs, &history, &config);
+
+            // Act
+            let result = sut.reveal_all().await;
+
+            // Assert
+            assert!(result.is_ok(), "reveal_all should succeed");
+        }
+
+        #[tokio::test]
+        async fn whois_never_finds_opted_out_members() {
+            // Arrange
+            let mock_repo = names_repository();
+            let mut mock_discord = discord_connector();
+            let mock_opt_outs = opt_outs();
+            let config = create_test_config();
+            mock_discord
+                .expect_send_reply()
+                .with(eq(
+                    "Here's who 'ali' could be:\n\t'Wonderland' is Alice Smith",
+                ))
+                .times(1)
+                .returning(|_| Ok(()));
+            let history = MemoryHistoryRepository::new();
+            let sut =
+                NicknamerImpl::new(&mock_repo, &mock_discord, &mock_opt_outs, &history, &config);
+
+            // Act
+            let result = sut.whois("ali").await;
+
+            // Assert
+            assert!(result.is_ok(), "whois should succeed");
+        }
+
+        #[tokio::test]
+        async fn reveal_fails_when_opt_outs_cannot_be_loaded() {
+            // Arrange
+            let mock_repo = names_repository();
+            let mock_discord = discord_connector();
+            let mut mock_opt_outs = MockOptOutRepository::new();
+            mock_opt_outs
+                .expect_load_opted_out()
+                .returning(|| Err(privacy::Error::CannotLoadOptOuts));
+            let config = create_test_config();
+            let member = ServerMemberBuilder::new()
+                .id(1)
+                .user_name("alice99")
+                .build();
+            let history = MemoryHistoryRepository::new();
+            let sut =
+                NicknamerImpl::new(&mock_repo, &mock_discord, &mock_opt_outs, &history, &config);
+
+            // Act
+            let result = sut.reveal(&member).await;
+
+            // Assert
+            assert!(matches!(result, Err(Error::OptOutError(_))));
+        }
+    }
+
+    mod nick_all_tests {
+        use super::{GUILD_ID, NO_OPT_OUTS, create_nicknamer, create_test_config};
+        use crate::nicknamer::connectors::discord::{
+            self, DryRunConnector, MockDiscordConnector, ServerMemberBuilder,
+        };
+        use crate::nicknamer::history::MemoryHistoryRepository;
+        use crate::nicknamer::names::{MockNamesRepository, Names};
+        use crate::nicknamer::{Nicknamer, NicknamerImpl};
+        use mockall::predicate::*;
+        use std::time::Duration;
+
+        const GUILD_OWNER_ID: u64 = 987654321;
+        const EMBED_ID: u64 = 555;
+
+        fn names_repository() -> MockNamesRepository {
+            let mut mock_repo = MockNamesRepository::new();
+            mock_repo
+                .expect_load_real_names()
+                .with(eq(GUILD_ID))
+                .returning(|_| {
+                    Ok([(1, "Alice"), (2, "Bob"), (3, "Carol")]
+                        .into_iter()
+                        .map(|(discord_id, name)| (discord_id, name.to_string()))
+                        .collect::<Names>())
+                });
+            mock_repo
+        }
+
+        fn discord_connector(members: Vec<discord::ServerMember>) -> MockDiscordConnector {
+            let mut mock_discord = MockDiscordConnector::new();
+            mock_discord
+                .expect_get_members_of_current_channel()
+                .times(1)
+                .returning(move || Ok(members.clone()));
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
+            mock_discord
+                .expect_get_guild_owner_id()
+                .returning(|| Ok(GUILD_OWNER_ID));
+            mock_discord
+        }
+
+        fn members_with_wrong_nicknames() -> Vec<discord::ServerMember> {
+            vec![
+                ServerMemberBuilder::new()
+                    .id(1)
+                    .user_name("alice99")
+                    .nick_name("Wonderland")
+                    .build(),
+                ServerMemberBuilder::new().id(2).user_name("bobby").build(),
+                ServerMemberBuilder::new()
+                    .id(3)
+                    .user_name("carol")
+                    .nick_name("Carol")
+                    .build(),
+            ]
+        }
+
+        fn expect_confirmation(mock_discord: &mut MockDiscordConnector, confirmed: bool) {
+            mock_discord
+                .expect_send_embed()
+                .with(
+                    eq("Change these nicknames to real names?"),
+                    eq("<@1>: 'Wonderland' → 'Alice'\n<@2>: 'bobby' → 'Bob'\n\nReact with ✅ within 60 seconds to make the changes"),
+                )
+                .times(1)
+                .returning(|_, _| Ok(EMBED_ID));
+            mock_discord
+                .expect_add_reaction()
+                .with(eq(EMBED_ID), eq("✅"))
+                .times(1)
+                .returning(|_, _| Ok(()));
+            mock_discord
+                .expect_await_author_reaction()
+                .with(eq(EMBED_ID), eq("✅"), eq(Duration::from_secs(60)))
+                .times(1)
+                .returning(move |_, _, _| Ok(confirmed));
+        }
+
+        #[tokio::test]
+        async fn nick_all_changes_nicknames_once_confirmed() {
+            // Arrange
+            let mock_repo = names_repository();
+            let mut mock_discord = discord_connector(members_with_wrong_nicknames());
+            let config = create_test_config();
+            expect_confirmation(&mut mock_discord, true);
+            mock_discord
+                .expect_change_member_nick_name()
+                .with(eq(1), eq("Alice"))
+                .times(1)
+                .returning(|_, _| Ok(()));
+            mock_discord
+                .expect_change_member_nick_name()
+                .with(eq(2), eq("Bob"))
+                .times(1)
+                .returning(|_, _| Err(discord::Error::NotEnoughPermissions));
+            mock_discord
+                .expect_send_reply()
+                .with(eq(
+                    "Changed 1 of 2 nicknames to real names, these couldn't be changed:\n\tbobby: Not enough permissions",
+                ))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.nick_all().await;
+
+            // Assert
+            assert!(result.is_ok(), "nick_all should succeed");
+        }
+
+        #[tokio::test]
+        async fn nick_all_changes_nothing_unless_confirmed() {
+            // Arrange
+            let mock_repo = names_repository();
+            let mut mock_discord = discord_connector(members_with_wrong_nicknames());
+            let config = create_test_config();
+            expect_confirmation(&mut mock_discord, false);
+            mock_discord.expect_change_member_nick_name().never();
+            mock_discord
+                .expect_send_reply()
+                .with(eq("Not confirmed in time, so nicknames stay as they are"))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.nick_all().await;
+
+            // Assert
+            assert!(result.is_ok(), "nick_all should succeed");
+        }
+
+        #[tokio::test]
+        async fn nick_all_asks_nothing_when_everyone_goes_by_their_real_name() {
+            // Arrange
+            let mock_repo = names_repository();
+            let members = vec![
+                ServerMemberBuilder::new()
+                    .id(3)
+                    .user_name("carol")
+                    .nick_name("Carol")
+                    .build(),
+            ];
+            let mut mock_discord = discord_connector(members);
+            let config = create_test_config();
+            mock_discord
+                .expect_send_reply()
+                .with(eq("Everyone here already goes by their real name"))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.nick_all().await;
+
+            // Assert
+            assert!(result.is_ok(), "nick_all should succeed");
+        }
+
+        #[tokio::test]
+        async fn nick_all_previews_change_nothing() {
+            // Arrange
+            let mock_repo = names_repository();
+            let mut mock_discord = discord_connector(members_with_wrong_nicknames());
+            let config = create_test_config();
+            mock_discord
+                .expect_send_embed()
+                .with(
+                    eq("[dry run] Change these nicknames to real names?"),
+                    always(),
+                )
+                .times(1)
+                .returning(|_, _| Ok(EMBED_ID));
+            mock_discord.expect_add_reaction().never();
+            mock_discord.expect_await_author_reaction().never();
+            mock_discord.expect_change_member_nick_name().never();
+            mock_discord
+                .expect_send_reply()
+                .with(eq("[dry run] Changed 2 of 2 nicknames to real names"))
+                .times(1)
+                .returning(|_| Ok(()));
+            let connector = DryRunConnector::new(mock_discord, true);
+            let history = MemoryHistoryRepository::new();
+            let sut = NicknamerImpl::new(&mock_repo, &connector, &NO_OPT_OUTS, &history, &config);
+
+            // Act
+            let result = sut.nick_all().await;
+
+            // Assert
+            assert!(result.is_ok(), "nick_all should succeed");
+            assert_eq!(
+                connector.summary().unwrap(),
+                "Nothing was changed, but I would have:\n\t\
+                 change the nickname of <@1> to 'Alice'\n\tchange the nickname of <@2> to 'Bob'"
+            );
+        }
+    }
+
+    mod import_names_tests {
+        use super::{GUILD_ID, MockRole, create_nicknamer, create_test_config};
+        use crate::nicknamer::Nicknamer;
+        use crate::nicknamer::connectors::discord::{self, MockDiscordConnector};
+        use crate::nicknamer::names::{MockNamesRepository, Names};
+        use mockall::predicate::*;
+        use std::time::Duration;
+
+        const EMBED_ID: u64 = 555;
+
+        fn attachment(filename: &str) -> discord::Attachment {
+            discord::Attachment {
+                id: 7,
+                filename: filename.to_string(),
+                size: 100,
+            }
+        }
+
+        fn names_repository() -> MockNamesRepository {
+            let mut mock_repo = MockNamesRepository::new();
+            mock_repo
+                .expect_load_real_names()
+                .with(eq(GUILD_ID))
+                .returning(|_| {
+                    Ok([(1, "Alice"), (2, "Bob")]
+                        .into_iter()
+                        .map(|(discord_id, name)| (discord_id, name.to_string()))
+                        .collect::<Names>())
+                });
+            mock_repo
+        }
+
+        /// Lets the invoker through the name editor check and hands out `contents` as the
+        /// attached file.
+        fn discord_connector(contents: &'static str) -> MockDiscordConnector {
+            let mut mock_discord = MockDiscordConnector::new();
+            mock_discord
+                .expect_get_role_by_name()
+                .returning(|_| Ok(Box::new(MockRole::new())));
+            mock_discord
+                .expect_author_has_role()
+                .returning(|_| Ok(true));
+            mock_discord
+                .expect_download_attachment()
+                .returning(move |_| Ok(contents.as_bytes().to_vec()));
+            mock_discord
+                .expect_get_guild_id()
+                .returning(|| Ok(GUILD_ID));
+            mock_discord
+        }
+
+        fn expect_confirmation(mock_discord: &mut MockDiscordConnector, confirmed: bool) {
+            mock_discord
+                .expect_send_embed()
+                .with(
+                    eq("Import these real names?"),
+                    eq("Real names to add (1):\n<@3>: Charlie\n\n\
+                        Real names to keep, as the file has different ones (1):\n<@2>: Bob (not Robert)\n\n\
+                        Already known: 1\n\n\
+                        React with ✅ within 60 seconds to import them"),
+                )
+                .times(1)
+                .returning(|_, _| Ok(EMBED_ID));
+            mock_discord
+                .expect_add_reaction()
+                .with(eq(EMBED_ID), eq("✅"))
+                .times(1)
+                .returning(|_, _| Ok(()));
+            mock_discord
+                .expect_await_author_reaction()
+                .with(eq(EMBED_ID), eq("✅"), eq(Duration::from_secs(60)))
+                .times(1)
+                .returning(move |_, _, _| Ok(confirmed));
+        }
+
+        const CSV: &str = "discord_id,name\n1,Alice\n2,Robert\n3,Charlie\n";
+
+        #[tokio::test]
+        async fn import_names_adds_new_names_once_confirmed() {
+            // Arrange
+            let mut mock_repo = names_repository();
+            let mut mock_discord = discord_connector(CSV);
+            let config = create_test_config();
+            expect_confirmation(&mut mock_discord, true);
+            mock_repo
+                .expect_save_name()
+                .with(eq(GUILD_ID), eq(3), eq("Charlie"))
+                .times(1)
+                .returning(|_, _, _| Ok(()));
+            mock_discord
+                .expect_send_reply()
+                .with(eq("Imported 1 of 1 real names"))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.import_names(&attachment("names.csv")).await;
+
+            // Assert
+            assert!(result.is_ok(), "import_names should succeed");
+        }
+
+        #[tokio::test]
+        async fn import_names_changes_nothing_unless_confirmed() {
+            // Arrange
+            let mut mock_repo = names_repository();
+            let mut mock_discord = discord_connector(CSV);
+            let config = create_test_config();
+            expect_confirmation(&mut mock_discord, false);
+            mock_repo.expect_save_name().never();
+            mock_discord
+                .expect_send_reply()
+                .with(eq("Not confirmed in time, so no names were imported"))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.import_names(&attachment("names.csv")).await;
+
+            // Assert
+            assert!(result.is_ok(), "import_names should succeed");
+        }
+
+        #[tokio::test]
+        async fn import_names_refuses_other_files_without_downloading_them() {
+            // Arrange
+            let mock_repo = MockNamesRepository::new();
+            let mut mock_discord = MockDiscordConnector::new();
+            let config = create_test_config();
+            mock_discord
+                .expect_get_role_by_name()
+                .returning(|_| Ok(Box::new(MockRole::new())));
+            mock_discord
+                .expect_author_has_role()
+                .returning(|_| Ok(true));
+            mock_discord.expect_download_attachment().never();
+            mock_discord
+                .expect_send_reply()
+                .with(eq(
+                    "I can't import names.txt: I only import .yml, .yaml and .csv files",
+                ))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.import_names(&attachment("names.txt")).await;
+
+            // Assert
+            assert!(result.is_ok(), "import_names should succeed");
+        }
+
+        #[tokio::test]
+        async fn import_names_reports_invalid_entries() {
+            // Arrange
+            let mock_repo = MockNamesRepository::new();
+            let mut mock_discord = discord_connector("names:\n  1: Alice\n  3: ''\n");
+            let config = create_test_config();
+            mock_discord.expect_send_embed().never();
+            mock_discord
+                .expect_send_reply()
+                .with(eq(
+                    "I can't import names.yml: Invalid names: 3: name must not be empty",
+                ))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.import_names(&attachment("names.yml")).await;
+
+            // Assert
+            assert!(result.is_ok(), "import_names should succeed");
+        }
+
+        #[tokio::test]
+        async fn import_names_asks_nothing_when_every_name_is_known() {
+            // Arrange
+            let mock_repo = names_repository();
+            let mut mock_discord = discord_connector("1: Alice\n2: Robert\n");
+            let config = create_test_config();
+            mock_discord.expect_send_embed().never();
+            mock_discord
+                .expect_send_reply()
+                .with(eq(
+                    "Everyone in names.yml already has a real name, so there's nothing to import",
+                ))
+                .times(1)
+                .returning(|_| Ok(()));
+            let sut = create_nicknamer(&mock_repo, &mock_discord, &config);
+
+            // Act
+            let result = sut.import_names(&attachment("names.yml")).await;
+
+            // Assert
+            assert!(result.is_ok(), "import_names should succeed");
+        }
+    }
 }
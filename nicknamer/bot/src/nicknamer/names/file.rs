@@ -0,0 +1,141 @@
+//! Repository that keeps names in a YAML file on disk, so that they can be changed from Discord.
+
+use crate::nicknamer::file_store::{self, FileFormat, FileStore};
+use crate::nicknamer::names::Error::{CannotLoadNames, CannotSaveNames};
+use crate::nicknamer::names::{Error, GuildNames, Names, NamesRepository};
+use async_trait::async_trait;
+use std::path::PathBuf;
+
+/// Repository implementation that reads and writes a `real_names.yml`-style file.
+///
+/// The file is read on every load, so edits made to it by hand are picked up too. It doesn't
+/// need to exist until the first name is saved. Files from before names were kept per guild are
+/// rewritten with a `guilds` section on the first change, keeping their names for every guild.
+pub struct FileNamesRepository {
+    store: FileStore<NamesYaml>,
+}
+
+impl FileNamesRepository {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self {
+            store: FileStore::new(path, NamesYaml),
+        }
+    }
+}
+
+/// The YAML the names are written down in, the same as the embedded names'.
+struct NamesYaml;
+
+impl FileFormat for NamesYaml {
+    type Value = GuildNames;
+
+    fn parse(&self, contents: &str) -> anyhow::Result<GuildNames> {
+        Ok(GuildNames::from_yaml(contents)?)
+    }
+
+    fn serialize(&self, names: &GuildNames) -> anyhow::Result<String> {
+        Ok(names.to_yaml()?)
+    }
+}
+
+impl From<file_store::Error> for Error {
+    fn from(err: file_store::Error) -> Self {
+        match err {
+            file_store::Error::CannotLoad => CannotLoadNames,
+            file_store::Error::CannotSave => CannotSaveNames,
+        }
+    }
+}
+
+#[async_trait]
+impl NamesRepository for FileNamesRepository {
+    async fn load_real_names(&self, guild_id: u64) -> Result<Names, Error> {
+        Ok(self.store.load().await?.in_guild(guild_id))
+    }
+
+    async fn save_name(&self, guild_id: u64, discord_id: u64, name: &str) -> Result<(), Error> {
+        self.store
+            .update(|names| names.insert(guild_id, discord_id, name.to_string()))
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_name(&self, guild_id: u64, discord_id: u64) -> Result<Option<String>, Error> {
+        Ok(self
+            .store
+            .update(|names| names.remove(guild_id, discord_id))
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUILD_ID: u64 = 42;
+
+    #[tokio::test]
+    async fn saves_and_deletes_names_in_the_file() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("real_names.yml");
+        let sut = FileNamesRepository::new(path.clone());
+
+        // Act
+        let before = sut.load_real_names(GUILD_ID).await.unwrap();
+        sut.save_name(GUILD_ID, 1, "Alice").await.unwrap();
+        sut.save_name(GUILD_ID, 2, "Bob").await.unwrap();
+        let removed = sut.delete_name(GUILD_ID, 1).await.unwrap();
+        let removed_again = sut.delete_name(GUILD_ID, 1).await.unwrap();
+
+        // Assert
+        assert!(before.is_empty());
+        assert_eq!(removed.as_deref(), Some("Alice"));
+        assert_eq!(removed_again, None);
+        let reread = FileNamesRepository::new(path)
+            .load_real_names(GUILD_ID)
+            .await
+            .unwrap();
+        assert_eq!(reread.len(), 1);
+        assert_eq!(reread.get(2), Some("Bob"));
+    }
+
+    #[tokio::test]
+    async fn migrates_files_from_before_guilds() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("real_names.yml");
+        std::fs::write(&path, "names:\n  1: Alice\n").unwrap();
+        let sut = FileNamesRepository::new(path.clone());
+
+        // Act
+        sut.save_name(GUILD_ID, 2, "Bob").await.unwrap();
+        let here = sut.load_real_names(GUILD_ID).await.unwrap();
+        let elsewhere = sut.load_real_names(GUILD_ID + 1).await.unwrap();
+
+        // Assert
+        assert_eq!(here.get(1), Some("Alice"));
+        assert_eq!(here.get(2), Some("Bob"));
+        assert_eq!(elsewhere.get(1), Some("Alice"));
+        assert_eq!(elsewhere.get(2), None);
+        assert_eq!(
+            std::fs::read_to_string(path).unwrap(),
+            "names:\n  1: Alice\nguilds:\n  42:\n    2: Bob\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_to_load_malformed_files() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("real_names.yml");
+        std::fs::write(&path, "names: [not, a, mapping]").unwrap();
+        let sut = FileNamesRepository::new(path);
+
+        // Act
+        let result = sut.save_name(GUILD_ID, 1, "Alice").await;
+
+        // Assert
+        assert!(matches!(result, Err(CannotLoadNames)));
+    }
+}
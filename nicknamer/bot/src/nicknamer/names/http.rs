@@ -0,0 +1,268 @@
+//! Repository that fetches names from the nicknamer server's JSON API.
+//!
+//! Names edited in the server's web UI are picked up on the next command, without rebuilding
+//! the bot. The bot logs in with the server's admin credentials and keeps the token until the
+//! server stops accepting it. The server keeps names per server ID, which is the ID of the
+//! guild they're used in.
+
+use crate::nicknamer::names::Error::CannotLoadNames;
+use crate::nicknamer::names::{Error, Names, NamesRepository};
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// The largest page of names the server hands out.
+const PER_PAGE: u64 = 100;
+
+#[derive(Deserialize)]
+struct LoginResponse {
+    token: String,
+}
+
+#[derive(Deserialize)]
+struct NameJson {
+    discord_id: u64,
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct NamesPage {
+    items: Vec<NameJson>,
+    page: u64,
+    total_pages: u64,
+}
+
+/// Repository implementation that loads names from the nicknamer server.
+pub struct HttpNamesRepository {
+    client: Client,
+    /// Base URL of the server, without a trailing slash
+    base_url: String,
+    username: String,
+    password: String,
+    /// Token from the last login, if it's still accepted
+    token: Mutex<Option<String>>,
+}
+
+impl HttpNamesRepository {
+    /// Creates a repository for the server at `base_url`, logging in as `username`.
+    pub(crate) fn new(base_url: &str, username: String, password: String) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            username,
+            password,
+            token: Mutex::new(None),
+        }
+    }
+
+    async fn login(&self) -> Result<String, Error> {
+        let body = HashMap::from([
+            ("username", self.username.as_str()),
+            ("password", self.password.as_str()),
+        ]);
+        let response = self
+            .client
+            .post(format!("{}/api/v1/login", self.base_url))
+            .json(&body)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| {
+                tracing::error!("Failed to log into the nicknamer server: {err}");
+                CannotLoadNames
+            })?;
+        let login: LoginResponse = response.json().await.map_err(|err| {
+            tracing::error!("Failed to read the nicknamer server's login response: {err}");
+            CannotLoadNames
+        })?;
+        Ok(login.token)
+    }
+
+    /// The token from the last login, logging in if there's none.
+    async fn token(&self) -> Result<String, Error> {
+        let cached = self.token.lock().expect("token lock poisoned").clone();
+        if let Some(token) = cached {
+            return Ok(token);
+        }
+        let token = self.login().await?;
+        *self.token.lock().expect("token lock poisoned") = Some(token.clone());
+        Ok(token)
+    }
+
+    /// Fetches a page of a guild's names, or `None` if the server doesn't accept `token`.
+    async fn fetch_page(
+        &self,
+        token: &str,
+        guild_id: u64,
+        page: u64,
+    ) -> Result<Option<NamesPage>, Error> {
+        let query = [
+            ("page", page.to_string()),
+            ("per_page", PER_PAGE.to_string()),
+            ("server_id", guild_id.to_string()),
+        ];
+        let response = self
+            .client
+            .get(format!("{}/api/v1/names", self.base_url))
+            .bearer_auth(token)
+            .query(&query)
+            .send()
+            .await
+            .map_err(|err| {
+                tracing::error!("Failed to reach the nicknamer server: {err}");
+                CannotLoadNames
+            })?;
+        if response.status() == StatusCode::UNAUTHORIZED {
+            return Ok(None);
+        }
+        let response = response.error_for_status().map_err(|err| {
+            tracing::error!("The nicknamer server failed to list names: {err}");
+            CannotLoadNames
+        })?;
+        let page = response.json().await.map_err(|err| {
+            tracing::error!("Failed to read names from the nicknamer server: {err}");
+            CannotLoadNames
+        })?;
+        Ok(Some(page))
+    }
+
+    /// Fetches a page of a guild's names, logging in again once if the token has expired.
+    async fn fetch_page_logged_in(&self, guild_id: u64, page: u64) -> Result<NamesPage, Error> {
+        let token = self.token().await?;
+        if let Some(names) = self.fetch_page(&token, guild_id, page).await? {
+            return Ok(names);
+        }
+        *self.token.lock().expect("token lock poisoned") = None;
+        let token = self.token().await?;
+        self.fetch_page(&token, guild_id, page)
+            .await?
+            .ok_or_else(|| {
+                tracing::error!("The nicknamer server rejected a fresh login token");
+                CannotLoadNames
+            })
+    }
+}
+
+#[async_trait]
+impl NamesRepository for HttpNamesRepository {
+    /// Loads the guild's real names from the server, a page at a time.
+    async fn load_real_names(&self, guild_id: u64) -> Result<Names, Error> {
+        let mut names = HashMap::new();
+        let mut page = 1;
+        loop {
+            let fetched = self.fetch_page_logged_in(guild_id, page).await?;
+            names.extend(
+                fetched
+                    .items
+                    .into_iter()
+                    .map(|item| (item.discord_id, item.name)),
+            );
+            if fetched.page >= fetched.total_pages {
+                break;
+            }
+            page += 1;
+        }
+        tracing::debug!("Loaded {} names from the nicknamer server", names.len());
+        Ok(Names { names })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::Query;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Serves three names of guild 42 over two pages of two, and counts logins. Tokens are
+    /// accepted from the second login on, as if the first one had expired.
+    async fn fake_server(logins: Arc<AtomicUsize>) -> String {
+        let app = Router::new()
+            .route(
+                "/api/v1/login",
+                post(move || {
+                    let login = logins.fetch_add(1, Ordering::SeqCst) + 1;
+                    async move { Json(serde_json::json!({ "token": format!("token-{login}") })) }
+                }),
+            )
+            .route(
+                "/api/v1/names",
+                get(
+                    |headers: HeaderMap, Query(query): Query<HashMap<String, String>>| async move {
+                        let authorization = headers.get("authorization").unwrap();
+                        if authorization == "Bearer token-1" {
+                            return Err(StatusCode::UNAUTHORIZED);
+                        }
+                        let server_id = &query["server_id"];
+                        if server_id != "42" {
+                            return Ok(Json(serde_json::json!({
+                                "items": [], "page": 1, "per_page": 2, "total": 0, "total_pages": 1,
+                            })));
+                        }
+                        let page = &query["page"];
+                        let items = match page.as_str() {
+                            "1" => serde_json::json!([
+                                { "id": 1, "discord_id": 1, "name": "Alice", "server_id": server_id },
+                                { "id": 2, "discord_id": 2, "name": "Bob", "server_id": server_id },
+                            ]),
+                            _ => serde_json::json!([
+                                { "id": 3, "discord_id": 3, "name": "Carol", "server_id": server_id },
+                            ]),
+                        };
+                        Ok(Json(serde_json::json!({
+                            "items": items,
+                            "page": page.parse::<u64>().unwrap(),
+                            "per_page": 2,
+                            "total": 3,
+                            "total_pages": 2,
+                        })))
+                    },
+                ),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}/")
+    }
+
+    #[tokio::test]
+    async fn loads_every_page_logging_in_again_when_the_token_expires() {
+        // Arrange
+        let logins = Arc::new(AtomicUsize::new(0));
+        let url = fake_server(logins.clone()).await;
+        let sut = HttpNamesRepository::new(&url, "admin".to_string(), "secret".to_string());
+
+        // Act
+        let names = sut.load_real_names(42).await.unwrap();
+        let reloaded = sut.load_real_names(42).await.unwrap();
+        let other_guild = sut.load_real_names(43).await.unwrap();
+
+        // Assert
+        assert_eq!(names.len(), 3);
+        assert_eq!(names.get(1), Some("Alice"));
+        assert_eq!(names.get(3), Some("Carol"));
+        assert_eq!(reloaded, names);
+        assert!(other_guild.is_empty());
+        assert_eq!(logins.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn fails_to_load_names_when_the_server_is_unreachable() {
+        // Arrange
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let sut = HttpNamesRepository::new(&url, "admin".to_string(), "secret".to_string());
+
+        // Act
+        let result = sut.load_real_names(42).await;
+
+        // Assert
+        assert!(matches!(result, Err(CannotLoadNames)));
+    }
+}
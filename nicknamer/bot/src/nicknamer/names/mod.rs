@@ -1,17 +1,29 @@
 //! Names module for handling user real name data.
 //!
 //! This module provides functionality for loading, storing, and accessing mappings
-//! between Discord user IDs and their real names. It includes:
-//! - Data structures for representing collections of user real names
-//! - A repository trait for loading name data
+//! between Discord user IDs and their real names in each guild. It includes:
+//! - A repository trait for loading and changing name data
 //! - An implementation that loads names from an embedded YAML file
+//! - An implementation that loads names from the nicknamer server's API
+//! - An implementation that keeps names in a YAML file on disk
+//! - A fuzzy search over the names, for finding members by a part of their real name
 //!
-//! The module supports deserializing name data from YAML format and provides
-//! error handling for failed loading operations.
+//! The names collection and its YAML format come from the `names-format` crate,
+//! so the file stays compatible with the server's bulk import. Files from before
+//! names were kept per guild still load, with their names used in every guild.
 
-use crate::{CONFIG_DIR, nicknamer::names::Error::CannotLoadNames};
+mod file;
+mod http;
+mod search;
+
+use crate::nicknamer::config::{ConfigFiles, NamesConfig};
+use crate::nicknamer::names::Error::CannotLoadNames;
+use anyhow::Context;
 use async_trait::async_trait;
-use serde::{Deserialize, Serialize};
+pub use file::FileNamesRepository;
+pub use http::HttpNamesRepository;
+pub use names_format::{GuildNames, Names};
+pub use search::search;
 use thiserror::Error;
 
 /// Errors that can occur during name operations.
@@ -23,61 +35,67 @@ pub enum Error {
     /// Indicates a failure to load the names data, typically from YAML parsing
     #[error("Failed to load names")]
     CannotLoadNames,
-}
-
-/// Collection of user real names indexed by Discord user IDs.
-///
-/// This structure stores a mapping of Discord user IDs to their corresponding
-/// real names, allowing for quick lookups. It is designed to be serialized
-/// to and deserialized from YAML format.
-#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
-pub struct Names {
-    /// Mapping of Discord user IDs to real names
-    pub(crate) names: std::collections::HashMap<u64, String>,
+    /// Indicates a failure to write the changed names back
+    #[error("Failed to save names")]
+    CannotSaveNames,
+    /// The repository can't be changed, e.g. because its names are built into the bot
+    #[error("Names can't be changed here")]
+    ReadOnly,
 }
 
 /// Trait defining operations for accessing user real name data.
 ///
 /// Implementations of this trait provide mechanisms for loading
-/// real name data from various sources.
+/// real name data from various sources. Names are kept per guild, as
+/// a member can be in several of the bot's guilds. Sources that can't be changed
+/// keep the default `save_name` and `delete_name`, which fail with `ReadOnly`.
 #[cfg_attr(test, mockall::automock)]
 #[async_trait]
 pub trait NamesRepository {
-    /// Loads the real names collection from the repository.
+    /// Loads the real names of the members of a guild from the repository.
     ///
     /// # Returns
     ///
     /// * `Result<Names, Error>` - The loaded names on success, or an error if loading fails
-    async fn load_real_names(&self) -> Result<Names, Error>;
+    async fn load_real_names(&self, guild_id: u64) -> Result<Names, Error>;
+
+    /// Sets the real name of a member of a guild, replacing any previous one.
+    async fn save_name(&self, _guild_id: u64, _discord_id: u64, _name: &str) -> Result<(), Error> {
+        Err(Error::ReadOnly)
+    }
+
+    /// Forgets the real name of a member of a guild.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Option<String>, Error>` - The name that was removed, if the user had one
+    async fn delete_name(&self, _guild_id: u64, _discord_id: u64) -> Result<Option<String>, Error> {
+        Err(Error::ReadOnly)
+    }
 }
 
 /// Repository implementation that loads names from an embedded YAML file.
 ///
 /// This implementation includes the names data directly in the binary,
-/// allowing for deployment without external data files.
+/// allowing for deployment without external data files. With `NICKNAMER_CONFIG_DIR` set, the
+/// file is read from there instead, once per (re)load of the config.
 pub struct EmbeddedNamesRepository {
     /// Contents of the embedded YAML file containing real names
-    embedded_names: &'static str,
+    embedded_names: String,
 }
 
 impl EmbeddedNamesRepository {
     /// Creates a new instance of the embedded names repository.
     ///
-    /// This constructor includes the contents of the real_names.yml file
-    /// directly in the binary at compile time.
+    /// This constructor reads the real_names.yml file next to config.toml, which
+    /// is included in the binary at compile time unless the config is read from a directory.
     ///
     /// # Returns
     ///
     /// A new EmbeddedNamesRepository instance
-    pub(crate) fn new() -> anyhow::Result<Self> {
-        let real_names_file = CONFIG_DIR
-            .get_file("real_names.yml")
-            .expect("Failed to find real_names.yml in the config directory");
-        let file_contents = real_names_file
-            .contents_utf8()
-            .expect("Failed to read real_names.yml as UTF-8");
+    pub(crate) fn new(files: &ConfigFiles) -> anyhow::Result<Self> {
         Ok(Self {
-            embedded_names: file_contents,
+            embedded_names: files.read("real_names.yml")?,
         })
     }
 }
@@ -86,15 +104,72 @@ impl EmbeddedNamesRepository {
 impl NamesRepository for EmbeddedNamesRepository {
     /// Loads real names from the embedded YAML data.
     ///
-    /// Deserializes the embedded YAML string into a Names collection.
-    /// Logs the number of names loaded for debugging purposes.
+    /// Deserializes the embedded YAML string into a GuildNames collection
+    /// and picks out the names that apply in the guild.
     ///
     /// # Returns
     ///
     /// * `Result<Names, Error>` - The loaded Names on success, or CannotLoadNames error on failure
-    async fn load_real_names(&self) -> Result<Names, Error> {
-        let names: Names = serde_yml::from_str(self.embedded_names).map_err(|_| CannotLoadNames)?;
-        Ok(names)
+    async fn load_real_names(&self, guild_id: u64) -> Result<Names, Error> {
+        let names = GuildNames::from_yaml(&self.embedded_names).map_err(|err| {
+            tracing::error!("Failed to parse embedded names: {err}");
+            CannotLoadNames
+        })?;
+        Ok(names.in_guild(guild_id))
+    }
+}
+
+/// The repository picked in the config.
+pub enum ConfiguredNamesRepository {
+    Embedded(EmbeddedNamesRepository),
+    Http(HttpNamesRepository),
+    File(FileNamesRepository),
+}
+
+impl ConfiguredNamesRepository {
+    /// Creates the repository `config` asks for, taking the server's credentials from the
+    /// environment.
+    pub(crate) fn from_config(config: &NamesConfig, files: &ConfigFiles) -> anyhow::Result<Self> {
+        match config {
+            NamesConfig::Embedded => Ok(Self::Embedded(EmbeddedNamesRepository::new(files)?)),
+            NamesConfig::Server { url } => {
+                let username = std::env::var("NICKNAMER_API_USERNAME")
+                    .context("NICKNAMER_API_USERNAME environment variable not set")?;
+                let password = std::env::var("NICKNAMER_API_PASSWORD")
+                    .context("NICKNAMER_API_PASSWORD environment variable not set")?;
+                Ok(Self::Http(HttpNamesRepository::new(
+                    url, username, password,
+                )))
+            }
+            NamesConfig::File { path } => Ok(Self::File(FileNamesRepository::new(path.clone()))),
+        }
+    }
+}
+
+#[async_trait]
+impl NamesRepository for ConfiguredNamesRepository {
+    async fn load_real_names(&self, guild_id: u64) -> Result<Names, Error> {
+        match self {
+            Self::Embedded(repository) => repository.load_real_names(guild_id).await,
+            Self::Http(repository) => repository.load_real_names(guild_id).await,
+            Self::File(repository) => repository.load_real_names(guild_id).await,
+        }
+    }
+
+    async fn save_name(&self, guild_id: u64, discord_id: u64, name: &str) -> Result<(), Error> {
+        match self {
+            Self::Embedded(repository) => repository.save_name(guild_id, discord_id, name).await,
+            Self::Http(repository) => repository.save_name(guild_id, discord_id, name).await,
+            Self::File(repository) => repository.save_name(guild_id, discord_id, name).await,
+        }
+    }
+
+    async fn delete_name(&self, guild_id: u64, discord_id: u64) -> Result<Option<String>, Error> {
+        match self {
+            Self::Embedded(repository) => repository.delete_name(guild_id, discord_id).await,
+            Self::Http(repository) => repository.delete_name(guild_id, discord_id).await,
+            Self::File(repository) => repository.delete_name(guild_id, discord_id).await,
+        }
     }
 }
 
@@ -113,7 +188,7 @@ names:
 "#;
 
         // Deserialize the YAML string using from_yaml
-        let deserialized: Names = serde_yml::from_str(yaml_data).unwrap();
+        let deserialized = Names::from_yaml(yaml_data).unwrap();
 
         // Create the expected RealNames object for comparison
         let mut expected = Names {
@@ -0,0 +1,137 @@
+//! Finding members by part of their real name, even when it's misspelled.
+
+use crate::nicknamer::names::Names;
+
+/// A real name found by [`search`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Found {
+    pub discord_id: u64,
+    pub name: String,
+}
+
+/// Searches real names for `query`, best matches first, returning at most `limit` of them.
+///
+/// Names match if they contain the query, ignoring case, or if the start of the name or of one
+/// of its words is a typo or two away from it, so that "Ali", "alice" and "Alcie" all find Alice.
+/// Short queries have to be spelled right, as nearly every short name is a typo away from them.
+pub fn search(names: &Names, query: &str, limit: usize) -> Vec<Found> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Vec::new();
+    }
+    let mut ranked: Vec<(usize, Found)> = names
+        .names
+        .iter()
+        .filter_map(|(discord_id, name)| {
+            let rank = rank(&query, &name.to_lowercase())?;
+            let found = Found {
+                discord_id: *discord_id,
+                name: name.clone(),
+            };
+            Some((rank, found))
+        })
+        .collect();
+    ranked.sort_by(|(rank, found), (other_rank, other)| {
+        rank.cmp(other_rank)
+            .then_with(|| found.name.cmp(&other.name))
+            .then_with(|| found.discord_id.cmp(&other.discord_id))
+    });
+    ranked
+        .into_iter()
+        .take(limit)
+        .map(|(_, found)| found)
+        .collect()
+}
+
+/// How well the lowercase `name` matches the lowercase `query`, lower being better: 0 for the
+/// name itself, 1 for names containing it, and 2 plus the number of typos otherwise. `None` if
+/// it takes more typos than the query's length forgives.
+fn rank(query: &str, name: &str) -> Option<usize> {
+    if name == query {
+        return Some(0);
+    }
+    if name.contains(query) {
+        return Some(1);
+    }
+    let query_length = query.chars().count();
+    let typos = std::iter::once(name)
+        .chain(name.split_whitespace())
+        .map(|candidate| {
+            let start: String = candidate.chars().take(query_length).collect();
+            strsim::damerau_levenshtein(query, &start)
+        })
+        .min()?;
+    (typos <= allowed_typos(query_length)).then_some(2 + typos)
+}
+
+/// How many typos a query of `length` characters forgives.
+fn allowed_typos(length: usize) -> usize {
+    match length {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names() -> Names {
+        [
+            (1, "Alice Smith"),
+            (2, "Bob"),
+            (3, "Al"),
+            (4, "Carol Alvarez"),
+        ]
+        .into_iter()
+        .map(|(discord_id, name)| (discord_id, name.to_string()))
+        .collect()
+    }
+
+    fn found_ids(query: &str) -> Vec<u64> {
+        search(&names(), query, 10)
+            .into_iter()
+            .map(|found| found.discord_id)
+            .collect()
+    }
+
+    #[test]
+    fn finds_names_containing_the_query() {
+        assert_eq!(found_ids("ali"), [1]);
+        assert_eq!(found_ids("SMITH"), [1]);
+        assert_eq!(found_ids("Al"), [3, 1, 4]);
+    }
+
+    #[test]
+    fn finds_misspelled_names() {
+        assert_eq!(found_ids("Alcie"), [1]);
+        assert_eq!(found_ids("Smiht"), [1]);
+        assert_eq!(found_ids("Carl"), [4]);
+    }
+
+    #[test]
+    fn ignores_names_that_are_not_alike() {
+        assert_eq!(found_ids("Zed"), Vec::<u64>::new());
+        assert_eq!(found_ids("   "), Vec::<u64>::new());
+    }
+
+    #[test]
+    fn returns_at_most_the_limit() {
+        let found = search(&names(), "al", 2);
+
+        assert_eq!(
+            found,
+            [
+                Found {
+                    discord_id: 3,
+                    name: "Al".to_string()
+                },
+                Found {
+                    discord_id: 1,
+                    name: "Alice Smith".to_string()
+                },
+            ]
+        );
+    }
+}
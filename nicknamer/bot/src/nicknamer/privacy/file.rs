@@ -0,0 +1,128 @@
+//! Repository that keeps opt-outs in a file on disk, so that they survive restarts.
+
+use crate::nicknamer::file_store::{self, FileFormat, FileStore};
+use crate::nicknamer::privacy::Error::{CannotLoadOptOuts, CannotSaveOptOuts};
+use crate::nicknamer::privacy::{Error, OptOutRepository};
+use anyhow::Context;
+use async_trait::async_trait;
+use std::collections::{BTreeSet, HashSet};
+use std::path::PathBuf;
+
+/// Repository implementation that reads and writes a file of Discord IDs, one per line.
+///
+/// The file doesn't need to exist until the first member opts out. Blank lines and lines
+/// starting with `#` are ignored, so the file can be annotated by hand.
+pub struct FileOptOutRepository {
+    store: FileStore<DiscordIds>,
+}
+
+impl FileOptOutRepository {
+    pub(crate) fn new(path: PathBuf) -> Self {
+        Self {
+            store: FileStore::new(path, DiscordIds),
+        }
+    }
+}
+
+/// Discord IDs, one per line, sorted so that changes to the file are easy to follow.
+struct DiscordIds;
+
+impl FileFormat for DiscordIds {
+    type Value = BTreeSet<u64>;
+
+    fn parse(&self, contents: &str) -> anyhow::Result<BTreeSet<u64>> {
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                line.parse::<u64>()
+                    .with_context(|| format!("Invalid Discord ID '{line}'"))
+            })
+            .collect()
+    }
+
+    fn serialize(&self, ids: &BTreeSet<u64>) -> anyhow::Result<String> {
+        Ok(ids.iter().map(|id| format!("{id}\n")).collect())
+    }
+}
+
+impl From<file_store::Error> for Error {
+    fn from(err: file_store::Error) -> Self {
+        match err {
+            file_store::Error::CannotLoad => CannotLoadOptOuts,
+            file_store::Error::CannotSave => CannotSaveOptOuts,
+        }
+    }
+}
+
+#[async_trait]
+impl OptOutRepository for FileOptOutRepository {
+    async fn load_opted_out(&self) -> Result<HashSet<u64>, Error> {
+        Ok(self.store.load().await?.into_iter().collect())
+    }
+
+    async fn opt_out(&self, discord_id: u64) -> Result<bool, Error> {
+        Ok(self
+            .store
+            .update(|opted_out| opted_out.insert(discord_id))
+            .await?)
+    }
+
+    async fn opt_in(&self, discord_id: u64) -> Result<bool, Error> {
+        Ok(self
+            .store
+            .update(|opted_out| opted_out.remove(&discord_id))
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn remembers_opt_outs_in_the_file() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("opt_outs.txt");
+        let sut = FileOptOutRepository::new(path.clone());
+
+        // Act
+        let before = sut.load_opted_out().await.unwrap();
+        let added = sut.opt_out(2).await.unwrap();
+        let added_again = sut.opt_out(2).await.unwrap();
+        sut.opt_out(1).await.unwrap();
+        let removed = sut.opt_in(1).await.unwrap();
+
+        // Assert
+        assert!(before.is_empty());
+        assert!(added);
+        assert!(!added_again);
+        assert!(removed);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "2\n");
+        let reread = FileOptOutRepository::new(path)
+            .load_opted_out()
+            .await
+            .unwrap();
+        assert_eq!(reread, HashSet::from([2]));
+    }
+
+    #[tokio::test]
+    async fn ignores_comments_but_not_invalid_ids() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let commented = dir.path().join("commented.txt");
+        let invalid = dir.path().join("invalid.txt");
+        std::fs::write(&commented, "# Asked in person\n1\n\n2\n").unwrap();
+        std::fs::write(&invalid, "1\nAlice\n").unwrap();
+
+        // Act
+        let loaded = FileOptOutRepository::new(commented).load_opted_out().await;
+        let failed = FileOptOutRepository::new(invalid).load_opted_out().await;
+
+        // Assert
+        assert_eq!(loaded.unwrap(), HashSet::from([1, 2]));
+        assert!(matches!(failed, Err(CannotLoadOptOuts)));
+    }
+}
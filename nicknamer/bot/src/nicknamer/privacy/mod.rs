@@ -0,0 +1,189 @@
+//! Members opting out of having their real names revealed.
+//!
+//! Members opt out, and back in, by sending the bot a direct message, so that nobody else sees
+//! them doing it. The opt-outs apply in every guild, as direct messages aren't sent in one.
+//! This module includes:
+//! - A repository trait for keeping track of who opted out
+//! - An implementation that only remembers opt-outs until the bot restarts
+//! - An implementation that keeps them in a file on disk
+//! - The replies to direct messages asking to opt out or in
+
+mod file;
+
+use crate::nicknamer::config::PrivacyConfig;
+use async_trait::async_trait;
+pub use file::FileOptOutRepository;
+use std::collections::{BTreeSet, HashSet};
+use std::sync::Mutex;
+use thiserror::Error;
+
+/// Errors that can occur keeping track of opt-outs.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// Indicates a failure to read who opted out
+    #[error("Failed to load opt-outs")]
+    CannotLoadOptOuts,
+    /// Indicates a failure to remember a change
+    #[error("Failed to save opt-outs")]
+    CannotSaveOptOuts,
+}
+
+/// Trait for keeping track of the members who don't want their real names revealed.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait OptOutRepository {
+    /// Loads the Discord IDs of every member who opted out.
+    async fn load_opted_out(&self) -> Result<HashSet<u64>, Error>;
+
+    /// Opts a member out.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool, Error>` - Whether they hadn't opted out already
+    async fn opt_out(&self, discord_id: u64) -> Result<bool, Error>;
+
+    /// Opts a member back in.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<bool, Error>` - Whether they had opted out
+    async fn opt_in(&self, discord_id: u64) -> Result<bool, Error>;
+}
+
+/// Repository implementation that keeps opt-outs in memory, for when no file is configured.
+#[derive(Default)]
+pub struct MemoryOptOutRepository {
+    opted_out: Mutex<BTreeSet<u64>>,
+}
+
+impl MemoryOptOutRepository {
+    pub const fn new() -> Self {
+        Self {
+            opted_out: Mutex::new(BTreeSet::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl OptOutRepository for MemoryOptOutRepository {
+    async fn load_opted_out(&self) -> Result<HashSet<u64>, Error> {
+        let opted_out = self.opted_out.lock().expect("opt-out lock poisoned");
+        Ok(opted_out.iter().copied().collect())
+    }
+
+    async fn opt_out(&self, discord_id: u64) -> Result<bool, Error> {
+        let mut opted_out = self.opted_out.lock().expect("opt-out lock poisoned");
+        Ok(opted_out.insert(discord_id))
+    }
+
+    async fn opt_in(&self, discord_id: u64) -> Result<bool, Error> {
+        let mut opted_out = self.opted_out.lock().expect("opt-out lock poisoned");
+        Ok(opted_out.remove(&discord_id))
+    }
+}
+
+/// The repository picked in the config.
+pub enum ConfiguredOptOutRepository {
+    Memory(MemoryOptOutRepository),
+    File(FileOptOutRepository),
+}
+
+impl ConfiguredOptOutRepository {
+    /// Keeps opt-outs in the configured file, or only in memory without one.
+    pub(crate) fn from_config(config: &PrivacyConfig) -> Self {
+        match &config.opt_out_file {
+            Some(path) => Self::File(FileOptOutRepository::new(path.clone())),
+            None => {
+                tracing::warn!("No opt-out file configured, opt-outs are forgotten on restart");
+                Self::Memory(MemoryOptOutRepository::new())
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl OptOutRepository for ConfiguredOptOutRepository {
+    async fn load_opted_out(&self) -> Result<HashSet<u64>, Error> {
+        match self {
+            Self::Memory(repository) => repository.load_opted_out().await,
+            Self::File(repository) => repository.load_opted_out().await,
+        }
+    }
+
+    async fn opt_out(&self, discord_id: u64) -> Result<bool, Error> {
+        match self {
+            Self::Memory(repository) => repository.opt_out(discord_id).await,
+            Self::File(repository) => repository.opt_out(discord_id).await,
+        }
+    }
+
+    async fn opt_in(&self, discord_id: u64) -> Result<bool, Error> {
+        match self {
+            Self::Memory(repository) => repository.opt_in(discord_id).await,
+            Self::File(repository) => repository.opt_in(discord_id).await,
+        }
+    }
+}
+
+/// Answers a direct message to the bot, opting its author out or back in if they asked to.
+///
+/// # Returns
+///
+/// * `Result<String, Error>` - The reply to send back
+pub async fn answer_direct_message(
+    opt_outs: &impl OptOutRepository,
+    author_id: u64,
+    content: &str,
+) -> Result<String, Error> {
+    let reply = match content.trim().to_lowercase().as_str() {
+        "optout" => {
+            if opt_outs.opt_out(author_id).await? {
+                tracing::info!("A member opted out of having their real name revealed");
+            }
+            "Got it, I'll keep your real name to myself. DM me `optin` if you change your mind."
+        }
+        "optin" => {
+            if opt_outs.opt_in(author_id).await? {
+                tracing::info!("A member opted back in to having their real name revealed");
+            }
+            "Got it, your real name is fair game again."
+        }
+        _ => "DM me `optout` and I'll never reveal your real name, or `optin` to undo that.",
+    };
+    Ok(reply.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn direct_messages_opt_members_out_and_back_in() {
+        // Arrange
+        let sut = MemoryOptOutRepository::new();
+
+        // Act
+        let opted_out_reply = answer_direct_message(&sut, 1, "  OptOut ").await.unwrap();
+        let opted_out = sut.load_opted_out().await.unwrap();
+        let opted_in_reply = answer_direct_message(&sut, 1, "optin").await.unwrap();
+        let opted_in = sut.load_opted_out().await.unwrap();
+
+        // Assert
+        assert!(opted_out_reply.starts_with("Got it, I'll keep your real name to myself"));
+        assert_eq!(opted_out, HashSet::from([1]));
+        assert_eq!(opted_in_reply, "Got it, your real name is fair game again.");
+        assert!(opted_in.is_empty());
+    }
+
+    #[tokio::test]
+    async fn other_direct_messages_get_instructions() {
+        let sut = MemoryOptOutRepository::new();
+
+        let reply = answer_direct_message(&sut, 1, "who are you?")
+            .await
+            .unwrap();
+
+        assert!(reply.contains("`optout`"));
+        assert!(sut.load_opted_out().await.unwrap().is_empty());
+    }
+}
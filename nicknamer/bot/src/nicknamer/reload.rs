@@ -0,0 +1,196 @@
+//! Reloading the config and real names while the bot runs.
+//!
+//! Commands and events take a [`Snapshot`] of the settings when they start, so that a reload
+//! in the middle of one can't leave it with half old and half new settings. Only what's read
+//! while handling them can change: the cooldowns, slash command registration, opt-out file and
+//! nickname history file are set up when the bot starts, so changing those still takes a
+//! restart.
+
+use crate::nicknamer::config::{Config, ConfigFiles};
+use crate::nicknamer::names::ConfiguredNamesRepository;
+use anyhow::Context;
+use arc_swap::ArcSwap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+use tracing::info;
+
+/// The config files the watcher reloads the settings on changes to.
+const WATCHED_FILES: [&str; 2] = ["config.toml", "real_names.yml"];
+
+/// The settings loaded together from the config files.
+pub struct Snapshot<REPO> {
+    pub config: Config,
+    pub names_repository: REPO,
+}
+
+impl Snapshot<ConfiguredNamesRepository> {
+    pub(crate) fn load(files: &ConfigFiles) -> anyhow::Result<Self> {
+        let config = Config::load(files).context("Failed to load the configuration")?;
+        let names_repository = ConfiguredNamesRepository::from_config(&config.names, files)
+            .context("Failed to set up the names repository")?;
+        Ok(Self {
+            config,
+            names_repository,
+        })
+    }
+}
+
+/// The current settings, swapped as a whole when they're reloaded.
+pub struct Settings<REPO> {
+    current: ArcSwap<Snapshot<REPO>>,
+}
+
+impl<REPO> Settings<REPO> {
+    pub fn new(snapshot: Snapshot<REPO>) -> Self {
+        Self {
+            current: ArcSwap::from_pointee(snapshot),
+        }
+    }
+
+    /// The settings as they are now, which stay the same however long they're held on to.
+    pub fn current(&self) -> Arc<Snapshot<REPO>> {
+        self.current.load_full()
+    }
+
+    pub fn replace(&self, snapshot: Snapshot<REPO>) {
+        self.current.store(Arc::new(snapshot));
+    }
+}
+
+impl Settings<ConfiguredNamesRepository> {
+    /// Loads the config files again, keeping the current settings if the new ones are broken.
+    pub(crate) fn reload(&self, files: &ConfigFiles) -> anyhow::Result<()> {
+        self.replace(Snapshot::load(files)?);
+        info!("Reloaded the configuration");
+        Ok(())
+    }
+}
+
+/// Calls `on_change` whenever one of the config files changes, checking every `interval`.
+///
+/// Returns straight away for the built-in files, as they can't change.
+pub async fn watch_config_files(
+    files: ConfigFiles,
+    interval: Duration,
+    mut on_change: impl FnMut(),
+) {
+    if files == ConfigFiles::BuiltIn {
+        return;
+    }
+    let modified = |files: &ConfigFiles| WATCHED_FILES.map(|name| files.modified(name));
+    let mut last_seen = modified(&files);
+    let mut ticks = tokio::time::interval(interval);
+    ticks.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    loop {
+        ticks.tick().await;
+        let seen = modified(&files);
+        if seen != last_seen {
+            info!("Config files changed");
+            last_seen = seen;
+            on_change();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nicknamer::names::NamesRepository;
+    use std::path::Path;
+    use std::time::SystemTime;
+
+    fn write_config(dir: &Path, insult: &str) {
+        let config = format!(
+            r#"
+                [nicknamer.reveal]
+                insult = "{insult}"
+                role_to_mention = "test role"
+                he_who_shall_not_be_named = 1
+            "#
+        );
+        std::fs::write(dir.join("config.toml"), config).unwrap();
+        std::fs::write(dir.join("real_names.yml"), "names:\n  1: Alice\n").unwrap();
+    }
+
+    #[tokio::test]
+    async fn reloads_keep_snapshots_in_use_as_they_were() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let files = ConfigFiles::Directory(dir.path().to_path_buf());
+        write_config(dir.path(), "old insult");
+        let settings = Settings::new(Snapshot::load(&files).unwrap());
+        let before = settings.current();
+
+        // Act
+        write_config(dir.path(), "new insult");
+        std::fs::write(dir.path().join("real_names.yml"), "names:\n  1: Alicia\n").unwrap();
+        settings.reload(&files).unwrap();
+
+        // Assert
+        let after = settings.current();
+        assert_eq!(before.config.nicknamer.reveal.insult, "old insult");
+        assert_eq!(after.config.nicknamer.reveal.insult, "new insult");
+        let names = after.names_repository.load_real_names(42).await.unwrap();
+        assert_eq!(names.get(1), Some("Alicia"));
+    }
+
+    #[tokio::test]
+    async fn broken_configs_are_not_reloaded() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let files = ConfigFiles::Directory(dir.path().to_path_buf());
+        write_config(dir.path(), "old insult");
+        let settings = Settings::new(Snapshot::load(&files).unwrap());
+
+        // Act
+        std::fs::write(dir.path().join("config.toml"), "[nicknamer").unwrap();
+        let result = settings.reload(&files);
+
+        // Assert
+        assert!(result.is_err());
+        assert_eq!(
+            settings.current().config.nicknamer.reveal.insult,
+            "old insult"
+        );
+    }
+
+    #[tokio::test]
+    async fn watcher_notices_changed_files() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        write_config(dir.path(), "old insult");
+        let files = ConfigFiles::Directory(dir.path().to_path_buf());
+        let (changed, mut changes) = tokio::sync::mpsc::unbounded_channel();
+        let watcher = tokio::spawn(watch_config_files(
+            files,
+            Duration::from_millis(10),
+            move || changed.send(()).unwrap(),
+        ));
+        tokio::time::sleep(Duration::from_millis(30)).await;
+
+        // Act
+        let file = std::fs::File::options()
+            .append(true)
+            .open(dir.path().join("real_names.yml"))
+            .unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+
+        // Assert
+        let change = tokio::time::timeout(Duration::from_secs(1), changes.recv()).await;
+        assert_eq!(change, Ok(Some(())));
+        watcher.abort();
+    }
+
+    #[tokio::test]
+    async fn built_in_files_are_not_watched() {
+        let watched = watch_config_files(ConfigFiles::BuiltIn, Duration::from_millis(10), || {
+            panic!("built-in files can't change")
+        });
+
+        let result = tokio::time::timeout(Duration::from_secs(1), watched).await;
+
+        assert!(result.is_ok());
+    }
+}
@@ -0,0 +1,242 @@
+//! Reporter that sends failed commands to Sentry as error events.
+//!
+//! Events go straight to the project's store endpoint, which the DSN from the project's
+//! settings points to. They carry the command's trace ID in their trace context, so they can
+//! be matched with the bot's log lines.
+
+use crate::nicknamer::reporting::{Error, ErrorReport, ErrorReporter};
+use async_trait::async_trait;
+use observability::TraceContext;
+use reqwest::{Client, Url};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Identifies the bot to Sentry.
+const CLIENT: &str = concat!("nicknamer-bot/", env!("CARGO_PKG_VERSION"));
+
+#[derive(Serialize)]
+struct Event<'a> {
+    event_id: String,
+    /// Seconds since the Unix epoch
+    timestamp: f64,
+    platform: &'static str,
+    level: &'static str,
+    logger: &'static str,
+    release: &'static str,
+    transaction: &'a str,
+    logentry: LogEntry<'a>,
+    user: EventUser,
+    tags: BTreeMap<&'static str, String>,
+    contexts: Contexts,
+}
+
+#[derive(Serialize)]
+struct LogEntry<'a> {
+    formatted: &'a str,
+}
+
+#[derive(Serialize)]
+struct EventUser {
+    id: String,
+}
+
+#[derive(Serialize)]
+struct Contexts {
+    trace: TraceEventContext,
+}
+
+#[derive(Serialize)]
+struct TraceEventContext {
+    trace_id: String,
+    span_id: String,
+}
+
+/// Reporter that sends reports to a Sentry project.
+pub struct SentryReporter {
+    client: Client,
+    store_url: Url,
+    public_key: String,
+}
+
+impl SentryReporter {
+    /// Creates a reporter for the project of a DSN like `https://<key>@<host>/<project ID>`.
+    pub(crate) fn from_dsn(dsn: &str) -> Result<Self, Error> {
+        let mut url = Url::parse(dsn).map_err(|_| Error::InvalidDsn)?;
+        let public_key = url.username().to_string();
+        let path = url.path().trim_end_matches('/').to_string();
+        let (prefix, project_id) = path.rsplit_once('/').ok_or(Error::InvalidDsn)?;
+        if public_key.is_empty() || project_id.is_empty() {
+            return Err(Error::InvalidDsn);
+        }
+        let store_path = format!("{}/api/{}/store/", prefix, project_id);
+        url.set_path(&store_path);
+        url.set_username("").map_err(|_| Error::InvalidDsn)?;
+        url.set_password(None).map_err(|_| Error::InvalidDsn)?;
+        Ok(Self {
+            client: Client::new(),
+            store_url: url,
+            public_key,
+        })
+    }
+
+    /// Creates a reporter for the project in the `SENTRY_DSN` environment variable, or `None`
+    /// if it isn't set.
+    pub(crate) fn from_env() -> Result<Option<Self>, Error> {
+        match std::env::var("SENTRY_DSN") {
+            Ok(dsn) if !dsn.is_empty() => Self::from_dsn(&dsn).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    fn event<'a>(&self, report: &'a ErrorReport) -> Event<'a> {
+        let mut tags = BTreeMap::from([("command", report.command.clone())]);
+        if let Some(guild_id) = report.guild_id {
+            tags.insert("guild_id", guild_id.to_string());
+        }
+        if let Some(discord_error) = &report.discord_error {
+            tags.insert("discord_error", discord_error.kind().to_string());
+        }
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0.0, |since_epoch| since_epoch.as_secs_f64());
+        Event {
+            // Sentry wants a random ID of the same shape as a trace ID
+            event_id: TraceContext::new_root().trace_id(),
+            timestamp,
+            platform: "other",
+            level: "error",
+            logger: "nicknamer-bot",
+            release: CLIENT,
+            transaction: &report.command,
+            logentry: LogEntry {
+                formatted: &report.error,
+            },
+            user: EventUser {
+                id: report.member_id.to_string(),
+            },
+            tags,
+            contexts: Contexts {
+                trace: TraceEventContext {
+                    trace_id: report.trace.trace_id(),
+                    span_id: report.trace.span_id(),
+                },
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl ErrorReporter for SentryReporter {
+    async fn report(&self, report: &ErrorReport) -> Result<(), Error> {
+        let auth = format!(
+            "Sentry sentry_version=7, sentry_client={}, sentry_key={}",
+            CLIENT, self.public_key
+        );
+        self.client
+            .post(self.store_url.clone())
+            .header("X-Sentry-Auth", auth)
+            .json(&self.event(report))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| {
+                tracing::error!("Failed to send an error report to Sentry: {err}");
+                Error::CannotSendReport
+            })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nicknamer::connectors::discord;
+    use axum::http::HeaderMap;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use tokio::sync::mpsc;
+
+    /// Serves the store endpoint of project 1, passing on the auth header and event it gets.
+    async fn fake_sentry(received: mpsc::UnboundedSender<(String, serde_json::Value)>) -> String {
+        let app = Router::new().route(
+            "/api/1/store/",
+            post(
+                move |headers: HeaderMap, Json(event): Json<serde_json::Value>| async move {
+                    let auth = headers["x-sentry-auth"].to_str().unwrap().to_string();
+                    received.send((auth, event)).unwrap();
+                    Json(serde_json::json!({ "id": "1" }))
+                },
+            ),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://public@{addr}/1")
+    }
+
+    #[test]
+    fn dsns_point_to_the_projects_store_endpoint() {
+        let sut = SentryReporter::from_dsn("https://abc@o1.ingest.sentry.io/sentry/42").unwrap();
+
+        assert_eq!(
+            sut.store_url.as_str(),
+            "https://o1.ingest.sentry.io/sentry/api/42/store/"
+        );
+        assert_eq!(sut.public_key, "abc");
+    }
+
+    #[test]
+    fn dsns_need_a_key_and_a_project() {
+        assert!(SentryReporter::from_dsn("https://o1.ingest.sentry.io/42").is_err());
+        assert!(SentryReporter::from_dsn("https://abc@o1.ingest.sentry.io/").is_err());
+        assert!(SentryReporter::from_dsn("not a dsn").is_err());
+    }
+
+    #[tokio::test]
+    async fn sends_reports_as_events_with_their_trace() {
+        // Arrange
+        let (received, mut events) = mpsc::unbounded_channel();
+        let sut = SentryReporter::from_dsn(&fake_sentry(received).await).unwrap();
+        let error = anyhow::Error::from(discord::Error::NotEnoughPermissions);
+        let report = ErrorReport::new("nick", 42, Some(7), 8, TraceContext::new_root(), &error);
+
+        // Act
+        sut.report(&report).await.unwrap();
+
+        // Assert
+        let (auth, event) = events.recv().await.unwrap();
+        assert!(auth.contains("sentry_key=public"));
+        assert_eq!(event["logentry"]["formatted"], "Not enough permissions");
+        assert_eq!(event["tags"]["command"], "nick");
+        assert_eq!(event["tags"]["discord_error"], "NotEnoughPermissions");
+        assert_eq!(event["user"]["id"], "42");
+        assert_eq!(
+            event["contexts"]["trace"]["trace_id"],
+            report.trace.trace_id()
+        );
+    }
+
+    #[tokio::test]
+    async fn fails_when_sentry_is_unreachable() {
+        // Arrange
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let dsn = format!("http://public@{}/1", listener.local_addr().unwrap());
+        drop(listener);
+        let sut = SentryReporter::from_dsn(&dsn).unwrap();
+        let report = ErrorReport::new(
+            "nick",
+            42,
+            None,
+            8,
+            TraceContext::new_root(),
+            &anyhow::anyhow!("oops"),
+        );
+
+        // Act
+        let result = sut.report(&report).await;
+
+        // Assert
+        assert!(matches!(result, Err(Error::CannotSendReport)));
+    }
+}
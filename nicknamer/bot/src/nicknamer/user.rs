@@ -1,6 +1,7 @@
 use crate::nicknamer::connectors::discord;
-use crate::nicknamer::connectors::discord::server_member;
+use crate::nicknamer::history;
 use crate::nicknamer::names;
+use crate::nicknamer::privacy;
 use thiserror::Error;
 
 #[derive(Debug, PartialEq, Default)]
@@ -11,8 +12,8 @@ pub struct User {
     pub real_name: Option<String>,
 }
 
-impl From<&server_member::ServerMember> for User {
-    fn from(discord_member: &server_member::ServerMember) -> Self {
+impl From<&discord::ServerMember> for User {
+    fn from(discord_member: &discord::ServerMember) -> Self {
         Self {
             id: discord_member.id,
             user_name: discord_member.user_name.clone(),
@@ -23,9 +24,14 @@ impl From<&server_member::ServerMember> for User {
 }
 
 #[derive(Error, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
     #[error("Something went wrong with Discord")]
     DiscordError(#[from] discord::Error),
     #[error("Something went wrong getting people's names")]
     NamesAccessError(#[from] names::Error),
+    #[error("Something went wrong checking who opted out")]
+    OptOutError(#[from] privacy::Error),
+    #[error("Something went wrong with nickname history")]
+    HistoryError(#[from] history::Error),
 }
@@ -5,6 +5,15 @@ mod m20250618_072946_rename_table_name;
 mod m20250622_231317_add_index;
 mod m20250706_102217_add_name_by_server;
 mod m20250715_180325_update_unique_column;
+mod m20261016_120000_create_feature_flags;
+mod m20261016_130000_create_api_tokens;
+mod m20261016_140000_create_users;
+mod m20261016_150000_create_audit_log;
+mod m20261016_160000_add_name_deleted_at;
+mod m20261016_170000_add_name_version;
+mod m20261016_180000_add_user_discord_id;
+mod m20261016_190000_create_servers;
+mod m20261016_200000_add_name_trigram_index;
 
 pub struct Migrator;
 
@@ -17,6 +26,15 @@ impl MigratorTrait for Migrator {
             Box::new(m20250622_231317_add_index::Migration),
             Box::new(m20250706_102217_add_name_by_server::Migration),
             Box::new(m20250715_180325_update_unique_column::Migration),
+            Box::new(m20261016_120000_create_feature_flags::Migration),
+            Box::new(m20261016_130000_create_api_tokens::Migration),
+            Box::new(m20261016_140000_create_users::Migration),
+            Box::new(m20261016_150000_create_audit_log::Migration),
+            Box::new(m20261016_160000_add_name_deleted_at::Migration),
+            Box::new(m20261016_170000_add_name_version::Migration),
+            Box::new(m20261016_180000_add_user_discord_id::Migration),
+            Box::new(m20261016_190000_create_servers::Migration),
+            Box::new(m20261016_200000_add_name_trigram_index::Migration),
         ]
     }
 }
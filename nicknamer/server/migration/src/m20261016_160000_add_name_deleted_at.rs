@@ -0,0 +1,85 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Name::Table)
+                    .add_column(
+                        ColumnDef::new(Name::DeletedAt)
+                            .timestamp_with_time_zone()
+                            .null(),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Names in the trash must not keep a user from getting a new name in the same server
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("name_discord_id_server_id_unique")
+                    .table(Name::Table)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .get_connection()
+            .execute_unprepared(
+                "CREATE UNIQUE INDEX name_discord_id_server_id_unique \
+                 ON name (discord_id, server_id) WHERE deleted_at IS NULL;",
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("name_discord_id_server_id_unique")
+                    .table(Name::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        // Names in the trash could clash with the names that replaced them
+        manager
+            .get_connection()
+            .execute_unprepared("DELETE FROM name WHERE deleted_at IS NOT NULL;")
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("name_discord_id_server_id_unique")
+                    .table(Name::Table)
+                    .col(Name::DiscordId)
+                    .col(Name::ServerId)
+                    .unique()
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Name::Table)
+                    .drop_column(Name::DeletedAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Name {
+    Table,
+    DiscordId,
+    ServerId,
+    DeletedAt,
+}
@@ -0,0 +1,100 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Servers::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Servers::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(Servers::GuildId)
+                            .big_integer()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(ColumnDef::new(Servers::Name).string().not_null())
+                    .col(ColumnDef::new(Servers::IconUrl).string().null())
+                    .col(ColumnDef::new(Servers::OwnerId).big_integer().null())
+                    .col(
+                        ColumnDef::new(Servers::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // Names keep their free-form server ID, and are linked to the server it refers to, if
+        // that server is registered
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Name::Table)
+                    .add_column(ColumnDef::new(Name::RegisteredServerId).integer().null())
+                    .add_foreign_key(
+                        TableForeignKey::new()
+                            .name("name_registered_server_id_fkey")
+                            .from_tbl(Name::Table)
+                            .from_col(Name::RegisteredServerId)
+                            .to_tbl(Servers::Table)
+                            .to_col(Servers::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .create_index(
+                Index::create()
+                    .name("name_registered_server_id_idx")
+                    .table(Name::Table)
+                    .col(Name::RegisteredServerId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Name::Table)
+                    .drop_column(Name::RegisteredServerId)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .drop_table(Table::drop().table(Servers::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Servers {
+    Table,
+    Id,
+    GuildId,
+    Name,
+    IconUrl,
+    OwnerId,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Name {
+    Table,
+    RegisteredServerId,
+}
@@ -0,0 +1,269 @@
+//! API tokens, which let machine clients like the Discord bot use the JSON API without logging
+//! in. Only a SHA-256 hash of each token is stored, so the token itself is shown once, when it
+//! is created, and can't be recovered afterwards.
+
+use crate::entities::*;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::*;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+use typed_ids::EntityId;
+
+pub mod web;
+
+/// The ID of an API token.
+pub type ApiTokenId = EntityId<api_token::Entity>;
+
+/// What every token starts with, which tells them apart from JWTs in the `Authorization` header.
+pub const TOKEN_PREFIX: &str = "nn_";
+
+/// What a token is allowed to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    /// Only requests that change nothing, like `GET`
+    ReadOnly,
+    /// Every request a logged-in user can make
+    ReadWrite,
+}
+
+impl TokenScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TokenScope::ReadOnly => "read_only",
+            TokenScope::ReadWrite => "read_write",
+        }
+    }
+
+    /// Whether the scope allows a request with `method`.
+    pub fn allows(self, method: &axum::http::Method) -> bool {
+        match self {
+            TokenScope::ReadOnly => method.is_safe(),
+            TokenScope::ReadWrite => true,
+        }
+    }
+}
+
+impl fmt::Display for TokenScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TokenScope {
+    type Err = ApiTokenServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read_only" => Ok(TokenScope::ReadOnly),
+            "read_write" => Ok(TokenScope::ReadWrite),
+            other => Err(ApiTokenServiceError::UnknownScope(other.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Eq)]
+pub struct ApiToken {
+    id: ApiTokenId,
+    name: String,
+    scope: TokenScope,
+    created_at: DateTimeWithTimeZone,
+}
+
+impl ApiToken {
+    /// Returns the ID of the token.
+    pub fn id(&self) -> ApiTokenId {
+        self.id
+    }
+
+    /// Returns the name the token was given, e.g. the client it was created for.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns what the token is allowed to do.
+    pub fn scope(&self) -> TokenScope {
+        self.scope
+    }
+
+    /// Returns when the token was created.
+    pub fn created_at(&self) -> DateTimeWithTimeZone {
+        self.created_at
+    }
+
+    /// The username requests made with the token are attributed to.
+    pub fn username(&self) -> String {
+        format!("token:{}", self.name)
+    }
+}
+
+impl TryFrom<api_token::Model> for ApiToken {
+    type Error = ApiTokenServiceError;
+
+    fn try_from(model: api_token::Model) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: model.id,
+            name: model.name,
+            scope: model.scope.parse()?,
+            created_at: model.created_at,
+        })
+    }
+}
+
+/// Error type for ApiTokenService operations.
+#[derive(Debug, thiserror::Error)]
+pub enum ApiTokenServiceError {
+    /// Represents a database error.
+    #[error("Database error: {0}")]
+    Database(#[from] sea_orm::DbErr),
+    /// Represents a token not found error.
+    #[error("API token with ID {0} not found")]
+    TokenNotFound(ApiTokenId),
+    /// Represents a token created without a name.
+    #[error("API tokens need a name")]
+    MissingName,
+    /// Represents a stored scope this version doesn't know.
+    #[error("Unknown API token scope '{0}'")]
+    UnknownScope(String),
+}
+
+pub struct ApiTokenService<'a> {
+    db: &'a sea_orm::DatabaseConnection,
+}
+
+impl ApiTokenService<'_> {
+    pub fn new(db: &sea_orm::DatabaseConnection) -> ApiTokenService<'_> {
+        ApiTokenService { db }
+    }
+
+    /// Creates a new API token.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - What the token is for, e.g. the client it's handed to.
+    /// * `scope` - What the token is allowed to do.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the created `ApiToken` and the token itself, which is never
+    /// available again, if successful, or an error otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn create_token(
+        &self,
+        name: String,
+        scope: TokenScope,
+    ) -> Result<(ApiToken, String), ApiTokenServiceError> {
+        let name = name.trim().to_string();
+        if name.is_empty() {
+            return Err(ApiTokenServiceError::MissingName);
+        }
+
+        let secret = generate_secret();
+        let active_model = api_token::ActiveModel {
+            name: ActiveValue::Set(name),
+            token_hash: ActiveValue::Set(hash_secret(&secret)),
+            scope: ActiveValue::Set(scope.to_string()),
+            ..Default::default()
+        };
+        let created_model = active_model.insert(self.db).await?;
+        Ok((ApiToken::try_from(created_model)?, secret))
+    }
+
+    /// Retrieves all API tokens, oldest first.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_all_tokens(&self) -> Result<Vec<ApiToken>, ApiTokenServiceError> {
+        api_token::Entity::find()
+            .order_by_asc(api_token::Column::Id)
+            .all(self.db)
+            .await?
+            .into_iter()
+            .map(ApiToken::try_from)
+            .collect()
+    }
+
+    /// Revokes an API token, so that it can't be used anymore.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the revoked `ApiToken` if successful, or an error otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn revoke_token(&self, id: ApiTokenId) -> Result<ApiToken, ApiTokenServiceError> {
+        let token = api_token::Entity::find_by_id(id)
+            .one(self.db)
+            .await?
+            .ok_or(ApiTokenServiceError::TokenNotFound(id))?;
+
+        api_token::Entity::delete_by_id(id).exec(self.db).await?;
+        ApiToken::try_from(token)
+    }
+
+    /// Looks up the API token a client presented.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `ApiToken` if the token is known, `None` if it isn't, or an
+    /// error.
+    #[tracing::instrument(skip(self, secret))]
+    pub async fn authenticate(
+        &self,
+        secret: &str,
+    ) -> Result<Option<ApiToken>, ApiTokenServiceError> {
+        api_token::Entity::find()
+            .filter(api_token::Column::TokenHash.eq(hash_secret(secret)))
+            .one(self.db)
+            .await?
+            .map(ApiToken::try_from)
+            .transpose()
+    }
+}
+
+/// Generates a new token: the prefix followed by 244 random bits.
+fn generate_secret() -> String {
+    format!(
+        "{}{}{}",
+        TOKEN_PREFIX,
+        uuid::Uuid::new_v4().simple(),
+        uuid::Uuid::new_v4().simple()
+    )
+}
+
+/// Hashes a token for storage. Tokens are random enough that a fast hash can't be brute forced.
+fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Method;
+
+    #[test]
+    fn secrets_are_prefixed_and_unique() {
+        let first = generate_secret();
+        let second = generate_secret();
+
+        assert!(first.starts_with(TOKEN_PREFIX));
+        assert_eq!(first.len(), TOKEN_PREFIX.len() + 64);
+        assert_ne!(first, second);
+        assert_eq!(hash_secret(&first), hash_secret(&first));
+        assert_ne!(hash_secret(&first), hash_secret(&second));
+        assert!(!hash_secret(&first).contains(&first));
+    }
+
+    #[test]
+    fn read_only_tokens_only_allow_safe_methods() {
+        assert!(TokenScope::ReadOnly.allows(&Method::GET));
+        assert!(!TokenScope::ReadOnly.allows(&Method::POST));
+        assert!(!TokenScope::ReadOnly.allows(&Method::DELETE));
+        assert!(TokenScope::ReadWrite.allows(&Method::PUT));
+    }
+
+    #[test]
+    fn scopes_round_trip_through_strings() {
+        for scope in [TokenScope::ReadOnly, TokenScope::ReadWrite] {
+            assert_eq!(scope.to_string().parse::<TokenScope>().unwrap(), scope);
+        }
+        assert!("admin".parse::<TokenScope>().is_err());
+    }
+}
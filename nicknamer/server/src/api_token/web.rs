@@ -0,0 +1,162 @@
+use askama::Template;
+use axum::{
+    Form, Router,
+    extract::{Path, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::Html,
+    routing::{delete, get},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::api_token::{ApiToken, ApiTokenId, ApiTokenService, ApiTokenServiceError, TokenScope};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiTokenForm {
+    name: String,
+    scope: TokenScope,
+}
+
+#[derive(Clone, Debug)]
+pub struct ApiTokenState {
+    pub db: Arc<sea_orm::DatabaseConnection>,
+}
+
+/// Custom error type for API token handler operations.
+#[derive(Debug, thiserror::Error)]
+enum ApiTokenError {
+    /// Represents an error during template rendering.
+    #[error("Template rendering failed")]
+    Template(#[from] askama::Error),
+    /// Represents an API token service error.
+    #[error("API token service error")]
+    Service(#[from] ApiTokenServiceError),
+}
+
+impl axum::response::IntoResponse for ApiTokenError {
+    fn into_response(self) -> axum::response::Response {
+        let (status_code, user_facing_error_message) = match self {
+            ApiTokenError::Service(ApiTokenServiceError::MissingName) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Give the token a name, so that you can tell what it's used for.",
+            ),
+            ApiTokenError::Service(ApiTokenServiceError::TokenNotFound(_)) => (
+                StatusCode::NOT_FOUND,
+                "The token doesn't exist anymore. It may have been revoked already.",
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "An unexpected error occurred while processing your request. Please try again later.",
+            ),
+        };
+
+        let error_template = ErrorMessageTemplate::new(user_facing_error_message.to_string());
+        let Ok(rendered) = error_template.render() else {
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        };
+
+        let mut response = (status_code, Html(rendered)).into_response();
+        // Add HTMX headers to retarget the error message to the error div
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static("hx-retarget"),
+            HeaderValue::from_static("#api-token-error"),
+        );
+        headers.insert(
+            HeaderName::from_static("hx-reswap"),
+            HeaderValue::from_static("innerHTML"),
+        );
+        response.headers_mut().extend(headers);
+        response
+    }
+}
+
+#[derive(Template)]
+#[template(path = "api_tokens.html")]
+struct ApiTokensTemplate;
+
+#[derive(Template)]
+#[template(path = "api_tokens/api_tokens_table.html")]
+struct ApiTokensTableTemplate {
+    tokens: Vec<ApiToken>,
+    /// The token that was just created, shown this one time only
+    created: Option<(ApiToken, String)>,
+}
+
+impl ApiTokensTableTemplate {
+    pub fn new(tokens: Vec<ApiToken>, created: Option<(ApiToken, String)>) -> Self {
+        Self { tokens, created }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "names/error_message.html")]
+struct ErrorMessageTemplate {
+    message: String,
+}
+
+impl ErrorMessageTemplate {
+    pub fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+/// Renders the table of all tokens, with the one that was just created if there is one.
+async fn render_tokens_table(
+    service: &ApiTokenService<'_>,
+    created: Option<(ApiToken, String)>,
+) -> Result<Html<String>, ApiTokenError> {
+    let tokens = service.get_all_tokens().await?;
+    let template = ApiTokensTableTemplate::new(tokens, created);
+    template.render().map(Html).map_err(ApiTokenError::from)
+}
+
+/// Handler for GET /admin/api-tokens that displays the API tokens page.
+#[tracing::instrument]
+async fn api_tokens_handler() -> Result<Html<String>, ApiTokenError> {
+    let template = ApiTokensTemplate;
+    template.render().map(Html).map_err(ApiTokenError::from)
+}
+
+/// Handler for GET /admin/api-tokens/table that returns just the tokens table fragment.
+#[tracing::instrument(skip(state))]
+async fn api_tokens_table_handler(
+    State(state): State<Arc<ApiTokenState>>,
+) -> Result<Html<String>, ApiTokenError> {
+    let service = ApiTokenService::new(&state.db);
+    render_tokens_table(&service, None).await
+}
+
+/// Handler for POST /admin/api-tokens that creates a token and shows it once.
+#[tracing::instrument(skip(state))]
+async fn create_api_token_handler(
+    State(state): State<Arc<ApiTokenState>>,
+    Form(form): Form<CreateApiTokenForm>,
+) -> Result<Html<String>, ApiTokenError> {
+    let service = ApiTokenService::new(&state.db);
+    let created = service.create_token(form.name, form.scope).await?;
+    render_tokens_table(&service, Some(created)).await
+}
+
+/// Handler for DELETE /admin/api-tokens/{id} that revokes a token.
+#[tracing::instrument(skip(state))]
+async fn revoke_api_token_handler(
+    State(state): State<Arc<ApiTokenState>>,
+    Path(id): Path<ApiTokenId>,
+) -> Result<Html<String>, ApiTokenError> {
+    let service = ApiTokenService::new(&state.db);
+    service.revoke_token(id).await?;
+    render_tokens_table(&service, None).await
+}
+
+/// Creates and returns the router of the API tokens admin pages.
+pub fn create_api_token_router(state: Arc<ApiTokenState>) -> Router {
+    Router::new()
+        .route(
+            "/admin/api-tokens",
+            get(api_tokens_handler).post(create_api_token_handler),
+        )
+        .route("/admin/api-tokens/table", get(api_tokens_table_handler))
+        .route("/admin/api-tokens/{id}", delete(revoke_api_token_handler))
+        .with_state(state)
+}
@@ -0,0 +1,81 @@
+use crate::audit::web::AuditState;
+use crate::audit::{AuditAction, AuditEntry, AuditService, AuditServiceError, NameValues};
+use api_error::{ApiError, ProblemDetails};
+use axum::{Router, extract::State, response::Json, routing::get};
+use pagination::{PageParams, Paginated};
+use serde::Serialize;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+/// JSON representation of an audit log entry for API responses.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditEntryJson {
+    /// Unique identifier for the entry
+    #[schema(value_type = u32)]
+    id: crate::audit::AuditEntryId,
+    /// Who made the change: a username, `token:` and an API token's name, or `system`
+    actor: String,
+    /// What was done to the name
+    action: AuditAction,
+    /// ID of the name that was changed, which may not exist anymore
+    #[schema(value_type = u32)]
+    name_id: crate::name::NameId,
+    /// The name before the change, missing if it was created or restored
+    before: Option<NameValues>,
+    /// The name after the change, missing if it was deleted or purged
+    after: Option<NameValues>,
+    /// When the change was made, in RFC 3339 format
+    created_at: String,
+}
+
+impl From<AuditEntry> for AuditEntryJson {
+    fn from(entry: AuditEntry) -> Self {
+        Self {
+            id: entry.id(),
+            actor: entry.actor().to_string(),
+            action: entry.action(),
+            name_id: entry.name_id(),
+            before: entry.before().cloned(),
+            after: entry.after().cloned(),
+            created_at: entry.created_at().to_rfc3339(),
+        }
+    }
+}
+
+impl From<AuditServiceError> for ApiError {
+    fn from(err: AuditServiceError) -> Self {
+        match err {
+            AuditServiceError::Database(err) => ApiError::from(err),
+            err => ApiError::internal("Failed to read the audit log").with_source(err),
+        }
+    }
+}
+
+/// Handler for GET /api/v1/audit - Returns a page of the audit log, newest entries first.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+    get,
+    path = "/api/v1/audit",
+    params(PageParams),
+    responses(
+        (status = 200, description = "Successfully retrieved the audit log", body = Paginated<AuditEntryJson>),
+        (status = 400, description = "Invalid pagination parameters", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Only admins can read the audit log", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    tag = "Audit"
+)]
+pub async fn get_audit_log_handler(
+    State(state): State<Arc<AuditState>>,
+    page: PageParams,
+) -> Result<Json<Paginated<AuditEntryJson>>, ApiError> {
+    let entries = AuditService::new(&state.db).list(page).await?;
+    Ok(Json(entries.map(AuditEntryJson::from)))
+}
+
+/// Creates the API router for the audit log.
+pub fn create_api_router(state: Arc<AuditState>) -> Router {
+    Router::new()
+        .route("/audit", get(get_audit_log_handler))
+        .with_state(state)
+}
@@ -0,0 +1,292 @@
+//! The audit log, which records who changed which name when, and what it was before and after.
+//! Entries are written by [`NameService`](crate::name::NameService) in the same transaction as
+//! the change they record, so that no change goes unrecorded.
+
+use crate::auth::CurrentUser;
+use crate::entities::*;
+use crate::name::{Name, NameId};
+use pagination::{PageParams, Paginated};
+use sea_orm::prelude::{DateTimeWithTimeZone, Json};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use typed_ids::{DiscordId, EntityId};
+
+pub mod api;
+pub mod web;
+
+/// The ID of an audit log entry.
+pub type AuditEntryId = EntityId<audit_log::Entity>;
+
+/// The actor of changes the server makes on its own, without a user asking for them.
+pub const SYSTEM_ACTOR: &str = "system";
+
+/// Returns who to attribute the changes of a request to: its user, or the server itself.
+pub fn actor(user: Option<&CurrentUser>) -> &str {
+    user.map_or(SYSTEM_ACTOR, |user| user.username.as_str())
+}
+
+/// What was done to a name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+    /// Created as one of many, by a bulk add
+    BulkCreate,
+    /// Deleted as one of many, by a bulk delete
+    BulkDelete,
+    /// Moved to another server as one of many, by a bulk move
+    BulkUpdate,
+    /// Moved to the trash as a duplicate of another name, by a merge
+    Merge,
+    /// Taken back out of the trash
+    Restore,
+    /// Removed for good, after it was in the trash for too long
+    Purge,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+            AuditAction::BulkCreate => "bulk_create",
+            AuditAction::BulkDelete => "bulk_delete",
+            AuditAction::BulkUpdate => "bulk_update",
+            AuditAction::Merge => "merge",
+            AuditAction::Restore => "restore",
+            AuditAction::Purge => "purge",
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = AuditServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "create" => Ok(AuditAction::Create),
+            "update" => Ok(AuditAction::Update),
+            "delete" => Ok(AuditAction::Delete),
+            "bulk_create" => Ok(AuditAction::BulkCreate),
+            "bulk_delete" => Ok(AuditAction::BulkDelete),
+            "bulk_update" => Ok(AuditAction::BulkUpdate),
+            "merge" => Ok(AuditAction::Merge),
+            "restore" => Ok(AuditAction::Restore),
+            "purge" => Ok(AuditAction::Purge),
+            other => Err(AuditServiceError::UnknownAction(other.to_string())),
+        }
+    }
+}
+
+/// The values of a name at one point in time.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct NameValues {
+    /// Discord user ID the name belongs to
+    #[schema(value_type = u64)]
+    pub discord_id: DiscordId,
+    /// The actual name/nickname
+    pub name: String,
+    /// Server ID the name is used in
+    pub server_id: String,
+}
+
+impl From<&Name> for NameValues {
+    fn from(name: &Name) -> Self {
+        Self {
+            discord_id: name.discord_id(),
+            name: name.name().to_string(),
+            server_id: name.server_id().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Eq)]
+pub struct AuditEntry {
+    id: AuditEntryId,
+    actor: String,
+    action: AuditAction,
+    name_id: NameId,
+    before: Option<NameValues>,
+    after: Option<NameValues>,
+    created_at: DateTimeWithTimeZone,
+}
+
+impl AuditEntry {
+    /// Returns the ID of the entry.
+    pub fn id(&self) -> AuditEntryId {
+        self.id
+    }
+
+    /// Returns who made the change: a username, `token:` and an API token's name, or
+    /// [`SYSTEM_ACTOR`].
+    pub fn actor(&self) -> &str {
+        &self.actor
+    }
+
+    /// Returns what was done.
+    pub fn action(&self) -> AuditAction {
+        self.action
+    }
+
+    /// Returns the ID of the name that was changed, which may not exist anymore.
+    pub fn name_id(&self) -> NameId {
+        self.name_id
+    }
+
+    /// Returns the name before the change, `None` if it was created or restored.
+    pub fn before(&self) -> Option<&NameValues> {
+        self.before.as_ref()
+    }
+
+    /// Returns the name after the change, `None` if it was deleted or purged.
+    pub fn after(&self) -> Option<&NameValues> {
+        self.after.as_ref()
+    }
+
+    /// Returns when the change was made.
+    pub fn created_at(&self) -> DateTimeWithTimeZone {
+        self.created_at
+    }
+}
+
+impl TryFrom<audit_log::Model> for AuditEntry {
+    type Error = AuditServiceError;
+
+    fn try_from(model: audit_log::Model) -> Result<Self, Self::Error> {
+        let values = |json: Option<Json>| json.map(serde_json::from_value).transpose();
+        Ok(Self {
+            id: model.id,
+            actor: model.actor,
+            action: model.action.parse()?,
+            name_id: model.name_id,
+            before: values(model.before)?,
+            after: values(model.after)?,
+            created_at: model.created_at,
+        })
+    }
+}
+
+/// Error type for AuditService operations.
+#[derive(Debug, thiserror::Error)]
+pub enum AuditServiceError {
+    /// Represents a database error.
+    #[error("Database error: {0}")]
+    Database(#[from] sea_orm::DbErr),
+    /// Represents a stored action this version doesn't know.
+    #[error("Unknown audit action '{0}'")]
+    UnknownAction(String),
+    /// Represents stored name values that can't be read.
+    #[error("Malformed name values: {0}")]
+    MalformedValues(#[from] serde_json::Error),
+}
+
+pub struct AuditService<'a> {
+    db: &'a sea_orm::DatabaseConnection,
+}
+
+impl AuditService<'_> {
+    pub fn new(db: &sea_orm::DatabaseConnection) -> AuditService<'_> {
+        AuditService { db }
+    }
+
+    /// Retrieves one page of the audit log, newest entries first.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - The page to return.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the requested page of `AuditEntry` if successful, or an error
+    /// otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn list(&self, page: PageParams) -> Result<Paginated<AuditEntry>, AuditServiceError> {
+        let select = audit_log::Entity::find().order_by_desc(audit_log::Column::Id);
+        let models = pagination::paginate(select, page, self.db).await?;
+        let entries = models
+            .items
+            .into_iter()
+            .map(AuditEntry::try_from)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Paginated::new(entries, page, models.total))
+    }
+}
+
+/// Records a change to a name. Pass the transaction the change is made in, so that the entry is
+/// only kept if the change is.
+///
+/// # Arguments
+///
+/// * `db` - The connection or transaction to write the entry with.
+/// * `actor` - Who made the change.
+/// * `action` - What was done.
+/// * `before` - The name before the change, `None` if it was created or restored.
+/// * `after` - The name after the change, `None` if it was deleted or purged.
+pub(crate) async fn record<C: ConnectionTrait>(
+    db: &C,
+    actor: &str,
+    action: AuditAction,
+    before: Option<&Name>,
+    after: Option<&Name>,
+) -> Result<(), DbErr> {
+    let Some(name_id) = after.or(before).map(Name::id) else {
+        return Ok(());
+    };
+    let values = |name: Option<&Name>| {
+        name.map(|name| serde_json::to_value(NameValues::from(name)))
+            .transpose()
+            .map_err(|err| DbErr::Custom(err.to_string()))
+    };
+
+    let active_model = audit_log::ActiveModel {
+        actor: ActiveValue::Set(actor.to_string()),
+        action: ActiveValue::Set(action.to_string()),
+        name_id: ActiveValue::Set(name_id),
+        before: ActiveValue::Set(values(before)?),
+        after: ActiveValue::Set(values(after)?),
+        ..Default::default()
+    };
+    active_model.insert(db).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actions_round_trip_through_strings() {
+        for action in [
+            AuditAction::Create,
+            AuditAction::Update,
+            AuditAction::Delete,
+            AuditAction::BulkCreate,
+            AuditAction::BulkDelete,
+            AuditAction::BulkUpdate,
+            AuditAction::Merge,
+            AuditAction::Restore,
+            AuditAction::Purge,
+        ] {
+            assert_eq!(action.to_string().parse::<AuditAction>().unwrap(), action);
+        }
+        assert!("rename".parse::<AuditAction>().is_err());
+    }
+
+    #[test]
+    fn changes_are_attributed_to_the_system_without_a_user() {
+        let user = CurrentUser::new("alice".to_string());
+        assert_eq!(actor(Some(&user)), "alice");
+        assert_eq!(actor(None), SYSTEM_ACTOR);
+    }
+}
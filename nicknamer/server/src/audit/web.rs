@@ -0,0 +1,67 @@
+use askama::Template;
+use axum::{
+    Router,
+    extract::State,
+    http::StatusCode,
+    response::{Html, IntoResponse, Response},
+    routing::get,
+};
+use pagination::{PageParams, Paginated};
+use std::sync::Arc;
+
+use crate::audit::{AuditEntry, AuditService, AuditServiceError};
+
+#[derive(Clone, Debug)]
+pub struct AuditState {
+    pub db: Arc<sea_orm::DatabaseConnection>,
+}
+
+/// Custom error type for audit log handler operations.
+#[derive(Debug, thiserror::Error)]
+enum AuditError {
+    /// Represents an error during template rendering.
+    #[error("Template rendering failed")]
+    Template(#[from] askama::Error),
+    /// Represents an audit service error.
+    #[error("Audit service error")]
+    Service(#[from] AuditServiceError),
+}
+
+impl IntoResponse for AuditError {
+    fn into_response(self) -> Response {
+        let user_facing_error_message =
+            "An unexpected error occurred while processing your request. Please try again later.";
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Html(format!(
+                "<h1>Internal Server Error</h1><p>{}</p>",
+                user_facing_error_message
+            )),
+        )
+            .into_response()
+    }
+}
+
+#[derive(Template)]
+#[template(path = "audit.html")]
+struct AuditTemplate {
+    entries: Paginated<AuditEntry>,
+}
+
+/// Handler for GET /audit that displays a page of the audit log, newest entries first.
+#[tracing::instrument(skip(state))]
+async fn audit_handler(
+    State(state): State<Arc<AuditState>>,
+    page: PageParams,
+) -> Result<Html<String>, AuditError> {
+    let entries = AuditService::new(&state.db).list(page).await?;
+    let template = AuditTemplate { entries };
+    template.render().map(Html).map_err(AuditError::from)
+}
+
+/// Creates and returns the router of the audit log page.
+pub fn create_audit_router(state: Arc<AuditState>) -> Router {
+    Router::new()
+        .route("/audit", get(audit_handler))
+        .with_state(state)
+}
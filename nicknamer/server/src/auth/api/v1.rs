@@ -11,44 +11,128 @@ pub struct LoginResponse {
     pub token: String,
 }
 
-use crate::auth::{AuthState, CurrentUser, decode_jwt, encode_jwt};
-use crate::web::api::v1::ServerErrorResponse;
+use crate::api_token::{ApiTokenService, TOKEN_PREFIX, web::ApiTokenState};
+use crate::auth::{AuthState, CurrentUser, has_role, load_role};
+use crate::user::{Role, UserService};
+use api_error::{ApiError, ProblemDetails};
 use axum::{
     Json, Router,
     extract::{Request, State},
-    http::{HeaderMap, StatusCode},
+    http::HeaderMap,
     middleware::Next,
     response::{IntoResponse, Response},
 };
+use axum_extra::extract::WithRejection;
 use std::sync::Arc;
 use utoipa::ToSchema;
 
 /// Creates a JSON API router for authentication endpoints.
 pub fn create_api_router(state: Arc<AuthState>) -> Router<()> {
     Router::new()
-        .route("/login", axum::routing::post(json_login_handler))
+        .route(
+            "/login",
+            axum::routing::post(json_login_handler).layer(state.rate_limits.login()),
+        )
         .with_state(state)
 }
 
 /// API authentication middleware that extracts the current user from Authorization Bearer header.
 /// Sets the CurrentUser extension if a valid JWT token is found in the Authorization header.
 pub async fn auth_user_middleware(
-    State(state): State<Arc<AuthState>>,
+    state: State<Arc<AuthState>>,
+    headers: HeaderMap,
+    request: Request,
+    next: Next,
+) -> Response {
+    web_auth::bearer_auth_middleware(state, headers, request, next).await
+}
+
+/// API token middleware that authenticates machine clients by the API token in the
+/// Authorization Bearer header, setting the CurrentUser extension the same way a JWT does.
+/// Requests a read-only token isn't allowed to make are rejected with FORBIDDEN, unknown and
+/// revoked tokens are left for require_auth_middleware to reject.
+pub async fn api_token_middleware(
+    State(state): State<Arc<ApiTokenState>>,
     headers: HeaderMap,
     mut request: Request,
     next: Next,
 ) -> Response {
-    if let Some(auth_header) = headers.get("authorization") {
-        if let Ok(auth_str) = auth_header.to_str() {
-            if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                if let Ok(claims) = decode_jwt(token, &state.jwt_secret).await {
-                    let current_user = CurrentUser::new(claims.username);
-                    request.extensions_mut().insert(current_user);
-                }
-            }
+    let Some(secret) = web_auth::bearer_token(&headers).filter(|t| t.starts_with(TOKEN_PREFIX))
+    else {
+        return next.run(request).await;
+    };
+
+    let token = match ApiTokenService::new(&state.db).authenticate(secret).await {
+        Ok(Some(token)) => token,
+        Ok(None) => return next.run(request).await,
+        Err(err) => {
+            return ApiError::internal("Failed to check the API token")
+                .with_source(err)
+                .into_response();
         }
+    };
+    if !token.scope().allows(request.method()) {
+        return ApiError::forbidden(format!(
+            "The API token '{}' is {} and can't make {} requests",
+            token.name(),
+            token.scope(),
+            request.method()
+        ))
+        .into_response();
+    }
+
+    request
+        .extensions_mut()
+        .insert(CurrentUser::new(token.username()));
+    request.extensions_mut().insert(Role::from(token.scope()));
+    next.run(request).await
+}
+
+/// API role middleware that looks up the account of the CurrentUser and sets its Role
+/// extension, unless an API token set it already. A user whose account was deleted since the
+/// token was issued is treated as unauthenticated.
+/// This middleware should be applied after auth_user_middleware and api_token_middleware.
+pub async fn user_role_middleware(
+    State(state): State<Arc<AuthState>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if let Err(err) = load_role(&state.db, &mut request).await {
+        return ApiError::internal("Failed to look up the current user")
+            .with_source(err)
+            .into_response();
+    }
+    next.run(request).await
+}
+
+/// Middleware that only lets users with at least the `required` role through.
+/// Returns FORBIDDEN for everyone else.
+/// This middleware should be applied after require_auth_middleware.
+pub async fn require_role_middleware(
+    State(required): State<Role>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !has_role(&request, required) {
+        return ApiError::forbidden(format!("Only {} users can access this resource", required))
+            .into_response();
     }
+    next.run(request).await
+}
 
+/// Middleware for the names endpoints that lets viewers read names, but only editors change
+/// them. Returns FORBIDDEN for requests the user's role doesn't allow.
+/// This middleware should be applied after require_auth_middleware.
+pub async fn require_editor_for_changes_middleware(request: Request, next: Next) -> Response {
+    let required = Role::required_for(request.method());
+    if !has_role(&request, required) {
+        return ApiError::forbidden(format!(
+            "Only {} users can make {} requests",
+            required,
+            request.method()
+        ))
+        .into_response();
+    }
     next.run(request).await
 }
 
@@ -60,11 +144,8 @@ pub async fn require_auth_middleware(request: Request, next: Next) -> Response {
     let is_authenticated = request.extensions().get::<CurrentUser>().is_some();
 
     if !is_authenticated {
-        let error_response = ServerErrorResponse::new_with_message(
-            "UNAUTHORIZED".to_string(),
-            "Authentication required to access this resource".to_string(),
-        );
-        return (StatusCode::UNAUTHORIZED, Json(error_response)).into_response();
+        return ApiError::unauthorized("Authentication required to access this resource")
+            .into_response();
     }
 
     next.run(request).await
@@ -79,39 +160,33 @@ pub async fn require_auth_middleware(request: Request, next: Next) -> Response {
     request_body = JsonLoginRequest,
     responses(
         (status = 200, description = "Successful login", body = LoginResponse),
-        (status = 401, description = "Invalid credentials", body = ServerErrorResponse),
-        (status = 500, description = "Internal server error", body = ServerErrorResponse)
+        (status = 400, description = "Malformed request body", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Invalid credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 429, description = "Too many login attempts", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
     ),
+    security(()),
     tag = "Authentication"
 )]
 pub async fn json_login_handler(
     State(state): State<Arc<AuthState>>,
-    Json(payload): Json<JsonLoginRequest>,
-) -> Result<Json<LoginResponse>, (StatusCode, Json<ServerErrorResponse>)> {
-    if payload.username == state.admin_username && payload.password == state.admin_password {
+    WithRejection(Json(payload), _): WithRejection<Json<JsonLoginRequest>, ApiError>,
+) -> Result<Json<LoginResponse>, ApiError> {
+    let user = UserService::new(&state.db)
+        .authenticate(&payload.username, &payload.password)
+        .await
+        .map_err(|err| ApiError::internal("Failed to check the credentials").with_source(err))?;
+    if let Some(user) = user {
         // Generate JWT token
-        let jwt_token = encode_jwt(payload.username.clone(), &state.jwt_secret)
-            .await
-            .map_err(|_| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ServerErrorResponse::new_with_message(
-                        "JWT_ERROR".to_string(),
-                        "Failed to generate authentication token".to_string(),
-                    )),
-                )
-            })?;
+        let jwt_token = state.jwt.encode(user.username()).map_err(|err| {
+            ApiError::internal("Failed to generate authentication token").with_source(err)
+        })?;
 
         let response = LoginResponse { token: jwt_token };
 
         Ok(Json(response))
     } else {
-        Err((
-            StatusCode::UNAUTHORIZED,
-            Json(ServerErrorResponse::new_with_message(
-                "INVALID_CREDENTIALS".to_string(),
-                "Invalid username or password".to_string(),
-            )),
-        ))
+        Err(ApiError::unauthorized("Invalid username or password")
+            .with_type("/problems/invalid-credentials"))
     }
 }
@@ -5,80 +5,151 @@ use axum::http::{HeaderMap, HeaderName, HeaderValue};
 use axum::middleware::Next;
 use axum::response::{Html, IntoResponse, Response};
 use axum_extra::extract::CookieJar;
-use jsonwebtoken::encode;
 use std::sync::Arc;
+use web_auth::{AuthProvider, Jwt};
 
+use crate::auth::oauth::DiscordOAuth;
 use crate::config::Config;
+use crate::rate_limits::RateLimits;
+use crate::user::{Role, UserService, UserServiceError};
 
-/// Represents the currently authenticated user.
-#[derive(Debug, Clone)]
-pub struct CurrentUser {
-    pub username: String,
-}
-
-impl CurrentUser {
-    /// Creates a new CurrentUser instance.
-    pub fn new(username: String) -> Self {
-        Self { username }
-    }
-}
+pub use web_auth::{Claims, CurrentUser, login_redirect_middleware};
 
-/// Authentication state containing admin credentials and JWT secret.
+/// Authentication state containing the JWT issuer, the database the users are stored in and the
+/// Discord application users can sign in with, if any.
 #[derive(Clone)]
 pub struct AuthState {
-    pub admin_username: String,
-    pub admin_password: String,
-    pub jwt_secret: String,
+    pub jwt: Jwt,
+    pub db: Arc<sea_orm::DatabaseConnection>,
+    pub discord: Option<DiscordOAuth>,
+    pub rate_limits: RateLimits,
 }
 
 impl AuthState {
     /// Creates a new AuthState from the application config.
-    pub fn from_config(config: &Config) -> Self {
+    pub fn from_config(config: &Config, db: Arc<sea_orm::DatabaseConnection>) -> Self {
         Self {
-            admin_username: config.admin_username.clone(),
-            admin_password: config.admin_password.clone(),
-            jwt_secret: config.jwt_secret.clone(),
+            jwt: Jwt::new(config.jwt_secret.expose().clone()),
+            db,
+            discord: DiscordOAuth::from_config(config),
+            rate_limits: RateLimits::from_config(config),
         }
     }
+
+    /// Lets users sign in with the `discord` application, instead of the configured one.
+    pub fn with_discord(mut self, discord: DiscordOAuth) -> Self {
+        self.discord = Some(discord);
+        self
+    }
+}
+
+impl AuthProvider for AuthState {
+    type Claims = Claims;
+
+    fn jwt(&self) -> &Jwt {
+        &self.jwt
+    }
 }
 
 /// Creates a login router with authentication routes.
 pub fn create_login_router(state: Arc<AuthState>) -> Router<()> {
     Router::new()
-        .route("/login", axum::routing::post(login_handler))
+        .route(
+            "/login",
+            axum::routing::post(login_handler).layer(state.rate_limits.login()),
+        )
         .route("/login", axum::routing::get(login_page_handler))
+        .route(
+            "/login/discord",
+            axum::routing::get(oauth::discord_login_handler),
+        )
+        .route(
+            "/login/discord/callback",
+            axum::routing::get(oauth::discord_callback_handler).layer(state.rate_limits.login()),
+        )
         .with_state(state)
 }
 
-/// Authentication middleware that checks for valid JWT tokens and sets CurrentUser extension.
-/// This middleware only populates the CurrentUser extension and does not perform redirects.
+/// Authentication middleware that checks the auth cookie for a valid JWT token and sets the
+/// CurrentUser extension. This middleware does not perform redirects.
 pub async fn auth_user_middleware(
-    State(state): State<Arc<AuthState>>,
+    state: State<Arc<AuthState>>,
     jar: CookieJar,
+    request: Request,
+    next: Next,
+) -> Response {
+    web_auth::cookie_auth_middleware(state, jar, request, next).await
+}
+
+/// Role middleware that looks up the account of the CurrentUser and sets its Role extension.
+/// A user whose account was deleted since they logged in is treated as logged out.
+/// This middleware should be applied after auth_user_middleware.
+pub async fn user_role_middleware(
+    State(state): State<Arc<AuthState>>,
     mut request: Request,
     next: Next,
 ) -> Response {
-    if let Some(token_cookie) = jar.get("auth_token") {
-        if let Ok(claims) = decode_jwt(token_cookie.value(), &state.jwt_secret).await {
-            let current_user = CurrentUser::new(claims.username);
-            request.extensions_mut().insert(current_user);
-        }
+    if let Err(err) = load_role(&state.db, &mut request).await {
+        tracing::error!("Failed to look up the role of the current user: {}", err);
+        return AuthError::from(err).into_response();
     }
-
     next.run(request).await
 }
 
-/// Login redirect middleware that redirects unauthenticated users to the login page.
-/// This middleware should be applied after auth_user_middleware to check for CurrentUser extension.
-pub async fn login_redirect_middleware(request: Request, next: Next) -> Response {
-    // Check if user is authenticated by looking for CurrentUser extension
-    let is_authenticated = request.extensions().get::<CurrentUser>().is_some();
+/// Sets the Role extension of a request from the account of its CurrentUser, unless an API
+/// token set it already, and removes the CurrentUser if the account doesn't exist anymore.
+pub(crate) async fn load_role(
+    db: &sea_orm::DatabaseConnection,
+    request: &mut Request,
+) -> Result<(), UserServiceError> {
+    if request.extensions().get::<Role>().is_some() {
+        return Ok(());
+    }
+    let Some(current_user) = request.extensions().get::<CurrentUser>() else {
+        return Ok(());
+    };
+
+    match UserService::new(db)
+        .find_by_username(&current_user.username)
+        .await?
+    {
+        Some(user) => {
+            request.extensions_mut().insert(user.role());
+        }
+        None => {
+            request.extensions_mut().remove::<CurrentUser>();
+        }
+    }
+    Ok(())
+}
 
-    // If no valid authentication and accessing a protected route, redirect to login
-    if !is_authenticated {
-        return axum::response::Redirect::to("/login").into_response();
+/// Whether the Role extension of a request includes `required`. Requests without one never do.
+pub(crate) fn has_role(request: &Request, required: Role) -> bool {
+    request
+        .extensions()
+        .get::<Role>()
+        .is_some_and(|role| role.includes(required))
+}
+
+/// Middleware that only lets users with at least the `required` role through, answering
+/// everyone else with FORBIDDEN. This middleware should be applied after user_role_middleware.
+pub async fn require_role_middleware(
+    State(required): State<Role>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !has_role(&request, required) {
+        return AuthError::Forbidden.into_response();
     }
+    next.run(request).await
+}
 
+/// Middleware for the names routes that lets viewers read names, but only editors change them.
+/// This middleware should be applied after user_role_middleware.
+pub async fn require_editor_for_changes_middleware(request: Request, next: Next) -> Response {
+    if !has_role(&request, Role::required_for(request.method())) {
+        return AuthError::Forbidden.into_response();
+    }
     next.run(request).await
 }
 
@@ -89,12 +160,6 @@ pub struct LoginRequest {
     pub password: String,
 }
 
-#[derive(serde::Serialize, serde::Deserialize, Debug)]
-pub struct Claims {
-    pub exp: usize,       // Expiry time of the token
-    pub iat: usize,       // Issued at time of the token
-    pub username: String, // Username of the authenticated user
-}
 /// Custom error type for authentication operations.
 #[derive(Debug, thiserror::Error)]
 pub enum AuthError {
@@ -106,10 +171,26 @@ pub enum AuthError {
     /// The specific `jsonwebtoken::errors::Error` is captured as the source of this error.
     #[error("JWT operation failed")]
     JwtError,
+    /// Represents an error looking up a user.
+    #[error("User lookup failed")]
+    Users(#[from] UserServiceError),
+    /// Represents a user whose role doesn't allow the request.
+    #[error("The current user's role doesn't allow this request")]
+    Forbidden,
 }
 
 impl axum::response::IntoResponse for AuthError {
     fn into_response(self) -> axum::response::Response {
+        if let AuthError::Forbidden = self {
+            return (
+                axum::http::StatusCode::FORBIDDEN,
+                Html(
+                    "<h1>Forbidden</h1><p>Your role doesn't allow this. Ask an admin if you need it.</p>",
+                ),
+            )
+                .into_response();
+        }
+
         let user_facing_error_message =
             "An unexpected error occurred while processing your request. Please try again later.";
         (
@@ -124,7 +205,7 @@ impl axum::response::IntoResponse for AuthError {
 }
 
 /// Handles the login request.
-/// Checks submitted username and password against admin credentials.
+/// Checks submitted username and password against the registered users.
 /// If a user is already logged in, returns a success message.
 pub async fn login_handler(
     State(state): State<Arc<AuthState>>,
@@ -164,25 +245,21 @@ async fn handle_login_attempt(
     jar: CookieJar,
     payload: LoginRequest,
 ) -> Result<(CookieJar, Response), AuthError> {
-    if payload.username == state.admin_username && payload.password == state.admin_password {
+    let user = UserService::new(&state.db)
+        .authenticate(&payload.username, &payload.password)
+        .await?;
+    if let Some(user) = user {
         // Generate JWT token
-        let jwt_token = encode_jwt(payload.username.clone(), &state.jwt_secret)
-            .await
+        let jwt_token = state
+            .jwt
+            .encode(user.username())
             .map_err(|_| AuthError::JwtError)?;
 
         // Create cookie with JWT token
-        let cookie = axum_extra::extract::cookie::Cookie::build(("auth_token", jwt_token))
-            .http_only(true)
-            .secure(false) // Set to true in production with HTTPS
-            .same_site(axum_extra::extract::cookie::SameSite::Lax)
-            .max_age(time::Duration::hours(24))
-            .path("/")
-            .build();
-
-        let updated_jar = jar.add(cookie);
+        let updated_jar = jar.add(web_auth::auth_cookie(jwt_token, state.jwt.ttl()));
 
         let html = LoginSuccessTemplate {
-            name: &payload.username,
+            name: user.username(),
         }
         .render()
         .map_err(AuthError::from)?;
@@ -209,30 +286,6 @@ async fn handle_login_attempt(
     }
 }
 
-pub async fn encode_jwt(username: String, jwt_secret: &str) -> anyhow::Result<String> {
-    let now = chrono::Utc::now();
-    let expire = chrono::Duration::hours(24);
-    let exp = (now + expire).timestamp() as usize;
-    let iat = now.timestamp() as usize;
-    let claims = Claims { exp, iat, username };
-    let jwt = encode(
-        &jsonwebtoken::Header::default(),
-        &claims,
-        &jsonwebtoken::EncodingKey::from_secret(jwt_secret.as_bytes()),
-    )?;
-    Ok(jwt)
-}
-
-#[allow(dead_code)]
-pub async fn decode_jwt(token: &str, jwt_secret: &str) -> anyhow::Result<Claims> {
-    let token_data = jsonwebtoken::decode(
-        token,
-        &jsonwebtoken::DecodingKey::from_secret(jwt_secret.as_bytes()),
-        &jsonwebtoken::Validation::default(),
-    )?;
-    Ok(token_data.claims)
-}
-
 #[derive(Template)]
 #[template(path = "login/login_success.html")]
 pub struct LoginSuccessTemplate<'a> {
@@ -247,16 +300,25 @@ pub struct LoginErrorMessageTemplate;
 #[template(path = "login.html")]
 pub struct LoginTemplate {
     pub username: Option<String>,
+    /// Whether to offer signing in with Discord
+    pub discord_login: bool,
+    /// Why the last sign in failed
+    pub error: Option<String>,
 }
 
 /// Handles GET requests to display the login page.
-#[tracing::instrument]
+#[tracing::instrument(skip(state))]
 pub async fn login_page_handler(
+    State(state): State<Arc<AuthState>>,
     current_user: Option<Extension<CurrentUser>>,
 ) -> Result<Html<String>, AuthError> {
     let username = current_user.map(|Extension(user)| user.username);
 
-    let template = LoginTemplate { username };
+    let template = LoginTemplate {
+        username,
+        discord_login: state.discord.is_some(),
+        error: None,
+    };
     template.render().map(Html).map_err(AuthError::from)
 }
 
@@ -273,14 +335,27 @@ mod tests {
         use tower::ServiceExt;
 
         let config = Config {
-            db_url: "".to_string(),
+            db_url: "".into(),
             port: 8080,
             admin_username: "admin".to_string(),
-            admin_password: "password".to_string(),
-            jwt_secret: "test_secret".to_string(),
+            admin_password: "password".into(),
+            jwt_secret: "test_secret".into(),
+            trash_retention_days: 30,
+            name_cache_ttl_seconds: 60,
+            discord_client_id: None,
+            discord_client_secret: None,
+            discord_redirect_url: None,
+            login_attempts_per_minute: 5,
+            api_ip_requests_per_minute: 300,
+            api_user_requests_per_second: 10,
+            api_user_burst: 20,
+            trusted_proxies: 0,
         };
 
-        let auth_state = Arc::new(AuthState::from_config(&config));
+        let auth_state = Arc::new(AuthState::from_config(
+            &config,
+            Arc::new(Default::default()),
+        ));
 
         // Create a test app with both middlewares in the correct order
         // Note: Layers are applied in reverse order (bottom to top)
@@ -310,9 +385,7 @@ mod tests {
         assert_eq!(location, "/login");
 
         // Test 2: Authenticated request should allow access
-        let jwt_token = encode_jwt("admin".to_string(), &config.jwt_secret)
-            .await
-            .unwrap();
+        let jwt_token = auth_state.jwt.encode("admin").unwrap();
 
         let response = app
             .oneshot(
@@ -335,3 +408,4 @@ mod tests {
 }
 
 pub mod api;
+pub mod oauth;
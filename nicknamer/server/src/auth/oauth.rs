@@ -0,0 +1,305 @@
+//! Signing in with Discord, using its OAuth2 authorization code flow. Discord only tells us who
+//! someone is: they are logged in as the user their Discord ID is linked to, with that user's
+//! role, and get the same JWT cookie as when logging in with a password.
+
+use askama::Template;
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{Html, IntoResponse, Redirect, Response};
+use axum_extra::extract::CookieJar;
+use axum_extra::extract::cookie::{Cookie, SameSite};
+use config_core::Secret;
+use serde::Deserialize;
+use std::sync::Arc;
+use typed_ids::DiscordId;
+
+use crate::auth::{AuthState, LoginTemplate};
+use crate::config::Config;
+use crate::user::{UserService, UserServiceError};
+
+/// Where users are sent to allow the Discord application to see who they are.
+pub const DISCORD_AUTHORIZE_URL: &str = "https://discord.com/oauth2/authorize";
+/// The Discord API the code is exchanged and the user looked up with.
+pub const DISCORD_API_URL: &str = "https://discord.com/api/v10";
+
+/// The cookie that remembers the state a sign in was started with, until Discord sends the user
+/// back with it.
+const STATE_COOKIE: &str = "discord_oauth_state";
+/// The path of the sign in routes, the only ones the state cookie is sent to.
+const LOGIN_PATH: &str = "/login/discord";
+
+/// A Discord application users can sign in with.
+#[derive(Clone, Debug)]
+pub struct DiscordOAuth {
+    client_id: String,
+    client_secret: Secret,
+    redirect_url: String,
+    api_url: String,
+    http: reqwest::Client,
+}
+
+impl DiscordOAuth {
+    pub fn new(client_id: String, client_secret: Secret, redirect_url: String) -> Self {
+        Self {
+            client_id,
+            client_secret,
+            redirect_url,
+            api_url: DISCORD_API_URL.to_string(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Creates the Discord application from the config, `None` if signing in with Discord isn't
+    /// configured.
+    pub fn from_config(config: &Config) -> Option<Self> {
+        Some(Self::new(
+            config.discord_client_id.clone()?,
+            config.discord_client_secret.clone()?,
+            config.discord_redirect_url.clone()?,
+        ))
+    }
+
+    /// Talks to the Discord API at `api_url` instead of Discord's, e.g. a fake one in tests.
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into();
+        self
+    }
+
+    /// Returns the URL to send users to, to sign in with Discord. Discord sends them back to the
+    /// redirect URL with a code and `state`.
+    pub fn authorize_url(&self, state: &str) -> String {
+        reqwest::Url::parse_with_params(
+            DISCORD_AUTHORIZE_URL,
+            [
+                ("response_type", "code"),
+                ("client_id", self.client_id.as_str()),
+                ("scope", "identify"),
+                ("redirect_uri", self.redirect_url.as_str()),
+                ("state", state),
+                ("prompt", "none"),
+            ],
+        )
+        .expect("the Discord authorize URL is valid")
+        .into()
+    }
+
+    /// Exchanges the code Discord sent the user back with for an access token, and looks up who
+    /// the token belongs to.
+    #[tracing::instrument(skip_all)]
+    async fn identify(&self, code: &str) -> Result<DiscordUser, OAuthError> {
+        let token: TokenResponse = self
+            .http
+            .post(format!("{}/oauth2/token", self.api_url))
+            .basic_auth(&self.client_id, Some(self.client_secret.expose()))
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", self.redirect_url.as_str()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        let user = self
+            .http
+            .get(format!("{}/users/@me", self.api_url))
+            .bearer_auth(&token.access_token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(user)
+    }
+}
+
+/// The part of Discord's token response we need.
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+/// The part of a Discord user we need.
+#[derive(Deserialize)]
+struct DiscordUser {
+    /// The user's snowflake, as a string
+    id: String,
+    username: String,
+}
+
+/// What Discord sends users back to the redirect URL with.
+#[derive(Debug, Deserialize)]
+pub struct CallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    /// Set instead of the code if the user didn't allow the sign in
+    error: Option<String>,
+}
+
+/// Custom error type for signing in with Discord.
+#[derive(Debug, thiserror::Error)]
+pub enum OAuthError {
+    /// Represents a request to sign in with Discord while it isn't configured.
+    #[error("Signing in with Discord isn't configured")]
+    Disabled,
+    /// Represents a callback without the state the sign in was started with.
+    #[error("The state doesn't match the one the sign in was started with")]
+    InvalidState,
+    /// Represents a user that didn't allow the sign in.
+    #[error("Discord didn't authorize the sign in: {0}")]
+    Denied(String),
+    /// Represents a failed request to Discord.
+    #[error("Request to Discord failed: {0}")]
+    Discord(#[from] reqwest::Error),
+    /// Represents a Discord user ID that isn't a snowflake.
+    #[error("Discord returned the invalid user ID '{0}'")]
+    InvalidDiscordId(String),
+    /// Represents a Discord user that isn't linked to any user.
+    #[error("The Discord user {0} isn't linked to a user")]
+    NotLinked(DiscordId),
+    /// Represents an error looking up a user.
+    #[error("User lookup failed")]
+    Users(#[from] UserServiceError),
+    /// Represents an error during JWT operations.
+    #[error("JWT operation failed")]
+    JwtError,
+}
+
+impl IntoResponse for OAuthError {
+    fn into_response(self) -> Response {
+        tracing::warn!("Signing in with Discord failed: {}", self);
+        let (status_code, user_facing_error_message) = match self {
+            OAuthError::Disabled => return StatusCode::NOT_FOUND.into_response(),
+            OAuthError::InvalidState | OAuthError::Denied(_) => (
+                StatusCode::BAD_REQUEST,
+                "Signing in with Discord was cancelled or has expired. Please try again.",
+            ),
+            OAuthError::Discord(_) | OAuthError::InvalidDiscordId(_) => (
+                StatusCode::BAD_GATEWAY,
+                "Discord couldn't confirm who you are. Please try again later.",
+            ),
+            OAuthError::NotLinked(_) => (
+                StatusCode::FORBIDDEN,
+                "Your Discord account isn't linked to a user. Ask an admin to link it.",
+            ),
+            OAuthError::Users(_) | OAuthError::JwtError => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "An unexpected error occurred while processing your request. Please try again later.",
+            ),
+        };
+
+        let template = LoginTemplate {
+            username: None,
+            discord_login: true,
+            error: Some(user_facing_error_message.to_string()),
+        };
+        match template.render() {
+            Ok(rendered) => (status_code, Html(rendered)).into_response(),
+            Err(_) => StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+        }
+    }
+}
+
+/// Handles GET /login/discord by sending the user to Discord, remembering a random state in a
+/// cookie to check when they come back.
+#[tracing::instrument(skip(state, jar))]
+pub async fn discord_login_handler(
+    State(state): State<Arc<AuthState>>,
+    jar: CookieJar,
+) -> Result<(CookieJar, Redirect), OAuthError> {
+    let discord = state.discord.as_ref().ok_or(OAuthError::Disabled)?;
+
+    let oauth_state = uuid::Uuid::new_v4().simple().to_string();
+    let redirect = Redirect::to(&discord.authorize_url(&oauth_state));
+    let cookie = Cookie::build((STATE_COOKIE, oauth_state))
+        .http_only(true)
+        .secure(false) // Set to true in production with HTTPS
+        .same_site(SameSite::Lax)
+        .path(LOGIN_PATH)
+        .build();
+    Ok((jar.add(cookie), redirect))
+}
+
+/// Handles GET /login/discord/callback, where Discord sends users back to. Logs them in as the
+/// user their Discord ID is linked to and sends them to the homepage.
+#[tracing::instrument(skip(state, jar, query))]
+pub async fn discord_callback_handler(
+    State(state): State<Arc<AuthState>>,
+    jar: CookieJar,
+    Query(query): Query<CallbackQuery>,
+) -> Result<(CookieJar, Redirect), OAuthError> {
+    let discord = state.discord.as_ref().ok_or(OAuthError::Disabled)?;
+
+    // The state can only be used once
+    let expected_state = jar
+        .get(STATE_COOKIE)
+        .map(|cookie| cookie.value().to_string());
+    let jar = jar.remove(Cookie::build(STATE_COOKIE).path(LOGIN_PATH));
+    if let Some(error) = query.error {
+        return Err(OAuthError::Denied(error));
+    }
+    let (Some(code), Some(expected_state)) = (query.code, expected_state) else {
+        return Err(OAuthError::InvalidState);
+    };
+    if query.state.as_deref() != Some(expected_state.as_str()) {
+        return Err(OAuthError::InvalidState);
+    }
+
+    let discord_user = discord.identify(&code).await?;
+    let discord_id: DiscordId = discord_user
+        .id
+        .parse()
+        .map_err(|_| OAuthError::InvalidDiscordId(discord_user.id.clone()))?;
+    let Some(user) = UserService::new(&state.db)
+        .find_by_discord_id(discord_id)
+        .await?
+    else {
+        tracing::warn!(
+            "Discord user {} ({}) isn't linked to a user",
+            discord_user.username,
+            discord_id
+        );
+        return Err(OAuthError::NotLinked(discord_id));
+    };
+
+    let jwt_token = state
+        .jwt
+        .encode(user.username())
+        .map_err(|_| OAuthError::JwtError)?;
+    tracing::info!(
+        "{} signed in with Discord as {}",
+        discord_user.username,
+        user.username()
+    );
+    let jar = jar.add(web_auth::auth_cookie(jwt_token, state.jwt.ttl()));
+    Ok((jar, Redirect::to("/")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn authorize_url_asks_discord_who_the_user_is() {
+        let discord = DiscordOAuth::new(
+            "1234".to_string(),
+            "secret".into(),
+            "https://nicknamer.example/login/discord/callback".to_string(),
+        );
+
+        let url = reqwest::Url::parse(&discord.authorize_url("some-state")).unwrap();
+        let params: Vec<(String, String)> = url.query_pairs().into_owned().collect();
+
+        assert!(url.as_str().starts_with(DISCORD_AUTHORIZE_URL));
+        assert!(params.contains(&("client_id".to_string(), "1234".to_string())));
+        assert!(params.contains(&("scope".to_string(), "identify".to_string())));
+        assert!(params.contains(&("state".to_string(), "some-state".to_string())));
+        assert!(params.contains(&(
+            "redirect_uri".to_string(),
+            "https://nicknamer.example/login/discord/callback".to_string()
+        )));
+        assert!(!url.as_str().contains("secret"));
+    }
+}
@@ -0,0 +1,73 @@
+//! Periodic jobs that run alongside the web server.
+
+use crate::name::NameService;
+use crate::name::web::NameState;
+use feature_flags::FeatureFlags;
+use jobs::{RetryPolicy, Schedule, Scheduler};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// How often the `names_total` gauge is refreshed.
+const NAME_COUNT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How often flags toggled by other instances are picked up.
+const FLAG_RELOAD_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often names that have been in the trash for too long are purged.
+const TRASH_PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Creates the scheduler holding the server's background jobs.
+pub fn scheduler(name_state: Arc<NameState>, flags: FeatureFlags) -> Scheduler {
+    let purge_state = name_state.clone();
+    Scheduler::new()
+        .job(
+            "record-name-count",
+            Schedule::every(NAME_COUNT_INTERVAL),
+            RetryPolicy::default(),
+            move || {
+                let name_state = name_state.clone();
+                async move { record_name_count(&name_state.db).await }
+            },
+        )
+        .job(
+            "purge-trash",
+            Schedule::every(TRASH_PURGE_INTERVAL),
+            RetryPolicy::default(),
+            move || {
+                let name_state = purge_state.clone();
+                async move { purge_trash(&name_state).await }
+            },
+        )
+        .job(
+            "reload-feature-flags",
+            Schedule::every(FLAG_RELOAD_INTERVAL),
+            RetryPolicy::none(),
+            move || {
+                let flags = flags.clone();
+                async move { Ok(flags.reload().await?) }
+            },
+        )
+}
+
+/// Publishes the number of stored names as the `names_total` gauge on `/metrics`.
+async fn record_name_count(db: &DatabaseConnection) -> anyhow::Result<()> {
+    let count = NameService::new(db).count_names().await?;
+    metrics::gauge!("names_total").set(count as f64);
+    Ok(())
+}
+
+/// Removes the names that have been in the trash for longer than the configured retention.
+async fn purge_trash(name_state: &NameState) -> anyhow::Result<()> {
+    let retention = Duration::from_secs(name_state.trash_retention_days * SECONDS_PER_DAY);
+    let deleted_before = SystemTime::now() - retention;
+    let purged = NameService::new(&name_state.db)
+        .purge_trash(deleted_before.into())
+        .await?;
+    if purged > 0 {
+        tracing::info!("Purged {} names from the trash", purged);
+    }
+    Ok(())
+}
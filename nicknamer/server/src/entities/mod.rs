@@ -2,4 +2,8 @@
 
 pub mod prelude;
 
+pub mod api_token;
+pub mod audit_log;
 pub mod name;
+pub mod server;
+pub mod user;
@@ -1,19 +1,38 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.12
 
 use sea_orm::entity::prelude::*;
+use typed_ids::{DiscordId, EntityId};
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq)]
 #[sea_orm(table_name = "name")]
 pub struct Model {
     #[sea_orm(primary_key)]
-    pub id: i32,
+    pub id: EntityId<Entity>,
     #[sea_orm(unique)]
-    pub discord_id: i64,
+    pub discord_id: DiscordId,
     pub name: String,
     pub server_id: String,
+    pub deleted_at: Option<DateTimeWithTimeZone>,
+    pub version: i32,
+    pub registered_server_id: Option<EntityId<super::server::Entity>>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::server::Entity",
+        from = "Column::RegisteredServerId",
+        to = "super::server::Column::Id",
+        on_update = "NoAction",
+        on_delete = "SetNull"
+    )]
+    Server,
+}
+
+impl Related<super::server::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Server.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -1,3 +1,7 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.12
 
+pub use super::api_token::Entity as ApiToken;
+pub use super::audit_log::Entity as AuditLog;
 pub use super::name::Entity as Name;
+pub use super::server::Entity as Server;
+pub use super::user::Entity as User;
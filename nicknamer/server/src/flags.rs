@@ -0,0 +1,16 @@
+//! Feature flags of the server. Toggle them at runtime through
+//! `PUT /api/v1/admin/feature-flags/{name}`, or set a `FEATURE_*` environment variable.
+
+use feature_flags::{FeatureFlags, SeaOrmFlagStore};
+use sea_orm::DatabaseConnection;
+
+/// Creates the server's flags, persisted in the `feature_flags` table.
+///
+/// Register a flag here with `.flag(name, FlagState::Off)` when a risky feature lands, and
+/// remove it once the feature is fully rolled out.
+pub fn feature_flags(db: DatabaseConnection) -> FeatureFlags {
+    FeatureFlags::builder()
+        .from_env()
+        .store(SeaOrmFlagStore::new(db))
+        .build()
+}
@@ -1,34 +1,122 @@
 pub mod config {
+    use config_core::{ConfigLoader, Secret, Validate, ValidationError};
     use serde::Deserialize;
 
     #[derive(Deserialize, Debug)]
     pub struct Config {
-        pub db_url: String,
-        #[serde(default = "default_port")]
+        pub db_url: Secret,
         pub port: u16,
+        /// The first admin, registered on first start while there are no users yet
         pub admin_username: String,
-        pub admin_password: String,
-        pub jwt_secret: String,
+        pub admin_password: Secret,
+        pub jwt_secret: Secret,
+        /// How many days deleted names stay in the trash before they are purged
+        pub trash_retention_days: u64,
+        /// How many seconds name lookups are cached for, unless names change in the meantime
+        pub name_cache_ttl_seconds: u64,
+        /// The client ID of the Discord application users can sign in with. Signing in with
+        /// Discord is only offered if this, its secret and the redirect URL are all set.
+        pub discord_client_id: Option<String>,
+        pub discord_client_secret: Option<Secret>,
+        /// Where Discord sends users back to: the public URL of `/login/discord/callback`
+        pub discord_redirect_url: Option<String>,
+        /// How many times per minute an IP address may try to log in
+        pub login_attempts_per_minute: u32,
+        /// How many API requests per minute an IP address may make
+        pub api_ip_requests_per_minute: u32,
+        /// How many API requests per second a user or API token may make on average
+        pub api_user_requests_per_second: u32,
+        /// How many API requests a user or API token may make at once
+        pub api_user_burst: u32,
+        /// How many reverse proxies in front of the server add the client's address to
+        /// `X-Forwarded-For`, like Railway's. With 0, clients are told apart by the address they
+        /// connect from and the header is ignored.
+        pub trusted_proxies: usize,
     }
 
     impl Config {
-        /// Loads configuration from environment variables.
-        pub fn from_env() -> anyhow::Result<Self> {
-            let settings = config::Config::builder()
-                .add_source(config::Environment::default())
-                .build()?;
-
-            let config: Config = settings.try_deserialize()?;
+        /// Loads configuration from environment variables, falling back to an optional
+        /// `nicknamer.toml` file and then to defaults.
+        pub fn load() -> anyhow::Result<Self> {
+            let config = ConfigLoader::new()
+                .file("nicknamer.toml")
+                .default_value("port", 8080)
+                .default_value("trash_retention_days", 30)
+                .default_value("name_cache_ttl_seconds", 60)
+                .default_value("login_attempts_per_minute", 5)
+                .default_value("api_ip_requests_per_minute", 300)
+                .default_value("api_user_requests_per_second", 10)
+                .default_value("api_user_burst", 20)
+                .default_value("trusted_proxies", 0)
+                .load()?;
             Ok(config)
         }
+
+        /// Reports the Discord login settings that are missing, if only some of them are set.
+        fn validate_discord_login(&self) -> Vec<ValidationError> {
+            let settings = [
+                ("discord_client_id", self.discord_client_id.as_deref()),
+                (
+                    "discord_client_secret",
+                    self.discord_client_secret
+                        .as_ref()
+                        .map(|secret| secret.expose().as_str()),
+                ),
+                ("discord_redirect_url", self.discord_redirect_url.as_deref()),
+            ];
+            let is_set = |value: Option<&str>| value.is_some_and(|value| !value.trim().is_empty());
+            if !settings.iter().any(|(_, value)| is_set(*value)) {
+                return Vec::new();
+            }
+            settings
+                .into_iter()
+                .filter(|(_, value)| !is_set(*value))
+                .map(|(field, _)| {
+                    ValidationError::new(field, "must be set to sign in with Discord")
+                })
+                .collect()
+        }
     }
 
-    fn default_port() -> u16 {
-        8080
+    impl Validate for Config {
+        fn validate(&self) -> Vec<ValidationError> {
+            [
+                ValidationError::require_non_empty("db_url", self.db_url.expose()),
+                ValidationError::require_non_empty("admin_username", &self.admin_username),
+                ValidationError::require_non_empty("admin_password", self.admin_password.expose()),
+                ValidationError::require_non_empty("jwt_secret", self.jwt_secret.expose()),
+                (self.trash_retention_days == 0)
+                    .then(|| ValidationError::new("trash_retention_days", "must be at least 1")),
+                (self.name_cache_ttl_seconds == 0)
+                    .then(|| ValidationError::new("name_cache_ttl_seconds", "must be at least 1")),
+                (self.login_attempts_per_minute == 0).then(|| {
+                    ValidationError::new("login_attempts_per_minute", "must be at least 1")
+                }),
+                (self.api_ip_requests_per_minute == 0).then(|| {
+                    ValidationError::new("api_ip_requests_per_minute", "must be at least 1")
+                }),
+                (self.api_user_requests_per_second == 0).then(|| {
+                    ValidationError::new("api_user_requests_per_second", "must be at least 1")
+                }),
+                (self.api_user_burst == 0)
+                    .then(|| ValidationError::new("api_user_burst", "must be at least 1")),
+            ]
+            .into_iter()
+            .flatten()
+            .chain(self.validate_discord_login())
+            .collect()
+        }
     }
 }
+pub mod api_token;
+pub mod audit;
+pub mod background;
 pub mod entities;
+pub mod flags;
 pub mod name;
+pub mod rate_limits;
+pub mod server;
+pub mod user;
 
 pub mod auth;
 pub mod web;
@@ -1,28 +1,47 @@
+use crate::audit;
+use crate::auth::CurrentUser;
+use crate::name::cache::CacheStats;
+use crate::name::export::{ExportFormat, export_names};
 use crate::name::web::NameState;
-use crate::name::{Name, NameService};
-use crate::web::api::v1::ServerErrorResponse;
+use crate::name::{
+    DEFAULT_SEARCH_LIMIT, Name, NameId, NameQuery, NameService, NameServiceError, NameSortField,
+    TrashedName,
+};
+use crate::server::ServerId;
+use api_error::{ApiError, FieldError, ProblemDetails};
 use axum::{
-    Router,
-    extract::{Query, State},
-    http::StatusCode,
-    response::Json,
-    routing::get,
+    Extension, Router,
+    body::Body,
+    extract::{Path, Query, State},
+    http::{HeaderValue, StatusCode, header},
+    response::{IntoResponse, Json, Response},
+    routing::{delete, get, patch, post, put},
 };
+use axum_extra::extract::WithRejection;
+use pagination::{PageParams, Paginated, SortOrder, SortParams};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use typed_ids::DiscordId;
 use utoipa::ToSchema;
 
 /// JSON representation of a Name for API responses.
 #[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct NameJson {
     /// Unique identifier for the name
-    id: u32,
+    #[schema(value_type = u32)]
+    id: NameId,
     /// Discord user ID associated with the name
-    discord_id: u64,
+    #[schema(value_type = u64)]
+    discord_id: DiscordId,
     /// The actual name/nickname
     name: String,
     /// Server ID associated with the name
     server_id: String,
+    /// Display name of the server, if it is registered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    server_name: Option<String>,
+    /// Version of the name, which every edit increments
+    version: i32,
 }
 
 impl From<Name> for NameJson {
@@ -32,77 +51,546 @@ impl From<Name> for NameJson {
             discord_id: name.discord_id(),
             name: name.name().to_string(),
             server_id: name.server_id().to_string(),
+            server_name: name.server_name().map(str::to_string),
+            version: name.version(),
         }
     }
 }
 
-/// API response for listing all names.
+/// JSON representation of a name in the trash for API responses.
 #[derive(Debug, Serialize, ToSchema)]
-pub struct NamesResponse {
-    /// List of names
-    names: Vec<NameJson>,
-    /// Total number of names
-    count: usize,
+pub struct TrashedNameJson {
+    #[serde(flatten)]
+    name: NameJson,
+    /// When the name was deleted, in RFC 3339 format
+    deleted_at: String,
+}
+
+impl From<TrashedName> for TrashedNameJson {
+    fn from(trashed: TrashedName) -> Self {
+        Self {
+            deleted_at: trashed.deleted_at().to_rfc3339(),
+            name: NameJson::from(trashed.name().clone()),
+        }
+    }
 }
 
-/// Query parameters for filtering names by server.
+impl From<NameServiceError> for ApiError {
+    fn from(err: NameServiceError) -> Self {
+        match err {
+            NameServiceError::DuplicateEntryError(..) => ApiError::conflict(err.to_string()),
+            NameServiceError::NameNotFound(_) => ApiError::not_found(err.to_string()),
+            NameServiceError::MalformedData(_) => ApiError::bad_request(err.to_string()),
+            NameServiceError::VersionConflict(_) => ApiError::conflict(err.to_string()),
+            NameServiceError::InvalidThreshold(_) => ApiError::bad_request(err.to_string()),
+            NameServiceError::Database(err) => ApiError::from(err),
+        }
+    }
+}
+
+/// JSON request body for creating a name.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateNameRequest {
+    /// Discord user ID the name belongs to
+    pub discord_id: u64,
+    /// The actual name/nickname
+    pub name: String,
+    /// Server ID the name is used in
+    pub server_id: String,
+}
+
+/// JSON request body for replacing the name and server of an existing entry.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateNameRequest {
+    /// The new name/nickname
+    pub name: String,
+    /// The new server ID
+    pub server_id: String,
+    /// The version of the name the update was made to. If given, the update fails with a 409
+    /// when the name has been changed since.
+    #[serde(default)]
+    pub version: Option<i32>,
+}
+
+/// JSON request body for moving multiple names to another server.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BulkUpdateServerRequest {
+    /// IDs of the name entries to move
+    #[schema(value_type = Vec<u32>)]
+    pub ids: Vec<NameId>,
+    /// The server ID to move them to
+    pub server_id: String,
+}
+
+/// Checks the fields shared by every request that writes a name, trimming both of them.
+///
+/// # Returns
+///
+/// * `Vec<FieldError>` - The invalid fields, empty if there are none
+fn validate_name_fields(name: &mut String, server_id: &mut String) -> Vec<FieldError> {
+    *name = name.trim().to_string();
+    *server_id = server_id.trim().to_string();
+
+    let mut errors = Vec::new();
+    if name.is_empty() {
+        errors.push(FieldError::new("name", "must not be empty"));
+    }
+    if server_id.is_empty() {
+        errors.push(FieldError::new("server_id", "must not be empty"));
+    }
+    errors
+}
+
+/// Query parameters for filtering names.
 #[derive(Debug, Deserialize, ToSchema)]
 pub struct NamesQuery {
     /// Optional server ID to filter names by
     #[serde(default)]
     server_id: Option<String>,
+    /// Optional ID of a registered server to filter names by
+    #[serde(default)]
+    #[schema(value_type = Option<u32>)]
+    registered_server_id: Option<ServerId>,
+    /// Optional Discord user ID to look the names of up
+    #[serde(default)]
+    #[schema(value_type = Option<u64>)]
+    discord_id: Option<DiscordId>,
+    /// Optional text the names must contain, ignoring case
+    #[serde(default)]
+    search: Option<String>,
 }
 
-/// Handler for GET /api/v1/names - Returns all names in JSON format.
+/// Handler for GET /api/v1/names - Returns a page of names in JSON format.
 #[tracing::instrument(skip(state))]
 #[utoipa::path(
     get,
     path = "/api/v1/names",
     params(
-        ("server_id" = Option<String>, Query, description = "Optional server ID to filter names by")
+        ("server_id" = Option<String>, Query, description = "Optional server ID to filter names by"),
+        ("registered_server_id" = Option<u32>, Query, description = "Optional ID of a registered server to filter names by"),
+        ("discord_id" = Option<u64>, Query, description = "Optional Discord user ID to look the names of up"),
+        ("search" = Option<String>, Query, description = "Optional text the names must contain, ignoring case"),
+        PageParams,
+        ("sort" = Option<NameSortField>, Query, description = "Field to sort by, `id` if omitted"),
+        ("order" = Option<SortOrder>, Query, description = "Sort direction, `asc` if omitted")
     ),
     responses(
-        (status = 200, description = "Successfully retrieved names", body = NamesResponse),
-        (status = 500, description = "Internal server error", body = ServerErrorResponse)
+        (status = 200, description = "Successfully retrieved names", body = Paginated<NameJson>),
+        (status = 400, description = "Invalid filter, pagination or sort parameters", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
     ),
     tag = "Names"
 )]
 pub async fn get_names_handler(
     State(state): State<Arc<NameState>>,
-    Query(query): Query<NamesQuery>,
-) -> Result<Json<NamesResponse>, (StatusCode, Json<ServerErrorResponse>)> {
-    let service = NameService::new(&state.db);
+    WithRejection(Query(query), _): WithRejection<Query<NamesQuery>, ApiError>,
+    page: PageParams,
+    sort: SortParams<NameSortField>,
+) -> Result<Json<Paginated<NameJson>>, ApiError> {
+    let query = NameQuery {
+        server_id: query.server_id,
+        registered_server_id: query.registered_server_id,
+        discord_id: query.discord_id,
+        search: query.search.filter(|search| !search.trim().is_empty()),
+        sort,
+        page,
+    };
+    let service = NameService::new(&state.db).with_cache(&state.cache);
+    let names = service.list(&query).await?;
+    Ok(Json(names.map(NameJson::from)))
+}
 
-    let names_result = match query.server_id {
-        Some(server_id) => service.get_names_by_server(&server_id).await,
-        None => service.get_all_names().await,
+/// Handler for POST /api/v1/names - Creates a name and returns it.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+    post,
+    path = "/api/v1/names",
+    request_body = CreateNameRequest,
+    responses(
+        (status = 201, description = "Name created", body = NameJson),
+        (status = 400, description = "Malformed request body", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "The user already has a name in this server", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid fields", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    tag = "Names"
+)]
+pub async fn create_name_handler(
+    State(state): State<Arc<NameState>>,
+    current_user: Option<Extension<CurrentUser>>,
+    WithRejection(Json(mut payload), _): WithRejection<Json<CreateNameRequest>, ApiError>,
+) -> Result<(StatusCode, Json<NameJson>), ApiError> {
+    let mut errors = validate_name_fields(&mut payload.name, &mut payload.server_id);
+    let discord_id = DiscordId::new(payload.discord_id)
+        .ok()
+        .filter(|_| payload.discord_id != 0);
+    if discord_id.is_none() {
+        errors.insert(
+            0,
+            FieldError::new("discord_id", "must be a Discord snowflake"),
+        );
+    }
+    let Some(discord_id) = discord_id.filter(|_| errors.is_empty()) else {
+        return Err(ApiError::validation(errors));
     };
 
-    match names_result {
-        Ok(names) => {
-            let json_names: Vec<NameJson> = names.into_iter().map(NameJson::from).collect();
-            let count = json_names.len();
+    let service = NameService::new(&state.db)
+        .with_cache(&state.cache)
+        .acting_as(audit::actor(current_user.as_deref()));
+    let name = service
+        .create_name(discord_id, payload.name, payload.server_id)
+        .await?;
+    Ok((StatusCode::CREATED, Json(NameJson::from(name))))
+}
 
-            Ok(Json(NamesResponse {
-                names: json_names,
-                count,
-            }))
-        }
-        Err(err) => {
-            tracing::error!("Failed to get names: {}", err);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ServerErrorResponse::new(
-                    "Failed to retrieve names".to_string(),
-                )),
-            ))
+/// Handler for PUT /api/v1/names/{id} - Replaces the name and server of an entry.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+    put,
+    path = "/api/v1/names/{id}",
+    params(("id" = u32, Path, description = "ID of the name entry")),
+    request_body = UpdateNameRequest,
+    responses(
+        (status = 200, description = "Name updated", body = NameJson),
+        (status = 400, description = "Malformed request body or ID", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Name not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "The user already has a name in the new server, or the name has been changed since `version`", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid fields", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    tag = "Names"
+)]
+pub async fn update_name_handler(
+    State(state): State<Arc<NameState>>,
+    current_user: Option<Extension<CurrentUser>>,
+    WithRejection(Path(id), _): WithRejection<Path<NameId>, ApiError>,
+    WithRejection(Json(mut payload), _): WithRejection<Json<UpdateNameRequest>, ApiError>,
+) -> Result<Json<NameJson>, ApiError> {
+    let errors = validate_name_fields(&mut payload.name, &mut payload.server_id);
+    if !errors.is_empty() {
+        return Err(ApiError::validation(errors));
+    }
+
+    let service = NameService::new(&state.db)
+        .with_cache(&state.cache)
+        .acting_as(audit::actor(current_user.as_deref()));
+    let name = service
+        .edit_name_by_id(id, payload.name, payload.server_id, payload.version)
+        .await?;
+    Ok(Json(NameJson::from(name)))
+}
+
+/// Handler for DELETE /api/v1/names/{id} - Deletes a name entry.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+    delete,
+    path = "/api/v1/names/{id}",
+    params(("id" = u32, Path, description = "ID of the name entry")),
+    responses(
+        (status = 204, description = "Name deleted"),
+        (status = 400, description = "Malformed ID", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Name not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    tag = "Names"
+)]
+pub async fn delete_name_handler(
+    State(state): State<Arc<NameState>>,
+    current_user: Option<Extension<CurrentUser>>,
+    WithRejection(Path(id), _): WithRejection<Path<NameId>, ApiError>,
+) -> Result<StatusCode, ApiError> {
+    let service = NameService::new(&state.db)
+        .with_cache(&state.cache)
+        .acting_as(audit::actor(current_user.as_deref()));
+    service.delete_name_by_id(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handler for GET /api/v1/names/trash - Returns a page of the names in the trash, most
+/// recently deleted first.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+    get,
+    path = "/api/v1/names/trash",
+    params(PageParams),
+    responses(
+        (status = 200, description = "Successfully retrieved the trash", body = Paginated<TrashedNameJson>),
+        (status = 400, description = "Invalid pagination parameters", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    tag = "Names"
+)]
+pub async fn get_trash_handler(
+    State(state): State<Arc<NameState>>,
+    page: PageParams,
+) -> Result<Json<Paginated<TrashedNameJson>>, ApiError> {
+    let service = NameService::new(&state.db).with_cache(&state.cache);
+    let names = service.list_trash(page).await?;
+    Ok(Json(names.map(TrashedNameJson::from)))
+}
+
+/// Handler for POST /api/v1/names/{id}/restore - Takes a name out of the trash and returns it.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+    post,
+    path = "/api/v1/names/{id}/restore",
+    params(("id" = u32, Path, description = "ID of the name entry in the trash")),
+    responses(
+        (status = 200, description = "Name restored", body = NameJson),
+        (status = 400, description = "Malformed ID", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Name not in the trash", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "The user has another name in the server by now", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    tag = "Names"
+)]
+pub async fn restore_name_handler(
+    State(state): State<Arc<NameState>>,
+    current_user: Option<Extension<CurrentUser>>,
+    WithRejection(Path(id), _): WithRejection<Path<NameId>, ApiError>,
+) -> Result<Json<NameJson>, ApiError> {
+    let service = NameService::new(&state.db)
+        .with_cache(&state.cache)
+        .acting_as(audit::actor(current_user.as_deref()));
+    let name = service.restore_name_by_id(id).await?;
+    Ok(Json(NameJson::from(name)))
+}
+
+/// Handler for DELETE /api/v1/names/trash/{id} - Removes a name from the trash for good.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+    delete,
+    path = "/api/v1/names/trash/{id}",
+    params(("id" = u32, Path, description = "ID of the name entry in the trash")),
+    responses(
+        (status = 204, description = "Name removed for good"),
+        (status = 400, description = "Malformed ID", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Name not in the trash", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    tag = "Names"
+)]
+pub async fn purge_name_handler(
+    State(state): State<Arc<NameState>>,
+    current_user: Option<Extension<CurrentUser>>,
+    WithRejection(Path(id), _): WithRejection<Path<NameId>, ApiError>,
+) -> Result<StatusCode, ApiError> {
+    let service = NameService::new(&state.db)
+        .with_cache(&state.cache)
+        .acting_as(audit::actor(current_user.as_deref()));
+    service.purge_name_by_id(id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Handler for PATCH /api/v1/names/bulk - Moves multiple names to another server, all at once
+/// or not at all, and returns the names that were moved.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+    patch,
+    path = "/api/v1/names/bulk",
+    request_body = BulkUpdateServerRequest,
+    responses(
+        (status = 200, description = "Names moved, ordered by ID. Names already in the server are left out", body = Vec<NameJson>),
+        (status = 400, description = "Malformed request body", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "One of the names not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "A user would have two names in the server", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Empty server ID", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    tag = "Names"
+)]
+pub async fn bulk_update_server_handler(
+    State(state): State<Arc<NameState>>,
+    current_user: Option<Extension<CurrentUser>>,
+    WithRejection(Json(payload), _): WithRejection<Json<BulkUpdateServerRequest>, ApiError>,
+) -> Result<Json<Vec<NameJson>>, ApiError> {
+    let server_id = payload.server_id.trim().to_string();
+    if server_id.is_empty() {
+        return Err(ApiError::validation(vec![FieldError::new(
+            "server_id",
+            "must not be empty",
+        )]));
+    }
+
+    let service = NameService::new(&state.db)
+        .with_cache(&state.cache)
+        .acting_as(audit::actor(current_user.as_deref()));
+    let names = service.bulk_update_server(&payload.ids, server_id).await?;
+    Ok(Json(names.into_iter().map(NameJson::from).collect()))
+}
+
+/// Query parameters for exporting names.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ExportNamesQuery {
+    /// Server ID to export the names of
+    server_id: String,
+    /// Format of the document, `yaml` if omitted
+    #[serde(default)]
+    format: ExportFormat,
+}
+
+/// Handler for GET /api/v1/names/export - Streams the names of a server as a document that
+/// can be imported again.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+    get,
+    path = "/api/v1/names/export",
+    params(
+        ("server_id" = String, Query, description = "Server ID to export the names of"),
+        ("format" = Option<ExportFormat>, Query, description = "Format of the document, `yaml` if omitted")
+    ),
+    responses(
+        (status = 200, description = "The names of the server, ordered by Discord user ID", content(
+            (String = "application/yaml"),
+            (String = "text/csv")
+        )),
+        (status = 400, description = "Missing server ID or unknown format", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Empty server ID", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    tag = "Names"
+)]
+pub async fn export_names_handler(
+    State(state): State<Arc<NameState>>,
+    WithRejection(Query(query), _): WithRejection<Query<ExportNamesQuery>, ApiError>,
+) -> Result<Response, ApiError> {
+    let server_id = query.server_id.trim().to_string();
+    if server_id.is_empty() {
+        return Err(ApiError::validation(vec![FieldError::new(
+            "server_id",
+            "must not be empty",
+        )]));
+    }
+
+    let headers = [
+        (
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(query.format.content_type()),
+        ),
+        (
+            header::CONTENT_DISPOSITION,
+            query.format.content_disposition(&server_id),
+        ),
+    ];
+    let body = Body::from_stream(export_names(state.db.clone(), server_id, query.format));
+    Ok((headers, body).into_response())
+}
+
+/// Most names a search may ask for.
+const MAX_SEARCH_LIMIT: u64 = 100;
+
+/// Query parameters for searching names.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SearchNamesQuery {
+    /// Text to search the names for
+    #[serde(default)]
+    q: String,
+    /// How many names to return at most, 20 if omitted
+    #[serde(default)]
+    limit: Option<u64>,
+}
+
+/// Handler for GET /api/v1/names/search - Returns the names best matching a text, those
+/// containing it first and then those spelled most similarly.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+    get,
+    path = "/api/v1/names/search",
+    params(
+        ("q" = String, Query, description = "Text to search the names for"),
+        ("limit" = Option<u64>, Query, description = "How many names to return at most, from 1 to 100, 20 if omitted")
+    ),
+    responses(
+        (status = 200, description = "The best matching names, best first", body = Vec<NameJson>),
+        (status = 400, description = "Invalid limit", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Empty search text or limit out of range", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    tag = "Names"
+)]
+pub async fn search_names_handler(
+    State(state): State<Arc<NameState>>,
+    WithRejection(Query(query), _): WithRejection<Query<SearchNamesQuery>, ApiError>,
+) -> Result<Json<Vec<NameJson>>, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+    let mut errors = Vec::new();
+    if query.q.trim().is_empty() {
+        errors.push(FieldError::new("q", "must not be empty"));
+    }
+    if !(1..=MAX_SEARCH_LIMIT).contains(&limit) {
+        errors.push(FieldError::new("limit", "must be between 1 and 100"));
+    }
+    if !errors.is_empty() {
+        return Err(ApiError::validation(errors));
+    }
+
+    let service = NameService::new(&state.db);
+    let names = service.search(&query.q, limit).await?;
+    Ok(Json(names.into_iter().map(NameJson::from).collect()))
+}
+
+/// JSON representation of how the cache of name lookups has been doing.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CacheStatsJson {
+    /// Lookups currently cached
+    entries: u64,
+    /// Lookups answered from the cache since the server started
+    hits: u64,
+    /// Lookups that went to the database since the server started
+    misses: u64,
+    /// Times the cache was cleared because names or servers changed
+    invalidations: u64,
+    /// Share of lookups answered from the cache, from 0 to 1
+    hit_rate: f64,
+}
+
+impl From<CacheStats> for CacheStatsJson {
+    fn from(stats: CacheStats) -> Self {
+        Self {
+            entries: stats.entries,
+            hits: stats.hits,
+            misses: stats.misses,
+            invalidations: stats.invalidations,
+            hit_rate: stats.hit_rate(),
         }
     }
 }
 
+/// Handler for GET /api/v1/admin/name-cache - Returns how the cache of name lookups has been
+/// doing.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+    get,
+    path = "/api/v1/admin/name-cache",
+    responses(
+        (status = 200, description = "Successfully retrieved the cache statistics", body = CacheStatsJson),
+        (status = 403, description = "Only admins can read the cache statistics", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    tag = "Names"
+)]
+pub async fn get_cache_stats_handler(State(state): State<Arc<NameState>>) -> Json<CacheStatsJson> {
+    Json(CacheStatsJson::from(state.cache.stats().await))
+}
+
+/// Creates the API router of the admin endpoints of names.
+pub fn create_admin_api_router(state: Arc<NameState>) -> Router {
+    Router::new()
+        .route("/admin/name-cache", get(get_cache_stats_handler))
+        .with_state(state)
+}
+
 /// Creates and returns the names API router.
 pub fn create_api_router(state: Arc<NameState>) -> Router {
     Router::new()
-        .route("/names", get(get_names_handler))
+        .route("/names", get(get_names_handler).post(create_name_handler))
+        .route("/names/bulk", patch(bulk_update_server_handler))
+        .route("/names/export", get(export_names_handler))
+        .route("/names/search", get(search_names_handler))
+        .route("/names/trash", get(get_trash_handler))
+        .route("/names/trash/{id}", delete(purge_name_handler))
+        .route("/names/{id}/restore", post(restore_name_handler))
+        .route(
+            "/names/{id}",
+            put(update_name_handler).delete(delete_name_handler),
+        )
         .with_state(state)
 }
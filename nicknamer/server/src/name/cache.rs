@@ -0,0 +1,222 @@
+//! An in-process cache in front of the name lookups the Discord bot makes all the time: all the
+//! names, the names of a server and pages of the JSON API. Lookups are kept in [`moka`] caches
+//! for a configured time, and forgotten as soon as names are changed through a
+//! [`NameService`](super::NameService) using the cache, or servers are changed through a
+//! [`ServerService`](crate::server::ServerService) that knows about it.
+
+use crate::name::{Name, NameQuery};
+use moka::future::Cache;
+use pagination::Paginated;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Number of lookups of each kind kept at most. Every search of the JSON API is a lookup of
+/// its own, so the ones least likely to be made again are dropped beyond it.
+const MAX_LOOKUPS: u64 = 1024;
+
+/// A lookup of a list of names that can be cached.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum Lookup {
+    /// Every name that isn't in the trash
+    All,
+    /// The names of the server with this ID
+    Server(String),
+}
+
+/// Cached name lookups, cheap to clone and shared by every clone.
+#[derive(Debug, Clone)]
+pub struct NameCache {
+    names: Cache<Lookup, Vec<Name>>,
+    pages: Cache<NameQuery, Paginated<Name>>,
+    /// Bumped on every invalidation, so that lookups loaded before it aren't cached after it
+    generation: Arc<AtomicU64>,
+    counters: Arc<Counters>,
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    hits: AtomicU64,
+    misses: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+/// How the cache has been doing since the server started.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CacheStats {
+    /// Lookups currently cached
+    pub entries: u64,
+    /// Lookups answered from the cache
+    pub hits: u64,
+    /// Lookups that went to the database
+    pub misses: u64,
+    /// Times the cache was cleared because names changed
+    pub invalidations: u64,
+}
+
+impl CacheStats {
+    /// Returns the share of lookups answered from the cache, from 0 to 1.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / lookups as f64
+    }
+}
+
+impl NameCache {
+    /// Creates an empty cache that keeps lookups for `ttl`.
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            names: Cache::builder()
+                .time_to_live(ttl)
+                .max_capacity(MAX_LOOKUPS)
+                .build(),
+            pages: Cache::builder()
+                .time_to_live(ttl)
+                .max_capacity(MAX_LOOKUPS)
+                .build(),
+            generation: Arc::default(),
+            counters: Arc::default(),
+        }
+    }
+
+    /// Returns the cached names of `lookup`, or loads them with `load` and caches them.
+    pub(crate) async fn names<E>(
+        &self,
+        lookup: Lookup,
+        load: impl Future<Output = Result<Vec<Name>, E>>,
+    ) -> Result<Vec<Name>, E> {
+        self.get_or_load(&self.names, lookup, load).await
+    }
+
+    /// Returns the cached page of names matching `query`, or loads it with `load` and caches it.
+    pub(crate) async fn page<E>(
+        &self,
+        query: NameQuery,
+        load: impl Future<Output = Result<Paginated<Name>, E>>,
+    ) -> Result<Paginated<Name>, E> {
+        self.get_or_load(&self.pages, query, load).await
+    }
+
+    async fn get_or_load<K, V, E>(
+        &self,
+        cache: &Cache<K, V>,
+        key: K,
+        load: impl Future<Output = Result<V, E>>,
+    ) -> Result<V, E>
+    where
+        K: Hash + Eq + Send + Sync + 'static,
+        V: Clone + Send + Sync + 'static,
+    {
+        if let Some(value) = cache.get(&key).await {
+            self.counters.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(value);
+        }
+        self.counters.misses.fetch_add(1, Ordering::Relaxed);
+
+        let generation = self.generation.load(Ordering::Acquire);
+        let value = load.await?;
+        // Names changed while they were being loaded may already be outdated
+        if self.generation.load(Ordering::Acquire) == generation {
+            cache.insert(key, value.clone()).await;
+        }
+        Ok(value)
+    }
+
+    /// Forgets every cached lookup, after names changed.
+    pub fn invalidate(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.names.invalidate_all();
+        self.pages.invalidate_all();
+        self.counters.invalidations.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns how the cache has been doing.
+    pub async fn stats(&self) -> CacheStats {
+        self.names.run_pending_tasks().await;
+        self.pages.run_pending_tasks().await;
+        CacheStats {
+            entries: self.names.entry_count() + self.pages.entry_count(),
+            hits: self.counters.hits.load(Ordering::Relaxed),
+            misses: self.counters.misses.load(Ordering::Relaxed),
+            invalidations: self.counters.invalidations.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::name::NameId;
+    use std::convert::Infallible;
+    use typed_ids::DiscordId;
+
+    fn names(name: &str) -> Vec<Name> {
+        vec![Name::new(
+            NameId::new(1).unwrap(),
+            DiscordId::new(100).unwrap(),
+            name.to_string(),
+            "server1".to_string(),
+            1,
+        )]
+    }
+
+    async fn load(name: &str) -> Result<Vec<Name>, Infallible> {
+        Ok(names(name))
+    }
+
+    #[tokio::test]
+    async fn keeps_lookups_until_names_change() {
+        let cache = NameCache::new(Duration::from_secs(60));
+
+        assert_eq!(
+            cache.names(Lookup::All, load("Alice")).await,
+            Ok(names("Alice"))
+        );
+        assert_eq!(
+            cache.names(Lookup::All, load("Bob")).await,
+            Ok(names("Alice"))
+        );
+        assert_eq!(
+            cache
+                .names(Lookup::Server("server1".to_string()), load("Bob"))
+                .await,
+            Ok(names("Bob"))
+        );
+
+        cache.invalidate();
+        assert_eq!(
+            cache.names(Lookup::All, load("Carol")).await,
+            Ok(names("Carol"))
+        );
+
+        let stats = cache.stats().await;
+        assert_eq!(
+            stats,
+            CacheStats {
+                entries: 1,
+                hits: 1,
+                misses: 3,
+                invalidations: 1,
+            }
+        );
+        assert_eq!(stats.hit_rate(), 0.25);
+    }
+
+    #[tokio::test]
+    async fn does_not_cache_names_loaded_before_an_invalidation() {
+        let cache = NameCache::new(Duration::from_secs(60));
+
+        let loaded = cache
+            .names(Lookup::All, async {
+                cache.invalidate();
+                load("Outdated").await
+            })
+            .await;
+        assert_eq!(loaded, Ok(names("Outdated")));
+        assert_eq!(cache.stats().await.entries, 0);
+    }
+}
@@ -0,0 +1,188 @@
+//! Finding names that are likely duplicates: the same user with names in several servers, or
+//! different users with very similar names. Names are compared by trigram similarity, the same
+//! measure Postgres' `pg_trgm` uses, so that small differences in spelling and case still match.
+
+use crate::name::Name;
+use std::collections::{BTreeMap, HashSet};
+
+/// How similar two names have to be to count as duplicates, unless asked otherwise.
+pub const DEFAULT_SIMILARITY_THRESHOLD: f64 = 0.6;
+
+/// Why a group of names is thought to be duplicates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateReason {
+    /// The names belong to the same user, in different servers
+    SameUser,
+    /// The names of different users are at least as similar as the threshold
+    SimilarNames,
+}
+
+/// Names that are likely duplicates of each other, of which one can be kept and the rest merged
+/// into it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DuplicateGroup {
+    reason: DuplicateReason,
+    names: Vec<Name>,
+    similarity: f64,
+}
+
+impl DuplicateGroup {
+    /// Returns why the names are thought to be duplicates.
+    pub fn reason(&self) -> DuplicateReason {
+        self.reason
+    }
+
+    /// Returns the names, ordered by ID.
+    pub fn names(&self) -> &[Name] {
+        &self.names
+    }
+
+    /// Returns how similar the names are, from 0 to 1.
+    pub fn similarity(&self) -> f64 {
+        self.similarity
+    }
+
+    /// Returns the similarity as a whole percentage, for display.
+    pub fn similarity_percent(&self) -> u32 {
+        (self.similarity * 100.0).round() as u32
+    }
+}
+
+/// Groups the names that are likely duplicates: first the names of each user with names in
+/// several servers, then the pairs of names of different users that are at least `threshold`
+/// similar, most similar first.
+///
+/// Every pair of names is compared, so this is meant for maintenance rather than every request.
+pub fn group_duplicates(mut names: Vec<Name>, threshold: f64) -> Vec<DuplicateGroup> {
+    names.sort_by_key(|name| name.id());
+
+    let mut by_user: BTreeMap<_, Vec<Name>> = BTreeMap::new();
+    for name in &names {
+        by_user
+            .entry(name.discord_id())
+            .or_default()
+            .push(name.clone());
+    }
+    let mut same_user: Vec<DuplicateGroup> = by_user
+        .into_values()
+        .filter(|names| names.len() > 1)
+        .map(|names| DuplicateGroup {
+            reason: DuplicateReason::SameUser,
+            similarity: names
+                .windows(2)
+                .map(|pair| similarity(pair[0].name(), pair[1].name()))
+                .fold(1.0, f64::min),
+            names,
+        })
+        .collect();
+    same_user.sort_by_key(|group| group.names[0].id());
+
+    let trigrams: Vec<HashSet<String>> = names.iter().map(|name| trigrams(name.name())).collect();
+    let mut similar_names = Vec::new();
+    for (i, first) in names.iter().enumerate() {
+        for (j, second) in names.iter().enumerate().skip(i + 1) {
+            if first.discord_id() == second.discord_id() {
+                continue;
+            }
+            let similarity = trigram_similarity(&trigrams[i], &trigrams[j]);
+            if similarity >= threshold {
+                similar_names.push(DuplicateGroup {
+                    reason: DuplicateReason::SimilarNames,
+                    names: vec![first.clone(), second.clone()],
+                    similarity,
+                });
+            }
+        }
+    }
+    similar_names.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+
+    same_user.extend(similar_names);
+    same_user
+}
+
+/// How similar two names are, from 0 for nothing in common to 1 for the same words, ignoring
+/// case and punctuation.
+pub fn similarity(a: &str, b: &str) -> f64 {
+    trigram_similarity(&trigrams(a), &trigrams(b))
+}
+
+fn trigram_similarity(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f64 / union as f64
+}
+
+/// The trigrams of a text, as `pg_trgm` takes them: every three characters of each lowercase
+/// word, padded with two spaces in front and one behind.
+fn trigrams(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .flat_map(|word| {
+            let padded: Vec<char> = format!("  {} ", word.to_lowercase()).chars().collect();
+            padded
+                .windows(3)
+                .map(|window| window.iter().collect::<String>())
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::name::NameId;
+    use typed_ids::DiscordId;
+
+    fn name(id: u32, discord_id: u64, name: &str, server_id: &str) -> Name {
+        Name::new(
+            NameId::new(id).unwrap(),
+            DiscordId::new(discord_id).unwrap(),
+            name.to_string(),
+            server_id.to_string(),
+            1,
+        )
+    }
+
+    #[test]
+    fn similarity_ignores_case_and_punctuation() {
+        assert_eq!(similarity("Alice", "alice!"), 1.0);
+        assert_eq!(similarity("Alice", "Bob"), 0.0);
+        assert_eq!(similarity("", ""), 0.0);
+        // 6 of the 12 trigrams of both are shared
+        assert_eq!(similarity("Jonathan", "Jonathon"), 0.5);
+    }
+
+    #[test]
+    fn groups_names_of_the_same_user_and_similar_names() {
+        let names = vec![
+            name(1, 100, "Alice", "server1"),
+            name(2, 200, "Bob", "server1"),
+            name(3, 100, "Alice", "server2"),
+            name(4, 300, "alice", "server1"),
+            name(5, 400, "Carol", "server2"),
+        ];
+
+        let groups = group_duplicates(names, DEFAULT_SIMILARITY_THRESHOLD);
+
+        let ids: Vec<(DuplicateReason, Vec<u32>)> = groups
+            .iter()
+            .map(|group| {
+                (
+                    group.reason(),
+                    group.names().iter().map(|name| name.id().get()).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            ids,
+            vec![
+                (DuplicateReason::SameUser, vec![1, 3]),
+                (DuplicateReason::SimilarNames, vec![1, 4]),
+                (DuplicateReason::SimilarNames, vec![3, 4]),
+            ]
+        );
+        assert_eq!(groups[0].similarity_percent(), 100);
+    }
+}
@@ -0,0 +1,150 @@
+//! Exporting the names of a server as a document that can be imported again: YAML for the bulk
+//! add form, CSV for the bot's `import-names` command. Exports are streamed in batches, so that
+//! a large server is never held in memory all at once.
+
+use crate::entities::*;
+use crate::name::{NameServiceError, live_names};
+use axum::http::HeaderValue;
+use futures_util::{Stream, StreamExt, stream};
+use names_format::{Format, NamesWriter};
+use sea_orm::*;
+use serde::Deserialize;
+use std::sync::Arc;
+use typed_ids::DiscordId;
+
+/// How many names are read from the database at a time.
+pub const EXPORT_BATCH_SIZE: u64 = 500;
+
+/// The format to export names in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    #[default]
+    Yaml,
+    Csv,
+}
+
+impl ExportFormat {
+    /// The content type of the exported document.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Yaml => "application/yaml",
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+        }
+    }
+
+    /// The file extension of the exported document.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Yaml => "yml",
+            ExportFormat::Csv => "csv",
+        }
+    }
+
+    /// The `Content-Disposition` that makes browsers download the export of `server_id`.
+    pub fn content_disposition(self, server_id: &str) -> HeaderValue {
+        let server_id: String = server_id
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let value = format!(
+            "attachment; filename=\"names-{}.{}\"",
+            server_id,
+            self.extension()
+        );
+        HeaderValue::from_str(&value).expect("only ASCII is left in the filename")
+    }
+}
+
+impl From<ExportFormat> for Format {
+    fn from(format: ExportFormat) -> Self {
+        match format {
+            ExportFormat::Yaml => Format::Yaml,
+            ExportFormat::Csv => Format::Csv,
+        }
+    }
+}
+
+/// Where an export has got to.
+struct Cursor {
+    db: Arc<DatabaseConnection>,
+    server_id: String,
+    /// The Discord ID of the last name exported so far, if any
+    after: Option<DiscordId>,
+    done: bool,
+}
+
+/// Streams the names used in a server as a document in `format`, ordered by Discord ID.
+///
+/// The names are read `EXPORT_BATCH_SIZE` at a time, each batch starting after the last Discord
+/// ID of the one before, so names added or removed while the export runs don't shift batches.
+///
+/// # Arguments
+///
+/// * `db` - The connection to read the names with, for as long as the stream is read.
+/// * `server_id` - The server to export the names of.
+/// * `format` - The format of the document.
+///
+/// # Returns
+///
+/// A stream of the pieces of the document, the first of which is its header.
+pub fn export_names(
+    db: Arc<DatabaseConnection>,
+    server_id: String,
+    format: ExportFormat,
+) -> impl Stream<Item = Result<String, NameServiceError>> + Send + 'static {
+    let writer = NamesWriter::new(format.into());
+    let header = stream::once(std::future::ready(Ok(writer.header())));
+    let cursor = Cursor {
+        db,
+        server_id,
+        after: None,
+        done: false,
+    };
+    let entries = stream::try_unfold(cursor, move |mut cursor| async move {
+        if cursor.done {
+            return Ok(None);
+        }
+        let mut select = live_names()
+            .filter(name::Column::ServerId.eq(cursor.server_id.as_str()))
+            .order_by_asc(name::Column::DiscordId)
+            .limit(EXPORT_BATCH_SIZE);
+        if let Some(after) = cursor.after {
+            select = select.filter(name::Column::DiscordId.gt(after));
+        }
+        let models = select.all(cursor.db.as_ref()).await?;
+
+        cursor.done = (models.len() as u64) < EXPORT_BATCH_SIZE;
+        cursor.after = models.last().map(|model| model.discord_id);
+        let batch = models
+            .iter()
+            .map(|model| writer.entry(model.discord_id.get(), &model.name))
+            .collect::<Result<String, _>>()
+            .map_err(|err| NameServiceError::MalformedData(err.to_string()))?;
+        Ok(Some((batch, cursor)))
+    });
+    header.chain(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downloads_are_named_after_the_server() {
+        assert_eq!(
+            ExportFormat::Csv.content_disposition("guild-1"),
+            "attachment; filename=\"names-guild-1.csv\""
+        );
+        assert_eq!(
+            ExportFormat::Yaml.content_disposition("a\"b/c"),
+            "attachment; filename=\"names-a_b_c.yml\""
+        );
+    }
+}
@@ -1,30 +1,59 @@
+use crate::audit::{self, AuditAction};
 use crate::entities::*;
+use crate::server::{self as registry, ServerId};
+use cache::{Lookup, NameCache};
+use duplicates::DuplicateGroup;
+use names_format::{Names, NamesMerge};
+use pagination::{PageParams, Paginated, SortParams};
+use sea_orm::prelude::{DateTimeUtc, DateTimeWithTimeZone};
+use sea_orm::sea_query::{Expr, Func};
 use sea_orm::*;
-use std::collections::HashMap;
+use std::collections::HashSet;
+use typed_ids::{DiscordId, EntityId};
 
 pub mod api;
+pub mod cache;
+pub mod duplicates;
+pub mod export;
 pub mod web;
 
+/// How many names a search returns, unless asked otherwise.
+pub const DEFAULT_SEARCH_LIMIT: u64 = 20;
+
+/// The ID of a name entry.
+pub type NameId = EntityId<name::Entity>;
+
 #[derive(Debug, PartialEq, Clone, Eq, Hash)]
 pub struct Name {
-    id: u32,
-    discord_id: u64,
+    id: NameId,
+    discord_id: DiscordId,
     name: String,
     server_id: String,
+    version: i32,
+    /// The name of the registered server `server_id` refers to
+    server_name: Option<String>,
 }
 
 impl Name {
-    pub fn new(id: u32, discord_id: u64, name: String, server_id: String) -> Self {
+    pub fn new(
+        id: NameId,
+        discord_id: DiscordId,
+        name: String,
+        server_id: String,
+        version: i32,
+    ) -> Self {
         Self {
             id,
             discord_id,
             name,
             server_id,
+            version,
+            server_name: None,
         }
     }
 
     /// Returns the Discord ID of the name.
-    pub fn discord_id(&self) -> u64 {
+    pub fn discord_id(&self) -> DiscordId {
         self.discord_id
     }
 
@@ -39,9 +68,74 @@ impl Name {
     }
 
     /// Returns the ID of the name.
-    pub fn id(&self) -> u32 {
+    pub fn id(&self) -> NameId {
         self.id
     }
+
+    /// Returns the version of the name, which every edit, deletion and restore increments. Edits
+    /// made knowing an older version fail, rather than overwriting changes their author hasn't seen.
+    pub fn version(&self) -> i32 {
+        self.version
+    }
+
+    /// Returns the display name of the server the name is used in, if it is registered.
+    pub fn server_name(&self) -> Option<&str> {
+        self.server_name.as_deref()
+    }
+}
+
+/// A name in the trash, which can be restored until it is purged.
+#[derive(Debug, PartialEq, Clone, Eq)]
+pub struct TrashedName {
+    name: Name,
+    deleted_at: DateTimeWithTimeZone,
+}
+
+impl TrashedName {
+    /// Returns the name as it was when it was deleted.
+    pub fn name(&self) -> &Name {
+        &self.name
+    }
+
+    /// Returns when the name was deleted.
+    pub fn deleted_at(&self) -> DateTimeWithTimeZone {
+        self.deleted_at
+    }
+}
+
+/// Fields that lists of names can be sorted by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NameSortField {
+    Id,
+    Name,
+    DiscordId,
+    ServerId,
+}
+
+/// Which names to list, in which order, and which page of them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct NameQuery {
+    /// Only names used in this server
+    pub server_id: Option<String>,
+    /// Only names used in this registered server
+    pub registered_server_id: Option<ServerId>,
+    /// Only the names of this user
+    pub discord_id: Option<DiscordId>,
+    /// Only names containing this text, ignoring case
+    pub search: Option<String>,
+    pub sort: SortParams<NameSortField>,
+    pub page: PageParams,
+}
+
+impl NameQuery {
+    /// Whether the query leaves out any names, rather than only paging through all of them.
+    pub fn is_filtered(&self) -> bool {
+        self.server_id.is_some()
+            || self.registered_server_id.is_some()
+            || self.discord_id.is_some()
+            || self.search.is_some()
+    }
 }
 
 /// Error type for NameService operations.
@@ -49,36 +143,82 @@ impl Name {
 pub enum NameServiceError {
     /// Represents a duplicate entry error (Discord ID + Server ID combination already exists).
     #[error("Entry with Discord ID {0} and Server ID '{1}' already exists")]
-    DuplicateEntryError(u64, String),
+    DuplicateEntryError(DiscordId, String),
     /// Represents a database error.
     #[error("Database error: {0}")]
     Database(#[from] sea_orm::DbErr),
     /// Represents a name not found error.
     #[error("Name entry with ID {0} not found")]
-    NameNotFound(u32),
+    NameNotFound(NameId),
     /// Represents malformed data error during bulk operations.
     #[error("Malformed data: {0}")]
     MalformedData(String),
+    /// Represents an edit of a name that has been changed since the editor last saw it.
+    #[error("Name entry with ID {0} has been changed by someone else in the meantime")]
+    VersionConflict(NameId),
+    /// Represents a similarity threshold outside of (0, 1].
+    #[error("The similarity threshold {0} isn't more than 0 and at most 1")]
+    InvalidThreshold(f64),
 }
 
 pub struct NameService<'a> {
     db: &'a sea_orm::DatabaseConnection,
+    /// Who the changes made through the service are recorded as made by
+    actor: &'a str,
+    /// The cache lookups go through and changes invalidate, if any
+    cache: Option<&'a NameCache>,
 }
 
 impl From<name::Model> for Name {
     fn from(model: name::Model) -> Self {
         Name::new(
-            model.id as u32,
-            model.discord_id as u64,
+            model.id,
+            model.discord_id,
             model.name,
             model.server_id,
+            model.version,
         )
     }
 }
 
-impl NameService<'_> {
-    pub fn new(db: &sea_orm::DatabaseConnection) -> NameService {
-        NameService { db }
+/// Names loaded along with the server they are registered in are shown with its name.
+impl From<(name::Model, Option<server::Model>)> for Name {
+    fn from((model, server): (name::Model, Option<server::Model>)) -> Self {
+        Name {
+            server_name: server.map(|server| server.name),
+            ..Name::from(model)
+        }
+    }
+}
+
+/// Selects the names that aren't in the trash, which is all that the service shows unless asked
+/// for the trash itself.
+pub(crate) fn live_names() -> Select<name::Entity> {
+    name::Entity::find().filter(name::Column::DeletedAt.is_null())
+}
+
+impl<'a> NameService<'a> {
+    pub fn new(db: &'a sea_orm::DatabaseConnection) -> NameService<'a> {
+        NameService {
+            db,
+            actor: audit::SYSTEM_ACTOR,
+            cache: None,
+        }
+    }
+
+    /// Answers the lookups of all names and of the names of a server from `cache`, and clears
+    /// it whenever names are changed through the service.
+    pub fn with_cache(self, cache: &'a NameCache) -> Self {
+        Self {
+            cache: Some(cache),
+            ..self
+        }
+    }
+
+    /// Records the changes made through the service as made by `actor` in the audit log,
+    /// rather than by the server itself.
+    pub fn acting_as(self, actor: &'a str) -> Self {
+        Self { actor, ..self }
     }
 
     /// Creates a new name entry in the database.
@@ -94,31 +234,23 @@ impl NameService<'_> {
     #[tracing::instrument(skip(self))]
     pub async fn create_name(
         &self,
-        discord_id: u64,
+        discord_id: DiscordId,
         name: String,
         server_id: String,
     ) -> Result<Name, NameServiceError> {
-        // Check if Discord ID + Server ID combination already exists
-        if self.entry_exists(discord_id, &server_id).await? {
-            return Err(NameServiceError::DuplicateEntryError(discord_id, server_id));
-        }
-
-        let active_model = name::ActiveModel {
-            discord_id: ActiveValue::Set(discord_id as i64),
-            name: ActiveValue::Set(name.clone()),
-            server_id: ActiveValue::Set(server_id.clone()),
-            ..Default::default()
-        };
-        let created_model = active_model.insert(self.db).await?;
-        Ok(Name::from(created_model))
+        self.insert_name(discord_id, name, server_id, AuditAction::Create)
+            .await
     }
 
-    /// Creates multiple name entries in the database from a YAML mapping.
-    /// Skips entries that already exist (Discord ID + Server ID combination).
+    /// Creates multiple name entries in the database from a YAML names document, in the format
+    /// shared with the bot's `real_names.yml`.
+    /// Skips entries that already exist (Discord ID + Server ID combination), as worked out by
+    /// [`NamesMerge`], which the bot's `import-names` command shares.
     ///
     /// # Arguments
     ///
-    /// * `yaml_content` - The YAML content as a string containing discord_id: name mappings.
+    /// * `yaml_content` - The YAML content as a string containing discord_id: name mappings,
+    ///   optionally under a `names` key.
     /// * `server_id` - The server ID where the names are used.
     ///
     /// # Returns
@@ -130,20 +262,41 @@ impl NameService<'_> {
         yaml_content: &str,
         server_id: String,
     ) -> Result<(usize, usize, Vec<String>), NameServiceError> {
-        // Parse YAML content
-        let yaml_map: HashMap<u64, String> = serde_yaml::from_str(yaml_content)
-            .map_err(|e| NameServiceError::MalformedData(format!("Invalid YAML format: {}", e)))?;
+        let names = Names::from_yaml(yaml_content)
+            .map_err(|e| NameServiceError::MalformedData(e.to_string()))?;
+        let existing: Names = self
+            .get_names_by_server(&server_id)
+            .await?
+            .into_iter()
+            .map(|name| (name.discord_id.get(), name.name))
+            .collect();
+        let merge = NamesMerge::new(&existing, &names);
+        for conflict in &merge.conflicts {
+            tracing::info!(
+                "Skipped existing entry for Discord ID {} in server {}",
+                conflict.discord_id,
+                server_id
+            );
+        }
 
         let mut created_count = 0;
-        let mut skipped_count = 0;
+        let mut skipped_count = merge.skipped();
         let mut errors = Vec::new();
 
-        for (discord_id, name) in yaml_map {
+        for (discord_id, name) in merge.added {
+            let discord_id = match DiscordId::new(discord_id) {
+                Ok(discord_id) => discord_id,
+                Err(e) => {
+                    errors.push(format!("Failed to create entry for {}: {}", name, e));
+                    continue;
+                }
+            };
             match self
-                .create_name(discord_id, name.clone(), server_id.clone())
+                .insert_name(discord_id, name, server_id.clone(), AuditAction::BulkCreate)
                 .await
             {
                 Ok(_) => created_count += 1,
+                // Created since the existing names were loaded
                 Err(NameServiceError::DuplicateEntryError(_, _)) => {
                     skipped_count += 1;
                     tracing::info!(
@@ -176,6 +329,8 @@ impl NameService<'_> {
     /// * `id` - The ID of the name entry to edit.
     /// * `new_name` - The new name for the entry.
     /// * `new_server_id` - The new server ID for the entry.
+    /// * `expected_version` - The version of the entry the edit was made to, if known. The edit
+    ///   fails with `VersionConflict` if the entry has been changed since.
     ///
     /// # Returns
     ///
@@ -183,21 +338,75 @@ impl NameService<'_> {
     #[tracing::instrument(skip(self))]
     pub async fn edit_name_by_id(
         &self,
-        id: u32,
+        id: NameId,
         new_name: String,
         new_server_id: String,
+        expected_version: Option<i32>,
     ) -> Result<Name, NameServiceError> {
-        let name_to_update = name::Entity::find_by_id(id as i32)
+        let name_to_update = live_names()
+            .filter(name::Column::Id.eq(id))
             .one(self.db)
             .await?
             .ok_or(NameServiceError::NameNotFound(id))?;
+        let expected_version = expected_version.unwrap_or(name_to_update.version);
+        if name_to_update.version != expected_version {
+            return Err(NameServiceError::VersionConflict(id));
+        }
+
+        // Moving the entry to another server must not clash with the user's name there
+        if name_to_update.server_id != new_server_id
+            && self
+                .entry_exists(name_to_update.discord_id, &new_server_id)
+                .await?
+        {
+            return Err(NameServiceError::DuplicateEntryError(
+                name_to_update.discord_id,
+                new_server_id,
+            ));
+        }
 
-        let mut active_model: name::ActiveModel = name_to_update.into();
-        active_model.name = ActiveValue::Set(new_name.clone());
-        active_model.server_id = ActiveValue::Set(new_server_id.clone());
-        let updated_model = active_model.update(self.db).await?;
+        let before = Name::from(name_to_update);
 
-        Ok(Name::from(updated_model))
+        let txn = self.db.begin().await?;
+        let registered = registry::registered_server_for(&txn, &new_server_id).await?;
+        // Only update the version that was checked, in case another edit got in in between
+        let result = name::Entity::update_many()
+            .col_expr(name::Column::Name, Expr::value(new_name))
+            .col_expr(name::Column::ServerId, Expr::value(new_server_id))
+            .col_expr(
+                name::Column::RegisteredServerId,
+                Expr::value(registered.map(|server| server.id)),
+            )
+            .col_expr(
+                name::Column::Version,
+                Expr::col(name::Column::Version).add(1),
+            )
+            .filter(name::Column::Id.eq(id))
+            .filter(name::Column::Version.eq(expected_version))
+            .filter(name::Column::DeletedAt.is_null())
+            .exec(&txn)
+            .await?;
+        if result.rows_affected == 0 {
+            return Err(NameServiceError::VersionConflict(id));
+        }
+        let updated = name::Entity::find_by_id(id)
+            .find_also_related(server::Entity)
+            .one(&txn)
+            .await?
+            .map(Name::from)
+            .ok_or(NameServiceError::NameNotFound(id))?;
+        audit::record(
+            &txn,
+            self.actor,
+            AuditAction::Update,
+            Some(&before),
+            Some(&updated),
+        )
+        .await?;
+        txn.commit().await?;
+        self.invalidate_cache();
+
+        Ok(updated)
     }
 
     /// Retrieves all name entries from the database.
@@ -207,13 +416,27 @@ impl NameService<'_> {
     /// A `Result` containing a vector of `Name` if successful, or an error otherwise.
     #[tracing::instrument(skip(self))]
     pub async fn get_all_names(&self) -> Result<Vec<Name>, NameServiceError> {
-        let names = name::Entity::find()
-            .all(self.db)
-            .await?
-            .into_iter()
-            .map(Name::from)
-            .collect();
-        Ok(names)
+        self.cached_names(Lookup::All, async {
+            let names = live_names()
+                .find_also_related(server::Entity)
+                .all(self.db)
+                .await?
+                .into_iter()
+                .map(Name::from)
+                .collect();
+            Ok(names)
+        })
+        .await
+    }
+
+    /// Counts the name entries in the database.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the number of names if successful, or an error otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn count_names(&self) -> Result<u64, NameServiceError> {
+        Ok(live_names().count(self.db).await?)
     }
 
     /// Retrieves name entries from the database filtered by server ID.
@@ -230,8 +453,102 @@ impl NameService<'_> {
         &self,
         server_id: &str,
     ) -> Result<Vec<Name>, NameServiceError> {
-        let names = name::Entity::find()
-            .filter(name::Column::ServerId.eq(server_id))
+        self.cached_names(Lookup::Server(server_id.to_string()), async {
+            let names = live_names()
+                .filter(name::Column::ServerId.eq(server_id))
+                .find_also_related(server::Entity)
+                .all(self.db)
+                .await?
+                .into_iter()
+                .map(Name::from)
+                .collect();
+            Ok(names)
+        })
+        .await
+    }
+
+    /// Retrieves one page of the name entries matching a query, along with how many match.
+    ///
+    /// Names are ordered by ID unless another sort field is requested, in which case the ID
+    /// breaks ties so that pages never overlap.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The filters, sort order and page to return.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the requested page of `Name` if successful, or an error otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn list(&self, query: &NameQuery) -> Result<Paginated<Name>, NameServiceError> {
+        self.cached_page(query, async {
+            let mut select = live_names();
+            if let Some(server_id) = &query.server_id {
+                select = select.filter(name::Column::ServerId.eq(server_id.as_str()));
+            }
+            if let Some(registered_server_id) = query.registered_server_id {
+                select = select.filter(name::Column::RegisteredServerId.eq(registered_server_id));
+            }
+            if let Some(discord_id) = query.discord_id {
+                select = select.filter(name::Column::DiscordId.eq(discord_id));
+            }
+            if let Some(search) = &query.search {
+                select = select.filter(
+                    Expr::expr(Func::lower(Expr::col((name::Entity, name::Column::Name))))
+                        .like(contains_pattern(&search.to_lowercase())),
+                );
+            }
+            let field = query.sort.field_or(NameSortField::Id);
+            let column = match field {
+                NameSortField::Id => name::Column::Id,
+                NameSortField::Name => name::Column::Name,
+                NameSortField::DiscordId => name::Column::DiscordId,
+                NameSortField::ServerId => name::Column::ServerId,
+            };
+            select = select.order_by(column, query.sort.order.into());
+            if field != NameSortField::Id {
+                select = select.order_by_asc(name::Column::Id);
+            }
+
+            let select = select.find_also_related(server::Entity);
+            let names = pagination::paginate(select, query.page, self.db).await?;
+            Ok(names.map(Name::from))
+        })
+        .await
+    }
+
+    /// Searches the names for `query`: names containing it, ignoring case, and names spelled
+    /// similarly enough. Names containing it come first, then the more similar names.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The text to search for. A blank query finds nothing.
+    /// * `limit` - How many names to return at most.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the best matching `Name`s if successful, or an error otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn search(&self, query: &str, limit: u64) -> Result<Vec<Name>, NameServiceError> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Both use the trigram index on the names
+        let contains =
+            Expr::cust_with_values(r#""name"."name" ILIKE ?"#, [contains_pattern(query)]);
+        let similar = Expr::cust_with_values(r#""name"."name" % ?"#, [query]);
+        let names = live_names()
+            .filter(Condition::any().add(contains.clone()).add(similar))
+            .order_by_desc(contains)
+            .order_by_desc(Expr::cust_with_values(
+                r#"similarity("name"."name", ?)"#,
+                [query],
+            ))
+            .order_by_asc(name::Column::Id)
+            .limit(limit)
+            .find_also_related(server::Entity)
             .all(self.db)
             .await?
             .into_iter()
@@ -240,7 +557,8 @@ impl NameService<'_> {
         Ok(names)
     }
 
-    /// Deletes a name entry by their ID.
+    /// Moves a name entry to the trash by their ID, from where it can be restored until it is
+    /// purged.
     ///
     /// # Arguments
     ///
@@ -250,18 +568,11 @@ impl NameService<'_> {
     ///
     /// A `Result` containing the deleted `Name` if successful, or an error otherwise.
     #[tracing::instrument(skip(self))]
-    pub async fn delete_name_by_id(&self, id: u32) -> Result<Name, NameServiceError> {
-        let name_to_delete = name::Entity::find_by_id(id as i32)
-            .one(self.db)
-            .await?
-            .ok_or(NameServiceError::NameNotFound(id))?;
-
-        let name_copy = Name::from(name_to_delete.clone());
-        name::Entity::delete_by_id(id as i32).exec(self.db).await?;
-        Ok(name_copy)
+    pub async fn delete_name_by_id(&self, id: NameId) -> Result<Name, NameServiceError> {
+        self.remove_name(id, AuditAction::Delete).await
     }
 
-    /// Deletes multiple name entries by their IDs.
+    /// Moves multiple name entries to the trash by their IDs.
     ///
     /// # Arguments
     ///
@@ -273,13 +584,13 @@ impl NameService<'_> {
     #[tracing::instrument(skip(self))]
     pub async fn bulk_delete_names(
         &self,
-        ids: &[u32],
+        ids: &[NameId],
     ) -> Result<(usize, Vec<String>), NameServiceError> {
         let mut deleted_count = 0;
         let mut failed_deletes = Vec::new();
 
         for &id in ids {
-            match self.delete_name_by_id(id).await {
+            match self.remove_name(id, AuditAction::BulkDelete).await {
                 Ok(_) => deleted_count += 1,
                 Err(NameServiceError::NameNotFound(_)) => {
                     failed_deletes.push(format!("Name with ID {} not found", id));
@@ -295,7 +606,448 @@ impl NameService<'_> {
         Ok((deleted_count, failed_deletes))
     }
 
-    /// Checks if a name entry with the given Discord ID and Server ID combination already exists.
+    /// Moves multiple name entries to another server, all at once or not at all.
+    ///
+    /// No user may end up with two names in the server: the move fails if two of the entries
+    /// belong to the same user, or if a user already has a name there that isn't being moved.
+    /// Entries that are already in the server are left as they are.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - A slice of IDs of the name entries to move.
+    /// * `server_id` - The server ID to move them to.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the moved `Name`s, ordered by ID, if successful, or an error
+    /// otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn bulk_update_server(
+        &self,
+        ids: &[NameId],
+        server_id: String,
+    ) -> Result<Vec<Name>, NameServiceError> {
+        let txn = self.db.begin().await?;
+        let selected = live_names()
+            .filter(name::Column::Id.is_in(ids.iter().copied()))
+            .order_by_asc(name::Column::Id)
+            .all(&txn)
+            .await?;
+        if let Some(&missing) = ids
+            .iter()
+            .find(|&&id| !selected.iter().any(|model| model.id == id))
+        {
+            return Err(NameServiceError::NameNotFound(missing));
+        }
+
+        let moving: Vec<Name> = selected
+            .into_iter()
+            .filter(|model| model.server_id != server_id)
+            .map(Name::from)
+            .collect();
+        if moving.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut discord_ids = HashSet::new();
+        for name in &moving {
+            if !discord_ids.insert(name.discord_id()) {
+                return Err(NameServiceError::DuplicateEntryError(
+                    name.discord_id(),
+                    server_id,
+                ));
+            }
+        }
+        if let Some(taken) = live_names()
+            .filter(name::Column::ServerId.eq(&server_id))
+            .filter(name::Column::DiscordId.is_in(discord_ids))
+            .one(&txn)
+            .await?
+        {
+            return Err(NameServiceError::DuplicateEntryError(
+                taken.discord_id,
+                server_id,
+            ));
+        }
+
+        let registered = registry::registered_server_for(&txn, &server_id).await?;
+        let moving_ids: Vec<NameId> = moving.iter().map(Name::id).collect();
+        name::Entity::update_many()
+            .col_expr(name::Column::ServerId, Expr::value(server_id))
+            .col_expr(
+                name::Column::RegisteredServerId,
+                Expr::value(registered.map(|server| server.id)),
+            )
+            .col_expr(
+                name::Column::Version,
+                Expr::col(name::Column::Version).add(1),
+            )
+            .filter(name::Column::Id.is_in(moving_ids.iter().copied()))
+            .exec(&txn)
+            .await?;
+        let moved: Vec<Name> = name::Entity::find()
+            .filter(name::Column::Id.is_in(moving_ids))
+            .order_by_asc(name::Column::Id)
+            .find_also_related(server::Entity)
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(Name::from)
+            .collect();
+        for (before, after) in moving.iter().zip(&moved) {
+            audit::record(
+                &txn,
+                self.actor,
+                AuditAction::BulkUpdate,
+                Some(before),
+                Some(after),
+            )
+            .await?;
+        }
+        txn.commit().await?;
+        self.invalidate_cache();
+
+        Ok(moved)
+    }
+
+    /// Finds the names that are likely duplicates: the names of users with names in several
+    /// servers, and the pairs of names of different users that are at least `threshold`
+    /// similar.
+    ///
+    /// # Arguments
+    ///
+    /// * `threshold` - How similar names have to be, more than 0 and at most 1 for the same
+    ///   words.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the groups of likely duplicates if successful, or an error
+    /// otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn find_duplicates(
+        &self,
+        threshold: f64,
+    ) -> Result<Vec<DuplicateGroup>, NameServiceError> {
+        if !(threshold > 0.0 && threshold <= 1.0) {
+            return Err(NameServiceError::InvalidThreshold(threshold));
+        }
+        let names = self.get_all_names().await?;
+        Ok(duplicates::group_duplicates(names, threshold))
+    }
+
+    /// Merges duplicate names into one of them: the name to keep stays, the others are moved to
+    /// the trash. Either all of them are moved, or none are.
+    ///
+    /// # Arguments
+    ///
+    /// * `keep` - The ID of the name to keep.
+    /// * `duplicates` - The IDs of its duplicates, which may include `keep`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the kept `Name` if successful, or an error otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn merge_names(
+        &self,
+        keep: NameId,
+        duplicates: &[NameId],
+    ) -> Result<Name, NameServiceError> {
+        let txn = self.db.begin().await?;
+        let kept = live_names()
+            .filter(name::Column::Id.eq(keep))
+            .find_also_related(server::Entity)
+            .one(&txn)
+            .await?
+            .map(Name::from)
+            .ok_or(NameServiceError::NameNotFound(keep))?;
+        let merged_ids: Vec<NameId> = duplicates
+            .iter()
+            .copied()
+            .filter(|&id| id != keep)
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let merged: Vec<Name> = live_names()
+            .filter(name::Column::Id.is_in(merged_ids.iter().copied()))
+            .order_by_asc(name::Column::Id)
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(Name::from)
+            .collect();
+        if let Some(&missing) = merged_ids
+            .iter()
+            .find(|&&id| !merged.iter().any(|name| name.id() == id))
+        {
+            return Err(NameServiceError::NameNotFound(missing));
+        }
+
+        name::Entity::update_many()
+            .col_expr(name::Column::DeletedAt, Expr::current_timestamp().into())
+            .col_expr(
+                name::Column::Version,
+                Expr::col(name::Column::Version).add(1),
+            )
+            .filter(name::Column::Id.is_in(merged_ids))
+            .exec(&txn)
+            .await?;
+        for name in &merged {
+            audit::record(&txn, self.actor, AuditAction::Merge, Some(name), None).await?;
+        }
+        txn.commit().await?;
+        self.invalidate_cache();
+
+        Ok(kept)
+    }
+
+    /// Retrieves one page of the names in the trash, most recently deleted first.
+    ///
+    /// # Arguments
+    ///
+    /// * `page` - The page to return.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the requested page of `TrashedName` if successful, or an error
+    /// otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn list_trash(
+        &self,
+        page: PageParams,
+    ) -> Result<Paginated<TrashedName>, NameServiceError> {
+        let select = name::Entity::find()
+            .filter(name::Column::DeletedAt.is_not_null())
+            .order_by_desc(name::Column::DeletedAt)
+            .order_by_desc(name::Column::Id);
+        let names = pagination::paginate(select, page, self.db).await?;
+        Ok(names.map(|model| TrashedName {
+            deleted_at: model
+                .deleted_at
+                .expect("names in the trash have been deleted"),
+            name: Name::from(model),
+        }))
+    }
+
+    /// Takes a name entry back out of the trash by their ID. Fails with `DuplicateEntryError`
+    /// if the user has been given another name in the same server since.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the name entry in the trash.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the restored `Name` if successful, or an error otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn restore_name_by_id(&self, id: NameId) -> Result<Name, NameServiceError> {
+        let trashed = name::Entity::find_by_id(id)
+            .filter(name::Column::DeletedAt.is_not_null())
+            .one(self.db)
+            .await?
+            .ok_or(NameServiceError::NameNotFound(id))?;
+        if self
+            .entry_exists(trashed.discord_id, &trashed.server_id)
+            .await?
+        {
+            return Err(NameServiceError::DuplicateEntryError(
+                trashed.discord_id,
+                trashed.server_id,
+            ));
+        }
+
+        // Edits made before the name was deleted are outdated once it is back
+        let txn = self.db.begin().await?;
+        let restored = name::Entity::update_many()
+            .col_expr(
+                name::Column::DeletedAt,
+                Expr::value(Option::<DateTimeWithTimeZone>::None),
+            )
+            .col_expr(
+                name::Column::Version,
+                Expr::col(name::Column::Version).add(1),
+            )
+            .filter(name::Column::Id.eq(id))
+            .filter(name::Column::DeletedAt.is_not_null())
+            .exec_with_returning(&txn)
+            .await?
+            .pop()
+            .ok_or(NameServiceError::NameNotFound(id))?;
+        let server = match restored.registered_server_id {
+            Some(id) => server::Entity::find_by_id(id).one(&txn).await?,
+            None => None,
+        };
+        let restored = Name::from((restored, server));
+        audit::record(
+            &txn,
+            self.actor,
+            AuditAction::Restore,
+            None,
+            Some(&restored),
+        )
+        .await?;
+        txn.commit().await?;
+        self.invalidate_cache();
+
+        Ok(restored)
+    }
+
+    /// Removes a name entry in the trash for good by their ID, without waiting for it to expire.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The ID of the name entry in the trash.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the purged `Name` if successful, or an error otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn purge_name_by_id(&self, id: NameId) -> Result<Name, NameServiceError> {
+        let txn = self.db.begin().await?;
+        let purged = name::Entity::delete_many()
+            .filter(name::Column::Id.eq(id))
+            .filter(name::Column::DeletedAt.is_not_null())
+            .exec_with_returning(&txn)
+            .await?
+            .pop()
+            .map(Name::from)
+            .ok_or(NameServiceError::NameNotFound(id))?;
+        audit::record(&txn, self.actor, AuditAction::Purge, Some(&purged), None).await?;
+        txn.commit().await?;
+
+        Ok(purged)
+    }
+
+    /// Removes the names that were moved to the trash before `deleted_before` for good.
+    ///
+    /// # Arguments
+    ///
+    /// * `deleted_before` - When the names to purge were deleted at the latest.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing how many names were purged if successful, or an error otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn purge_trash(&self, deleted_before: DateTimeUtc) -> Result<u64, NameServiceError> {
+        let expired: Vec<Name> = name::Entity::find()
+            .filter(name::Column::DeletedAt.lt(deleted_before))
+            .all(self.db)
+            .await?
+            .into_iter()
+            .map(Name::from)
+            .collect();
+        if expired.is_empty() {
+            return Ok(0);
+        }
+
+        let txn = self.db.begin().await?;
+        let purged = name::Entity::delete_many()
+            .filter(name::Column::Id.is_in(expired.iter().map(Name::id)))
+            .exec(&txn)
+            .await?;
+        for name in &expired {
+            audit::record(&txn, self.actor, AuditAction::Purge, Some(name), None).await?;
+        }
+        txn.commit().await?;
+
+        Ok(purged.rows_affected)
+    }
+
+    /// Answers the `lookup` of a list of names from the cache if the service has one, loading it
+    /// with `load` otherwise.
+    async fn cached_names(
+        &self,
+        lookup: Lookup,
+        load: impl Future<Output = Result<Vec<Name>, NameServiceError>>,
+    ) -> Result<Vec<Name>, NameServiceError> {
+        match self.cache {
+            Some(cache) => cache.names(lookup, load).await,
+            None => load.await,
+        }
+    }
+
+    /// Answers the page of names matching `query` from the cache if the service has one,
+    /// loading it with `load` otherwise.
+    async fn cached_page(
+        &self,
+        query: &NameQuery,
+        load: impl Future<Output = Result<Paginated<Name>, NameServiceError>>,
+    ) -> Result<Paginated<Name>, NameServiceError> {
+        match self.cache {
+            Some(cache) => cache.page(query.clone(), load).await,
+            None => load.await,
+        }
+    }
+
+    /// Clears the cache, if the service has one, after names changed.
+    fn invalidate_cache(&self) {
+        if let Some(cache) = self.cache {
+            cache.invalidate();
+        }
+    }
+
+    /// Inserts a name entry, unless the Discord ID + Server ID combination already exists, and
+    /// records it in the audit log as `action`.
+    async fn insert_name(
+        &self,
+        discord_id: DiscordId,
+        name: String,
+        server_id: String,
+        action: AuditAction,
+    ) -> Result<Name, NameServiceError> {
+        // Check if Discord ID + Server ID combination already exists
+        if self.entry_exists(discord_id, &server_id).await? {
+            return Err(NameServiceError::DuplicateEntryError(discord_id, server_id));
+        }
+
+        let txn = self.db.begin().await?;
+        let registered = registry::registered_server_for(&txn, &server_id).await?;
+        let active_model = name::ActiveModel {
+            discord_id: ActiveValue::Set(discord_id),
+            name: ActiveValue::Set(name),
+            server_id: ActiveValue::Set(server_id),
+            registered_server_id: ActiveValue::Set(registered.as_ref().map(|server| server.id)),
+            ..Default::default()
+        };
+        let created = Name::from((active_model.insert(&txn).await?, registered));
+        audit::record(&txn, self.actor, action, None, Some(&created)).await?;
+        txn.commit().await?;
+        self.invalidate_cache();
+
+        Ok(created)
+    }
+
+    /// Moves a name entry to the trash and records it in the audit log as `action`.
+    async fn remove_name(&self, id: NameId, action: AuditAction) -> Result<Name, NameServiceError> {
+        let name_to_delete = live_names()
+            .filter(name::Column::Id.eq(id))
+            .one(self.db)
+            .await?
+            .ok_or(NameServiceError::NameNotFound(id))?;
+        let deleted = Name::from(name_to_delete);
+
+        let txn = self.db.begin().await?;
+        let removed = name::Entity::update_many()
+            .col_expr(name::Column::DeletedAt, Expr::current_timestamp().into())
+            .col_expr(
+                name::Column::Version,
+                Expr::col(name::Column::Version).add(1),
+            )
+            .filter(name::Column::Id.eq(id))
+            .filter(name::Column::DeletedAt.is_null())
+            .exec(&txn)
+            .await?;
+        // Deleted by someone else since it was looked up
+        if removed.rows_affected == 0 {
+            return Err(NameServiceError::NameNotFound(id));
+        }
+        audit::record(&txn, self.actor, action, Some(&deleted), None).await?;
+        txn.commit().await?;
+        self.invalidate_cache();
+
+        Ok(deleted)
+    }
+
+    /// Checks if a name entry with the given Discord ID and Server ID combination already exists
+    /// outside the trash.
     ///
     /// # Arguments
     ///
@@ -308,11 +1060,11 @@ impl NameService<'_> {
     #[tracing::instrument(skip(self))]
     async fn entry_exists(
         &self,
-        discord_id: u64,
+        discord_id: DiscordId,
         server_id: &str,
     ) -> Result<bool, NameServiceError> {
-        let existing_name = name::Entity::find()
-            .filter(name::Column::DiscordId.eq(discord_id as i64))
+        let existing_name = live_names()
+            .filter(name::Column::DiscordId.eq(discord_id))
             .filter(name::Column::ServerId.eq(server_id))
             .one(self.db)
             .await?;
@@ -329,11 +1081,33 @@ impl NameService<'_> {
     ///
     /// A `Result` containing the `Name` if successful, or an error otherwise.
     #[tracing::instrument(skip(self))]
-    pub async fn get_name_by_id(&self, id: u32) -> Result<Name, NameServiceError> {
-        let name_model = name::Entity::find_by_id(id as i32)
+    pub async fn get_name_by_id(&self, id: NameId) -> Result<Name, NameServiceError> {
+        let name_model = live_names()
+            .filter(name::Column::Id.eq(id))
+            .find_also_related(server::Entity)
             .one(self.db)
             .await?
             .ok_or(NameServiceError::NameNotFound(id))?;
         Ok(Name::from(name_model))
     }
 }
+
+/// A `LIKE` pattern matching any text that contains `text`, which may itself contain wildcards.
+fn contains_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_patterns_match_wildcards_literally() {
+        assert_eq!(contains_pattern("ali"), "%ali%");
+        assert_eq!(contains_pattern("100%_a\\b"), "%100\\%\\_a\\\\b%");
+    }
+}
@@ -1,19 +1,33 @@
 use askama::Template;
 use axum::{
-    Form, Router,
-    extract::{RawQuery, State},
-    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
-    response::Html,
-    routing::get,
+    Extension, Form, Router,
+    body::Body,
+    extract::{Query, RawQuery, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode, header},
+    response::{Html, IntoResponse, Response},
+    routing::{get, patch, post},
 };
-use serde::Deserialize;
+use pagination::{PageParams, Paginated, SortParams};
+use serde::{Deserialize, Deserializer};
+use std::fmt::Display;
+use std::str::FromStr;
 use std::sync::Arc;
-
-use crate::name::{Name, NameService, NameServiceError};
+use typed_ids::DiscordId;
+
+use crate::audit;
+use crate::auth::CurrentUser;
+use crate::name::cache::NameCache;
+use crate::name::duplicates::{DEFAULT_SIMILARITY_THRESHOLD, DuplicateGroup, DuplicateReason};
+use crate::name::export::{ExportFormat, export_names};
+use crate::name::{
+    DEFAULT_SEARCH_LIMIT, Name, NameId, NameQuery, NameService, NameServiceError, NameSortField,
+    TrashedName,
+};
+use crate::server::ServerId;
 
 #[derive(Debug, Deserialize)]
 pub struct CreateNameForm {
-    discord_id: u64,
+    discord_id: DiscordId,
     name: String,
     server_id: String,
 }
@@ -22,6 +36,9 @@ pub struct CreateNameForm {
 pub struct EditNameForm {
     name: String,
     server_id: String,
+    /// The version of the name the form was filled in for
+    #[serde(default)]
+    version: Option<i32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -30,19 +47,66 @@ pub struct BulkAddForm {
     yaml_content: String,
 }
 
-/// Helper function to get all names, sort them using the provided function, and render them as a names table.
-/// This reduces code duplication across handlers that need to display sorted names.
-#[tracing::instrument(skip(name_service, sort_fn))]
-async fn render_names_table<F>(
-    name_service: &NameService<'_>,
-    sort_fn: F,
-) -> Result<String, NameError>
+/// Filters of the names table, as submitted by its filter form. Fields left empty don't filter.
+#[derive(Debug, Deserialize)]
+pub struct NamesTableQuery {
+    #[serde(default, deserialize_with = "empty_as_none")]
+    server_id: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    registered_server_id: Option<ServerId>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    discord_id: Option<DiscordId>,
+    #[serde(default, deserialize_with = "empty_as_none")]
+    search: Option<String>,
+}
+
+/// What to search the names for, as typed into the live search box.
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    #[serde(default)]
+    q: String,
+}
+
+/// What to export, as submitted by the export form.
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    server_id: String,
+    #[serde(default)]
+    format: ExportFormat,
+}
+
+/// How similar names have to be to be shown as duplicates, as submitted by the duplicates form.
+#[derive(Debug, Deserialize)]
+pub struct DuplicatesQuery {
+    #[serde(default, deserialize_with = "empty_as_none")]
+    threshold: Option<f64>,
+}
+
+/// Deserializes a form field, taking an empty one as missing.
+fn empty_as_none<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
 where
-    F: FnOnce(&mut Vec<Name>),
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
 {
-    let mut names = name_service.get_all_names().await?;
-    sort_fn(&mut names);
-    let table_template = NamesTableTemplate::new(names);
+    match Option::<String>::deserialize(deserializer)?
+        .as_deref()
+        .map(str::trim)
+    {
+        None | Some("") => Ok(None),
+        Some(value) => value.parse().map(Some).map_err(serde::de::Error::custom),
+    }
+}
+
+/// Helper function to get the page of names a query asks for and render them as a names table.
+/// This reduces code duplication across handlers that need to display names.
+#[tracing::instrument(skip(name_service))]
+async fn render_names_table(
+    name_service: &NameService<'_>,
+    query: &NameQuery,
+) -> Result<String, NameError> {
+    let names = name_service.list(query).await?;
+    let table_template = NamesTableTemplate::new(names, query.is_filtered());
     table_template.render().map_err(NameError::from)
 }
 
@@ -64,15 +128,81 @@ enum NameError {
     /// Represents an I/O error.
     #[error("I/O error: {0}")]
     Io(#[from] std::io::Error),
+    /// Represents a name that can't be restored, as the user has another name in its server.
+    #[error("The user already has another name in this server")]
+    RestoreConflict,
+    /// Represents a name that isn't in the trash, as it has been restored or purged already.
+    #[error("The name isn't in the trash")]
+    NotInTrash,
+    /// Represents a bulk move that would give a user two names in the same server.
+    #[error("Some of the users already have a name in the target server")]
+    BulkMoveConflict,
+    /// Represents a bulk move without a server to move the names to.
+    #[error("No server ID to move the names to")]
+    MissingServerId,
+    /// Represents a bulk move of names that have been deleted in the meantime.
+    #[error("Some of the selected names don't exist anymore")]
+    SelectionOutdated,
+    /// Represents a merge without a name to keep.
+    #[error("No name to keep")]
+    MissingNameToKeep,
+    /// Represents a merge of names that have been deleted in the meantime.
+    #[error("Some of the duplicates don't exist anymore")]
+    MergeOutdated,
 }
 
 impl axum::response::IntoResponse for NameError {
     fn into_response(self) -> axum::response::Response {
+        // Errors of the trash and bulk pages go above their tables, rather than replacing them
+        let retarget = match &self {
+            NameError::RestoreConflict | NameError::NotInTrash => Some("#trash-error"),
+            NameError::BulkMoveConflict
+            | NameError::MissingServerId
+            | NameError::SelectionOutdated => Some("#bulk-error"),
+            NameError::MissingNameToKeep
+            | NameError::MergeOutdated
+            | NameError::Service(NameServiceError::InvalidThreshold(_)) => {
+                Some("#duplicates-error")
+            }
+            _ => None,
+        };
         let (status_code, user_facing_error_message) = match self {
             NameError::DuplicateEntry => (
                 StatusCode::UNPROCESSABLE_ENTITY,
                 "A name entry already exists for this Discord ID and Server ID combination. Please use a different combination.",
             ),
+            NameError::RestoreConflict => (
+                StatusCode::CONFLICT,
+                "The user already has another name in this server. Delete that one before restoring this one.",
+            ),
+            NameError::NotInTrash => (
+                StatusCode::NOT_FOUND,
+                "The name isn't in the trash anymore. It may have been restored or removed for good already.",
+            ),
+            NameError::BulkMoveConflict => (
+                StatusCode::CONFLICT,
+                "Some of the selected users already have a name in that server, or more than one was selected for them. No names were moved.",
+            ),
+            NameError::MissingServerId => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Please enter the server ID to move the selected names to.",
+            ),
+            NameError::SelectionOutdated => (
+                StatusCode::NOT_FOUND,
+                "Some of the selected names have been deleted in the meantime. No names were moved.",
+            ),
+            NameError::MissingNameToKeep => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Please choose the name to keep.",
+            ),
+            NameError::MergeOutdated => (
+                StatusCode::NOT_FOUND,
+                "Some of the duplicates have been deleted in the meantime. No names were merged.",
+            ),
+            NameError::Service(NameServiceError::InvalidThreshold(_)) => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "The similarity threshold has to be more than 0 and at most 1.",
+            ),
             _ => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 "An unexpected error occurred while processing your request. Please try again later.",
@@ -91,6 +221,12 @@ impl axum::response::IntoResponse for NameError {
             HeaderName::from_static("hx-reswap"),
             HeaderValue::from_static("innerHTML"),
         );
+        if let Some(target) = retarget {
+            headers.insert(
+                HeaderName::from_static("hx-retarget"),
+                HeaderValue::from_static(target),
+            );
+        }
         response.headers_mut().extend(headers);
         response
     }
@@ -113,12 +249,14 @@ struct AddNameFormTemplate;
 #[derive(Template)]
 #[template(path = "names/names_table.html")]
 struct NamesTableTemplate {
-    names: Vec<Name>,
+    names: Paginated<Name>,
+    /// Whether the names are only those matching some filters
+    filtered: bool,
 }
 
 impl NamesTableTemplate {
-    pub fn new(names: Vec<Name>) -> Self {
-        Self { names }
+    pub fn new(names: Paginated<Name>, filtered: bool) -> Self {
+        Self { names, filtered }
     }
 }
 
@@ -146,6 +284,19 @@ impl EditNameFormTemplate {
     }
 }
 
+/// The row of a name that was changed by someone else while it was being edited, offering to
+/// overwrite their change or to keep it.
+#[derive(Template)]
+#[template(path = "names/edit_conflict.html")]
+struct EditConflictTemplate {
+    /// The name as it is now
+    current: Name,
+    /// The name that was submitted
+    name: String,
+    /// The server ID that was submitted
+    server_id: String,
+}
+
 #[derive(Template)]
 #[template(path = "names/name_row.html")]
 struct NameRowTemplate {
@@ -206,9 +357,44 @@ impl BulkDeleteTableTemplate {
     }
 }
 
+#[derive(Template)]
+#[template(path = "names/duplicates.html")]
+struct DuplicatesTemplate {
+    threshold: f64,
+}
+
+#[derive(Template)]
+#[template(path = "names/duplicates_table.html")]
+struct DuplicatesTableTemplate {
+    groups: Vec<DuplicateGroup>,
+    threshold: f64,
+}
+
+#[derive(Template)]
+#[template(path = "names/search_results.html")]
+struct SearchResultsTemplate {
+    names: Vec<Name>,
+}
+
+#[derive(Template)]
+#[template(path = "trash.html")]
+struct TrashTemplate {
+    retention_days: u64,
+}
+
+#[derive(Template)]
+#[template(path = "names/trash_table.html")]
+struct TrashTableTemplate {
+    names: Paginated<TrashedName>,
+}
+
 #[derive(Clone, Debug)]
 pub struct NameState {
     pub db: Arc<sea_orm::DatabaseConnection>,
+    /// How many days deleted names stay in the trash before they are purged
+    pub trash_retention_days: u64,
+    /// The cache of name lookups, shared with the JSON API
+    pub cache: NameCache,
 }
 
 /// Handler for the /names endpoint that displays all names in a table.
@@ -222,9 +408,12 @@ async fn names_handler() -> Result<Html<String>, NameError> {
 #[tracing::instrument(skip(state))]
 async fn create_name_handler(
     State(state): State<Arc<NameState>>,
+    current_user: Option<Extension<CurrentUser>>,
     Form(form): Form<CreateNameForm>,
 ) -> Result<Html<String>, NameError> {
-    let name_service = NameService::new(&state.db);
+    let name_service = NameService::new(&state.db)
+        .with_cache(&state.cache)
+        .acting_as(audit::actor(current_user.as_deref()));
 
     match name_service
         .create_name(form.discord_id, form.name, form.server_id)
@@ -232,10 +421,7 @@ async fn create_name_handler(
     {
         Ok(_) => {
             // Get updated names for the table and render
-            let table_html = render_names_table(&name_service, |names| {
-                names.sort_by_key(|name| name.id());
-            })
-            .await?;
+            let table_html = render_names_table(&name_service, &NameQuery::default()).await?;
             Ok(Html(table_html))
         }
         Err(NameServiceError::DuplicateEntryError(_, _)) => Err(NameError::DuplicateEntry),
@@ -254,17 +440,17 @@ async fn add_name_form_handler() -> Result<Html<String>, NameError> {
 #[tracing::instrument(skip(state))]
 async fn delete_name_handler(
     State(state): State<Arc<NameState>>,
-    axum::extract::Path(id): axum::extract::Path<u32>,
+    current_user: Option<Extension<CurrentUser>>,
+    axum::extract::Path(id): axum::extract::Path<NameId>,
 ) -> Result<Html<String>, NameError> {
-    let name_service = NameService::new(&state.db);
+    let name_service = NameService::new(&state.db)
+        .with_cache(&state.cache)
+        .acting_as(audit::actor(current_user.as_deref()));
 
     match name_service.delete_name_by_id(id).await {
         Ok(_) => {
             // Get updated names for the table and render
-            let table_html = render_names_table(&name_service, |names| {
-                names.sort_by_key(|name| name.id());
-            })
-            .await?;
+            let table_html = render_names_table(&name_service, &NameQuery::default()).await?;
             Ok(Html(table_html))
         }
         Err(err) => Err(NameError::Service(err)),
@@ -275,12 +461,15 @@ async fn delete_name_handler(
 #[tracing::instrument(skip(state))]
 async fn bulk_delete_names_handler(
     State(state): State<Arc<NameState>>,
+    current_user: Option<Extension<CurrentUser>>,
     RawQuery(query): RawQuery,
 ) -> Result<Html<String>, NameError> {
-    let name_service = NameService::new(&state.db);
+    let name_service = NameService::new(&state.db)
+        .with_cache(&state.cache)
+        .acting_as(audit::actor(current_user.as_deref()));
 
     // Parse query parameters manually to handle multiple values with the same key
-    let selected_ids: Vec<u32> = if let Some(query_str) = query {
+    let selected_ids: Vec<NameId> = if let Some(query_str) = query {
         query_str
             .split('&')
             .filter_map(|pair| {
@@ -298,20 +487,14 @@ async fn bulk_delete_names_handler(
 
     if selected_ids.is_empty() {
         // No names selected for deletion, just return the current table
-        let table_html = render_names_table(&name_service, |names| {
-            names.sort_by_key(|name| name.id());
-        })
-        .await?;
+        let table_html = render_names_table(&name_service, &NameQuery::default()).await?;
         return Ok(Html(table_html));
     }
 
     match name_service.bulk_delete_names(&selected_ids).await {
         Ok(_) => {
             // Get updated names for the table and render
-            let table_html = render_names_table(&name_service, |names| {
-                names.sort_by_key(|name| name.id());
-            })
-            .await?;
+            let table_html = render_names_table(&name_service, &NameQuery::default()).await?;
             Ok(Html(table_html))
         }
         Err(err) => Err(NameError::Service(err)),
@@ -322,9 +505,9 @@ async fn bulk_delete_names_handler(
 #[tracing::instrument(skip(state))]
 async fn edit_name_handler(
     State(state): State<Arc<NameState>>,
-    axum::extract::Path(id): axum::extract::Path<u32>,
+    axum::extract::Path(id): axum::extract::Path<NameId>,
 ) -> Result<Html<String>, NameError> {
-    let name_service = NameService::new(&state.db);
+    let name_service = NameService::new(&state.db).with_cache(&state.cache);
 
     match name_service.get_name_by_id(id).await {
         Ok(name) => {
@@ -335,17 +518,21 @@ async fn edit_name_handler(
     }
 }
 
-/// Handler for updating a name via PUT request.
+/// Handler for updating a name via PUT request. If the name has been changed since the form
+/// was filled in, responds with a 409 and a row to resolve the conflict with.
 #[tracing::instrument(skip(state))]
 async fn update_name_handler(
     State(state): State<Arc<NameState>>,
-    axum::extract::Path(id): axum::extract::Path<u32>,
+    current_user: Option<Extension<CurrentUser>>,
+    axum::extract::Path(id): axum::extract::Path<NameId>,
     Form(form): Form<EditNameForm>,
-) -> Result<Html<String>, NameError> {
-    let name_service = NameService::new(&state.db);
+) -> Result<(StatusCode, Html<String>), NameError> {
+    let name_service = NameService::new(&state.db)
+        .with_cache(&state.cache)
+        .acting_as(audit::actor(current_user.as_deref()));
 
     match name_service
-        .edit_name_by_id(id, form.name, form.server_id)
+        .edit_name_by_id(id, form.name.clone(), form.server_id.clone(), form.version)
         .await
     {
         Ok(_) => {
@@ -356,32 +543,88 @@ async fn update_name_handler(
             let row_template = NameRowTemplate::new(updated_name);
             let row_html = row_template.render().map_err(NameError::from)?;
 
-            Ok(Html(row_html))
+            Ok((StatusCode::OK, Html(row_html)))
+        }
+        Err(NameServiceError::VersionConflict(_)) => {
+            let template = EditConflictTemplate {
+                current: name_service.get_name_by_id(id).await?,
+                name: form.name,
+                server_id: form.server_id,
+            };
+            let conflict_html = template.render().map_err(NameError::from)?;
+            Ok((StatusCode::CONFLICT, Html(conflict_html)))
         }
         Err(err) => Err(NameError::Service(err)),
     }
 }
 
-/// Handler for GET /names/table that returns just the names table fragment.
+/// Handler for GET /names/table that returns just the names table fragment, with the page,
+/// filters and sort order given as query parameters.
 #[tracing::instrument(skip(state))]
 async fn names_table_handler(
     State(state): State<Arc<NameState>>,
+    Query(filters): Query<NamesTableQuery>,
+    page: PageParams,
+    sort: SortParams<NameSortField>,
 ) -> Result<Html<String>, NameError> {
-    let name_service = NameService::new(&state.db);
-    let table_html = render_names_table(&name_service, |names| {
-        names.sort_by_key(|name| name.id());
-    })
-    .await?;
+    let query = NameQuery {
+        server_id: filters.server_id,
+        registered_server_id: filters.registered_server_id,
+        discord_id: filters.discord_id,
+        search: filters.search,
+        sort,
+        page,
+    };
+    let name_service = NameService::new(&state.db).with_cache(&state.cache);
+    let table_html = render_names_table(&name_service, &query).await?;
     Ok(Html(table_html))
 }
 
+/// Handler for GET /names/search that returns the names best matching the text typed into the
+/// live search box, or nothing while it is blank.
+#[tracing::instrument(skip(state))]
+async fn search_names_handler(
+    State(state): State<Arc<NameState>>,
+    Query(query): Query<SearchQuery>,
+) -> Result<Html<String>, NameError> {
+    if query.q.trim().is_empty() {
+        return Ok(Html(String::new()));
+    }
+    let name_service = NameService::new(&state.db);
+    let names = name_service.search(&query.q, DEFAULT_SEARCH_LIMIT).await?;
+    let template = SearchResultsTemplate { names };
+    template.render().map(Html).map_err(NameError::from)
+}
+
+/// Handler for GET /names/export that downloads the names of a server as a YAML or CSV
+/// document, streamed as it is read from the database.
+#[tracing::instrument(skip(state))]
+async fn export_names_handler(
+    State(state): State<Arc<NameState>>,
+    Query(query): Query<ExportQuery>,
+) -> Response {
+    let server_id = query.server_id.trim().to_string();
+    let headers = [
+        (
+            header::CONTENT_TYPE,
+            HeaderValue::from_static(query.format.content_type()),
+        ),
+        (
+            header::CONTENT_DISPOSITION,
+            query.format.content_disposition(&server_id),
+        ),
+    ];
+    let body = Body::from_stream(export_names(state.db.clone(), server_id, query.format));
+    (headers, body).into_response()
+}
+
 /// Handler for GET /names/{id} that returns a single name row.
 #[tracing::instrument(skip(state))]
 async fn get_name_row_handler(
     State(state): State<Arc<NameState>>,
-    axum::extract::Path(id): axum::extract::Path<u32>,
+    axum::extract::Path(id): axum::extract::Path<NameId>,
 ) -> Result<Html<String>, NameError> {
-    let name_service = NameService::new(&state.db);
+    let name_service = NameService::new(&state.db).with_cache(&state.cache);
 
     match name_service.get_name_by_id(id).await {
         Ok(name) => {
@@ -403,9 +646,12 @@ async fn bulk_add_form_handler() -> Result<Html<String>, NameError> {
 #[tracing::instrument(skip(state, form))]
 async fn bulk_add_handler(
     State(state): State<Arc<NameState>>,
+    current_user: Option<Extension<CurrentUser>>,
     Form(form): Form<BulkAddForm>,
 ) -> Result<Html<String>, NameError> {
-    let name_service = NameService::new(&state.db);
+    let name_service = NameService::new(&state.db)
+        .with_cache(&state.cache)
+        .acting_as(audit::actor(current_user.as_deref()));
 
     // Process the bulk upload using the pasted YAML content
     match name_service
@@ -438,7 +684,7 @@ async fn bulk_delete_page_handler() -> Result<Html<String>, NameError> {
 async fn bulk_delete_table_handler(
     State(state): State<Arc<NameState>>,
 ) -> Result<Html<String>, NameError> {
-    let name_service = NameService::new(&state.db);
+    let name_service = NameService::new(&state.db).with_cache(&state.cache);
     let mut names = name_service.get_all_names().await?;
     names.sort_by_key(|name| name.id());
     let template = BulkDeleteTableTemplate::new(names);
@@ -449,12 +695,15 @@ async fn bulk_delete_table_handler(
 #[tracing::instrument(skip(state))]
 async fn bulk_delete_names_delete_handler(
     State(state): State<Arc<NameState>>,
+    current_user: Option<Extension<CurrentUser>>,
     RawQuery(query): RawQuery,
 ) -> Result<Html<String>, NameError> {
-    let name_service = NameService::new(&state.db);
+    let name_service = NameService::new(&state.db)
+        .with_cache(&state.cache)
+        .acting_as(audit::actor(current_user.as_deref()));
 
     // Parse query parameters manually to handle multiple values with the same key
-    let selected_ids: Vec<u32> = if let Some(query_str) = query {
+    let selected_ids: Vec<NameId> = if let Some(query_str) = query {
         query_str
             .split('&')
             .filter_map(|pair| {
@@ -482,6 +731,168 @@ async fn bulk_delete_names_delete_handler(
     template.render().map(Html).map_err(NameError::from)
 }
 
+/// Handler for PATCH /names/bulk that moves the selected names to another server and returns
+/// the updated bulk delete table. Either all of the names are moved, or none are.
+#[tracing::instrument(skip(state))]
+async fn bulk_move_names_handler(
+    State(state): State<Arc<NameState>>,
+    current_user: Option<Extension<CurrentUser>>,
+    Form(fields): Form<Vec<(String, String)>>,
+) -> Result<Html<String>, NameError> {
+    let name_service = NameService::new(&state.db)
+        .with_cache(&state.cache)
+        .acting_as(audit::actor(current_user.as_deref()));
+
+    // Collect the fields by hand, as every selected name has a `selected_ids` field of its own
+    let mut selected_ids: Vec<NameId> = Vec::new();
+    let mut server_id = String::new();
+    for (key, value) in fields {
+        match key.as_str() {
+            "selected_ids" => selected_ids.extend(value.parse::<NameId>().ok()),
+            "server_id" => server_id = value.trim().to_string(),
+            _ => {}
+        }
+    }
+    if server_id.is_empty() {
+        return Err(NameError::MissingServerId);
+    }
+
+    if !selected_ids.is_empty() {
+        match name_service
+            .bulk_update_server(&selected_ids, server_id)
+            .await
+        {
+            Ok(_) => {}
+            Err(NameServiceError::DuplicateEntryError(_, _)) => {
+                return Err(NameError::BulkMoveConflict);
+            }
+            Err(NameServiceError::NameNotFound(_)) => return Err(NameError::SelectionOutdated),
+            Err(err) => return Err(NameError::Service(err)),
+        }
+    }
+
+    let mut names = name_service.get_all_names().await?;
+    names.sort_by_key(|name| name.id());
+    let template = BulkDeleteTableTemplate::new(names);
+    template.render().map(Html).map_err(NameError::from)
+}
+
+/// Finds the names that are at least `threshold` similar and renders them as the duplicates
+/// table.
+async fn render_duplicates_table(
+    name_service: &NameService<'_>,
+    threshold: f64,
+) -> Result<Html<String>, NameError> {
+    let groups = name_service.find_duplicates(threshold).await?;
+    let template = DuplicatesTableTemplate { groups, threshold };
+    template.render().map(Html).map_err(NameError::from)
+}
+
+/// Handler for GET /names/duplicates that displays the duplicates page.
+#[tracing::instrument]
+async fn duplicates_handler() -> Result<Html<String>, NameError> {
+    let template = DuplicatesTemplate {
+        threshold: DEFAULT_SIMILARITY_THRESHOLD,
+    };
+    template.render().map(Html).map_err(NameError::from)
+}
+
+/// Handler for GET /names/duplicates/table that returns the likely duplicates at the requested
+/// similarity threshold.
+#[tracing::instrument(skip(state))]
+async fn duplicates_table_handler(
+    State(state): State<Arc<NameState>>,
+    Query(query): Query<DuplicatesQuery>,
+) -> Result<Html<String>, NameError> {
+    let name_service = NameService::new(&state.db).with_cache(&state.cache);
+    let threshold = query.threshold.unwrap_or(DEFAULT_SIMILARITY_THRESHOLD);
+    render_duplicates_table(&name_service, threshold).await
+}
+
+/// Handler for POST /names/duplicates/merge that keeps one of a group of duplicates, moves the
+/// others to the trash and returns the updated duplicates table.
+#[tracing::instrument(skip(state))]
+async fn merge_duplicates_handler(
+    State(state): State<Arc<NameState>>,
+    current_user: Option<Extension<CurrentUser>>,
+    Form(fields): Form<Vec<(String, String)>>,
+) -> Result<Html<String>, NameError> {
+    let name_service = NameService::new(&state.db)
+        .with_cache(&state.cache)
+        .acting_as(audit::actor(current_user.as_deref()));
+
+    // Collect the fields by hand, as every duplicate has an `ids` field of its own
+    let mut ids: Vec<NameId> = Vec::new();
+    let mut keep: Option<NameId> = None;
+    let mut threshold = DEFAULT_SIMILARITY_THRESHOLD;
+    for (key, value) in fields {
+        match key.as_str() {
+            "ids" => ids.extend(value.parse::<NameId>().ok()),
+            "keep" => keep = value.parse().ok(),
+            "threshold" => threshold = value.trim().parse().unwrap_or(threshold),
+            _ => {}
+        }
+    }
+    let Some(keep) = keep else {
+        return Err(NameError::MissingNameToKeep);
+    };
+
+    match name_service.merge_names(keep, &ids).await {
+        Ok(_) => render_duplicates_table(&name_service, threshold).await,
+        Err(NameServiceError::NameNotFound(_)) => Err(NameError::MergeOutdated),
+        Err(err) => Err(NameError::Service(err)),
+    }
+}
+
+/// Renders a page of the trash as the trash table.
+async fn render_trash_table(
+    name_service: &NameService<'_>,
+    page: PageParams,
+) -> Result<Html<String>, NameError> {
+    let names = name_service.list_trash(page).await?;
+    let template = TrashTableTemplate { names };
+    template.render().map(Html).map_err(NameError::from)
+}
+
+/// Handler for GET /names/trash that displays the trash.
+#[tracing::instrument(skip(state))]
+async fn trash_handler(State(state): State<Arc<NameState>>) -> Result<Html<String>, NameError> {
+    let template = TrashTemplate {
+        retention_days: state.trash_retention_days,
+    };
+    template.render().map(Html).map_err(NameError::from)
+}
+
+/// Handler for GET /names/trash/table that returns a page of the trash table fragment.
+#[tracing::instrument(skip(state))]
+async fn trash_table_handler(
+    State(state): State<Arc<NameState>>,
+    page: PageParams,
+) -> Result<Html<String>, NameError> {
+    let name_service = NameService::new(&state.db).with_cache(&state.cache);
+    render_trash_table(&name_service, page).await
+}
+
+/// Handler for POST /names/trash/{id}/restore that takes a name out of the trash and returns
+/// the updated trash table.
+#[tracing::instrument(skip(state))]
+async fn restore_name_handler(
+    State(state): State<Arc<NameState>>,
+    current_user: Option<Extension<CurrentUser>>,
+    axum::extract::Path(id): axum::extract::Path<NameId>,
+) -> Result<Html<String>, NameError> {
+    let name_service = NameService::new(&state.db)
+        .with_cache(&state.cache)
+        .acting_as(audit::actor(current_user.as_deref()));
+
+    match name_service.restore_name_by_id(id).await {
+        Ok(_) => render_trash_table(&name_service, PageParams::default()).await,
+        Err(NameServiceError::DuplicateEntryError(_, _)) => Err(NameError::RestoreConflict),
+        Err(NameServiceError::NameNotFound(_)) => Err(NameError::NotInTrash),
+        Err(err) => Err(NameError::Service(err)),
+    }
+}
+
 /// Creates and returns the name router with all name-related routes.
 pub fn create_name_router(state: Arc<NameState>) -> Router {
     Router::new()
@@ -501,6 +912,15 @@ pub fn create_name_router(state: Arc<NameState>) -> Router {
             get(bulk_delete_page_handler).delete(bulk_delete_names_delete_handler),
         )
         .route("/names/delete/table", get(bulk_delete_table_handler))
+        .route("/names/bulk", patch(bulk_move_names_handler))
+        .route("/names/duplicates", get(duplicates_handler))
+        .route("/names/duplicates/table", get(duplicates_table_handler))
+        .route("/names/duplicates/merge", post(merge_duplicates_handler))
+        .route("/names/export", get(export_names_handler))
+        .route("/names/search", get(search_names_handler))
+        .route("/names/trash", get(trash_handler))
+        .route("/names/trash/table", get(trash_table_handler))
+        .route("/names/trash/{id}/restore", post(restore_name_handler))
         .route(
             "/names/{id}",
             get(get_name_row_handler)
@@ -0,0 +1,116 @@
+//! Rate limits of the server's routes. Clients are told apart by their IP address, as reported
+//! by the trusted proxies in front of the server if there are any, or by user once they are
+//! authenticated.
+
+use crate::config::Config;
+use rate_limit::{ForwardedIp, Quota, RateLimitLayer, User};
+
+/// How many requests clients may make, as configured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimits {
+    /// Login attempts per minute per IP address
+    pub login_attempts_per_minute: u32,
+    /// API requests per minute per IP address, authenticated or not
+    pub api_ip_requests_per_minute: u32,
+    /// API requests per second per user, whether they use a JWT or an API token
+    pub api_user_requests_per_second: u32,
+    /// API requests a user may make at once
+    pub api_user_burst: u32,
+    /// Reverse proxies in front of the server whose `X-Forwarded-For` entries are trusted
+    pub trusted_proxies: usize,
+}
+
+impl RateLimits {
+    /// Takes the limits from the application config.
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            login_attempts_per_minute: config.login_attempts_per_minute,
+            api_ip_requests_per_minute: config.api_ip_requests_per_minute,
+            api_user_requests_per_second: config.api_user_requests_per_second,
+            api_user_burst: config.api_user_burst,
+            trusted_proxies: config.trusted_proxies,
+        }
+    }
+
+    /// Limits login attempts per IP address, to slow down password guessing.
+    pub fn login(&self) -> RateLimitLayer<ForwardedIp> {
+        RateLimitLayer::new(
+            Quota::per_minute(self.login_attempts_per_minute),
+            self.client_ip(),
+        )
+    }
+
+    /// Limits API requests per IP address before they are authenticated, so that clients
+    /// without a valid token can't flood the API or guess tokens quickly either.
+    pub fn api_per_ip(&self) -> RateLimitLayer<ForwardedIp> {
+        RateLimitLayer::new(
+            Quota::per_minute(self.api_ip_requests_per_minute),
+            self.client_ip(),
+        )
+    }
+
+    /// Limits authenticated API requests per user, in bursts.
+    pub fn api_per_user(&self) -> RateLimitLayer<User> {
+        RateLimitLayer::new(
+            Quota::per_second(self.api_user_requests_per_second).allow_burst(self.api_user_burst),
+            User,
+        )
+    }
+
+    /// Tells clients apart by the address the trusted proxies report, or by the address they
+    /// connect from without any.
+    fn client_ip(&self) -> ForwardedIp {
+        ForwardedIp::new(self.trusted_proxies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+    use axum::extract::ConnectInfo;
+    use axum::http::{Request, StatusCode};
+    use axum::routing::post;
+    use std::net::SocketAddr;
+    use tower::ServiceExt;
+
+    fn limits(trusted_proxies: usize) -> RateLimits {
+        RateLimits {
+            login_attempts_per_minute: 1,
+            api_ip_requests_per_minute: 300,
+            api_user_requests_per_second: 10,
+            api_user_burst: 20,
+            trusted_proxies,
+        }
+    }
+
+    async fn attempt_logins(limits: RateLimits) -> Vec<StatusCode> {
+        let app = axum::Router::new()
+            .route("/login", post(|| async { "OK" }))
+            .layer(limits.login());
+        let mut statuses = Vec::new();
+        for forwarded_for in ["203.0.113.7", "198.51.100.1"] {
+            let mut request = Request::post("/login")
+                .header("x-forwarded-for", forwarded_for)
+                .body(Body::empty())
+                .unwrap();
+            request
+                .extensions_mut()
+                .insert(ConnectInfo(SocketAddr::from(([192, 0, 2, 1], 4000))));
+            statuses.push(app.clone().oneshot(request).await.unwrap().status());
+        }
+        statuses
+    }
+
+    #[tokio::test]
+    async fn only_trusts_forwarded_addresses_behind_proxies() {
+        assert_eq!(
+            attempt_logins(limits(0)).await,
+            [StatusCode::OK, StatusCode::TOO_MANY_REQUESTS]
+        );
+        assert_eq!(
+            attempt_logins(limits(1)).await,
+            [StatusCode::OK, StatusCode::OK]
+        );
+    }
+}
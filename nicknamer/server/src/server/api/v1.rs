@@ -0,0 +1,282 @@
+use crate::server::web::ServerState;
+use crate::server::{Server, ServerDetails, ServerId, ServerService, ServerServiceError};
+use api_error::{ApiError, FieldError, ProblemDetails};
+use axum::{
+    Router,
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+};
+use axum_extra::extract::WithRejection;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use typed_ids::DiscordId;
+use utoipa::ToSchema;
+
+/// JSON representation of a registered server for API responses.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ServerJson {
+    /// Unique identifier for the server
+    #[schema(value_type = u32)]
+    id: ServerId,
+    /// ID of the Discord guild, which names use as their server ID
+    #[schema(value_type = u64)]
+    guild_id: DiscordId,
+    /// Display name of the server
+    name: String,
+    /// URL of the server's icon
+    icon_url: Option<String>,
+    /// Discord user ID of the server's owner
+    #[schema(value_type = Option<u64>)]
+    owner_id: Option<DiscordId>,
+    /// When the server was registered, in RFC 3339 format
+    created_at: String,
+}
+
+impl From<Server> for ServerJson {
+    fn from(server: Server) -> Self {
+        Self {
+            id: server.id(),
+            guild_id: server.guild_id(),
+            name: server.name().to_string(),
+            icon_url: server.icon_url().map(str::to_string),
+            owner_id: server.owner_id(),
+            created_at: server.created_at().to_rfc3339(),
+        }
+    }
+}
+
+impl From<ServerServiceError> for ApiError {
+    fn from(err: ServerServiceError) -> Self {
+        match err {
+            ServerServiceError::DuplicateGuild(_) => ApiError::conflict(err.to_string()),
+            ServerServiceError::ServerNotFound(_) => ApiError::not_found(err.to_string()),
+            ServerServiceError::MissingName => {
+                ApiError::validation(vec![FieldError::new("name", "must not be empty")])
+            }
+            ServerServiceError::InvalidIconUrl(_) => ApiError::validation(vec![FieldError::new(
+                "icon_url",
+                "must be an http or https URL",
+            )]),
+            ServerServiceError::Database(err) => ApiError::from(err),
+        }
+    }
+}
+
+/// JSON request body for registering a server.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateServerRequest {
+    /// ID of the Discord guild
+    pub guild_id: u64,
+    /// Display name of the server
+    pub name: String,
+    /// URL of the server's icon
+    #[serde(default)]
+    pub icon_url: Option<String>,
+    /// Discord user ID of the server's owner
+    #[serde(default)]
+    pub owner_id: Option<u64>,
+}
+
+/// JSON request body for replacing the details of a registered server.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateServerRequest {
+    /// Display name of the server
+    pub name: String,
+    /// URL of the server's icon, removed if omitted
+    #[serde(default)]
+    pub icon_url: Option<String>,
+    /// Discord user ID of the server's owner, removed if omitted
+    #[serde(default)]
+    pub owner_id: Option<u64>,
+}
+
+/// Checks that `id` is a Discord snowflake, adding an error for `field` to `errors` if it isn't.
+fn snowflake(field: &str, id: u64, errors: &mut Vec<FieldError>) -> Option<DiscordId> {
+    let discord_id = DiscordId::new(id).ok().filter(|_| id != 0);
+    if discord_id.is_none() {
+        errors.push(FieldError::new(field, "must be a Discord snowflake"));
+    }
+    discord_id
+}
+
+/// Builds the details of a server from a request, adding an error to `errors` if the owner
+/// isn't a Discord snowflake.
+fn server_details(
+    name: String,
+    icon_url: Option<String>,
+    owner_id: Option<u64>,
+    errors: &mut Vec<FieldError>,
+) -> ServerDetails {
+    ServerDetails {
+        name,
+        icon_url,
+        owner_id: owner_id.and_then(|owner_id| snowflake("owner_id", owner_id, errors)),
+    }
+}
+
+/// Handler for GET /api/v1/servers - Returns all registered servers, ordered by name.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+    get,
+    path = "/api/v1/servers",
+    responses(
+        (status = 200, description = "Successfully retrieved the servers", body = Vec<ServerJson>),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    tag = "Servers"
+)]
+pub async fn get_servers_handler(
+    State(state): State<Arc<ServerState>>,
+) -> Result<Json<Vec<ServerJson>>, ApiError> {
+    let servers = ServerService::new(&state.db)
+        .with_name_cache(&state.name_cache)
+        .list_servers()
+        .await?;
+    Ok(Json(servers.into_iter().map(ServerJson::from).collect()))
+}
+
+/// Handler for POST /api/v1/servers - Registers a server, linking the names whose server ID is
+/// its guild ID to it.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+    post,
+    path = "/api/v1/servers",
+    request_body = CreateServerRequest,
+    responses(
+        (status = 201, description = "Server registered", body = ServerJson),
+        (status = 400, description = "Malformed request body", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "The guild is already registered", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid fields", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    tag = "Servers"
+)]
+pub async fn create_server_handler(
+    State(state): State<Arc<ServerState>>,
+    WithRejection(Json(payload), _): WithRejection<Json<CreateServerRequest>, ApiError>,
+) -> Result<(StatusCode, Json<ServerJson>), ApiError> {
+    let mut errors = Vec::new();
+    let guild_id = snowflake("guild_id", payload.guild_id, &mut errors);
+    let details = server_details(
+        payload.name,
+        payload.icon_url,
+        payload.owner_id,
+        &mut errors,
+    );
+    let Some(guild_id) = guild_id.filter(|_| errors.is_empty()) else {
+        return Err(ApiError::validation(errors));
+    };
+
+    let server = ServerService::new(&state.db)
+        .with_name_cache(&state.name_cache)
+        .create_server(guild_id, details)
+        .await?;
+    Ok((StatusCode::CREATED, Json(ServerJson::from(server))))
+}
+
+/// Handler for GET /api/v1/servers/{id} - Returns a registered server.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+    get,
+    path = "/api/v1/servers/{id}",
+    params(("id" = u32, Path, description = "ID of the server")),
+    responses(
+        (status = 200, description = "Successfully retrieved the server", body = ServerJson),
+        (status = 400, description = "Malformed ID", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Server not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    tag = "Servers"
+)]
+pub async fn get_server_handler(
+    State(state): State<Arc<ServerState>>,
+    WithRejection(Path(id), _): WithRejection<Path<ServerId>, ApiError>,
+) -> Result<Json<ServerJson>, ApiError> {
+    let server = ServerService::new(&state.db)
+        .with_name_cache(&state.name_cache)
+        .get_server(id)
+        .await?;
+    Ok(Json(ServerJson::from(server)))
+}
+
+/// Handler for PUT /api/v1/servers/{id} - Replaces the name, icon and owner of a server.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+    put,
+    path = "/api/v1/servers/{id}",
+    params(("id" = u32, Path, description = "ID of the server")),
+    request_body = UpdateServerRequest,
+    responses(
+        (status = 200, description = "Server updated", body = ServerJson),
+        (status = 400, description = "Malformed request body or ID", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Server not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid fields", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    tag = "Servers"
+)]
+pub async fn update_server_handler(
+    State(state): State<Arc<ServerState>>,
+    WithRejection(Path(id), _): WithRejection<Path<ServerId>, ApiError>,
+    WithRejection(Json(payload), _): WithRejection<Json<UpdateServerRequest>, ApiError>,
+) -> Result<Json<ServerJson>, ApiError> {
+    let mut errors = Vec::new();
+    let details = server_details(
+        payload.name,
+        payload.icon_url,
+        payload.owner_id,
+        &mut errors,
+    );
+    if !errors.is_empty() {
+        return Err(ApiError::validation(errors));
+    }
+
+    let server = ServerService::new(&state.db)
+        .with_name_cache(&state.name_cache)
+        .update_server(id, details)
+        .await?;
+    Ok(Json(ServerJson::from(server)))
+}
+
+/// Handler for DELETE /api/v1/servers/{id} - Unregisters a server. Its names are kept.
+#[tracing::instrument(skip(state))]
+#[utoipa::path(
+    delete,
+    path = "/api/v1/servers/{id}",
+    params(("id" = u32, Path, description = "ID of the server")),
+    responses(
+        (status = 204, description = "Server unregistered"),
+        (status = 400, description = "Malformed ID", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Server not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+    tag = "Servers"
+)]
+pub async fn delete_server_handler(
+    State(state): State<Arc<ServerState>>,
+    WithRejection(Path(id), _): WithRejection<Path<ServerId>, ApiError>,
+) -> Result<StatusCode, ApiError> {
+    ServerService::new(&state.db)
+        .with_name_cache(&state.name_cache)
+        .delete_server(id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Creates and returns the servers API router.
+pub fn create_api_router(state: Arc<ServerState>) -> Router {
+    Router::new()
+        .route(
+            "/servers",
+            get(get_servers_handler).post(create_server_handler),
+        )
+        .route(
+            "/servers/{id}",
+            get(get_server_handler)
+                .put(update_server_handler)
+                .delete(delete_server_handler),
+        )
+        .with_state(state)
+}
@@ -0,0 +1,324 @@
+//! The registry of the Discord servers names are used in. Names only store the ID of their
+//! server as free-form text; registering the guild with that ID links them to it, so that they
+//! can be shown with the server's name and filtered by it.
+
+use crate::entities::*;
+use crate::name::cache::NameCache;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use typed_ids::{DiscordId, EntityId};
+
+pub mod api;
+pub mod web;
+
+/// The ID of a registered server.
+pub type ServerId = EntityId<server::Entity>;
+
+#[derive(Debug, PartialEq, Clone, Eq)]
+pub struct Server {
+    id: ServerId,
+    guild_id: DiscordId,
+    name: String,
+    icon_url: Option<String>,
+    owner_id: Option<DiscordId>,
+    created_at: DateTimeWithTimeZone,
+}
+
+impl Server {
+    /// Returns the ID of the server.
+    pub fn id(&self) -> ServerId {
+        self.id
+    }
+
+    /// Returns the ID of the Discord guild, which names use as their server ID.
+    pub fn guild_id(&self) -> DiscordId {
+        self.guild_id
+    }
+
+    /// Returns the display name of the server.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the URL of the server's icon, if it has one.
+    pub fn icon_url(&self) -> Option<&str> {
+        self.icon_url.as_deref()
+    }
+
+    /// Returns the Discord user that owns the server, if known.
+    pub fn owner_id(&self) -> Option<DiscordId> {
+        self.owner_id
+    }
+
+    /// Returns when the server was registered.
+    pub fn created_at(&self) -> DateTimeWithTimeZone {
+        self.created_at
+    }
+}
+
+impl From<server::Model> for Server {
+    fn from(model: server::Model) -> Self {
+        Self {
+            id: model.id,
+            guild_id: model.guild_id,
+            name: model.name,
+            icon_url: model.icon_url,
+            owner_id: model.owner_id,
+            created_at: model.created_at,
+        }
+    }
+}
+
+/// What a server is registered with, besides its guild ID.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerDetails {
+    /// The display name of the server
+    pub name: String,
+    /// The URL of the server's icon
+    pub icon_url: Option<String>,
+    /// The Discord user that owns the server
+    pub owner_id: Option<DiscordId>,
+}
+
+/// Error type for ServerService operations.
+#[derive(Debug, thiserror::Error)]
+pub enum ServerServiceError {
+    /// Represents a database error.
+    #[error("Database error: {0}")]
+    Database(#[from] sea_orm::DbErr),
+    /// Represents a server not found error.
+    #[error("Server with ID {0} not found")]
+    ServerNotFound(ServerId),
+    /// Represents a guild that is already registered.
+    #[error("The guild {0} is already registered")]
+    DuplicateGuild(DiscordId),
+    /// Represents a server registered without a name.
+    #[error("Servers need a name")]
+    MissingName,
+    /// Represents an icon URL that isn't an http(s) URL.
+    #[error("The icon URL '{0}' isn't an http or https URL")]
+    InvalidIconUrl(String),
+}
+
+pub struct ServerService<'a> {
+    db: &'a sea_orm::DatabaseConnection,
+    /// The cache of name lookups, which show the names of the servers names are linked to
+    name_cache: Option<&'a NameCache>,
+}
+
+impl<'a> ServerService<'a> {
+    pub fn new(db: &'a sea_orm::DatabaseConnection) -> ServerService<'a> {
+        ServerService {
+            db,
+            name_cache: None,
+        }
+    }
+
+    /// Clears `name_cache` whenever a server is registered, renamed or unregistered, since
+    /// that changes how names are shown.
+    pub fn with_name_cache(self, name_cache: &'a NameCache) -> Self {
+        Self {
+            name_cache: Some(name_cache),
+            ..self
+        }
+    }
+
+    /// Registers a Discord guild, and links the names whose server ID is the guild's ID to it.
+    ///
+    /// # Arguments
+    ///
+    /// * `guild_id` - The ID of the Discord guild.
+    /// * `details` - The name, icon and owner of the server.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the registered `Server` if successful, or an error otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn create_server(
+        &self,
+        guild_id: DiscordId,
+        details: ServerDetails,
+    ) -> Result<Server, ServerServiceError> {
+        let details = validate_details(details)?;
+        if self.find_by_guild_id(guild_id).await?.is_some() {
+            return Err(ServerServiceError::DuplicateGuild(guild_id));
+        }
+
+        let active_model = server::ActiveModel {
+            guild_id: ActiveValue::Set(guild_id),
+            name: ActiveValue::Set(details.name),
+            icon_url: ActiveValue::Set(details.icon_url),
+            owner_id: ActiveValue::Set(details.owner_id),
+            ..Default::default()
+        };
+
+        let txn = self.db.begin().await?;
+        let created = active_model.insert(&txn).await?;
+        name::Entity::update_many()
+            .col_expr(name::Column::RegisteredServerId, Expr::value(created.id))
+            .filter(name::Column::ServerId.eq(guild_id.to_string()))
+            .exec(&txn)
+            .await?;
+        txn.commit().await?;
+        self.invalidate_name_cache();
+
+        Ok(Server::from(created))
+    }
+
+    /// Changes the name, icon and owner of a registered server.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the updated `Server` if successful, or an error otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn update_server(
+        &self,
+        id: ServerId,
+        details: ServerDetails,
+    ) -> Result<Server, ServerServiceError> {
+        let details = validate_details(details)?;
+        self.get_server(id).await?;
+
+        let active_model = server::ActiveModel {
+            id: ActiveValue::Unchanged(id),
+            name: ActiveValue::Set(details.name),
+            icon_url: ActiveValue::Set(details.icon_url),
+            owner_id: ActiveValue::Set(details.owner_id),
+            ..Default::default()
+        };
+        let updated_model = active_model.update(self.db).await?;
+        self.invalidate_name_cache();
+        Ok(Server::from(updated_model))
+    }
+
+    /// Unregisters a server. Its names are kept, but aren't linked to any server anymore.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the deleted `Server` if successful, or an error otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_server(&self, id: ServerId) -> Result<Server, ServerServiceError> {
+        let server = self.get_server(id).await?;
+        server::Entity::delete_by_id(id).exec(self.db).await?;
+        self.invalidate_name_cache();
+        Ok(server)
+    }
+
+    /// Retrieves a registered server by its ID.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_server(&self, id: ServerId) -> Result<Server, ServerServiceError> {
+        server::Entity::find_by_id(id)
+            .one(self.db)
+            .await?
+            .map(Server::from)
+            .ok_or(ServerServiceError::ServerNotFound(id))
+    }
+
+    /// Looks up the server registered for a Discord guild.
+    #[tracing::instrument(skip(self))]
+    pub async fn find_by_guild_id(
+        &self,
+        guild_id: DiscordId,
+    ) -> Result<Option<Server>, ServerServiceError> {
+        Ok(server::Entity::find()
+            .filter(server::Column::GuildId.eq(guild_id))
+            .one(self.db)
+            .await?
+            .map(Server::from))
+    }
+
+    /// Retrieves all registered servers, ordered by name.
+    #[tracing::instrument(skip(self))]
+    pub async fn list_servers(&self) -> Result<Vec<Server>, ServerServiceError> {
+        let servers = server::Entity::find()
+            .order_by_asc(server::Column::Name)
+            .order_by_asc(server::Column::Id)
+            .all(self.db)
+            .await?
+            .into_iter()
+            .map(Server::from)
+            .collect();
+        Ok(servers)
+    }
+
+    fn invalidate_name_cache(&self) {
+        if let Some(name_cache) = self.name_cache {
+            name_cache.invalidate();
+        }
+    }
+}
+
+/// Looks up the server registered for the guild a name's free-form server ID refers to, if it
+/// is a guild ID at all.
+pub(crate) async fn registered_server_for<C: ConnectionTrait>(
+    db: &C,
+    server_id: &str,
+) -> Result<Option<server::Model>, DbErr> {
+    let Ok(guild_id) = server_id.trim().parse::<DiscordId>() else {
+        return Ok(None);
+    };
+    server::Entity::find()
+        .filter(server::Column::GuildId.eq(guild_id))
+        .one(db)
+        .await
+}
+
+/// Trims the details of a server, leaving out an empty icon URL, and checks them.
+fn validate_details(details: ServerDetails) -> Result<ServerDetails, ServerServiceError> {
+    let name = details.name.trim().to_string();
+    if name.is_empty() {
+        return Err(ServerServiceError::MissingName);
+    }
+    let icon_url = details
+        .icon_url
+        .map(|icon_url| icon_url.trim().to_string())
+        .filter(|icon_url| !icon_url.is_empty());
+    if let Some(icon_url) = &icon_url {
+        let is_http =
+            reqwest::Url::parse(icon_url).is_ok_and(|url| matches!(url.scheme(), "http" | "https"));
+        if !is_http {
+            return Err(ServerServiceError::InvalidIconUrl(icon_url.clone()));
+        }
+    }
+    Ok(ServerDetails {
+        name,
+        icon_url,
+        owner_id: details.owner_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details(name: &str, icon_url: Option<&str>) -> ServerDetails {
+        ServerDetails {
+            name: name.to_string(),
+            icon_url: icon_url.map(str::to_string),
+            owner_id: None,
+        }
+    }
+
+    #[test]
+    fn details_are_trimmed_and_checked() {
+        let validated = validate_details(details(" Lowkey Lab ", Some(" "))).unwrap();
+        assert_eq!(validated, details("Lowkey Lab", None));
+
+        let validated =
+            validate_details(details("Lab", Some("https://cdn.example/icon.png"))).unwrap();
+        assert_eq!(
+            validated.icon_url.as_deref(),
+            Some("https://cdn.example/icon.png")
+        );
+
+        assert!(matches!(
+            validate_details(details("  ", None)),
+            Err(ServerServiceError::MissingName)
+        ));
+        assert!(matches!(
+            validate_details(details("Lab", Some("javascript:alert(1)"))),
+            Err(ServerServiceError::InvalidIconUrl(_))
+        ));
+    }
+}
@@ -0,0 +1,243 @@
+use askama::Template;
+use axum::{
+    Form, Router,
+    extract::{Path, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::Html,
+    routing::{get, put},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use typed_ids::DiscordId;
+
+use crate::name::cache::NameCache;
+use crate::server::{Server, ServerDetails, ServerId, ServerService, ServerServiceError};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateServerForm {
+    guild_id: String,
+    name: String,
+    #[serde(default)]
+    icon_url: String,
+    #[serde(default)]
+    owner_id: String,
+}
+
+/// The details of a server, as submitted when registering or editing it. Empty icon URLs and
+/// owners are left out.
+#[derive(Debug, Deserialize)]
+pub struct ServerDetailsForm {
+    name: String,
+    #[serde(default)]
+    icon_url: String,
+    #[serde(default)]
+    owner_id: String,
+}
+
+impl ServerDetailsForm {
+    fn parse(self) -> Result<ServerDetails, ServerError> {
+        let owner_id = match self.owner_id.trim() {
+            "" => None,
+            owner_id => Some(parse_discord_id(owner_id)?),
+        };
+        Ok(ServerDetails {
+            name: self.name,
+            icon_url: Some(self.icon_url),
+            owner_id,
+        })
+    }
+}
+
+fn parse_discord_id(id: &str) -> Result<DiscordId, ServerError> {
+    id.trim().parse().map_err(|_| ServerError::InvalidDiscordId)
+}
+
+#[derive(Clone, Debug)]
+pub struct ServerState {
+    pub db: Arc<sea_orm::DatabaseConnection>,
+    /// The cache of name lookups, cleared when servers change
+    pub name_cache: NameCache,
+}
+
+/// Custom error type for server handler operations.
+#[derive(Debug, thiserror::Error)]
+enum ServerError {
+    /// Represents an error during template rendering.
+    #[error("Template rendering failed")]
+    Template(#[from] askama::Error),
+    /// Represents a server service error.
+    #[error("Server service error")]
+    Service(#[from] ServerServiceError),
+    /// Represents a guild or owner ID that isn't a Discord ID.
+    #[error("Invalid Discord ID")]
+    InvalidDiscordId,
+}
+
+impl axum::response::IntoResponse for ServerError {
+    fn into_response(self) -> axum::response::Response {
+        let (status_code, user_facing_error_message) = match self {
+            ServerError::Service(
+                err @ (ServerServiceError::MissingName | ServerServiceError::InvalidIconUrl(_)),
+            ) => (StatusCode::UNPROCESSABLE_ENTITY, err.to_string()),
+            ServerError::InvalidDiscordId => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Guild and owner IDs are the numeric IDs Discord shows in developer mode."
+                    .to_string(),
+            ),
+            ServerError::Service(err @ ServerServiceError::DuplicateGuild(_)) => {
+                (StatusCode::CONFLICT, err.to_string())
+            }
+            ServerError::Service(ServerServiceError::ServerNotFound(_)) => (
+                StatusCode::NOT_FOUND,
+                "The server isn't registered anymore. It may have been deleted already."
+                    .to_string(),
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "An unexpected error occurred while processing your request. Please try again later."
+                    .to_string(),
+            ),
+        };
+
+        let error_template = ErrorMessageTemplate::new(user_facing_error_message);
+        let Ok(rendered) = error_template.render() else {
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        };
+
+        let mut response = (status_code, Html(rendered)).into_response();
+        // Add HTMX headers to retarget the error message to the error div
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static("hx-retarget"),
+            HeaderValue::from_static("#server-error"),
+        );
+        headers.insert(
+            HeaderName::from_static("hx-reswap"),
+            HeaderValue::from_static("innerHTML"),
+        );
+        response.headers_mut().extend(headers);
+        response
+    }
+}
+
+#[derive(Template)]
+#[template(path = "servers.html")]
+struct ServersTemplate;
+
+#[derive(Template)]
+#[template(path = "servers/servers_table.html")]
+struct ServersTableTemplate {
+    servers: Vec<Server>,
+}
+
+#[derive(Template)]
+#[template(path = "servers/server_options.html")]
+struct ServerOptionsTemplate {
+    servers: Vec<Server>,
+}
+
+#[derive(Template)]
+#[template(path = "names/error_message.html")]
+struct ErrorMessageTemplate {
+    message: String,
+}
+
+impl ErrorMessageTemplate {
+    pub fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+/// Renders the table of all registered servers.
+async fn render_servers_table(service: &ServerService<'_>) -> Result<Html<String>, ServerError> {
+    let servers = service.list_servers().await?;
+    let template = ServersTableTemplate { servers };
+    template.render().map(Html).map_err(ServerError::from)
+}
+
+/// Handler for GET /servers that displays the servers page.
+#[tracing::instrument]
+async fn servers_handler() -> Result<Html<String>, ServerError> {
+    ServersTemplate
+        .render()
+        .map(Html)
+        .map_err(ServerError::from)
+}
+
+/// Handler for GET /servers/table that returns just the servers table fragment.
+#[tracing::instrument(skip(state))]
+async fn servers_table_handler(
+    State(state): State<Arc<ServerState>>,
+) -> Result<Html<String>, ServerError> {
+    let service = ServerService::new(&state.db).with_name_cache(&state.name_cache);
+    render_servers_table(&service).await
+}
+
+/// Handler for GET /servers/options that returns the registered servers as the options of the
+/// names table's server filter.
+#[tracing::instrument(skip(state))]
+async fn server_options_handler(
+    State(state): State<Arc<ServerState>>,
+) -> Result<Html<String>, ServerError> {
+    let servers = ServerService::new(&state.db)
+        .with_name_cache(&state.name_cache)
+        .list_servers()
+        .await?;
+    let template = ServerOptionsTemplate { servers };
+    template.render().map(Html).map_err(ServerError::from)
+}
+
+/// Handler for POST /servers that registers a server.
+#[tracing::instrument(skip(state))]
+async fn create_server_handler(
+    State(state): State<Arc<ServerState>>,
+    Form(form): Form<CreateServerForm>,
+) -> Result<Html<String>, ServerError> {
+    let guild_id = parse_discord_id(&form.guild_id)?;
+    let details = ServerDetailsForm {
+        name: form.name,
+        icon_url: form.icon_url,
+        owner_id: form.owner_id,
+    }
+    .parse()?;
+    let service = ServerService::new(&state.db).with_name_cache(&state.name_cache);
+    service.create_server(guild_id, details).await?;
+    render_servers_table(&service).await
+}
+
+/// Handler for PUT /servers/{id} that changes the name, icon and owner of a server.
+#[tracing::instrument(skip(state))]
+async fn update_server_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<ServerId>,
+    Form(form): Form<ServerDetailsForm>,
+) -> Result<Html<String>, ServerError> {
+    let details = form.parse()?;
+    let service = ServerService::new(&state.db).with_name_cache(&state.name_cache);
+    service.update_server(id, details).await?;
+    render_servers_table(&service).await
+}
+
+/// Handler for DELETE /servers/{id} that unregisters a server.
+#[tracing::instrument(skip(state))]
+async fn delete_server_handler(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<ServerId>,
+) -> Result<Html<String>, ServerError> {
+    let service = ServerService::new(&state.db).with_name_cache(&state.name_cache);
+    service.delete_server(id).await?;
+    render_servers_table(&service).await
+}
+
+/// Creates and returns the router of the server registry pages.
+pub fn create_server_router(state: Arc<ServerState>) -> Router {
+    Router::new()
+        .route("/servers", get(servers_handler).post(create_server_handler))
+        .route("/servers/table", get(servers_table_handler))
+        .route("/servers/options", get(server_options_handler))
+        .route(
+            "/servers/{id}",
+            put(update_server_handler).delete(delete_server_handler),
+        )
+        .with_state(state)
+}
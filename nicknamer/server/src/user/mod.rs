@@ -0,0 +1,446 @@
+//! User accounts, which people log in to the web UI and the JSON API with. Every account has a
+//! role that decides what it may do, and only an argon2 hash of its password is stored. Linking
+//! an account to a Discord user lets them log in with Discord instead of their password.
+
+use crate::api_token::TokenScope;
+use crate::entities::*;
+use argon2::Argon2;
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use axum::http::Method;
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::*;
+use std::fmt;
+use std::str::FromStr;
+use typed_ids::{DiscordId, EntityId};
+
+pub mod web;
+
+/// The ID of a user.
+pub type UserId = EntityId<user::Entity>;
+
+/// How long a password has to be at least.
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// What a user is allowed to do. Every role may do everything the roles before it may.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// May look at names, but not change them
+    Viewer,
+    /// May also add, edit and delete names
+    Editor,
+    /// May also manage users, API tokens and feature flags
+    Admin,
+}
+
+impl Role {
+    /// Every role, most powerful first.
+    pub const ALL: [Role; 3] = [Role::Admin, Role::Editor, Role::Viewer];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Editor => "editor",
+            Role::Admin => "admin",
+        }
+    }
+
+    /// Whether the role may do everything `other` may.
+    pub fn includes(self, other: Role) -> bool {
+        self >= other
+    }
+
+    /// The role a request with `method` to the names routes needs: viewers may read names,
+    /// changing them takes an editor.
+    pub fn required_for(method: &Method) -> Role {
+        if method.is_safe() {
+            Role::Viewer
+        } else {
+            Role::Editor
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = UserServiceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "viewer" => Ok(Role::Viewer),
+            "editor" => Ok(Role::Editor),
+            "admin" => Ok(Role::Admin),
+            other => Err(UserServiceError::UnknownRole(other.to_string())),
+        }
+    }
+}
+
+/// API tokens act with the role their scope matches.
+impl From<TokenScope> for Role {
+    fn from(scope: TokenScope) -> Self {
+        match scope {
+            TokenScope::ReadOnly => Role::Viewer,
+            TokenScope::ReadWrite => Role::Editor,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Eq)]
+pub struct User {
+    id: UserId,
+    username: String,
+    role: Role,
+    created_at: DateTimeWithTimeZone,
+    discord_id: Option<DiscordId>,
+}
+
+impl User {
+    /// Returns the ID of the user.
+    pub fn id(&self) -> UserId {
+        self.id
+    }
+
+    /// Returns the name the user logs in with.
+    pub fn username(&self) -> &str {
+        &self.username
+    }
+
+    /// Returns what the user is allowed to do.
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    /// Returns when the user was registered.
+    pub fn created_at(&self) -> DateTimeWithTimeZone {
+        self.created_at
+    }
+
+    /// Returns the Discord user that may log in as the user, if one is linked.
+    pub fn discord_id(&self) -> Option<DiscordId> {
+        self.discord_id
+    }
+}
+
+impl TryFrom<user::Model> for User {
+    type Error = UserServiceError;
+
+    fn try_from(model: user::Model) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: model.id,
+            username: model.username,
+            role: model.role.parse()?,
+            created_at: model.created_at,
+            discord_id: model.discord_id,
+        })
+    }
+}
+
+/// Error type for UserService operations.
+#[derive(Debug, thiserror::Error)]
+pub enum UserServiceError {
+    /// Represents a database error.
+    #[error("Database error: {0}")]
+    Database(#[from] sea_orm::DbErr),
+    /// Represents a user not found error.
+    #[error("User with ID {0} not found")]
+    UserNotFound(UserId),
+    /// Represents a user registered without a username.
+    #[error("Users need a username")]
+    MissingUsername,
+    /// Represents a username that is already taken.
+    #[error("The username '{0}' is already taken")]
+    DuplicateUsername(String),
+    /// Represents a password shorter than MIN_PASSWORD_LENGTH.
+    #[error("Passwords need at least {MIN_PASSWORD_LENGTH} characters")]
+    PasswordTooShort,
+    /// Represents a change that would leave no admin to manage users.
+    #[error("The last admin can't be removed or demoted")]
+    LastAdmin,
+    /// Represents a Discord user that is already linked to another user.
+    #[error("The Discord user {0} is already linked to another user")]
+    DuplicateDiscordId(DiscordId),
+    /// Represents a stored role this version doesn't know.
+    #[error("Unknown role '{0}'")]
+    UnknownRole(String),
+    /// Represents a failure to hash or read a password hash.
+    #[error("Password hashing failed: {0}")]
+    PasswordHash(argon2::password_hash::Error),
+}
+
+pub struct UserService<'a> {
+    db: &'a sea_orm::DatabaseConnection,
+}
+
+impl UserService<'_> {
+    pub fn new(db: &sea_orm::DatabaseConnection) -> UserService<'_> {
+        UserService { db }
+    }
+
+    /// Registers a new user.
+    ///
+    /// # Arguments
+    ///
+    /// * `username` - The name the user logs in with.
+    /// * `password` - The user's password, of at least `MIN_PASSWORD_LENGTH` characters.
+    /// * `role` - What the user is allowed to do.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the registered `User` if successful, or an error otherwise.
+    #[tracing::instrument(skip(self, password))]
+    pub async fn create_user(
+        &self,
+        username: String,
+        password: &str,
+        role: Role,
+    ) -> Result<User, UserServiceError> {
+        let username = username.trim().to_string();
+        if username.is_empty() {
+            return Err(UserServiceError::MissingUsername);
+        }
+        if password.chars().count() < MIN_PASSWORD_LENGTH {
+            return Err(UserServiceError::PasswordTooShort);
+        }
+        if self.find_model(&username).await?.is_some() {
+            return Err(UserServiceError::DuplicateUsername(username));
+        }
+
+        let active_model = user::ActiveModel {
+            username: ActiveValue::Set(username),
+            password_hash: ActiveValue::Set(hash_password(password)?),
+            role: ActiveValue::Set(role.to_string()),
+            ..Default::default()
+        };
+        let created_model = active_model.insert(self.db).await?;
+        User::try_from(created_model)
+    }
+
+    /// Registers the first admin, if there are no users yet.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the registered admin, `None` if there already are users, or an
+    /// error.
+    #[tracing::instrument(skip(self, password))]
+    pub async fn ensure_admin(
+        &self,
+        username: String,
+        password: &str,
+    ) -> Result<Option<User>, UserServiceError> {
+        if user::Entity::find().count(self.db).await? > 0 {
+            return Ok(None);
+        }
+        self.create_user(username, password, Role::Admin)
+            .await
+            .map(Some)
+    }
+
+    /// Checks the credentials someone logs in with.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `User` if the password is theirs, `None` if it isn't or there
+    /// is no such user, or an error.
+    #[tracing::instrument(skip(self, password))]
+    pub async fn authenticate(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> Result<Option<User>, UserServiceError> {
+        let Some(model) = self.find_model(username).await? else {
+            return Ok(None);
+        };
+        if !verify_password(password, &model.password_hash)? {
+            return Ok(None);
+        }
+        User::try_from(model).map(Some)
+    }
+
+    /// Looks up a user by the name they log in with.
+    #[tracing::instrument(skip(self))]
+    pub async fn find_by_username(&self, username: &str) -> Result<Option<User>, UserServiceError> {
+        self.find_model(username)
+            .await?
+            .map(User::try_from)
+            .transpose()
+    }
+
+    /// Looks up the user a Discord user is linked to.
+    #[tracing::instrument(skip(self))]
+    pub async fn find_by_discord_id(
+        &self,
+        discord_id: DiscordId,
+    ) -> Result<Option<User>, UserServiceError> {
+        user::Entity::find()
+            .filter(user::Column::DiscordId.eq(discord_id))
+            .one(self.db)
+            .await?
+            .map(User::try_from)
+            .transpose()
+    }
+
+    /// Links a user to the Discord user that may log in as them, or unlinks them with `None`.
+    /// A Discord user can only be linked to one user.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the updated `User` if successful, or an error otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn set_discord_id(
+        &self,
+        id: UserId,
+        discord_id: Option<DiscordId>,
+    ) -> Result<User, UserServiceError> {
+        self.get_user(id).await?;
+        if let Some(discord_id) = discord_id {
+            let linked = self.find_by_discord_id(discord_id).await?;
+            if linked.is_some_and(|linked| linked.id != id) {
+                return Err(UserServiceError::DuplicateDiscordId(discord_id));
+            }
+        }
+
+        let active_model = user::ActiveModel {
+            id: ActiveValue::Unchanged(id),
+            discord_id: ActiveValue::Set(discord_id),
+            ..Default::default()
+        };
+        let updated_model = active_model.update(self.db).await?;
+        User::try_from(updated_model)
+    }
+
+    /// Retrieves all users, oldest first.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_all_users(&self) -> Result<Vec<User>, UserServiceError> {
+        user::Entity::find()
+            .order_by_asc(user::Column::Id)
+            .all(self.db)
+            .await?
+            .into_iter()
+            .map(User::try_from)
+            .collect()
+    }
+
+    /// Changes what a user is allowed to do. The last admin can't be demoted.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the updated `User` if successful, or an error otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn set_role(&self, id: UserId, role: Role) -> Result<User, UserServiceError> {
+        let user = self.get_user(id).await?;
+        if user.role == Role::Admin && role != Role::Admin {
+            self.ensure_other_admin().await?;
+        }
+
+        let active_model = user::ActiveModel {
+            id: ActiveValue::Unchanged(id),
+            role: ActiveValue::Set(role.to_string()),
+            ..Default::default()
+        };
+        let updated_model = active_model.update(self.db).await?;
+        User::try_from(updated_model)
+    }
+
+    /// Deletes a user, so that they can't log in anymore. The last admin can't be deleted.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the deleted `User` if successful, or an error otherwise.
+    #[tracing::instrument(skip(self))]
+    pub async fn delete_user(&self, id: UserId) -> Result<User, UserServiceError> {
+        let user = self.get_user(id).await?;
+        if user.role == Role::Admin {
+            self.ensure_other_admin().await?;
+        }
+
+        user::Entity::delete_by_id(id).exec(self.db).await?;
+        Ok(user)
+    }
+
+    async fn get_user(&self, id: UserId) -> Result<User, UserServiceError> {
+        user::Entity::find_by_id(id)
+            .one(self.db)
+            .await?
+            .ok_or(UserServiceError::UserNotFound(id))
+            .and_then(User::try_from)
+    }
+
+    async fn find_model(&self, username: &str) -> Result<Option<user::Model>, UserServiceError> {
+        Ok(user::Entity::find()
+            .filter(user::Column::Username.eq(username.trim()))
+            .one(self.db)
+            .await?)
+    }
+
+    /// Fails with `LastAdmin` unless there is more than one admin.
+    async fn ensure_other_admin(&self) -> Result<(), UserServiceError> {
+        let admins = user::Entity::find()
+            .filter(user::Column::Role.eq(Role::Admin.as_str()))
+            .count(self.db)
+            .await?;
+        if admins <= 1 {
+            return Err(UserServiceError::LastAdmin);
+        }
+        Ok(())
+    }
+}
+
+/// Hashes a password for storage, with a fresh random salt.
+fn hash_password(password: &str) -> Result<String, UserServiceError> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(UserServiceError::PasswordHash)
+}
+
+/// Checks a password against a hash made by `hash_password`.
+fn verify_password(password: &str, password_hash: &str) -> Result<bool, UserServiceError> {
+    let parsed = PasswordHash::new(password_hash).map_err(UserServiceError::PasswordHash)?;
+    Ok(Argon2::default()
+        .verify_password(password.as_bytes(), &parsed)
+        .is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn passwords_only_verify_against_their_own_hash() {
+        let first = hash_password("correct horse").unwrap();
+        let second = hash_password("correct horse").unwrap();
+
+        assert_ne!(first, second);
+        assert!(!first.contains("correct horse"));
+        assert!(verify_password("correct horse", &first).unwrap());
+        assert!(verify_password("correct horse", &second).unwrap());
+        assert!(!verify_password("battery staple", &first).unwrap());
+        assert!(verify_password("correct horse", "not a hash").is_err());
+    }
+
+    #[test]
+    fn roles_include_the_roles_below_them() {
+        assert!(Role::Admin.includes(Role::Editor));
+        assert!(Role::Editor.includes(Role::Viewer));
+        assert!(Role::Editor.includes(Role::Editor));
+        assert!(!Role::Viewer.includes(Role::Editor));
+        assert!(!Role::Editor.includes(Role::Admin));
+        assert_eq!(Role::required_for(&Method::GET), Role::Viewer);
+        assert_eq!(Role::required_for(&Method::DELETE), Role::Editor);
+    }
+
+    #[test]
+    fn roles_round_trip_through_strings() {
+        for role in Role::ALL {
+            assert_eq!(role.to_string().parse::<Role>().unwrap(), role);
+        }
+        assert!("owner".parse::<Role>().is_err());
+    }
+}
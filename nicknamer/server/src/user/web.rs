@@ -0,0 +1,226 @@
+use askama::Template;
+use axum::{
+    Form, Router,
+    extract::{Path, State},
+    http::{HeaderMap, HeaderName, HeaderValue, StatusCode},
+    response::Html,
+    routing::{delete, get, put},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use typed_ids::DiscordId;
+
+use crate::user::{MIN_PASSWORD_LENGTH, Role, User, UserId, UserService, UserServiceError};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateUserForm {
+    username: String,
+    password: String,
+    role: Role,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetRoleForm {
+    role: Role,
+}
+
+/// The Discord user to link, as submitted in the users table. Empty unlinks the user.
+#[derive(Debug, Deserialize)]
+pub struct SetDiscordIdForm {
+    discord_id: String,
+}
+
+#[derive(Clone, Debug)]
+pub struct UserState {
+    pub db: Arc<sea_orm::DatabaseConnection>,
+}
+
+/// Custom error type for user handler operations.
+#[derive(Debug, thiserror::Error)]
+enum UserError {
+    /// Represents an error during template rendering.
+    #[error("Template rendering failed")]
+    Template(#[from] askama::Error),
+    /// Represents a user service error.
+    #[error("User service error")]
+    Service(#[from] UserServiceError),
+    /// Represents a Discord ID that isn't a Discord user ID.
+    #[error("Invalid Discord ID")]
+    InvalidDiscordId,
+}
+
+impl axum::response::IntoResponse for UserError {
+    fn into_response(self) -> axum::response::Response {
+        let (status_code, user_facing_error_message) = match self {
+            UserError::Service(
+                err @ (UserServiceError::MissingUsername
+                | UserServiceError::PasswordTooShort
+                | UserServiceError::DuplicateUsername(_)
+                | UserServiceError::DuplicateDiscordId(_)),
+            ) => (StatusCode::UNPROCESSABLE_ENTITY, err.to_string()),
+            UserError::InvalidDiscordId => (
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Discord IDs are the numeric user IDs Discord shows in developer mode.".to_string(),
+            ),
+            UserError::Service(UserServiceError::LastAdmin) => (
+                StatusCode::CONFLICT,
+                "Someone has to stay an admin. Make another user an admin first.".to_string(),
+            ),
+            UserError::Service(UserServiceError::UserNotFound(_)) => (
+                StatusCode::NOT_FOUND,
+                "The user doesn't exist anymore. They may have been deleted already.".to_string(),
+            ),
+            _ => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "An unexpected error occurred while processing your request. Please try again later."
+                    .to_string(),
+            ),
+        };
+
+        let error_template = ErrorMessageTemplate::new(user_facing_error_message);
+        let Ok(rendered) = error_template.render() else {
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        };
+
+        let mut response = (status_code, Html(rendered)).into_response();
+        // Add HTMX headers to retarget the error message to the error div
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            HeaderName::from_static("hx-retarget"),
+            HeaderValue::from_static("#user-error"),
+        );
+        headers.insert(
+            HeaderName::from_static("hx-reswap"),
+            HeaderValue::from_static("innerHTML"),
+        );
+        response.headers_mut().extend(headers);
+        response
+    }
+}
+
+#[derive(Template)]
+#[template(path = "users.html")]
+struct UsersTemplate {
+    min_password_length: usize,
+}
+
+#[derive(Template)]
+#[template(path = "users/users_table.html")]
+struct UsersTableTemplate {
+    users: Vec<User>,
+    roles: [Role; 3],
+}
+
+impl UsersTableTemplate {
+    pub fn new(users: Vec<User>) -> Self {
+        Self {
+            users,
+            roles: Role::ALL,
+        }
+    }
+}
+
+#[derive(Template)]
+#[template(path = "names/error_message.html")]
+struct ErrorMessageTemplate {
+    message: String,
+}
+
+impl ErrorMessageTemplate {
+    pub fn new(message: String) -> Self {
+        Self { message }
+    }
+}
+
+/// Renders the table of all users.
+async fn render_users_table(service: &UserService<'_>) -> Result<Html<String>, UserError> {
+    let users = service.get_all_users().await?;
+    let template = UsersTableTemplate::new(users);
+    template.render().map(Html).map_err(UserError::from)
+}
+
+/// Handler for GET /admin/users that displays the users page.
+#[tracing::instrument]
+async fn users_handler() -> Result<Html<String>, UserError> {
+    let template = UsersTemplate {
+        min_password_length: MIN_PASSWORD_LENGTH,
+    };
+    template.render().map(Html).map_err(UserError::from)
+}
+
+/// Handler for GET /admin/users/table that returns just the users table fragment.
+#[tracing::instrument(skip(state))]
+async fn users_table_handler(
+    State(state): State<Arc<UserState>>,
+) -> Result<Html<String>, UserError> {
+    let service = UserService::new(&state.db);
+    render_users_table(&service).await
+}
+
+/// Handler for POST /admin/users that registers a user.
+#[tracing::instrument(skip(state, form), fields(username = %form.username, role = %form.role))]
+async fn create_user_handler(
+    State(state): State<Arc<UserState>>,
+    Form(form): Form<CreateUserForm>,
+) -> Result<Html<String>, UserError> {
+    let service = UserService::new(&state.db);
+    service
+        .create_user(form.username, &form.password, form.role)
+        .await?;
+    render_users_table(&service).await
+}
+
+/// Handler for PUT /admin/users/{id}/role that changes what a user is allowed to do.
+#[tracing::instrument(skip(state))]
+async fn set_role_handler(
+    State(state): State<Arc<UserState>>,
+    Path(id): Path<UserId>,
+    Form(form): Form<SetRoleForm>,
+) -> Result<Html<String>, UserError> {
+    let service = UserService::new(&state.db);
+    service.set_role(id, form.role).await?;
+    render_users_table(&service).await
+}
+
+/// Handler for PUT /admin/users/{id}/discord that links a user to the Discord user that may log
+/// in as them, or unlinks them if the Discord ID is left empty.
+#[tracing::instrument(skip(state))]
+async fn set_discord_id_handler(
+    State(state): State<Arc<UserState>>,
+    Path(id): Path<UserId>,
+    Form(form): Form<SetDiscordIdForm>,
+) -> Result<Html<String>, UserError> {
+    let discord_id = match form.discord_id.trim() {
+        "" => None,
+        discord_id => Some(
+            discord_id
+                .parse::<DiscordId>()
+                .map_err(|_| UserError::InvalidDiscordId)?,
+        ),
+    };
+    let service = UserService::new(&state.db);
+    service.set_discord_id(id, discord_id).await?;
+    render_users_table(&service).await
+}
+
+/// Handler for DELETE /admin/users/{id} that deletes a user.
+#[tracing::instrument(skip(state))]
+async fn delete_user_handler(
+    State(state): State<Arc<UserState>>,
+    Path(id): Path<UserId>,
+) -> Result<Html<String>, UserError> {
+    let service = UserService::new(&state.db);
+    service.delete_user(id).await?;
+    render_users_table(&service).await
+}
+
+/// Creates and returns the router of the user admin pages.
+pub fn create_user_router(state: Arc<UserState>) -> Router {
+    Router::new()
+        .route("/admin/users", get(users_handler).post(create_user_handler))
+        .route("/admin/users/table", get(users_table_handler))
+        .route("/admin/users/{id}", delete(delete_user_handler))
+        .route("/admin/users/{id}/role", put(set_role_handler))
+        .route("/admin/users/{id}/discord", put(set_discord_id_handler))
+        .with_state(state)
+}
@@ -2,90 +2,241 @@ pub(crate) mod v1 {
     use std::sync::Arc;
 
     use crate::{
+        api_token::web::ApiTokenState,
+        audit::web::AuditState,
         auth::{self, AuthState},
         name::web::NameState,
+        server::web::ServerState,
+        user::Role,
     };
 
+    use feature_flags::FeatureFlags;
+
     use axum::{
         Router,
         middleware::{from_fn, from_fn_with_state},
+        response::Redirect,
+        routing::get,
     };
 
     use tower::ServiceBuilder;
-    use utoipa::{OpenApi, ToSchema};
+    use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+    use utoipa::{Modify, OpenApi};
     use utoipa_swagger_ui::SwaggerUi;
 
-    /// Unified error response for all API endpoints
-    #[derive(serde::Serialize, Debug, ToSchema)]
-    pub struct ServerErrorResponse {
-        pub error: String,
-        pub message: String,
-    }
-
-    impl ServerErrorResponse {
-        /// Create a new error response with just an error code and default message
-        pub fn new(error: String) -> Self {
-            Self {
-                error: error.clone(),
-                message: format!("An error occurred: {}", error),
-            }
-        }
-
-        /// Create a new error response with both error code and custom message
-        pub fn new_with_message(error: String, message: String) -> Self {
-            Self { error, message }
-        }
-    }
-
     /// OpenAPI documentation for the v1 API
     #[derive(OpenApi)]
     #[openapi(
         paths(
             crate::auth::api::v1::json_login_handler,
             crate::name::api::v1::get_names_handler,
+            crate::name::api::v1::create_name_handler,
+            crate::name::api::v1::update_name_handler,
+            crate::name::api::v1::delete_name_handler,
+            crate::name::api::v1::bulk_update_server_handler,
+            crate::name::api::v1::export_names_handler,
+            crate::name::api::v1::search_names_handler,
+            crate::name::api::v1::get_trash_handler,
+            crate::name::api::v1::restore_name_handler,
+            crate::name::api::v1::purge_name_handler,
+            crate::server::api::v1::get_servers_handler,
+            crate::server::api::v1::create_server_handler,
+            crate::server::api::v1::get_server_handler,
+            crate::server::api::v1::update_server_handler,
+            crate::server::api::v1::delete_server_handler,
+            crate::name::api::v1::get_cache_stats_handler,
+            crate::audit::api::v1::get_audit_log_handler,
         ),
         components(
             schemas(
                 crate::auth::api::v1::JsonLoginRequest,
                 crate::auth::api::v1::LoginResponse,
-                ServerErrorResponse,
+                api_error::ProblemDetails,
+                api_error::FieldError,
                 crate::name::api::v1::NameJson,
-                crate::name::api::v1::NamesResponse,
+                crate::name::api::v1::CreateNameRequest,
+                crate::name::api::v1::UpdateNameRequest,
+                crate::name::api::v1::BulkUpdateServerRequest,
+                crate::name::NameSortField,
+                crate::name::export::ExportFormat,
+                crate::name::api::v1::TrashedNameJson,
+                crate::name::api::v1::CacheStatsJson,
+                pagination::Paginated<crate::name::api::v1::TrashedNameJson>,
+                pagination::Paginated<crate::name::api::v1::NameJson>,
+                pagination::SortOrder,
+                crate::server::api::v1::ServerJson,
+                crate::server::api::v1::CreateServerRequest,
+                crate::server::api::v1::UpdateServerRequest,
+                crate::audit::api::v1::AuditEntryJson,
+                crate::audit::AuditAction,
+                crate::audit::NameValues,
+                pagination::Paginated<crate::audit::api::v1::AuditEntryJson>,
             )
         ),
+        modifiers(&BearerAuth),
+        security(("bearer_auth" = [])),
         tags(
             (name = "Authentication", description = "Authentication endpoints"),
-            (name = "Names", description = "Name management endpoints")
+            (name = "Names", description = "Name management endpoints"),
+            (name = "Servers", description = "Registry of the Discord servers names are used in"),
+            (name = "Audit", description = "Audit log of name changes")
         ),
         info(
             title = "Nicknamer API",
             version = "1.0.0",
-            description = "API for managing Discord nicknames and authentication"
+            description = "API for managing Discord nicknames and authentication. Authenticate with the JWT from /api/v1/login or with an API token, as a Bearer token."
         )
     )]
     pub struct ApiDoc;
 
-    const API_DOCS_PATH: &str = "/api-docs/openapi.json";
+    /// Documents the Bearer authentication every endpoint but login needs, so that Swagger UI
+    /// can send a JWT or an API token.
+    struct BearerAuth;
+
+    impl Modify for BearerAuth {
+        fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+            let components = openapi.components.get_or_insert_with(Default::default);
+            components.add_security_scheme(
+                "bearer_auth",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+        }
+    }
+
+    const API_DOCS_PATH: &str = "/api/v1/openapi.json";
+    /// Where the document was served before it moved next to the API, kept for old clients.
+    const OLD_API_DOCS_PATH: &str = "/api-docs/openapi.json";
     const SWAGGER_UI_PATH: &str = "/swagger-ui";
 
     /// Creates the API routes for JSON API endpoints.
     pub fn create_api_router(
         auth_state: Arc<AuthState>,
         name_state: Arc<NameState>,
+        server_state: Arc<ServerState>,
+        token_state: Arc<ApiTokenState>,
+        audit_state: Arc<AuditState>,
+        flags: FeatureFlags,
     ) -> axum::Router {
+        let rate_limits = auth_state.rate_limits;
         let login_router = auth::api::v1::create_api_router(auth_state.clone());
-        let names_router = crate::name::api::v1::create_api_router(name_state.clone());
-        let protected_routes = names_router
-            .layer(ServiceBuilder::new().layer(from_fn(auth::api::v1::require_auth_middleware)));
+        let names_router = crate::name::api::v1::create_api_router(name_state.clone())
+            .merge(crate::server::api::v1::create_api_router(server_state))
+            .route_layer(from_fn(
+                auth::api::v1::require_editor_for_changes_middleware,
+            ));
+        let admin_router = crate::audit::api::v1::create_api_router(audit_state)
+            .merge(crate::name::api::v1::create_admin_api_router(name_state))
+            .nest("/admin/feature-flags", flags.admin_router())
+            .route_layer(from_fn_with_state(
+                Role::Admin,
+                auth::api::v1::require_role_middleware,
+            ));
+        let protected_routes = names_router.merge(admin_router).layer(
+            ServiceBuilder::new()
+                .layer(from_fn(auth::api::v1::require_auth_middleware))
+                .layer(rate_limits.api_per_user()),
+        );
         let public_routes = login_router;
-        let api_routes = public_routes.merge(protected_routes);
+        let api_routes = public_routes.merge(protected_routes).layer(
+            ServiceBuilder::new()
+                .layer(rate_limits.api_per_ip())
+                .layer(from_fn_with_state(
+                    auth_state.clone(),
+                    auth::api::v1::auth_user_middleware,
+                ))
+                .layer(from_fn_with_state(
+                    token_state,
+                    auth::api::v1::api_token_middleware,
+                ))
+                .layer(from_fn_with_state(
+                    auth_state,
+                    auth::api::v1::user_role_middleware,
+                )),
+        );
 
         Router::new()
             .merge(SwaggerUi::new(SWAGGER_UI_PATH).url(API_DOCS_PATH, ApiDoc::openapi()))
+            .route(
+                OLD_API_DOCS_PATH,
+                get(|| async { Redirect::permanent(API_DOCS_PATH) }),
+            )
             .nest("/api/v1", api_routes)
-            .layer(ServiceBuilder::new().layer(from_fn_with_state(
-                auth_state,
-                auth::api::v1::auth_user_middleware,
-            )))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::flags::feature_flags;
+        use crate::name::cache::NameCache;
+        use axum::body::Body;
+        use axum::http::{Request, StatusCode};
+        use std::time::Duration;
+        use tower::ServiceExt;
+
+        #[tokio::test]
+        async fn serves_the_openapi_document_with_the_api() {
+            let db = Arc::new(sea_orm::DatabaseConnection::default());
+            let config = crate::config::Config {
+                db_url: "".into(),
+                port: 8080,
+                admin_username: "admin".to_string(),
+                admin_password: "password".into(),
+                jwt_secret: "test_secret".into(),
+                trash_retention_days: 30,
+                name_cache_ttl_seconds: 60,
+                discord_client_id: None,
+                discord_client_secret: None,
+                discord_redirect_url: None,
+                login_attempts_per_minute: 5,
+                api_ip_requests_per_minute: 300,
+                api_user_requests_per_second: 10,
+                api_user_burst: 20,
+                trusted_proxies: 0,
+            };
+            let app = create_api_router(
+                Arc::new(AuthState::from_config(&config, db.clone())),
+                Arc::new(NameState {
+                    db: db.clone(),
+                    trash_retention_days: 30,
+                    cache: NameCache::new(Duration::from_secs(60)),
+                }),
+                Arc::new(ServerState {
+                    db: db.clone(),
+                    name_cache: NameCache::new(Duration::from_secs(60)),
+                }),
+                Arc::new(ApiTokenState { db: db.clone() }),
+                Arc::new(AuditState { db: db.clone() }),
+                feature_flags((*db).clone()),
+            );
+
+            let old_path_response = app
+                .clone()
+                .oneshot(Request::get(OLD_API_DOCS_PATH).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+            let response = app
+                .oneshot(Request::get(API_DOCS_PATH).body(Body::empty()).unwrap())
+                .await
+                .unwrap();
+
+            assert_eq!(old_path_response.status(), StatusCode::PERMANENT_REDIRECT);
+            assert_eq!(old_path_response.headers()["location"], API_DOCS_PATH);
+
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let document: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert_eq!(document["info"]["title"], "Nicknamer API");
+            assert!(document["paths"]["/api/v1/names"]["get"].is_object());
+            assert_eq!(
+                document["components"]["securitySchemes"]["bearer_auth"]["scheme"],
+                "bearer"
+            );
+            assert_eq!(
+                document["paths"]["/api/v1/login"]["post"]["security"],
+                serde_json::json!([{}])
+            );
+        }
     }
 }
@@ -4,8 +4,11 @@ use axum::http::{HeaderName, StatusCode, header};
 use axum::middleware::{from_fn, from_fn_with_state};
 use axum::response::Html;
 use migration::MigratorTrait;
+use observability::{Metrics, RequestIdLayer, TraceContextLayer, request_span, track_http_metrics};
 use sea_orm::Database;
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 use tower::ServiceBuilder;
 use tower_http::cors::CorsLayer;
 use tower_http::sensitive_headers::{
@@ -13,11 +16,20 @@ use tower_http::sensitive_headers::{
 };
 use tower_http::trace::TraceLayer;
 
+use crate::api_token::web::{ApiTokenState, create_api_token_router};
+use crate::audit::web::{AuditState, create_audit_router};
 use crate::auth::{
     AuthState, CurrentUser, auth_user_middleware, create_login_router, login_redirect_middleware,
+    require_editor_for_changes_middleware, require_role_middleware, user_role_middleware,
 };
+use crate::background;
 use crate::config::{self, Config};
+use crate::flags::feature_flags;
+use crate::name::cache::NameCache;
 use crate::name::web::{NameState, create_name_router};
+use crate::server::web::{ServerState, create_server_router};
+use crate::user::web::{UserState, create_user_router};
+use crate::user::{Role, UserService};
 use crate::web::api::v1::create_api_router;
 pub(crate) mod api;
 
@@ -57,33 +69,132 @@ pub async fn start_web_server(config: config::Config) -> anyhow::Result<()> {
     let listener = tokio::net::TcpListener::bind(&server_address).await?;
     tracing::info!("Web server running on http://{}", server_address);
 
-    let db = Database::connect(&config.db_url).await?;
+    let metrics = Metrics::install()?;
+
+    let db = Database::connect(config.db_url.expose().as_str()).await?;
     migration::Migrator::up(&db, None).await?;
     tracing::info!("Database migrations applied successfully");
 
+    let flags = feature_flags(db.clone());
+    flags.reload().await?;
+
+    // The configured admin is registered on first start, so that someone can log in at all
+    let admin = UserService::new(&db)
+        .ensure_admin(
+            config.admin_username.clone(),
+            config.admin_password.expose(),
+        )
+        .await?;
+    if let Some(admin) = admin {
+        tracing::info!("Registered the first admin, {}", admin.username());
+    }
+
+    let db = Arc::new(db);
     // Create AuthState from config
-    let auth_state = Arc::new(AuthState::from_config(&config));
-    let name_state = Arc::new(NameState { db: Arc::new(db) });
+    let auth_state = Arc::new(AuthState::from_config(&config, db.clone()));
+    let name_cache = NameCache::new(Duration::from_secs(config.name_cache_ttl_seconds));
+    let name_state = Arc::new(NameState {
+        db: db.clone(),
+        trash_retention_days: config.trash_retention_days,
+        cache: name_cache.clone(),
+    });
+    let token_state = Arc::new(ApiTokenState { db: db.clone() });
+    let user_state = Arc::new(UserState { db: db.clone() });
+    let server_state = Arc::new(ServerState {
+        db: db.clone(),
+        name_cache,
+    });
+    let audit_state = Arc::new(AuditState { db });
+
+    let scheduler = background::scheduler(name_state.clone(), flags.clone()).start();
 
-    let web_app = create_web_handler(auth_state.clone(), name_state.clone());
-    let api = create_api_router(auth_state.clone(), name_state.clone());
-    let app = web_app.merge(api);
+    let web_app = create_web_handler(
+        auth_state.clone(),
+        name_state.clone(),
+        server_state.clone(),
+        token_state.clone(),
+        user_state,
+        audit_state.clone(),
+    );
+    let api = create_api_router(
+        auth_state.clone(),
+        name_state.clone(),
+        server_state,
+        token_state,
+        audit_state,
+        flags.clone(),
+    );
+    let app = web_app
+        .merge(api)
+        .route_layer(from_fn(track_http_metrics))
+        .merge(metrics.router())
+        .layer(Extension(flags))
+        .layer(RequestIdLayer)
+        .layer(TraceContextLayer);
 
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal())
+    .await?;
+    scheduler.shutdown().await;
     Ok(())
 }
 
+/// Resolves once the process is asked to stop, with Ctrl+C or, on Unix, SIGTERM.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", err);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(err) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    tracing::info!("Shutdown signal received, finishing in-flight work");
+}
+
 /// Creates the main web application router with all routes and middleware configured.
 ///
 /// # Arguments
 ///
 /// * `auth_state` - The authentication state for handling user sessions
 /// * `name_state` - The name state for managing name-related operations
+/// * `server_state` - The server state for the server registry pages
+/// * `token_state` - The API token state for the token admin pages
+/// * `user_state` - The user state for the user admin pages
+/// * `audit_state` - The audit state for the audit log page
 ///
 /// # Returns
 ///
 /// A configured `Router` with all public and protected routes, middleware layers applied
-fn create_web_handler(auth_state: Arc<AuthState>, name_state: Arc<NameState>) -> axum::Router {
+fn create_web_handler(
+    auth_state: Arc<AuthState>,
+    name_state: Arc<NameState>,
+    server_state: Arc<ServerState>,
+    token_state: Arc<ApiTokenState>,
+    user_state: Arc<UserState>,
+    audit_state: Arc<AuditState>,
+) -> axum::Router {
     use axum::Router;
 
     let sensitive_headers: Arc<[_]> = Arc::new([
@@ -97,11 +208,20 @@ fn create_web_handler(auth_state: Arc<AuthState>, name_state: Arc<NameState>) ->
     let login_router = create_login_router(auth_state.clone());
 
     // Create name router with database connection
-    let name_router = create_name_router(name_state);
+    let name_router = create_name_router(name_state)
+        .merge(create_server_router(server_state))
+        .route_layer(from_fn(require_editor_for_changes_middleware));
+
+    let admin_router = Router::new()
+        .merge(create_api_token_router(token_state))
+        .merge(create_user_router(user_state))
+        .merge(create_audit_router(audit_state))
+        .route_layer(from_fn_with_state(Role::Admin, require_role_middleware));
 
-    let protected_routes = Router::new().merge(name_router).layer(
+    let protected_routes = Router::new().merge(name_router).merge(admin_router).layer(
         ServiceBuilder::new()
             .layer(from_fn_with_state(auth_state.clone(), auth_user_middleware))
+            .layer(from_fn_with_state(auth_state.clone(), user_role_middleware))
             .layer(from_fn(login_redirect_middleware)),
     );
 
@@ -115,7 +235,8 @@ fn create_web_handler(auth_state: Arc<AuthState>, name_state: Arc<NameState>) ->
         .merge(login_router)
         .layer(
             ServiceBuilder::new()
-                .layer(from_fn_with_state(auth_state.clone(), auth_user_middleware)),
+                .layer(from_fn_with_state(auth_state.clone(), auth_user_middleware))
+                .layer(from_fn_with_state(auth_state.clone(), user_role_middleware)),
         );
 
     Router::new()
@@ -126,7 +247,7 @@ fn create_web_handler(auth_state: Arc<AuthState>, name_state: Arc<NameState>) ->
                 .layer(SetSensitiveRequestHeadersLayer::from_shared(Arc::clone(
                     &sensitive_headers,
                 )))
-                .layer(TraceLayer::new_for_http())
+                .layer(TraceLayer::new_for_http().make_span_with(request_span))
                 .layer(SetSensitiveResponseHeadersLayer::from_shared(
                     sensitive_headers,
                 ))
@@ -151,10 +272,12 @@ pub async fn welcome_handler() -> Result<Html<String>, WebError> {
 #[tracing::instrument]
 pub async fn call_to_action_handler(
     current_user: Option<Extension<CurrentUser>>,
+    role: Option<Extension<Role>>,
 ) -> Result<Html<String>, WebError> {
+    let is_admin = role.is_some_and(|Extension(role)| role.includes(Role::Admin));
     let template = match current_user {
-        Some(Extension(user)) => CallToActionTemplate::new(Some(user.username.clone())),
-        None => CallToActionTemplate::new(None),
+        Some(Extension(user)) => CallToActionTemplate::new(Some(user.username.clone()), is_admin),
+        None => CallToActionTemplate::new(None, false),
     };
     template.render().map(Html).map_err(WebError::from)
 }
@@ -173,11 +296,13 @@ impl IndexTemplate {
 #[template(path = "welcome/call_to_action.html")]
 struct CallToActionTemplate {
     username: Option<String>,
+    /// Whether to offer the admin pages
+    is_admin: bool,
 }
 
 impl CallToActionTemplate {
-    pub fn new(username: Option<String>) -> Self {
-        Self { username }
+    pub fn new(username: Option<String>, is_admin: bool) -> Self {
+        Self { username, is_admin }
     }
 }
 
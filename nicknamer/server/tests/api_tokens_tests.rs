@@ -0,0 +1,176 @@
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use axum::middleware::{from_fn, from_fn_with_state};
+use nicknamer_server::api_token::web::{ApiTokenState, create_api_token_router};
+use nicknamer_server::api_token::{ApiTokenService, TokenScope};
+use nicknamer_server::auth::CurrentUser;
+use nicknamer_server::auth::api::v1::{api_token_middleware, require_auth_middleware};
+use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+mod common;
+
+use db_test_support::TestDb;
+
+async fn setup() -> anyhow::Result<TestDb> {
+    // Allow multiple calls to init for tests.
+    let _ = tracing_subscriber::fmt().try_init();
+    common::setup_db().await
+}
+
+fn create_token_state(db: DatabaseConnection) -> Arc<ApiTokenState> {
+    Arc::new(ApiTokenState { db: Arc::new(db) })
+}
+
+/// A protected API route that echoes who made the request, behind the token middleware.
+fn create_protected_app(state: Arc<ApiTokenState>) -> axum::Router {
+    let whoami = |axum::Extension(user): axum::Extension<CurrentUser>| async move { user.username };
+    axum::Router::new()
+        .route("/api/v1/whoami", axum::routing::get(whoami).post(whoami))
+        .layer(from_fn(require_auth_middleware))
+        .layer(from_fn_with_state(state, api_token_middleware))
+}
+
+fn request(method: Method, token: &str) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri("/api/v1/whoami")
+        .header("authorization", format!("Bearer {}", token))
+        .body(Body::empty())
+        .unwrap()
+}
+
+async fn body_text(response: axum::response::Response) -> String {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn can_authenticate_with_created_tokens_only() {
+    let state = setup().await.expect("Failed to setup test context");
+    let service = ApiTokenService::new(&state.db);
+
+    let (token, secret) = service
+        .create_token(" Discord bot ".to_string(), TokenScope::ReadWrite)
+        .await
+        .expect("Failed to create token");
+
+    assert_eq!(token.name(), "Discord bot");
+    assert_eq!(token.scope(), TokenScope::ReadWrite);
+    let authenticated = service.authenticate(&secret).await.unwrap();
+    assert_eq!(authenticated, Some(token.clone()));
+    assert_eq!(service.authenticate("nn_guessed").await.unwrap(), None);
+
+    service.revoke_token(token.id()).await.unwrap();
+    assert_eq!(service.authenticate(&secret).await.unwrap(), None);
+    assert!(service.get_all_tokens().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn tokens_authenticate_api_requests_within_their_scope() {
+    let state = setup().await.expect("Failed to setup test context");
+    let service = ApiTokenService::new(&state.db);
+    let (_, read_only) = service
+        .create_token("reporting".to_string(), TokenScope::ReadOnly)
+        .await
+        .unwrap();
+    let (_, read_write) = service
+        .create_token("Discord bot".to_string(), TokenScope::ReadWrite)
+        .await
+        .unwrap();
+    let app = create_protected_app(create_token_state(state.db));
+
+    let response = app
+        .clone()
+        .oneshot(request(Method::GET, &read_only))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_text(response).await, "token:reporting");
+
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, &read_only))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    assert_eq!(
+        response.headers()["content-type"],
+        "application/problem+json"
+    );
+
+    let response = app
+        .clone()
+        .oneshot(request(Method::POST, &read_write))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(body_text(response).await, "token:Discord bot");
+
+    let response = app
+        .oneshot(request(Method::GET, "nn_unknown"))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn can_create_and_revoke_tokens_from_the_admin_page() {
+    let state = setup().await.expect("Failed to setup test context");
+    let app = create_api_token_router(create_token_state(state.db.clone()));
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/admin/api-tokens")
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from("name=Discord+bot&scope=read_only"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_text(response).await;
+    assert!(body.contains("Created the token \"Discord bot\""));
+    assert!(body.contains("read_only"));
+    assert!(body.contains("nn_"));
+
+    let tokens = ApiTokenService::new(&state.db)
+        .get_all_tokens()
+        .await
+        .unwrap();
+    assert_eq!(tokens.len(), 1);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::DELETE)
+                .uri(format!("/admin/api-tokens/{}", tokens[0].id()))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_text(response).await;
+    assert!(body.contains("No API tokens have been created."));
+    assert!(!body.contains("nn_"));
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::DELETE)
+                .uri(format!("/admin/api-tokens/{}", tokens[0].id()))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
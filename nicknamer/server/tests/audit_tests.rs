@@ -0,0 +1,216 @@
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use nicknamer_server::audit::api::v1::create_api_router;
+use nicknamer_server::audit::web::AuditState;
+use nicknamer_server::audit::{AuditAction, AuditService, NameValues, SYSTEM_ACTOR};
+use nicknamer_server::name::NameService;
+use pagination::PageParams;
+use sea_orm::prelude::DateTimeUtc;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tower::ServiceExt;
+use typed_ids::DiscordId;
+
+mod common;
+
+use db_test_support::TestDb;
+
+async fn setup() -> anyhow::Result<TestDb> {
+    // Allow multiple calls to init for tests.
+    let _ = tracing_subscriber::fmt().try_init();
+    common::setup_db().await
+}
+
+fn snowflake(id: u64) -> DiscordId {
+    DiscordId::new(id).unwrap()
+}
+
+fn values(discord_id: u64, name: &str, server_id: &str) -> NameValues {
+    NameValues {
+        discord_id: snowflake(discord_id),
+        name: name.to_string(),
+        server_id: server_id.to_string(),
+    }
+}
+
+#[tokio::test]
+async fn records_every_change_to_names() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_service = NameService::new(&state.db).acting_as("alice");
+
+    let created = name_service
+        .create_name(
+            snowflake(123456789),
+            "Alice".to_string(),
+            "server1".to_string(),
+        )
+        .await
+        .unwrap();
+    name_service
+        .edit_name_by_id(
+            created.id(),
+            "Alicia".to_string(),
+            "server2".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+    name_service.delete_name_by_id(created.id()).await.unwrap();
+    NameService::new(&state.db)
+        .bulk_create_names("987654321: Bob", "server1".to_string())
+        .await
+        .unwrap();
+
+    let log = AuditService::new(&state.db)
+        .list(PageParams::default())
+        .await
+        .unwrap();
+    assert_eq!(log.total, 4);
+    let [bulk_create, delete, update, create] = log.items.as_slice() else {
+        panic!("Expected 4 entries, got {:?}", log.items);
+    };
+
+    assert_eq!(create.actor(), "alice");
+    assert_eq!(create.action(), AuditAction::Create);
+    assert_eq!(create.name_id(), created.id());
+    assert_eq!(create.before(), None);
+    assert_eq!(create.after(), Some(&values(123456789, "Alice", "server1")));
+
+    assert_eq!(update.action(), AuditAction::Update);
+    assert_eq!(
+        update.before(),
+        Some(&values(123456789, "Alice", "server1"))
+    );
+    assert_eq!(
+        update.after(),
+        Some(&values(123456789, "Alicia", "server2"))
+    );
+
+    assert_eq!(delete.action(), AuditAction::Delete);
+    assert_eq!(
+        delete.before(),
+        Some(&values(123456789, "Alicia", "server2"))
+    );
+    assert_eq!(delete.after(), None);
+
+    assert_eq!(bulk_create.actor(), SYSTEM_ACTOR);
+    assert_eq!(bulk_create.action(), AuditAction::BulkCreate);
+    assert_eq!(
+        bulk_create.after(),
+        Some(&values(987654321, "Bob", "server1"))
+    );
+}
+
+#[tokio::test]
+async fn records_restores_and_purges() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_service = NameService::new(&state.db).acting_as("alice");
+    let created = name_service
+        .create_name(
+            snowflake(123456789),
+            "Alice".to_string(),
+            "server1".to_string(),
+        )
+        .await
+        .unwrap();
+    name_service.delete_name_by_id(created.id()).await.unwrap();
+    name_service.restore_name_by_id(created.id()).await.unwrap();
+    name_service.delete_name_by_id(created.id()).await.unwrap();
+    let in_an_hour = DateTimeUtc::from(SystemTime::now() + Duration::from_secs(60 * 60));
+    NameService::new(&state.db)
+        .purge_trash(in_an_hour)
+        .await
+        .unwrap();
+
+    let log = AuditService::new(&state.db)
+        .list(PageParams::default())
+        .await
+        .unwrap();
+    let [purge, _, restore, ..] = log.items.as_slice() else {
+        panic!("Expected 5 entries, got {:?}", log.items);
+    };
+
+    assert_eq!(restore.actor(), "alice");
+    assert_eq!(restore.action(), AuditAction::Restore);
+    assert_eq!(restore.before(), None);
+    assert_eq!(
+        restore.after(),
+        Some(&values(123456789, "Alice", "server1"))
+    );
+
+    assert_eq!(purge.actor(), SYSTEM_ACTOR);
+    assert_eq!(purge.action(), AuditAction::Purge);
+    assert_eq!(purge.name_id(), created.id());
+    assert_eq!(purge.before(), Some(&values(123456789, "Alice", "server1")));
+    assert_eq!(purge.after(), None);
+}
+
+#[tokio::test]
+async fn does_not_record_changes_that_fail() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_service = NameService::new(&state.db);
+
+    name_service
+        .create_name(
+            snowflake(123456789),
+            "Alice".to_string(),
+            "server1".to_string(),
+        )
+        .await
+        .unwrap();
+    assert!(
+        name_service
+            .create_name(
+                snowflake(123456789),
+                "Again".to_string(),
+                "server1".to_string()
+            )
+            .await
+            .is_err()
+    );
+
+    let log = AuditService::new(&state.db)
+        .list(PageParams::default())
+        .await
+        .unwrap();
+    assert_eq!(log.total, 1);
+}
+
+#[tokio::test]
+async fn can_page_through_the_audit_log_via_json_api() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_service = NameService::new(&state.db).acting_as("token:Discord bot");
+    for id in 1..=3 {
+        name_service
+            .create_name(snowflake(id), format!("User {}", id), "server1".to_string())
+            .await
+            .unwrap();
+    }
+    let app = create_api_router(Arc::new(AuditState {
+        db: Arc::new(state.db.clone()),
+    }));
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri("/audit?page=1&per_page=2")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    assert_eq!(json["total"], 3);
+    assert_eq!(json["total_pages"], 2);
+    assert_eq!(json["items"].as_array().unwrap().len(), 2);
+    assert_eq!(json["items"][0]["actor"], "token:Discord bot");
+    assert_eq!(json["items"][0]["action"], "create");
+    assert_eq!(json["items"][0]["after"]["name"], "User 3");
+    assert_eq!(json["items"][0]["before"], serde_json::Value::Null);
+}
@@ -1,96 +1,100 @@
-use axum::body::Body;
-use axum::extract::Extension;
-use axum::http::Request;
+use axum::extract::{Extension, State};
+use axum::http::StatusCode;
 use axum::middleware::{from_fn, from_fn_with_state};
 use insta::assert_yaml_snapshot;
+use nicknamer_server::auth::oauth::{DISCORD_AUTHORIZE_URL, DiscordOAuth};
 use nicknamer_server::auth::{
-    AuthError, AuthState, CurrentUser, create_login_router, encode_jwt, login_page_handler,
+    AuthError, AuthState, CurrentUser, create_login_router, login_page_handler,
 };
 use nicknamer_server::config::Config;
+use nicknamer_server::user::{Role, UserService};
+use sea_orm::DatabaseConnection;
 use std::sync::Arc;
-use tower::ServiceExt;
+use typed_ids::DiscordId;
 
 mod common;
 
-use common::{HttpResponseSnapshot, stub_user_middleware};
+use common::stub_user_middleware;
+use db_test_support::TestDb;
+use http_snapshot::{HttpResponseSnapshot, TestRequest};
 
-/// Setup function for auth endpoint tests.
-async fn setup_auth_state() -> Arc<AuthState> {
+/// Setup function for auth endpoint tests, with the users stored in `db`.
+async fn setup_auth_state(db: DatabaseConnection) -> Arc<AuthState> {
     let config = Config {
-        db_url: "".to_string(),
+        db_url: "".into(),
         port: 8080,
         admin_username: "admin".to_string(),
-        admin_password: "password".to_string(),
-        jwt_secret: "some_secret".to_string(),
+        admin_password: "password".into(),
+        jwt_secret: "some_secret".into(),
+        trash_retention_days: 30,
+        name_cache_ttl_seconds: 60,
+        discord_client_id: None,
+        discord_client_secret: None,
+        discord_redirect_url: None,
+        login_attempts_per_minute: 5,
+        api_ip_requests_per_minute: 300,
+        api_user_requests_per_second: 10,
+        api_user_burst: 20,
+        // The tests report client addresses the way Railway's proxy does
+        trusted_proxies: 1,
     };
-    Arc::new(AuthState::from_config(&config))
+    Arc::new(AuthState::from_config(&config, Arc::new(db)))
+}
+
+/// Setup function for tests that log in, with a database holding the user admin/password.
+async fn setup_with_admin() -> (TestDb, Arc<AuthState>) {
+    let test_db = common::setup_db()
+        .await
+        .expect("Failed to setup test database");
+    UserService::new(&test_db.db)
+        .create_user("admin".to_string(), "password", Role::Admin)
+        .await
+        .expect("Failed to create admin");
+    let auth_state = setup_auth_state(test_db.db.clone()).await;
+    (test_db, auth_state)
 }
 
 /// Test helper to create test app with auth state.
-async fn create_test_app() -> (axum::Router, Arc<AuthState>) {
-    let auth_state = setup_auth_state().await;
+async fn create_test_app() -> (TestDb, axum::Router, Arc<AuthState>) {
+    let (test_db, auth_state) = setup_with_admin().await;
     let app = create_login_router(auth_state.clone()).layer(from_fn_with_state(
         auth_state.clone(),
         nicknamer_server::auth::auth_user_middleware,
     ));
-    (app, auth_state)
+    (test_db, app, auth_state)
 }
 
 /// Test helper to create test app with a logged-in user.
 async fn create_test_app_with_logged_in_user() -> (axum::Router, Arc<AuthState>) {
-    let auth_state = setup_auth_state().await;
+    let auth_state = setup_auth_state(DatabaseConnection::default()).await;
     let app = create_login_router(auth_state.clone()).layer(from_fn(stub_user_middleware));
     (app, auth_state)
 }
 
 #[tokio::test]
 async fn can_login_with_valid_credentials() {
-    let (app, _auth_state) = create_test_app().await;
-
-    let request = Request::builder()
-        .method("POST")
-        .uri("/login")
-        .header("content-type", "application/x-www-form-urlencoded")
-        .body(Body::from("username=admin&password=password"))
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
+    let (_test_db, app, _auth_state) = create_test_app().await;
 
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
+    let response = TestRequest::post("/login")
+        .form(&[("username", "admin"), ("password", "password")])
+        .send(app)
+        .await;
 
-    let snapshot_data =
-        HttpResponseSnapshot::new(body_text, status, &headers, "login_with_valid_credentials");
+    let snapshot_data = response.html_snapshot("login_with_valid_credentials");
 
     assert_yaml_snapshot!(snapshot_data);
 }
 
 #[tokio::test]
 async fn can_reject_invalid_credentials() {
-    let (app, _auth_state) = create_test_app().await;
+    let (_test_db, app, _auth_state) = create_test_app().await;
 
-    let request = Request::builder()
-        .method("POST")
-        .uri("/login")
-        .header("content-type", "application/x-www-form-urlencoded")
-        .body(Body::from("username=wrong&password=wrong"))
-        .unwrap();
+    let response = TestRequest::post("/login")
+        .form(&[("username", "wrong"), ("password", "wrong")])
+        .send(app)
+        .await;
 
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
-
-    let snapshot_data =
-        HttpResponseSnapshot::new(body_text, status, &headers, "reject_invalid_credentials");
+    let snapshot_data = response.html_snapshot("reject_invalid_credentials");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -119,61 +123,29 @@ async fn can_handle_template_error_with_internal_server_error() {
 
 #[tokio::test]
 async fn can_return_success_when_already_logged_in() {
-    let (app, auth_state) = create_test_app().await;
+    let (_test_db, app, auth_state) = create_test_app().await;
 
     // First, create a valid JWT token
-    let jwt_token = encode_jwt("admin".to_string(), &auth_state.jwt_secret)
-        .await
-        .unwrap();
+    let jwt_token = auth_state.jwt.encode("admin").unwrap();
 
-    let request = Request::builder()
-        .method("POST")
-        .uri("/login")
-        .header("content-type", "application/x-www-form-urlencoded")
-        .header("cookie", format!("auth_token={}", jwt_token))
-        .body(Body::from("username=admin&password=password"))
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
+    let response = TestRequest::post("/login")
+        .cookie("auth_token", &jwt_token)
+        .form(&[("username", "admin"), ("password", "password")])
+        .send(app)
+        .await;
 
-    let snapshot_data = HttpResponseSnapshot::new(
-        body_text,
-        status,
-        &headers,
-        "return_success_when_already_logged_in",
-    );
+    let snapshot_data = response.html_snapshot("return_success_when_already_logged_in");
 
     assert_yaml_snapshot!(snapshot_data);
 }
 
 #[tokio::test]
 async fn can_display_login_page() {
-    let (app, _auth_state) = create_test_app().await;
-
-    let request = Request::builder()
-        .method("GET")
-        .uri("/login")
-        .body(Body::empty())
-        .unwrap();
+    let (_test_db, app, _auth_state) = create_test_app().await;
 
-    let response = app.oneshot(request).await.unwrap();
+    let response = TestRequest::get("/login").send(app).await;
 
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
-
-    let snapshot_data =
-        HttpResponseSnapshot::new(body_text, status, &headers, "display_login_page");
+    let snapshot_data = response.html_snapshot("display_login_page");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -182,34 +154,18 @@ async fn can_display_login_page() {
 async fn can_display_login_page_with_homepage_button_when_logged_in() {
     let (app, _auth_state) = create_test_app_with_logged_in_user().await;
 
-    let request = Request::builder()
-        .method("GET")
-        .uri("/login")
-        .body(Body::empty())
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
+    let response = TestRequest::get("/login").send(app).await;
 
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
-
-    let snapshot_data = HttpResponseSnapshot::new(
-        body_text,
-        status,
-        &headers,
-        "display_login_page_with_homepage_button_when_logged_in",
-    );
+    let snapshot_data =
+        response.html_snapshot("display_login_page_with_homepage_button_when_logged_in");
 
     assert_yaml_snapshot!(snapshot_data);
 }
 
 #[tokio::test]
 async fn can_render_login_page_form_when_user_not_logged_in() {
-    let result = login_page_handler(None).await;
+    let auth_state = setup_auth_state(DatabaseConnection::default()).await;
+    let result = login_page_handler(State(auth_state), None).await;
 
     assert!(result.is_ok());
     let html = result.unwrap().0;
@@ -229,7 +185,8 @@ async fn can_render_login_page_with_homepage_button_when_user_logged_in() {
     let current_user = CurrentUser::new("testuser".to_string());
     let extension = Extension(current_user);
 
-    let result = login_page_handler(Some(extension)).await;
+    let auth_state = setup_auth_state(DatabaseConnection::default()).await;
+    let result = login_page_handler(State(auth_state), Some(extension)).await;
 
     assert!(result.is_ok());
     let html = result.unwrap().0;
@@ -249,37 +206,34 @@ mod api {
 
         use std::sync::Arc;
 
-        use crate::{common::JsonApiResponseSnapshot, setup_auth_state};
+        use crate::{setup_auth_state, setup_with_admin};
+        use db_test_support::TestDb;
+        use http_snapshot::{JsonApiResponseSnapshot, TestRequest};
 
-        use axum::{body::Body, http::Request};
         use insta::assert_yaml_snapshot;
         use nicknamer_server::auth::{AuthState, api::v1::create_api_router};
-        use tower::ServiceExt;
+        use sea_orm::DatabaseConnection;
 
         /// Test helper to create JSON API test app.
-        async fn create_json_api_test_app() -> (axum::Router, Arc<AuthState>) {
-            let auth_state = setup_auth_state().await;
+        async fn create_json_api_test_app() -> (TestDb, axum::Router, Arc<AuthState>) {
+            let (test_db, auth_state) = setup_with_admin().await;
             let app = create_api_router(auth_state.clone());
-            (app, auth_state)
+            (test_db, app, auth_state)
         }
 
         #[tokio::test]
         async fn can_login_with_valid_credentials_via_json_api() {
-            let (app, _auth_state) = create_json_api_test_app().await;
-
-            let login_payload = r#"{"username": "admin", "password": "password"}"#;
+            let (_test_db, app, _auth_state) = create_json_api_test_app().await;
 
-            let request = Request::builder()
-                .method("POST")
-                .uri("/login")
-                .header("content-type", "application/json")
-                .body(Body::from(login_payload))
-                .unwrap();
+            let login_payload = serde_json::json!({"username": "admin", "password": "password"});
 
-            let response = app.oneshot(request).await.unwrap();
+            let response = TestRequest::post("/login")
+                .json(&login_payload)
+                .send(app)
+                .await;
 
-            let status = response.status();
-            let headers = response.headers().clone();
+            let status = response.status;
+            let headers = response.headers;
             assert!(
                 status.is_success(),
                 "Expected success status, got: {}",
@@ -299,32 +253,17 @@ mod api {
 
         #[tokio::test]
         async fn can_reject_invalid_credentials_via_json_api() {
-            let (app, _auth_state) = create_json_api_test_app().await;
-
-            let invalid_payload = r#"{"username": "admin", "password": "wrong_password"}"#;
+            let (_test_db, app, _auth_state) = create_json_api_test_app().await;
 
-            let request = Request::builder()
-                .method("POST")
-                .uri("/login")
-                .header("content-type", "application/json")
-                .body(Body::from(invalid_payload))
-                .unwrap();
+            let invalid_payload =
+                serde_json::json!({"username": "admin", "password": "wrong_password"});
 
-            let response = app.oneshot(request).await.unwrap();
+            let response = TestRequest::post("/login")
+                .json(&invalid_payload)
+                .send(app)
+                .await;
 
-            let status = response.status();
-            let headers = response.headers().clone();
-            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-                .await
-                .unwrap();
-            let body_text = std::str::from_utf8(&body).unwrap();
-
-            let snapshot_data = JsonApiResponseSnapshot::new(
-                body_text,
-                status,
-                &headers,
-                "json_api_reject_invalid_credentials",
-            );
+            let snapshot_data = response.json_snapshot("json_api_reject_invalid_credentials");
 
             assert_yaml_snapshot!(snapshot_data);
         }
@@ -334,8 +273,6 @@ mod api {
             use axum::middleware::from_fn;
             use nicknamer_server::auth::api::v1::require_auth_middleware;
 
-            let (_app, _auth_state) = create_json_api_test_app().await;
-
             // Create a protected route with the require_auth_middleware
             let protected_app = axum::Router::new()
                 .route(
@@ -344,27 +281,12 @@ mod api {
                 )
                 .layer(from_fn(require_auth_middleware));
 
-            let request = Request::builder()
-                .method("GET")
-                .uri("/api/v1/protected")
-                .body(Body::empty())
-                .unwrap();
-
-            let response = protected_app.oneshot(request).await.unwrap();
+            let response = TestRequest::get("/api/v1/protected")
+                .send(protected_app)
+                .await;
 
-            let status = response.status();
-            let headers = response.headers().clone();
-            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-                .await
-                .unwrap();
-            let body_text = std::str::from_utf8(&body).unwrap();
-
-            let snapshot_data = JsonApiResponseSnapshot::new(
-                body_text,
-                status,
-                &headers,
-                "json_api_reject_requests_without_authorization_header",
-            );
+            let snapshot_data =
+                response.json_snapshot("json_api_reject_requests_without_authorization_header");
 
             assert_yaml_snapshot!(snapshot_data);
         }
@@ -374,7 +296,7 @@ mod api {
             use axum::middleware::{from_fn, from_fn_with_state};
             use nicknamer_server::auth::api::v1::{auth_user_middleware, require_auth_middleware};
 
-            let auth_state = setup_auth_state().await;
+            let auth_state = setup_auth_state(DatabaseConnection::default()).await;
 
             // Create a protected route with both middlewares
             let protected_app = axum::Router::new()
@@ -385,28 +307,13 @@ mod api {
                 .layer(from_fn(require_auth_middleware))
                 .layer(from_fn_with_state(auth_state.clone(), auth_user_middleware));
 
-            let request = Request::builder()
-                .method("GET")
-                .uri("/api/v1/protected")
-                .header("authorization", "Bearer invalid_token")
-                .body(Body::empty())
-                .unwrap();
+            let response = TestRequest::get("/api/v1/protected")
+                .bearer_token("invalid_token")
+                .send(protected_app)
+                .await;
 
-            let response = protected_app.oneshot(request).await.unwrap();
-
-            let status = response.status();
-            let headers = response.headers().clone();
-            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-                .await
-                .unwrap();
-            let body_text = std::str::from_utf8(&body).unwrap();
-
-            let snapshot_data = JsonApiResponseSnapshot::new(
-                body_text,
-                status,
-                &headers,
-                "json_api_reject_requests_with_invalid_bearer_token",
-            );
+            let snapshot_data =
+                response.json_snapshot("json_api_reject_requests_with_invalid_bearer_token");
 
             assert_yaml_snapshot!(snapshot_data);
         }
@@ -416,7 +323,7 @@ mod api {
             use axum::middleware::{from_fn, from_fn_with_state};
             use nicknamer_server::auth::api::v1::{auth_user_middleware, require_auth_middleware};
 
-            let auth_state = setup_auth_state().await;
+            let auth_state = setup_auth_state(DatabaseConnection::default()).await;
 
             // Create a protected route with both middlewares
             let protected_app = axum::Router::new()
@@ -428,35 +335,190 @@ mod api {
                 .layer(from_fn_with_state(auth_state.clone(), auth_user_middleware));
 
             // Create a valid JWT token
-            let jwt_token =
-                nicknamer_server::auth::encode_jwt("admin".to_string(), &auth_state.jwt_secret)
-                    .await
-                    .unwrap();
-
-            let request = Request::builder()
-                .method("GET")
-                .uri("/api/v1/protected")
-                .header("authorization", format!("Bearer {}", jwt_token))
-                .body(Body::empty())
-                .unwrap();
-
-            let response = protected_app.oneshot(request).await.unwrap();
-
-            let status = response.status();
-            let headers = response.headers().clone();
-            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-                .await
-                .unwrap();
-            let body_text = std::str::from_utf8(&body).unwrap();
+            let jwt_token = auth_state.jwt.encode("admin").unwrap();
 
-            let snapshot_data = JsonApiResponseSnapshot::new(
-                body_text,
-                status,
-                &headers,
-                "json_api_allow_requests_with_valid_bearer_token",
-            );
+            let response = TestRequest::get("/api/v1/protected")
+                .bearer_token(&jwt_token)
+                .send(protected_app)
+                .await;
+
+            let snapshot_data =
+                response.json_snapshot("json_api_allow_requests_with_valid_bearer_token");
 
             assert_yaml_snapshot!(snapshot_data);
         }
     }
 }
+
+#[tokio::test]
+async fn can_rate_limit_login_attempts_per_client() {
+    let (_test_db, app, _auth_state) = create_test_app().await;
+    let attempt = |ip: &str| {
+        TestRequest::post("/login")
+            .header("x-forwarded-for", ip)
+            .form(&[("username", "admin"), ("password", "guess")])
+    };
+
+    for _ in 0..5 {
+        let response = attempt("203.0.113.7").send(app.clone()).await;
+        assert_eq!(response.status, StatusCode::OK);
+    }
+    let response = attempt("203.0.113.7").send(app.clone()).await;
+    assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers["retry-after"], "12");
+
+    let response = attempt("198.51.100.1").send(app).await;
+    assert_eq!(response.status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn login_attempts_are_limited_as_configured() {
+    let (_test_db, auth_state) = setup_with_admin().await;
+    let mut auth_state = (*auth_state).clone();
+    auth_state.rate_limits.login_attempts_per_minute = 2;
+    let app = create_login_router(Arc::new(auth_state));
+    let attempt = || {
+        TestRequest::post("/login")
+            .header("x-forwarded-for", "203.0.113.7")
+            .form(&[("username", "admin"), ("password", "guess")])
+    };
+
+    for _ in 0..2 {
+        let response = attempt().send(app.clone()).await;
+        assert_eq!(response.status, StatusCode::OK);
+    }
+    let response = attempt().send(app).await;
+    assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers["retry-after"], "30");
+}
+
+/// Serves the parts of the Discord API signing in uses, as if the Discord user with `discord_id`
+/// signed in, and returns its URL.
+async fn serve_fake_discord(discord_id: &'static str) -> String {
+    let app = axum::Router::new()
+        .route(
+            "/oauth2/token",
+            axum::routing::post(|| async {
+                axum::Json(serde_json::json!({
+                    "access_token": "discord-token",
+                    "token_type": "Bearer",
+                }))
+            }),
+        )
+        .route(
+            "/users/@me",
+            axum::routing::get(move || async move {
+                axum::Json(serde_json::json!({ "id": discord_id, "username": "alice" }))
+            }),
+        );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    format!("http://{address}")
+}
+
+/// Setup function for tests that sign in with Discord, as the Discord user with `discord_id`.
+async fn create_discord_app(discord_id: &'static str) -> (TestDb, axum::Router, Arc<AuthState>) {
+    let (test_db, auth_state) = setup_with_admin().await;
+    let discord = DiscordOAuth::new(
+        "client".to_string(),
+        "secret".into(),
+        "http://localhost:8080/login/discord/callback".to_string(),
+    )
+    .with_api_url(serve_fake_discord(discord_id).await);
+    let auth_state = Arc::new(Arc::unwrap_or_clone(auth_state).with_discord(discord));
+    let app = create_login_router(auth_state.clone());
+    (test_db, app, auth_state)
+}
+
+/// Starts signing in with Discord, returning the state Discord is asked to send back.
+async fn start_discord_login(app: &axum::Router) -> String {
+    let response = TestRequest::get("/login/discord").send(app.clone()).await;
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+    let location = response.headers["location"].to_str().unwrap();
+    assert!(location.starts_with(DISCORD_AUTHORIZE_URL));
+
+    let state_cookie = response.headers["set-cookie"].to_str().unwrap();
+    let state = state_cookie
+        .split(';')
+        .next()
+        .and_then(|cookie| cookie.strip_prefix("discord_oauth_state="))
+        .unwrap();
+    assert!(location.contains(&format!("state={state}")));
+    state.to_string()
+}
+
+#[tokio::test]
+async fn can_sign_in_with_discord_as_the_linked_user() {
+    let (test_db, app, auth_state) = create_discord_app("123456789").await;
+    let users = UserService::new(&test_db.db);
+    let admin = users.find_by_username("admin").await.unwrap().unwrap();
+    users
+        .set_discord_id(admin.id(), Some(DiscordId::new(123456789).unwrap()))
+        .await
+        .unwrap();
+
+    let response = TestRequest::get("/login").send(app.clone()).await;
+    assert!(response.body.contains("href=\"/login/discord\""));
+
+    let state = start_discord_login(&app).await;
+    let response = TestRequest::get(format!("/login/discord/callback?code=abc&state={state}"))
+        .cookie("discord_oauth_state", &state)
+        .send(app)
+        .await;
+
+    assert_eq!(response.status, StatusCode::SEE_OTHER);
+    assert_eq!(response.headers["location"], "/");
+    let token = response
+        .headers
+        .get_all("set-cookie")
+        .iter()
+        .find_map(|cookie| cookie.to_str().unwrap().strip_prefix("auth_token="))
+        .and_then(|cookie| cookie.split(';').next())
+        .expect("Expected the auth cookie to be set");
+    let user = auth_state.jwt.current_user(token).unwrap();
+    assert_eq!(user.username, "admin");
+}
+
+#[tokio::test]
+async fn can_not_sign_in_with_discord_without_the_state_or_a_linked_user() {
+    let (_test_db, app, _auth_state) = create_discord_app("987654321").await;
+
+    let state = start_discord_login(&app).await;
+    let response = TestRequest::get("/login/discord/callback?code=abc&state=forged")
+        .cookie("discord_oauth_state", &state)
+        .send(app.clone())
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = TestRequest::get("/login/discord/callback?error=access_denied")
+        .cookie("discord_oauth_state", &state)
+        .send(app.clone())
+        .await;
+    assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+    let response = TestRequest::get(format!("/login/discord/callback?code=abc&state={state}"))
+        .cookie("discord_oauth_state", &state)
+        .send(app)
+        .await;
+    assert_eq!(response.status, StatusCode::FORBIDDEN);
+    assert!(response.body.contains("linked to a user"));
+    assert!(
+        response
+            .headers
+            .get_all("set-cookie")
+            .iter()
+            .all(|cookie| !cookie.to_str().unwrap().starts_with("auth_token="))
+    );
+}
+
+#[tokio::test]
+async fn does_not_offer_discord_sign_in_unless_configured() {
+    let (_test_db, app, _auth_state) = create_test_app().await;
+
+    let response = TestRequest::get("/login").send(app.clone()).await;
+    assert!(!response.body.contains("Sign in with Discord"));
+
+    let response = TestRequest::get("/login/discord").send(app).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+}
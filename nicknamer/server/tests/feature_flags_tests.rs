@@ -0,0 +1,34 @@
+use feature_flags::FlagState;
+use nicknamer_server::flags::feature_flags;
+
+mod common;
+
+use db_test_support::TestDb;
+
+async fn setup() -> anyhow::Result<TestDb> {
+    // Allow multiple calls to init for tests.
+    let _ = tracing_subscriber::fmt().try_init();
+    common::setup_db().await
+}
+
+#[tokio::test]
+async fn can_persist_toggled_flags() {
+    let state = setup().await.expect("Failed to setup test context");
+    let flags = feature_flags(state.db.clone());
+
+    flags
+        .set("live-tables", FlagState::Rollout(25))
+        .await
+        .expect("Failed to toggle flag");
+    flags
+        .set("live-tables", FlagState::On)
+        .await
+        .expect("Failed to toggle flag again");
+
+    // A second instance sees the latest toggle once it reloads
+    let other = feature_flags(state.db.clone());
+    assert_eq!(other.state("live-tables"), None);
+    other.reload().await.expect("Failed to reload flags");
+    assert_eq!(other.state("live-tables"), Some(FlagState::On));
+    assert!(other.is_enabled("live-tables"));
+}
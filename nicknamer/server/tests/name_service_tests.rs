@@ -1,31 +1,40 @@
+use nicknamer_server::audit::{AuditAction, AuditService};
 use nicknamer_server::entities::name;
-use nicknamer_server::name::NameService;
-use sea_orm::{ActiveModelTrait, ActiveValue, DatabaseConnection, EntityTrait};
-use testcontainers_modules::{postgres, testcontainers};
+use nicknamer_server::name::cache::NameCache;
+use nicknamer_server::name::{
+    Name, NameId, NameQuery, NameService, NameServiceError, NameSortField,
+};
+use nicknamer_server::server::{ServerDetails, ServerService};
+use pagination::{PageParams, Paginated, SortOrder, SortParams};
+use sea_orm::prelude::DateTimeUtc;
+use sea_orm::{ActiveModelTrait, ActiveValue, EntityTrait};
+use std::time::{Duration, SystemTime};
+use typed_ids::DiscordId;
 
 mod common;
 
-// 1. Define TestContext struct locally
-pub struct TestContext {
-    #[allow(dead_code)] // container is kept to ensure it's not dropped
-    pub container: testcontainers::ContainerAsync<postgres::Postgres>,
-    pub db: DatabaseConnection,
-}
+use db_test_support::TestDb;
 
-// 2. Define setup() function locally, using public functions from common module
-async fn setup() -> anyhow::Result<TestContext> {
+// Define setup() function locally, using public functions from common module
+async fn setup() -> anyhow::Result<TestDb> {
     // Allow multiple calls to init for tests.
     let _ = tracing_subscriber::fmt().try_init();
-    let container = common::setup_container().await?;
-    let db = common::setup_db(&container).await?;
-    Ok(TestContext { db, container })
+    common::setup_db().await
+}
+
+fn snowflake(id: u64) -> DiscordId {
+    DiscordId::new(id).unwrap()
+}
+
+fn name_id(id: u32) -> NameId {
+    NameId::new(id).unwrap()
 }
 
 #[tokio::test]
 async fn can_register_name() {
     let state = setup().await.expect("Failed to setup test context");
     let name_service = NameService::new(&state.db);
-    let discord_id = 123456789;
+    let discord_id = snowflake(123456789);
     let name = "TestUser".to_string();
     let created_name = name_service
         .create_name(discord_id, name.clone(), "server123".to_string())
@@ -35,7 +44,7 @@ async fn can_register_name() {
     // Verify that the created name has the correct properties
     assert_eq!(created_name.discord_id(), discord_id);
     assert_eq!(created_name.name(), &name);
-    assert!(created_name.id() > 0); // ID should be generated and positive
+    assert!(created_name.id().get() > 0); // ID should be generated and positive
 }
 
 #[tokio::test]
@@ -44,7 +53,7 @@ async fn can_update_name() {
     let name_service = NameService::new(&state.db);
 
     // Create a name entry directly using the entity ActiveModel
-    let initial_discord_id = 987654321;
+    let initial_discord_id = snowflake(987654321);
     let initial_name = "InitialName".to_string();
     let active_model = name::ActiveModel {
         discord_id: ActiveValue::Set(initial_discord_id),
@@ -65,9 +74,10 @@ async fn can_update_name() {
     let new_name = "UpdatedName".to_string();
     let updated_name = name_service
         .edit_name_by_id(
-            initial_name_entry.id as u32,
+            initial_name_entry.id,
             new_name.clone(),
             "server456".to_string(),
+            None,
         )
         .await
         .expect("Failed to update name");
@@ -76,6 +86,7 @@ async fn can_update_name() {
         let mut expected_model = initial_name_entry.clone();
         expected_model.name = new_name.clone();
         expected_model.server_id = "server456".to_string();
+        expected_model.version += 1;
         nicknamer_server::name::Name::from(expected_model)
     };
     assert_eq!(updated_name, expected_updated_name);
@@ -88,7 +99,7 @@ async fn can_handle_update_when_name_not_found() {
 
     // Create a name entry directly using the entity ActiveModel to ensure there's some data
     let active_model = name::ActiveModel {
-        discord_id: ActiveValue::Set(111222333),
+        discord_id: ActiveValue::Set(snowflake(111222333)),
         name: ActiveValue::Set("SomeUser".to_string()),
         server_id: ActiveValue::Set("server789".to_string()),
         ..Default::default()
@@ -99,12 +110,13 @@ async fn can_handle_update_when_name_not_found() {
         .expect("Failed to create name");
 
     // Verify that an error is returned if the name ID does not exist
-    let non_existent_id = initial_name.id + 1; // Assuming this ID won't exist
+    let non_existent_id = name_id(initial_name.id.get() + 1); // Assuming this ID won't exist
     let result = name_service
         .edit_name_by_id(
-            non_existent_id as u32,
+            non_existent_id,
             "AnotherName".to_string(),
             "server999".to_string(),
+            None,
         )
         .await;
     assert!(result.is_err());
@@ -122,7 +134,7 @@ async fn can_get_all_names() {
     let name_service = NameService::new(&state.db);
 
     // Create a couple of name entries directly using the entity ActiveModel
-    let name1_discord_id = 1;
+    let name1_discord_id = snowflake(1);
     let name1_name = "UserOne".to_string();
     let active_model1 = name::ActiveModel {
         discord_id: ActiveValue::Set(name1_discord_id),
@@ -134,7 +146,7 @@ async fn can_get_all_names() {
         .await
         .expect("Failed to create name1");
 
-    let name2_discord_id = 2;
+    let name2_discord_id = snowflake(2);
     let name2_name = "UserTwo".to_string();
     let active_model2 = name::ActiveModel {
         discord_id: ActiveValue::Set(name2_discord_id),
@@ -173,11 +185,31 @@ async fn can_handle_empty_names_list() {
     assert!(names.is_empty());
 }
 
+#[tokio::test]
+async fn can_count_names() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_service = NameService::new(&state.db);
+    assert_eq!(name_service.count_names().await.unwrap(), 0);
+
+    for (discord_id, server_id) in [
+        (snowflake(1), "server123"),
+        (snowflake(2), "server123"),
+        (snowflake(1), "server456"),
+    ] {
+        name_service
+            .create_name(discord_id, "TestUser".to_string(), server_id.to_string())
+            .await
+            .expect("Failed to create name");
+    }
+
+    assert_eq!(name_service.count_names().await.unwrap(), 3);
+}
+
 #[tokio::test]
 async fn cannot_create_name_with_duplicate_discord_id_and_server_id() {
     let state = setup().await.expect("Failed to setup test context");
     let name_service = NameService::new(&state.db);
-    let discord_id = 123456789;
+    let discord_id = snowflake(123456789);
     let server_id = "server123";
 
     // First name creation should succeed
@@ -204,7 +236,7 @@ async fn cannot_create_name_with_duplicate_discord_id_and_server_id() {
 async fn can_create_name_with_same_discord_id_but_different_server_id() {
     let state = setup().await.expect("Failed to setup test context");
     let name_service = NameService::new(&state.db);
-    let discord_id = 123456789;
+    let discord_id = snowflake(123456789);
 
     // First name creation should succeed
     let first_name = name_service
@@ -237,7 +269,7 @@ async fn can_delete_name() {
     let name_service = NameService::new(&state.db);
 
     // Create a name to delete using ActiveModel
-    let discord_id: i64 = 123456789;
+    let discord_id = snowflake(123456789);
     let name = "TestUser".to_string();
     let active_model = name::ActiveModel {
         discord_id: ActiveValue::Set(discord_id),
@@ -265,15 +297,23 @@ async fn can_delete_name() {
 
     // Verify the deleted name matches what was created
     assert_eq!(deleted_name.id(), created_name.id());
-    assert_eq!(deleted_name.discord_id(), discord_id as u64);
+    assert_eq!(deleted_name.discord_id(), discord_id);
     assert_eq!(deleted_name.name(), &name);
 
-    // Verify it was deleted
+    // Verify it was moved to the trash rather than removed
     let names_after = name::Entity::find()
         .all(&state.db)
         .await
         .expect("Failed to get all names from database");
-    assert!(names_after.is_empty());
+    assert_eq!(names_after.len(), 1);
+    assert!(names_after[0].deleted_at.is_some());
+    assert!(name_service.get_all_names().await.unwrap().is_empty());
+    assert!(
+        name_service
+            .get_name_by_id(created_name.id())
+            .await
+            .is_err()
+    );
 }
 
 #[tokio::test]
@@ -282,7 +322,7 @@ async fn can_handle_delete_when_name_not_found() {
     let name_service = NameService::new(&state.db);
 
     // Try to delete a non-existent name
-    let result = name_service.delete_name_by_id(999).await;
+    let result = name_service.delete_name_by_id(name_id(999)).await;
 
     // Should return an error
     assert!(result.is_err());
@@ -291,13 +331,48 @@ async fn can_handle_delete_when_name_not_found() {
     }
 }
 
+#[tokio::test]
+async fn deletes_a_name_only_once_when_deleted_concurrently() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_service = NameService::new(&state.db);
+    let created = name_service
+        .create_name(
+            snowflake(123456789),
+            "TestUser".to_string(),
+            "server1".to_string(),
+        )
+        .await
+        .unwrap();
+
+    let (first, second) = tokio::join!(
+        name_service.delete_name_by_id(created.id()),
+        name_service.delete_name_by_id(created.id())
+    );
+
+    let results = [first, second];
+    assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+    assert!(results.iter().any(|result| matches!(
+        result,
+        Err(NameServiceError::NameNotFound(id)) if *id == created.id()
+    )));
+    let deletes = AuditService::new(&state.db)
+        .list(PageParams::default())
+        .await
+        .unwrap()
+        .items
+        .into_iter()
+        .filter(|entry| entry.action() == AuditAction::Delete)
+        .count();
+    assert_eq!(deletes, 1);
+}
+
 #[tokio::test]
 async fn can_get_name_by_id() {
     let state = setup().await.expect("Failed to setup test context");
     let name_service = NameService::new(&state.db);
 
     // Create a name entry directly using the entity ActiveModel
-    let discord_id = 555666777;
+    let discord_id = snowflake(555666777);
     let name = "GetTestUser".to_string();
     let active_model = name::ActiveModel {
         discord_id: ActiveValue::Set(discord_id),
@@ -311,7 +386,7 @@ async fn can_get_name_by_id() {
 
     // Get the name by ID
     let retrieved_name = name_service
-        .get_name_by_id(created_name_model.id as u32)
+        .get_name_by_id(created_name_model.id)
         .await
         .expect("Failed to get name by ID");
 
@@ -327,7 +402,7 @@ async fn can_handle_get_name_by_nonexistent_id() {
 
     // Create a name entry to ensure we have some data and know what ID won't exist
     let active_model = name::ActiveModel {
-        discord_id: ActiveValue::Set(777888999),
+        discord_id: ActiveValue::Set(snowflake(777888999)),
         name: ActiveValue::Set("ExistingUser".to_string()),
         ..Default::default()
     };
@@ -337,8 +412,8 @@ async fn can_handle_get_name_by_nonexistent_id() {
         .expect("Failed to create name");
 
     // Try to get a name with a non-existent ID
-    let non_existent_id = created_name.id + 100; // Ensure this ID won't exist
-    let result = name_service.get_name_by_id(non_existent_id as u32).await;
+    let non_existent_id = name_id(created_name.id.get() + 100); // Ensure this ID won't exist
+    let result = name_service.get_name_by_id(non_existent_id).await;
 
     // Should return an error
     assert!(result.is_err());
@@ -357,9 +432,9 @@ async fn can_get_multiple_names_by_different_ids() {
 
     // Create multiple name entries
     let names_data = vec![
-        (111222333, "FirstUser".to_string()),
-        (444555666, "SecondUser".to_string()),
-        (777888999, "ThirdUser".to_string()),
+        (snowflake(111222333), "FirstUser".to_string()),
+        (snowflake(444555666), "SecondUser".to_string()),
+        (snowflake(777888999), "ThirdUser".to_string()),
     ];
 
     let mut created_models = Vec::new();
@@ -379,7 +454,7 @@ async fn can_get_multiple_names_by_different_ids() {
     // Retrieve each name by ID and verify
     for created_model in created_models {
         let retrieved_name = name_service
-            .get_name_by_id(created_model.id as u32)
+            .get_name_by_id(created_model.id)
             .await
             .expect("Failed to get name by ID");
 
@@ -464,7 +539,7 @@ async fn can_bulk_create_names_from_valid_yaml() {
                     expected_discord_id, expected_name
                 )
             });
-        assert!(found_name.id() > 0);
+        assert!(found_name.id().get() > 0);
     }
 }
 
@@ -504,7 +579,11 @@ async fn can_bulk_create_names_and_skip_duplicates() {
 
     // First, create an existing name entry
     name_service
-        .create_name(123456789, "ExistingUser".to_string(), server_id.clone())
+        .create_name(
+            snowflake(123456789),
+            "ExistingUser".to_string(),
+            server_id.clone(),
+        )
         .await
         .expect("Failed to create existing name");
 
@@ -557,7 +636,7 @@ async fn can_bulk_create_names_and_skip_duplicates() {
                     expected_discord_id, expected_name
                 )
             });
-        assert!(found_name.id() > 0);
+        assert!(found_name.id().get() > 0);
     }
 }
 
@@ -707,7 +786,7 @@ async fn can_handle_bulk_create_with_special_characters_in_names() {
                     expected_discord_id, expected_name
                 )
             });
-        assert!(found_name.id() > 0);
+        assert!(found_name.id().get() > 0);
     }
 }
 
@@ -718,9 +797,21 @@ async fn can_bulk_delete_names() {
 
     // Create multiple names to delete
     let names_data = vec![
-        (123456789u64, "User1".to_string(), "server1".to_string()),
-        (987654321u64, "User2".to_string(), "server1".to_string()),
-        (555666777u64, "User3".to_string(), "server2".to_string()),
+        (
+            snowflake(123456789),
+            "User1".to_string(),
+            "server1".to_string(),
+        ),
+        (
+            snowflake(987654321),
+            "User2".to_string(),
+            "server1".to_string(),
+        ),
+        (
+            snowflake(555666777),
+            "User3".to_string(),
+            "server2".to_string(),
+        ),
     ];
 
     let mut created_ids = Vec::new();
@@ -766,12 +857,16 @@ async fn can_handle_bulk_delete_with_nonexistent_ids() {
 
     // Create one name
     let created_name = name_service
-        .create_name(123456789, "TestUser".to_string(), "server1".to_string())
+        .create_name(
+            snowflake(123456789),
+            "TestUser".to_string(),
+            "server1".to_string(),
+        )
         .await
         .expect("Failed to create name");
 
     // Try to delete the existing name and a non-existent one
-    let ids_to_delete = vec![created_name.id(), 99999];
+    let ids_to_delete = vec![created_name.id(), name_id(99999)];
     let result = name_service
         .bulk_delete_names(&ids_to_delete)
         .await
@@ -798,12 +893,16 @@ async fn can_handle_bulk_delete_with_empty_list() {
 
     // Create one name first
     let _created_name = name_service
-        .create_name(123456789, "TestUser".to_string(), "server1".to_string())
+        .create_name(
+            snowflake(123456789),
+            "TestUser".to_string(),
+            "server1".to_string(),
+        )
         .await
         .expect("Failed to create name");
 
     // Try to delete with empty list
-    let ids_to_delete: Vec<u32> = vec![];
+    let ids_to_delete: Vec<NameId> = vec![];
     let result = name_service
         .bulk_delete_names(&ids_to_delete)
         .await
@@ -828,11 +927,31 @@ async fn can_handle_bulk_delete_all_names() {
 
     // Create multiple names
     let names_data = vec![
-        (123456789u64, "User1".to_string(), "server1".to_string()),
-        (987654321u64, "User2".to_string(), "server1".to_string()),
-        (555666777u64, "User3".to_string(), "server2".to_string()),
-        (444333222u64, "User4".to_string(), "server2".to_string()),
-        (111222333u64, "User5".to_string(), "server3".to_string()),
+        (
+            snowflake(123456789),
+            "User1".to_string(),
+            "server1".to_string(),
+        ),
+        (
+            snowflake(987654321),
+            "User2".to_string(),
+            "server1".to_string(),
+        ),
+        (
+            snowflake(555666777),
+            "User3".to_string(),
+            "server2".to_string(),
+        ),
+        (
+            snowflake(444333222),
+            "User4".to_string(),
+            "server2".to_string(),
+        ),
+        (
+            snowflake(111222333),
+            "User5".to_string(),
+            "server3".to_string(),
+        ),
     ];
 
     let mut created_ids = Vec::new();
@@ -869,6 +988,143 @@ async fn can_handle_bulk_delete_all_names() {
     assert!(all_names_after.is_empty());
 }
 
+#[tokio::test]
+async fn can_bulk_move_names_to_another_server() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_service = NameService::new(&state.db);
+    let alice = name_service
+        .create_name(
+            snowflake(123456789),
+            "Alice".to_string(),
+            "server1".to_string(),
+        )
+        .await
+        .unwrap();
+    let bob = name_service
+        .create_name(
+            snowflake(987654321),
+            "Bob".to_string(),
+            "server2".to_string(),
+        )
+        .await
+        .unwrap();
+    let carol = name_service
+        .create_name(
+            snowflake(555666777),
+            "Carol".to_string(),
+            "server3".to_string(),
+        )
+        .await
+        .unwrap();
+
+    let moved = name_service
+        .bulk_update_server(&[bob.id(), alice.id(), carol.id()], "server3".to_string())
+        .await
+        .expect("Failed to bulk move names");
+
+    // Carol's name was in the server already, so it is left as it is
+    assert_eq!(moved.len(), 2);
+    assert_eq!(moved[0].id(), alice.id());
+    assert_eq!(moved[1].id(), bob.id());
+    assert!(moved.iter().all(|name| name.server_id() == "server3"));
+    assert!(moved.iter().all(|name| name.version() == 2));
+    let server3 = name_service.get_names_by_server("server3").await.unwrap();
+    assert_eq!(server3.len(), 3);
+    let carol = name_service.get_name_by_id(carol.id()).await.unwrap();
+    assert_eq!(carol.version(), 1);
+}
+
+#[tokio::test]
+async fn bulk_move_fails_without_moving_anything_if_a_user_would_have_two_names() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_service = NameService::new(&state.db);
+    let alice = name_service
+        .create_name(
+            snowflake(123456789),
+            "Alice".to_string(),
+            "server1".to_string(),
+        )
+        .await
+        .unwrap();
+    let bob = name_service
+        .create_name(
+            snowflake(987654321),
+            "Bob".to_string(),
+            "server1".to_string(),
+        )
+        .await
+        .unwrap();
+    name_service
+        .create_name(
+            snowflake(987654321),
+            "Bobby".to_string(),
+            "server2".to_string(),
+        )
+        .await
+        .unwrap();
+    let alice_elsewhere = name_service
+        .create_name(
+            snowflake(123456789),
+            "Ali".to_string(),
+            "server3".to_string(),
+        )
+        .await
+        .unwrap();
+
+    // Bob already has a name in server2
+    let result = name_service
+        .bulk_update_server(&[alice.id(), bob.id()], "server2".to_string())
+        .await;
+    assert!(matches!(
+        result,
+        Err(NameServiceError::DuplicateEntryError(discord_id, ref server_id))
+            if discord_id == snowflake(987654321) && server_id == "server2"
+    ));
+
+    // Both of Alice's names were selected
+    let result = name_service
+        .bulk_update_server(&[alice.id(), alice_elsewhere.id()], "server4".to_string())
+        .await;
+    assert!(matches!(
+        result,
+        Err(NameServiceError::DuplicateEntryError(discord_id, _)) if discord_id == snowflake(123456789)
+    ));
+
+    let alice = name_service.get_name_by_id(alice.id()).await.unwrap();
+    assert_eq!(alice.server_id(), "server1");
+    assert_eq!(alice.version(), 1);
+}
+
+#[tokio::test]
+async fn bulk_move_fails_if_a_name_does_not_exist() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_service = NameService::new(&state.db);
+    let alice = name_service
+        .create_name(
+            snowflake(123456789),
+            "Alice".to_string(),
+            "server1".to_string(),
+        )
+        .await
+        .unwrap();
+    let bob = name_service
+        .create_name(
+            snowflake(987654321),
+            "Bob".to_string(),
+            "server1".to_string(),
+        )
+        .await
+        .unwrap();
+    name_service.delete_name_by_id(bob.id()).await.unwrap();
+
+    let result = name_service
+        .bulk_update_server(&[alice.id(), bob.id()], "server2".to_string())
+        .await;
+    assert!(matches!(result, Err(NameServiceError::NameNotFound(id)) if id == bob.id()));
+    let alice = name_service.get_name_by_id(alice.id()).await.unwrap();
+    assert_eq!(alice.server_id(), "server1");
+}
+
 #[tokio::test]
 async fn can_get_names_by_server() {
     let state = setup().await.expect("Failed to setup test context");
@@ -879,17 +1135,25 @@ async fn can_get_names_by_server() {
     let server2_id = "server2".to_string();
 
     let name1 = name_service
-        .create_name(123456789, "Alice".to_string(), server1_id.clone())
+        .create_name(
+            snowflake(123456789),
+            "Alice".to_string(),
+            server1_id.clone(),
+        )
         .await
         .expect("Failed to create name1");
 
     let name2 = name_service
-        .create_name(987654321, "Bob".to_string(), server1_id.clone())
+        .create_name(snowflake(987654321), "Bob".to_string(), server1_id.clone())
         .await
         .expect("Failed to create name2");
 
     let name3 = name_service
-        .create_name(555666777, "Charlie".to_string(), server2_id.clone())
+        .create_name(
+            snowflake(555666777),
+            "Charlie".to_string(),
+            server2_id.clone(),
+        )
         .await
         .expect("Failed to create name3");
 
@@ -924,7 +1188,7 @@ async fn can_get_empty_list_for_nonexistent_server() {
     // Create a name for a specific server
     name_service
         .create_name(
-            123456789,
+            snowflake(123456789),
             "TestUser".to_string(),
             "existing-server".to_string(),
         )
@@ -949,12 +1213,20 @@ async fn can_get_names_by_server_with_special_characters() {
     let special_server_id = "server-with-special!@#$%^&*()".to_string();
 
     let name1 = name_service
-        .create_name(123456789, "Alice".to_string(), special_server_id.clone())
+        .create_name(
+            snowflake(123456789),
+            "Alice".to_string(),
+            special_server_id.clone(),
+        )
         .await
         .expect("Failed to create name with special server ID");
 
     let name2 = name_service
-        .create_name(987654321, "Bob".to_string(), "normal-server".to_string())
+        .create_name(
+            snowflake(987654321),
+            "Bob".to_string(),
+            "normal-server".to_string(),
+        )
         .await
         .expect("Failed to create name with normal server ID");
 
@@ -968,3 +1240,477 @@ async fn can_get_names_by_server_with_special_characters() {
     assert!(special_server_names.contains(&name1));
     assert!(!special_server_names.contains(&name2));
 }
+
+#[tokio::test]
+async fn can_list_names_matching_a_query() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_service = NameService::new(&state.db);
+    for (discord_id, name, server_id) in [
+        (111, "Alice", "server1"),
+        (222, "Malice", "server1"),
+        (333, "Bob", "server1"),
+        (111, "Alicia", "server2"),
+        (444, "100% Real", "server2"),
+    ] {
+        name_service
+            .create_name(
+                snowflake(discord_id),
+                name.to_string(),
+                server_id.to_string(),
+            )
+            .await
+            .expect("Failed to create name");
+    }
+    let names_of = |page: &Paginated<Name>| -> Vec<String> {
+        page.items
+            .iter()
+            .map(|name| name.name().to_string())
+            .collect()
+    };
+
+    let searched = name_service
+        .list(&NameQuery {
+            search: Some("ALI".to_string()),
+            server_id: Some("server1".to_string()),
+            ..Default::default()
+        })
+        .await
+        .expect("Failed to search names");
+    assert_eq!(names_of(&searched), vec!["Alice", "Malice"]);
+    assert_eq!(searched.total, 2);
+
+    let by_user = name_service
+        .list(&NameQuery {
+            discord_id: Some(snowflake(111)),
+            ..Default::default()
+        })
+        .await
+        .expect("Failed to look up names of a user");
+    assert_eq!(names_of(&by_user), vec!["Alice", "Alicia"]);
+
+    let wildcard = name_service
+        .list(&NameQuery {
+            search: Some("0%".to_string()),
+            ..Default::default()
+        })
+        .await
+        .expect("Failed to search names");
+    assert_eq!(names_of(&wildcard), vec!["100% Real"]);
+
+    let sorted_page = name_service
+        .list(&NameQuery {
+            sort: SortParams {
+                sort: Some(NameSortField::Name),
+                order: SortOrder::Desc,
+            },
+            page: PageParams::new(2, 2).unwrap(),
+            ..Default::default()
+        })
+        .await
+        .expect("Failed to list names");
+    assert_eq!(names_of(&sorted_page), vec!["Alicia", "Alice"]);
+    assert_eq!((sorted_page.total, sorted_page.total_pages), (5, 3));
+}
+
+#[tokio::test]
+async fn can_restore_deleted_names_from_the_trash() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_service = NameService::new(&state.db);
+    let created = name_service
+        .create_name(
+            snowflake(123456789),
+            "Alice".to_string(),
+            "server1".to_string(),
+        )
+        .await
+        .unwrap();
+    name_service.delete_name_by_id(created.id()).await.unwrap();
+
+    let trash = name_service
+        .list_trash(PageParams::default())
+        .await
+        .unwrap();
+    assert_eq!(trash.total, 1);
+    assert_eq!(trash.items[0].name().id(), created.id());
+    assert_eq!(trash.items[0].name().name(), "Alice");
+    assert!(matches!(
+        name_service.delete_name_by_id(created.id()).await,
+        Err(NameServiceError::NameNotFound(_))
+    ));
+
+    let restored = name_service.restore_name_by_id(created.id()).await.unwrap();
+    assert_eq!(
+        (restored.id(), restored.name(), restored.server_id()),
+        (created.id(), "Alice", "server1")
+    );
+    assert_eq!(
+        name_service.get_all_names().await.unwrap(),
+        vec![restored.clone()]
+    );
+    assert_eq!(
+        name_service
+            .list_trash(PageParams::default())
+            .await
+            .unwrap()
+            .total,
+        0
+    );
+    assert!(matches!(
+        name_service.restore_name_by_id(created.id()).await,
+        Err(NameServiceError::NameNotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn can_not_restore_names_that_have_been_replaced() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_service = NameService::new(&state.db);
+    let original = name_service
+        .create_name(
+            snowflake(123456789),
+            "Alice".to_string(),
+            "server1".to_string(),
+        )
+        .await
+        .unwrap();
+    name_service.delete_name_by_id(original.id()).await.unwrap();
+
+    // Names in the trash don't keep the user from getting a new name in the server
+    let replacement = name_service
+        .create_name(
+            snowflake(123456789),
+            "Alicia".to_string(),
+            "server1".to_string(),
+        )
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        name_service.restore_name_by_id(original.id()).await,
+        Err(NameServiceError::DuplicateEntryError(_, _))
+    ));
+    assert_eq!(
+        name_service.get_all_names().await.unwrap(),
+        vec![replacement]
+    );
+}
+
+#[tokio::test]
+async fn purges_only_names_deleted_before_the_cutoff() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_service = NameService::new(&state.db);
+    let deleted = name_service
+        .create_name(
+            snowflake(123456789),
+            "Alice".to_string(),
+            "server1".to_string(),
+        )
+        .await
+        .unwrap();
+    let kept = name_service
+        .create_name(
+            snowflake(987654321),
+            "Bob".to_string(),
+            "server1".to_string(),
+        )
+        .await
+        .unwrap();
+    name_service.delete_name_by_id(deleted.id()).await.unwrap();
+
+    let an_hour_ago = DateTimeUtc::from(SystemTime::now() - Duration::from_secs(60 * 60));
+    assert_eq!(name_service.purge_trash(an_hour_ago).await.unwrap(), 0);
+    assert_eq!(
+        name_service
+            .list_trash(PageParams::default())
+            .await
+            .unwrap()
+            .total,
+        1
+    );
+
+    let in_an_hour = DateTimeUtc::from(SystemTime::now() + Duration::from_secs(60 * 60));
+    assert_eq!(name_service.purge_trash(in_an_hour).await.unwrap(), 1);
+    assert_eq!(
+        name_service
+            .list_trash(PageParams::default())
+            .await
+            .unwrap()
+            .total,
+        0
+    );
+    assert!(
+        name::Entity::find_by_id(deleted.id())
+            .one(&state.db)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert_eq!(name_service.get_all_names().await.unwrap(), vec![kept]);
+}
+
+#[tokio::test]
+async fn can_purge_only_names_in_the_trash() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_service = NameService::new(&state.db);
+    let created = name_service
+        .create_name(
+            snowflake(123456789),
+            "Alice".to_string(),
+            "server1".to_string(),
+        )
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        name_service.purge_name_by_id(created.id()).await,
+        Err(NameServiceError::NameNotFound(_))
+    ));
+
+    name_service.delete_name_by_id(created.id()).await.unwrap();
+    let purged = name_service.purge_name_by_id(created.id()).await.unwrap();
+    assert_eq!(purged.id(), created.id());
+    assert!(
+        name::Entity::find_by_id(created.id())
+            .one(&state.db)
+            .await
+            .unwrap()
+            .is_none()
+    );
+    assert!(matches!(
+        name_service.purge_name_by_id(created.id()).await,
+        Err(NameServiceError::NameNotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn can_not_edit_names_changed_since_the_expected_version() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_service = NameService::new(&state.db);
+    let created = name_service
+        .create_name(
+            snowflake(123456789),
+            "Alice".to_string(),
+            "server1".to_string(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(created.version(), 1);
+
+    let first = name_service
+        .edit_name_by_id(
+            created.id(),
+            "Alicia".to_string(),
+            "server1".to_string(),
+            Some(created.version()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(first.version(), 2);
+
+    // A second editor who loaded the name before the first edit
+    let second = name_service
+        .edit_name_by_id(
+            created.id(),
+            "Ali".to_string(),
+            "server1".to_string(),
+            Some(created.version()),
+        )
+        .await;
+    assert!(matches!(second, Err(NameServiceError::VersionConflict(_))));
+    assert_eq!(
+        name_service.get_name_by_id(created.id()).await.unwrap(),
+        first
+    );
+
+    let overwritten = name_service
+        .edit_name_by_id(
+            created.id(),
+            "Ali".to_string(),
+            "server1".to_string(),
+            Some(first.version()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(overwritten.name(), "Ali");
+    assert_eq!(overwritten.version(), 3);
+}
+
+#[tokio::test]
+async fn rejects_edits_to_versions_from_before_a_delete_and_restore() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_service = NameService::new(&state.db);
+    let created = name_service
+        .create_name(snowflake(1), "Alice".to_string(), "server1".to_string())
+        .await
+        .unwrap();
+
+    name_service.delete_name_by_id(created.id()).await.unwrap();
+    let restored = name_service.restore_name_by_id(created.id()).await.unwrap();
+    assert_eq!(restored.version(), created.version() + 2);
+
+    // An editor who loaded the name before it was deleted
+    let stale = name_service
+        .edit_name_by_id(
+            created.id(),
+            "Alicia".to_string(),
+            "server1".to_string(),
+            Some(created.version()),
+        )
+        .await;
+    assert!(matches!(stale, Err(NameServiceError::VersionConflict(_))));
+    assert_eq!(
+        name_service.get_name_by_id(created.id()).await.unwrap(),
+        restored
+    );
+}
+
+#[tokio::test]
+async fn can_find_duplicates_and_merge_them_into_one_name() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_service = NameService::new(&state.db);
+    let create = |discord_id: u64, name: &str, server_id: &str| {
+        name_service.create_name(
+            snowflake(discord_id),
+            name.to_string(),
+            server_id.to_string(),
+        )
+    };
+    let alice = create(1, "Alice", "server1").await.unwrap();
+    let alice_elsewhere = create(1, "Alice", "server2").await.unwrap();
+    let lookalike = create(2, "alice", "server1").await.unwrap();
+    create(3, "Bob", "server1").await.unwrap();
+
+    let groups = name_service.find_duplicates(0.6).await.unwrap();
+    let grouped: Vec<Vec<NameId>> = groups
+        .iter()
+        .map(|group| group.names().iter().map(Name::id).collect())
+        .collect();
+    assert_eq!(
+        grouped,
+        vec![
+            vec![alice.id(), alice_elsewhere.id()],
+            vec![alice.id(), lookalike.id()],
+            vec![alice_elsewhere.id(), lookalike.id()],
+        ]
+    );
+    assert!(matches!(
+        name_service.find_duplicates(0.0).await,
+        Err(NameServiceError::InvalidThreshold(_))
+    ));
+
+    let kept = name_service
+        .merge_names(
+            alice.id(),
+            &[alice.id(), alice_elsewhere.id(), lookalike.id()],
+        )
+        .await
+        .unwrap();
+    assert_eq!(kept, alice);
+    assert!(name_service.find_duplicates(0.6).await.unwrap().is_empty());
+    let trash = name_service
+        .list_trash(PageParams::default())
+        .await
+        .unwrap();
+    assert_eq!(trash.total, 2);
+    let log = AuditService::new(&state.db)
+        .list(PageParams::default())
+        .await
+        .unwrap();
+    let merged: Vec<NameId> = log
+        .items
+        .iter()
+        .filter(|entry| entry.action() == AuditAction::Merge)
+        .map(|entry| entry.name_id())
+        .collect();
+    assert_eq!(merged.len(), 2);
+    assert!(merged.contains(&alice_elsewhere.id()) && merged.contains(&lookalike.id()));
+
+    assert!(matches!(
+        name_service.merge_names(alice.id(), &[lookalike.id()]).await,
+        Err(NameServiceError::NameNotFound(id)) if id == lookalike.id()
+    ));
+}
+
+#[tokio::test]
+async fn caches_lookups_until_names_or_servers_change() {
+    let state = setup().await.expect("Failed to setup test context");
+    let cache = NameCache::new(Duration::from_secs(60));
+    let cached = NameService::new(&state.db).with_cache(&cache);
+    let uncached = NameService::new(&state.db);
+    let alice = cached
+        .create_name(snowflake(1), "Alice".to_string(), "111".to_string())
+        .await
+        .unwrap();
+
+    assert_eq!(cached.get_all_names().await.unwrap(), vec![alice.clone()]);
+    // Changes the cache doesn't know about only show once it expires
+    let bob = uncached
+        .create_name(snowflake(2), "Bob".to_string(), "111".to_string())
+        .await
+        .unwrap();
+    assert_eq!(cached.get_all_names().await.unwrap(), vec![alice.clone()]);
+    assert_eq!(
+        cached.get_names_by_server("111").await.unwrap(),
+        vec![alice.clone(), bob.clone()]
+    );
+
+    cached.delete_name_by_id(bob.id()).await.unwrap();
+    assert_eq!(cached.get_all_names().await.unwrap(), vec![alice.clone()]);
+    assert_eq!(
+        cached.get_names_by_server("111").await.unwrap(),
+        vec![alice.clone()]
+    );
+
+    ServerService::new(&state.db)
+        .with_name_cache(&cache)
+        .create_server(
+            snowflake(111),
+            ServerDetails {
+                name: "Lowkey Lab".to_string(),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    let names = cached.get_all_names().await.unwrap();
+    assert_eq!(names[0].server_name(), Some("Lowkey Lab"));
+
+    let stats = cache.stats().await;
+    assert_eq!(stats.hits, 1);
+    assert_eq!(stats.invalidations, 3);
+}
+
+#[tokio::test]
+async fn can_search_names_by_containing_or_similar_text() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_service = NameService::new(&state.db);
+    let create = |discord_id: u64, name: &str| {
+        name_service.create_name(
+            snowflake(discord_id),
+            name.to_string(),
+            "server1".to_string(),
+        )
+    };
+    let jonathan = create(1, "Jonathan").await.unwrap();
+    let jonathon = create(2, "Jonathon").await.unwrap();
+    create(3, "Bob").await.unwrap();
+    let jonathan_smith = create(4, "Jonathan Smith").await.unwrap();
+    let trashed = create(5, "Jonathan").await.unwrap();
+    name_service.delete_name_by_id(trashed.id()).await.unwrap();
+
+    let found: Vec<NameId> = name_service
+        .search("jonathan", 20)
+        .await
+        .unwrap()
+        .iter()
+        .map(Name::id)
+        .collect();
+    assert_eq!(
+        found,
+        vec![jonathan.id(), jonathan_smith.id(), jonathon.id()]
+    );
+
+    let found = name_service.search("jonathan", 2).await.unwrap();
+    assert_eq!(found.len(), 2);
+    assert!(name_service.search("  ", 20).await.unwrap().is_empty());
+}
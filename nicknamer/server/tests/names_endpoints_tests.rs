@@ -1,45 +1,50 @@
 use axum::body::Body;
 use axum::http::{Method, Request, StatusCode};
 use insta::assert_yaml_snapshot;
+use names_format::{Format, Names};
 use nicknamer_server::entities::name;
 use nicknamer_server::name::api::v1::create_api_router;
+use nicknamer_server::name::cache::NameCache;
+use nicknamer_server::name::export::EXPORT_BATCH_SIZE;
 use nicknamer_server::name::web::{NameState, create_name_router};
-use sea_orm::{ActiveModelTrait, DatabaseConnection, Set};
+use nicknamer_server::name::{NameId, NameService};
+use sea_orm::{ActiveModelTrait, DatabaseConnection, EntityTrait, Set};
 use std::sync::Arc;
-use testcontainers_modules::{postgres, testcontainers};
+use std::time::Duration;
 use tower::ServiceExt;
+use typed_ids::DiscordId;
 
 mod common;
 
-use common::HttpResponseSnapshot;
-
-/// Test context for endpoint tests.
-pub struct TestContext {
-    #[allow(dead_code)] // container is kept to ensure it's not dropped
-    pub container: testcontainers::ContainerAsync<postgres::Postgres>,
-    pub db: DatabaseConnection,
-}
+use db_test_support::TestDb;
+use http_snapshot::{HttpResponseSnapshot, TestRequest};
 
 /// Setup function for endpoint tests using PostgreSQL container.
-async fn setup() -> anyhow::Result<TestContext> {
+async fn setup() -> anyhow::Result<TestDb> {
     // Allow multiple calls to init for tests.
     let _ = tracing_subscriber::fmt().try_init();
-    let container = common::setup_container().await?;
-    let db = common::setup_db(&container).await?;
-    Ok(TestContext { db, container })
+    common::setup_db().await
+}
+
+fn discord_id(id: u64) -> DiscordId {
+    DiscordId::new(id).unwrap()
+}
+
+fn name_id(id: u32) -> NameId {
+    NameId::new(id).unwrap()
 }
 
 /// Test helper to create test names in the database.
 async fn create_test_names(db: &DatabaseConnection) {
     let name1 = name::ActiveModel {
-        discord_id: Set(123456789),
+        discord_id: Set(discord_id(123456789)),
         name: Set("TestUser1".to_string()),
         server_id: Set("test-server-1".to_string()),
         ..Default::default()
     };
 
     let name2 = name::ActiveModel {
-        discord_id: Set(987654321),
+        discord_id: Set(discord_id(987654321)),
         name: Set("TestUser2".to_string()),
         server_id: Set("test-server-1".to_string()),
         ..Default::default()
@@ -53,14 +58,14 @@ async fn create_test_names(db: &DatabaseConnection) {
 async fn create_test_names_multiple_servers(db: &DatabaseConnection) {
     // Server 1 names
     let name1 = name::ActiveModel {
-        discord_id: Set(123456789),
+        discord_id: Set(discord_id(123456789)),
         name: Set("Alice".to_string()),
         server_id: Set("server1".to_string()),
         ..Default::default()
     };
 
     let name2 = name::ActiveModel {
-        discord_id: Set(987654321),
+        discord_id: Set(discord_id(987654321)),
         name: Set("Bob".to_string()),
         server_id: Set("server1".to_string()),
         ..Default::default()
@@ -68,14 +73,14 @@ async fn create_test_names_multiple_servers(db: &DatabaseConnection) {
 
     // Server 2 names
     let name3 = name::ActiveModel {
-        discord_id: Set(555666777),
+        discord_id: Set(discord_id(555666777)),
         name: Set("Charlie".to_string()),
         server_id: Set("server2".to_string()),
         ..Default::default()
     };
 
     let name4 = name::ActiveModel {
-        discord_id: Set(444333222),
+        discord_id: Set(discord_id(444333222)),
         name: Set("David".to_string()),
         server_id: Set("server2".to_string()),
         ..Default::default()
@@ -88,23 +93,23 @@ async fn create_test_names_multiple_servers(db: &DatabaseConnection) {
 }
 
 /// Test helper to create test names in the database and return their IDs.
-async fn create_test_names_with_ids(db: &DatabaseConnection) -> Vec<i32> {
+async fn create_test_names_with_ids(db: &DatabaseConnection) -> Vec<NameId> {
     let name1 = name::ActiveModel {
-        discord_id: Set(123456789),
+        discord_id: Set(discord_id(123456789)),
         name: Set("TestUser1".to_string()),
         server_id: Set("test-server-1".to_string()),
         ..Default::default()
     };
 
     let name2 = name::ActiveModel {
-        discord_id: Set(987654321),
+        discord_id: Set(discord_id(987654321)),
         name: Set("TestUser2".to_string()),
         server_id: Set("test-server-1".to_string()),
         ..Default::default()
     };
 
     let name3 = name::ActiveModel {
-        discord_id: Set(555444333),
+        discord_id: Set(discord_id(555444333)),
         name: Set("TestUser3".to_string()),
         server_id: Set("test-server-1".to_string()),
         ..Default::default()
@@ -118,9 +123,9 @@ async fn create_test_names_with_ids(db: &DatabaseConnection) -> Vec<i32> {
 }
 
 /// Test helper to create a single test name and return its ID.
-async fn create_single_test_name(db: &DatabaseConnection) -> i32 {
+async fn create_single_test_name(db: &DatabaseConnection) -> NameId {
     let name = name::ActiveModel {
-        discord_id: Set(555444333),
+        discord_id: Set(discord_id(555444333)),
         name: Set("DeleteTestUser".to_string()),
         server_id: Set("test-server-1".to_string()),
         ..Default::default()
@@ -131,9 +136,9 @@ async fn create_single_test_name(db: &DatabaseConnection) -> i32 {
 }
 
 /// Test helper to create a single test name for editing and return its ID.
-async fn create_editable_test_name(db: &DatabaseConnection) -> i32 {
+async fn create_editable_test_name(db: &DatabaseConnection) -> NameId {
     let name = name::ActiveModel {
-        discord_id: Set(777888999),
+        discord_id: Set(discord_id(777888999)),
         name: Set("EditableTestUser".to_string()),
         server_id: Set("test-server-1".to_string()),
         ..Default::default()
@@ -155,7 +160,11 @@ async fn create_editable_test_name(db: &DatabaseConnection) -> i32 {
 /// # Returns
 /// An `Arc<NameState>` instance that wraps the shared state.
 fn create_name_state(db: DatabaseConnection) -> Arc<NameState> {
-    Arc::new(NameState { db: Arc::new(db) })
+    Arc::new(NameState {
+        db: Arc::new(db),
+        trash_retention_days: 30,
+        cache: NameCache::new(Duration::from_secs(60)),
+    })
 }
 
 #[tokio::test]
@@ -166,26 +175,9 @@ async fn can_display_names_table_when_names_exist() {
     let name_state = create_name_state(state.db);
     let app = create_name_router(name_state);
 
-    let request = Request::builder()
-        .uri("/names")
-        .body(Body::empty())
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
+    let response = TestRequest::get("/names").send(app).await;
 
-    let snapshot_data = HttpResponseSnapshot::new(
-        body_text,
-        status,
-        &headers,
-        "names_table_with_existing_names",
-    );
+    let snapshot_data = response.html_snapshot("names_table_with_existing_names");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -197,21 +189,9 @@ async fn can_display_empty_names_table_when_no_names_exist() {
     let name_state = create_name_state(state.db);
     let app = create_name_router(name_state);
 
-    let request = Request::builder()
-        .uri("/names")
-        .body(Body::empty())
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
+    let response = TestRequest::get("/names").send(app).await;
 
-    let snapshot_data = HttpResponseSnapshot::new(body_text, status, &headers, "empty_names_table");
+    let snapshot_data = response.html_snapshot("empty_names_table");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -223,26 +203,9 @@ async fn names_endpoint_returns_correct_content_type() {
     let name_state = create_name_state(state.db);
     let app = create_name_router(name_state);
 
-    let request = Request::builder()
-        .uri("/names")
-        .body(Body::empty())
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
+    let response = TestRequest::get("/names").send(app).await;
 
-    let snapshot_data = HttpResponseSnapshot::new(
-        body_text,
-        status,
-        &headers,
-        "names_endpoint_content_type_check",
-    );
+    let snapshot_data = response.html_snapshot("names_endpoint_content_type_check");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -255,24 +218,13 @@ async fn can_create_name_successfully() {
     let app = create_name_router(name_state.clone());
 
     let form_data = "discord_id=555666777&name=NewTestUser&server_id=test-server-1";
-    let request = Request::builder()
-        .method(Method::POST)
-        .uri("/names")
+    let response = TestRequest::post("/names")
         .header("content-type", "application/x-www-form-urlencoded")
-        .body(Body::from(form_data))
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
+        .body(form_data)
+        .send(app)
+        .await;
 
-    let snapshot_data =
-        HttpResponseSnapshot::new(body_text, status, &headers, "create_name_successfully");
+    let snapshot_data = response.html_snapshot("create_name_successfully");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -286,28 +238,13 @@ async fn can_create_multiple_names_and_update_count() {
     let app = create_name_router(name_state.clone());
 
     let form_data = "discord_id=111222333&name=ThirdUser&server_id=test-server-1";
-    let request = Request::builder()
-        .method(Method::POST)
-        .uri("/names")
+    let response = TestRequest::post("/names")
         .header("content-type", "application/x-www-form-urlencoded")
-        .body(Body::from(form_data))
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
+        .body(form_data)
+        .send(app)
+        .await;
 
-    let snapshot_data = HttpResponseSnapshot::new(
-        body_text,
-        status,
-        &headers,
-        "create_multiple_names_update_count",
-    );
+    let snapshot_data = response.html_snapshot("create_multiple_names_update_count");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -320,24 +257,13 @@ async fn can_handle_form_with_special_characters_in_name() {
     let app = create_name_router(name_state.clone());
 
     let form_data = "discord_id=888999000&name=User%20With%20Spaces%21&server_id=test-server-1";
-    let request = Request::builder()
-        .method(Method::POST)
-        .uri("/names")
+    let response = TestRequest::post("/names")
         .header("content-type", "application/x-www-form-urlencoded")
-        .body(Body::from(form_data))
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
+        .body(form_data)
+        .send(app)
+        .await;
 
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
-
-    let snapshot_data =
-        HttpResponseSnapshot::new(body_text, status, &headers, "form_with_special_characters");
+    let snapshot_data = response.html_snapshot("form_with_special_characters");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -349,21 +275,9 @@ async fn can_serve_add_name_form() {
     let name_state = create_name_state(state.db);
     let app = create_name_router(name_state);
 
-    let request = Request::builder()
-        .uri("/names/add")
-        .body(Body::empty())
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
+    let response = TestRequest::get("/names/add").send(app).await;
 
-    let snapshot_data = HttpResponseSnapshot::new(body_text, status, &headers, "add_name_form");
+    let snapshot_data = response.html_snapshot("add_name_form");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -376,24 +290,13 @@ async fn post_endpoint_returns_table_fragment_not_full_page() {
     let app = create_name_router(name_state.clone());
 
     let form_data = "discord_id=777888999&name=FragmentTestUser&server_id=test-server-1";
-    let request = Request::builder()
-        .method(Method::POST)
-        .uri("/names")
+    let response = TestRequest::post("/names")
         .header("content-type", "application/x-www-form-urlencoded")
-        .body(Body::from(form_data))
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
+        .body(form_data)
+        .send(app)
+        .await;
 
-    let snapshot_data =
-        HttpResponseSnapshot::new(body_text, status, &headers, "table_fragment_not_full_page");
+    let snapshot_data = response.html_snapshot("table_fragment_not_full_page");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -598,27 +501,11 @@ async fn delete_endpoint_returns_correct_content_type() {
     let name_state = create_name_state(state.db);
     let app = create_name_router(name_state);
 
-    let request = Request::builder()
-        .method(Method::DELETE)
-        .uri(format!("/names/{}", name_id))
-        .body(Body::empty())
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
+    let response = TestRequest::delete(format!("/names/{}", name_id))
+        .send(app)
+        .await;
 
-    let snapshot_data = HttpResponseSnapshot::new(
-        body_text,
-        status,
-        &headers,
-        "delete_endpoint_content_type_check",
-    );
+    let snapshot_data = response.html_snapshot("delete_endpoint_content_type_check");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -724,6 +611,47 @@ async fn can_update_name_successfully() {
     assert_yaml_snapshot!(snapshot_data);
 }
 
+#[tokio::test]
+async fn offers_to_resolve_edits_of_names_changed_in_the_meantime() {
+    let state = setup().await.expect("Failed to setup test context");
+    let name_id = create_editable_test_name(&state.db).await;
+    let app = create_name_router(create_name_state(state.db));
+
+    let response = TestRequest::put(format!("/names/{}", name_id))
+        .form(&[
+            ("name", "FirstEdit"),
+            ("server_id", "test-server-1"),
+            ("version", "1"),
+        ])
+        .send(app.clone())
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = TestRequest::put(format!("/names/{}", name_id))
+        .form(&[
+            ("name", "SecondEdit"),
+            ("server_id", "test-server-1"),
+            ("version", "1"),
+        ])
+        .send(app.clone())
+        .await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert!(response.body.contains("<strong>FirstEdit</strong>"));
+    assert!(response.body.contains("value=\"SecondEdit\""));
+    assert!(response.body.contains("name=\"version\" value=\"2\""));
+
+    let response = TestRequest::put(format!("/names/{}", name_id))
+        .form(&[
+            ("name", "SecondEdit"),
+            ("server_id", "test-server-1"),
+            ("version", "2"),
+        ])
+        .send(app)
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("SecondEdit"));
+}
+
 #[tokio::test]
 async fn can_update_name_with_special_characters() {
     let state = setup().await.expect("Failed to setup test context");
@@ -848,28 +776,13 @@ async fn update_endpoint_returns_correct_content_type() {
     let app = create_name_router(name_state);
 
     let form_data = "name=ContentTypeTestUser&server_id=test-server-1";
-    let request = Request::builder()
-        .method(Method::PUT)
-        .uri(format!("/names/{}", name_id))
+    let response = TestRequest::put(format!("/names/{}", name_id))
         .header("content-type", "application/x-www-form-urlencoded")
-        .body(Body::from(form_data))
-        .unwrap();
+        .body(form_data)
+        .send(app)
+        .await;
 
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
-
-    let snapshot_data = HttpResponseSnapshot::new(
-        body_text,
-        status,
-        &headers,
-        "update_endpoint_content_type_check",
-    );
+    let snapshot_data = response.html_snapshot("update_endpoint_content_type_check");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -882,26 +795,11 @@ async fn edit_form_endpoint_returns_correct_content_type() {
     let name_state = create_name_state(state.db);
     let app = create_name_router(name_state);
 
-    let request = Request::builder()
-        .uri(format!("/names/{}/edit", name_id))
-        .body(Body::empty())
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
+    let response = TestRequest::get(format!("/names/{}/edit", name_id))
+        .send(app)
+        .await;
 
-    let snapshot_data = HttpResponseSnapshot::new(
-        body_text,
-        status,
-        &headers,
-        "edit_form_endpoint_content_type_check",
-    );
+    let snapshot_data = response.html_snapshot("edit_form_endpoint_content_type_check");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -987,27 +885,9 @@ async fn can_get_names_table_fragment_when_names_exist() {
     let name_state = create_name_state(state.db);
     let app = create_name_router(name_state);
 
-    let request = Request::builder()
-        .method(Method::GET)
-        .uri("/names/table")
-        .body(Body::empty())
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
+    let response = TestRequest::get("/names/table").send(app).await;
 
-    let snapshot_data = HttpResponseSnapshot::new(
-        body_text,
-        status,
-        &headers,
-        "names_table_fragment_with_data",
-    );
+    let snapshot_data = response.html_snapshot("names_table_fragment_with_data");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -1019,23 +899,9 @@ async fn can_get_empty_names_table_fragment_when_no_names_exist() {
     let name_state = create_name_state(state.db);
     let app = create_name_router(name_state);
 
-    let request = Request::builder()
-        .method(Method::GET)
-        .uri("/names/table")
-        .body(Body::empty())
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
+    let response = TestRequest::get("/names/table").send(app).await;
 
-    let snapshot_data =
-        HttpResponseSnapshot::new(body_text, status, &headers, "empty_names_table_fragment");
+    let snapshot_data = response.html_snapshot("empty_names_table_fragment");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -1115,24 +981,27 @@ async fn names_table_fragment_sorts_names_by_id() {
 
     // Create names in non-sequential order to test sorting
     let name3 = name::ActiveModel {
-        id: Set(3),
-        discord_id: Set(333444555),
+        id: Set(name_id(3)),
+        discord_id: Set(discord_id(333444555)),
         name: Set("ThirdUser".to_string()),
         server_id: Set("test-server-1".to_string()),
+        ..Default::default()
     };
 
     let name1 = name::ActiveModel {
-        id: Set(1),
-        discord_id: Set(111222333),
+        id: Set(name_id(1)),
+        discord_id: Set(discord_id(111222333)),
         name: Set("FirstUser".to_string()),
         server_id: Set("test-server-1".to_string()),
+        ..Default::default()
     };
 
     let name2 = name::ActiveModel {
-        id: Set(2),
-        discord_id: Set(222333444),
+        id: Set(name_id(2)),
+        discord_id: Set(discord_id(222333444)),
         name: Set("SecondUser".to_string()),
         server_id: Set("test-server-1".to_string()),
+        ..Default::default()
     };
 
     let _result3 = name3.insert(&state.db).await.unwrap();
@@ -1142,27 +1011,9 @@ async fn names_table_fragment_sorts_names_by_id() {
     let name_state = create_name_state(state.db);
     let app = create_name_router(name_state);
 
-    let request = Request::builder()
-        .method(Method::GET)
-        .uri("/names/table")
-        .body(Body::empty())
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
+    let response = TestRequest::get("/names/table").send(app).await;
 
-    let snapshot_data = HttpResponseSnapshot::new(
-        body_text,
-        status,
-        &headers,
-        "names_table_fragment_sorted_by_id",
-    );
+    let snapshot_data = response.html_snapshot("names_table_fragment_sorted_by_id");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -1174,10 +1025,11 @@ async fn names_table_fragment_handles_large_dataset() {
     // Create multiple names to test pagination/large dataset handling
     for i in 1..=10 {
         let name = name::ActiveModel {
-            id: Set(i),
-            discord_id: Set(100000000 + i as i64),
+            id: Set(name_id(i)),
+            discord_id: Set(discord_id(100000000 + u64::from(i))),
             name: Set(format!("TestUser{}", i)),
             server_id: Set("test-server-1".to_string()),
+            ..Default::default()
         };
         let _result = name.insert(&state.db).await.unwrap();
     }
@@ -1218,12 +1070,273 @@ async fn names_table_fragment_handles_large_dataset() {
     assert_yaml_snapshot!(snapshot_data);
 }
 
-/// API v1 tests module for JSON endpoints
-pub mod api {
-    pub mod v1 {
-        use super::super::*;
-        use common::JsonApiResponseSnapshot;
-        use serde_json::Value;
+#[tokio::test]
+async fn names_table_fragment_pages_and_filters_names() {
+    let state = setup().await.expect("Failed to setup test context");
+    create_test_names_multiple_servers(&state.db).await;
+    let app = create_name_router(create_name_state(state.db));
+
+    let response = TestRequest::get("/names/table?per_page=3&page=2&sort=name")
+        .send(app.clone())
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    let body = &response.body;
+    assert!(body.contains("David"));
+    assert!(!body.contains("Alice"));
+    assert!(body.contains("Showing 4–4 of 4"));
+    assert!(body.contains("hx-get=\"/names/table?page=1\""));
+
+    let response = TestRequest::get("/names/table?search=&server_id=server1&discord_id=")
+        .send(app.clone())
+        .await;
+    let body = &response.body;
+    assert!(body.contains("Alice") && body.contains("Bob"));
+    assert!(!body.contains("Charlie"));
+    assert!(body.contains("Matching names"));
+    assert!(!body.contains("Showing"));
+
+    let response = TestRequest::get("/names/table?search=nobody")
+        .send(app)
+        .await;
+    assert!(response.body.contains("No names match the filters."));
+}
+
+#[tokio::test]
+async fn can_export_the_names_of_a_server_for_reimport() {
+    let state = setup().await.expect("Failed to setup test context");
+    create_test_names_multiple_servers(&state.db).await;
+    let app = create_name_router(create_name_state(state.db.clone()));
+
+    let response = TestRequest::get("/names/export?server_id=server1&format=yaml")
+        .send(app.clone())
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.headers["content-type"], "application/yaml");
+    assert_eq!(
+        response.headers["content-disposition"],
+        "attachment; filename=\"names-server1.yml\""
+    );
+    let names = Names::parse(&response.body, Format::Yaml).unwrap();
+    assert_eq!(names.len(), 2);
+    assert_eq!(names.get(123456789), Some("Alice"));
+    assert_eq!(names.get(555666777), None);
+
+    let (created, skipped, errors) = NameService::new(&state.db)
+        .bulk_create_names(&response.body, "server3".to_string())
+        .await
+        .unwrap();
+    assert_eq!((created, skipped), (2, 0));
+    assert!(errors.is_empty());
+
+    let response = TestRequest::get("/names/export?server_id=server2&format=csv")
+        .send(app.clone())
+        .await;
+    assert_eq!(response.headers["content-type"], "text/csv; charset=utf-8");
+    let names = Names::parse(&response.body, Format::Csv).unwrap();
+    assert_eq!(names.len(), 2);
+    assert_eq!(names.get(444333222), Some("David"));
+
+    let response = TestRequest::get("/names/export?server_id=nowhere")
+        .send(app)
+        .await;
+    assert!(
+        Names::parse(&response.body, Format::Yaml)
+            .unwrap()
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn exports_every_name_across_batches() {
+    let state = setup().await.expect("Failed to setup test context");
+    let count = EXPORT_BATCH_SIZE + 1;
+    let names = (1..=count).map(|i| name::ActiveModel {
+        discord_id: Set(discord_id(100000000 + i)),
+        name: Set(format!("User {}", i)),
+        server_id: Set("big-server".to_string()),
+        ..Default::default()
+    });
+    name::Entity::insert_many(names)
+        .exec(&state.db)
+        .await
+        .unwrap();
+    let app = create_name_router(create_name_state(state.db));
+
+    let response = TestRequest::get("/names/export?server_id=big-server&format=csv")
+        .send(app)
+        .await;
+    let names = Names::parse(&response.body, Format::Csv).unwrap();
+    assert_eq!(names.len() as u64, count);
+    assert_eq!(
+        names.get(100000000 + count),
+        Some(format!("User {}", count).as_str())
+    );
+}
+
+#[tokio::test]
+async fn can_restore_deleted_names_from_the_trash_page() {
+    let state = setup().await.expect("Failed to setup test context");
+    let ids = create_test_names_with_ids(&state.db).await;
+    let app = create_name_router(create_name_state(state.db.clone()));
+
+    let response = TestRequest::get("/names/trash").send(app.clone()).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("restored for 30 days"));
+
+    let response = TestRequest::delete(format!("/names/{}", ids[0]))
+        .send(app.clone())
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = TestRequest::get("/names/trash/table")
+        .send(app.clone())
+        .await;
+    assert!(
+        response
+            .body
+            .contains(&format!("hx-post=\"/names/trash/{}/restore\"", ids[0]))
+    );
+
+    let response = TestRequest::post(format!("/names/trash/{}/restore", ids[0]))
+        .send(app.clone())
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("The trash is empty."));
+    let names = NameService::new(&state.db).get_all_names().await.unwrap();
+    assert_eq!(names.len(), ids.len());
+
+    let response = TestRequest::post(format!("/names/trash/{}/restore", ids[0]))
+        .send(app)
+        .await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(response.headers["hx-retarget"], "#trash-error");
+}
+
+#[tokio::test]
+async fn can_bulk_move_selected_names_to_another_server() {
+    let state = setup().await.expect("Failed to setup test context");
+    let ids = create_test_names_with_ids(&state.db).await;
+    let app = create_name_router(create_name_state(state.db.clone()));
+    let move_names = |server_id: &str| {
+        TestRequest::new(Method::PATCH, "/names/bulk").form(&[
+            ("selected_ids", ids[0].to_string()),
+            ("selected_ids", ids[1].to_string()),
+            ("server_id", server_id.to_string()),
+        ])
+    };
+
+    let response = move_names(" ").send(app.clone()).await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.headers["hx-retarget"], "#bulk-error");
+
+    // The first user already has a name in the third server
+    NameService::new(&state.db)
+        .create_name(
+            discord_id(123456789),
+            "Elsewhere".to_string(),
+            "test-server-3".to_string(),
+        )
+        .await
+        .unwrap();
+    let response = move_names("test-server-3").send(app.clone()).await;
+    assert_eq!(response.status, StatusCode::CONFLICT);
+    assert_eq!(response.headers["hx-retarget"], "#bulk-error");
+
+    let response = move_names("test-server-2").send(app).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("id=\"move-selected-btn\""));
+    let moved = NameService::new(&state.db)
+        .get_names_by_server("test-server-2")
+        .await
+        .unwrap();
+    assert_eq!(moved.len(), 2);
+}
+
+#[tokio::test]
+async fn can_merge_duplicates_from_the_duplicates_page() {
+    let state = setup().await.expect("Failed to setup test context");
+    let ids = create_test_names_with_ids(&state.db).await;
+    let duplicate = NameService::new(&state.db)
+        .create_name(
+            discord_id(123456789),
+            "TestUser1".to_string(),
+            "test-server-2".to_string(),
+        )
+        .await
+        .unwrap();
+    let app = create_name_router(create_name_state(state.db.clone()));
+
+    let response = TestRequest::get("/names/duplicates")
+        .send(app.clone())
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+
+    let response = TestRequest::get("/names/duplicates/table?threshold=1")
+        .send(app.clone())
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("Same user in"));
+    assert!(
+        response
+            .body
+            .contains(&format!("name=\"ids\" value=\"{}\"", duplicate.id()))
+    );
+
+    let response = TestRequest::get("/names/duplicates/table?threshold=2")
+        .send(app.clone())
+        .await;
+    assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.headers["hx-retarget"], "#duplicates-error");
+
+    let merge = || {
+        TestRequest::post("/names/duplicates/merge").form(&[
+            ("ids", ids[0].to_string()),
+            ("ids", duplicate.id().to_string()),
+            ("keep", ids[0].to_string()),
+            ("threshold", "1".to_string()),
+        ])
+    };
+    let response = merge().send(app.clone()).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("No likely duplicates found."));
+    let names = NameService::new(&state.db).get_all_names().await.unwrap();
+    assert_eq!(names.len(), ids.len());
+
+    let response = merge().send(app).await;
+    assert_eq!(response.status, StatusCode::NOT_FOUND);
+    assert_eq!(response.headers["hx-retarget"], "#duplicates-error");
+}
+
+#[tokio::test]
+async fn can_live_search_names() {
+    let state = setup().await.expect("Failed to setup test context");
+    create_test_names(&state.db).await;
+    let app = create_name_router(create_name_state(state.db));
+
+    let response = TestRequest::get("/names/search?q=testuser2")
+        .send(app.clone())
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("TestUser2"));
+    assert!(!response.body.contains("No names match your search."));
+
+    let response = TestRequest::get("/names/search?q=nobody")
+        .send(app.clone())
+        .await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.contains("No names match your search."));
+
+    let response = TestRequest::get("/names/search?q=").send(app).await;
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(response.body.is_empty());
+}
+
+/// API v1 tests module for JSON endpoints
+pub mod api {
+    pub mod v1 {
+        use super::super::*;
+        use http_snapshot::JsonApiResponseSnapshot;
+        use serde_json::Value;
 
         #[tokio::test]
         async fn can_get_names_as_json_when_names_exist() {
@@ -1256,11 +1369,11 @@ pub mod api {
 
             // Parse and validate JSON structure
             let json: Value = serde_json::from_str(body_text).expect("Should be valid JSON");
-            assert!(json["names"].is_array());
-            assert_eq!(json["count"], 2);
+            assert!(json["items"].is_array());
+            assert_eq!(json["total"], 2);
 
             // Validate the names array contains our test data
-            let names = json["names"].as_array().unwrap();
+            let names = json["items"].as_array().unwrap();
             assert_eq!(names.len(), 2);
 
             // Check that both test users are present
@@ -1305,9 +1418,9 @@ pub mod api {
 
             // Parse and validate JSON structure
             let json: Value = serde_json::from_str(body_text).expect("Should be valid JSON");
-            assert!(json["names"].is_array());
-            assert_eq!(json["count"], 0);
-            assert_eq!(json["names"].as_array().unwrap().len(), 0);
+            assert!(json["items"].is_array());
+            assert_eq!(json["total"], 0);
+            assert_eq!(json["items"].as_array().unwrap().len(), 0);
 
             let snapshot_data =
                 JsonApiResponseSnapshot::new(body_text, status, &headers, "api_v1_names_empty");
@@ -1343,11 +1456,11 @@ pub mod api {
 
             // Validate root structure
             assert!(json.is_object());
-            assert!(json["names"].is_array());
-            assert!(json["count"].is_number());
+            assert!(json["items"].is_array());
+            assert!(json["total"].is_number());
 
             // Validate each name object structure
-            let names = json["names"].as_array().unwrap();
+            let names = json["items"].as_array().unwrap();
             for name in names {
                 assert!(name["id"].is_number());
                 assert!(name["discord_id"].is_number());
@@ -1369,6 +1482,125 @@ pub mod api {
             assert_yaml_snapshot!(snapshot_data);
         }
 
+        #[tokio::test]
+        async fn api_v1_rejects_updates_to_outdated_versions() {
+            let state = setup().await.expect("Failed to setup test context");
+            let ids = create_test_names_with_ids(&state.db).await;
+            let app = create_api_router(create_name_state(state.db));
+            let update = |version: i32| {
+                TestRequest::put(format!("/names/{}", ids[0])).json(&serde_json::json!({
+                    "name": "Renamed",
+                    "server_id": "test-server-1",
+                    "version": version,
+                }))
+            };
+
+            let response = update(1).send(app.clone()).await;
+            assert_eq!(response.status, StatusCode::OK);
+            let json: Value = response.json();
+            assert_eq!(json["version"], 2);
+
+            let response = update(1).send(app).await;
+            assert_eq!(response.status, StatusCode::CONFLICT);
+        }
+
+        #[tokio::test]
+        async fn api_v1_can_bulk_move_names_to_another_server() {
+            let state = setup().await.expect("Failed to setup test context");
+            let ids = create_test_names_with_ids(&state.db).await;
+            let app = create_api_router(create_name_state(state.db));
+            let move_names = |server_id: &str| {
+                TestRequest::new(Method::PATCH, "/names/bulk").json(&serde_json::json!({
+                    "ids": [ids[0], ids[1]],
+                    "server_id": server_id,
+                }))
+            };
+
+            let response = move_names("test-server-2").send(app.clone()).await;
+            assert_eq!(response.status, StatusCode::OK);
+            let json: Value = response.json();
+            let names = json.as_array().unwrap();
+            assert_eq!(names.len(), 2);
+            assert_eq!(names[0]["server_id"], "test-server-2");
+            assert_eq!(names[0]["version"], 2);
+
+            let response = move_names("").send(app.clone()).await;
+            assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+            let response = TestRequest::new(Method::PATCH, "/names/bulk")
+                .json(&serde_json::json!({
+                    "ids": [ids[2]],
+                    "server_id": "test-server-2",
+                }))
+                .send(app.clone())
+                .await;
+            assert_eq!(response.status, StatusCode::OK);
+            let response = TestRequest::new(Method::PATCH, "/names/bulk")
+                .json(&serde_json::json!({
+                    "ids": [ids[0], 999999],
+                    "server_id": "test-server-3",
+                }))
+                .send(app)
+                .await;
+            assert_eq!(response.status, StatusCode::NOT_FOUND);
+        }
+
+        #[tokio::test]
+        async fn api_v1_can_export_names_and_requires_a_server_id() {
+            let state = setup().await.expect("Failed to setup test context");
+            create_test_names(&state.db).await;
+            let app = create_api_router(create_name_state(state.db));
+
+            let response = TestRequest::get("/names/export?server_id=test-server-1&format=csv")
+                .send(app.clone())
+                .await;
+            assert_eq!(response.status, StatusCode::OK);
+            assert_eq!(
+                response.body,
+                "discord_id,name\n123456789,TestUser1\n987654321,TestUser2\n"
+            );
+
+            let response = TestRequest::get("/names/export?server_id=%20")
+                .send(app.clone())
+                .await;
+            assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+            let json: Value = response.json();
+            assert_eq!(json["errors"][0]["field"], "server_id");
+
+            let response = TestRequest::get("/names/export?server_id=test-server-1&format=xml")
+                .send(app)
+                .await;
+            assert_eq!(response.status, StatusCode::BAD_REQUEST);
+        }
+
+        #[tokio::test]
+        async fn api_v1_can_search_names_and_validates_the_query() {
+            let state = setup().await.expect("Failed to setup test context");
+            create_test_names(&state.db).await;
+            let app = create_api_router(create_name_state(state.db));
+
+            let response = TestRequest::get("/names/search?q=testuser1&limit=1")
+                .send(app.clone())
+                .await;
+            assert_eq!(response.status, StatusCode::OK);
+            let json: Value = response.json();
+            assert_eq!(json.as_array().map(Vec::len), Some(1));
+            assert_eq!(json[0]["name"], "TestUser1");
+
+            let response = TestRequest::get("/names/search?q=%20&limit=0")
+                .send(app.clone())
+                .await;
+            assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+            let json: Value = response.json();
+            assert_eq!(json["errors"][0]["field"], "q");
+            assert_eq!(json["errors"][1]["field"], "limit");
+
+            let response = TestRequest::get("/names/search?q=test&limit=many")
+                .send(app)
+                .await;
+            assert_eq!(response.status, StatusCode::BAD_REQUEST);
+        }
+
         #[tokio::test]
         async fn api_v1_names_endpoint_handles_large_dataset() {
             let state = setup().await.expect("Failed to setup test context");
@@ -1376,7 +1608,7 @@ pub mod api {
             // Create 10 test names
             for i in 1..=10 {
                 let name = name::ActiveModel {
-                    discord_id: Set(100000000 + i),
+                    discord_id: Set(discord_id(100000000 + i)),
                     name: Set(format!("TestUser{}", i)),
                     server_id: Set("test-server-1".to_string()),
                     ..Default::default()
@@ -1407,11 +1639,11 @@ pub mod api {
 
             // Parse and validate JSON structure
             let json: Value = serde_json::from_str(body_text).expect("Should be valid JSON");
-            assert_eq!(json["count"], 10);
-            assert_eq!(json["names"].as_array().unwrap().len(), 10);
+            assert_eq!(json["total"], 10);
+            assert_eq!(json["items"].as_array().unwrap().len(), 10);
 
             // Verify all names are present
-            let names = json["names"].as_array().unwrap();
+            let names = json["items"].as_array().unwrap();
             let name_values: Vec<&str> =
                 names.iter().map(|n| n["name"].as_str().unwrap()).collect();
 
@@ -1429,6 +1661,73 @@ pub mod api {
             assert_yaml_snapshot!(snapshot_data);
         }
 
+        #[tokio::test]
+        async fn api_v1_names_endpoint_returns_requested_page_in_requested_order() {
+            let state = setup().await.expect("Failed to setup test context");
+
+            for i in 1..=5 {
+                let name = name::ActiveModel {
+                    discord_id: Set(discord_id(100000000 + i)),
+                    name: Set(format!("TestUser{}", i)),
+                    server_id: Set("test-server-1".to_string()),
+                    ..Default::default()
+                };
+                name.insert(&state.db).await.unwrap();
+            }
+
+            let name_state = create_name_state(state.db);
+            let app = create_api_router(name_state);
+
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri("/names?page=2&per_page=2&sort=name&order=desc")
+                .body(Body::empty())
+                .unwrap();
+
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            let json: Value = serde_json::from_slice(&body).expect("Should be valid JSON");
+            assert_eq!(json["page"], 2);
+            assert_eq!(json["per_page"], 2);
+            assert_eq!(json["total"], 5);
+            assert_eq!(json["total_pages"], 3);
+
+            let name_values: Vec<&str> = json["items"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|n| n["name"].as_str().unwrap())
+                .collect();
+            assert_eq!(name_values, vec!["TestUser3", "TestUser2"]);
+        }
+
+        #[tokio::test]
+        async fn api_v1_names_endpoint_rejects_invalid_pagination() {
+            let state = setup().await.expect("Failed to setup test context");
+
+            let name_state = create_name_state(state.db);
+            let app = create_api_router(name_state);
+
+            for uri in [
+                "/names?per_page=1000",
+                "/names?page=0",
+                "/names?sort=secret",
+            ] {
+                let request = Request::builder()
+                    .method(Method::GET)
+                    .uri(uri)
+                    .body(Body::empty())
+                    .unwrap();
+
+                let response = app.clone().oneshot(request).await.unwrap();
+                assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{uri}");
+            }
+        }
+
         #[tokio::test]
         async fn api_v1_names_endpoint_returns_names_in_consistent_order() {
             let state = setup().await.expect("Failed to setup test context");
@@ -1468,8 +1767,8 @@ pub mod api {
 
             // Parse and verify structure
             let json: Value = serde_json::from_str(body_text1).expect("Should be valid JSON");
-            assert!(json["names"].is_array());
-            assert_eq!(json["count"], 2);
+            assert!(json["items"].is_array());
+            assert_eq!(json["total"], 2);
         }
 
         #[tokio::test]
@@ -1504,11 +1803,11 @@ pub mod api {
 
             // Parse and validate JSON structure
             let json: Value = serde_json::from_str(body_text).expect("Should be valid JSON");
-            assert!(json["names"].is_array());
-            assert_eq!(json["count"], 2);
+            assert!(json["items"].is_array());
+            assert_eq!(json["total"], 2);
 
             // Validate that only server1 names are returned
-            let names = json["names"].as_array().unwrap();
+            let names = json["items"].as_array().unwrap();
             assert_eq!(names.len(), 2);
 
             let name_values: Vec<&str> =
@@ -1562,11 +1861,11 @@ pub mod api {
 
             // Parse and validate JSON structure
             let json: Value = serde_json::from_str(body_text).expect("Should be valid JSON");
-            assert!(json["names"].is_array());
-            assert_eq!(json["count"], 2);
+            assert!(json["items"].is_array());
+            assert_eq!(json["total"], 2);
 
             // Validate that only server2 names are returned
-            let names = json["names"].as_array().unwrap();
+            let names = json["items"].as_array().unwrap();
             assert_eq!(names.len(), 2);
 
             let name_values: Vec<&str> =
@@ -1620,9 +1919,9 @@ pub mod api {
 
             // Parse and validate JSON structure
             let json: Value = serde_json::from_str(body_text).expect("Should be valid JSON");
-            assert!(json["names"].is_array());
-            assert_eq!(json["count"], 0);
-            assert_eq!(json["names"].as_array().unwrap().len(), 0);
+            assert!(json["items"].is_array());
+            assert_eq!(json["total"], 0);
+            assert_eq!(json["items"].as_array().unwrap().len(), 0);
 
             let snapshot_data = JsonApiResponseSnapshot::new(
                 body_text,
@@ -1663,11 +1962,11 @@ pub mod api {
 
             // Parse and validate JSON structure
             let json: Value = serde_json::from_str(body_text).expect("Should be valid JSON");
-            assert!(json["names"].is_array());
-            assert_eq!(json["count"], 4); // All names from both servers
+            assert!(json["items"].is_array());
+            assert_eq!(json["total"], 4); // All names from both servers
 
             // Validate that all names are returned
-            let names = json["names"].as_array().unwrap();
+            let names = json["items"].as_array().unwrap();
             assert_eq!(names.len(), 4);
 
             let name_values: Vec<&str> =
@@ -1716,9 +2015,9 @@ pub mod api {
 
             // Parse and validate JSON structure
             let json: Value = serde_json::from_str(body_text).expect("Should be valid JSON");
-            assert!(json["names"].is_array());
-            assert_eq!(json["count"], 0); // Empty string should match no servers
-            assert_eq!(json["names"].as_array().unwrap().len(), 0);
+            assert!(json["items"].is_array());
+            assert_eq!(json["total"], 0); // Empty string should match no servers
+            assert_eq!(json["items"].as_array().unwrap().len(), 0);
 
             let snapshot_data = JsonApiResponseSnapshot::new(
                 body_text,
@@ -1729,6 +2028,296 @@ pub mod api {
 
             assert_yaml_snapshot!(snapshot_data);
         }
+
+        #[tokio::test]
+        async fn can_search_names_and_look_up_users() {
+            let state = setup().await.expect("Failed to setup test context");
+            create_test_names_multiple_servers(&state.db).await;
+            let app = create_api_router(create_name_state(state.db));
+
+            for (uri, expected) in [
+                ("/names?search=LI", vec!["Alice", "Charlie"]),
+                (
+                    "/names?search=a&server_id=server2",
+                    vec!["Charlie", "David"],
+                ),
+                ("/names?discord_id=987654321", vec!["Bob"]),
+                ("/names?search=", vec!["Alice", "Bob", "Charlie", "David"]),
+            ] {
+                let request = Request::builder()
+                    .method(Method::GET)
+                    .uri(uri)
+                    .body(Body::empty())
+                    .unwrap();
+                let response = app.clone().oneshot(request).await.unwrap();
+                assert_eq!(response.status(), StatusCode::OK, "{uri}");
+
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                let json: Value = serde_json::from_slice(&body).unwrap();
+                let names: Vec<&str> = json["items"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|n| n["name"].as_str().unwrap())
+                    .collect();
+                assert_eq!(names, expected, "{uri}");
+                assert_eq!(json["total"], expected.len(), "{uri}");
+            }
+
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri("/names?discord_id=someone")
+                .body(Body::empty())
+                .unwrap();
+            let response = app.oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+            assert_eq!(
+                response.headers()["content-type"],
+                "application/problem+json"
+            );
+        }
+
+        fn json_request(method: Method, uri: &str, body: Value) -> Request<Body> {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        }
+
+        async fn json_response(response: axum::response::Response) -> (StatusCode, String, Value) {
+            let status = response.status();
+            let content_type = response.headers()["content-type"]
+                .to_str()
+                .unwrap()
+                .to_string();
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            (status, content_type, serde_json::from_slice(&body).unwrap())
+        }
+
+        #[tokio::test]
+        async fn can_create_name_as_json() {
+            let state = setup().await.expect("Failed to setup test context");
+            let app = create_api_router(create_name_state(state.db));
+
+            let request = json_request(
+                Method::POST,
+                "/names",
+                serde_json::json!({
+                    "discord_id": 123456789,
+                    "name": " Alice ",
+                    "server_id": "server1",
+                }),
+            );
+            let (status, content_type, json) =
+                json_response(app.clone().oneshot(request).await.unwrap()).await;
+
+            assert_eq!(status, StatusCode::CREATED);
+            assert_eq!(content_type, "application/json");
+            assert!(json["id"].as_u64().unwrap() > 0);
+            assert_eq!(json["discord_id"], 123456789);
+            assert_eq!(json["name"], "Alice");
+            assert_eq!(json["server_id"], "server1");
+
+            let request = Request::builder()
+                .method(Method::GET)
+                .uri("/names")
+                .body(Body::empty())
+                .unwrap();
+            let (_, _, json) = json_response(app.oneshot(request).await.unwrap()).await;
+            assert_eq!(json["total"], 1);
+        }
+
+        #[tokio::test]
+        async fn creating_a_duplicate_name_is_a_conflict() {
+            let state = setup().await.expect("Failed to setup test context");
+            create_test_names(&state.db).await;
+            let app = create_api_router(create_name_state(state.db));
+
+            let request = json_request(
+                Method::POST,
+                "/names",
+                serde_json::json!({
+                    "discord_id": 123456789,
+                    "name": "Someone else",
+                    "server_id": "test-server-1",
+                }),
+            );
+            let (status, content_type, json) =
+                json_response(app.oneshot(request).await.unwrap()).await;
+
+            assert_eq!(status, StatusCode::CONFLICT);
+            assert_eq!(content_type, "application/problem+json");
+            assert_eq!(json["status"], 409);
+        }
+
+        #[tokio::test]
+        async fn creating_a_name_with_invalid_fields_lists_them() {
+            let state = setup().await.expect("Failed to setup test context");
+            let app = create_api_router(create_name_state(state.db));
+
+            let request = json_request(
+                Method::POST,
+                "/names",
+                serde_json::json!({
+                    "discord_id": 0,
+                    "name": "  ",
+                    "server_id": "server1",
+                }),
+            );
+            let (status, content_type, json) =
+                json_response(app.oneshot(request).await.unwrap()).await;
+
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(content_type, "application/problem+json");
+            let fields: Vec<&str> = json["errors"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|error| error["field"].as_str().unwrap())
+                .collect();
+            assert_eq!(fields, vec!["discord_id", "name"]);
+        }
+
+        #[tokio::test]
+        async fn can_update_name_as_json() {
+            let state = setup().await.expect("Failed to setup test context");
+            let id = create_editable_test_name(&state.db).await;
+            let app = create_api_router(create_name_state(state.db));
+
+            let request = json_request(
+                Method::PUT,
+                &format!("/names/{}", id),
+                serde_json::json!({ "name": "Renamed", "server_id": "server2" }),
+            );
+            let (status, _, json) = json_response(app.oneshot(request).await.unwrap()).await;
+
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(json["id"], u32::from(id));
+            assert_eq!(json["name"], "Renamed");
+            assert_eq!(json["server_id"], "server2");
+        }
+
+        #[tokio::test]
+        async fn updating_a_missing_or_clashing_name_fails() {
+            let state = setup().await.expect("Failed to setup test context");
+            let ids = create_test_names_with_ids(&state.db).await;
+            let app = create_api_router(create_name_state(state.db.clone()));
+            let clashing = name::ActiveModel {
+                discord_id: Set(discord_id(123456789)),
+                name: Set("Elsewhere".to_string()),
+                server_id: Set("other-server".to_string()),
+                ..Default::default()
+            }
+            .insert(&state.db)
+            .await
+            .unwrap();
+
+            let request = json_request(
+                Method::PUT,
+                "/names/99999",
+                serde_json::json!({ "name": "Renamed", "server_id": "server1" }),
+            );
+            let (status, content_type, _) =
+                json_response(app.clone().oneshot(request).await.unwrap()).await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+            assert_eq!(content_type, "application/problem+json");
+
+            let request = json_request(
+                Method::PUT,
+                &format!("/names/{}", clashing.id),
+                serde_json::json!({ "name": "Elsewhere", "server_id": "test-server-1" }),
+            );
+            let (status, _, _) = json_response(app.clone().oneshot(request).await.unwrap()).await;
+            assert_eq!(status, StatusCode::CONFLICT);
+
+            let request = json_request(
+                Method::PUT,
+                &format!("/names/{}", ids[0]),
+                serde_json::json!({ "name": "", "server_id": "test-server-1" }),
+            );
+            let (status, _, json) = json_response(app.oneshot(request).await.unwrap()).await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(json["errors"][0]["field"], "name");
+        }
+
+        #[tokio::test]
+        async fn can_delete_name_as_json() {
+            let state = setup().await.expect("Failed to setup test context");
+            let id = create_single_test_name(&state.db).await;
+            let app = create_api_router(create_name_state(state.db));
+
+            let request = Request::builder()
+                .method(Method::DELETE)
+                .uri(format!("/names/{}", id))
+                .body(Body::empty())
+                .unwrap();
+            let response = app.clone().oneshot(request).await.unwrap();
+            assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+            let request = Request::builder()
+                .method(Method::DELETE)
+                .uri(format!("/names/{}", id))
+                .body(Body::empty())
+                .unwrap();
+            let (status, content_type, json) =
+                json_response(app.oneshot(request).await.unwrap()).await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+            assert_eq!(content_type, "application/problem+json");
+            assert_eq!(
+                json["detail"],
+                format!("Name entry with ID {} not found", id)
+            );
+        }
+
+        #[tokio::test]
+        async fn can_purge_deleted_names_as_json() {
+            let state = setup().await.expect("Failed to setup test context");
+            let id = create_single_test_name(&state.db).await;
+            let app = create_api_router(create_name_state(state.db));
+            let delete = |uri: String| {
+                Request::builder()
+                    .method(Method::DELETE)
+                    .uri(uri)
+                    .body(Body::empty())
+                    .unwrap()
+            };
+
+            // Only names in the trash can be purged
+            let response = app
+                .clone()
+                .oneshot(delete(format!("/names/trash/{}", id)))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+            let response = app
+                .clone()
+                .oneshot(delete(format!("/names/{}", id)))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NO_CONTENT);
+            let response = app
+                .clone()
+                .oneshot(delete(format!("/names/trash/{}", id)))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+            let (status, content_type, _) = json_response(
+                app.oneshot(delete(format!("/names/trash/{}", id)))
+                    .await
+                    .unwrap(),
+            )
+            .await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+            assert_eq!(content_type, "application/problem+json");
+        }
     }
 }
 
@@ -1739,21 +2328,9 @@ async fn can_serve_bulk_add_form() {
     let name_state = create_name_state(state.db);
     let app = create_name_router(name_state);
 
-    let request = Request::builder()
-        .uri("/names/bulk-add")
-        .body(Body::empty())
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
+    let response = TestRequest::get("/names/bulk-add").send(app).await;
 
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
-
-    let snapshot_data = HttpResponseSnapshot::new(body_text, status, &headers, "bulk_add_form");
+    let snapshot_data = response.html_snapshot("bulk_add_form");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -1768,28 +2345,13 @@ async fn can_process_bulk_add_with_valid_yaml() {
     let yaml_content = "123456789: TestUser1\n987654321: TestUser2\n111222333: TestUser3";
     let form_data = format!("server_id=test-server-1&yaml_content={}", yaml_content);
 
-    let request = Request::builder()
-        .method(Method::POST)
-        .uri("/names/bulk-add")
+    let response = TestRequest::post("/names/bulk-add")
         .header("content-type", "application/x-www-form-urlencoded")
-        .body(Body::from(form_data))
-        .unwrap();
+        .body(form_data)
+        .send(app)
+        .await;
 
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
-
-    let snapshot_data = HttpResponseSnapshot::new(
-        body_text,
-        status,
-        &headers,
-        "bulk_add_success_with_valid_yaml",
-    );
+    let snapshot_data = response.html_snapshot("bulk_add_success_with_valid_yaml");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -1808,28 +2370,13 @@ async fn can_handle_bulk_add_with_some_duplicate_entries() {
     let yaml_content = "123456789: TestUser1\n555666777: NewUser1\n888999000: NewUser2";
     let form_data = format!("server_id=test-server-1&yaml_content={}", yaml_content);
 
-    let request = Request::builder()
-        .method(Method::POST)
-        .uri("/names/bulk-add")
+    let response = TestRequest::post("/names/bulk-add")
         .header("content-type", "application/x-www-form-urlencoded")
-        .body(Body::from(form_data))
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
+        .body(form_data)
+        .send(app)
+        .await;
 
-    let snapshot_data = HttpResponseSnapshot::new(
-        body_text,
-        status,
-        &headers,
-        "bulk_add_success_with_duplicates",
-    );
+    let snapshot_data = response.html_snapshot("bulk_add_success_with_duplicates");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -1845,24 +2392,13 @@ async fn can_handle_bulk_add_with_invalid_yaml() {
     let yaml_content = "invalid: yaml: content: [unclosed";
     let form_data = format!("server_id=test-server-1&yaml_content={}", yaml_content);
 
-    let request = Request::builder()
-        .method(Method::POST)
-        .uri("/names/bulk-add")
+    let response = TestRequest::post("/names/bulk-add")
         .header("content-type", "application/x-www-form-urlencoded")
-        .body(Body::from(form_data))
-        .unwrap();
+        .body(form_data)
+        .send(app)
+        .await;
 
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
-
-    let snapshot_data =
-        HttpResponseSnapshot::new(body_text, status, &headers, "bulk_add_with_invalid_yaml");
+    let snapshot_data = response.html_snapshot("bulk_add_with_invalid_yaml");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -1878,24 +2414,13 @@ async fn can_handle_bulk_add_with_empty_yaml() {
     let yaml_content = "";
     let form_data = format!("server_id=test-server-1&yaml_content={}", yaml_content);
 
-    let request = Request::builder()
-        .method(Method::POST)
-        .uri("/names/bulk-add")
+    let response = TestRequest::post("/names/bulk-add")
         .header("content-type", "application/x-www-form-urlencoded")
-        .body(Body::from(form_data))
-        .unwrap();
+        .body(form_data)
+        .send(app)
+        .await;
 
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
-
-    let snapshot_data =
-        HttpResponseSnapshot::new(body_text, status, &headers, "bulk_add_with_empty_yaml");
+    let snapshot_data = response.html_snapshot("bulk_add_with_empty_yaml");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -1989,7 +2514,7 @@ async fn can_handle_bulk_delete_with_nonexistent_ids() {
 
     // Include some nonexistent IDs along with valid ones using query parameters
     let valid_id = test_ids[0];
-    let invalid_ids = [99999, 88888];
+    let invalid_ids = [name_id(99999), name_id(88888)];
     let mut selected_ids = vec![valid_id];
     selected_ids.extend_from_slice(&invalid_ids);
 
@@ -2077,27 +2602,11 @@ async fn bulk_delete_endpoint_returns_correct_content_type() {
 
     let query_params = format!("selected_ids={}", test_ids[0]);
 
-    let request = Request::builder()
-        .method(Method::DELETE)
-        .uri(format!("/names/delete?{}", query_params))
-        .body(Body::empty())
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
+    let response = TestRequest::delete(format!("/names/delete?{}", query_params))
+        .send(app)
+        .await;
 
-    let snapshot_data = HttpResponseSnapshot::new(
-        body_text,
-        status,
-        &headers,
-        "bulk_delete_content_type_check",
-    );
+    let snapshot_data = response.html_snapshot("bulk_delete_content_type_check");
 
     assert_yaml_snapshot!(snapshot_data);
 }
@@ -0,0 +1,375 @@
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use nicknamer_server::name::cache::NameCache;
+use nicknamer_server::name::web::{NameState, create_name_router};
+use nicknamer_server::name::{NameQuery, NameService};
+use nicknamer_server::server::api::v1::create_api_router;
+use nicknamer_server::server::web::{ServerState, create_server_router};
+use nicknamer_server::server::{ServerDetails, ServerService, ServerServiceError};
+use std::sync::Arc;
+use std::time::Duration;
+use tower::ServiceExt;
+use typed_ids::DiscordId;
+
+mod common;
+
+use db_test_support::TestDb;
+
+async fn setup() -> anyhow::Result<TestDb> {
+    // Allow multiple calls to init for tests.
+    let _ = tracing_subscriber::fmt().try_init();
+    common::setup_db().await
+}
+
+fn snowflake(id: u64) -> DiscordId {
+    DiscordId::new(id).unwrap()
+}
+
+fn details(name: &str) -> ServerDetails {
+    ServerDetails {
+        name: name.to_string(),
+        ..Default::default()
+    }
+}
+
+async fn body_text(response: axum::response::Response) -> String {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+#[tokio::test]
+async fn can_register_update_and_delete_servers() {
+    let state = setup().await.expect("Failed to setup test context");
+    let service = ServerService::new(&state.db);
+
+    let server = service
+        .create_server(
+            snowflake(111),
+            ServerDetails {
+                name: " Lowkey Lab ".to_string(),
+                icon_url: Some("https://cdn.example/icon.png".to_string()),
+                owner_id: Some(snowflake(42)),
+            },
+        )
+        .await
+        .expect("Failed to register server");
+    assert_eq!(server.guild_id(), snowflake(111));
+    assert_eq!(server.name(), "Lowkey Lab");
+    assert_eq!(server.icon_url(), Some("https://cdn.example/icon.png"));
+    assert_eq!(server.owner_id(), Some(snowflake(42)));
+
+    assert!(matches!(
+        service
+            .create_server(snowflake(111), details("Again"))
+            .await,
+        Err(ServerServiceError::DuplicateGuild(id)) if id == snowflake(111)
+    ));
+    assert!(matches!(
+        service.create_server(snowflake(222), details(" ")).await,
+        Err(ServerServiceError::MissingName)
+    ));
+
+    let updated = service
+        .update_server(server.id(), details("The Lab"))
+        .await
+        .unwrap();
+    assert_eq!(updated.name(), "The Lab");
+    assert_eq!(updated.icon_url(), None);
+    assert_eq!(updated.owner_id(), None);
+    assert_eq!(service.get_server(server.id()).await.unwrap(), updated);
+
+    let other = service
+        .create_server(snowflake(222), details("Another Lab"))
+        .await
+        .unwrap();
+    assert_eq!(
+        service.list_servers().await.unwrap(),
+        vec![other, updated.clone()]
+    );
+
+    assert_eq!(service.delete_server(server.id()).await.unwrap(), updated);
+    assert!(matches!(
+        service.get_server(server.id()).await,
+        Err(ServerServiceError::ServerNotFound(_))
+    ));
+}
+
+#[tokio::test]
+async fn names_are_linked_to_the_server_registered_for_their_guild() {
+    let state = setup().await.expect("Failed to setup test context");
+    let names = NameService::new(&state.db);
+    let servers = ServerService::new(&state.db);
+
+    let before = names
+        .create_name(snowflake(1), "Alice".to_string(), "111".to_string())
+        .await
+        .unwrap();
+    assert_eq!(before.server_name(), None);
+    names
+        .create_name(snowflake(2), "Bob".to_string(), "not-a-guild".to_string())
+        .await
+        .unwrap();
+
+    let server = servers
+        .create_server(snowflake(111), details("Lowkey Lab"))
+        .await
+        .unwrap();
+    let alice = names.get_name_by_id(before.id()).await.unwrap();
+    assert_eq!(alice.server_name(), Some("Lowkey Lab"));
+
+    let carol = names
+        .create_name(snowflake(3), "Carol".to_string(), "111".to_string())
+        .await
+        .unwrap();
+    assert_eq!(carol.server_name(), Some("Lowkey Lab"));
+    let bob = names
+        .get_all_names()
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|name| name.name() == "Bob")
+        .unwrap();
+    let bob = names
+        .edit_name_by_id(bob.id(), "Bob".to_string(), "111".to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(bob.server_name(), Some("Lowkey Lab"));
+
+    let registered = names
+        .list(&NameQuery {
+            registered_server_id: Some(server.id()),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(registered.total, 3);
+    assert!(
+        registered
+            .items
+            .iter()
+            .all(|name| name.server_name() == Some("Lowkey Lab"))
+    );
+
+    let moved = names
+        .edit_name_by_id(
+            carol.id(),
+            "Carol".to_string(),
+            "elsewhere".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+    assert_eq!(moved.server_name(), None);
+
+    servers.delete_server(server.id()).await.unwrap();
+    let alice = names.get_name_by_id(alice.id()).await.unwrap();
+    assert_eq!(alice.server_id(), "111");
+    assert_eq!(alice.server_name(), None);
+}
+
+#[tokio::test]
+async fn can_manage_servers_from_the_servers_page() {
+    let state = setup().await.expect("Failed to setup test context");
+    let db = Arc::new(state.db.clone());
+    NameService::new(&state.db)
+        .create_name(snowflake(1), "Alice".to_string(), "111".to_string())
+        .await
+        .unwrap();
+    let name_cache = NameCache::new(Duration::from_secs(60));
+    let app = create_server_router(Arc::new(ServerState {
+        db: db.clone(),
+        name_cache: name_cache.clone(),
+    }))
+    .merge(create_name_router(Arc::new(NameState {
+        db,
+        trash_retention_days: 30,
+        cache: name_cache,
+    })));
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/servers")
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(
+                    "guild_id=111&name=Lowkey+Lab&icon_url=&owner_id=",
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(body_text(response).await.contains("Lowkey Lab"));
+    let server = ServerService::new(&state.db).list_servers().await.unwrap()[0].clone();
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri("/servers/options")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let options = body_text(response).await;
+    assert!(options.contains(&format!(
+        "<option value=\"{}\">Lowkey Lab</option>",
+        server.id()
+    )));
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri(format!("/names/table?registered_server_id={}", server.id()))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let table = body_text(response).await;
+    assert!(table.contains("<span title=\"111\">Lowkey Lab</span>"));
+    assert!(table.contains("Alice"));
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::PUT)
+                .uri(format!("/servers/{}", server.id()))
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from("name=The+Lab&icon_url=&owner_id=not+an+id"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.headers()["hx-retarget"], "#server-error");
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::DELETE)
+                .uri(format!("/servers/{}", server.id()))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(
+        body_text(response)
+            .await
+            .contains("No servers are registered yet.")
+    );
+}
+
+#[tokio::test]
+async fn can_manage_servers_via_json_api() {
+    let state = setup().await.expect("Failed to setup test context");
+    let app = create_api_router(Arc::new(ServerState {
+        db: Arc::new(state.db.clone()),
+        name_cache: NameCache::new(Duration::from_secs(60)),
+    }));
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/servers")
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    r#"{"guild_id": 111, "name": "Lowkey Lab", "owner_id": 42}"#,
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(created["guild_id"], 111);
+    assert_eq!(created["name"], "Lowkey Lab");
+    assert_eq!(created["owner_id"], 42);
+    assert_eq!(created["icon_url"], serde_json::Value::Null);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/servers")
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"guild_id": 0, "name": "Nowhere"}"#))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::PUT)
+                .uri(format!("/servers/{}", created["id"]))
+                .header("content-type", "application/json")
+                .body(Body::from(
+                    r#"{"name": "The Lab", "icon_url": "https://cdn.example/icon.png"}"#,
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri("/servers")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let servers: serde_json::Value = serde_json::from_str(&body_text(response).await).unwrap();
+    assert_eq!(servers[0]["name"], "The Lab");
+    assert_eq!(servers[0]["icon_url"], "https://cdn.example/icon.png");
+    assert_eq!(servers[0]["owner_id"], serde_json::Value::Null);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::DELETE)
+                .uri(format!("/servers/{}", created["id"]))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = app
+        .oneshot(
+            Request::builder()
+                .method(Method::GET)
+                .uri(format!("/servers/{}", created["id"]))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,306 @@
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use axum::middleware::{from_fn, from_fn_with_state};
+use nicknamer_server::auth::{
+    CurrentUser, require_editor_for_changes_middleware, require_role_middleware,
+};
+use nicknamer_server::user::web::{UserState, create_user_router};
+use nicknamer_server::user::{Role, UserService, UserServiceError};
+use std::sync::Arc;
+use tower::ServiceExt;
+use typed_ids::DiscordId;
+
+mod common;
+
+use db_test_support::TestDb;
+
+async fn setup() -> anyhow::Result<TestDb> {
+    // Allow multiple calls to init for tests.
+    let _ = tracing_subscriber::fmt().try_init();
+    common::setup_db().await
+}
+
+async fn body_text(response: axum::response::Response) -> String {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    String::from_utf8(body.to_vec()).unwrap()
+}
+
+/// A names route and an admin route behind the role checks, as a user with `role`.
+fn create_role_checked_app(role: Role) -> axum::Router {
+    let names = axum::Router::new()
+        .route(
+            "/names",
+            axum::routing::get(|| async { "names" }).post(|| async { "created" }),
+        )
+        .route_layer(from_fn(require_editor_for_changes_middleware));
+    let admin = axum::Router::new()
+        .route("/admin/users", axum::routing::get(|| async { "users" }))
+        .route_layer(from_fn_with_state(Role::Admin, require_role_middleware));
+
+    names
+        .merge(admin)
+        .layer(axum::Extension(role))
+        .layer(axum::Extension(CurrentUser::new("someone".to_string())))
+}
+
+fn request(method: Method, uri: &str) -> Request<Body> {
+    Request::builder()
+        .method(method)
+        .uri(uri)
+        .body(Body::empty())
+        .unwrap()
+}
+
+#[tokio::test]
+async fn roles_decide_which_requests_users_can_make() {
+    let cases = [
+        (
+            Role::Viewer,
+            [StatusCode::OK, StatusCode::FORBIDDEN, StatusCode::FORBIDDEN],
+        ),
+        (
+            Role::Editor,
+            [StatusCode::OK, StatusCode::OK, StatusCode::FORBIDDEN],
+        ),
+        (
+            Role::Admin,
+            [StatusCode::OK, StatusCode::OK, StatusCode::OK],
+        ),
+    ];
+
+    for (role, [read, change, manage]) in cases {
+        let app = create_role_checked_app(role);
+
+        let response = app
+            .clone()
+            .oneshot(request(Method::GET, "/names"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), read, "{} reading names", role);
+
+        let response = app
+            .clone()
+            .oneshot(request(Method::POST, "/names"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), change, "{} changing names", role);
+
+        let response = app
+            .oneshot(request(Method::GET, "/admin/users"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), manage, "{} managing users", role);
+    }
+}
+
+#[tokio::test]
+async fn can_register_and_authenticate_users() {
+    let state = setup().await.expect("Failed to setup test context");
+    let service = UserService::new(&state.db);
+
+    let user = service
+        .create_user(" alice ".to_string(), "correct horse", Role::Editor)
+        .await
+        .expect("Failed to create user");
+    assert_eq!(user.username(), "alice");
+    assert_eq!(user.role(), Role::Editor);
+
+    let authenticated = service
+        .authenticate("alice", "correct horse")
+        .await
+        .unwrap();
+    assert_eq!(authenticated, Some(user));
+    assert_eq!(service.authenticate("alice", "wrong").await.unwrap(), None);
+    assert_eq!(
+        service.authenticate("bob", "correct horse").await.unwrap(),
+        None
+    );
+
+    assert!(matches!(
+        service
+            .create_user("alice".to_string(), "another password", Role::Viewer)
+            .await,
+        Err(UserServiceError::DuplicateUsername(_))
+    ));
+    assert!(matches!(
+        service
+            .create_user("bob".to_string(), "short", Role::Viewer)
+            .await,
+        Err(UserServiceError::PasswordTooShort)
+    ));
+    assert!(matches!(
+        service
+            .create_user(" ".to_string(), "long enough", Role::Viewer)
+            .await,
+        Err(UserServiceError::MissingUsername)
+    ));
+}
+
+#[tokio::test]
+async fn can_only_register_the_first_admin_once() {
+    let state = setup().await.expect("Failed to setup test context");
+    let service = UserService::new(&state.db);
+
+    let admin = service
+        .ensure_admin("admin".to_string(), "password")
+        .await
+        .unwrap()
+        .expect("Expected the first admin to be registered");
+    assert_eq!(admin.role(), Role::Admin);
+
+    let again = service
+        .ensure_admin("other".to_string(), "password")
+        .await
+        .unwrap();
+    assert_eq!(again, None);
+    assert_eq!(service.get_all_users().await.unwrap(), vec![admin]);
+}
+
+#[tokio::test]
+async fn can_not_remove_the_last_admin() {
+    let state = setup().await.expect("Failed to setup test context");
+    let service = UserService::new(&state.db);
+    let admin = service
+        .create_user("admin".to_string(), "password", Role::Admin)
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        service.set_role(admin.id(), Role::Editor).await,
+        Err(UserServiceError::LastAdmin)
+    ));
+    assert!(matches!(
+        service.delete_user(admin.id()).await,
+        Err(UserServiceError::LastAdmin)
+    ));
+
+    let other = service
+        .create_user("other".to_string(), "password", Role::Viewer)
+        .await
+        .unwrap();
+    let other = service.set_role(other.id(), Role::Admin).await.unwrap();
+    assert_eq!(other.role(), Role::Admin);
+
+    let demoted = service.set_role(admin.id(), Role::Viewer).await.unwrap();
+    assert_eq!(demoted.role(), Role::Viewer);
+    service.delete_user(demoted.id()).await.unwrap();
+    assert_eq!(service.get_all_users().await.unwrap(), vec![other]);
+}
+
+#[tokio::test]
+async fn can_link_users_to_discord_accounts() {
+    let state = setup().await.expect("Failed to setup test context");
+    let service = UserService::new(&state.db);
+    let alice = service
+        .create_user("alice".to_string(), "correct horse", Role::Editor)
+        .await
+        .unwrap();
+    let bob = service
+        .create_user("bob".to_string(), "battery staple", Role::Viewer)
+        .await
+        .unwrap();
+    let discord_id = DiscordId::new(123456789).unwrap();
+    assert_eq!(alice.discord_id(), None);
+
+    let alice = service
+        .set_discord_id(alice.id(), Some(discord_id))
+        .await
+        .unwrap();
+    assert_eq!(alice.discord_id(), Some(discord_id));
+    let linked = service.find_by_discord_id(discord_id).await.unwrap();
+    assert_eq!(linked, Some(alice.clone()));
+
+    let result = service.set_discord_id(bob.id(), Some(discord_id)).await;
+    assert!(matches!(
+        result,
+        Err(UserServiceError::DuplicateDiscordId(id)) if id == discord_id
+    ));
+
+    service.set_discord_id(alice.id(), None).await.unwrap();
+    let linked = service.find_by_discord_id(discord_id).await.unwrap();
+    assert_eq!(linked, None);
+}
+
+#[tokio::test]
+async fn can_manage_users_from_the_admin_page() {
+    let state = setup().await.expect("Failed to setup test context");
+    let app = create_user_router(Arc::new(UserState {
+        db: Arc::new(state.db.clone()),
+    }));
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/admin/users")
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(
+                    "username=alice&password=correct+horse&role=viewer",
+                ))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = body_text(response).await;
+    assert!(body.contains("alice"));
+    assert!(!body.contains("correct horse"));
+
+    let users = UserService::new(&state.db).get_all_users().await.unwrap();
+    assert_eq!(users.len(), 1);
+    assert_eq!(users[0].role(), Role::Viewer);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::PUT)
+                .uri(format!("/admin/users/{}/role", users[0].id()))
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from("role=editor"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let users = UserService::new(&state.db).get_all_users().await.unwrap();
+    assert_eq!(users[0].role(), Role::Editor);
+
+    let response = app
+        .clone()
+        .oneshot(
+            Request::builder()
+                .method(Method::POST)
+                .uri("/admin/users")
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from("username=bob&password=short&role=viewer"))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(response.headers()["hx-retarget"], "#user-error");
+
+    let response = app
+        .clone()
+        .oneshot(request(
+            Method::DELETE,
+            &format!("/admin/users/{}", users[0].id()),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(!body_text(response).await.contains("alice"));
+
+    let response = app
+        .oneshot(request(
+            Method::DELETE,
+            &format!("/admin/users/{}", users[0].id()),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -1,13 +1,10 @@
 use axum::Router;
-use axum::body::Body;
-use axum::http::Request;
 use insta::assert_yaml_snapshot;
 use nicknamer_server::web::{call_to_action_handler, health_check_handler, welcome_handler};
-use tower::ServiceExt;
 
 mod common;
 
-use common::HttpResponseSnapshot;
+use http_snapshot::TestRequest;
 
 /// Create a router for testing web endpoints.
 /// This function creates a minimal router with just the public routes needed for testing.
@@ -25,18 +22,9 @@ fn create_test_router() -> Router {
 async fn can_render_welcome_page() {
     let app = create_test_router();
 
-    let request = Request::builder().uri("/").body(Body::empty()).unwrap();
+    let response = TestRequest::get("/").send(app).await;
 
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
-
-    let snapshot = HttpResponseSnapshot::new(body_text, status, &headers, "welcome_page");
+    let snapshot = response.html_snapshot("welcome_page");
     assert_yaml_snapshot!(snapshot);
 }
 
@@ -44,26 +32,9 @@ async fn can_render_welcome_page() {
 async fn can_render_call_to_action_for_unauthenticated_user() {
     let app = create_test_router();
 
-    let request = Request::builder()
-        .uri("/call-to-action")
-        .body(Body::empty())
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
+    let response = TestRequest::get("/call-to-action").send(app).await;
 
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
-
-    let snapshot = HttpResponseSnapshot::new(
-        body_text,
-        status,
-        &headers,
-        "call_to_action_unauthenticated",
-    );
+    let snapshot = response.html_snapshot("call_to_action_unauthenticated");
     assert_yaml_snapshot!(snapshot);
 }
 
@@ -71,20 +42,8 @@ async fn can_render_call_to_action_for_unauthenticated_user() {
 async fn can_check_health_endpoint() {
     let app = create_test_router();
 
-    let request = Request::builder()
-        .uri("/health")
-        .body(Body::empty())
-        .unwrap();
-
-    let response = app.oneshot(request).await.unwrap();
-
-    let status = response.status();
-    let headers = response.headers().clone();
-    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
-        .await
-        .unwrap();
-    let body_text = std::str::from_utf8(&body).unwrap();
+    let response = TestRequest::get("/health").send(app).await;
 
-    let snapshot = HttpResponseSnapshot::new(body_text, status, &headers, "health_check");
+    let snapshot = response.html_snapshot("health_check");
     assert_yaml_snapshot!(snapshot);
 }
@@ -0,0 +1,260 @@
+use anyhow::{Context, ensure};
+use reqwest::{Client, StatusCode};
+use serde::Deserialize;
+
+/// Checks that `{base_url}/health` answers `OK`.
+pub async fn health(client: &Client, base_url: &str) -> anyhow::Result<()> {
+    let response = client.get(format!("{base_url}/health")).send().await?;
+    let status = response.status();
+    let body = response.text().await?;
+    ensure!(
+        status == StatusCode::OK && body.trim() == "OK",
+        "expected 200 OK, got {status}: {body}"
+    );
+    Ok(())
+}
+
+/// Checks that the page at `base_url` loads, for apps without a health endpoint.
+pub async fn page(client: &Client, base_url: &str) -> anyhow::Result<()> {
+    let response = client.get(base_url).send().await?;
+    ensure!(
+        response.status().is_success(),
+        "expected a page, got {}",
+        response.status()
+    );
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct NamesPage {
+    items: Vec<NameJson>,
+}
+
+#[derive(Deserialize)]
+struct NameJson {
+    id: u32,
+    name: String,
+}
+
+/// Creates, reads, renames and deletes a name in a throwaway server through nicknamer's JSON
+/// API, so that no real data is touched. `token` is an API token allowed to make changes.
+///
+/// The name is purged from the trash at the end, so that smoke tests don't leave names behind.
+pub async fn nicknamer_crud(client: &Client, base_url: &str, token: &str) -> anyhow::Result<()> {
+    let nicknamer = Nicknamer {
+        client,
+        base_url,
+        token,
+        server_id: format!("smoke-{}", uuid::Uuid::new_v4()),
+    };
+
+    // Keep the id below 2^63, the largest Discord ID nicknamer stores
+    let discord_id = uuid::Uuid::new_v4().as_u64_pair().0 >> 1;
+    let created = nicknamer
+        .send(
+            client
+                .post(nicknamer.url("/names"))
+                .json(&serde_json::json!({
+                    "discord_id": discord_id,
+                    "name": "Smoke Test",
+                    "server_id": nicknamer.server_id,
+                })),
+        )
+        .await
+        .context("create failed")?
+        .json::<NameJson>()
+        .await?;
+
+    let result = nicknamer.read_rename_and_delete(created.id).await;
+    if result.is_err() {
+        // Best effort, the error to report is the one that got us here
+        let _ = nicknamer.delete(created.id).await;
+    }
+    let purged = nicknamer
+        .send(client.delete(nicknamer.url(&format!("/names/trash/{}", created.id))))
+        .await
+        .context("purge failed");
+    result.and(purged.map(drop))
+}
+
+struct Nicknamer<'a> {
+    client: &'a Client,
+    base_url: &'a str,
+    token: &'a str,
+    server_id: String,
+}
+
+impl Nicknamer<'_> {
+    async fn read_rename_and_delete(&self, id: u32) -> anyhow::Result<()> {
+        ensure!(
+            self.names().await?.iter().any(|name| name.id == id),
+            "created name is missing"
+        );
+
+        let renamed =
+            self.send(self.client.put(self.url(&format!("/names/{id}"))).json(
+                &serde_json::json!({
+                    "name": "Smoke Test Renamed",
+                    "server_id": self.server_id,
+                }),
+            ))
+            .await
+            .context("update failed")?
+            .json::<NameJson>()
+            .await?;
+        ensure!(
+            renamed.name == "Smoke Test Renamed",
+            "update returned '{}'",
+            renamed.name
+        );
+        ensure!(
+            self.names()
+                .await?
+                .iter()
+                .any(|name| name.name == "Smoke Test Renamed"),
+            "updated name is missing"
+        );
+
+        self.delete(id).await.context("delete failed")?;
+        ensure!(
+            self.names().await?.is_empty(),
+            "deleted name is still listed"
+        );
+        Ok(())
+    }
+
+    /// The names in the throwaway server.
+    async fn names(&self) -> anyhow::Result<Vec<NameJson>> {
+        let page = self
+            .send(
+                self.client
+                    .get(self.url("/names"))
+                    .query(&[("server_id", &self.server_id)]),
+            )
+            .await
+            .context("listing names failed")?
+            .json::<NamesPage>()
+            .await?;
+        Ok(page.items)
+    }
+
+    /// Moves the name to the trash.
+    async fn delete(&self, id: u32) -> anyhow::Result<()> {
+        self.send(self.client.delete(self.url(&format!("/names/{id}"))))
+            .await?;
+        Ok(())
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/api/v1{path}", self.base_url)
+    }
+
+    /// Sends `request` with the API token, failing unless it succeeds.
+    async fn send(&self, request: reqwest::RequestBuilder) -> anyhow::Result<reqwest::Response> {
+        let response = request.bearer_auth(self.token).send().await?;
+        // Redirects mean the token wasn't accepted, so they count as failures too
+        ensure!(response.status().is_success(), "got {}", response.status());
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::extract::{Request, State};
+    use axum::http::HeaderValue;
+    use axum::middleware::{self, Next};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::{delete, get, put};
+    use axum::{Json, Router};
+    use serde_json::{Value, json};
+    use std::sync::{Arc, Mutex};
+
+    async fn serve(app: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{addr}")
+    }
+
+    #[tokio::test]
+    async fn checks_health_endpoints() {
+        let healthy = serve(Router::new().route("/health", get(|| async { "OK" }))).await;
+        let broken = serve(Router::new()).await;
+        let client = Client::new();
+
+        assert!(health(&client, &healthy).await.is_ok());
+        let err = health(&client, &broken).await.unwrap_err();
+        assert!(err.to_string().contains("404"), "{err}");
+        assert!(page(&client, &broken).await.is_err());
+    }
+
+    /// The one name a fake nicknamer API holds.
+    #[derive(Default)]
+    struct FakeName {
+        name: Option<String>,
+        trashed: bool,
+    }
+
+    type Fake = Arc<Mutex<FakeName>>;
+
+    fn fake_nicknamer(fake: Fake) -> Router {
+        async fn create(State(fake): State<Fake>, Json(body): Json<Value>) -> Response {
+            let name = body["name"].as_str().unwrap().to_string();
+            fake.lock().unwrap().name = Some(name.clone());
+            (StatusCode::CREATED, Json(json!({ "id": 1, "name": name }))).into_response()
+        }
+        async fn list(State(fake): State<Fake>) -> Json<Value> {
+            let fake = fake.lock().unwrap();
+            let items: Vec<Value> = match &fake.name {
+                Some(name) if !fake.trashed => vec![json!({ "id": 1, "name": name })],
+                _ => Vec::new(),
+            };
+            Json(json!({ "items": items }))
+        }
+        async fn rename(State(fake): State<Fake>, Json(body): Json<Value>) -> Json<Value> {
+            let name = body["name"].as_str().unwrap().to_string();
+            fake.lock().unwrap().name = Some(name.clone());
+            Json(json!({ "id": 1, "name": name }))
+        }
+        async fn trash(State(fake): State<Fake>) -> StatusCode {
+            fake.lock().unwrap().trashed = true;
+            StatusCode::NO_CONTENT
+        }
+        async fn purge(State(fake): State<Fake>) -> StatusCode {
+            let mut fake = fake.lock().unwrap();
+            if !fake.trashed {
+                return StatusCode::NOT_FOUND;
+            }
+            fake.name = None;
+            StatusCode::NO_CONTENT
+        }
+        async fn require_token(request: Request, next: Next) -> Response {
+            let expected = HeaderValue::from_static("Bearer nn_smoke");
+            if request.headers().get("authorization") != Some(&expected) {
+                return StatusCode::UNAUTHORIZED.into_response();
+            }
+            next.run(request).await
+        }
+
+        Router::new()
+            .route("/api/v1/names", get(list).post(create))
+            .route("/api/v1/names/{id}", put(rename).delete(trash))
+            .route("/api/v1/names/trash/{id}", delete(purge))
+            .layer(middleware::from_fn(require_token))
+            .with_state(fake)
+    }
+
+    #[tokio::test]
+    async fn runs_the_names_crud_cycle_through_the_api_and_purges_the_name() {
+        let fake = Fake::default();
+        let url = serve(fake_nicknamer(fake.clone())).await;
+        let client = Client::new();
+
+        nicknamer_crud(&client, &url, "nn_smoke").await.unwrap();
+        assert_eq!(fake.lock().unwrap().name, None);
+
+        let err = nicknamer_crud(&client, &url, "nn_wrong").await.unwrap_err();
+        assert!(err.to_string().contains("create failed"), "{err}");
+    }
+}
@@ -0,0 +1,98 @@
+//! Post-deploy smoke test: checks that every deployed service is up and that nicknamer can
+//! store names, printing a pass/fail line per check and exiting with an error if any failed.
+//!
+//! Services are configured with `SMOKE_*` environment variables, see `smoke --help`. Services
+//! without a URL are skipped.
+
+mod checks;
+mod report;
+
+use clap::Parser;
+use report::{Outcome, Report};
+use std::process::ExitCode;
+use std::time::Duration;
+
+#[derive(Parser, Debug)]
+struct Cli {
+    /// Base URL of the nicknamer server
+    #[arg(long, env = "SMOKE_NICKNAMER_URL")]
+    nicknamer_url: Option<String>,
+    /// Read-write API token for the nicknamer API, enables the names CRUD check
+    #[arg(long, env = "SMOKE_NICKNAMER_TOKEN", hide_env_values = true)]
+    nicknamer_token: Option<String>,
+    /// Base URL of the nicknamer bot's health server
+    #[arg(long, env = "SMOKE_NICKNAMER_BOT_URL")]
+    nicknamer_bot_url: Option<String>,
+    /// Base URL of the hot_dog app
+    #[arg(long, env = "SMOKE_HOT_DOG_URL")]
+    hot_dog_url: Option<String>,
+    /// Base URL of the guess_the_word app
+    #[arg(long, env = "SMOKE_GUESS_THE_WORD_URL")]
+    guess_the_word_url: Option<String>,
+    /// Seconds to wait for each request
+    #[arg(long, env = "SMOKE_TIMEOUT", default_value_t = 10)]
+    timeout: u64,
+}
+
+const NO_URL: &str = "no URL configured";
+
+#[tokio::main]
+async fn main() -> anyhow::Result<ExitCode> {
+    let cli = Cli::parse();
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(cli.timeout))
+        .redirect(reqwest::redirect::Policy::none())
+        .build()?;
+    let mut report = Report::default();
+
+    match cli.nicknamer_url.as_deref().map(base_url) {
+        Some(url) => {
+            let health = outcome(checks::health(&client, url).await);
+            report.record("nicknamer", "health", health);
+            let crud = match &cli.nicknamer_token {
+                Some(token) => outcome(checks::nicknamer_crud(&client, url, token).await),
+                None => Outcome::Skip("no API token configured"),
+            };
+            report.record("nicknamer", "names CRUD", crud);
+        }
+        None => {
+            report.record("nicknamer", "health", Outcome::Skip(NO_URL));
+            report.record("nicknamer", "names CRUD", Outcome::Skip(NO_URL));
+        }
+    }
+
+    let bot = match cli.nicknamer_bot_url.as_deref().map(base_url) {
+        Some(url) => outcome(checks::health(&client, url).await),
+        None => Outcome::Skip(NO_URL),
+    };
+    report.record("nicknamer bot", "health", bot);
+
+    for (service, url) in [
+        ("hot_dog", &cli.hot_dog_url),
+        ("guess_the_word", &cli.guess_the_word_url),
+    ] {
+        let page = match url.as_deref().map(base_url) {
+            Some(url) => outcome(checks::page(&client, url).await),
+            None => Outcome::Skip(NO_URL),
+        };
+        report.record(service, "home page", page);
+    }
+
+    println!("{report}");
+    Ok(if report.passed() {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    })
+}
+
+fn base_url(url: &str) -> &str {
+    url.trim_end_matches('/')
+}
+
+fn outcome(result: anyhow::Result<()>) -> Outcome {
+    match result {
+        Ok(()) => Outcome::Pass,
+        Err(err) => Outcome::Fail(err),
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt;
+
+/// Outcome of a single check.
+#[derive(Debug)]
+pub enum Outcome {
+    Pass,
+    Fail(anyhow::Error),
+    /// The check wasn't configured, e.g. the service's URL is missing.
+    Skip(&'static str),
+}
+
+/// Outcomes of every check, grouped by service in the order they ran.
+#[derive(Debug, Default)]
+pub struct Report {
+    results: Vec<(&'static str, &'static str, Outcome)>,
+}
+
+impl Report {
+    pub fn record(&mut self, service: &'static str, check: &'static str, outcome: Outcome) {
+        self.results.push((service, check, outcome));
+    }
+
+    /// Whether no check failed. Skipped checks don't count as failures.
+    pub fn passed(&self) -> bool {
+        !self
+            .results
+            .iter()
+            .any(|(_, _, outcome)| matches!(outcome, Outcome::Fail(_)))
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (service, check, outcome) in &self.results {
+            match outcome {
+                Outcome::Pass => writeln!(f, "PASS {service}: {check}")?,
+                Outcome::Fail(err) => writeln!(f, "FAIL {service}: {check}: {err:#}")?,
+                Outcome::Skip(reason) => writeln!(f, "SKIP {service}: {check} ({reason})")?,
+            }
+        }
+        let failed = self
+            .results
+            .iter()
+            .filter(|(_, _, outcome)| matches!(outcome, Outcome::Fail(_)))
+            .count();
+        write!(f, "{} checks, {} failed", self.results.len(), failed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_every_check_and_fails_on_any_failure() {
+        let mut report = Report::default();
+        report.record("nicknamer", "health", Outcome::Pass);
+        report.record("hot_dog", "health", Outcome::Skip("no URL"));
+        assert!(report.passed());
+
+        report.record(
+            "nicknamer",
+            "names CRUD",
+            Outcome::Fail(anyhow::anyhow!("login failed")),
+        );
+
+        assert!(!report.passed());
+        assert_eq!(
+            report.to_string(),
+            "PASS nicknamer: health\n\
+             SKIP hot_dog: health (no URL)\n\
+             FAIL nicknamer: names CRUD: login failed\n\
+             3 checks, 1 failed"
+        );
+    }
+}
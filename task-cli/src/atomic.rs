@@ -0,0 +1,217 @@
+use crate::TaskError;
+use fs4::FileExt;
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// `path` with `suffix` added to its file name, `tasks.json.bak` for `tasks.json`.
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name: OsString = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// The backup [`replace`] keeps of `path` when asked to.
+pub(crate) fn backup_path(path: &Path) -> PathBuf {
+    with_suffix(path, ".bak")
+}
+
+/// Takes an exclusive advisory lock on `path.lock`, held until the returned file is dropped.
+///
+/// `path` itself can't hold the lock, [`replace`] swaps it for a new file on every save. The lock
+/// only keeps out other processes that take it too.
+pub(crate) fn lock(path: &Path) -> Result<File, TaskError> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let file = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(with_suffix(path, ".lock"))?;
+    // Called through the trait, `File::lock` itself needs a newer toolchain than the pinned one
+    FileExt::lock(&file)?;
+    Ok(file)
+}
+
+/// Replaces the file at `path` with what `write` writes, without ever leaving a half-written
+/// file there.
+///
+/// The contents are written and flushed to disk in a temporary file next to `path`, then renamed
+/// over it, so a crash leaves either the old file or the new one. With `backup`, the old file is
+/// first copied to `path.bak`, replacing the previous backup.
+pub(crate) fn replace(
+    path: &Path,
+    backup: bool,
+    write: impl FnOnce(&mut dyn Write) -> Result<(), TaskError>,
+) -> Result<(), TaskError> {
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty());
+    if let Some(dir) = dir {
+        fs::create_dir_all(dir)?;
+    }
+
+    // Named after the process, so that concurrent commands don't write to the same file
+    let temp_path = with_suffix(path, &format!(".{}.tmp", std::process::id()));
+    let written = write_and_sync(&temp_path, write);
+    if let Err(error) = written {
+        let _ = fs::remove_file(&temp_path);
+        return Err(error);
+    }
+
+    if backup && path.exists() {
+        // Also renamed into place, so a crash while copying can't leave a broken backup
+        let backup_path = backup_path(path);
+        let temp_backup_path = with_suffix(&backup_path, ".tmp");
+        fs::copy(path, &temp_backup_path)?;
+        fs::rename(&temp_backup_path, &backup_path)?;
+    }
+
+    if let Err(error) = fs::rename(&temp_path, path) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(error.into());
+    }
+    sync_dir(dir.unwrap_or(Path::new(".")))
+}
+
+fn write_and_sync(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> Result<(), TaskError>,
+) -> Result<(), TaskError> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write(&mut writer)?;
+    let file = writer.into_inner().map_err(|error| error.into_error())?;
+    file.sync_all()?;
+    Ok(())
+}
+
+/// Makes the rename durable, it's only recorded in the directory.
+#[cfg(unix)]
+fn sync_dir(dir: &Path) -> Result<(), TaskError> {
+    File::open(dir)?.sync_all()?;
+    Ok(())
+}
+
+/// Directories can't be opened as files on Windows, where renames are made durable by the file
+/// system itself.
+#[cfg(not(unix))]
+fn sync_dir(_dir: &Path) -> Result<(), TaskError> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TaskRepository;
+    use std::io;
+
+    /// Stands in for a crash halfway through a save: writes the first half of `contents`, then
+    /// fails.
+    fn partial_write(contents: &str) -> impl FnOnce(&mut dyn Write) -> Result<(), TaskError> {
+        move |writer| {
+            writer.write_all(&contents.as_bytes()[..contents.len() / 2])?;
+            Err(io::Error::other("disk unplugged").into())
+        }
+    }
+
+    fn leftover_files(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_save_atomic_round_trip() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("tasks.json");
+        let mut tasks = TaskRepository::new();
+        let id = tasks.add_task("Task".to_string());
+
+        // Act
+        tasks.save_atomic(&path).unwrap();
+
+        // Assert
+        let loaded = TaskRepository::new_from_json(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(loaded.get_task(id), tasks.get_task(id));
+        assert_eq!(leftover_files(path.parent().unwrap()), ["tasks.json"]);
+    }
+
+    #[test]
+    fn test_partial_write_keeps_old_file() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.json");
+        let mut tasks = TaskRepository::new();
+        tasks.add_task("Saved".to_string());
+        tasks.save_atomic(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        tasks.add_task("Lost in the crash".to_string());
+        let mut unsaved = Vec::new();
+        tasks.save_as_json(&mut unsaved).unwrap();
+
+        // Act
+        let result = replace(
+            &path,
+            true,
+            partial_write(&String::from_utf8(unsaved).unwrap()),
+        );
+
+        // Assert
+        assert!(matches!(result, Err(TaskError::Io(_))));
+        assert_eq!(fs::read_to_string(&path).unwrap(), saved);
+        // Neither the temporary file nor a backup of an unchanged file is left behind
+        assert_eq!(leftover_files(dir.path()), ["tasks.json"]);
+    }
+
+    #[test]
+    fn test_partial_write_of_new_file_leaves_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.json");
+
+        let result = replace(&path, false, partial_write(r#"{"tasks":{}}"#));
+
+        assert!(result.is_err());
+        assert!(leftover_files(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn test_lock_is_exclusive_until_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.json");
+
+        let held = lock(&path).unwrap();
+        let other = File::open(with_suffix(&path, ".lock")).unwrap();
+
+        assert!(FileExt::try_lock(&other).is_err());
+        drop(held);
+        assert!(FileExt::try_lock(&other).is_ok());
+    }
+
+    #[test]
+    fn test_backup_keeps_previous_save() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.json");
+        let mut tasks = TaskRepository::new();
+        tasks.add_task("First".to_string());
+        tasks.save_atomic_with_backup(&path).unwrap();
+        assert!(!backup_path(&path).exists());
+        let first = fs::read_to_string(&path).unwrap();
+
+        // Act
+        tasks.add_task("Second".to_string());
+        tasks.save_atomic_with_backup(&path).unwrap();
+        let second = fs::read_to_string(&path).unwrap();
+        tasks.add_task("Third".to_string());
+        tasks.save_atomic_with_backup(&path).unwrap();
+
+        // Assert
+        assert_ne!(first, second);
+        assert_eq!(fs::read_to_string(backup_path(&path)).unwrap(), second);
+        assert_eq!(leftover_files(dir.path()), ["tasks.json", "tasks.json.bak"]);
+    }
+}
@@ -0,0 +1,210 @@
+use crate::{TaskError, TaskRepository};
+use std::collections::HashSet;
+use std::str::FromStr;
+
+/// IDs given on the command line, either one ID or an inclusive range like `5-9`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct IdRange {
+    pub first: u32,
+    pub last: u32,
+}
+
+impl IdRange {
+    pub fn ids(self) -> impl Iterator<Item = u32> {
+        self.first..=self.last
+    }
+
+    /// Every ID in `ranges`, in order, without duplicates.
+    pub fn flatten(ranges: &[IdRange]) -> Vec<u32> {
+        let mut seen = HashSet::new();
+        ranges
+            .iter()
+            .flat_map(|range| range.ids())
+            .filter(|id| seen.insert(*id))
+            .collect()
+    }
+}
+
+impl FromStr for IdRange {
+    type Err = TaskError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let invalid = || TaskError::InvalidIdRange(input.to_string());
+        let parse = |id: &str| id.trim().parse::<u32>().map_err(|_| invalid());
+        let (first, last) = match input.split_once('-') {
+            Some((first, last)) => (parse(first)?, parse(last)?),
+            None => {
+                let id = parse(input)?;
+                (id, id)
+            }
+        };
+        if first > last {
+            return Err(invalid());
+        }
+        Ok(Self { first, last })
+    }
+}
+
+/// A change [`TaskRepository::apply_bulk`] can make to many tasks at once.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum BulkOperation {
+    MarkInProgress,
+    MarkDone,
+    Delete,
+}
+
+/// What [`TaskRepository::apply_bulk`] did to each task.
+#[derive(Debug, Default)]
+pub struct BulkResult {
+    /// The tasks changed, in the order they were changed.
+    pub succeeded: Vec<u32>,
+    /// The tasks that couldn't be changed, and why.
+    pub failed: Vec<(u32, TaskError)>,
+    /// The next occurrences of repeating tasks marked done.
+    pub next_occurrences: Vec<u32>,
+}
+
+impl BulkResult {
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+impl TaskRepository {
+    /// Applies `operation` to every task in `ids`, carrying on past the ones that fail.
+    ///
+    /// Subtasks and dependencies are marked done before the tasks waiting on them, so that they
+    /// can be finished together. Subtasks already deleted along with their parent count as
+    /// deleted.
+    pub fn apply_bulk(&mut self, ids: &[u32], operation: BulkOperation) -> BulkResult {
+        let ids = match operation {
+            BulkOperation::MarkDone => self.completion_order(ids).unwrap_or_else(|| ids.to_vec()),
+            _ => ids.to_vec(),
+        };
+        let existing: HashSet<u32> = ids
+            .iter()
+            .copied()
+            .filter(|id| self.tasks.contains_key(id))
+            .collect();
+
+        let mut result = BulkResult::default();
+        for id in ids {
+            let outcome = match operation {
+                BulkOperation::MarkInProgress => self.mark_in_progress(id),
+                BulkOperation::MarkDone => self.mark_done(id).map(|next| {
+                    result.next_occurrences.extend(next);
+                }),
+                BulkOperation::Delete => match self.delete_task(id) {
+                    Err(TaskError::NotFound(_)) if existing.contains(&id) => Ok(()),
+                    outcome => outcome,
+                },
+            };
+            match outcome {
+                Ok(()) => result.succeeded.push(id),
+                Err(error) => result.failed.push((id, error)),
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Status;
+
+    #[test]
+    fn test_parse_id_ranges() {
+        assert_eq!(
+            "5-9".parse::<IdRange>().unwrap(),
+            IdRange { first: 5, last: 9 }
+        );
+        assert_eq!(
+            "3".parse::<IdRange>().unwrap(),
+            IdRange { first: 3, last: 3 }
+        );
+        for input in ["", "9-5", "a-b", "1-", "-3", "1-2-3"] {
+            assert!(
+                matches!(input.parse::<IdRange>(), Err(TaskError::InvalidIdRange(_))),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_flatten_keeps_order_without_duplicates() {
+        let ranges: Vec<IdRange> = ["4", "1-3", "2", "6-7"]
+            .iter()
+            .map(|range| range.parse().unwrap())
+            .collect();
+
+        assert_eq!(IdRange::flatten(&ranges), [4, 1, 2, 3, 6, 7]);
+    }
+
+    #[test]
+    fn test_apply_bulk_reports_each_task() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let first = repo.add_task("First".to_string());
+        let parent = repo.add_task("Parent".to_string());
+        repo.add_subtask(parent, "Open subtask".to_string())
+            .unwrap();
+
+        // Act
+        let result = repo.apply_bulk(&[first, parent, 42], BulkOperation::MarkDone);
+
+        // Assert
+        assert!(!result.is_success());
+        assert_eq!(result.succeeded, [first]);
+        assert!(matches!(
+            result.failed[..],
+            [
+                (2, TaskError::OpenSubtasks(2)),
+                (42, TaskError::NotFound(42))
+            ]
+        ));
+        assert_eq!(repo.get_task(first).unwrap().status, Status::Done);
+    }
+
+    #[test]
+    fn test_apply_bulk_marks_subtasks_done_before_parents() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let parent = repo.add_task("Parent".to_string());
+        let child = repo.add_subtask(parent, "Child".to_string()).unwrap();
+        let grandchild = repo.add_subtask(child, "Grandchild".to_string()).unwrap();
+
+        // Act
+        let result = repo.apply_bulk(&[parent, child, grandchild], BulkOperation::MarkDone);
+
+        // Assert
+        assert!(result.is_success());
+        assert_eq!(result.succeeded, [grandchild, child, parent]);
+        assert_eq!(repo.get_tasks_with_status(Status::Done).len(), 3);
+    }
+
+    #[test]
+    fn test_apply_bulk_delete_counts_subtasks_deleted_with_parent() {
+        let mut repo = TaskRepository::new();
+        let parent = repo.add_task("Parent".to_string());
+        let child = repo.add_subtask(parent, "Child".to_string()).unwrap();
+
+        let result = repo.apply_bulk(&[parent, child, 3], BulkOperation::Delete);
+
+        assert_eq!(result.succeeded, [parent, child]);
+        assert!(matches!(result.failed[..], [(3, TaskError::NotFound(3))]));
+    }
+
+    #[test]
+    fn test_apply_bulk_collects_next_occurrences() {
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Standup".to_string());
+        repo.set_recurrence(id, Some("daily".parse().unwrap()))
+            .unwrap();
+
+        let result = repo.apply_bulk(&[id], BulkOperation::MarkDone);
+
+        assert_eq!(result.next_occurrences, [2]);
+    }
+}
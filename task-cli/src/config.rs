@@ -0,0 +1,353 @@
+use crate::{DEFAULT_PROJECT, TaskError, normalize_project};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Environment variable with the store to use, like `--store`.
+pub const STORE_ENV: &str = "TASK_CLI_STORE";
+/// Environment variable with the JSON file to use, like `--file`.
+pub const FILE_ENV: &str = "TASK_CLI_FILE";
+/// Environment variable with the current project, like `--project`.
+pub const PROJECT_ENV: &str = "TASK_CLI_PROJECT";
+
+/// The file the CLI used before it had a config, still used when it exists.
+const LEGACY_FILE: &str = "tasks.json";
+
+/// Defaults set with `task-cli config set`, saved as JSON in the config directory.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Config {
+    /// Store used when neither a flag nor an environment variable picks one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub store: Option<String>,
+    /// Project new tasks go in and `list` shows, unless a flag or an environment variable picks
+    /// one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+}
+
+impl Config {
+    /// Loads the config at `path`, empty if the file doesn't exist yet.
+    pub fn load(path: &Path) -> Result<Self, TaskError> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), TaskError> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Sets `store`, `file` as a shorthand for a JSON store, or `project`.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), TaskError> {
+        match key {
+            "store" => self.store = Some(value.to_string()),
+            "file" => self.store = Some(format!("json:{}", value)),
+            "project" => self.project = Some(normalize_project(value)?),
+            _ => return Err(TaskError::UnknownConfigKey(key.to_string())),
+        }
+        Ok(())
+    }
+
+    pub fn unset(&mut self, key: &str) -> Result<(), TaskError> {
+        match key {
+            "store" | "file" => self.store = None,
+            "project" => self.project = None,
+            _ => return Err(TaskError::UnknownConfigKey(key.to_string())),
+        }
+        Ok(())
+    }
+}
+
+/// The current project: the one given by `flag`, else by [`PROJECT_ENV`], else by the config,
+/// else [`DEFAULT_PROJECT`].
+pub fn current_project(
+    flag: Option<&str>,
+    env: Option<&str>,
+    config: &Config,
+) -> Result<String, TaskError> {
+    let project = flag
+        .or(env.filter(|env| !env.is_empty()))
+        .or(config.project.as_deref())
+        .unwrap_or(DEFAULT_PROJECT);
+    normalize_project(project)
+}
+
+/// Where the CLI keeps its config and, by default, its tasks, following the XDG base directory
+/// spec.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dirs {
+    /// `$XDG_CONFIG_HOME/task-cli`, or `~/.config/task-cli`.
+    pub config_dir: PathBuf,
+    /// `$XDG_DATA_HOME/task-cli`, or `~/.local/share/task-cli`.
+    pub data_dir: PathBuf,
+}
+
+impl Dirs {
+    /// `None` without a home directory, unless both XDG variables are set.
+    pub fn from_env() -> Option<Self> {
+        Self::from_vars(|name| std::env::var_os(name).map(PathBuf::from))
+    }
+
+    /// Works out the directories from the environment variables returned by `var`.
+    pub fn from_vars(var: impl Fn(&str) -> Option<PathBuf>) -> Option<Self> {
+        // The spec says relative paths are invalid and should be ignored
+        let absolute = |name| var(name).filter(|path: &PathBuf| path.is_absolute());
+        let config_home =
+            absolute("XDG_CONFIG_HOME").or_else(|| Some(absolute("HOME")?.join(".config")))?;
+        let data_home = absolute("XDG_DATA_HOME")
+            .or_else(|| Some(absolute("HOME")?.join(".local").join("share")))?;
+        Some(Self {
+            config_dir: config_home.join("task-cli"),
+            data_dir: data_home.join("task-cli"),
+        })
+    }
+
+    pub fn config_file(&self) -> PathBuf {
+        self.config_dir.join("config.json")
+    }
+
+    pub fn default_store(&self) -> String {
+        format!("json:{}", self.data_dir.join("tasks.json").display())
+    }
+}
+
+/// What picked the store, most important first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoreSource {
+    StoreFlag,
+    FileFlag,
+    StoreEnv,
+    FileEnv,
+    ConfigFile,
+    /// A `tasks.json` left in the working directory by older versions.
+    LegacyFile,
+    /// Nothing did, the tasks are in the data directory.
+    Default,
+}
+
+impl Display for StoreSource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StoreSource::StoreFlag => write!(f, "--store"),
+            StoreSource::FileFlag => write!(f, "--file"),
+            StoreSource::StoreEnv => write!(f, "{}", STORE_ENV),
+            StoreSource::FileEnv => write!(f, "{}", FILE_ENV),
+            StoreSource::ConfigFile => write!(f, "config file"),
+            StoreSource::LegacyFile => write!(f, "{} in the current directory", LEGACY_FILE),
+            StoreSource::Default => write!(f, "default"),
+        }
+    }
+}
+
+/// Everything that can pick the store the CLI uses.
+#[derive(Debug, Default, Clone)]
+pub struct StoreSources {
+    pub store_flag: Option<String>,
+    pub file_flag: Option<PathBuf>,
+    pub store_env: Option<String>,
+    pub file_env: Option<PathBuf>,
+    pub config: Config,
+    pub legacy_file_exists: bool,
+}
+
+impl StoreSources {
+    /// Reads the environment variables and the working directory, on top of the given flags and
+    /// config.
+    pub fn from_env(
+        store_flag: Option<String>,
+        file_flag: Option<PathBuf>,
+        config: Config,
+    ) -> Self {
+        let non_empty = |name| std::env::var(name).ok().filter(|value| !value.is_empty());
+        Self {
+            store_flag,
+            file_flag,
+            store_env: non_empty(STORE_ENV),
+            file_env: non_empty(FILE_ENV).map(PathBuf::from),
+            config,
+            legacy_file_exists: Path::new(LEGACY_FILE).exists(),
+        }
+    }
+
+    /// The store to use, as a spec for [`open_store`](crate::open_store), and what picked it.
+    ///
+    /// Only fails when nothing picked a store and there are no `dirs` for the default one.
+    pub fn resolve(&self, dirs: Option<&Dirs>) -> Result<(String, StoreSource), TaskError> {
+        let json = |path: &PathBuf| format!("json:{}", path.display());
+        let resolved = if let Some(store) = &self.store_flag {
+            (store.clone(), StoreSource::StoreFlag)
+        } else if let Some(file) = &self.file_flag {
+            (json(file), StoreSource::FileFlag)
+        } else if let Some(store) = &self.store_env {
+            (store.clone(), StoreSource::StoreEnv)
+        } else if let Some(file) = &self.file_env {
+            (json(file), StoreSource::FileEnv)
+        } else if let Some(store) = &self.config.store {
+            (store.clone(), StoreSource::ConfigFile)
+        } else if self.legacy_file_exists {
+            (format!("json:{}", LEGACY_FILE), StoreSource::LegacyFile)
+        } else {
+            let dirs = dirs.ok_or(TaskError::NoHomeDirectory)?;
+            (dirs.default_store(), StoreSource::Default)
+        };
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dirs() -> Dirs {
+        Dirs::from_vars(|name| (name == "HOME").then(|| PathBuf::from("/home/me"))).unwrap()
+    }
+
+    #[test]
+    fn test_dirs_default_to_home() {
+        assert_eq!(
+            dirs(),
+            Dirs {
+                config_dir: PathBuf::from("/home/me/.config/task-cli"),
+                data_dir: PathBuf::from("/home/me/.local/share/task-cli"),
+            }
+        );
+        assert_eq!(
+            dirs().default_store(),
+            "json:/home/me/.local/share/task-cli/tasks.json"
+        );
+    }
+
+    #[test]
+    fn test_dirs_use_absolute_xdg_variables() {
+        let dirs = Dirs::from_vars(|name| match name {
+            "XDG_CONFIG_HOME" => Some(PathBuf::from("/xdg/config")),
+            // Relative, so ignored
+            "XDG_DATA_HOME" => Some(PathBuf::from("data")),
+            "HOME" => Some(PathBuf::from("/home/me")),
+            _ => None,
+        })
+        .unwrap();
+
+        assert_eq!(
+            dirs.config_file(),
+            PathBuf::from("/xdg/config/task-cli/config.json")
+        );
+        assert_eq!(
+            dirs.data_dir,
+            PathBuf::from("/home/me/.local/share/task-cli")
+        );
+    }
+
+    #[test]
+    fn test_dirs_need_a_home() {
+        assert_eq!(Dirs::from_vars(|_| None), None);
+        assert!(matches!(
+            StoreSources::default().resolve(None),
+            Err(TaskError::NoHomeDirectory)
+        ));
+    }
+
+    #[test]
+    fn test_resolve_prefers_flags_then_env_then_config() {
+        // Arrange
+        let mut sources = StoreSources {
+            store_flag: Some("sqlite:flag.db".to_string()),
+            file_flag: Some(PathBuf::from("flag.json")),
+            store_env: Some("sqlite:env.db".to_string()),
+            file_env: Some(PathBuf::from("env.json")),
+            config: Config {
+                store: Some("sqlite:config.db".to_string()),
+                project: None,
+            },
+            legacy_file_exists: true,
+        };
+        let mut resolved = Vec::new();
+
+        // Act
+        resolved.push(sources.resolve(Some(&dirs())).unwrap());
+        sources.store_flag = None;
+        resolved.push(sources.resolve(Some(&dirs())).unwrap());
+        sources.file_flag = None;
+        resolved.push(sources.resolve(Some(&dirs())).unwrap());
+        sources.store_env = None;
+        resolved.push(sources.resolve(Some(&dirs())).unwrap());
+        sources.file_env = None;
+        resolved.push(sources.resolve(Some(&dirs())).unwrap());
+        sources.config.store = None;
+        resolved.push(sources.resolve(Some(&dirs())).unwrap());
+        sources.legacy_file_exists = false;
+        resolved.push(sources.resolve(Some(&dirs())).unwrap());
+
+        // Assert
+        let expected = [
+            ("sqlite:flag.db", StoreSource::StoreFlag),
+            ("json:flag.json", StoreSource::FileFlag),
+            ("sqlite:env.db", StoreSource::StoreEnv),
+            ("json:env.json", StoreSource::FileEnv),
+            ("sqlite:config.db", StoreSource::ConfigFile),
+            ("json:tasks.json", StoreSource::LegacyFile),
+            (
+                "json:/home/me/.local/share/task-cli/tasks.json",
+                StoreSource::Default,
+            ),
+        ];
+        let expected: Vec<(String, StoreSource)> = expected
+            .into_iter()
+            .map(|(store, source)| (store.to_string(), source))
+            .collect();
+        assert_eq!(resolved, expected);
+    }
+
+    #[test]
+    fn test_config_set_unset_and_round_trip() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("task-cli").join("config.json");
+        let mut config = Config::load(&path).unwrap();
+        assert_eq!(config, Config::default());
+
+        // Act
+        config.set("file", "/data/tasks.json").unwrap();
+        config.save(&path).unwrap();
+        let mut loaded = Config::load(&path).unwrap();
+
+        // Assert
+        assert_eq!(loaded.store.as_deref(), Some("json:/data/tasks.json"));
+        loaded.unset("store").unwrap();
+        assert_eq!(loaded, Config::default());
+        assert!(matches!(
+            loaded.set("colour", "red"),
+            Err(TaskError::UnknownConfigKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_current_project_prefers_flag_then_env_then_config() {
+        let mut config = Config::default();
+        assert_eq!(
+            current_project(None, None, &config).unwrap(),
+            DEFAULT_PROJECT
+        );
+
+        config.set("project", "Home").unwrap();
+        assert_eq!(current_project(None, Some(""), &config).unwrap(), "home");
+        assert_eq!(
+            current_project(None, Some("work"), &config).unwrap(),
+            "work"
+        );
+        assert_eq!(
+            current_project(Some("Website"), Some("work"), &config).unwrap(),
+            "website"
+        );
+        assert!(matches!(
+            current_project(Some(" "), None, &config),
+            Err(TaskError::EmptyProject)
+        ));
+    }
+}
@@ -0,0 +1,296 @@
+use crate::{Status, Task, TaskError, TaskRepository};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, BinaryHeap, HashSet};
+
+/// Orders `nodes` so that each comes after the nodes `edges` says it depends on, by smallest ID
+/// first where that leaves a choice. Edges to nodes outside `nodes` are ignored.
+///
+/// Fails with the nodes that are part of, or depend on, a cycle.
+pub(crate) fn topological_sort(
+    nodes: &BTreeSet<u32>,
+    edges: impl Fn(u32) -> Vec<u32>,
+) -> Result<Vec<u32>, Vec<u32>> {
+    // How many dependencies of each node are still to be ordered, and who depends on it
+    let mut waiting_on: BTreeMap<u32, usize> = nodes.iter().map(|node| (*node, 0)).collect();
+    let mut dependents: BTreeMap<u32, Vec<u32>> = BTreeMap::new();
+    for &node in nodes {
+        let dependencies: BTreeSet<u32> = edges(node)
+            .into_iter()
+            .filter(|dependency| nodes.contains(dependency))
+            .collect();
+        for dependency in dependencies {
+            *waiting_on.get_mut(&node).expect("node was counted") += 1;
+            dependents.entry(dependency).or_default().push(node);
+        }
+    }
+
+    let mut ready: BinaryHeap<Reverse<u32>> = waiting_on
+        .iter()
+        .filter(|(_, waiting)| **waiting == 0)
+        .map(|(node, _)| Reverse(*node))
+        .collect();
+    let mut order = Vec::with_capacity(nodes.len());
+    while let Some(Reverse(node)) = ready.pop() {
+        order.push(node);
+        for dependent in dependents.remove(&node).unwrap_or_default() {
+            let waiting = waiting_on.get_mut(&dependent).expect("node was counted");
+            *waiting -= 1;
+            if *waiting == 0 {
+                ready.push(Reverse(dependent));
+            }
+        }
+    }
+
+    if order.len() == nodes.len() {
+        Ok(order)
+    } else {
+        let ordered: HashSet<u32> = order.into_iter().collect();
+        Err(nodes
+            .iter()
+            .copied()
+            .filter(|node| !ordered.contains(node))
+            .collect())
+    }
+}
+
+/// Whether `to` can be reached from `from` by following `edges`.
+pub(crate) fn reaches(from: u32, to: u32, edges: impl Fn(u32) -> Vec<u32>) -> bool {
+    let mut seen = HashSet::new();
+    let mut to_visit = vec![from];
+    while let Some(node) = to_visit.pop() {
+        if node == to {
+            return true;
+        }
+        if seen.insert(node) {
+            to_visit.extend(edges(node));
+        }
+    }
+    false
+}
+
+impl TaskRepository {
+    fn dependency_ids(&self, id: u32) -> Vec<u32> {
+        self.tasks
+            .get(&id)
+            .map(|task| task.depends_on.iter().copied().collect())
+            .unwrap_or_default()
+    }
+
+    /// Makes `id` wait on `on`: it can't be marked done until `on` is.
+    pub fn add_dependency(&mut self, id: u32, on: u32) -> Result<(), TaskError> {
+        for id in [id, on] {
+            if !self.tasks.contains_key(&id) {
+                return Err(TaskError::NotFound(id));
+            }
+        }
+        if reaches(on, id, |node| self.dependency_ids(node)) {
+            return Err(TaskError::DependencyCycle { id, on });
+        }
+        let task = self.task_mut(id).expect("task exists");
+        if task.depends_on.insert(on) {
+            task.updated_at = chrono::Utc::now();
+        }
+        Ok(())
+    }
+
+    pub fn remove_dependency(&mut self, id: u32, on: u32) -> Result<(), TaskError> {
+        if !self.tasks.contains_key(&id) {
+            return Err(TaskError::NotFound(id));
+        }
+        if self.tasks[&id].depends_on.contains(&on) {
+            let task = self.task_mut(id).expect("task exists");
+            task.depends_on.remove(&on);
+            task.updated_at = chrono::Utc::now();
+        }
+        Ok(())
+    }
+
+    /// The dependencies of a task that aren't done yet, archived ones included. Deleted
+    /// dependencies don't count.
+    pub fn unmet_dependencies(&self, id: u32) -> Vec<u32> {
+        let Some(task) = self.tasks.get(&id) else {
+            return Vec::new();
+        };
+        task.depends_on
+            .iter()
+            .copied()
+            .filter(|dependency| {
+                self.locate(*dependency)
+                    .0
+                    .is_some_and(|dependency| dependency.status != Status::Done)
+            })
+            .collect()
+    }
+
+    /// Open tasks waiting on unfinished dependencies, by ID, with the dependencies they wait on.
+    pub fn get_blocked_tasks(&self) -> Vec<(Task, Vec<u32>)> {
+        let mut blocked: Vec<(Task, Vec<u32>)> = self
+            .tasks
+            .values()
+            .filter(|task| task.status != Status::Done)
+            .map(|task| (task.clone(), self.unmet_dependencies(task.id)))
+            .filter(|(_, unmet)| !unmet.is_empty())
+            .collect();
+        blocked.sort_by_key(|(task, _)| task.id);
+        blocked
+    }
+
+    /// The order in which `ids` can be marked done: dependencies and subtasks before the tasks
+    /// waiting on them. `None` if they depend on each other in a cycle, which only hand-edited
+    /// files can contain.
+    pub fn completion_order(&self, ids: &[u32]) -> Option<Vec<u32>> {
+        let nodes: BTreeSet<u32> = ids.iter().copied().collect();
+        topological_sort(&nodes, |id| {
+            let mut prerequisites = self.dependency_ids(id);
+            prerequisites.extend(self.subtask_ids(id));
+            prerequisites
+        })
+        .ok()
+    }
+
+    /// Forgets dependencies on a deleted task.
+    pub(crate) fn remove_dependencies_on(&mut self, id: u32) {
+        let dependents: Vec<u32> = self
+            .tasks
+            .values()
+            .filter(|task| task.depends_on.contains(&id))
+            .map(|task| task.id)
+            .collect();
+        for dependent in dependents {
+            let task = self.task_mut(dependent).expect("task exists");
+            task.depends_on.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(u32, u32)]) -> impl Fn(u32) -> Vec<u32> + '_ {
+        move |node| {
+            edges
+                .iter()
+                .filter(|(from, _)| *from == node)
+                .map(|(_, to)| *to)
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_topological_sort_puts_dependencies_first() {
+        let nodes = BTreeSet::from([1, 2, 3, 4, 5]);
+        // 1 depends on 3, 3 on 2 and 5, 4 on 9 which isn't sorted
+        let edges = [(1, 3), (3, 2), (3, 5), (4, 9)];
+
+        let order = topological_sort(&nodes, graph(&edges)).unwrap();
+
+        assert_eq!(order, [2, 4, 5, 3, 1]);
+    }
+
+    #[test]
+    fn test_topological_sort_reports_cycles() {
+        let nodes = BTreeSet::from([1, 2, 3, 4]);
+        let edges = [(1, 2), (2, 3), (3, 1), (4, 1)];
+
+        let cycle = topological_sort(&nodes, graph(&edges)).unwrap_err();
+
+        assert_eq!(cycle, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_reaches() {
+        let edges = [(1, 2), (2, 3), (3, 1), (4, 1)];
+
+        assert!(reaches(1, 3, graph(&edges)));
+        assert!(reaches(4, 3, graph(&edges)));
+        assert!(!reaches(1, 4, graph(&edges)));
+    }
+
+    #[test]
+    fn test_dependencies_block_marking_done() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let release = repo.add_task("Release".to_string());
+        let tests = repo.add_task("Tests".to_string());
+        repo.add_dependency(release, tests).unwrap();
+
+        // Act
+        let blocked = repo.mark_done(release);
+        let listed = repo.get_blocked_tasks();
+        repo.mark_done(tests).unwrap();
+
+        // Assert
+        assert!(matches!(blocked, Err(TaskError::Blocked(1))));
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].0.id, release);
+        assert_eq!(listed[0].1, [tests]);
+        assert!(repo.get_blocked_tasks().is_empty());
+        assert!(repo.mark_done(release).is_ok());
+    }
+
+    #[test]
+    fn test_add_dependency_rejects_cycles() {
+        let mut repo = TaskRepository::new();
+        let a = repo.add_task("A".to_string());
+        let b = repo.add_task("B".to_string());
+        let c = repo.add_task("C".to_string());
+        repo.add_dependency(a, b).unwrap();
+        repo.add_dependency(b, c).unwrap();
+
+        assert!(matches!(
+            repo.add_dependency(c, a),
+            Err(TaskError::DependencyCycle { id: 3, on: 1 })
+        ));
+        assert!(matches!(
+            repo.add_dependency(a, a),
+            Err(TaskError::DependencyCycle { id: 1, on: 1 })
+        ));
+        assert!(matches!(
+            repo.add_dependency(a, 42),
+            Err(TaskError::NotFound(42))
+        ));
+    }
+
+    #[test]
+    fn test_deleting_a_dependency_unblocks() {
+        let mut repo = TaskRepository::new();
+        let a = repo.add_task("A".to_string());
+        let b = repo.add_task("B".to_string());
+        repo.add_dependency(a, b).unwrap();
+
+        repo.delete_task(b).unwrap();
+
+        assert!(repo.get_task(a).unwrap().depends_on.is_empty());
+        assert!(repo.mark_done(a).is_ok());
+    }
+
+    #[test]
+    fn test_remove_dependency() {
+        let mut repo = TaskRepository::new();
+        let a = repo.add_task("A".to_string());
+        let b = repo.add_task("B".to_string());
+        repo.add_dependency(a, b).unwrap();
+
+        repo.remove_dependency(a, b).unwrap();
+
+        assert!(repo.unmet_dependencies(a).is_empty());
+        assert!(repo.mark_done(a).is_ok());
+    }
+
+    #[test]
+    fn test_completion_order_covers_dependencies_and_subtasks() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let parent = repo.add_task("Parent".to_string());
+        let child = repo.add_subtask(parent, "Child".to_string()).unwrap();
+        let other = repo.add_task("Other".to_string());
+        repo.add_dependency(child, other).unwrap();
+
+        // Act
+        let order = repo.completion_order(&[parent, child, other]).unwrap();
+
+        // Assert
+        assert_eq!(order, [other, child, parent]);
+    }
+}
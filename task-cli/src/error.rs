@@ -0,0 +1,70 @@
+use crate::Status;
+use thiserror::Error;
+
+/// Errors returned by [`TaskRepository`](crate::TaskRepository).
+#[derive(Debug, Error)]
+pub enum TaskError {
+    #[error("Task with ID {0} not found")]
+    NotFound(u32),
+    #[error("Task with ID {id} cannot go from {from} to {to}")]
+    InvalidTransition { id: u32, from: Status, to: Status },
+    #[error("Task with ID {0} has open subtasks")]
+    OpenSubtasks(u32),
+    #[error("Task with ID {id} cannot be a subtask of {parent_id}, it would be its own ancestor")]
+    Cycle { id: u32, parent_id: u32 },
+    #[error("Task with ID {id} cannot depend on {on}, it would depend on itself")]
+    DependencyCycle { id: u32, on: u32 },
+    #[error("Task with ID {0} is waiting on tasks that aren't done")]
+    Blocked(u32),
+    #[error("Tag cannot be empty")]
+    EmptyTag,
+    #[error("Project name cannot be empty")]
+    EmptyProject,
+    #[error("Invalid due date '{0}', expected YYYY-MM-DD, YYYY-MM-DD HH:MM or RFC 3339")]
+    InvalidDueDate(String),
+    #[error(
+        "Invalid recurrence '{0}', expected daily, weekly, weekdays, every N days, every N weeks or days like mon,wed,fri"
+    )]
+    InvalidRecurrence(String),
+    #[error("Invalid ID range '{0}', expected an ID or a range like 5-9")]
+    InvalidIdRange(String),
+    #[error(
+        "Unknown column '{0}', expected id, description, status, priority, due, tags, project, parent, depends, repeats, tracked, created or updated"
+    )]
+    InvalidColumn(String),
+    /// Some of the tasks given to a command couldn't be changed, the others were.
+    #[error("{failed} of {total} tasks could not be changed")]
+    BulkFailed { failed: usize, total: usize },
+    #[error("Task with ID {0} is already being tracked")]
+    AlreadyTracking(u32),
+    #[error("Task with ID {0} is not being tracked")]
+    NotTracking(u32),
+    #[error("Session overlaps another session of task with ID {0}")]
+    OverlappingSession(u32),
+    #[error("A session must end after it starts")]
+    InvalidSession,
+    /// The task file isn't valid JSON, or isn't a task list.
+    #[error("Invalid task data: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("Cannot access the task file: {0}")]
+    Io(#[from] std::io::Error),
+    #[cfg(feature = "sqlite")]
+    #[error("Cannot access the task database: {0}")]
+    Database(#[from] rusqlite::Error),
+    /// Another command saved the tasks between this one loading and saving them.
+    #[error("The tasks were changed by another command, try again")]
+    Conflict,
+    /// A task was changed outside of the CLI since the operation being undone or redone.
+    #[error(
+        "Task with ID {0} was changed outside of task-cli, the operation can't be undone or redone"
+    )]
+    JournalOutOfDate(u32),
+    #[error("The store has no journal to undo and redo from")]
+    NoJournal,
+    #[error("Invalid store '{0}', expected json:PATH or sqlite:PATH")]
+    InvalidStore(String),
+    #[error("Unknown config key '{0}', expected store or file")]
+    UnknownConfigKey(String),
+    #[error("Cannot find the home directory, set HOME or pick a store with --store or --file")]
+    NoHomeDirectory,
+}
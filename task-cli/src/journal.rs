@@ -0,0 +1,317 @@
+use crate::{Task, TaskError, TaskRepository};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// A task before and after an [`Operation`], `None` when it didn't exist.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskChange {
+    pub id: u32,
+    pub before: Option<Task>,
+    pub after: Option<Task>,
+    /// Whether the task was in the archive before and after.
+    #[serde(default)]
+    pub before_archived: bool,
+    #[serde(default)]
+    pub after_archived: bool,
+}
+
+/// A task, or `None` when it doesn't exist, and whether it's archived.
+type TaskState<'a> = (&'a Option<Task>, bool);
+
+/// The changes made by one command, which can be undone and redone as a whole.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Operation {
+    /// What the command did, e.g. `mark task 3 done`.
+    pub description: String,
+    pub changes: Vec<TaskChange>,
+}
+
+impl Operation {
+    pub fn new(description: impl Into<String>, changes: Vec<TaskChange>) -> Self {
+        Self {
+            description: description.into(),
+            changes,
+        }
+    }
+
+    /// Puts the changed tasks back the way they were before the operation.
+    pub fn revert(&self, tasks: &mut TaskRepository) -> Result<(), TaskError> {
+        self.replace(tasks, |change| {
+            (
+                (&change.after, change.after_archived),
+                (&change.before, change.before_archived),
+            )
+        })
+    }
+
+    /// Makes the changes of the operation again, after it was reverted.
+    pub fn reapply(&self, tasks: &mut TaskRepository) -> Result<(), TaskError> {
+        self.replace(tasks, |change| {
+            (
+                (&change.before, change.before_archived),
+                (&change.after, change.after_archived),
+            )
+        })
+    }
+
+    fn replace(
+        &self,
+        tasks: &mut TaskRepository,
+        states: impl Fn(&TaskChange) -> (TaskState<'_>, TaskState<'_>),
+    ) -> Result<(), TaskError> {
+        // Checks every task first, so that a task changed behind the journal's back doesn't
+        // leave the operation half undone
+        for change in &self.changes {
+            let ((expected, archived), _) = states(change);
+            if tasks.locate(change.id) != (expected.as_ref(), archived) {
+                return Err(TaskError::JournalOutOfDate(change.id));
+            }
+        }
+        for change in &self.changes {
+            let (_, (replacement, archived)) = states(change);
+            tasks.restore(change.id, replacement.clone(), archived);
+        }
+        Ok(())
+    }
+}
+
+/// One line of the journal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "entry", rename_all = "snake_case")]
+enum Entry {
+    Applied { operation: Operation },
+    Undone,
+    Redone,
+}
+
+/// Operations that can be undone and redone, worked out from the journal.
+#[derive(Debug, Default)]
+pub struct History {
+    done: Vec<Operation>,
+    undone: Vec<Operation>,
+}
+
+impl History {
+    /// The operation `undo` reverts, the latest one that's still done.
+    pub fn next_undo(&self) -> Option<&Operation> {
+        self.done.last()
+    }
+
+    /// The operation `redo` reapplies, the latest one undone, unless something was done since.
+    pub fn next_redo(&self) -> Option<&Operation> {
+        self.undone.last()
+    }
+
+    fn push(&mut self, entry: Entry) {
+        match entry {
+            Entry::Applied { operation } => {
+                self.done.push(operation);
+                self.undone.clear();
+            }
+            Entry::Undone => self.undone.extend(self.done.pop()),
+            Entry::Redone => self.done.extend(self.undone.pop()),
+        }
+    }
+}
+
+/// Append-only log of the operations on a task store, one JSON entry per line.
+///
+/// Undoing and redoing are logged as entries too, so the file is never rewritten.
+#[derive(Debug, Clone)]
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn history(&self) -> Result<History, TaskError> {
+        let mut history = History::default();
+        if !self.path.exists() {
+            return Ok(history);
+        }
+        for line in fs::read_to_string(&self.path)?.lines() {
+            if !line.trim().is_empty() {
+                history.push(serde_json::from_str(line)?);
+            }
+        }
+        Ok(history)
+    }
+
+    pub fn record(&self, operation: Operation) -> Result<(), TaskError> {
+        self.append(&Entry::Applied { operation })
+    }
+
+    /// Logs that [`History::next_undo`] was reverted.
+    pub fn record_undo(&self) -> Result<(), TaskError> {
+        self.append(&Entry::Undone)
+    }
+
+    /// Logs that [`History::next_redo`] was reapplied.
+    pub fn record_redo(&self) -> Result<(), TaskError> {
+        self.append(&Entry::Redone)
+    }
+
+    fn append(&self, entry: &Entry) -> Result<(), TaskError> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Status;
+
+    /// Runs `change` on `tasks` and records it like a command does.
+    fn run(
+        journal: &Journal,
+        tasks: &mut TaskRepository,
+        description: &str,
+        change: impl FnOnce(&mut TaskRepository),
+    ) {
+        change(tasks);
+        journal
+            .record(Operation::new(description, tasks.take_changes()))
+            .unwrap();
+    }
+
+    fn undo(journal: &Journal, tasks: &mut TaskRepository) -> String {
+        let history = journal.history().unwrap();
+        let operation = history.next_undo().unwrap();
+        operation.revert(tasks).unwrap();
+        journal.record_undo().unwrap();
+        operation.description.clone()
+    }
+
+    fn redo(journal: &Journal, tasks: &mut TaskRepository) -> String {
+        let history = journal.history().unwrap();
+        let operation = history.next_redo().unwrap();
+        operation.reapply(tasks).unwrap();
+        journal.record_redo().unwrap();
+        operation.description.clone()
+    }
+
+    #[test]
+    fn test_take_changes_lists_changed_tasks_once() {
+        // Arrange
+        let mut tasks = TaskRepository::new();
+        let id = tasks.add_task("Task".to_string());
+        tasks.take_changes();
+        let before = tasks.get_task(id).cloned();
+
+        // Act
+        tasks.update_task(id, "Renamed".to_string()).unwrap();
+        tasks.mark_in_progress(id).unwrap();
+        tasks.remove_tag(id, "missing").unwrap();
+        let changes = tasks.take_changes();
+
+        // Assert
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].before, before);
+        assert_eq!(changes[0].after.as_ref(), tasks.get_task(id));
+        assert!(tasks.take_changes().is_empty());
+    }
+
+    #[test]
+    fn test_undo_and_redo() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("tasks.json.journal"));
+        let mut tasks = TaskRepository::new();
+        let mut id = 0;
+        run(&journal, &mut tasks, "add", |tasks| {
+            id = tasks.add_task("Task".to_string())
+        });
+        run(&journal, &mut tasks, "tag", |tasks| {
+            tasks.add_tag(id, "work").unwrap()
+        });
+
+        // Act & Assert
+        assert_eq!(undo(&journal, &mut tasks), "tag");
+        assert!(tasks.get_tasks_with_tag("work").is_empty());
+        assert_eq!(undo(&journal, &mut tasks), "add");
+        assert!(tasks.get_task(id).is_none());
+        assert!(journal.history().unwrap().next_undo().is_none());
+
+        assert_eq!(redo(&journal, &mut tasks), "add");
+        assert_eq!(redo(&journal, &mut tasks), "tag");
+        assert_eq!(tasks.get_tasks_with_tag("work").len(), 1);
+        assert!(journal.history().unwrap().next_redo().is_none());
+    }
+
+    #[test]
+    fn test_new_operation_discards_redo() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("tasks.json.journal"));
+        let mut tasks = TaskRepository::new();
+        run(&journal, &mut tasks, "add", |tasks| {
+            tasks.add_task("First".to_string());
+        });
+        undo(&journal, &mut tasks);
+
+        run(&journal, &mut tasks, "add", |tasks| {
+            tasks.add_task("Second".to_string());
+        });
+
+        assert!(journal.history().unwrap().next_redo().is_none());
+    }
+
+    #[test]
+    fn test_undo_restores_deleted_subtasks_and_ids_stay_unused() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("tasks.json.journal"));
+        let mut tasks = TaskRepository::new();
+        let parent = tasks.add_task("Parent".to_string());
+        let child = tasks.add_subtask(parent, "Child".to_string()).unwrap();
+        tasks.take_changes();
+        run(&journal, &mut tasks, "delete", |tasks| {
+            tasks.delete_task(parent).unwrap()
+        });
+
+        // Act
+        undo(&journal, &mut tasks);
+
+        // Assert
+        assert_eq!(tasks.get_subtasks(parent).len(), 1);
+        assert!(tasks.get_task(child).is_some());
+        assert_eq!(tasks.add_task("New".to_string()), 3);
+    }
+
+    #[test]
+    fn test_undo_refuses_tasks_changed_since() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let journal = Journal::new(dir.path().join("tasks.json.journal"));
+        let mut tasks = TaskRepository::new();
+        let mut id = 0;
+        run(&journal, &mut tasks, "add", |tasks| {
+            id = tasks.add_task("Task".to_string())
+        });
+        // Changed without going through the journal
+        tasks.set_status(id, Status::Done).unwrap();
+
+        // Act
+        let result = journal
+            .history()
+            .unwrap()
+            .next_undo()
+            .unwrap()
+            .revert(&mut tasks);
+
+        // Assert
+        assert!(matches!(result, Err(TaskError::JournalOutOfDate(1))));
+        assert_eq!(tasks.get_task(id).unwrap().status, Status::Done);
+    }
+}
@@ -1,7 +1,36 @@
 use crate::Status::{InProgress, Todo};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+mod atomic;
+mod bulk;
+pub mod config;
+mod dependencies;
+mod error;
+mod journal;
+mod projects;
+mod recurrence;
+pub mod render;
+pub mod rpc;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod stats;
+mod store;
+mod tracking;
+
+pub use bulk::{BulkOperation, BulkResult, IdRange};
+pub use error::TaskError;
+pub use journal::{History, Journal, Operation, TaskChange};
+pub use projects::{DEFAULT_PROJECT, ProjectSummary, normalize_project};
+pub use recurrence::Recurrence;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteStore;
+pub use stats::{Stats, WeekStats};
+pub use store::{JsonFileStore, MemoryStore, TaskStore, open_store};
+pub use tracking::{TimeReport, TimeSpan, format_duration, start_of_week};
 
 #[derive(Debug, Eq, PartialEq, Serialize, Deserialize, Clone)]
 pub struct Task {
@@ -10,6 +39,62 @@ pub struct Task {
     status: Status,
     created_at: chrono::DateTime<chrono::Utc>,
     updated_at: chrono::DateTime<chrono::Utc>,
+    /// When the task was last marked done, `None` while it's open. Tasks marked done before this
+    /// was recorded have none either.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    completed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    due_date: Option<DateTime<Utc>>,
+    #[serde(default)]
+    tags: BTreeSet<String>,
+    #[serde(default)]
+    priority: Priority,
+    #[serde(default)]
+    parent_id: Option<u32>,
+    /// Tasks saved before there were projects are in [`DEFAULT_PROJECT`].
+    #[serde(default = "projects::default_project")]
+    project: String,
+    /// Tasks that have to be done before this one can be.
+    #[serde(default)]
+    depends_on: BTreeSet<u32>,
+    /// Set on the latest occurrence of a repeating task only.
+    #[serde(default)]
+    recurrence: Option<Recurrence>,
+    /// Work sessions, oldest first. At most one is running, the last one.
+    #[serde(default)]
+    sessions: Vec<TimeSpan>,
+}
+
+impl Task {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
+
+    pub fn project(&self) -> &str {
+        &self.project
+    }
+
+    pub fn sessions(&self) -> &[TimeSpan] {
+        &self.sessions
+    }
+
+    /// Whether a work session on the task is running.
+    pub fn is_tracking(&self) -> bool {
+        self.sessions.last().is_some_and(TimeSpan::is_running)
+    }
+
+    /// Total time worked on the task, counting a running session up to `now`.
+    pub fn tracked_time(&self, now: DateTime<Utc>) -> Duration {
+        self.sessions
+            .iter()
+            .fold(Duration::zero(), |total, session| {
+                total + session.duration(now)
+            })
+    }
 }
 
 impl Display for Task {
@@ -18,10 +103,114 @@ impl Display for Task {
             f,
             "{}: {} [Status: {}]",
             self.id, self.description, self.status
-        )
+        )?;
+        if let Some(due_date) = self.due_date {
+            write!(f, " [Due: {}]", due_date.format("%Y-%m-%d %H:%M UTC"))?;
+        }
+        if let Some(parent_id) = self.parent_id {
+            write!(f, " [Subtask of: {}]", parent_id)?;
+        }
+        if self.project != DEFAULT_PROJECT {
+            write!(f, " [Project: {}]", self.project)?;
+        }
+        if !self.depends_on.is_empty() {
+            let ids: Vec<String> = self.depends_on.iter().map(u32::to_string).collect();
+            write!(f, " [Depends on: {}]", ids.join(", "))?;
+        }
+        if self.priority != Priority::default() {
+            write!(f, " [Priority: {}]", self.priority)?;
+        }
+        if let Some(recurrence) = &self.recurrence {
+            write!(f, " [Repeats: {}]", recurrence)?;
+        }
+        if !self.tags.is_empty() {
+            let tags: Vec<&str> = self.tags.iter().map(String::as_str).collect();
+            write!(f, " [Tags: {}]", tags.join(", "))?;
+        }
+        if !self.sessions.is_empty() {
+            let tracked = format_duration(self.tracked_time(Utc::now()));
+            let running = if self.is_tracking() { ", running" } else { "" };
+            write!(f, " [Tracked: {}{}]", tracked, running)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a due date given on the command line, either as an RFC 3339 timestamp, as
+/// `YYYY-MM-DD HH:MM` in UTC, or as a bare `YYYY-MM-DD`, which means the end of that day in UTC.
+pub fn parse_due_date(input: &str) -> Result<DateTime<Utc>, TaskError> {
+    let input = input.trim();
+    if let Ok(date_time) = DateTime::parse_from_rfc3339(input) {
+        return Ok(date_time.with_timezone(&Utc));
+    }
+    if let Ok(date_time) = NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M") {
+        return Ok(date_time.and_utc());
+    }
+    NaiveDate::parse_from_str(input, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(23, 59, 59))
+        .map(|date_time| date_time.and_utc())
+        .ok_or_else(|| TaskError::InvalidDueDate(input.to_string()))
+}
+
+/// How urgent a task is, ordered from [`Priority::Low`] to [`Priority::Urgent`].
+#[derive(Debug, Default, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, Clone, Copy)]
+pub enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+    Urgent,
+}
+
+impl Display for Priority {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Priority::Low => write!(f, "Low"),
+            Priority::Medium => write!(f, "Medium"),
+            Priority::High => write!(f, "High"),
+            Priority::Urgent => write!(f, "Urgent"),
+        }
+    }
+}
+
+/// Orders in which tasks can be listed. Ties are broken by ID.
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub enum SortKey {
+    #[default]
+    Id,
+    /// Most urgent first.
+    Priority,
+    /// Soonest deadline first, tasks without one last.
+    DueDate,
+    /// Oldest first.
+    CreatedAt,
+}
+
+impl SortKey {
+    pub fn sort(self, tasks: &mut [Task]) {
+        match self {
+            SortKey::Id => tasks.sort_by_key(|task| task.id),
+            SortKey::Priority => {
+                tasks.sort_by_key(|task| (std::cmp::Reverse(task.priority), task.id))
+            }
+            SortKey::DueDate => {
+                tasks.sort_by_key(|task| (task.due_date.is_none(), task.due_date, task.id))
+            }
+            SortKey::CreatedAt => tasks.sort_by_key(|task| (task.created_at, task.id)),
+        }
     }
 }
 
+/// Tags are compared case-insensitively, so they're stored trimmed and lowercased.
+fn normalize_tag(tag: &str) -> Result<String, TaskError> {
+    let tag = tag.trim().to_lowercase();
+    if tag.is_empty() {
+        return Err(TaskError::EmptyTag);
+    }
+    Ok(tag)
+}
+
 #[derive(Debug, Default, Eq, PartialEq, Serialize, Deserialize, Clone)]
 pub enum Status {
     #[default]
@@ -39,10 +228,44 @@ impl Display for Status {
     }
 }
 
+impl Status {
+    /// Whether a task with this status may be moved to `next`.
+    ///
+    /// Tasks move forward from To Do to In Progress to Done, possibly skipping In Progress. Work
+    /// in progress can be put back to To Do, and a done task can be reopened as In Progress.
+    /// Setting the status a task already has is always allowed.
+    pub fn can_transition_to(&self, next: &Status) -> bool {
+        matches!(
+            (self, next),
+            (Todo, Todo)
+                | (Todo, InProgress)
+                | (Todo, Status::Done)
+                | (InProgress, Todo)
+                | (InProgress, InProgress)
+                | (InProgress, Status::Done)
+                | (Status::Done, InProgress)
+                | (Status::Done, Status::Done)
+        )
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct TaskRepository {
     tasks: HashMap<u32, Task>,
     next_id: u32,
+    /// IDs of the tasks with each tag, rebuilt from the tasks on load.
+    #[serde(skip)]
+    tag_index: HashMap<String, BTreeSet<u32>>,
+    /// IDs of the tasks in each project, rebuilt from the tasks on load.
+    #[serde(skip)]
+    project_index: HashMap<String, BTreeSet<u32>>,
+    /// Archived tasks, kept apart from the others and saved separately by the stores.
+    #[serde(skip)]
+    archived: HashMap<u32, Task>,
+    /// Tasks as they were before the changes not yet taken by [`TaskRepository::take_changes`],
+    /// and whether they were archived.
+    #[serde(skip)]
+    tracked: BTreeMap<u32, (Option<Task>, bool)>,
 }
 
 impl Default for TaskRepository {
@@ -56,11 +279,65 @@ impl TaskRepository {
         Self {
             tasks: HashMap::new(),
             next_id: 1,
+            tag_index: HashMap::new(),
+            project_index: HashMap::new(),
+            archived: HashMap::new(),
+            tracked: BTreeMap::new(),
+        }
+    }
+
+    pub fn new_from_json(json: &str) -> Result<Self, TaskError> {
+        let mut repository: Self = serde_json::from_str(json)?;
+        repository.rebuild_indexes();
+        Ok(repository)
+    }
+
+    /// Builds a repository from tasks loaded by a [`TaskStore`].
+    #[cfg(feature = "sqlite")]
+    pub(crate) fn from_tasks(
+        tasks: impl IntoIterator<Item = Task>,
+        archived: impl IntoIterator<Item = Task>,
+        next_id: u32,
+    ) -> Self {
+        let mut repository = Self {
+            tasks: tasks.into_iter().map(|task| (task.id, task)).collect(),
+            next_id,
+            tag_index: HashMap::new(),
+            project_index: HashMap::new(),
+            archived: archived.into_iter().map(|task| (task.id, task)).collect(),
+            tracked: BTreeMap::new(),
+        };
+        repository.rebuild_indexes();
+        repository
+    }
+
+    fn rebuild_indexes(&mut self) {
+        self.tag_index.clear();
+        self.project_index.clear();
+        let ids: Vec<u32> = self.tasks.keys().copied().collect();
+        for id in ids {
+            self.index_task(id);
+        }
+    }
+
+    /// Adds a task to the tag and project indexes.
+    fn index_task(&mut self, id: u32) {
+        let task = &self.tasks[&id];
+        for tag in &task.tags {
+            self.tag_index.entry(tag.clone()).or_default().insert(id);
         }
+        self.project_index
+            .entry(task.project.clone())
+            .or_default()
+            .insert(id);
     }
 
-    pub fn new_from_json(json: &str) -> Self {
-        serde_json::from_str(json).expect("cannot deserialize repository")
+    /// Removes a task taken out of the active tasks from the tag and project indexes.
+    fn unindex_task(&mut self, task: &Task) {
+        for tag in &task.tags {
+            projects::unindex(&mut self.tag_index, tag, task.id);
+        }
+        projects::unindex(&mut self.project_index, &task.project, task.id);
     }
 
     pub fn get_task(&self, id: u32) -> Option<&Task> {
@@ -75,23 +352,413 @@ impl TaskRepository {
             .collect()
     }
 
-    pub fn update_task(&mut self, id: u32, description: String) -> Result<(), String> {
-        match self.tasks.get_mut(&id) {
+    /// Every task, in the order given by `key`.
+    pub fn get_tasks_sorted_by(&self, key: SortKey) -> Vec<Task> {
+        let mut tasks: Vec<Task> = self.tasks.values().cloned().collect();
+        key.sort(&mut tasks);
+        tasks
+    }
+
+    /// Tasks with `tag`, by ID.
+    pub fn get_tasks_with_tag(&self, tag: &str) -> Vec<Task> {
+        let Ok(tag) = normalize_tag(tag) else {
+            return Vec::new();
+        };
+        self.tag_index
+            .get(&tag)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.tasks.get(id))
+            .cloned()
+            .collect()
+    }
+
+    /// Every tag in use, alphabetically.
+    pub fn get_tags(&self) -> Vec<&str> {
+        let mut tags: Vec<&str> = self.tag_index.keys().map(String::as_str).collect();
+        tags.sort_unstable();
+        tags
+    }
+
+    /// Open tasks whose due date has passed, most overdue first.
+    pub fn get_overdue_tasks(&self) -> Vec<Task> {
+        let now = Utc::now();
+        self.open_tasks_due_between(DateTime::<Utc>::MIN_UTC, now)
+    }
+
+    /// Open tasks due from now until `within` from now, soonest first.
+    pub fn get_tasks_due_within(&self, within: Duration) -> Vec<Task> {
+        let now = Utc::now();
+        self.open_tasks_due_between(now, now + within)
+    }
+
+    fn open_tasks_due_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<Task> {
+        let mut tasks: Vec<Task> = self
+            .tasks
+            .values()
+            .filter(|task| task.status != Status::Done)
+            .filter(|task| task.due_date.is_some_and(|due| from <= due && due < to))
+            .cloned()
+            .collect();
+        tasks.sort_by_key(|task| (task.due_date, task.id));
+        tasks
+    }
+
+    pub fn update_task(&mut self, id: u32, description: String) -> Result<(), TaskError> {
+        match self.task_mut(id) {
             Some(task) => {
                 task.description = description;
                 task.updated_at = chrono::Utc::now();
                 Ok(())
             }
-            None => Err(format!("Task with ID {} not found", id)),
+            None => Err(TaskError::NotFound(id)),
+        }
+    }
+
+    /// Sets or, with `None`, clears the due date of a task.
+    pub fn set_due_date(
+        &mut self,
+        id: u32,
+        due_date: Option<DateTime<Utc>>,
+    ) -> Result<(), TaskError> {
+        let Some(task) = self.task_mut(id) else {
+            return Err(TaskError::NotFound(id));
+        };
+        task.due_date = due_date;
+        task.updated_at = chrono::Utc::now();
+        Ok(())
+    }
+
+    pub fn set_priority(&mut self, id: u32, priority: Priority) -> Result<(), TaskError> {
+        let Some(task) = self.task_mut(id) else {
+            return Err(TaskError::NotFound(id));
+        };
+        if task.priority != priority {
+            task.priority = priority;
+            task.updated_at = chrono::Utc::now();
+        }
+        Ok(())
+    }
+
+    /// Tags a task. Adding a tag the task already has leaves it untouched.
+    pub fn add_tag(&mut self, id: u32, tag: &str) -> Result<(), TaskError> {
+        let tag = normalize_tag(tag)?;
+        let Some(task) = self.task_mut(id) else {
+            return Err(TaskError::NotFound(id));
+        };
+        if task.tags.insert(tag.clone()) {
+            task.updated_at = chrono::Utc::now();
+            self.tag_index.entry(tag).or_default().insert(id);
+        }
+        Ok(())
+    }
+
+    /// Removes a tag from a task. Removing a tag the task doesn't have leaves it untouched.
+    pub fn remove_tag(&mut self, id: u32, tag: &str) -> Result<(), TaskError> {
+        let tag = normalize_tag(tag)?;
+        let Some(task) = self.task_mut(id) else {
+            return Err(TaskError::NotFound(id));
+        };
+        if task.tags.remove(&tag) {
+            task.updated_at = chrono::Utc::now();
+            projects::unindex(&mut self.tag_index, &tag, id);
+        }
+        Ok(())
+    }
+
+    /// Deletes a task along with all of its subtasks.
+    pub fn delete_task(&mut self, id: u32) -> Result<(), TaskError> {
+        if !self.tasks.contains_key(&id) {
+            return Err(TaskError::NotFound(id));
+        }
+        let mut to_delete = vec![id];
+        while let Some(id) = to_delete.pop() {
+            self.track(id);
+            let Some(task) = self.tasks.remove(&id) else {
+                continue;
+            };
+            self.unindex_task(&task);
+            to_delete.extend(self.subtask_ids(id));
+            self.remove_dependencies_on(id);
+        }
+        Ok(())
+    }
+
+    /// Adds a task as a subtask of `parent_id`.
+    pub fn add_subtask(&mut self, parent_id: u32, description: String) -> Result<u32, TaskError> {
+        if !self.tasks.contains_key(&parent_id) {
+            return Err(TaskError::NotFound(parent_id));
+        }
+        let id = self.add_task(description);
+        self.set_parent(id, Some(parent_id))?;
+        let project = self.tasks[&parent_id].project.clone();
+        self.set_project(id, &project)?;
+        Ok(id)
+    }
+
+    /// Makes a task a subtask of `parent_id` or, with `None`, a top-level task.
+    ///
+    /// An open task can't be under a done one, so moving an open task under a done parent reopens
+    /// the parent as In Progress, and so on up the tree.
+    pub fn set_parent(&mut self, id: u32, parent_id: Option<u32>) -> Result<(), TaskError> {
+        if !self.tasks.contains_key(&id) {
+            return Err(TaskError::NotFound(id));
+        }
+        if let Some(parent_id) = parent_id {
+            if !self.tasks.contains_key(&parent_id) {
+                return Err(TaskError::NotFound(parent_id));
+            }
+            if parent_id == id || self.ancestor_ids(parent_id).contains(&id) {
+                return Err(TaskError::Cycle { id, parent_id });
+            }
+        }
+        let task = self.task_mut(id).expect("task exists");
+        if task.parent_id != parent_id {
+            task.parent_id = parent_id;
+            task.updated_at = chrono::Utc::now();
+            if task.status != Status::Done {
+                self.reopen_done_ancestors(id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Direct subtasks of a task, by ID.
+    pub fn get_subtasks(&self, id: u32) -> Vec<Task> {
+        self.subtask_ids(id)
+            .into_iter()
+            .map(|id| self.tasks[&id].clone())
+            .collect()
+    }
+
+    fn subtask_ids(&self, id: u32) -> Vec<u32> {
+        let mut ids: Vec<u32> = self
+            .tasks
+            .values()
+            .filter(|task| task.parent_id == Some(id))
+            .map(|task| task.id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// IDs of the parent of a task, its parent, and so on up to the top-level task.
+    fn ancestor_ids(&self, id: u32) -> Vec<u32> {
+        let mut ids = Vec::new();
+        let mut current = self.tasks.get(&id).and_then(|task| task.parent_id);
+        while let Some(parent_id) = current {
+            // Stops on cycles, which hand-edited files could contain
+            if parent_id == id || ids.contains(&parent_id) {
+                break;
+            }
+            ids.push(parent_id);
+            current = self.tasks.get(&parent_id).and_then(|task| task.parent_id);
+        }
+        ids
+    }
+
+    fn reopen_done_ancestors(&mut self, id: u32) {
+        for ancestor_id in self.ancestor_ids(id) {
+            if let Some(ancestor) = self
+                .task_mut(ancestor_id)
+                .filter(|ancestor| ancestor.status == Status::Done)
+            {
+                ancestor.status = InProgress;
+                ancestor.updated_at = chrono::Utc::now();
+                ancestor.completed_at = None;
+            }
+        }
+    }
+
+    /// Remembers how a task was before a change, once per batch of changes.
+    fn track(&mut self, id: u32) {
+        if !self.tracked.contains_key(&id) {
+            let (task, archived) = self.locate(id);
+            self.tracked.insert(id, (task.cloned(), archived));
+        }
+    }
+
+    /// A task, archived or not, and whether it's archived.
+    pub(crate) fn locate(&self, id: u32) -> (Option<&Task>, bool) {
+        match self.tasks.get(&id) {
+            Some(task) => (Some(task), false),
+            None => {
+                let archived = self.archived.get(&id);
+                (archived, archived.is_some())
+            }
+        }
+    }
+
+    /// The task to change, tracking how it was for [`TaskRepository::take_changes`].
+    fn task_mut(&mut self, id: u32) -> Option<&mut Task> {
+        self.track(id);
+        self.tasks.get_mut(&id)
+    }
+
+    /// Every task changed since the last call, with how it was before and after the changes.
+    pub fn take_changes(&mut self) -> Vec<TaskChange> {
+        std::mem::take(&mut self.tracked)
+            .into_iter()
+            .map(|(id, (before, before_archived))| {
+                let (after, after_archived) = self.locate(id);
+                TaskChange {
+                    id,
+                    before,
+                    after: after.cloned(),
+                    before_archived,
+                    after_archived,
+                }
+            })
+            .filter(|change| {
+                (&change.before, change.before_archived) != (&change.after, change.after_archived)
+            })
+            .collect()
+    }
+
+    /// Puts a task back the way it was, in the archive or not, or removes it with `None`, without
+    /// tracking the change.
+    pub(crate) fn restore(&mut self, id: u32, task: Option<Task>, archived: bool) {
+        self.archived.remove(&id);
+        if let Some(old) = self.tasks.remove(&id) {
+            self.unindex_task(&old);
+        }
+        let Some(task) = task else {
+            return;
+        };
+        if archived {
+            self.archived.insert(id, task);
+        } else {
+            self.tasks.insert(id, task);
+            self.index_task(id);
+        }
+        self.next_id = self.next_id.max(id + 1);
+    }
+
+    /// Moves a task, along with its subtasks, to the archive. Returns the IDs of the archived
+    /// tasks.
+    pub fn archive_task(&mut self, id: u32) -> Result<Vec<u32>, TaskError> {
+        if !self.tasks.contains_key(&id) {
+            return Err(TaskError::NotFound(id));
+        }
+        let mut archived = Vec::new();
+        let mut to_archive = vec![id];
+        while let Some(id) = to_archive.pop() {
+            to_archive.extend(self.subtask_ids(id));
+            self.track(id);
+            let Some(task) = self.tasks.remove(&id) else {
+                continue;
+            };
+            self.unindex_task(&task);
+            self.archived.insert(id, task);
+            archived.push(id);
+        }
+        archived.sort_unstable();
+        Ok(archived)
+    }
+
+    /// Archives every done task, returning the IDs of the archived tasks.
+    pub fn archive_done(&mut self) -> Vec<u32> {
+        let mut done: Vec<u32> = self
+            .tasks
+            .values()
+            .filter(|task| task.status == Status::Done)
+            .map(|task| task.id)
+            .collect();
+        done.sort_unstable();
+        let mut archived = Vec::new();
+        for id in done {
+            // Already archived along with a done parent
+            if self.tasks.contains_key(&id) {
+                archived.extend(self.archive_task(id).expect("task exists"));
+            }
+        }
+        archived.sort_unstable();
+        archived
+    }
+
+    /// Brings an archived task back, along with the subtasks archived with it. Returns the IDs of
+    /// the restored tasks.
+    ///
+    /// Restoring an open subtask of a done task reopens it, like reopening the subtask would.
+    pub fn unarchive_task(&mut self, id: u32) -> Result<Vec<u32>, TaskError> {
+        if !self.archived.contains_key(&id) {
+            return Err(TaskError::NotFound(id));
+        }
+        let mut restored = Vec::new();
+        let mut to_restore = vec![id];
+        while let Some(id) = to_restore.pop() {
+            self.track(id);
+            let Some(task) = self.archived.remove(&id) else {
+                continue;
+            };
+            self.tasks.insert(id, task);
+            self.index_task(id);
+            restored.push(id);
+            to_restore.extend(
+                self.archived
+                    .values()
+                    .filter(|task| task.parent_id == Some(id))
+                    .map(|task| task.id),
+            );
+        }
+        if self.tasks[&id].status != Status::Done {
+            self.reopen_done_ancestors(id);
+        }
+        restored.sort_unstable();
+        Ok(restored)
+    }
+
+    pub fn get_archived_task(&self, id: u32) -> Option<&Task> {
+        self.archived.get(&id)
+    }
+
+    /// The archived tasks, by ID.
+    pub fn get_archived_tasks(&self) -> Vec<Task> {
+        let mut archived: Vec<Task> = self.archived.values().cloned().collect();
+        SortKey::Id.sort(&mut archived);
+        archived
+    }
+
+    /// Writes the archived tasks as JSON, apart from the others written by
+    /// [`save_as_json`](Self::save_as_json).
+    pub fn save_archive_as_json(&self, writer: impl std::io::Write) -> Result<(), TaskError> {
+        write_json(writer, &self.archived)
+    }
+
+    /// Replaces the archived tasks with the ones in `json`, as written by
+    /// [`save_archive_as_json`](Self::save_archive_as_json).
+    ///
+    /// Tasks that aren't archived win over archived copies of them, which a save interrupted
+    /// halfway through archiving can leave behind.
+    pub fn load_archive_from_json(&mut self, json: &str) -> Result<(), TaskError> {
+        let mut archived: HashMap<u32, Task> = serde_json::from_str(json)?;
+        archived.retain(|id, _| !self.tasks.contains_key(id));
+        self.next_id = archived
+            .keys()
+            .fold(self.next_id, |next_id, id| next_id.max(id + 1));
+        self.archived = archived;
+        Ok(())
+    }
+
+    /// Renders the tasks as a tree of top-level tasks and their subtasks.
+    pub fn tree(&self) -> TaskTree<'_> {
+        TaskTree {
+            repository: self,
+            project: None,
         }
     }
 
-    pub fn delete_task(&mut self, id: u32) {
-        self.tasks.remove(&id);
+    /// Like [`tree`](Self::tree), for the top-level tasks in `project` only.
+    pub fn project_tree(&self, project: &str) -> TaskTree<'_> {
+        TaskTree {
+            repository: self,
+            project: Some(normalize_project(project).unwrap_or_default()),
+        }
     }
 
     pub fn add_task(&mut self, description: String) -> u32 {
         let curr_id = self.next_id;
+        self.track(curr_id);
         self.tasks.insert(
             curr_id,
             Task {
@@ -100,33 +767,231 @@ impl TaskRepository {
                 status: Todo,
                 created_at: chrono::Utc::now(),
                 updated_at: chrono::Utc::now(),
+                completed_at: None,
+                due_date: None,
+                tags: BTreeSet::new(),
+                priority: Priority::default(),
+                parent_id: None,
+                project: projects::default_project(),
+                depends_on: BTreeSet::new(),
+                recurrence: None,
+                sessions: Vec::new(),
             },
         );
+        self.index_task(curr_id);
         self.next_id += 1;
         curr_id
     }
 
-    pub fn mark_in_progress(&mut self, id: u32) -> Result<(), String> {
-        let Some(task) = self.tasks.get_mut(&id) else {
-            return Err(format!("Task with ID {} not found", id));
+    pub fn mark_in_progress(&mut self, id: u32) -> Result<(), TaskError> {
+        self.set_status(id, InProgress)
+    }
+
+    /// Marks a task as done, returning the ID of its next occurrence if it repeats.
+    pub fn mark_done(&mut self, id: u32) -> Result<Option<u32>, TaskError> {
+        self.change_status(id, Status::Done)
+    }
+
+    /// Moves a task to `status`, if [`Status::can_transition_to`] allows it. Setting the status
+    /// the task already has leaves it untouched.
+    ///
+    /// A task can only be done once all of its subtasks are, and reopening a subtask reopens its
+    /// done parents as In Progress. Finishing a repeating task schedules its next occurrence.
+    pub fn set_status(&mut self, id: u32, status: Status) -> Result<(), TaskError> {
+        self.change_status(id, status).map(|_| ())
+    }
+
+    fn change_status(&mut self, id: u32, status: Status) -> Result<Option<u32>, TaskError> {
+        let Some(task) = self.tasks.get(&id) else {
+            return Err(TaskError::NotFound(id));
         };
-        task.status = InProgress;
-        task.updated_at = chrono::Utc::now();
+        if !task.status.can_transition_to(&status) {
+            return Err(TaskError::InvalidTransition {
+                id,
+                from: task.status.clone(),
+                to: status,
+            });
+        }
+        if task.status == status {
+            return Ok(None);
+        }
+        if status == Status::Done
+            && self
+                .subtask_ids(id)
+                .iter()
+                .any(|subtask_id| self.tasks[subtask_id].status != Status::Done)
+        {
+            return Err(TaskError::OpenSubtasks(id));
+        }
+        if status == Status::Done && !self.unmet_dependencies(id).is_empty() {
+            return Err(TaskError::Blocked(id));
+        }
+        let done = status == Status::Done;
+        let now = Utc::now();
+        let task = self.task_mut(id).expect("task exists");
+        task.status = status;
+        task.updated_at = now;
+        task.completed_at = done.then_some(now);
+        if done {
+            // Nobody works on a finished task
+            if let Some(session) = task
+                .sessions
+                .last_mut()
+                .filter(|session| session.is_running())
+            {
+                session.end = Some(now.max(session.start));
+            }
+            return Ok(self.schedule_next_occurrence(id));
+        }
+        self.reopen_done_ancestors(id);
+        Ok(None)
+    }
+
+    /// Adds the next occurrence of a repeating task that was just done, due on the next
+    /// occurrence after its due date that isn't already past, or after now if it had none.
+    ///
+    /// The recurrence moves to the new task, so that reopening and finishing the old one again
+    /// doesn't schedule a second occurrence.
+    fn schedule_next_occurrence(&mut self, id: u32) -> Option<u32> {
+        let task = self.task_mut(id)?;
+        let recurrence = task.recurrence.take()?;
+        let previous = task.clone();
+        let now = Utc::now();
+        let due_date = recurrence.next_after_now(previous.due_date.unwrap_or(now), now);
+
+        let next_id = self.add_task(previous.description);
+        let mut next = self.tasks.remove(&next_id).expect("task was just added");
+        self.unindex_task(&next);
+        next.due_date = Some(due_date);
+        next.tags = previous.tags;
+        next.priority = previous.priority;
+        next.parent_id = previous.parent_id;
+        next.project = previous.project;
+        next.recurrence = Some(recurrence);
+        self.tasks.insert(next_id, next);
+        self.index_task(next_id);
+        Some(next_id)
+    }
+
+    /// Makes a task repeat or, with `None`, stop repeating.
+    pub fn set_recurrence(
+        &mut self,
+        id: u32,
+        recurrence: Option<Recurrence>,
+    ) -> Result<(), TaskError> {
+        let Some(task) = self.task_mut(id) else {
+            return Err(TaskError::NotFound(id));
+        };
+        if task.recurrence != recurrence {
+            task.recurrence = recurrence;
+            task.updated_at = chrono::Utc::now();
+        }
         Ok(())
     }
 
-    pub fn mark_done(&mut self, id: u32) -> Result<(), String> {
-        let Some(task) = self.tasks.get_mut(&id) else {
-            return Err(format!("Task with ID {} not found", id));
+    /// Starts a work session on a task, now.
+    pub fn start_tracking(&mut self, id: u32) -> Result<(), TaskError> {
+        self.start_tracking_at(id, Utc::now())
+    }
+
+    pub fn start_tracking_at(&mut self, id: u32, start: DateTime<Utc>) -> Result<(), TaskError> {
+        self.add_session(id, TimeSpan::new(start, None))
+    }
+
+    /// Ends the running work session on a task now, returning how long it lasted.
+    pub fn stop_tracking(&mut self, id: u32) -> Result<Duration, TaskError> {
+        self.stop_tracking_at(id, Utc::now())
+    }
+
+    pub fn stop_tracking_at(&mut self, id: u32, end: DateTime<Utc>) -> Result<Duration, TaskError> {
+        let Some(task) = self.tasks.get(&id) else {
+            return Err(TaskError::NotFound(id));
         };
-        task.status = Status::Done;
-        task.updated_at = chrono::Utc::now();
+        let Some(session) = task.sessions.last().filter(|session| session.is_running()) else {
+            return Err(TaskError::NotTracking(id));
+        };
+        if end < session.start {
+            return Err(TaskError::InvalidSession);
+        }
+        let task = self.task_mut(id).expect("task exists");
+        let session = task.sessions.last_mut().expect("session is running");
+        session.end = Some(end);
+        task.updated_at = Utc::now();
+        Ok(end - session.start)
+    }
+
+    /// Records a work session on a task, running if it has no end.
+    ///
+    /// Sessions of a task can't overlap, and only the latest one can be running.
+    pub fn add_session(&mut self, id: u32, session: TimeSpan) -> Result<(), TaskError> {
+        let Some(task) = self.tasks.get(&id) else {
+            return Err(TaskError::NotFound(id));
+        };
+        if session.end.is_some_and(|end| end <= session.start) {
+            return Err(TaskError::InvalidSession);
+        }
+        if session.is_running() && task.is_tracking() {
+            return Err(TaskError::AlreadyTracking(id));
+        }
+        if task.sessions.iter().any(|other| other.overlaps(&session)) {
+            return Err(TaskError::OverlappingSession(id));
+        }
+        let task = self.task_mut(id).expect("task exists");
+        task.sessions.push(session);
+        task.sessions.sort_by_key(|session| session.start);
+        task.updated_at = Utc::now();
         Ok(())
     }
 
-    pub fn save_as_json(&self, writer: impl std::io::Write) {
-        serde_json::to_writer(writer, &self).expect("cannot serialize repository");
+    /// Time tracked on each task since `since`, or ever with `None`, counting running sessions up
+    /// to `now`.
+    pub fn time_report(&self, since: Option<DateTime<Utc>>, now: DateTime<Utc>) -> TimeReport {
+        let from = since.unwrap_or(DateTime::<Utc>::MIN_UTC);
+        let mut rows: Vec<(Task, Duration)> = self
+            .tasks
+            .values()
+            .map(|task| {
+                let tracked = task
+                    .sessions
+                    .iter()
+                    .fold(Duration::zero(), |total, session| {
+                        total + session.duration_between(from, now, now)
+                    });
+                (task.clone(), tracked)
+            })
+            .filter(|(_, tracked)| *tracked > Duration::zero())
+            .collect();
+        rows.sort_by(|(a, a_tracked), (b, b_tracked)| {
+            b_tracked.cmp(a_tracked).then(a.id.cmp(&b.id))
+        });
+        TimeReport { since, rows }
+    }
+
+    pub fn save_as_json(&self, writer: impl std::io::Write) -> Result<(), TaskError> {
+        write_json(writer, self)
     }
+
+    /// Saves the tasks as JSON to `path`, through a temporary file renamed over it so that a
+    /// crash mid-write leaves the previous save intact.
+    pub fn save_atomic(&self, path: impl AsRef<Path>) -> Result<(), TaskError> {
+        atomic::replace(path.as_ref(), false, |writer| self.save_as_json(writer))
+    }
+
+    /// Like [`save_atomic`](Self::save_atomic), also keeping the previous save in `path.bak`.
+    pub fn save_atomic_with_backup(&self, path: impl AsRef<Path>) -> Result<(), TaskError> {
+        atomic::replace(path.as_ref(), true, |writer| self.save_as_json(writer))
+    }
+}
+
+fn write_json(writer: impl std::io::Write, value: &impl Serialize) -> Result<(), TaskError> {
+    serde_json::to_writer(writer, value).map_err(|error| {
+        // Failing to write is an I/O problem, not a problem with the tasks
+        if error.is_io() {
+            TaskError::Io(error.into())
+        } else {
+            TaskError::Serialization(error)
+        }
+    })
 }
 
 impl Display for TaskRepository {
@@ -151,6 +1016,51 @@ impl Display for TaskRepository {
     }
 }
 
+/// Tree view of a [`TaskRepository`], see [`TaskRepository::tree`].
+pub struct TaskTree<'a> {
+    repository: &'a TaskRepository,
+    project: Option<String>,
+}
+
+impl TaskTree<'_> {
+    fn write_subtasks(&self, f: &mut Formatter<'_>, id: u32, indent: &str) -> std::fmt::Result {
+        let subtasks = self.repository.get_subtasks(id);
+        for (i, task) in subtasks.iter().enumerate() {
+            let last = i + 1 == subtasks.len();
+            let (branch, continuation) = if last {
+                ("└── ", "    ")
+            } else {
+                ("├── ", "│   ")
+            };
+            writeln!(f, "{}{}{}", indent, branch, task)?;
+            self.write_subtasks(f, task.id, &format!("{}{}", indent, continuation))?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for TaskTree<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let tasks = &self.repository.tasks;
+        let mut roots: Vec<&Task> = tasks
+            .values()
+            .filter(|task| task.parent_id.is_none_or(|id| !tasks.contains_key(&id)))
+            .filter(|task| {
+                self.project
+                    .as_ref()
+                    .is_none_or(|project| task.project == *project)
+            })
+            .collect();
+        roots.sort_by_key(|task| task.id);
+
+        for task in roots {
+            writeln!(f, "{}", task)?;
+            self.write_subtasks(f, task.id, "")?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -256,11 +1166,11 @@ mod next_id_tests {
 
         // Serialize the repository
         let mut buffer = Vec::new();
-        original_repo.save_as_json(&mut buffer);
+        original_repo.save_as_json(&mut buffer).unwrap();
         let json = String::from_utf8(buffer).unwrap();
 
         // Create a new repository from this JSON
-        let loaded_repo = TaskRepository::new_from_json(&json);
+        let loaded_repo = TaskRepository::new_from_json(&json).unwrap();
 
         // Verify the next_id was preserved
         assert_eq!(
@@ -310,7 +1220,7 @@ mod next_id_tests {
         "#;
 
         // Load the repository
-        let repo = TaskRepository::new_from_json(json);
+        let repo = TaskRepository::new_from_json(json).unwrap();
 
         // Verify the specified next_id is used
         assert_eq!(
@@ -354,7 +1264,7 @@ mod update_task_tests {
         let mut repo = TaskRepository::new();
         let result = repo.update_task(1, "Updated task".to_string());
         assert!(result.is_err());
-        assert_eq!(result.unwrap_err(), "Task with ID 1 not found");
+        assert_eq!(result.unwrap_err().to_string(), "Task with ID 1 not found");
     }
 
     #[test]
@@ -374,9 +1284,7 @@ mod update_task_tests {
         let mut repo = TaskRepository::new();
         let id = repo.add_task("Original task".to_string());
 
-        // Assuming tasks start with Status::Todo
-        // First update the status to something else if your code supports it
-        // For example: repo.set_status(id, Status::InProgress);
+        repo.set_status(id, Status::InProgress).unwrap();
 
         // Now update the description
         let result = repo.update_task(id, "Updated description".to_string());
@@ -384,7 +1292,7 @@ mod update_task_tests {
 
         // Check that status is preserved
         let updated_task = repo.get_task(id).unwrap();
-        assert_eq!(updated_task.status, Status::Todo); // Or whatever the status was before
+        assert_eq!(updated_task.status, Status::InProgress);
     }
 
     #[test]
@@ -484,20 +1392,23 @@ mod delete_task_tests {
         let id = repo.add_task("Test task".to_string());
 
         // Act
-        repo.delete_task(id);
+        repo.delete_task(id).unwrap();
 
         // Assert
         assert!(repo.get_task(id).is_none());
     }
 
     #[test]
-    fn test_delete_nonexistent_task_does_not_panic() {
+    fn test_delete_nonexistent_task_returns_error() {
         // Arrange
         let mut repo = TaskRepository::new();
         let nonexistent_id = 9999;
 
-        // Act & Assert - should not panic
-        repo.delete_task(nonexistent_id);
+        // Act
+        let result = repo.delete_task(nonexistent_id);
+
+        // Assert
+        assert!(matches!(result, Err(TaskError::NotFound(9999))));
     }
 
     #[test]
@@ -509,7 +1420,7 @@ mod delete_task_tests {
         let id3 = repo.add_task("Task 3".to_string());
 
         // Act
-        repo.delete_task(id2);
+        repo.delete_task(id2).unwrap();
 
         // Assert
         assert!(repo.get_task(id1).is_some());
@@ -527,7 +1438,7 @@ mod delete_task_tests {
         let _ = repo.get_task(id).unwrap().clone();
 
         // Act
-        repo.delete_task(id);
+        repo.delete_task(id).unwrap();
 
         // Assert
         assert!(repo.get_task(id).is_none());
@@ -545,7 +1456,7 @@ mod delete_task_tests {
         let id1 = repo.add_task(description.clone());
 
         // Act
-        repo.delete_task(id1);
+        repo.delete_task(id1).unwrap();
         let id2 = repo.add_task(description);
 
         // Assert
@@ -566,7 +1477,7 @@ mod delete_task_tests {
 
         // Act
         for id in ids {
-            repo.delete_task(id);
+            repo.delete_task(id).unwrap();
         }
 
         // Assert - repository should be empty
@@ -583,10 +1494,11 @@ mod delete_task_tests {
         let id = repo.add_task("Task to delete twice".to_string());
 
         // Act
-        repo.delete_task(id);
+        repo.delete_task(id).unwrap();
 
-        // Should not panic when deleting again
-        repo.delete_task(id);
+        // Deleting again reports the task is gone
+        let result = repo.delete_task(id);
+        assert!(matches!(result, Err(TaskError::NotFound(_))));
 
         // Assert
         assert!(repo.get_task(id).is_none());
@@ -623,7 +1535,7 @@ mod mark_in_progress_tests {
         // Assert
         assert!(result.is_err());
         let error = result.unwrap_err();
-        assert!(error.contains(&nonexistent_id.to_string()));
+        assert!(error.to_string().contains(&nonexistent_id.to_string()));
     }
 
     #[test]
@@ -726,7 +1638,7 @@ mod mark_in_progress_tests {
         // Arrange
         let mut repo = TaskRepository::new();
         let id = repo.add_task("Task to delete".to_string());
-        repo.delete_task(id);
+        repo.delete_task(id).unwrap();
 
         // Act
         let result = repo.mark_in_progress(id);
@@ -734,7 +1646,7 @@ mod mark_in_progress_tests {
         // Assert
         assert!(result.is_err());
         let error = result.unwrap_err();
-        assert!(error.contains(&id.to_string()));
+        assert!(error.to_string().contains(&id.to_string()));
     }
 }
 
@@ -768,7 +1680,7 @@ mod mark_done_tests {
         // Assert
         assert!(result.is_err());
         let error = result.unwrap_err();
-        assert!(error.contains(&nonexistent_id.to_string()));
+        assert!(error.to_string().contains(&nonexistent_id.to_string()));
     }
 
     #[test]
@@ -891,7 +1803,7 @@ mod mark_done_tests {
         // Arrange
         let mut repo = TaskRepository::new();
         let id = repo.add_task("Task to delete".to_string());
-        repo.delete_task(id);
+        repo.delete_task(id).unwrap();
 
         // Act
         let result = repo.mark_done(id);
@@ -899,7 +1811,7 @@ mod mark_done_tests {
         // Assert
         assert!(result.is_err());
         let error = result.unwrap_err();
-        assert!(error.contains(&id.to_string()));
+        assert!(error.to_string().contains(&id.to_string()));
     }
 
     #[test]
@@ -1024,7 +1936,7 @@ mod get_tasks_with_status_tests {
         assert_eq!(repo.get_tasks_with_status(Status::Todo).len(), 1);
 
         // Delete the task
-        repo.delete_task(id);
+        repo.delete_task(id).unwrap();
 
         // Act
         let tasks = repo.get_tasks_with_status(Status::Todo);
@@ -1100,3 +2012,1058 @@ mod get_tasks_with_status_tests {
         assert_eq!(tasks[0].status, Status::Todo);
     }
 }
+
+#[cfg(test)]
+mod set_status_tests {
+    use super::*;
+
+    /// Adds a task and moves it to `status` along a legal path.
+    fn task_with_status(repo: &mut TaskRepository, status: Status) -> u32 {
+        let id = repo.add_task("Task".to_string());
+        match status {
+            Status::Todo => {}
+            Status::InProgress => repo.mark_in_progress(id).unwrap(),
+            Status::Done => {
+                repo.mark_done(id).unwrap();
+            }
+        }
+        id
+    }
+
+    fn assert_transition(from: Status, to: Status, allowed: bool) {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let id = task_with_status(&mut repo, from.clone());
+
+        // Act
+        let result = repo.set_status(id, to.clone());
+
+        // Assert
+        assert_eq!(result.is_ok(), allowed, "{from} -> {to}");
+        assert_eq!(from.can_transition_to(&to), allowed);
+        let expected = if allowed { to } else { from };
+        assert_eq!(repo.get_task(id).unwrap().status, expected);
+    }
+
+    #[test]
+    fn test_todo_to_todo_is_allowed() {
+        assert_transition(Status::Todo, Status::Todo, true);
+    }
+
+    #[test]
+    fn test_todo_to_in_progress_is_allowed() {
+        assert_transition(Status::Todo, Status::InProgress, true);
+    }
+
+    #[test]
+    fn test_todo_to_done_is_allowed() {
+        assert_transition(Status::Todo, Status::Done, true);
+    }
+
+    #[test]
+    fn test_in_progress_to_todo_is_allowed() {
+        assert_transition(Status::InProgress, Status::Todo, true);
+    }
+
+    #[test]
+    fn test_in_progress_to_in_progress_is_allowed() {
+        assert_transition(Status::InProgress, Status::InProgress, true);
+    }
+
+    #[test]
+    fn test_in_progress_to_done_is_allowed() {
+        assert_transition(Status::InProgress, Status::Done, true);
+    }
+
+    #[test]
+    fn test_done_to_todo_is_rejected() {
+        assert_transition(Status::Done, Status::Todo, false);
+    }
+
+    #[test]
+    fn test_done_to_in_progress_is_allowed() {
+        assert_transition(Status::Done, Status::InProgress, true);
+    }
+
+    #[test]
+    fn test_done_to_done_is_allowed() {
+        assert_transition(Status::Done, Status::Done, true);
+    }
+
+    #[test]
+    fn test_rejected_transition_explains_why() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let id = task_with_status(&mut repo, Status::Done);
+        let before = repo.get_task(id).unwrap().updated_at;
+
+        // Act
+        let error = repo.set_status(id, Status::Todo).unwrap_err();
+
+        // Assert
+        assert_eq!(
+            error.to_string(),
+            format!("Task with ID {} cannot go from Done to To Do", id)
+        );
+        assert_eq!(repo.get_task(id).unwrap().updated_at, before);
+    }
+
+    #[test]
+    fn test_set_status_of_nonexistent_task_returns_error() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+
+        // Act
+        let result = repo.set_status(999, Status::Done);
+
+        // Assert
+        assert!(matches!(result, Err(TaskError::NotFound(999))));
+    }
+
+    #[test]
+    fn test_set_status_updates_timestamp_only_on_change() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Task".to_string());
+        let created = repo.get_task(id).unwrap().updated_at;
+        std::thread::sleep(std::time::Duration::from_millis(5));
+
+        // Act
+        repo.set_status(id, Status::Todo).unwrap();
+        let unchanged = repo.get_task(id).unwrap().updated_at;
+        repo.set_status(id, Status::InProgress).unwrap();
+        let changed = repo.get_task(id).unwrap().updated_at;
+
+        // Assert
+        assert_eq!(unchanged, created);
+        assert!(changed > created);
+    }
+}
+
+#[cfg(test)]
+mod due_date_tests {
+    use super::*;
+
+    fn task_due_in(repo: &mut TaskRepository, description: &str, due_in: Duration) -> u32 {
+        let id = repo.add_task(description.to_string());
+        repo.set_due_date(id, Some(Utc::now() + due_in)).unwrap();
+        id
+    }
+
+    #[test]
+    fn test_new_task_has_no_due_date() {
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Task".to_string());
+
+        assert_eq!(repo.get_task(id).unwrap().due_date, None);
+    }
+
+    #[test]
+    fn test_set_and_clear_due_date() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Task".to_string());
+        let due = parse_due_date("2030-01-31").unwrap();
+
+        // Act & Assert
+        repo.set_due_date(id, Some(due)).unwrap();
+        assert_eq!(repo.get_task(id).unwrap().due_date, Some(due));
+        repo.set_due_date(id, None).unwrap();
+        assert_eq!(repo.get_task(id).unwrap().due_date, None);
+    }
+
+    #[test]
+    fn test_set_due_date_of_nonexistent_task_returns_error() {
+        let mut repo = TaskRepository::new();
+
+        let result = repo.set_due_date(999, None);
+
+        assert!(matches!(result, Err(TaskError::NotFound(999))));
+    }
+
+    #[test]
+    fn test_get_overdue_tasks_sorted_by_deadline() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let a_bit_late = task_due_in(&mut repo, "A bit late", Duration::hours(-1));
+        let very_late = task_due_in(&mut repo, "Very late", Duration::days(-3));
+        let done = task_due_in(&mut repo, "Done late", Duration::days(-2));
+        repo.mark_done(done).unwrap();
+        task_due_in(&mut repo, "Upcoming", Duration::hours(1));
+        repo.add_task("No deadline".to_string());
+
+        // Act
+        let overdue = repo.get_overdue_tasks();
+
+        // Assert
+        let ids: Vec<u32> = overdue.iter().map(|task| task.id).collect();
+        assert_eq!(ids, vec![very_late, a_bit_late]);
+    }
+
+    #[test]
+    fn test_get_tasks_due_within_sorted_by_deadline() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let tomorrow = task_due_in(&mut repo, "Tomorrow", Duration::days(1));
+        let in_an_hour = task_due_in(&mut repo, "In an hour", Duration::hours(1));
+        task_due_in(&mut repo, "Next month", Duration::days(30));
+        task_due_in(&mut repo, "Overdue", Duration::hours(-1));
+
+        // Act
+        let upcoming = repo.get_tasks_due_within(Duration::days(7));
+
+        // Assert
+        let ids: Vec<u32> = upcoming.iter().map(|task| task.id).collect();
+        assert_eq!(ids, vec![in_an_hour, tomorrow]);
+    }
+
+    #[test]
+    fn test_due_date_survives_json_round_trip() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Task".to_string());
+        let due = parse_due_date("2030-01-31 09:30").unwrap();
+        repo.set_due_date(id, Some(due)).unwrap();
+        let mut json = Vec::new();
+
+        // Act
+        repo.save_as_json(&mut json).unwrap();
+        let loaded = TaskRepository::new_from_json(std::str::from_utf8(&json).unwrap()).unwrap();
+
+        // Assert
+        assert_eq!(loaded.get_task(id).unwrap().due_date, Some(due));
+    }
+
+    #[test]
+    fn test_tasks_saved_without_due_dates_still_load() {
+        let json = r#"{"tasks":{"1":{"id":1,"description":"Old task","status":"Todo","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z"}},"next_id":2}"#;
+
+        let repo = TaskRepository::new_from_json(json).unwrap();
+
+        assert_eq!(repo.get_task(1).unwrap().due_date, None);
+    }
+
+    #[test]
+    fn test_display_shows_due_date() {
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Write report".to_string());
+        repo.set_due_date(id, Some(parse_due_date("2030-01-31 09:30").unwrap()))
+            .unwrap();
+
+        assert_eq!(
+            repo.get_task(id).unwrap().to_string(),
+            "1: Write report [Status: To Do] [Due: 2030-01-31 09:30 UTC]"
+        );
+    }
+
+    #[test]
+    fn test_parse_due_date_formats() {
+        assert_eq!(
+            parse_due_date("2030-01-31").unwrap().to_rfc3339(),
+            "2030-01-31T23:59:59+00:00"
+        );
+        assert_eq!(
+            parse_due_date("2030-01-31 09:30").unwrap().to_rfc3339(),
+            "2030-01-31T09:30:00+00:00"
+        );
+        assert_eq!(
+            parse_due_date("2030-01-31T09:30:00+02:00")
+                .unwrap()
+                .to_rfc3339(),
+            "2030-01-31T07:30:00+00:00"
+        );
+        assert!(matches!(
+            parse_due_date("next tuesday"),
+            Err(TaskError::InvalidDueDate(_))
+        ));
+    }
+}
+
+#[cfg(test)]
+mod error_tests {
+    use super::*;
+
+    #[test]
+    fn test_new_from_json_rejects_malformed_data() {
+        let result = TaskRepository::new_from_json("{not json");
+
+        assert!(matches!(result, Err(TaskError::Serialization(_))));
+    }
+
+    #[test]
+    fn test_save_as_json_reports_io_errors() {
+        struct FailingWriter;
+
+        impl std::io::Write for FailingWriter {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("disk full"))
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut repo = TaskRepository::new();
+        repo.add_task("Task".to_string());
+
+        let result = repo.save_as_json(FailingWriter);
+
+        assert!(matches!(result, Err(TaskError::Io(_))));
+    }
+}
+
+#[cfg(test)]
+mod tag_tests {
+    use super::*;
+
+    fn ids(tasks: &[Task]) -> Vec<u32> {
+        tasks.iter().map(|task| task.id).collect()
+    }
+
+    #[test]
+    fn test_add_and_remove_tags() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Fix bug".to_string());
+
+        // Act & Assert
+        repo.add_tag(id, "work").unwrap();
+        repo.add_tag(id, "urgent").unwrap();
+        assert_eq!(ids(&repo.get_tasks_with_tag("work")), vec![id]);
+        assert_eq!(repo.get_tags(), vec!["urgent", "work"]);
+
+        repo.remove_tag(id, "work").unwrap();
+        assert!(repo.get_tasks_with_tag("work").is_empty());
+        assert_eq!(repo.get_tags(), vec!["urgent"]);
+    }
+
+    #[test]
+    fn test_tags_are_case_insensitive_and_trimmed() {
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Fix bug".to_string());
+
+        repo.add_tag(id, " Work ").unwrap();
+        repo.add_tag(id, "WORK").unwrap();
+
+        assert_eq!(repo.get_task(id).unwrap().tags.len(), 1);
+        assert_eq!(ids(&repo.get_tasks_with_tag("work")), vec![id]);
+    }
+
+    #[test]
+    fn test_tag_errors() {
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Fix bug".to_string());
+
+        assert!(matches!(
+            repo.add_tag(999, "work"),
+            Err(TaskError::NotFound(999))
+        ));
+        assert!(matches!(
+            repo.remove_tag(999, "work"),
+            Err(TaskError::NotFound(999))
+        ));
+        assert!(matches!(repo.add_tag(id, "  "), Err(TaskError::EmptyTag)));
+    }
+
+    #[test]
+    fn test_removing_missing_tag_is_noop() {
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Fix bug".to_string());
+        let updated_at = repo.get_task(id).unwrap().updated_at;
+
+        repo.remove_tag(id, "work").unwrap();
+
+        assert_eq!(repo.get_task(id).unwrap().updated_at, updated_at);
+    }
+
+    #[test]
+    fn test_get_tasks_with_tag_sorted_by_id() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let first = repo.add_task("First".to_string());
+        let second = repo.add_task("Second".to_string());
+        let untagged = repo.add_task("Untagged".to_string());
+        repo.add_tag(second, "work").unwrap();
+        repo.add_tag(first, "work").unwrap();
+        repo.add_tag(untagged, "home").unwrap();
+
+        // Act
+        let tasks = repo.get_tasks_with_tag("work");
+
+        // Assert
+        assert_eq!(ids(&tasks), vec![first, second]);
+    }
+
+    #[test]
+    fn test_delete_task_removes_it_from_tag_index() {
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Fix bug".to_string());
+        repo.add_tag(id, "work").unwrap();
+
+        repo.delete_task(id).unwrap();
+
+        assert!(repo.get_tasks_with_tag("work").is_empty());
+        assert!(repo.get_tags().is_empty());
+    }
+
+    #[test]
+    fn test_tag_index_is_rebuilt_on_load() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Fix bug".to_string());
+        repo.add_tag(id, "work").unwrap();
+        let mut json = Vec::new();
+
+        // Act
+        repo.save_as_json(&mut json).unwrap();
+        let loaded = TaskRepository::new_from_json(std::str::from_utf8(&json).unwrap()).unwrap();
+
+        // Assert
+        assert_eq!(ids(&loaded.get_tasks_with_tag("work")), vec![id]);
+    }
+
+    #[test]
+    fn test_display_shows_tags() {
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Fix bug".to_string());
+        repo.add_tag(id, "work").unwrap();
+        repo.add_tag(id, "urgent").unwrap();
+
+        assert_eq!(
+            repo.get_task(id).unwrap().to_string(),
+            "1: Fix bug [Status: To Do] [Tags: urgent, work]"
+        );
+    }
+}
+
+#[cfg(test)]
+mod priority_tests {
+    use super::*;
+
+    fn ids(tasks: &[Task]) -> Vec<u32> {
+        tasks.iter().map(|task| task.id).collect()
+    }
+
+    #[test]
+    fn test_new_task_has_medium_priority() {
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Task".to_string());
+
+        assert_eq!(repo.get_task(id).unwrap().priority, Priority::Medium);
+    }
+
+    #[test]
+    fn test_set_priority() {
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Task".to_string());
+
+        repo.set_priority(id, Priority::Urgent).unwrap();
+
+        assert_eq!(repo.get_task(id).unwrap().priority, Priority::Urgent);
+        assert_eq!(
+            repo.get_task(id).unwrap().to_string(),
+            "1: Task [Status: To Do] [Priority: Urgent]"
+        );
+    }
+
+    #[test]
+    fn test_set_priority_of_nonexistent_task_returns_error() {
+        let mut repo = TaskRepository::new();
+
+        let result = repo.set_priority(999, Priority::High);
+
+        assert!(matches!(result, Err(TaskError::NotFound(999))));
+    }
+
+    #[test]
+    fn test_get_tasks_sorted_by_priority() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let medium = repo.add_task("Medium".to_string());
+        let low = repo.add_task("Low".to_string());
+        let urgent = repo.add_task("Urgent".to_string());
+        let other_medium = repo.add_task("Other medium".to_string());
+        repo.set_priority(low, Priority::Low).unwrap();
+        repo.set_priority(urgent, Priority::Urgent).unwrap();
+
+        // Act
+        let tasks = repo.get_tasks_sorted_by(SortKey::Priority);
+
+        // Assert
+        assert_eq!(ids(&tasks), vec![urgent, medium, other_medium, low]);
+    }
+
+    #[test]
+    fn test_get_tasks_sorted_by_due_date_puts_undated_last() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let undated = repo.add_task("Undated".to_string());
+        let later = repo.add_task("Later".to_string());
+        let sooner = repo.add_task("Sooner".to_string());
+        repo.set_due_date(later, Some(parse_due_date("2030-02-01").unwrap()))
+            .unwrap();
+        repo.set_due_date(sooner, Some(parse_due_date("2030-01-01").unwrap()))
+            .unwrap();
+
+        // Act
+        let tasks = repo.get_tasks_sorted_by(SortKey::DueDate);
+
+        // Assert
+        assert_eq!(ids(&tasks), vec![sooner, later, undated]);
+    }
+
+    #[test]
+    fn test_get_tasks_sorted_by_id() {
+        let mut repo = TaskRepository::new();
+        for i in 0..10 {
+            repo.add_task(format!("Task {}", i));
+        }
+
+        let tasks = repo.get_tasks_sorted_by(SortKey::Id);
+
+        assert_eq!(ids(&tasks), (1..=10).collect::<Vec<u32>>());
+    }
+
+    #[test]
+    fn test_tasks_saved_without_priority_load_as_medium() {
+        let json = r#"{"tasks":{"1":{"id":1,"description":"Old task","status":"Todo","created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z"}},"next_id":2}"#;
+
+        let repo = TaskRepository::new_from_json(json).unwrap();
+
+        assert_eq!(repo.get_task(1).unwrap().priority, Priority::Medium);
+    }
+}
+
+#[cfg(test)]
+mod subtask_tests {
+    use super::*;
+
+    fn ids(tasks: &[Task]) -> Vec<u32> {
+        tasks.iter().map(|task| task.id).collect()
+    }
+
+    #[test]
+    fn test_add_subtask() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let parent = repo.add_task("Parent".to_string());
+
+        // Act
+        let child = repo.add_subtask(parent, "Child".to_string()).unwrap();
+
+        // Assert
+        assert_eq!(repo.get_task(child).unwrap().parent_id, Some(parent));
+        assert_eq!(ids(&repo.get_subtasks(parent)), vec![child]);
+    }
+
+    #[test]
+    fn test_add_subtask_of_nonexistent_task_adds_nothing() {
+        let mut repo = TaskRepository::new();
+
+        let result = repo.add_subtask(999, "Child".to_string());
+
+        assert!(matches!(result, Err(TaskError::NotFound(999))));
+        assert!(repo.tasks.is_empty());
+    }
+
+    #[test]
+    fn test_set_parent_rejects_cycles() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let a = repo.add_task("A".to_string());
+        let b = repo.add_subtask(a, "B".to_string()).unwrap();
+        let c = repo.add_subtask(b, "C".to_string()).unwrap();
+
+        // Act & Assert
+        assert_eq!(
+            repo.set_parent(a, Some(c)).unwrap_err().to_string(),
+            "Task with ID 1 cannot be a subtask of 3, it would be its own ancestor"
+        );
+        assert!(repo.set_parent(a, Some(a)).is_err());
+        assert_eq!(repo.get_task(a).unwrap().parent_id, None);
+    }
+
+    #[test]
+    fn test_set_parent_to_none_makes_top_level_task() {
+        let mut repo = TaskRepository::new();
+        let parent = repo.add_task("Parent".to_string());
+        let child = repo.add_subtask(parent, "Child".to_string()).unwrap();
+
+        repo.set_parent(child, None).unwrap();
+
+        assert!(repo.get_subtasks(parent).is_empty());
+    }
+
+    #[test]
+    fn test_parent_cannot_be_done_with_open_subtasks() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let parent = repo.add_task("Parent".to_string());
+        let child = repo.add_subtask(parent, "Child".to_string()).unwrap();
+
+        // Act & Assert
+        assert!(matches!(
+            repo.mark_done(parent),
+            Err(TaskError::OpenSubtasks(1))
+        ));
+        repo.mark_done(child).unwrap();
+        repo.mark_done(parent).unwrap();
+        assert_eq!(repo.get_task(parent).unwrap().status, Status::Done);
+    }
+
+    #[test]
+    fn test_reopening_subtask_reopens_done_ancestors() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let grandparent = repo.add_task("Grandparent".to_string());
+        let parent = repo.add_subtask(grandparent, "Parent".to_string()).unwrap();
+        let child = repo.add_subtask(parent, "Child".to_string()).unwrap();
+        for id in [child, parent, grandparent] {
+            repo.mark_done(id).unwrap();
+        }
+
+        // Act
+        repo.mark_in_progress(child).unwrap();
+
+        // Assert
+        assert_eq!(repo.get_task(parent).unwrap().status, InProgress);
+        assert_eq!(repo.get_task(grandparent).unwrap().status, InProgress);
+    }
+
+    #[test]
+    fn test_adding_open_subtask_reopens_done_parent() {
+        let mut repo = TaskRepository::new();
+        let parent = repo.add_task("Parent".to_string());
+        repo.mark_done(parent).unwrap();
+
+        repo.add_subtask(parent, "Child".to_string()).unwrap();
+
+        assert_eq!(repo.get_task(parent).unwrap().status, InProgress);
+    }
+
+    #[test]
+    fn test_delete_task_deletes_subtasks() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let parent = repo.add_task("Parent".to_string());
+        let child = repo.add_subtask(parent, "Child".to_string()).unwrap();
+        let grandchild = repo.add_subtask(child, "Grandchild".to_string()).unwrap();
+        let other = repo.add_task("Other".to_string());
+        repo.add_tag(grandchild, "work").unwrap();
+
+        // Act
+        repo.delete_task(parent).unwrap();
+
+        // Assert
+        assert_eq!(ids(&repo.get_tasks_sorted_by(SortKey::Id)), vec![other]);
+        assert!(repo.get_tags().is_empty());
+    }
+
+    #[test]
+    fn test_tree_rendering() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let parent = repo.add_task("Parent".to_string());
+        let first = repo.add_subtask(parent, "First".to_string()).unwrap();
+        repo.add_subtask(first, "Nested".to_string()).unwrap();
+        repo.add_subtask(parent, "Second".to_string()).unwrap();
+        repo.add_task("Other".to_string());
+
+        // Act
+        let tree = repo.tree().to_string();
+
+        // Assert
+        assert_eq!(
+            tree,
+            "1: Parent [Status: To Do]\n\
+             ├── 2: First [Status: To Do] [Subtask of: 1]\n\
+             │   └── 3: Nested [Status: To Do] [Subtask of: 2]\n\
+             └── 4: Second [Status: To Do] [Subtask of: 1]\n\
+             5: Other [Status: To Do]\n"
+        );
+    }
+}
+
+#[cfg(test)]
+mod recurrence_tests {
+    use super::*;
+
+    fn repeating_task(repo: &mut TaskRepository, recurrence: &str, due: Option<&str>) -> u32 {
+        let id = repo.add_task("Standup".to_string());
+        repo.set_recurrence(id, Some(recurrence.parse().unwrap()))
+            .unwrap();
+        repo.set_due_date(id, due.map(|due| parse_due_date(due).unwrap()))
+            .unwrap();
+        id
+    }
+
+    #[test]
+    fn test_finishing_repeating_task_schedules_next_occurrence() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let tomorrow = Utc::now() + Duration::days(1);
+        let id = repeating_task(&mut repo, "weekly", None);
+        repo.set_due_date(id, Some(tomorrow)).unwrap();
+        repo.add_tag(id, "work").unwrap();
+        repo.set_priority(id, Priority::High).unwrap();
+
+        // Act
+        let next_id = repo.mark_done(id).unwrap().unwrap();
+
+        // Assert
+        let next = repo.get_task(next_id).unwrap();
+        assert_eq!(next.status, Todo);
+        assert_eq!(next.description, "Standup");
+        assert_eq!(next.due_date, Some(tomorrow + Duration::weeks(1)));
+        assert_eq!(next.priority, Priority::High);
+        assert_eq!(next.recurrence, Some(Recurrence::Weeks(1)));
+        assert_eq!(repo.get_tasks_with_tag("work").len(), 2);
+        assert_eq!(repo.get_task(id).unwrap().recurrence, None);
+    }
+
+    #[test]
+    fn test_overdue_repeating_task_skips_missed_occurrences() {
+        let mut repo = TaskRepository::new();
+        let id = repeating_task(&mut repo, "daily", Some("2020-01-01 09:00"));
+
+        let next_id = repo.mark_done(id).unwrap().unwrap();
+
+        let due = repo.get_task(next_id).unwrap().due_date.unwrap();
+        assert!(due > Utc::now() && due <= Utc::now() + Duration::days(1));
+        assert_eq!(due.format("%H:%M").to_string(), "09:00");
+    }
+
+    #[test]
+    fn test_repeating_task_without_due_date_is_next_due_after_now() {
+        let mut repo = TaskRepository::new();
+        let id = repeating_task(&mut repo, "every 3 days", None);
+        let before = Utc::now();
+
+        let next_id = repo.mark_done(id).unwrap().unwrap();
+
+        let due = repo.get_task(next_id).unwrap().due_date.unwrap();
+        assert!(due >= before + Duration::days(3));
+        assert!(due <= Utc::now() + Duration::days(3));
+    }
+
+    #[test]
+    fn test_reopening_and_finishing_again_schedules_nothing() {
+        let mut repo = TaskRepository::new();
+        let id = repeating_task(&mut repo, "daily", None);
+        repo.mark_done(id).unwrap();
+        repo.mark_in_progress(id).unwrap();
+
+        let next = repo.mark_done(id).unwrap();
+
+        assert_eq!(next, None);
+        assert_eq!(repo.get_tasks_sorted_by(SortKey::Id).len(), 2);
+    }
+
+    #[test]
+    fn test_finishing_task_that_does_not_repeat_schedules_nothing() {
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Once".to_string());
+
+        assert_eq!(repo.mark_done(id).unwrap(), None);
+    }
+
+    #[test]
+    fn test_recurrence_survives_json_round_trip() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let id = repeating_task(&mut repo, "mon,wed,fri", None);
+        let mut json = Vec::new();
+
+        // Act
+        repo.save_as_json(&mut json).unwrap();
+        let loaded = TaskRepository::new_from_json(std::str::from_utf8(&json).unwrap()).unwrap();
+
+        // Assert
+        assert_eq!(
+            loaded.get_task(id).unwrap().to_string(),
+            "1: Standup [Status: To Do] [Repeats: mon,wed,fri]"
+        );
+    }
+}
+
+#[cfg(test)]
+mod time_tracking_tests {
+    use super::*;
+
+    fn utc(input: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(input).unwrap().into()
+    }
+
+    #[test]
+    fn test_start_and_stop_accumulate_tracked_time() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Task".to_string());
+
+        // Act
+        repo.start_tracking_at(id, utc("2025-01-06T09:00:00Z"))
+            .unwrap();
+        let first = repo
+            .stop_tracking_at(id, utc("2025-01-06T10:30:00Z"))
+            .unwrap();
+        repo.start_tracking_at(id, utc("2025-01-06T14:00:00Z"))
+            .unwrap();
+
+        // Assert
+        let task = repo.get_task(id).unwrap();
+        assert_eq!(first, Duration::minutes(90));
+        assert!(task.is_tracking());
+        assert_eq!(
+            task.tracked_time(utc("2025-01-06T14:30:00Z")),
+            Duration::hours(2)
+        );
+    }
+
+    #[test]
+    fn test_tracking_errors() {
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Task".to_string());
+
+        assert!(matches!(
+            repo.stop_tracking(id),
+            Err(TaskError::NotTracking(1))
+        ));
+        assert!(matches!(
+            repo.start_tracking(42),
+            Err(TaskError::NotFound(42))
+        ));
+        repo.start_tracking_at(id, utc("2025-01-06T09:00:00Z"))
+            .unwrap();
+        assert!(matches!(
+            repo.start_tracking(id),
+            Err(TaskError::AlreadyTracking(1))
+        ));
+        assert!(matches!(
+            repo.stop_tracking_at(id, utc("2025-01-06T08:00:00Z")),
+            Err(TaskError::InvalidSession)
+        ));
+    }
+
+    #[test]
+    fn test_add_session_rejects_overlaps() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Task".to_string());
+        let morning = TimeSpan::new(
+            utc("2025-01-06T09:00:00Z"),
+            Some(utc("2025-01-06T12:00:00Z")),
+        );
+        repo.add_session(id, morning).unwrap();
+
+        // Act
+        let overlapping = repo.add_session(
+            id,
+            TimeSpan::new(
+                utc("2025-01-06T11:00:00Z"),
+                Some(utc("2025-01-06T13:00:00Z")),
+            ),
+        );
+        let running_since_morning =
+            repo.add_session(id, TimeSpan::new(utc("2025-01-06T10:00:00Z"), None));
+        let backwards = repo.add_session(
+            id,
+            TimeSpan::new(
+                utc("2025-01-06T15:00:00Z"),
+                Some(utc("2025-01-06T14:00:00Z")),
+            ),
+        );
+        let earlier = TimeSpan::new(
+            utc("2025-01-06T07:00:00Z"),
+            Some(utc("2025-01-06T09:00:00Z")),
+        );
+        repo.add_session(id, earlier).unwrap();
+
+        // Assert
+        assert!(matches!(overlapping, Err(TaskError::OverlappingSession(1))));
+        assert!(matches!(
+            running_since_morning,
+            Err(TaskError::OverlappingSession(1))
+        ));
+        assert!(matches!(backwards, Err(TaskError::InvalidSession)));
+        assert_eq!(repo.get_task(id).unwrap().sessions(), [earlier, morning]);
+    }
+
+    #[test]
+    fn test_marking_done_stops_tracking() {
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Task".to_string());
+        repo.start_tracking(id).unwrap();
+
+        repo.mark_done(id).unwrap();
+
+        let task = repo.get_task(id).unwrap();
+        assert!(!task.is_tracking());
+        assert_eq!(task.sessions().len(), 1);
+    }
+
+    #[test]
+    fn test_week_report() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let now = utc("2025-01-08T12:00:00Z");
+        let short = repo.add_task("Short".to_string());
+        let long = repo.add_task("Long".to_string());
+        let last_week = repo.add_task("Last week".to_string());
+        repo.add_session(
+            short,
+            TimeSpan::new(
+                utc("2025-01-07T09:00:00Z"),
+                Some(utc("2025-01-07T09:45:00Z")),
+            ),
+        )
+        .unwrap();
+        repo.add_session(
+            long,
+            TimeSpan::new(
+                utc("2025-01-05T23:00:00Z"),
+                Some(utc("2025-01-06T01:00:00Z")),
+            ),
+        )
+        .unwrap();
+        repo.start_tracking_at(long, utc("2025-01-08T11:00:00Z"))
+            .unwrap();
+        repo.add_session(
+            last_week,
+            TimeSpan::new(
+                utc("2025-01-01T09:00:00Z"),
+                Some(utc("2025-01-01T17:00:00Z")),
+            ),
+        )
+        .unwrap();
+
+        // Act
+        let report = repo.time_report(Some(start_of_week(now)), now);
+
+        // Assert
+        let rows: Vec<(u32, Duration)> = report
+            .rows
+            .iter()
+            .map(|(task, tracked)| (task.id, *tracked))
+            .collect();
+        assert_eq!(
+            rows,
+            [(long, Duration::hours(2)), (short, Duration::minutes(45))]
+        );
+        assert_eq!(report.total(), Duration::minutes(165));
+        assert_eq!(
+            report.to_string(),
+            "Time tracked since 2025-01-06:\n    2h 00m  2: Long (running)\n    0h 45m  1: Short\n    2h 45m  Total\n"
+        );
+        assert_eq!(
+            repo.time_report(None, now).total(),
+            Duration::minutes(165 + 60 + 8 * 60)
+        );
+    }
+}
+
+#[cfg(test)]
+mod archive_tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_task_moves_it_with_its_subtasks() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let parent = repo.add_task("Parent".to_string());
+        let child = repo.add_subtask(parent, "Child".to_string()).unwrap();
+        let other = repo.add_task("Other".to_string());
+        repo.add_tag(child, "work").unwrap();
+
+        // Act
+        let archived = repo.archive_task(parent).unwrap();
+
+        // Assert
+        assert_eq!(archived, [parent, child]);
+        assert!(repo.get_task(parent).is_none());
+        assert!(repo.get_task(child).is_none());
+        assert!(repo.get_task(other).is_some());
+        assert!(repo.get_tasks_with_tag("work").is_empty());
+        let ids: Vec<u32> = repo.get_archived_tasks().iter().map(|t| t.id).collect();
+        assert_eq!(ids, [parent, child]);
+        // Archived IDs aren't reused
+        assert_eq!(repo.add_task("New".to_string()), 4);
+    }
+
+    #[test]
+    fn test_archive_errors() {
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Task".to_string());
+        repo.archive_task(id).unwrap();
+
+        assert!(matches!(repo.archive_task(id), Err(TaskError::NotFound(1))));
+        assert!(matches!(
+            repo.unarchive_task(42),
+            Err(TaskError::NotFound(42))
+        ));
+        assert!(matches!(repo.mark_done(id), Err(TaskError::NotFound(1))));
+    }
+
+    #[test]
+    fn test_archive_done_only_archives_done_tasks() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let done = repo.add_task("Done".to_string());
+        let done_child = repo.add_subtask(done, "Done child".to_string()).unwrap();
+        let open = repo.add_task("Open".to_string());
+        repo.mark_done(done_child).unwrap();
+        repo.mark_done(done).unwrap();
+
+        // Act
+        let archived = repo.archive_done();
+
+        // Assert
+        assert_eq!(archived, [done, done_child]);
+        assert!(repo.get_task(open).is_some());
+        assert!(repo.archive_done().is_empty());
+    }
+
+    #[test]
+    fn test_unarchive_task_restores_subtasks_and_reopens_done_parent() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let parent = repo.add_task("Parent".to_string());
+        let child = repo.add_subtask(parent, "Child".to_string()).unwrap();
+        let grandchild = repo.add_subtask(child, "Grandchild".to_string()).unwrap();
+        repo.archive_task(child).unwrap();
+        repo.mark_done(parent).unwrap();
+        repo.add_tag(parent, "work").unwrap();
+
+        // Act
+        let restored = repo.unarchive_task(child).unwrap();
+
+        // Assert
+        assert_eq!(restored, [child, grandchild]);
+        assert_eq!(repo.get_subtasks(parent).len(), 1);
+        assert_eq!(repo.get_subtasks(child).len(), 1);
+        assert_eq!(repo.get_task(parent).unwrap().status, Status::InProgress);
+        assert!(repo.get_archived_tasks().is_empty());
+    }
+
+    #[test]
+    fn test_archive_changes_can_be_undone() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Task".to_string());
+        repo.add_tag(id, "work").unwrap();
+        repo.take_changes();
+        repo.archive_task(id).unwrap();
+        let operation = journal::Operation::new("archive", repo.take_changes());
+
+        // Act & Assert
+        operation.revert(&mut repo).unwrap();
+        assert!(repo.get_task(id).is_some());
+        assert!(repo.get_archived_task(id).is_none());
+        assert_eq!(repo.get_tasks_with_tag("work").len(), 1);
+
+        operation.reapply(&mut repo).unwrap();
+        assert!(repo.get_task(id).is_none());
+        assert!(repo.get_archived_task(id).is_some());
+        assert!(repo.get_tasks_with_tag("work").is_empty());
+    }
+}
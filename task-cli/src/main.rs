@@ -1,11 +1,30 @@
-use clap::{Parser, Subcommand, ValueEnum};
-use std::fs;
-use std::fs::{File, OpenOptions};
-use std::path::Path;
-use task_cli::TaskRepository;
+use chrono::{DateTime, Duration, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use task_cli::config::{Config, Dirs, PROJECT_ENV, StoreSources};
+use task_cli::render::{Column, Format, Renderer};
+use task_cli::{BulkOperation, IdRange, Recurrence, TaskError, TaskRepository, TaskStore};
 
 #[derive(Parser, Debug)]
 struct Cli {
+    /// Where tasks are kept: json:PATH or sqlite:PATH [env: TASK_CLI_STORE]
+    #[arg(long, global = true, conflicts_with = "file")]
+    store: Option<String>,
+    /// JSON file tasks are kept in [env: TASK_CLI_FILE]
+    #[arg(long, global = true)]
+    file: Option<PathBuf>,
+    /// Project to add tasks to and list [env: TASK_CLI_PROJECT]
+    #[arg(long, global = true)]
+    project: Option<String>,
+    /// How to print lists of tasks
+    #[arg(long, global = true, value_enum, default_value_t = FormatArg::Table)]
+    format: FormatArg,
+    /// Columns to show, comma separated: id, description, status, priority, due, tags, project,
+    /// parent, depends, repeats, tracked, created, updated
+    #[arg(long, global = true, value_delimiter = ',')]
+    columns: Vec<Column>,
     #[command(subcommand)]
     command: Commands,
 }
@@ -27,108 +46,696 @@ impl From<StatusArg> for task_cli::Status {
     }
 }
 
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum PriorityArg {
+    Low,
+    Medium,
+    High,
+    Urgent,
+}
+
+impl From<PriorityArg> for task_cli::Priority {
+    fn from(priority_arg: PriorityArg) -> Self {
+        match priority_arg {
+            PriorityArg::Low => task_cli::Priority::Low,
+            PriorityArg::Medium => task_cli::Priority::Medium,
+            PriorityArg::High => task_cli::Priority::High,
+            PriorityArg::Urgent => task_cli::Priority::Urgent,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum FormatArg {
+    Table,
+    Plain,
+    Json,
+}
+
+impl From<FormatArg> for Format {
+    fn from(format_arg: FormatArg) -> Self {
+        match format_arg {
+            FormatArg::Table => Format::Table,
+            FormatArg::Plain => Format::Plain,
+            FormatArg::Json => Format::Json,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum SortArg {
+    Id,
+    Priority,
+    Due,
+    Created,
+}
+
+impl From<SortArg> for task_cli::SortKey {
+    fn from(sort_arg: SortArg) -> Self {
+        match sort_arg {
+            SortArg::Id => task_cli::SortKey::Id,
+            SortArg::Priority => task_cli::SortKey::Priority,
+            SortArg::Due => task_cli::SortKey::DueDate,
+            SortArg::Created => task_cli::SortKey::CreatedAt,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Subcommand)]
 enum Commands {
     Add {
         description: String,
+        /// Deadline, as YYYY-MM-DD, YYYY-MM-DD HH:MM (UTC) or RFC 3339
+        #[arg(long, value_parser = task_cli::parse_due_date)]
+        due: Option<DateTime<Utc>>,
+        /// Tag to attach, can be repeated
+        #[arg(long = "tag")]
+        tags: Vec<String>,
+        #[arg(long)]
+        priority: Option<PriorityArg>,
+        /// Repeat the task once done: daily, weekly, weekdays, "every N days", "every N weeks"
+        /// or days like mon,wed,fri
+        #[arg(long)]
+        repeat: Option<Recurrence>,
+        /// ID of the task to add this one as a subtask of, in the same project
+        #[arg(long)]
+        parent: Option<u32>,
     },
     Update {
         id: u32,
-        description: String,
+        #[arg(required_unless_present = "priority")]
+        description: Option<String>,
+        #[arg(long)]
+        priority: Option<PriorityArg>,
     },
     #[command(name = "mark-in-progress")]
     MarkInProgress {
-        id: u32,
+        #[command(flatten)]
+        selection: Selection,
     },
     #[command(name = "mark-done")]
     MarkDone {
-        id: u32,
+        #[command(flatten)]
+        selection: Selection,
     },
     Delete {
-        id: u32,
+        #[command(flatten)]
+        selection: Selection,
     },
     List {
         // Optional positional argument for status
         status: Option<StatusArg>,
+        /// Only list tasks with this tag
+        #[arg(long)]
+        tag: Option<String>,
+        /// Order to list tasks in, by ID if not given
+        #[arg(long)]
+        sort: Option<SortArg>,
+        /// Show every task, with subtasks under their parents
+        #[arg(long, conflicts_with_all = ["status", "tag", "sort"])]
+        tree: bool,
+        /// Show the archived tasks instead, from every project
+        #[arg(long, conflicts_with_all = ["status", "tag", "sort", "tree"])]
+        archived: bool,
+        /// Show tasks from every project, not only the current one
+        #[arg(long, conflicts_with = "archived")]
+        all_projects: bool,
+    },
+    /// Lists the projects with how many open and done tasks they have
+    Projects,
+    /// Moves a task and its subtasks to another project
+    Move { id: u32, project: String },
+    /// Moves a task and its subtasks to the archive
+    Archive {
+        #[arg(required_unless_present = "done")]
+        id: Option<u32>,
+        /// Archive every done task
+        #[arg(long, conflicts_with = "id")]
+        done: bool,
+    },
+    /// Brings an archived task and its subtasks back
+    Restore { id: u32 },
+    /// Adds a tag to a task
+    Tag { id: u32, tag: String },
+    /// Removes a tag from a task
+    Untag { id: u32, tag: String },
+    /// Makes a task wait on another before it can be marked done
+    Depends {
+        id: u32,
+        /// ID of the task to wait on
+        #[arg(long)]
+        on: u32,
+        /// Stop waiting on it instead
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Lists the tasks waiting on tasks that aren't done
+    Blocked,
+    /// Lists overdue tasks and tasks due soon, by deadline
+    Due {
+        /// How many days ahead to look for upcoming tasks
+        #[arg(long, default_value_t = 7)]
+        days: i64,
+    },
+    /// Starts tracking time spent on a task
+    Start { id: u32 },
+    /// Stops tracking time spent on a task
+    Stop { id: u32 },
+    /// Shows the time tracked on each task
+    Report {
+        /// Only count time tracked since Monday (UTC)
+        #[arg(long)]
+        week: bool,
+    },
+    /// Shows counts by status, how fast tasks get done and a burndown chart
+    Stats {
+        /// How many weeks of created and completed tasks to show
+        #[arg(long, default_value_t = 4)]
+        weeks: u32,
+        /// How many days the burndown chart covers
+        #[arg(long, default_value_t = 14)]
+        days: u32,
     },
+    /// Shows where tasks are kept, or changes the default
+    Config {
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+    },
+    /// Answers JSON-RPC requests, one per line, for editors and scripts
+    Serve {
+        /// Read requests from stdin and write responses to stdout
+        #[arg(long, required = true)]
+        stdio: bool,
+    },
+    /// Reverts the last change made to the tasks
+    Undo,
+    /// Makes the last undone change again
+    Redo,
 }
 
-fn open_file_and_truncate(path: &Path) -> File {
-    let file = OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .create(true)
-        .open(path)
-        .expect("cannot open file");
-    file
+/// The tasks a command changes, given by ID or picked by filters.
+#[derive(Debug, Clone, Args)]
+struct Selection {
+    /// IDs of the tasks, or ranges of IDs like 5-9
+    #[arg(
+        required_unless_present_any = ["status", "tag"],
+        conflicts_with_all = ["status", "tag"]
+    )]
+    ids: Vec<IdRange>,
+    /// Every task with this status
+    #[arg(long)]
+    status: Option<StatusArg>,
+    /// Every task with this tag
+    #[arg(long)]
+    tag: Option<String>,
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Cli::parse();
+impl Selection {
+    fn ids(self, tasks: &TaskRepository) -> Vec<u32> {
+        if !self.ids.is_empty() {
+            return IdRange::flatten(&self.ids);
+        }
+        let status = self.status.map(task_cli::Status::from);
+        let mut selected = match &self.tag {
+            Some(tag) => tasks.get_tasks_with_tag(tag),
+            None => tasks.get_tasks_sorted_by(task_cli::SortKey::Id),
+        };
+        selected.retain(|task| status.as_ref().is_none_or(|status| task.status() == status));
+        task_cli::SortKey::Id.sort(&mut selected);
+        selected.iter().map(task_cli::Task::id).collect()
+    }
+}
+
+#[derive(Debug, Clone, Subcommand)]
+enum ConfigAction {
+    /// Sets a default: `store` (json:PATH or sqlite:PATH), `file` (path to a JSON file) or
+    /// `project` (the current project)
+    Set { key: String, value: String },
+    /// Removes a default
+    Unset { key: String },
+}
+
+/// Shows where tasks are kept and the config, or changes the config.
+fn configure(
+    sources: &StoreSources,
+    dirs: &Dirs,
+    project: &str,
+    action: Option<ConfigAction>,
+) -> Result<(), TaskError> {
+    let config_file = dirs.config_file();
+    let mut config = sources.config.clone();
+    match action {
+        None => {
+            let (spec, source) = sources.resolve(Some(dirs))?;
+            println!("Store: {} (from {})", spec, source);
+            println!("Project: {}", project);
+            println!("Config file: {}", config_file.display());
+            println!(
+                "Default store: {}",
+                config.store.as_deref().unwrap_or("not set")
+            );
+            return Ok(());
+        }
+        Some(ConfigAction::Set { key, value }) => config.set(&key, &value)?,
+        Some(ConfigAction::Unset { key }) => config.unset(&key)?,
+    }
+    config.save(&config_file)?;
+    println!("Config saved to {}", config_file.display());
+    Ok(())
+}
+
+fn join_ids(ids: &[u32]) -> String {
+    let ids: Vec<String> = ids.iter().map(u32::to_string).collect();
+    ids.join(", ")
+}
 
-    const TASK_FILE: &str = "tasks.json";
+/// `task 3`, or `tasks 1, 2` for many.
+fn describe_tasks(ids: &[u32]) -> String {
+    match ids {
+        [id] => format!("task {}", id),
+        ids => format!("tasks {}", join_ids(ids)),
+    }
+}
 
-    let path = Path::new(TASK_FILE);
+/// Applies `operation` to the selected tasks, saving the ones that succeeded and reporting the
+/// others.
+fn apply_bulk(
+    store: &mut dyn TaskStore,
+    tasks: &mut TaskRepository,
+    selection: Selection,
+    operation: BulkOperation,
+) -> Result<(), TaskError> {
+    let ids = selection.ids(tasks);
+    if ids.is_empty() {
+        println!("No tasks matched");
+        return Ok(());
+    }
+    let mut result = tasks.apply_bulk(&ids, operation);
 
-    let mut tasks = if !path.exists() {
-        TaskRepository::new()
+    if !result.succeeded.is_empty() {
+        let changed = describe_tasks(&result.succeeded);
+        let description = match operation {
+            BulkOperation::MarkInProgress => format!("mark {} in progress", changed),
+            BulkOperation::MarkDone => format!("mark {} done", changed),
+            BulkOperation::Delete => format!("delete {}", changed),
+        };
+        store.commit(tasks, description)?;
+    }
+    for id in &result.succeeded {
+        match operation {
+            BulkOperation::MarkInProgress => println!("Task with ID {} marked as in progress", id),
+            BulkOperation::MarkDone => println!("Task with ID {} marked as done", id),
+            BulkOperation::Delete => println!("Task with ID {} deleted", id),
+        }
+    }
+    for next in &result.next_occurrences {
+        if let Some(next) = tasks.get_task(*next) {
+            println!("Next occurrence: {}", next);
+        }
+    }
+
+    // A single task fails with its own error, like the other commands
+    if let Some((_, error)) = result.failed.pop_if(|_| ids.len() == 1) {
+        return Err(error);
+    }
+    for (_, error) in &result.failed {
+        eprintln!("Error: {}", error);
+    }
+    if result.is_success() {
+        Ok(())
     } else {
-        let contents = fs::read_to_string(path).expect("cannot read file that currently exists");
-        if contents.is_empty() {
-            TaskRepository::new()
-        } else {
-            TaskRepository::new_from_json(&contents)
+        Err(TaskError::BulkFailed {
+            failed: result.failed.len(),
+            total: ids.len(),
+        })
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Cli::parse();
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("Error: {}", error);
+            exit_code(&error)
         }
+    }
+}
+
+/// Exit codes follow sysexits.h for problems with the task file, so that scripts can tell them
+/// apart from mistakes in the command.
+fn exit_code(error: &TaskError) -> ExitCode {
+    match error {
+        // EX_DATAERR
+        TaskError::Serialization(_) => ExitCode::from(65),
+        // EX_IOERR
+        TaskError::Io(_) => ExitCode::from(74),
+        #[cfg(feature = "sqlite")]
+        TaskError::Database(_) => ExitCode::from(74),
+        _ => ExitCode::FAILURE,
+    }
+}
+
+fn run(args: Cli) -> Result<(), TaskError> {
+    let dirs = Dirs::from_env();
+    let config = match &dirs {
+        Some(dirs) => Config::load(&dirs.config_file())?,
+        None => Config::default(),
     };
+    let sources = StoreSources::from_env(args.store, args.file, config);
+    let project = task_cli::config::current_project(
+        args.project.as_deref(),
+        std::env::var(PROJECT_ENV).ok().as_deref(),
+        &sources.config,
+    )?;
+
+    if let Commands::Config { action } = args.command {
+        let dirs = dirs.ok_or(TaskError::NoHomeDirectory)?;
+        return configure(&sources, &dirs, &project, action);
+    }
+
+    let (spec, _) = sources.resolve(dirs.as_ref())?;
+    let mut store = task_cli::open_store(&spec)?;
+    if let Commands::Serve { .. } = args.command {
+        let mut server = task_cli::rpc::Server::new(store, std::io::stdout().lock());
+        return server.serve(std::io::stdin().lock());
+    }
+    let color = task_cli::render::use_color(
+        std::io::stdout().is_terminal(),
+        std::env::var("NO_COLOR").ok().as_deref(),
+    );
+    let renderer = Renderer::new(args.format.into())
+        .with_columns(args.columns.clone())
+        .with_color(color);
+
+    // When another command saves in between, this one is run again on the tasks as that one left
+    // them, so that neither change is lost
+    let mut attempts = 1;
+    loop {
+        let outcome = execute(
+            &mut *store,
+            args.command.clone(),
+            &project,
+            &renderer,
+            &args.columns,
+        );
+        match outcome {
+            Err(TaskError::Conflict) if attempts < MAX_ATTEMPTS => attempts += 1,
+            outcome => return outcome,
+        }
+    }
+}
+
+/// How many times a command is run before giving up on saving over concurrent changes.
+const MAX_ATTEMPTS: u32 = 10;
 
-    match args.command {
-        Commands::Add { description } => {
-            let mut file = open_file_and_truncate(path);
-            let id = tasks.add_task(description);
-            tasks.save_as_json(&mut file);
+/// Runs a command on the tasks in `store`.
+fn execute(
+    store: &mut dyn TaskStore,
+    command: Commands,
+    project: &str,
+    renderer: &Renderer,
+    columns: &[Column],
+) -> Result<(), TaskError> {
+    let mut tasks = store.load()?;
+    let json = renderer.format() == Format::Json;
+
+    match command {
+        Commands::Add {
+            description,
+            due,
+            tags: new_tags,
+            priority,
+            repeat,
+            parent,
+        } => {
+            let id = match parent {
+                Some(parent) => tasks.add_subtask(parent, description)?,
+                None => {
+                    let id = tasks.add_task(description);
+                    tasks.set_project(id, project)?;
+                    id
+                }
+            };
+            if let Some(priority) = priority {
+                tasks.set_priority(id, priority.into())?;
+            }
+            if due.is_some() {
+                tasks.set_due_date(id, due)?;
+            }
+            if repeat.is_some() {
+                tasks.set_recurrence(id, repeat)?;
+            }
+            for tag in new_tags {
+                tasks.add_tag(id, &tag)?;
+            }
+            store.commit(&mut tasks, format!("add task {}", id))?;
             println!("Task added with ID {}", id);
         }
-        Commands::Update { id, description } => {
-            let mut file = open_file_and_truncate(path);
-            tasks
-                .update_task(id, description)
-                .expect("cannot update task");
-            tasks.save_as_json(&mut file);
-        }
-        Commands::MarkInProgress { id } => {
-            let mut file = open_file_and_truncate(path);
-            tasks
-                .mark_in_progress(id)
-                .expect("cannot mark task as in progress");
-            tasks.save_as_json(&mut file);
-            println!("Task with ID {} marked as in progress", id);
-        }
-        Commands::MarkDone { id } => {
-            let mut file = open_file_and_truncate(path);
-            tasks.mark_done(id).expect("cannot mark task as done");
-            tasks.save_as_json(&mut file);
-            println!("Task with ID {} marked as done", id);
-        }
-        Commands::Delete { id } => {
-            tasks.delete_task(id);
-            let mut file = open_file_and_truncate(path);
-            tasks.save_as_json(&mut file);
-            println!("Task with ID {} deleted", id);
-        }
-        Commands::List { status } => {
+        Commands::Update {
+            id,
+            description,
+            priority,
+        } => {
+            if let Some(description) = description {
+                tasks.update_task(id, description)?;
+            }
+            if let Some(priority) = priority {
+                tasks.set_priority(id, priority.into())?;
+            }
+            store.commit(&mut tasks, format!("update task {}", id))?;
+        }
+        Commands::MarkInProgress { selection } => {
+            apply_bulk(
+                &mut *store,
+                &mut tasks,
+                selection,
+                BulkOperation::MarkInProgress,
+            )?;
+        }
+        Commands::MarkDone { selection } => {
+            apply_bulk(&mut *store, &mut tasks, selection, BulkOperation::MarkDone)?;
+        }
+        Commands::Delete { selection } => {
+            apply_bulk(&mut *store, &mut tasks, selection, BulkOperation::Delete)?;
+        }
+        Commands::Tag { id, tag } => {
+            tasks.add_tag(id, &tag)?;
+            store.commit(&mut tasks, format!("tag task {} {}", id, tag))?;
+            println!("Task with ID {} tagged {}", id, tag);
+        }
+        Commands::Untag { id, tag } => {
+            tasks.remove_tag(id, &tag)?;
+            store.commit(&mut tasks, format!("untag task {} {}", id, tag))?;
+            println!("Tag {} removed from task with ID {}", tag, id);
+        }
+        Commands::Depends { id, on, remove } => {
+            if remove {
+                tasks.remove_dependency(id, on)?;
+                store.commit(&mut tasks, format!("make task {} not depend on {}", id, on))?;
+                println!("Task with ID {} no longer depends on {}", id, on);
+            } else {
+                tasks.add_dependency(id, on)?;
+                store.commit(&mut tasks, format!("make task {} depend on {}", id, on))?;
+                println!("Task with ID {} now depends on {}", id, on);
+            }
+        }
+        Commands::Move { id, project } => {
+            let moved = tasks.set_project(id, &project)?;
+            let project = tasks
+                .get_task(id)
+                .expect("task exists")
+                .project()
+                .to_string();
+            if moved.is_empty() {
+                println!("Task with ID {} is already in project {}", id, project);
+                return Ok(());
+            }
+            store.commit(
+                &mut tasks,
+                format!("move {} to project {}", describe_tasks(&moved), project),
+            )?;
+            println!("Moved {} to project {}", describe_tasks(&moved), project);
+        }
+        Commands::Projects => {
+            let projects = tasks.get_projects();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&projects)?);
+                return Ok(());
+            }
+            if projects.is_empty() {
+                println!("No projects found.");
+            }
+            for summary in projects {
+                let current = if summary.name == project { "*" } else { " " };
+                println!(
+                    "{} {} ({} open, {} done)",
+                    current, summary.name, summary.open, summary.done
+                );
+            }
+        }
+        Commands::Blocked => {
+            let blocked = tasks.get_blocked_tasks();
+            if renderer.format() == Format::Plain {
+                for (task, waiting_on) in blocked {
+                    println!("{} [Waiting on: {}]", task, join_ids(&waiting_on));
+                }
+                return Ok(());
+            }
+            // Tables show what each task waits on unless other columns were picked
+            let renderer = if columns.is_empty() && !json {
+                renderer
+                    .clone()
+                    .with_columns([&Column::DEFAULT[..], &[Column::Depends]].concat())
+            } else {
+                renderer.clone()
+            };
+            let blocked: Vec<task_cli::Task> = blocked.into_iter().map(|(task, _)| task).collect();
+            print!("{}", renderer.render(&blocked)?);
+        }
+        Commands::Start { id } => {
+            tasks.start_tracking(id)?;
+            store.commit(&mut tasks, format!("start task {}", id))?;
+            println!("Started tracking task with ID {}", id);
+        }
+        Commands::Stop { id } => {
+            let session = tasks.stop_tracking(id)?;
+            store.commit(&mut tasks, format!("stop task {}", id))?;
+            let total = tasks
+                .get_task(id)
+                .expect("task exists")
+                .tracked_time(Utc::now());
+            println!(
+                "Stopped tracking task with ID {} after {} ({} in total)",
+                id,
+                task_cli::format_duration(session),
+                task_cli::format_duration(total)
+            );
+        }
+        Commands::Report { week } => {
+            let now = Utc::now();
+            let since = week.then(|| task_cli::start_of_week(now));
+            print!("{}", tasks.time_report(since, now));
+        }
+        Commands::Stats { weeks, days } => {
+            print!("{}", tasks.stats(Utc::now(), weeks, days));
+        }
+        Commands::Config { .. } => unreachable!("handled before the store is opened"),
+        Commands::Serve { .. } => unreachable!("handled before the tasks are loaded"),
+        Commands::Undo => {
+            let journal = store.journal().ok_or(TaskError::NoJournal)?;
+            let history = journal.history()?;
+            let Some(operation) = history.next_undo() else {
+                println!("Nothing to undo");
+                return Ok(());
+            };
+            operation.revert(&mut tasks)?;
+            store.save_and_log(&tasks, &|journal| journal.record_undo())?;
+            println!("Undid: {}", operation.description);
+        }
+        Commands::Redo => {
+            let journal = store.journal().ok_or(TaskError::NoJournal)?;
+            let history = journal.history()?;
+            let Some(operation) = history.next_redo() else {
+                println!("Nothing to redo");
+                return Ok(());
+            };
+            operation.reapply(&mut tasks)?;
+            store.save_and_log(&tasks, &|journal| journal.record_redo())?;
+            println!("Redid: {}", operation.description);
+        }
+        Commands::Archive { id, .. } => {
+            let (archived, description) = match id {
+                Some(id) => (tasks.archive_task(id)?, format!("archive task {}", id)),
+                None => (tasks.archive_done(), "archive done tasks".to_string()),
+            };
+            store.commit(&mut tasks, description)?;
+            println!(
+                "Archived {} task(s): {}",
+                archived.len(),
+                join_ids(&archived)
+            );
+        }
+        Commands::Restore { id } => {
+            let restored = tasks.unarchive_task(id)?;
+            store.commit(&mut tasks, format!("restore task {}", id))?;
+            println!(
+                "Restored {} task(s): {}",
+                restored.len(),
+                join_ids(&restored)
+            );
+        }
+        Commands::List { archived: true, .. } => {
+            if !json {
+                println!("Archived tasks:");
+            }
+            print!("{}", renderer.render(&tasks.get_archived_tasks())?);
+        }
+        Commands::List {
+            tree: true,
+            all_projects,
+            ..
+        } => {
+            if all_projects {
+                print!("{}", tasks.tree());
+            } else {
+                print!("{}", tasks.project_tree(project));
+            }
+        }
+        Commands::List {
+            status,
+            tag,
+            sort,
+            all_projects,
+            ..
+        } => {
             let filtered_status = status.map(task_cli::Status::from);
 
-            let Some(status) = filtered_status else {
+            let mut listed = match (filtered_status, &tag) {
+                (None, None) if !all_projects => tasks.get_tasks_in_project(project),
                 // Show all tasks
-                println!("{}", tasks);
-                return Ok(());
+                (None, None) => tasks.get_tasks_sorted_by(task_cli::SortKey::Id),
+                (Some(status), None) => {
+                    // Filter tasks by status
+                    if !json {
+                        println!("Listing tasks with status: {:?}", status);
+                    }
+                    tasks.get_tasks_with_status(status)
+                }
+                (status, Some(tag)) => {
+                    if !json {
+                        println!("Listing tasks tagged: {}", tag);
+                    }
+                    let mut tagged = tasks.get_tasks_with_tag(tag);
+                    if let Some(status) = status {
+                        tagged.retain(|task| *task.status() == status);
+                    }
+                    tagged
+                }
             };
-
-            // Filter tasks by status
-            println!("Listing tasks with status: {:?}", status);
-            for task in tasks.get_tasks_with_status(status) {
-                println!("{}", task);
+            if !all_projects {
+                listed.retain(|task| task.project() == project);
+            }
+            sort.map_or(task_cli::SortKey::Id, task_cli::SortKey::from)
+                .sort(&mut listed);
+            print!("{}", renderer.render(&listed)?);
+        }
+        Commands::Due { days } => {
+            let overdue = tasks.get_overdue_tasks();
+            let upcoming = tasks.get_tasks_due_within(Duration::days(days));
+            if json {
+                let due = serde_json::json!({
+                    "overdue": renderer.json(&overdue)?,
+                    "upcoming": renderer.json(&upcoming)?,
+                });
+                println!("{}", serde_json::to_string_pretty(&due)?);
+                return Ok(());
             }
+            println!("Overdue:");
+            print!("{}", renderer.render(&overdue)?);
+            println!("Due within {} days:", days);
+            print!("{}", renderer.render(&upcoming)?);
         }
     };
 
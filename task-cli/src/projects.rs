@@ -0,0 +1,208 @@
+use crate::{Status, Task, TaskError, TaskRepository};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+
+/// The project of tasks not put in another one, including every task saved before there were
+/// projects.
+pub const DEFAULT_PROJECT: &str = "default";
+
+pub(crate) fn default_project() -> String {
+    DEFAULT_PROJECT.to_string()
+}
+
+/// Project names are compared case-insensitively, like tags, so they're stored trimmed and
+/// lowercased.
+pub fn normalize_project(project: &str) -> Result<String, TaskError> {
+    let project = project.trim().to_lowercase();
+    if project.is_empty() {
+        return Err(TaskError::EmptyProject);
+    }
+    Ok(project)
+}
+
+/// Removes `id` from the IDs under `key`, dropping the key once it has none.
+pub(crate) fn unindex(index: &mut HashMap<String, BTreeSet<u32>>, key: &str, id: u32) {
+    if let Some(ids) = index.get_mut(key) {
+        ids.remove(&id);
+        if ids.is_empty() {
+            index.remove(key);
+        }
+    }
+}
+
+/// A project and how many of its tasks are open and done, archived tasks aside.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize)]
+pub struct ProjectSummary {
+    pub name: String,
+    pub open: usize,
+    pub done: usize,
+}
+
+impl TaskRepository {
+    /// Moves a task, along with its subtasks, to `project`. Returns the IDs of the moved tasks.
+    pub fn set_project(&mut self, id: u32, project: &str) -> Result<Vec<u32>, TaskError> {
+        let project = normalize_project(project)?;
+        if !self.tasks.contains_key(&id) {
+            return Err(TaskError::NotFound(id));
+        }
+        let mut moved = Vec::new();
+        let mut to_move = vec![id];
+        while let Some(id) = to_move.pop() {
+            to_move.extend(self.subtask_ids(id));
+            if self.tasks[&id].project == project {
+                continue;
+            }
+            let task = self.task_mut(id).expect("task exists");
+            let previous = std::mem::replace(&mut task.project, project.clone());
+            task.updated_at = chrono::Utc::now();
+            unindex(&mut self.project_index, &previous, id);
+            self.project_index
+                .entry(project.clone())
+                .or_default()
+                .insert(id);
+            moved.push(id);
+        }
+        moved.sort_unstable();
+        Ok(moved)
+    }
+
+    /// Tasks in `project`, by ID.
+    pub fn get_tasks_in_project(&self, project: &str) -> Vec<Task> {
+        let Ok(project) = normalize_project(project) else {
+            return Vec::new();
+        };
+        self.project_index
+            .get(&project)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.tasks.get(id))
+            .cloned()
+            .collect()
+    }
+
+    /// Every project with tasks, alphabetically.
+    pub fn get_projects(&self) -> Vec<ProjectSummary> {
+        let mut projects: Vec<ProjectSummary> = self
+            .project_index
+            .iter()
+            .map(|(name, ids)| {
+                let done = ids
+                    .iter()
+                    .filter(|id| self.tasks[*id].status == Status::Done)
+                    .count();
+                ProjectSummary {
+                    name: name.clone(),
+                    open: ids.len() - done,
+                    done,
+                }
+            })
+            .collect();
+        projects.sort_by(|a, b| a.name.cmp(&b.name));
+        projects
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_old_files_put_tasks_in_the_default_project() {
+        let json = r#"{"tasks":{"1":{"id":1,"description":"Old task","status":"Todo",
+            "created_at":"2024-01-01T00:00:00Z","updated_at":"2024-01-01T00:00:00Z"}},
+            "next_id":2}"#;
+
+        let repo = TaskRepository::new_from_json(json).unwrap();
+
+        assert_eq!(repo.get_task(1).unwrap().project(), DEFAULT_PROJECT);
+        assert_eq!(repo.get_tasks_in_project(DEFAULT_PROJECT).len(), 1);
+    }
+
+    #[test]
+    fn test_set_project_moves_subtasks_and_reindexes() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let parent = repo.add_task("Parent".to_string());
+        let child = repo.add_subtask(parent, "Child".to_string()).unwrap();
+        let other = repo.add_task("Other".to_string());
+
+        // Act
+        let moved = repo.set_project(parent, " Website ").unwrap();
+        let added = repo.add_subtask(parent, "Added later".to_string()).unwrap();
+
+        // Assert
+        assert_eq!(moved, [parent, child]);
+        let in_website: Vec<u32> = repo
+            .get_tasks_in_project("website")
+            .iter()
+            .map(Task::id)
+            .collect();
+        assert_eq!(in_website, [parent, child, added]);
+        let in_default: Vec<u32> = repo
+            .get_tasks_in_project(DEFAULT_PROJECT)
+            .iter()
+            .map(Task::id)
+            .collect();
+        assert_eq!(in_default, [other]);
+        assert!(matches!(
+            repo.set_project(parent, "  "),
+            Err(TaskError::EmptyProject)
+        ));
+    }
+
+    #[test]
+    fn test_project_is_kept_by_next_occurrences_and_archiving() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let standup = repo.add_task("Standup".to_string());
+        repo.set_project(standup, "work").unwrap();
+        repo.set_recurrence(standup, Some("daily".parse().unwrap()))
+            .unwrap();
+
+        // Act
+        let next = repo.mark_done(standup).unwrap().unwrap();
+        repo.archive_task(standup).unwrap();
+        let while_archived = repo.get_tasks_in_project("work").len();
+        repo.unarchive_task(standup).unwrap();
+
+        // Assert
+        assert_eq!(repo.get_task(next).unwrap().project(), "work");
+        assert_eq!(while_archived, 1);
+        assert_eq!(repo.get_tasks_in_project("work").len(), 2);
+        assert!(repo.get_tasks_in_project(DEFAULT_PROJECT).is_empty());
+    }
+
+    #[test]
+    fn test_get_projects_counts_open_and_done_tasks() {
+        // Arrange
+        let mut repo = TaskRepository::new();
+        let docs = repo.add_task("Docs".to_string());
+        repo.set_project(docs, "website").unwrap();
+        repo.mark_done(docs).unwrap();
+        let fix = repo.add_task("Fix".to_string());
+        repo.set_project(fix, "website").unwrap();
+        repo.add_task("Groceries".to_string());
+        let archived = repo.add_task("Archived".to_string());
+        repo.archive_task(archived).unwrap();
+
+        // Act
+        let projects = repo.get_projects();
+
+        // Assert
+        assert_eq!(
+            projects,
+            [
+                ProjectSummary {
+                    name: DEFAULT_PROJECT.to_string(),
+                    open: 1,
+                    done: 0,
+                },
+                ProjectSummary {
+                    name: "website".to_string(),
+                    open: 1,
+                    done: 1,
+                },
+            ]
+        );
+    }
+}
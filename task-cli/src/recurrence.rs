@@ -0,0 +1,225 @@
+use crate::TaskError;
+use chrono::{DateTime, Datelike, Duration, Utc, Weekday};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+const WEEKDAYS: [Weekday; 5] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+];
+
+/// How often a repeating task comes back once it's done.
+///
+/// Occurrences are computed in UTC, like due dates, so a task due at 09:00 UTC stays due at
+/// 09:00 UTC across daylight saving time changes.
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub enum Recurrence {
+    /// Every `n` days, `daily` or `every 3 days`.
+    Days(u32),
+    /// Every `n` weeks, `weekly` or `every 2 weeks`.
+    Weeks(u32),
+    /// On some days of the week, `weekdays` or `mon,wed,fri`. Sorted from Monday, without
+    /// duplicates.
+    OnDays(Vec<Weekday>),
+}
+
+impl Recurrence {
+    /// The first occurrence after `from`, at the same time of day.
+    pub fn next_after(&self, from: DateTime<Utc>) -> DateTime<Utc> {
+        match self {
+            Recurrence::Days(days) => from + Duration::days(i64::from(*days)),
+            Recurrence::Weeks(weeks) => from + Duration::weeks(i64::from(*weeks)),
+            Recurrence::OnDays(days) => (1..=7)
+                .map(|offset| from + Duration::days(offset))
+                .find(|next| days.contains(&next.weekday()))
+                .expect("parsing guarantees at least one day"),
+        }
+    }
+
+    /// The first occurrence after `from` that's still ahead of `now`, skipping the ones missed
+    /// while a task was overdue.
+    pub fn next_after_now(&self, from: DateTime<Utc>, now: DateTime<Utc>) -> DateTime<Utc> {
+        let mut next = self.next_after(from);
+        while next <= now {
+            next = self.next_after(next);
+        }
+        next
+    }
+}
+
+impl Display for Recurrence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Recurrence::Days(1) => write!(f, "daily"),
+            Recurrence::Days(days) => write!(f, "every {} days", days),
+            Recurrence::Weeks(1) => write!(f, "weekly"),
+            Recurrence::Weeks(weeks) => write!(f, "every {} weeks", weeks),
+            Recurrence::OnDays(days) if days[..] == WEEKDAYS => write!(f, "weekdays"),
+            Recurrence::OnDays(days) => {
+                let days: Vec<String> = days
+                    .iter()
+                    .map(|day| day.to_string().to_lowercase())
+                    .collect();
+                write!(f, "{}", days.join(","))
+            }
+        }
+    }
+}
+
+impl FromStr for Recurrence {
+    type Err = TaskError;
+
+    /// Parses `daily`, `weekly`, `weekdays`, `every N days`, `every N weeks` or a list of days of
+    /// the week such as `mon,wed,fri`.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let invalid = || TaskError::InvalidRecurrence(input.to_string());
+        let normalized = input.trim().to_lowercase();
+        match normalized.as_str() {
+            "daily" => return Ok(Recurrence::Days(1)),
+            "weekly" => return Ok(Recurrence::Weeks(1)),
+            "weekdays" => return Ok(Recurrence::OnDays(WEEKDAYS.to_vec())),
+            _ => {}
+        }
+
+        if let Some(every) = normalized.strip_prefix("every ") {
+            let (count, unit) = every.split_once(' ').ok_or_else(invalid)?;
+            let count: u32 = count.parse().map_err(|_| invalid())?;
+            if count == 0 {
+                return Err(invalid());
+            }
+            return match unit.trim() {
+                "day" | "days" => Ok(Recurrence::Days(count)),
+                "week" | "weeks" => Ok(Recurrence::Weeks(count)),
+                _ => Err(invalid()),
+            };
+        }
+
+        let mut days = normalized
+            .split(',')
+            .map(|day| day.trim().parse::<Weekday>().map_err(|_| invalid()))
+            .collect::<Result<Vec<_>, _>>()?;
+        days.sort_by_key(Weekday::num_days_from_monday);
+        days.dedup();
+        Ok(Recurrence::OnDays(days))
+    }
+}
+
+impl From<Recurrence> for String {
+    fn from(recurrence: Recurrence) -> Self {
+        recurrence.to_string()
+    }
+}
+
+impl TryFrom<String> for Recurrence {
+    type Error = TaskError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(input: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(input).unwrap().into()
+    }
+
+    #[test]
+    fn test_parse_and_display_round_trip() {
+        for input in [
+            "daily",
+            "weekly",
+            "weekdays",
+            "every 3 days",
+            "every 2 weeks",
+            "mon,wed,fri",
+        ] {
+            assert_eq!(input.parse::<Recurrence>().unwrap().to_string(), input);
+        }
+    }
+
+    #[test]
+    fn test_parse_normalizes_day_lists() {
+        assert_eq!(
+            "Friday, mon,fri".parse::<Recurrence>().unwrap(),
+            Recurrence::OnDays(vec![Weekday::Mon, Weekday::Fri])
+        );
+        assert_eq!(
+            "mon,tue,wed,thu,fri"
+                .parse::<Recurrence>()
+                .unwrap()
+                .to_string(),
+            "weekdays"
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_recurrences() {
+        for input in ["", "hourly", "every 0 days", "every two days", "mon,funday"] {
+            assert!(
+                matches!(
+                    input.parse::<Recurrence>(),
+                    Err(TaskError::InvalidRecurrence(_))
+                ),
+                "{}",
+                input
+            );
+        }
+    }
+
+    #[test]
+    fn test_next_after_keeps_utc_time_across_dst_changes() {
+        // Europe moves its clocks forward on 2025-03-30 and the US on 2025-03-09, neither moves
+        // a UTC due date
+        let daily = Recurrence::Days(1);
+        assert_eq!(
+            daily.next_after(utc("2025-03-29T09:00:00Z")),
+            utc("2025-03-30T09:00:00Z")
+        );
+        let weekly = Recurrence::Weeks(1);
+        assert_eq!(
+            weekly.next_after(utc("2025-03-05T23:30:00Z")),
+            utc("2025-03-12T23:30:00Z")
+        );
+    }
+
+    #[test]
+    fn test_next_after_on_days_of_the_week() {
+        let recurrence: Recurrence = "mon,wed".parse().unwrap();
+
+        // 2025-01-06 is a Monday
+        let wednesday = recurrence.next_after(utc("2025-01-06T08:00:00Z"));
+        let monday = recurrence.next_after(wednesday);
+
+        assert_eq!(wednesday, utc("2025-01-08T08:00:00Z"));
+        assert_eq!(monday, utc("2025-01-13T08:00:00Z"));
+    }
+
+    #[test]
+    fn test_next_after_now_skips_missed_occurrences() {
+        let daily = Recurrence::Days(1);
+
+        let next = daily.next_after_now(utc("2025-01-01T09:00:00Z"), utc("2025-01-05T12:00:00Z"));
+
+        assert_eq!(next, utc("2025-01-06T09:00:00Z"));
+    }
+
+    #[test]
+    fn test_serializes_as_string() {
+        let recurrence: Recurrence = "every 2 weeks".parse().unwrap();
+
+        let json = serde_json::to_string(&recurrence).unwrap();
+
+        assert_eq!(json, r#""every 2 weeks""#);
+        assert_eq!(
+            serde_json::from_str::<Recurrence>(&json).unwrap(),
+            recurrence
+        );
+    }
+}
@@ -0,0 +1,439 @@
+//! Renders lists of tasks for the terminal, as a table, as plain lines or as JSON.
+
+use crate::{Priority, Status, Task, TaskError, format_duration};
+use chrono::Utc;
+use serde_json::{Map, Value};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const BOLD_RED: &str = "\x1b[1;31m";
+
+/// How to render tasks.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Format {
+    /// Aligned columns under a header.
+    #[default]
+    Table,
+    /// One line per task, as [`Task`]'s `Display`.
+    Plain,
+    /// An array of task objects.
+    Json,
+}
+
+/// A field of a task that can be shown as a column.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Column {
+    Id,
+    Description,
+    Status,
+    Priority,
+    Due,
+    Tags,
+    Project,
+    Parent,
+    Depends,
+    Repeats,
+    Tracked,
+    Created,
+    Updated,
+}
+
+impl Column {
+    /// Shown when no columns are picked.
+    pub const DEFAULT: [Column; 6] = [
+        Column::Id,
+        Column::Description,
+        Column::Status,
+        Column::Priority,
+        Column::Due,
+        Column::Tags,
+    ];
+
+    const ALL: [Column; 13] = [
+        Column::Id,
+        Column::Description,
+        Column::Status,
+        Column::Priority,
+        Column::Due,
+        Column::Tags,
+        Column::Project,
+        Column::Parent,
+        Column::Depends,
+        Column::Repeats,
+        Column::Tracked,
+        Column::Created,
+        Column::Updated,
+    ];
+
+    /// Name used to pick the column, and as its key in JSON.
+    pub fn name(self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::Description => "description",
+            Column::Status => "status",
+            Column::Priority => "priority",
+            Column::Due => "due",
+            Column::Tags => "tags",
+            Column::Project => "project",
+            Column::Parent => "parent",
+            Column::Depends => "depends",
+            Column::Repeats => "repeats",
+            Column::Tracked => "tracked",
+            Column::Created => "created",
+            Column::Updated => "updated",
+        }
+    }
+
+    fn header(self) -> &'static str {
+        match self {
+            Column::Id => "ID",
+            Column::Description => "Description",
+            Column::Status => "Status",
+            Column::Priority => "Priority",
+            Column::Due => "Due (UTC)",
+            Column::Tags => "Tags",
+            Column::Project => "Project",
+            Column::Parent => "Parent",
+            Column::Depends => "Depends on",
+            Column::Repeats => "Repeats",
+            Column::Tracked => "Tracked",
+            Column::Created => "Created (UTC)",
+            Column::Updated => "Updated (UTC)",
+        }
+    }
+
+    /// The cell for `task`, empty when the task has no value.
+    fn text(self, task: &Task) -> String {
+        let time = |time: chrono::DateTime<Utc>| time.format("%Y-%m-%d %H:%M").to_string();
+        let ids = |ids: &mut dyn Iterator<Item = u32>| {
+            ids.map(|id| id.to_string()).collect::<Vec<_>>().join(", ")
+        };
+        match self {
+            Column::Id => task.id.to_string(),
+            Column::Description => task.description.clone(),
+            Column::Status => task.status.to_string(),
+            Column::Priority => task.priority.to_string(),
+            Column::Due => task.due_date.map(time).unwrap_or_default(),
+            Column::Tags => task.tags.iter().cloned().collect::<Vec<_>>().join(", "),
+            Column::Project => task.project.clone(),
+            Column::Parent => task.parent_id.map(|id| id.to_string()).unwrap_or_default(),
+            Column::Depends => ids(&mut task.depends_on.iter().copied()),
+            Column::Repeats => task
+                .recurrence
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+            Column::Tracked if task.sessions.is_empty() => String::new(),
+            Column::Tracked => format_duration(task.tracked_time(Utc::now())),
+            Column::Created => time(task.created_at),
+            Column::Updated => time(task.updated_at),
+        }
+    }
+
+    /// The ANSI style of the cell for `task`, if it has one.
+    fn style(self, task: &Task) -> Option<&'static str> {
+        match self {
+            Column::Status => match task.status {
+                Status::Todo => None,
+                Status::InProgress => Some(YELLOW),
+                Status::Done => Some(GREEN),
+            },
+            Column::Priority => match task.priority {
+                Priority::Low => Some(DIM),
+                Priority::Medium => None,
+                Priority::High => Some(RED),
+                Priority::Urgent => Some(BOLD_RED),
+            },
+            Column::Due => task
+                .due_date
+                .filter(|due| task.status != Status::Done && *due < Utc::now())
+                .map(|_| RED),
+            _ => None,
+        }
+    }
+
+    fn json(self, task: &Task) -> Value {
+        match self {
+            Column::Id => json(&task.id),
+            Column::Description => json(&task.description),
+            Column::Status => json(&task.status),
+            Column::Priority => json(&task.priority),
+            Column::Due => json(&task.due_date),
+            Column::Tags => json(&task.tags),
+            Column::Project => json(&task.project),
+            Column::Parent => json(&task.parent_id),
+            Column::Depends => json(&task.depends_on),
+            Column::Repeats => json(&task.recurrence),
+            Column::Tracked => json(&task.tracked_time(Utc::now()).num_seconds()),
+            Column::Created => json(&task.created_at),
+            Column::Updated => json(&task.updated_at),
+        }
+    }
+}
+
+fn json<T: serde::Serialize + ?Sized>(value: &T) -> Value {
+    serde_json::to_value(value).expect("task fields serialize to JSON")
+}
+
+impl Display for Column {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Column {
+    type Err = TaskError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let name = input.trim().to_lowercase();
+        Column::ALL
+            .into_iter()
+            .find(|column| column.name() == name)
+            .ok_or_else(|| TaskError::InvalidColumn(input.to_string()))
+    }
+}
+
+/// Whether to color output going to a terminal, given the value of `NO_COLOR`, which turns
+/// colors off when set to anything but an empty string (<https://no-color.org>).
+pub fn use_color(is_terminal: bool, no_color: Option<&str>) -> bool {
+    is_terminal && no_color.is_none_or(str::is_empty)
+}
+
+/// Renders lists of tasks in a [`Format`], with the picked columns.
+#[derive(Debug, Clone, Default)]
+pub struct Renderer {
+    format: Format,
+    /// `None` for [`Column::DEFAULT`], or every field of the tasks in JSON.
+    columns: Option<Vec<Column>>,
+    color: bool,
+}
+
+impl Renderer {
+    pub fn new(format: Format) -> Self {
+        Self {
+            format,
+            ..Self::default()
+        }
+    }
+
+    /// Shows only `columns`, in that order. Ignored by [`Format::Plain`].
+    pub fn with_columns(mut self, columns: Vec<Column>) -> Self {
+        self.columns = Some(columns).filter(|columns| !columns.is_empty());
+        self
+    }
+
+    /// Colors tables with ANSI escape codes.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    fn columns(&self) -> &[Column] {
+        self.columns.as_deref().unwrap_or(&Column::DEFAULT)
+    }
+
+    /// The tasks as text, ending with a newline.
+    pub fn render(&self, tasks: &[Task]) -> Result<String, TaskError> {
+        match self.format {
+            Format::Table => Ok(self.table(tasks)),
+            Format::Plain => Ok(tasks.iter().map(|task| format!("{}\n", task)).collect()),
+            Format::Json => Ok(format!(
+                "{}\n",
+                serde_json::to_string_pretty(&self.json(tasks)?)?
+            )),
+        }
+    }
+
+    /// The tasks as a JSON array, of whole tasks unless columns were picked.
+    pub fn json(&self, tasks: &[Task]) -> Result<Value, TaskError> {
+        let Some(columns) = &self.columns else {
+            return Ok(serde_json::to_value(tasks)?);
+        };
+        Ok(tasks
+            .iter()
+            .map(|task| {
+                let fields: Map<String, Value> = columns
+                    .iter()
+                    .map(|column| (column.name().to_string(), column.json(task)))
+                    .collect();
+                Value::Object(fields)
+            })
+            .collect())
+    }
+
+    fn table(&self, tasks: &[Task]) -> String {
+        if tasks.is_empty() {
+            return "No tasks found.\n".to_string();
+        }
+        let columns = self.columns();
+        let rows: Vec<Vec<String>> = tasks
+            .iter()
+            .map(|task| columns.iter().map(|column| column.text(task)).collect())
+            .collect();
+        let widths: Vec<usize> = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                rows.iter()
+                    .map(|row| row[i].chars().count())
+                    .chain([column.header().len()])
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+
+        let headers: Vec<String> = columns
+            .iter()
+            .map(|column| column.header().to_string())
+            .collect();
+        let mut table = self.row(&headers, &widths, |_| Some(BOLD));
+        for (task, row) in tasks.iter().zip(&rows) {
+            table.push_str(&self.row(row, &widths, |i| columns[i].style(task)));
+        }
+        table
+    }
+
+    /// One line of the table, padded to `widths`. Styles are added after padding, so escape
+    /// codes don't count towards the widths.
+    fn row(
+        &self,
+        cells: &[String],
+        widths: &[usize],
+        style: impl Fn(usize) -> Option<&'static str>,
+    ) -> String {
+        let mut line = String::new();
+        for (i, (cell, width)) in cells.iter().zip(widths).enumerate() {
+            let last = i + 1 == cells.len();
+            let padding = if last {
+                0
+            } else {
+                width - cell.chars().count() + 2
+            };
+            match style(i).filter(|_| self.color) {
+                Some(style) => line.push_str(&format!("{}{}{}", style, cell, RESET)),
+                None => line.push_str(cell),
+            }
+            line.push_str(&" ".repeat(padding));
+        }
+        format!("{}\n", line.trim_end())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TaskRepository;
+
+    fn tasks() -> Vec<Task> {
+        let mut repo = TaskRepository::new();
+        let docs = repo.add_task("Write docs".to_string());
+        repo.set_priority(docs, Priority::Urgent).unwrap();
+        repo.add_tag(docs, "work").unwrap();
+        let fix = repo.add_task("Fix bug".to_string());
+        repo.mark_in_progress(fix).unwrap();
+        repo.get_tasks_sorted_by(crate::SortKey::Id)
+    }
+
+    #[test]
+    fn test_table_aligns_columns() {
+        let renderer = Renderer::new(Format::Table).with_columns(vec![
+            Column::Id,
+            Column::Description,
+            Column::Status,
+            Column::Tags,
+        ]);
+
+        let table = renderer.render(&tasks()).unwrap();
+
+        assert_eq!(
+            table,
+            "ID  Description  Status       Tags\n\
+             1   Write docs   To Do        work\n\
+             2   Fix bug      In Progress\n"
+        );
+    }
+
+    #[test]
+    fn test_table_colors_by_status_and_priority() {
+        let renderer = Renderer::new(Format::Table)
+            .with_columns(vec![Column::Status, Column::Priority])
+            .with_color(true);
+
+        let table = renderer.render(&tasks()).unwrap();
+
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(
+            lines[0],
+            format!("{BOLD}Status{RESET}       {BOLD}Priority{RESET}")
+        );
+        assert_eq!(lines[1], format!("To Do        {BOLD_RED}Urgent{RESET}"));
+        assert_eq!(lines[2], format!("{YELLOW}In Progress{RESET}  Medium"));
+    }
+
+    #[test]
+    fn test_empty_table() {
+        assert_eq!(
+            Renderer::default().render(&[]).unwrap(),
+            "No tasks found.\n"
+        );
+    }
+
+    #[test]
+    fn test_plain_uses_task_display() {
+        let tasks = tasks();
+
+        let plain = Renderer::new(Format::Plain).render(&tasks).unwrap();
+
+        assert_eq!(plain, format!("{}\n{}\n", tasks[0], tasks[1]));
+    }
+
+    #[test]
+    fn test_json_has_whole_tasks_or_picked_columns() {
+        let tasks = tasks();
+
+        let whole = Renderer::new(Format::Json).json(&tasks).unwrap();
+        let picked = Renderer::new(Format::Json)
+            .with_columns(vec![Column::Id, Column::Tags, Column::Due])
+            .json(&tasks)
+            .unwrap();
+
+        assert_eq!(
+            whole[0]["created_at"],
+            serde_json::to_value(tasks[0].created_at).unwrap()
+        );
+        assert_eq!(
+            picked,
+            serde_json::json!([
+                {"id": 1, "tags": ["work"], "due": null},
+                {"id": 2, "tags": [], "due": null},
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_columns() {
+        assert_eq!("Due".parse::<Column>().unwrap(), Column::Due);
+        assert!(matches!(
+            "colour".parse::<Column>(),
+            Err(TaskError::InvalidColumn(_))
+        ));
+    }
+
+    #[test]
+    fn test_use_color_respects_no_color() {
+        assert!(use_color(true, None));
+        assert!(use_color(true, Some("")));
+        assert!(!use_color(true, Some("1")));
+        assert!(!use_color(false, None));
+    }
+}
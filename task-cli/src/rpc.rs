@@ -0,0 +1,453 @@
+//! A small JSON-RPC 2.0 protocol over newline-delimited JSON, for editors and scripts that drive
+//! the tasks from one long-running process instead of a command per change.
+//!
+//! Every request is one line, and so is every response. The methods are:
+//!
+//! - `list`, with optional `status`, `tag`, `project` and `archived` params, returns the matching
+//!   tasks.
+//! - `add`, with a `description` and optional `due`, `priority`, `tags`, `project`, `parent` and
+//!   `repeat`, returns the new task.
+//! - `update`, with an `id` and optional `description`, `priority`, `status` and `due`, returns
+//!   the updated task.
+//! - `delete`, with an `id`, deletes the task and its subtasks.
+//! - `subscribe` makes the server send a `changed` notification, with the changed tasks before
+//!   and after, whenever a request changes them.
+
+use crate::{Priority, Recurrence, SortKey, Status, Task, TaskChange, TaskError, TaskStore};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::io::{BufRead, Write};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Any other [`TaskError`].
+const TASK_ERROR: i64 = -32000;
+const NOT_FOUND: i64 = -32001;
+/// The tasks were changed by someone else in the meantime, the request can be retried.
+const CONFLICT: i64 = -32002;
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    /// Absent for notifications, which get no response.
+    #[serde(default)]
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Serialize)]
+struct Response {
+    jsonrpc: &'static str,
+    id: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RpcError>,
+}
+
+impl Response {
+    fn new(id: Value, outcome: Result<Value, RpcError>) -> Self {
+        let (result, error) = match outcome {
+            Ok(result) => (Some(result), None),
+            Err(error) => (None, Some(error)),
+        };
+        Self {
+            jsonrpc: "2.0",
+            id,
+            result,
+            error,
+        }
+    }
+}
+
+/// An error response, with a JSON-RPC error code.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<TaskError> for RpcError {
+    fn from(error: TaskError) -> Self {
+        let code = match error {
+            TaskError::NotFound(_) => NOT_FOUND,
+            TaskError::Conflict => CONFLICT,
+            TaskError::EmptyTag
+            | TaskError::InvalidDueDate(_)
+            | TaskError::InvalidRecurrence(_)
+            | TaskError::InvalidIdRange(_) => INVALID_PARAMS,
+            _ => TASK_ERROR,
+        };
+        Self::new(code, error.to_string())
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ListParams {
+    status: Option<Status>,
+    tag: Option<String>,
+    project: Option<String>,
+    #[serde(default)]
+    archived: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AddParams {
+    description: String,
+    due: Option<String>,
+    priority: Option<Priority>,
+    #[serde(default)]
+    tags: Vec<String>,
+    project: Option<String>,
+    parent: Option<u32>,
+    repeat: Option<Recurrence>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct UpdateParams {
+    id: u32,
+    description: Option<String>,
+    priority: Option<Priority>,
+    status: Option<Status>,
+    due: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DeleteParams {
+    id: u32,
+}
+
+/// Answers JSON-RPC requests on the tasks in a store, loading them for every request so that
+/// changes made by other commands in the meantime aren't lost.
+pub struct Server<W: Write> {
+    store: Box<dyn TaskStore>,
+    output: W,
+    subscribed: bool,
+}
+
+impl<W: Write> Server<W> {
+    pub fn new(store: Box<dyn TaskStore>, output: W) -> Self {
+        Self {
+            store,
+            output,
+            subscribed: false,
+        }
+    }
+
+    /// Answers the requests read from `input`, one per line, until it's closed.
+    pub fn serve(&mut self, input: impl BufRead) -> Result<(), TaskError> {
+        for line in input.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut changes = Vec::new();
+            if let Some(response) = self.handle(&line, &mut changes) {
+                self.send(&response)?;
+            }
+            if self.subscribed && !changes.is_empty() {
+                self.send(&json!({
+                    "jsonrpc": "2.0",
+                    "method": "changed",
+                    "params": { "changes": changes },
+                }))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn send(&mut self, message: &impl Serialize) -> Result<(), TaskError> {
+        serde_json::to_writer(&mut self.output, message)?;
+        self.output.write_all(b"\n")?;
+        // Clients wait for each response before sending the next request
+        self.output.flush()?;
+        Ok(())
+    }
+
+    /// The response to one line, `None` for notifications. The changes the request made are
+    /// added to `changes`.
+    fn handle(&mut self, line: &str, changes: &mut Vec<TaskChange>) -> Option<Response> {
+        let value: Value = match serde_json::from_str(line) {
+            Ok(value) => value,
+            Err(error) => {
+                let error = RpcError::new(PARSE_ERROR, format!("Parse error: {}", error));
+                return Some(Response::new(Value::Null, Err(error)));
+            }
+        };
+        let request = match serde_json::from_value::<Request>(value) {
+            Ok(request) if request.jsonrpc == "2.0" => request,
+            Ok(_) | Err(_) => {
+                let error = RpcError::new(INVALID_REQUEST, "Invalid request");
+                return Some(Response::new(Value::Null, Err(error)));
+            }
+        };
+        let outcome = self.call(&request.method, request.params, changes);
+        request.id.map(|id| Response::new(id, outcome))
+    }
+
+    fn call(
+        &mut self,
+        method: &str,
+        params: Value,
+        changes: &mut Vec<TaskChange>,
+    ) -> Result<Value, RpcError> {
+        match method {
+            "list" => self.list(parse_params(params)?),
+            "add" => self.add(parse_params(params)?, changes),
+            "update" => self.update(parse_params(params)?, changes),
+            "delete" => self.delete(parse_params(params)?, changes),
+            "subscribe" => {
+                self.subscribed = true;
+                Ok(Value::Bool(true))
+            }
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Method '{}' not found", method),
+            )),
+        }
+    }
+
+    fn list(&mut self, params: ListParams) -> Result<Value, RpcError> {
+        let tasks = self.store.load()?;
+        let mut listed = match (&params.tag, params.archived) {
+            (_, true) => tasks.get_archived_tasks(),
+            (Some(tag), false) => tasks.get_tasks_with_tag(tag),
+            (None, false) => tasks.get_tasks_sorted_by(SortKey::Id),
+        };
+        if let (true, Some(tag)) = (params.archived, &params.tag) {
+            let tag = crate::normalize_tag(tag)?;
+            listed.retain(|task| task.tags.contains(&tag));
+        }
+        if let Some(status) = &params.status {
+            listed.retain(|task| task.status() == status);
+        }
+        if let Some(project) = &params.project {
+            let project = crate::normalize_project(project)?;
+            listed.retain(|task| task.project() == project);
+        }
+        SortKey::Id.sort(&mut listed);
+        to_value(&listed)
+    }
+
+    fn add(&mut self, params: AddParams, changes: &mut Vec<TaskChange>) -> Result<Value, RpcError> {
+        let due = params
+            .due
+            .as_deref()
+            .map(crate::parse_due_date)
+            .transpose()?;
+        let mut tasks = self.store.load()?;
+        let id = match params.parent {
+            Some(parent) => tasks.add_subtask(parent, params.description)?,
+            None => tasks.add_task(params.description),
+        };
+        if let Some(priority) = params.priority {
+            tasks.set_priority(id, priority)?;
+        }
+        if due.is_some() {
+            tasks.set_due_date(id, due)?;
+        }
+        if params.repeat.is_some() {
+            tasks.set_recurrence(id, params.repeat)?;
+        }
+        for tag in &params.tags {
+            tasks.add_tag(id, tag)?;
+        }
+        if let Some(project) = &params.project {
+            tasks.set_project(id, project)?;
+        }
+        changes.extend(self.store.commit(&mut tasks, format!("add task {}", id))?);
+        task_value(tasks.get_task(id))
+    }
+
+    fn update(
+        &mut self,
+        params: UpdateParams,
+        changes: &mut Vec<TaskChange>,
+    ) -> Result<Value, RpcError> {
+        let id = params.id;
+        let due = params
+            .due
+            .as_deref()
+            .map(crate::parse_due_date)
+            .transpose()?;
+        let mut tasks = self.store.load()?;
+        if tasks.get_task(id).is_none() {
+            return Err(TaskError::NotFound(id).into());
+        }
+        if let Some(description) = params.description {
+            tasks.update_task(id, description)?;
+        }
+        if let Some(priority) = params.priority {
+            tasks.set_priority(id, priority)?;
+        }
+        if due.is_some() {
+            tasks.set_due_date(id, due)?;
+        }
+        if let Some(status) = params.status {
+            tasks.set_status(id, status)?;
+        }
+        changes.extend(
+            self.store
+                .commit(&mut tasks, format!("update task {}", id))?,
+        );
+        task_value(tasks.get_task(id))
+    }
+
+    fn delete(
+        &mut self,
+        params: DeleteParams,
+        changes: &mut Vec<TaskChange>,
+    ) -> Result<Value, RpcError> {
+        let mut tasks = self.store.load()?;
+        tasks.delete_task(params.id)?;
+        changes.extend(
+            self.store
+                .commit(&mut tasks, format!("delete task {}", params.id))?,
+        );
+        Ok(Value::Null)
+    }
+}
+
+/// Missing params are the same as empty ones, so methods whose params are all optional can be
+/// called without any.
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params = match params {
+        Value::Null => Value::Object(Default::default()),
+        params => params,
+    };
+    serde_json::from_value(params)
+        .map_err(|error| RpcError::new(INVALID_PARAMS, format!("Invalid params: {}", error)))
+}
+
+fn to_value(value: &impl Serialize) -> Result<Value, RpcError> {
+    serde_json::to_value(value).map_err(|error| TaskError::from(error).into())
+}
+
+fn task_value(task: Option<&Task>) -> Result<Value, RpcError> {
+    to_value(&task.expect("task was just changed"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryStore;
+
+    /// Sends `requests` to a server on an empty store, returning every line it wrote back.
+    fn exchange(requests: &[Value]) -> Vec<Value> {
+        let input: String = requests
+            .iter()
+            .map(|request| format!("{}\n", request))
+            .collect();
+        let mut output = Vec::new();
+        Server::new(Box::new(MemoryStore::default()), &mut output)
+            .serve(input.as_bytes())
+            .unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_add_then_list() {
+        let responses = exchange(&[
+            json!({"jsonrpc": "2.0", "id": 1, "method": "add",
+                   "params": {"description": "Task", "tags": ["Work"], "priority": "High"}}),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "list", "params": {"tag": "work"}}),
+        ]);
+
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0]["result"]["id"], 1);
+        assert_eq!(responses[0]["result"]["priority"], "High");
+        assert_eq!(responses[1]["id"], 2);
+        assert_eq!(responses[1]["result"][0]["description"], "Task");
+    }
+
+    #[test]
+    fn test_errors_have_json_rpc_codes() {
+        let responses = exchange(&[
+            json!({"jsonrpc": "2.0", "id": 1, "method": "delete", "params": {"id": 9}}),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "explode"}),
+            json!({"jsonrpc": "2.0", "id": 3, "method": "add", "params": {"title": "Task"}}),
+            json!({"jsonrpc": "2.0", "id": 4, "method": "add",
+                   "params": {"description": "Task", "due": "someday"}}),
+            json!({"jsonrpc": "1.0", "id": 5, "method": "list"}),
+        ]);
+
+        let codes: Vec<&Value> = responses
+            .iter()
+            .map(|response| &response["error"]["code"])
+            .collect();
+        assert_eq!(
+            codes,
+            [
+                NOT_FOUND,
+                METHOD_NOT_FOUND,
+                INVALID_PARAMS,
+                INVALID_PARAMS,
+                INVALID_REQUEST
+            ]
+        );
+        assert_eq!(responses[0]["error"]["message"], "Task with ID 9 not found");
+    }
+
+    #[test]
+    fn test_parse_errors_and_notifications() {
+        let mut output = Vec::new();
+        let input = "{not json\n{\"jsonrpc\": \"2.0\", \"method\": \"add\", \"params\": {\"description\": \"Quiet\"}}\n";
+
+        Server::new(Box::new(MemoryStore::default()), &mut output)
+            .serve(input.as_bytes())
+            .unwrap();
+
+        // Only the parse error is answered
+        let output = String::from_utf8(output).unwrap();
+        let responses: Vec<Value> = output
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0]["id"], Value::Null);
+        assert_eq!(responses[0]["error"]["code"], PARSE_ERROR);
+    }
+
+    #[test]
+    fn test_subscribers_are_notified_of_changes() {
+        let responses = exchange(&[
+            json!({"jsonrpc": "2.0", "id": 1, "method": "add", "params": {"description": "Unseen"}}),
+            json!({"jsonrpc": "2.0", "id": 2, "method": "subscribe"}),
+            json!({"jsonrpc": "2.0", "id": 3, "method": "update",
+                   "params": {"id": 1, "status": "InProgress"}}),
+            json!({"jsonrpc": "2.0", "id": 4, "method": "list"}),
+        ]);
+
+        assert_eq!(responses.len(), 5);
+        assert_eq!(responses[2]["result"]["status"], "InProgress");
+        let notification = &responses[3];
+        assert_eq!(notification["method"], "changed");
+        assert!(notification.get("id").is_none());
+        let change = &notification["params"]["changes"][0];
+        assert_eq!(change["before"]["status"], "Todo");
+        assert_eq!(change["after"]["status"], "InProgress");
+        assert_eq!(responses[4]["id"], 4);
+    }
+}
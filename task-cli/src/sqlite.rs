@@ -0,0 +1,186 @@
+use crate::store::journal_next_to;
+use crate::{Journal, Task, TaskError, TaskRepository, TaskStore};
+use rusqlite::types::FromSql;
+use rusqlite::{Connection, OptionalExtension, ToSql, TransactionBehavior, params};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Stores tasks in a SQLite database, one row per task, with archived tasks in a table of their
+/// own.
+///
+/// Saving only writes the tasks that changed, in a transaction, and fails with
+/// [`TaskError::Conflict`] if another command saved since these tasks were loaded.
+pub struct SqliteStore {
+    connection: Connection,
+    /// `None` for in-memory databases.
+    path: Option<PathBuf>,
+    /// Version of the tasks when they were last loaded, bumped on every save.
+    loaded_version: Option<i64>,
+}
+
+impl SqliteStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, TaskError> {
+        let path = path.as_ref();
+        Self::new(Connection::open(path)?, Some(path.to_path_buf()))
+    }
+
+    pub fn open_in_memory() -> Result<Self, TaskError> {
+        Self::new(Connection::open_in_memory()?, None)
+    }
+
+    fn new(connection: Connection, path: Option<PathBuf>) -> Result<Self, TaskError> {
+        connection.busy_timeout(Duration::from_secs(5))?;
+        // Tasks are stored as their JSON so that new task fields don't need migrations
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS tasks (id INTEGER PRIMARY KEY, data TEXT NOT NULL);
+             CREATE TABLE IF NOT EXISTS archived (id INTEGER PRIMARY KEY, data TEXT NOT NULL);
+             CREATE TABLE IF NOT EXISTS meta (key TEXT PRIMARY KEY, value INTEGER NOT NULL);",
+        )?;
+        Ok(Self {
+            connection,
+            path,
+            loaded_version: None,
+        })
+    }
+}
+
+fn meta<T: FromSql>(connection: &Connection, key: &str) -> Result<Option<T>, TaskError> {
+    Ok(connection
+        .query_row("SELECT value FROM meta WHERE key = ?1", [key], |row| {
+            row.get(0)
+        })
+        .optional()?)
+}
+
+fn set_meta(connection: &Connection, key: &str, value: impl ToSql) -> Result<(), TaskError> {
+    connection.execute(
+        "INSERT INTO meta (key, value) VALUES (?1, ?2)
+         ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+        params![key, value],
+    )?;
+    Ok(())
+}
+
+/// Every task in `table`, `tasks` or `archived`.
+fn load_table(connection: &Connection, table: &str) -> Result<Vec<Task>, TaskError> {
+    connection
+        .prepare(&format!("SELECT data FROM {}", table))?
+        .query_map([], |row| row.get::<_, String>(0))?
+        .map(|data| Ok(serde_json::from_str::<Task>(&data?)?))
+        .collect()
+}
+
+/// Makes `table` hold `tasks`, only writing the rows that changed.
+fn save_table(
+    connection: &Connection,
+    table: &str,
+    tasks: &HashMap<u32, Task>,
+) -> Result<(), TaskError> {
+    let stored_ids = connection
+        .prepare(&format!("SELECT id FROM {}", table))?
+        .query_map([], |row| row.get::<_, u32>(0))?
+        .collect::<Result<HashSet<_>, _>>()?;
+    for id in stored_ids.iter().filter(|id| !tasks.contains_key(id)) {
+        connection.execute(&format!("DELETE FROM {} WHERE id = ?1", table), [id])?;
+    }
+    let mut upsert = connection.prepare(&format!(
+        "INSERT INTO {} (id, data) VALUES (?1, ?2)
+         ON CONFLICT (id) DO UPDATE SET data = excluded.data
+         WHERE data != excluded.data",
+        table
+    ))?;
+    for task in tasks.values() {
+        upsert.execute(params![task.id, serde_json::to_string(task)?])?;
+    }
+    Ok(())
+}
+
+impl TaskStore for SqliteStore {
+    fn load(&mut self) -> Result<TaskRepository, TaskError> {
+        let transaction = self.connection.transaction()?;
+        let version = meta(&transaction, "version")?.unwrap_or(0);
+        let next_id = meta(&transaction, "next_id")?.unwrap_or(1);
+        let tasks = load_table(&transaction, "tasks")?;
+        let archived = load_table(&transaction, "archived")?;
+        transaction.commit()?;
+
+        self.loaded_version = Some(version);
+        Ok(TaskRepository::from_tasks(tasks, archived, next_id))
+    }
+
+    fn save(&mut self, tasks: &TaskRepository) -> Result<(), TaskError> {
+        // Takes the write lock straight away, so no one can save between the check and the
+        // writes
+        let transaction = self
+            .connection
+            .transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let version: i64 = meta(&transaction, "version")?.unwrap_or(0);
+        if self.loaded_version.is_some_and(|loaded| loaded != version) {
+            return Err(TaskError::Conflict);
+        }
+
+        save_table(&transaction, "tasks", &tasks.tasks)?;
+        save_table(&transaction, "archived", &tasks.archived)?;
+        set_meta(&transaction, "next_id", tasks.next_id)?;
+        set_meta(&transaction, "version", version + 1)?;
+        transaction.commit()?;
+
+        self.loaded_version = Some(version + 1);
+        Ok(())
+    }
+
+    fn journal(&self) -> Option<Journal> {
+        self.path.as_deref().map(journal_next_to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::tests::check_round_trip;
+
+    #[test]
+    fn test_sqlite_store_round_trip() {
+        check_round_trip(&mut SqliteStore::open_in_memory().unwrap());
+    }
+
+    #[test]
+    fn test_sqlite_store_persists_to_file() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.db");
+        let mut store = SqliteStore::open(&path).unwrap();
+        let mut tasks = store.load().unwrap();
+        let id = tasks.add_task("Task".to_string());
+        store.save(&tasks).unwrap();
+
+        // Act
+        let loaded = SqliteStore::open(&path).unwrap().load().unwrap();
+
+        // Assert
+        assert_eq!(loaded.get_task(id), tasks.get_task(id));
+    }
+
+    #[test]
+    fn test_sqlite_store_rejects_stale_saves() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.db");
+        let mut first = SqliteStore::open(&path).unwrap();
+        let mut second = SqliteStore::open(&path).unwrap();
+        let mut first_tasks = first.load().unwrap();
+        let mut second_tasks = second.load().unwrap();
+
+        // Act
+        first_tasks.add_task("First".to_string());
+        first.save(&first_tasks).unwrap();
+        second_tasks.add_task("Second".to_string());
+        let result = second.save(&second_tasks);
+
+        // Assert
+        assert!(matches!(result, Err(TaskError::Conflict)));
+        let loaded = second.load().unwrap();
+        assert_eq!(loaded.get_task(1).unwrap().description, "First");
+    }
+}
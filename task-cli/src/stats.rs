@@ -0,0 +1,292 @@
+use crate::{Status, Task, TaskRepository, format_duration, start_of_week};
+use chrono::{DateTime, Duration, NaiveDate, NaiveTime, Utc};
+use std::fmt::{Display, Formatter};
+
+/// Widest bar of the burndown chart, in characters.
+const CHART_WIDTH: usize = 40;
+
+/// Tasks created and completed in a week.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct WeekStats {
+    /// Midnight UTC on the Monday the week starts on.
+    pub start: DateTime<Utc>,
+    pub created: usize,
+    pub completed: usize,
+}
+
+/// Aggregates over the tasks, see [`TaskRepository::stats`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stats {
+    pub todo: usize,
+    pub in_progress: usize,
+    pub done: usize,
+    pub archived: usize,
+    /// Average time from adding a task to finishing it, `None` without finished tasks.
+    pub average_completion: Option<Duration>,
+    /// The last weeks, oldest first, the current one last.
+    pub weeks: Vec<WeekStats>,
+    /// Tasks open at the end of each of the last days, oldest first, today last.
+    pub burndown: Vec<(NaiveDate, usize)>,
+}
+
+/// When a done task was finished. Tasks finished before that was recorded count as finished
+/// when they were last changed.
+fn completed_at(task: &Task) -> Option<DateTime<Utc>> {
+    (task.status == Status::Done).then(|| task.completed_at.unwrap_or(task.updated_at))
+}
+
+fn is_open_at(task: &Task, at: DateTime<Utc>) -> bool {
+    task.created_at < at && completed_at(task).is_none_or(|completed| completed >= at)
+}
+
+impl TaskRepository {
+    /// Counts the tasks by status, and works out how fast tasks get done over the last `weeks`
+    /// and `days` before `now`.
+    ///
+    /// Archived tasks are counted apart from the others, but they count towards everything
+    /// else, so that archiving doesn't rewrite history.
+    pub fn stats(&self, now: DateTime<Utc>, weeks: u32, days: u32) -> Stats {
+        let count = |status: Status| {
+            self.tasks
+                .values()
+                .filter(|task| task.status == status)
+                .count()
+        };
+        let all: Vec<&Task> = self.tasks.values().chain(self.archived.values()).collect();
+
+        let completion_times: Vec<Duration> = all
+            .iter()
+            .filter_map(|task| Some(completed_at(task)? - task.created_at))
+            .collect();
+        let average_completion = (!completion_times.is_empty()).then(|| {
+            let total = completion_times
+                .iter()
+                .fold(Duration::zero(), |total, time| total + *time);
+            total / completion_times.len() as i32
+        });
+
+        let this_week = start_of_week(now);
+        let weeks = (0..i64::from(weeks))
+            .rev()
+            .map(|ago| {
+                let start = this_week - Duration::weeks(ago);
+                let end = start + Duration::weeks(1);
+                let within = |time: DateTime<Utc>| start <= time && time < end;
+                WeekStats {
+                    start,
+                    created: all.iter().filter(|task| within(task.created_at)).count(),
+                    completed: all
+                        .iter()
+                        .filter(|task| completed_at(task).is_some_and(within))
+                        .count(),
+                }
+            })
+            .collect();
+
+        let today = now.date_naive();
+        let burndown = (0..i64::from(days))
+            .rev()
+            .map(|ago| {
+                let day = today - Duration::days(ago);
+                let end_of_day = (day + Duration::days(1)).and_time(NaiveTime::MIN).and_utc();
+                let at = end_of_day.min(now);
+                let open = all.iter().filter(|task| is_open_at(task, at)).count();
+                (day, open)
+            })
+            .collect();
+
+        Stats {
+            todo: count(Status::Todo),
+            in_progress: count(Status::InProgress),
+            done: count(Status::Done),
+            archived: self.archived.len(),
+            average_completion,
+            weeks,
+            burndown,
+        }
+    }
+}
+
+/// Formats a completion time in days and hours once it's over a day, e.g. `3d 04h`.
+fn format_days(duration: Duration) -> String {
+    if duration < Duration::days(1) {
+        return format_duration(duration);
+    }
+    format!("{}d {:02}h", duration.num_days(), duration.num_hours() % 24)
+}
+
+impl Display for Stats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Tasks: {} to do, {} in progress, {} done, {} archived",
+            self.todo, self.in_progress, self.done, self.archived
+        )?;
+        match self.average_completion {
+            Some(average) => writeln!(f, "Average completion time: {}", format_days(average))?,
+            None => writeln!(f, "Average completion time: no tasks done yet")?,
+        }
+
+        if !self.weeks.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "  Week of     Created  Completed")?;
+            for week in &self.weeks {
+                writeln!(
+                    f,
+                    "  {}  {:>7}  {:>9}",
+                    week.start.format("%Y-%m-%d"),
+                    week.created,
+                    week.completed
+                )?;
+            }
+        }
+
+        if !self.burndown.is_empty() {
+            writeln!(f)?;
+            writeln!(f, "Open tasks at the end of each day:")?;
+            let most = self.burndown.iter().map(|(_, open)| *open).max();
+            let most = most.unwrap_or_default().max(1);
+            for (day, open) in &self.burndown {
+                // Scaled down to fit, without hiding days with only a few open tasks
+                let width = (open * CHART_WIDTH).div_ceil(most.max(CHART_WIDTH));
+                let bar = "#".repeat(width);
+                let gap = if bar.is_empty() { "" } else { " " };
+                writeln!(f, "  {}  {}{}{}", day.format("%m-%d"), bar, gap, open)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(input: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(input).unwrap().into()
+    }
+
+    /// Adds a task created at `created`, and done at `completed` if given.
+    fn add(repo: &mut TaskRepository, created: &str, completed: Option<&str>) -> u32 {
+        let id = repo.add_task(format!("Created {}", created));
+        let task = repo.tasks.get_mut(&id).unwrap();
+        task.created_at = utc(created);
+        task.updated_at = utc(created);
+        if let Some(completed) = completed {
+            task.status = Status::Done;
+            task.completed_at = Some(utc(completed));
+            task.updated_at = utc(completed);
+        }
+        id
+    }
+
+    /// Two weeks of tasks, up to Wednesday 2025-01-15.
+    fn repository() -> TaskRepository {
+        let mut repo = TaskRepository::new();
+        add(
+            &mut repo,
+            "2025-01-06T09:00:00Z",
+            Some("2025-01-08T09:00:00Z"),
+        );
+        add(
+            &mut repo,
+            "2025-01-07T09:00:00Z",
+            Some("2025-01-14T09:00:00Z"),
+        );
+        add(&mut repo, "2025-01-13T09:00:00Z", None);
+        let started = add(&mut repo, "2025-01-14T09:00:00Z", None);
+        repo.tasks.get_mut(&started).unwrap().status = Status::InProgress;
+        let archived = add(
+            &mut repo,
+            "2025-01-01T09:00:00Z",
+            Some("2025-01-02T09:00:00Z"),
+        );
+        repo.archive_task(archived).unwrap();
+        repo
+    }
+
+    #[test]
+    fn test_stats_counts_and_averages() {
+        let stats = repository().stats(utc("2025-01-15T12:00:00Z"), 2, 3);
+
+        assert_eq!(
+            (stats.todo, stats.in_progress, stats.done, stats.archived),
+            (1, 1, 2, 1)
+        );
+        // 2, 7 and 1 days
+        assert_eq!(stats.average_completion, Some(Duration::days(10) / 3));
+    }
+
+    #[test]
+    fn test_stats_per_week() {
+        let stats = repository().stats(utc("2025-01-15T12:00:00Z"), 3, 0);
+
+        assert_eq!(
+            stats.weeks,
+            [
+                WeekStats {
+                    start: utc("2024-12-30T00:00:00Z"),
+                    created: 1,
+                    completed: 1,
+                },
+                WeekStats {
+                    start: utc("2025-01-06T00:00:00Z"),
+                    created: 2,
+                    completed: 1,
+                },
+                WeekStats {
+                    start: utc("2025-01-13T00:00:00Z"),
+                    created: 2,
+                    completed: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_burndown_counts_open_tasks_each_day() {
+        let stats = repository().stats(utc("2025-01-15T12:00:00Z"), 0, 4);
+
+        let day = |input: &str| input.parse::<NaiveDate>().unwrap();
+        assert_eq!(
+            stats.burndown,
+            [
+                (day("2025-01-12"), 1),
+                (day("2025-01-13"), 2),
+                (day("2025-01-14"), 2),
+                (day("2025-01-15"), 2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reopening_forgets_completion() {
+        let mut repo = TaskRepository::new();
+        let id = repo.add_task("Task".to_string());
+        repo.mark_done(id).unwrap();
+        assert!(repo.get_task(id).unwrap().completed_at.is_some());
+
+        repo.mark_in_progress(id).unwrap();
+
+        assert_eq!(repo.get_task(id).unwrap().completed_at, None);
+        assert_eq!(repo.stats(Utc::now(), 1, 1).average_completion, None);
+    }
+
+    #[test]
+    fn test_display() {
+        let stats = repository().stats(utc("2025-01-15T12:00:00Z"), 2, 2);
+
+        assert_eq!(
+            stats.to_string(),
+            "Tasks: 1 to do, 1 in progress, 2 done, 1 archived\n\
+             Average completion time: 3d 08h\n\
+             \n  Week of     Created  Completed\n\
+             \x20 2025-01-06        2          1\n\
+             \x20 2025-01-13        2          1\n\
+             \n\
+             Open tasks at the end of each day:\n\
+             \x20 01-14  ## 2\n\
+             \x20 01-15  ## 2\n"
+        );
+    }
+}
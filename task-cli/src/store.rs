@@ -0,0 +1,379 @@
+use crate::{Journal, Operation, TaskChange, TaskError, TaskRepository};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Where the CLI loads tasks from and saves them to.
+///
+/// Commands load the tasks, change them in memory and save them back. Stores that can be shared
+/// by concurrent commands refuse to save over changes made since the tasks were loaded, with
+/// [`TaskError::Conflict`].
+pub trait TaskStore {
+    fn load(&mut self) -> Result<TaskRepository, TaskError>;
+
+    fn save(&mut self, tasks: &TaskRepository) -> Result<(), TaskError>;
+
+    /// Where the operations on this store are logged, for undo and redo.
+    fn journal(&self) -> Option<Journal> {
+        None
+    }
+
+    /// Saves `tasks`, then has `log` append to the journal, if the store keeps one. Stores shared
+    /// by concurrent commands log before anyone else can save, so that the journal lists the
+    /// operations in the order they were saved.
+    fn save_and_log(
+        &mut self,
+        tasks: &TaskRepository,
+        log: &dyn Fn(&Journal) -> Result<(), TaskError>,
+    ) -> Result<(), TaskError> {
+        self.save(tasks)?;
+        match self.journal() {
+            Some(journal) => log(&journal),
+            None => Ok(()),
+        }
+    }
+
+    /// Saves the changes made to `tasks` since they were loaded, logging them to the journal as
+    /// `description` for undo. Returns the changes.
+    fn commit(
+        &mut self,
+        tasks: &mut TaskRepository,
+        description: String,
+    ) -> Result<Vec<TaskChange>, TaskError> {
+        let changes = tasks.take_changes();
+        self.save_and_log(tasks, &|journal| {
+            if changes.is_empty() {
+                return Ok(());
+            }
+            journal.record(Operation::new(description.clone(), changes.clone()))
+        })?;
+        Ok(changes)
+    }
+}
+
+/// The journal kept next to a store's file, `tasks.json.journal` for `tasks.json`.
+pub(crate) fn journal_next_to(path: &Path) -> Journal {
+    let mut journal_path = path.as_os_str().to_owned();
+    journal_path.push(".journal");
+    Journal::new(journal_path)
+}
+
+/// The archive kept next to a JSON file, `tasks.json.archive` for `tasks.json`.
+fn archive_next_to(path: &Path) -> PathBuf {
+    let mut archive_path = path.as_os_str().to_owned();
+    archive_path.push(".archive");
+    PathBuf::from(archive_path)
+}
+
+/// Opens the store described by `spec`: `json:PATH`, `sqlite:PATH`, or a bare path to a JSON
+/// file.
+pub fn open_store(spec: &str) -> Result<Box<dyn TaskStore>, TaskError> {
+    match spec.split_once(':') {
+        Some(("json", path)) if !path.is_empty() => {
+            Ok(Box::new(JsonFileStore::new(path).with_backup()))
+        }
+        #[cfg(feature = "sqlite")]
+        Some(("sqlite", path)) if !path.is_empty() => Ok(Box::new(crate::SqliteStore::open(path)?)),
+        None if !spec.is_empty() => Ok(Box::new(JsonFileStore::new(spec).with_backup())),
+        _ => Err(TaskError::InvalidStore(spec.to_string())),
+    }
+}
+
+/// Stores every task in one JSON file, the CLI's original format, and archived tasks in another
+/// next to it.
+///
+/// The file has a version, bumped on every save. Saving rewrites the whole file while holding a
+/// lock on `tasks.json.lock`, and fails with [`TaskError::Conflict`] if another command saved
+/// since these tasks were loaded.
+#[derive(Debug, Clone)]
+pub struct JsonFileStore {
+    path: PathBuf,
+    backup: bool,
+    /// Version of the file when the tasks were last loaded, bumped on every save.
+    loaded_version: Option<u64>,
+}
+
+/// The version saved along with the tasks. Files written before there were versions have none,
+/// and count as version 0.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Version {
+    #[serde(default)]
+    version: u64,
+}
+
+/// How the tasks are written to a JSON file: the repository, with the version first.
+#[derive(Serialize)]
+struct Versioned<'a> {
+    version: u64,
+    #[serde(flatten)]
+    tasks: &'a TaskRepository,
+}
+
+/// The version of the file with `contents`, where an empty file is version 0.
+fn version_of(contents: &str) -> Result<u64, TaskError> {
+    if contents.is_empty() {
+        return Ok(0);
+    }
+    Ok(serde_json::from_str::<Version>(contents)?.version)
+}
+
+impl JsonFileStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            backup: false,
+            loaded_version: None,
+        }
+    }
+
+    fn read(&self) -> Result<String, TaskError> {
+        if self.path.exists() {
+            Ok(fs::read_to_string(&self.path)?)
+        } else {
+            Ok(String::new())
+        }
+    }
+
+    /// Keeps the previous save in `tasks.json.bak` every time the tasks are saved.
+    pub fn with_backup(mut self) -> Self {
+        self.backup = true;
+        self
+    }
+
+    /// Saves `tasks` while the caller holds the lock on the file.
+    fn save_locked(&mut self, tasks: &TaskRepository) -> Result<(), TaskError> {
+        let version = version_of(&self.read()?)?;
+        if self.loaded_version.is_some_and(|loaded| loaded != version) {
+            return Err(TaskError::Conflict);
+        }
+
+        // The archive goes first, so that a failed save can at worst leave an archived task in
+        // both files, rather than in neither
+        let archive_path = archive_next_to(&self.path);
+        if !tasks.archived.is_empty() || archive_path.exists() {
+            crate::atomic::replace(&archive_path, self.backup, |writer| {
+                tasks.save_archive_as_json(writer)
+            })?;
+        }
+        let versioned = Versioned {
+            version: version + 1,
+            tasks,
+        };
+        crate::atomic::replace(&self.path, self.backup, |writer| {
+            crate::write_json(writer, &versioned)
+        })?;
+
+        self.loaded_version = Some(version + 1);
+        Ok(())
+    }
+}
+
+impl TaskStore for JsonFileStore {
+    fn load(&mut self) -> Result<TaskRepository, TaskError> {
+        let contents = self.read()?;
+        let version = version_of(&contents)?;
+        let mut tasks = if contents.is_empty() {
+            TaskRepository::new()
+        } else {
+            TaskRepository::new_from_json(&contents)?
+        };
+        let archive_path = archive_next_to(&self.path);
+        if archive_path.exists() {
+            tasks.load_archive_from_json(&fs::read_to_string(archive_path)?)?;
+        }
+        self.loaded_version = Some(version);
+        Ok(tasks)
+    }
+
+    fn save(&mut self, tasks: &TaskRepository) -> Result<(), TaskError> {
+        // Held until the end, so no one can save between the check and the writes
+        let _lock = crate::atomic::lock(&self.path)?;
+        self.save_locked(tasks)
+    }
+
+    fn save_and_log(
+        &mut self,
+        tasks: &TaskRepository,
+        log: &dyn Fn(&Journal) -> Result<(), TaskError>,
+    ) -> Result<(), TaskError> {
+        // Also held while logging, so that the next save is logged after this one
+        let _lock = crate::atomic::lock(&self.path)?;
+        self.save_locked(tasks)?;
+        log(&journal_next_to(&self.path))
+    }
+
+    fn journal(&self) -> Option<Journal> {
+        Some(journal_next_to(&self.path))
+    }
+}
+
+/// Keeps tasks in memory, for tests.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    tasks: TaskRepository,
+}
+
+impl TaskStore for MemoryStore {
+    fn load(&mut self) -> Result<TaskRepository, TaskError> {
+        Ok(self.tasks.clone())
+    }
+
+    fn save(&mut self, tasks: &TaskRepository) -> Result<(), TaskError> {
+        self.tasks = tasks.clone();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Adds a task through `store` the way a command does, checking it's there on the next load.
+    pub(crate) fn check_round_trip(store: &mut dyn TaskStore) {
+        let mut tasks = store.load().unwrap();
+        assert_eq!(tasks.get_tasks_sorted_by(crate::SortKey::Id).len(), 0);
+        let id = tasks.add_task("Task".to_string());
+        tasks.add_tag(id, "work").unwrap();
+        store.save(&tasks).unwrap();
+
+        let mut loaded = store.load().unwrap();
+        assert_eq!(loaded.get_task(id), tasks.get_task(id));
+        assert_eq!(loaded.get_tasks_with_tag("work").len(), 1);
+        assert_eq!(loaded.add_task("Next".to_string()), id + 1);
+
+        loaded.delete_task(id).unwrap();
+        loaded.archive_task(id + 1).unwrap();
+        store.save(&loaded).unwrap();
+        let mut archived = store.load().unwrap();
+        assert!(archived.get_task(id).is_none());
+        assert!(archived.get_task(id + 1).is_none());
+        assert_eq!(
+            archived.get_archived_task(id + 1),
+            loaded.get_archived_task(id + 1)
+        );
+
+        archived.unarchive_task(id + 1).unwrap();
+        store.save(&archived).unwrap();
+        let restored = store.load().unwrap();
+        assert!(restored.get_task(id + 1).is_some());
+        assert!(restored.get_archived_tasks().is_empty());
+    }
+
+    #[test]
+    fn test_memory_store_round_trip() {
+        check_round_trip(&mut MemoryStore::default());
+    }
+
+    #[test]
+    fn test_json_file_store_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.json");
+
+        check_round_trip(&mut JsonFileStore::new(&path).with_backup());
+
+        assert!(path.exists());
+        assert!(crate::atomic::backup_path(&path).exists());
+    }
+
+    #[test]
+    fn test_json_file_store_prefers_tasks_over_stale_archive() {
+        // Arrange: as if a save was interrupted after writing the archive but not the tasks
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.json");
+        let mut store = JsonFileStore::new(&path);
+        let mut tasks = TaskRepository::new();
+        let id = tasks.add_task("Task".to_string());
+        store.save(&tasks).unwrap();
+        tasks.archive_task(id).unwrap();
+        fs::write(archive_next_to(&path), {
+            let mut archive = Vec::new();
+            tasks.save_archive_as_json(&mut archive).unwrap();
+            archive
+        })
+        .unwrap();
+
+        // Act
+        let loaded = store.load().unwrap();
+
+        // Assert
+        assert!(loaded.get_task(id).is_some());
+        assert!(loaded.get_archived_tasks().is_empty());
+    }
+
+    #[test]
+    fn test_json_file_store_rejects_stale_saves() {
+        // Arrange
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.json");
+        let mut first = JsonFileStore::new(&path);
+        let mut second = JsonFileStore::new(&path);
+        let mut first_tasks = first.load().unwrap();
+        let mut second_tasks = second.load().unwrap();
+
+        // Act
+        first_tasks.add_task("First".to_string());
+        first.save(&first_tasks).unwrap();
+        second_tasks.add_task("Second".to_string());
+        let result = second.save(&second_tasks);
+
+        // Assert
+        assert!(matches!(result, Err(TaskError::Conflict)));
+        let mut loaded = second.load().unwrap();
+        assert_eq!(loaded.get_task(1).unwrap().description, "First");
+        loaded.add_task("Second".to_string());
+        assert!(second.save(&loaded).is_ok());
+    }
+
+    #[test]
+    fn test_json_file_store_versions_files_without_one() {
+        // Arrange: a file saved before there were versions
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.json");
+        let mut tasks = TaskRepository::new();
+        tasks.add_task("Old".to_string());
+        tasks.save_atomic(&path).unwrap();
+        let mut store = JsonFileStore::new(&path);
+
+        // Act
+        let mut loaded = store.load().unwrap();
+        loaded.add_task("New".to_string());
+        store.save(&loaded).unwrap();
+
+        // Assert
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with(r#"{"version":1,"#));
+        assert_eq!(
+            store
+                .load()
+                .unwrap()
+                .get_tasks_sorted_by(crate::SortKey::Id)
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_json_file_store_reports_malformed_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tasks.json");
+        fs::write(&path, "{not json").unwrap();
+
+        let result = JsonFileStore::new(&path).load();
+
+        assert!(matches!(result, Err(TaskError::Serialization(_))));
+    }
+
+    #[test]
+    fn test_open_store_parses_specs() {
+        assert!(open_store("json:tasks.json").is_ok());
+        assert!(open_store("tasks.json").is_ok());
+        assert!(matches!(
+            open_store("json:"),
+            Err(TaskError::InvalidStore(_))
+        ));
+        assert!(matches!(
+            open_store("postgres:tasks"),
+            Err(TaskError::InvalidStore(_))
+        ));
+    }
+}
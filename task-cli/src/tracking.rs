@@ -0,0 +1,160 @@
+use crate::Task;
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+/// A session of work on a task, still running while `end` is `None`.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TimeSpan {
+    pub start: DateTime<Utc>,
+    #[serde(default)]
+    pub end: Option<DateTime<Utc>>,
+}
+
+impl TimeSpan {
+    pub fn new(start: DateTime<Utc>, end: Option<DateTime<Utc>>) -> Self {
+        Self { start, end }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.end.is_none()
+    }
+
+    /// When the session ends, `now` if it's still running.
+    pub fn end_or(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        self.end.unwrap_or(now).max(self.start)
+    }
+
+    pub fn duration(&self, now: DateTime<Utc>) -> Duration {
+        self.end_or(now) - self.start
+    }
+
+    /// How much of the session falls between `from` and `to`.
+    pub fn duration_between(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Duration {
+        let start = self.start.max(from);
+        let end = self.end_or(now).min(to);
+        (end - start).max(Duration::zero())
+    }
+
+    /// Whether both sessions cover some of the same time. A running session covers everything
+    /// after its start.
+    pub fn overlaps(&self, other: &TimeSpan) -> bool {
+        let ends_after = |span: &TimeSpan, start| span.end.is_none_or(|end| end > start);
+        ends_after(self, other.start) && ends_after(other, self.start)
+    }
+}
+
+/// Midnight UTC on the Monday of the week `now` is in.
+pub fn start_of_week(now: DateTime<Utc>) -> DateTime<Utc> {
+    let monday = now.date_naive() - Duration::days(i64::from(now.weekday().num_days_from_monday()));
+    monday.and_time(NaiveTime::MIN).and_utc()
+}
+
+/// Formats a tracked duration as hours and minutes, e.g. `2h 05m`.
+pub fn format_duration(duration: Duration) -> String {
+    let minutes = duration.num_minutes();
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+/// Time tracked per task over a period, see
+/// [`TaskRepository::time_report`](crate::TaskRepository::time_report).
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeReport {
+    /// Start of the period, `None` for all the time ever tracked.
+    pub since: Option<DateTime<Utc>>,
+    /// The tasks with time tracked in the period, most tracked first.
+    pub rows: Vec<(Task, Duration)>,
+}
+
+impl TimeReport {
+    pub fn total(&self) -> Duration {
+        self.rows
+            .iter()
+            .fold(Duration::zero(), |total, (_, duration)| total + *duration)
+    }
+}
+
+impl Display for TimeReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.since {
+            Some(since) => writeln!(f, "Time tracked since {}:", since.format("%Y-%m-%d"))?,
+            None => writeln!(f, "Time tracked:")?,
+        }
+        if self.rows.is_empty() {
+            writeln!(f, "  No time tracked.")?;
+            return Ok(());
+        }
+        for (task, duration) in &self.rows {
+            let running = if task.is_tracking() { " (running)" } else { "" };
+            writeln!(
+                f,
+                "  {:>8}  {}: {}{}",
+                format_duration(*duration),
+                task.id,
+                task.description,
+                running
+            )?;
+        }
+        writeln!(f, "  {:>8}  Total", format_duration(self.total()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(input: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(input).unwrap().into()
+    }
+
+    fn span(start: &str, end: Option<&str>) -> TimeSpan {
+        TimeSpan::new(utc(start), end.map(utc))
+    }
+
+    #[test]
+    fn test_overlaps() {
+        let morning = span("2025-01-06T09:00:00Z", Some("2025-01-06T12:00:00Z"));
+        let lunch = span("2025-01-06T11:30:00Z", Some("2025-01-06T13:00:00Z"));
+        let afternoon = span("2025-01-06T12:00:00Z", Some("2025-01-06T17:00:00Z"));
+        let running = span("2025-01-06T16:00:00Z", None);
+
+        assert!(morning.overlaps(&lunch));
+        assert!(lunch.overlaps(&morning));
+        // Touching isn't overlapping
+        assert!(!morning.overlaps(&afternoon));
+        assert!(running.overlaps(&afternoon));
+        assert!(!running.overlaps(&morning));
+    }
+
+    #[test]
+    fn test_duration_between_clips_to_period() {
+        let now = utc("2025-01-07T10:00:00Z");
+        let over_midnight = span("2025-01-05T23:00:00Z", Some("2025-01-06T01:30:00Z"));
+        let running = span("2025-01-07T09:00:00Z", None);
+        let week = start_of_week(now);
+
+        assert_eq!(week, utc("2025-01-06T00:00:00Z"));
+        assert_eq!(
+            over_midnight.duration_between(week, now, now),
+            Duration::minutes(90)
+        );
+        assert_eq!(running.duration(now), Duration::hours(1));
+        assert_eq!(
+            span("2025-01-01T09:00:00Z", Some("2025-01-01T10:00:00Z"))
+                .duration_between(week, now, now),
+            Duration::zero()
+        );
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::minutes(125)), "2h 05m");
+        assert_eq!(format_duration(Duration::seconds(59)), "0h 00m");
+        assert_eq!(format_duration(Duration::hours(31)), "31h 00m");
+    }
+}
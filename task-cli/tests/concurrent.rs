@@ -0,0 +1,54 @@
+use serde_json::Value;
+use std::process::{Command, Stdio};
+
+#[test]
+fn concurrent_adds_keep_every_task() {
+    // Arrange
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("tasks.json");
+
+    // Act
+    let adds: Vec<_> = (1..=8)
+        .map(|i| {
+            Command::new(env!("CARGO_BIN_EXE_task-cli"))
+                .args(["add", &format!("Task {}", i), "--file"])
+                .arg(&file)
+                // Keeps the user's config out of the tests
+                .env("XDG_CONFIG_HOME", dir.path())
+                .env_remove("TASK_CLI_PROJECT")
+                .stdout(Stdio::null())
+                .spawn()
+                .unwrap()
+        })
+        .collect();
+    for mut add in adds {
+        assert!(add.wait().unwrap().success());
+    }
+
+    // Assert
+    let saved: Value = serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+    let mut descriptions: Vec<&str> = saved["tasks"]
+        .as_object()
+        .unwrap()
+        .values()
+        .map(|task| task["description"].as_str().unwrap())
+        .collect();
+    descriptions.sort_unstable();
+    let expected: Vec<String> = (1..=8).map(|i| format!("Task {}", i)).collect();
+    assert_eq!(descriptions, expected);
+    assert_eq!(saved["version"], 8);
+    // Each add takes the next ID as it saves, so the journal has them in the order of the saves
+    let journal = std::fs::read_to_string(dir.path().join("tasks.json.journal")).unwrap();
+    let logged: Vec<String> = journal
+        .lines()
+        .map(|line| {
+            let entry: Value = serde_json::from_str(line).unwrap();
+            entry["operation"]["description"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        })
+        .collect();
+    let expected: Vec<String> = (1..=8).map(|id| format!("add task {}", id)).collect();
+    assert_eq!(logged, expected);
+}
@@ -0,0 +1,134 @@
+use serde_json::{Value, json};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+/// `task-cli serve --stdio` on the tasks in `file`, talked to one line at a time.
+struct Client {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: u64,
+}
+
+impl Client {
+    fn start(dir: &Path, file: &Path) -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_task-cli"))
+            .args(["serve", "--stdio", "--file"])
+            .arg(file)
+            // Keeps the user's config out of the tests
+            .env("XDG_CONFIG_HOME", dir)
+            .env_remove("TASK_CLI_STORE")
+            .env_remove("TASK_CLI_FILE")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+        Self {
+            child,
+            stdin,
+            stdout,
+            next_id: 1,
+        }
+    }
+
+    fn send(&mut self, message: &Value) {
+        writeln!(self.stdin, "{}", message).unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut line = String::new();
+        self.stdout.read_line(&mut line).unwrap();
+        serde_json::from_str(&line).unwrap()
+    }
+
+    /// Sends a request and waits for its response.
+    fn call(&mut self, method: &str, params: Value) -> Value {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.send(&json!({"jsonrpc": "2.0", "id": id, "method": method, "params": params}));
+        let response = self.receive();
+        assert_eq!(response["id"], id);
+        response
+    }
+
+    fn stop(mut self) {
+        drop(self.stdin);
+        assert!(self.child.wait().unwrap().success());
+    }
+}
+
+#[test]
+fn serves_requests_and_saves_changes() {
+    // Arrange
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("tasks.json");
+    let mut client = Client::start(dir.path(), &file);
+
+    // Act
+    let added = client.call(
+        "add",
+        json!({"description": "Write docs", "tags": ["work"], "due": "2030-01-31"}),
+    );
+    client.call("add", json!({"description": "Ship it"}));
+    let updated = client.call("update", json!({"id": 2, "status": "Done"}));
+    let deleted = client.call("delete", json!({"id": 1}));
+    let listed = client.call("list", json!({}));
+    let missing = client.call("update", json!({"id": 1, "description": "Gone"}));
+    client.stop();
+
+    // Assert
+    assert_eq!(added["result"]["id"], 1);
+    assert_eq!(added["result"]["tags"], json!(["work"]));
+    assert_eq!(added["result"]["due_date"], "2030-01-31T23:59:59Z");
+    assert_eq!(updated["result"]["status"], "Done");
+    assert_eq!(deleted["result"], Value::Null);
+    assert_eq!(listed["result"].as_array().unwrap().len(), 1);
+    assert_eq!(listed["result"][0]["description"], "Ship it");
+    assert_eq!(missing["error"]["code"], -32001);
+
+    let saved: Value = serde_json::from_str(&std::fs::read_to_string(&file).unwrap()).unwrap();
+    assert_eq!(saved["tasks"]["2"]["status"], "Done");
+    assert!(saved["tasks"].get("1").is_none());
+}
+
+#[test]
+fn subscribers_get_changes_and_cli_can_undo_them() {
+    // Arrange
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("tasks.json");
+    let mut client = Client::start(dir.path(), &file);
+    assert_eq!(client.call("subscribe", Value::Null)["result"], true);
+
+    // Act
+    client.call("add", json!({"description": "Task"}));
+    let notification = client.receive();
+    client.stop();
+    let undo = Command::new(env!("CARGO_BIN_EXE_task-cli"))
+        .args(["undo", "--file"])
+        .arg(&file)
+        .env("XDG_CONFIG_HOME", dir.path())
+        .output()
+        .unwrap();
+
+    // Assert
+    assert_eq!(notification["method"], "changed");
+    let change = &notification["params"]["changes"][0];
+    assert_eq!(change["before"], Value::Null);
+    assert_eq!(change["after"]["description"], "Task");
+    assert_eq!(String::from_utf8_lossy(&undo.stdout), "Undid: add task 1\n");
+}
+
+#[test]
+fn requires_stdio_flag() {
+    let status = Command::new(env!("CARGO_BIN_EXE_task-cli"))
+        .arg("serve")
+        .stderr(Stdio::null())
+        .status()
+        .unwrap();
+
+    assert!(!status.success());
+}